@@ -0,0 +1,30 @@
+-- Link login accounts to the employee record they teach as, so student data
+-- can be scoped to a teacher's caseload
+ALTER TABLE users
+  ADD COLUMN IF NOT EXISTS employee_id INT;
+
+ALTER TABLE users
+ADD CONSTRAINT fk_users_employee
+  FOREIGN KEY (employee_id)
+  REFERENCES employees(id)
+  ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_users_employee_id ON users(employee_id);
+
+-- Backfill teacher accounts whose name matches exactly one employee
+UPDATE users u
+SET employee_id = e.id
+FROM employees e
+WHERE u.employee_id IS NULL
+  AND u.role = 'teacher'
+  AND LOWER(u.first_name) = LOWER(e.firstname)
+  AND LOWER(u.last_name) = LOWER(e.lastname)
+  AND (
+    SELECT COUNT(*) FROM employees e2
+    WHERE LOWER(e2.firstname) = LOWER(e.firstname)
+      AND LOWER(e2.lastname) = LOWER(e.lastname)
+  ) = 1;
+
+-- Caseload lookups go through the teacher on either the enrollment or its course
+CREATE INDEX IF NOT EXISTS idx_student_enrollments_teacher_status
+  ON student_enrollments(teacher_id, status);
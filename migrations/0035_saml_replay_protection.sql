@@ -0,0 +1,18 @@
+-- AuthnRequests we have sent, so a response's InResponseTo can be matched
+-- to a login that actually started here
+CREATE TABLE IF NOT EXISTS saml_pending_requests (
+  request_id VARCHAR(255) PRIMARY KEY,
+  institution_id VARCHAR(255) NOT NULL,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+-- Assertion IDs already consumed; kept until the assertion would have expired
+CREATE TABLE IF NOT EXISTS saml_consumed_assertions (
+  assertion_id VARCHAR(255) PRIMARY KEY,
+  institution_id VARCHAR(255) NOT NULL,
+  expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  consumed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_saml_pending_requests_created ON saml_pending_requests(created_at);
+CREATE INDEX IF NOT EXISTS idx_saml_consumed_assertions_expires ON saml_consumed_assertions(expires_at);
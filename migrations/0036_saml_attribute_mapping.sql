@@ -0,0 +1,4 @@
+-- Directory fields read through each institution's SAML attribute mapping
+ALTER TABLE saml_user_mappings
+  ADD COLUMN IF NOT EXISTS school VARCHAR(255),
+  ADD COLUMN IF NOT EXISTS employee_number VARCHAR(100);
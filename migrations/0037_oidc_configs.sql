@@ -0,0 +1,31 @@
+-- OpenID Connect clients, one per institution, alongside saml_configs
+CREATE TABLE oidc_configs (
+  id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+  institution_name VARCHAR(255) NOT NULL UNIQUE,
+  issuer TEXT NOT NULL,
+  client_id VARCHAR(255) NOT NULL,
+  client_secret TEXT NOT NULL DEFAULT '',
+  scopes VARCHAR(255) NOT NULL DEFAULT 'openid email profile',
+  active BOOLEAN NOT NULL DEFAULT true,
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  attribute_mapping JSONB NOT NULL DEFAULT '{}',
+  role_mapping JSONB NOT NULL DEFAULT '{}',
+  auto_provision BOOLEAN NOT NULL DEFAULT true,
+  trust_unverified_email BOOLEAN NOT NULL DEFAULT false,
+
+  UNIQUE (issuer, client_id)
+);
+
+-- Authorization requests awaiting their callback. The state value is the key;
+-- the nonce and PKCE verifier never leave the server.
+CREATE TABLE oidc_pending_logins (
+  state VARCHAR(128) PRIMARY KEY,
+  config_id UUID NOT NULL REFERENCES oidc_configs(id) ON DELETE CASCADE,
+  nonce VARCHAR(128) NOT NULL,
+  code_verifier VARCHAR(128) NOT NULL,
+  redirect_to TEXT NOT NULL DEFAULT '/dashboard',
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_oidc_pending_logins_created_at ON oidc_pending_logins(created_at);
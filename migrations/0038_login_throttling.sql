@@ -0,0 +1,27 @@
+-- Failed password logins, counted per username and per client IP
+CREATE TABLE IF NOT EXISTS login_throttles (
+    scope VARCHAR(16) NOT NULL CHECK (scope IN ('username', 'ip')),
+    subject VARCHAR(255) NOT NULL,
+    failure_count INTEGER NOT NULL DEFAULT 0,
+    last_failure_at TIMESTAMPTZ,
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (scope, subject)
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_throttles_locked_until ON login_throttles (locked_until)
+    WHERE locked_until IS NOT NULL;
+
+-- Audit trail of lockouts and manual unlocks
+CREATE TABLE IF NOT EXISTS login_lockout_events (
+    id SERIAL PRIMARY KEY,
+    scope VARCHAR(16) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    event VARCHAR(16) NOT NULL CHECK (event IN ('locked', 'unlocked')),
+    failure_count INTEGER,
+    locked_until TIMESTAMPTZ,
+    ip_address VARCHAR(64),
+    actor_user_id BIGINT REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_lockout_events_subject ON login_lockout_events (scope, subject, created_at);
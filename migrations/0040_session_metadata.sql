@@ -0,0 +1,10 @@
+-- Per-session metadata for the My Sessions view and idle expiry
+ALTER TABLE sessions
+    ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN IF NOT EXISTS idle_timeout_minutes INTEGER,
+    ADD COLUMN IF NOT EXISTS ip_address VARCHAR(64),
+    ADD COLUMN IF NOT EXISTS user_agent TEXT,
+    ADD COLUMN IF NOT EXISTS provider VARCHAR(16) NOT NULL DEFAULT 'password'
+        CHECK (provider IN ('password', 'saml', 'oidc'));
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions (user_id);
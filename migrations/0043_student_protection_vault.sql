@@ -0,0 +1,27 @@
+-- Encrypted student ID mapping written when student protection is enabled.
+-- Only one mapping exists at a time; it is removed again on restore.
+CREATE TABLE IF NOT EXISTS student_protection_vault (
+    id INT PRIMARY KEY DEFAULT 1 CHECK (id = 1),
+    nonce BYTEA NOT NULL,
+    ciphertext BYTEA NOT NULL,
+    key_check TEXT NOT NULL,
+    student_count INT NOT NULL,
+    created_by INT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Re-keying students cascades to their scores and enrollments, so the
+-- protection engine no longer drops and re-adds these constraints. The old
+-- scripts left them under several names, or missing entirely.
+ALTER TABLE scores DROP CONSTRAINT IF EXISTS fk_scores_student_id_fkey;
+ALTER TABLE scores DROP CONSTRAINT IF EXISTS scores_student_id_fkey;
+ALTER TABLE scores ADD CONSTRAINT scores_student_id_fkey
+    FOREIGN KEY (student_id) REFERENCES students(student_id)
+    ON DELETE CASCADE ON UPDATE CASCADE NOT VALID;
+
+ALTER TABLE student_enrollments DROP CONSTRAINT IF EXISTS fk_student_enrollments_student;
+ALTER TABLE student_enrollments DROP CONSTRAINT IF EXISTS fk_student_enrollments_student_id_fkey;
+ALTER TABLE student_enrollments DROP CONSTRAINT IF EXISTS student_enrollments_student_id_fkey;
+ALTER TABLE student_enrollments ADD CONSTRAINT fk_student_enrollments_student
+    FOREIGN KEY (student_id) REFERENCES students(student_id)
+    ON DELETE CASCADE ON UPDATE CASCADE NOT VALID;
@@ -0,0 +1,11 @@
+-- The mapping key is now derived from admin passphrases instead of being
+-- issued by the server, so the vault records how to derive it again.
+-- Rows sealed before this keep a NULL salt and open with the issued key.
+ALTER TABLE student_protection_vault
+    ADD COLUMN IF NOT EXISTS kdf_salt BYTEA,
+    ADD COLUMN IF NOT EXISTS kdf_memory_kib INT,
+    ADD COLUMN IF NOT EXISTS kdf_iterations INT,
+    ADD COLUMN IF NOT EXISTS kdf_parallelism INT,
+    ADD COLUMN IF NOT EXISTS key_shares SMALLINT NOT NULL DEFAULT 1;
+
+ALTER TABLE student_protection_vault DROP COLUMN IF EXISTS key_check;
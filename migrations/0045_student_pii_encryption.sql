@@ -0,0 +1,41 @@
+-- Data keys for student PII, each stored wrapped by a master key from the
+-- configured key provider. Exactly one key encrypts new writes; older keys
+-- stay until every row has been re-encrypted, then are deleted.
+CREATE TABLE IF NOT EXISTS student_data_keys (
+    id SERIAL PRIMARY KEY,
+    master_key_id TEXT NOT NULL,
+    wrapped_key BYTEA NOT NULL,
+    wrapped_index_key BYTEA NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    rewrapped_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_student_data_keys_active
+    ON student_data_keys (active) WHERE active;
+
+-- Ciphertext and blind indexes for the PII columns. Encrypted rows leave the
+-- plaintext columns NULL, so those can no longer be NOT NULL.
+ALTER TABLE students
+    ADD COLUMN IF NOT EXISTS pii_key_id INT REFERENCES student_data_keys(id),
+    ADD COLUMN IF NOT EXISTS firstname_enc BYTEA,
+    ADD COLUMN IF NOT EXISTS lastname_enc BYTEA,
+    ADD COLUMN IF NOT EXISTS pin_enc BYTEA,
+    ADD COLUMN IF NOT EXISTS date_of_birth_enc BYTEA,
+    ADD COLUMN IF NOT EXISTS notes_enc BYTEA,
+    ADD COLUMN IF NOT EXISTS firstname_bidx BYTEA,
+    ADD COLUMN IF NOT EXISTS lastname_bidx BYTEA,
+    ADD COLUMN IF NOT EXISTS date_of_birth_bidx BYTEA,
+    ADD COLUMN IF NOT EXISTS name_tokens BYTEA[];
+
+ALTER TABLE students
+    ALTER COLUMN firstname DROP NOT NULL,
+    ALTER COLUMN lastname DROP NOT NULL,
+    ALTER COLUMN pin DROP NOT NULL,
+    ALTER COLUMN date_of_birth DROP NOT NULL,
+    ALTER COLUMN notes DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_students_pii_key ON students (pii_key_id);
+CREATE INDEX IF NOT EXISTS idx_students_firstname_bidx ON students (firstname_bidx);
+CREATE INDEX IF NOT EXISTS idx_students_lastname_bidx ON students (lastname_bidx);
+CREATE INDEX IF NOT EXISTS idx_students_name_tokens ON students USING GIN (name_tokens);
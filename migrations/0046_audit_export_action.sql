@@ -0,0 +1,4 @@
+-- Disclosures of student records are logged as 'export' events
+ALTER TABLE audit_events DROP CONSTRAINT IF EXISTS audit_events_action_check;
+ALTER TABLE audit_events
+    ADD CONSTRAINT audit_events_action_check CHECK (action IN ('create', 'update', 'delete', 'export'));
@@ -0,0 +1,41 @@
+-- Retention rules for students who have left. A student whose latest
+-- enrollment has one of `statuses` is purged `retain_years` after that
+-- enrollment ended, unless they are on legal hold.
+CREATE TABLE IF NOT EXISTS retention_policies (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    statuses TEXT[] NOT NULL,
+    retain_years INT NOT NULL CHECK (retain_years >= 0),
+    -- Keep scores without anything identifying for trend reporting
+    keep_deidentified_scores BOOLEAN NOT NULL DEFAULT TRUE,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    updated_by INT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE students
+    ADD COLUMN IF NOT EXISTS legal_hold BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS legal_hold_reason TEXT,
+    ADD COLUMN IF NOT EXISTS legal_hold_set_by TEXT,
+    ADD COLUMN IF NOT EXISTS legal_hold_set_at TIMESTAMPTZ;
+
+-- Scores of purged students. subject_key is random per student, so a
+-- student's scores stay together without pointing back at them.
+CREATE TABLE IF NOT EXISTS deidentified_scores (
+    id BIGSERIAL PRIMARY KEY,
+    subject_key UUID NOT NULL,
+    test_id UUID NOT NULL REFERENCES tests(test_id) ON DELETE CASCADE,
+    date_administered TIMESTAMP,
+    test_scores INT[],
+    test_variant INT NOT NULL,
+    attempt INT NOT NULL,
+    -- From the student's last enrollment
+    grade_level grade_enum,
+    academic_year school_year_enum,
+    policy_id INT REFERENCES retention_policies(id) ON DELETE SET NULL,
+    purged_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_deidentified_scores_test ON deidentified_scores (test_id);
+CREATE INDEX IF NOT EXISTS idx_deidentified_scores_subject ON deidentified_scores (subject_key);
@@ -0,0 +1,23 @@
+-- Duplicate students folded into a surviving record. Each row keeps what
+-- is needed to undo the merge: the merged student's row as it was, and
+-- which enrollments, scores and sessions moved over.
+CREATE TABLE IF NOT EXISTS student_merges (
+    id SERIAL PRIMARY KEY,
+    survivor_id INT NOT NULL REFERENCES students(student_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    merged_id INT NOT NULL,
+    -- to_jsonb() of the merged student's row; PII stays sealed as stored
+    merged_student JSONB NOT NULL,
+    moved_enrollment_ids INT[] NOT NULL DEFAULT '{}',
+    -- [{test_id, test_variant, attempt, merged_attempt}]: attempts are
+    -- renumbered after the survivor's own to keep them unique
+    moved_scores JSONB NOT NULL DEFAULT '[]',
+    moved_session_ids UUID[] NOT NULL DEFAULT '{}',
+    reason TEXT,
+    merged_by TEXT NOT NULL,
+    merged_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    unmerged_by TEXT,
+    unmerged_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_student_merges_survivor ON student_merges (survivor_id);
+CREATE INDEX IF NOT EXISTS idx_student_merges_merged ON student_merges (merged_id);
@@ -0,0 +1,34 @@
+-- History of each student's program flags and placement. A version is
+-- current while valid_to is NULL; a change closes it and opens the next.
+-- Names, birth date, PIN and notes are sealed PII and are not versioned.
+CREATE TABLE IF NOT EXISTS student_versions (
+    id BIGSERIAL PRIMARY KEY,
+    student_id INT NOT NULL REFERENCES students(student_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    valid_from TIMESTAMPTZ NOT NULL,
+    valid_to TIMESTAMPTZ,
+    changed_by TEXT NOT NULL,
+    preferred TEXT,
+    gender gender_enum,
+    esl esl_enum,
+    current_grade_level grade_enum,
+    teacher TEXT,
+    iep BOOLEAN,
+    bip BOOLEAN,
+    student_504 BOOLEAN,
+    readplan BOOLEAN,
+    gt BOOLEAN,
+    intervention intervention_enum,
+    eye_glasses BOOLEAN,
+    CHECK (valid_to IS NULL OR valid_to >= valid_from)
+);
+
+CREATE INDEX IF NOT EXISTS idx_student_versions_student ON student_versions (student_id, valid_from);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_student_versions_current ON student_versions (student_id) WHERE valid_to IS NULL;
+
+-- Students as they are now; nothing earlier was kept
+INSERT INTO student_versions (student_id, valid_from, changed_by, preferred, gender, esl, current_grade_level, teacher,
+                              iep, bip, student_504, readplan, gt, intervention, eye_glasses)
+SELECT student_id, NOW(), 'history started', preferred, gender, esl, current_grade_level, teacher,
+       iep, bip, student_504, readplan, gt, intervention, eye_glasses
+FROM students
+WHERE NOT EXISTS (SELECT 1 FROM student_versions v WHERE v.student_id = students.student_id);
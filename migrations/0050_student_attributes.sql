@@ -0,0 +1,28 @@
+-- District-defined student fields beyond the built-in program flags
+-- (Title I, migrant, homeless, ...). `key` is the column header used by
+-- the bulk CSV import.
+CREATE TABLE IF NOT EXISTS student_attribute_definitions (
+    id SERIAL PRIMARY KEY,
+    key TEXT NOT NULL UNIQUE CHECK (key ~ '^[a-z][a-z0-9_]*$'),
+    label TEXT NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('bool', 'text', 'enum', 'date')),
+    -- Allowed values of an enum attribute, in display order
+    options TEXT[] NOT NULL DEFAULT '{}',
+    sort_order INT NOT NULL DEFAULT 0,
+    -- Inactive attributes keep their values but are hidden from forms
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Values are stored normalized: 'true'/'false', YYYY-MM-DD, or one of the
+-- enum options. A missing row means unset.
+CREATE TABLE IF NOT EXISTS student_attribute_values (
+    student_id INT NOT NULL REFERENCES students(student_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    attribute_id INT NOT NULL REFERENCES student_attribute_definitions(id) ON DELETE CASCADE,
+    value TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (student_id, attribute_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_student_attribute_values_attribute ON student_attribute_values (attribute_id, value);
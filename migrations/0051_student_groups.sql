@@ -0,0 +1,29 @@
+-- Named student groups that cut across grades and classes, such as a
+-- reading specialist's intervention groups. The owner is the employee who
+-- runs the group; active members join that employee's caseload.
+CREATE TABLE IF NOT EXISTS student_groups (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    owner_id INT REFERENCES employees(id) ON DELETE SET NULL,
+    purpose TEXT NOT NULL CHECK (purpose IN ('intervention', 'enrichment', 'ell', 'other')),
+    description TEXT,
+    -- Inactive groups keep their members for reporting but grant no access
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_student_groups_owner ON student_groups (owner_id) WHERE active;
+
+-- A student is a member from start_date through end_date; an open end date
+-- means the student is still in the group
+CREATE TABLE IF NOT EXISTS student_group_members (
+    group_id INT NOT NULL REFERENCES student_groups(id) ON DELETE CASCADE,
+    student_id INT NOT NULL REFERENCES students(student_id) ON DELETE CASCADE ON UPDATE CASCADE,
+    start_date DATE NOT NULL DEFAULT CURRENT_DATE,
+    end_date DATE,
+    PRIMARY KEY (group_id, student_id),
+    CHECK (end_date IS NULL OR end_date >= start_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_student_group_members_student ON student_group_members (student_id);
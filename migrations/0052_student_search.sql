@@ -0,0 +1,11 @@
+-- Trigram indexes behind the ranked, typo-tolerant student search. Encrypted
+-- names are NULL here and are searched through name_tokens instead.
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX IF NOT EXISTS idx_students_search_name ON students USING GIN (
+    (LOWER(COALESCE(preferred, '') || ' ' || COALESCE(firstname, '') || ' ' || COALESCE(lastname, ''))) gin_trgm_ops
+);
+
+CREATE INDEX IF NOT EXISTS idx_students_search_teacher ON students USING GIN (LOWER(teacher) gin_trgm_ops);
+
+CREATE INDEX IF NOT EXISTS idx_students_search_id ON students USING GIN ((student_id::text) gin_trgm_ops);
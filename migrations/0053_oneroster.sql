@@ -0,0 +1,37 @@
+-- OneRoster sourcedIds, so records imported from a student information
+-- system match up on the next import and export under the same ids.
+ALTER TABLE students ADD COLUMN IF NOT EXISTS sourced_id TEXT UNIQUE;
+
+ALTER TABLE employees ADD COLUMN IF NOT EXISTS sourced_id TEXT UNIQUE;
+
+-- A course here is a OneRoster class; the OneRoster course it belongs to
+-- and the term it runs in are kept alongside
+ALTER TABLE courses
+    ADD COLUMN IF NOT EXISTS sourced_id TEXT UNIQUE,
+    ADD COLUMN IF NOT EXISTS course_sourced_id TEXT,
+    ADD COLUMN IF NOT EXISTS term_sourced_id TEXT;
+
+ALTER TABLE student_enrollments ADD COLUMN IF NOT EXISTS sourced_id TEXT UNIQUE;
+
+-- Orgs and academic sessions have no counterpart in the app. They are kept
+-- as imported so exports name the same schools and terms.
+CREATE TABLE IF NOT EXISTS roster_orgs (
+    sourced_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    org_type TEXT NOT NULL,
+    identifier TEXT,
+    parent_sourced_id TEXT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS roster_academic_sessions (
+    sourced_id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    session_type TEXT NOT NULL,
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    parent_sourced_id TEXT,
+    school_year INT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    CHECK (end_date >= start_date)
+);
@@ -0,0 +1,13 @@
+-- Saved column mappings for CSV imports, one per source system and import
+-- kind, so a district's SIS export maps the same way every time
+CREATE TABLE IF NOT EXISTS import_mapping_profiles (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    import_kind TEXT NOT NULL CHECK (import_kind IN ('students', 'enrollments')),
+    -- A ColumnMapping: template column to file header, and value transforms
+    mapping JSONB NOT NULL,
+    updated_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (name, import_kind)
+);
@@ -0,0 +1,9 @@
+-- Rows that used to cascade away with the merged student. Its history is
+-- kept as it was and comes back on unmerge; attribute values and group
+-- memberships the survivor lacked move over and are listed by ID.
+ALTER TABLE student_merges
+    ADD COLUMN IF NOT EXISTS merged_versions JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN IF NOT EXISTS merged_attribute_values JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN IF NOT EXISTS merged_group_memberships JSONB NOT NULL DEFAULT '[]',
+    ADD COLUMN IF NOT EXISTS moved_attribute_ids INT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS moved_group_ids INT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,8 @@
+-- An undone merge no longer needs the merged record it put back. Unmerging
+-- now clears it; this clears the copies kept by merges undone before.
+UPDATE student_merges
+SET merged_student = jsonb_build_object('student_id', merged_id),
+    merged_versions = '[]',
+    merged_attribute_values = '[]',
+    merged_group_memberships = '[]'
+WHERE unmerged_at IS NOT NULL;
@@ -2,8 +2,10 @@ use leptos::*;
 use leptos_router::*;
 // Importing necessary components and pages
 use crate::app::components::{
+    audit_log::AuditLogPage,
     live_testing::{test_session::RealtimeTestSession, AnonymousStudentTest},
     login_components::{RequestPasswordResetForm, ResetPasswordForm},
+    oidc_admin::OidcAdminPanel,
     saml_admin::SamlAdminPanel,
     test_components::test_variation_manager::TestVariationManager,
     test_templates::{FlashCardSet, GridTest},
@@ -54,6 +56,8 @@ pub fn AppRoutes() -> impl IntoView {
             <Route path="/myaccount" view=MyAccount/>
             <Route path="/teachers" view=Teachers/>
             <Route path="/admin/saml" view=SamlAdminPanel/>
+            <Route path="/admin/oidc" view=OidcAdminPanel/>
+            <Route path="/admin/audit" view=AuditLogPage/>
 
             // 404 fallback
             <Route path="/*any" view=NotFound/>
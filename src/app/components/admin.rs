@@ -3,3 +3,12 @@ pub use saml_admin::*;
 
 pub mod saml_admin_content;
 pub use saml_admin_content::*;
+
+pub mod saml_mapping_editor;
+pub use saml_mapping_editor::*;
+
+pub mod oidc_admin;
+pub use oidc_admin::*;
+
+pub mod audit_log;
+pub use audit_log::*;
@@ -0,0 +1,316 @@
+use crate::app::models::audit::{
+    AuditAction, AuditChainStatus, AuditEntity, AuditEvent, AuditEventFilter,
+};
+use crate::app::models::permission::Permission;
+use crate::app::models::user::SessionUser;
+use crate::app::server_functions::audit::{
+    export_audit_events_csv, get_audit_events, verify_audit_chain, AUDIT_PAGE_LIMIT,
+};
+use chrono::NaiveDate;
+use leptos::*;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+
+#[cfg(feature = "hydrate")]
+use {js_sys::Array, wasm_bindgen::JsCast};
+
+#[component]
+pub fn AuditLogPage() -> impl IntoView {
+    let current_user = use_context::<ReadSignal<Option<SessionUser>>>().unwrap();
+    let (events, set_events) = create_signal::<Vec<AuditEvent>>(Vec::new());
+    let (loading, set_loading) = create_signal(false);
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+    let (chain_status, set_chain_status) = create_signal::<Option<AuditChainStatus>>(None);
+
+    // Filter fields
+    let (actor, set_actor) = create_signal(String::new());
+    let (action, set_action) = create_signal(String::new());
+    let (entity_type, set_entity_type) = create_signal(String::new());
+    let (entity_id, set_entity_id) = create_signal(String::new());
+    let (from_date, set_from_date) = create_signal(String::new());
+    let (to_date, set_to_date) = create_signal(String::new());
+
+    let can_view = move || {
+        current_user
+            .get()
+            .map(|user| Permission::ViewAuditLog.is_granted_to(&user))
+            .unwrap_or(false)
+    };
+
+    let current_filter = move || {
+        let text = |value: String| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        AuditEventFilter {
+            actor: text(actor.get()),
+            action: AuditAction::from_str(&action.get()).ok(),
+            entity_type: AuditEntity::from_str(&entity_type.get()).ok(),
+            entity_id: text(entity_id.get()),
+            from: NaiveDate::parse_from_str(&from_date.get(), "%Y-%m-%d").ok(),
+            to: NaiveDate::parse_from_str(&to_date.get(), "%Y-%m-%d").ok(),
+        }
+    };
+
+    let search = create_action(move |filter: &AuditEventFilter| {
+        let filter = filter.clone();
+        async move {
+            set_loading.set(true);
+            match get_audit_events(filter).await {
+                Ok(list) => {
+                    if list.len() as i64 >= AUDIT_PAGE_LIMIT {
+                        set_message.set(Some((
+                            format!(
+                                "Showing the newest {} events. Narrow the filters or export to CSV for the rest.",
+                                AUDIT_PAGE_LIMIT
+                            ),
+                            true,
+                        )));
+                    } else {
+                        set_message.set(None);
+                    }
+                    set_events.set(list);
+                }
+                Err(e) => {
+                    set_message.set(Some((format!("Failed to load audit log: {}", e), false)))
+                }
+            }
+            set_loading.set(false);
+        }
+    });
+
+    create_effect(move |_| {
+        if can_view() {
+            search.dispatch(AuditEventFilter::default());
+        }
+    });
+
+    let verify = create_action(move |_: &()| async move {
+        set_chain_status.set(None);
+        match verify_audit_chain().await {
+            Ok(status) => set_chain_status.set(Some(status)),
+            Err(e) => set_message.set(Some((format!("Failed to verify chain: {}", e), false))),
+        }
+    });
+
+    let export = create_action(move |filter: &AuditEventFilter| {
+        let filter = filter.clone();
+        async move {
+            match export_audit_events_csv(filter).await {
+                Ok(csv) => {
+                    #[cfg(feature = "hydrate")]
+                    {
+                        let blob = web_sys::Blob::new_with_str_sequence(&Array::of1(&csv.into()))
+                            .unwrap_or_else(|_| web_sys::Blob::new().unwrap());
+
+                        let url =
+                            web_sys::Url::create_object_url_with_blob(&blob).unwrap_or_default();
+
+                        if let Some(window) = web_sys::window() {
+                            if let Some(document) = window.document() {
+                                if let Ok(a) = document.create_element("a") {
+                                    let _ = a.set_attribute("href", &url);
+                                    let _ = a.set_attribute("download", "audit_log.csv");
+
+                                    if let Some(html_element) = a.dyn_ref::<web_sys::HtmlElement>()
+                                    {
+                                        html_element.click();
+                                    }
+                                }
+                            }
+                        }
+                        let _ = web_sys::Url::revoke_object_url(&url);
+                    }
+                    #[cfg(not(feature = "hydrate"))]
+                    let _ = csv;
+                }
+                Err(e) => set_message.set(Some((format!("Failed to export: {}", e), false))),
+            }
+        }
+    });
+
+    let input_class = "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent";
+
+    let text_filter = move |label: &'static str,
+                            input_type: &'static str,
+                            value: ReadSignal<String>,
+                            set_value: WriteSignal<String>| {
+        view! {
+            <div>
+                <label class="block text-sm font-medium text-gray-700 mb-2">{label}</label>
+                <input
+                    type=input_type
+                    class=input_class
+                    prop:value=move || value.get()
+                    on:input=move |ev| set_value.set(event_target_value(&ev))
+                />
+            </div>
+        }
+    };
+
+    let show_json = |value: &Option<serde_json::Value>| {
+        value
+            .as_ref()
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "—".to_string())
+    };
+
+    view! {
+        <div class="max-w-7xl mx-auto p-6">
+            <div class="mb-6">
+                <h1 class="text-3xl font-bold text-gray-900">"Audit Log"</h1>
+                <p class="mt-2 text-gray-600">"Every change to scores, students, tests and settings, with who made it and when"</p>
+            </div>
+
+            {move || {
+                if !can_view() {
+                    view! {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded">
+                            "Access denied. Administrator privileges required."
+                        </div>
+                    }.into_view()
+                } else {
+                    view! {
+                        <div class="space-y-6">
+                            {move || {
+                                message.get().map(|(msg, is_success)| {
+                                    let bg_class = if is_success { "bg-blue-50 border-blue-200 text-blue-800" } else { "bg-red-100 border-red-400 text-red-700" };
+                                    view! {
+                                        <div class={format!("border px-4 py-3 rounded {}", bg_class)}>
+                                            {msg}
+                                        </div>
+                                    }
+                                })
+                            }}
+
+                            <form
+                                class="bg-gray-50 p-6 rounded-lg"
+                                on:submit=move |ev| {
+                                    ev.prevent_default();
+                                    search.dispatch(current_filter());
+                                }
+                            >
+                                <div class="grid grid-cols-1 md:grid-cols-3 gap-4">
+                                    {text_filter("Actor", "text", actor, set_actor)}
+                                    <div>
+                                        <label class="block text-sm font-medium text-gray-700 mb-2">"Action"</label>
+                                        <select
+                                            class=input_class
+                                            on:change=move |ev| set_action.set(event_target_value(&ev))
+                                        >
+                                            <option value="">"Any"</option>
+                                            {AuditAction::iter().map(|action| view! {
+                                                <option value=action.as_str()>{action.as_str()}</option>
+                                            }).collect_view()}
+                                        </select>
+                                    </div>
+                                    <div>
+                                        <label class="block text-sm font-medium text-gray-700 mb-2">"Record type"</label>
+                                        <select
+                                            class=input_class
+                                            on:change=move |ev| set_entity_type.set(event_target_value(&ev))
+                                        >
+                                            <option value="">"Any"</option>
+                                            {AuditEntity::iter().map(|entity| view! {
+                                                <option value=entity.as_str()>{entity.label()}</option>
+                                            }).collect_view()}
+                                        </select>
+                                    </div>
+                                    {text_filter("Record ID", "text", entity_id, set_entity_id)}
+                                    {text_filter("From", "date", from_date, set_from_date)}
+                                    {text_filter("To", "date", to_date, set_to_date)}
+                                </div>
+
+                                <div class="mt-6 flex justify-end space-x-3">
+                                    <button
+                                        type="button"
+                                        class="px-4 py-2 border border-gray-300 rounded-md text-gray-700 hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2"
+                                        on:click=move |_| verify.dispatch(())
+                                        prop:disabled=move || verify.pending().get()
+                                    >
+                                        {move || if verify.pending().get() { "Verifying..." } else { "Verify Chain" }}
+                                    </button>
+                                    <button
+                                        type="button"
+                                        class="px-4 py-2 border border-gray-300 rounded-md text-gray-700 hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2"
+                                        on:click=move |_| export.dispatch(current_filter())
+                                        prop:disabled=move || export.pending().get()
+                                    >
+                                        "Export CSV"
+                                    </button>
+                                    <button
+                                        type="submit"
+                                        class="px-4 py-2 bg-blue-600 text-white rounded-md hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2 disabled:bg-gray-400 disabled:cursor-not-allowed"
+                                        prop:disabled=move || loading.get()
+                                    >
+                                        {move || if loading.get() { "Searching..." } else { "Search" }}
+                                    </button>
+                                </div>
+                            </form>
+
+                            {move || {
+                                chain_status.get().map(|status| match status.first_invalid_id {
+                                    None => view! {
+                                        <div class="border px-4 py-3 rounded bg-green-100 border-green-400 text-green-700">
+                                            {format!("Chain intact: {} events verified.", status.events_checked)}
+                                        </div>
+                                    },
+                                    Some(id) => view! {
+                                        <div class="border px-4 py-3 rounded bg-red-100 border-red-400 text-red-700">
+                                            {format!(
+                                                "Chain broken at event {}. The {} events before it verified; that event or the log after it has been altered.",
+                                                id, status.events_checked
+                                            )}
+                                        </div>
+                                    },
+                                })
+                            }}
+
+                            <div class="bg-white shadow rounded-lg overflow-x-auto">
+                                {move || {
+                                    let list = events.get();
+                                    if list.is_empty() {
+                                        view! {
+                                            <div class="p-6 text-center text-gray-500">
+                                                <p>"No audit events match these filters."</p>
+                                            </div>
+                                        }.into_view()
+                                    } else {
+                                        view! {
+                                            <table class="min-w-full divide-y divide-gray-200 text-sm">
+                                                <thead class="bg-gray-50">
+                                                    <tr>
+                                                        <th class="px-4 py-3 text-left font-medium text-gray-500">"When"</th>
+                                                        <th class="px-4 py-3 text-left font-medium text-gray-500">"Actor"</th>
+                                                        <th class="px-4 py-3 text-left font-medium text-gray-500">"Action"</th>
+                                                        <th class="px-4 py-3 text-left font-medium text-gray-500">"Record"</th>
+                                                        <th class="px-4 py-3 text-left font-medium text-gray-500">"Before"</th>
+                                                        <th class="px-4 py-3 text-left font-medium text-gray-500">"After"</th>
+                                                    </tr>
+                                                </thead>
+                                                <tbody class="divide-y divide-gray-200">
+                                                    {list.into_iter().map(|event| view! {
+                                                        <tr class="align-top hover:bg-gray-50">
+                                                            <td class="px-4 py-3 whitespace-nowrap text-gray-700">
+                                                                {event.occurred_at.format("%Y-%m-%d %H:%M:%S").to_string()}
+                                                            </td>
+                                                            <td class="px-4 py-3 text-gray-900">{event.actor_username.clone()}</td>
+                                                            <td class="px-4 py-3 text-gray-900">{event.action.as_str()}</td>
+                                                            <td class="px-4 py-3 text-gray-900">
+                                                                <div>{event.entity_type.label()}</div>
+                                                                <div class="font-mono text-xs text-gray-500">{event.entity_id.clone()}</div>
+                                                            </td>
+                                                            <td class="px-4 py-3 font-mono text-xs text-gray-600 break-all max-w-xs">{show_json(&event.before)}</td>
+                                                            <td class="px-4 py-3 font-mono text-xs text-gray-600 break-all max-w-xs">{show_json(&event.after)}</td>
+                                                        </tr>
+                                                    }).collect_view()}
+                                                </tbody>
+                                            </table>
+                                        }.into_view()
+                                    }
+                                }}
+                            </div>
+                        </div>
+                    }.into_view()
+                }
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,316 @@
+use crate::app::models::auth::OidcConfig;
+use crate::app::models::user::{SessionUser, UserRole};
+use crate::app::server_functions::oidc_auth::{
+    create_oidc_config, delete_oidc_config, get_oidc_configs, toggle_oidc_config,
+};
+use leptos::*;
+
+#[component]
+pub fn OidcAdminPanel() -> impl IntoView {
+    let current_user = use_context::<ReadSignal<Option<SessionUser>>>().unwrap();
+    let (configs, set_configs) = create_signal::<Vec<OidcConfig>>(Vec::new());
+    let (show_add_form, set_show_add_form) = create_signal(false);
+    let (loading, set_loading) = create_signal(false);
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+
+    // Form fields for adding a new OIDC config
+    let (institution_name, set_institution_name) = create_signal("".to_string());
+    let (issuer, set_issuer) = create_signal("".to_string());
+    let (client_id, set_client_id) = create_signal("".to_string());
+    let (client_secret, set_client_secret) = create_signal("".to_string());
+    let (scopes, set_scopes) = create_signal("openid email profile".to_string());
+    let (trust_unverified_email, set_trust_unverified_email) = create_signal(false);
+
+    let is_admin = move || {
+        current_user
+            .get()
+            .map(|user| matches!(user.role, UserRole::Admin | UserRole::SuperAdmin))
+            .unwrap_or(false)
+    };
+
+    let reload = move || {
+        spawn_local(async move {
+            match get_oidc_configs().await {
+                Ok(list) => set_configs.set(list),
+                Err(e) => {
+                    logging::log!("Failed to load OIDC configs: {:?}", e);
+                    set_message.set(Some((format!("Failed to load institutions: {}", e), false)));
+                }
+            }
+        });
+    };
+
+    create_effect(move |_| {
+        if is_admin() {
+            reload();
+        }
+    });
+
+    let handle_add_config = create_action(move |_: &()| {
+        let institution_name = institution_name.get();
+        let issuer = issuer.get();
+        let client_id = client_id.get();
+        let client_secret = client_secret.get();
+        let scopes = scopes.get();
+        let trust_unverified_email = trust_unverified_email.get();
+
+        async move {
+            set_loading.set(true);
+            set_message.set(None);
+
+            if institution_name.trim().is_empty()
+                || issuer.trim().is_empty()
+                || client_id.trim().is_empty()
+            {
+                set_message.set(Some((
+                    "All required fields must be filled".to_string(),
+                    false,
+                )));
+                set_loading.set(false);
+                return;
+            }
+
+            match create_oidc_config(
+                institution_name,
+                issuer,
+                client_id,
+                client_secret,
+                scopes,
+                trust_unverified_email,
+            )
+            .await
+            {
+                Ok(response) => {
+                    set_message.set(Some((response.message, response.success)));
+                    if response.success {
+                        set_institution_name.set("".to_string());
+                        set_issuer.set("".to_string());
+                        set_client_id.set("".to_string());
+                        set_client_secret.set("".to_string());
+                        set_scopes.set("openid email profile".to_string());
+                        set_trust_unverified_email.set(false);
+                        set_show_add_form.set(false);
+                        reload();
+                    }
+                }
+                Err(e) => {
+                    set_message.set(Some((
+                        format!("Failed to create OIDC config: {}", e),
+                        false,
+                    )));
+                }
+            }
+
+            set_loading.set(false);
+        }
+    });
+
+    let handle_toggle = create_action(move |config_id: &String| {
+        let config_id = config_id.clone();
+        async move {
+            match toggle_oidc_config(config_id).await {
+                Ok(response) => {
+                    set_message.set(Some((response.message, response.success)));
+                    reload();
+                }
+                Err(e) => set_message.set(Some((format!("Failed to update: {}", e), false))),
+            }
+        }
+    });
+
+    let handle_delete = create_action(move |config_id: &String| {
+        let config_id = config_id.clone();
+        async move {
+            match delete_oidc_config(config_id).await {
+                Ok(response) => {
+                    set_message.set(Some((response.message, response.success)));
+                    reload();
+                }
+                Err(e) => set_message.set(Some((format!("Failed to delete: {}", e), false))),
+            }
+        }
+    });
+
+    let redirect_uri = format!(
+        "{}/oidc/callback",
+        std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string())
+    );
+
+    let text_input = move |label: &'static str,
+                           placeholder: &'static str,
+                           input_type: &'static str,
+                           value: ReadSignal<String>,
+                           set_value: WriteSignal<String>| {
+        view! {
+            <div>
+                <label class="block text-sm font-medium text-gray-700 mb-2">{label}</label>
+                <input
+                    type=input_type
+                    class="w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                    prop:value=move || value.get()
+                    on:input=move |ev| set_value.set(event_target_value(&ev))
+                    prop:disabled=move || loading.get()
+                    placeholder=placeholder
+                />
+            </div>
+        }
+    };
+
+    view! {
+        <div class="max-w-6xl mx-auto p-6">
+            <div class="mb-6">
+                <h1 class="text-3xl font-bold text-gray-900">"OpenID Connect Administration"</h1>
+                <p class="mt-2 text-gray-600">"Manage OIDC single sign-on for Google Workspace, Azure AD and other providers"</p>
+            </div>
+
+            {move || {
+                if !is_admin() {
+                    view! {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded">
+                            "Access denied. Administrator privileges required."
+                        </div>
+                    }.into_view()
+                } else {
+                    view! {
+                        <div class="space-y-6">
+                            {move || {
+                                message.get().map(|(msg, is_success)| {
+                                    let bg_class = if is_success { "bg-green-100 border-green-400 text-green-700" } else { "bg-red-100 border-red-400 text-red-700" };
+                                    view! {
+                                        <div class={format!("border px-4 py-3 rounded {}", bg_class)}>
+                                            {msg}
+                                        </div>
+                                    }
+                                })
+                            }}
+
+                            <div class="flex justify-between items-center">
+                                <h2 class="text-xl font-semibold text-gray-900">"OIDC Institutions"</h2>
+                                <button
+                                    class="px-4 py-2 bg-blue-600 text-white rounded-md hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2"
+                                    on:click=move |_| set_show_add_form.update(|show| *show = !*show)
+                                >
+                                    {move || if show_add_form.get() { "Cancel" } else { "Add Institution" }}
+                                </button>
+                            </div>
+
+                            {move || {
+                                show_add_form.get().then(|| view! {
+                                    <div class="bg-gray-50 p-6 rounded-lg">
+                                        <h3 class="text-lg font-medium text-gray-900 mb-4">"Add OIDC Configuration"</h3>
+                                        <form on:submit=move |ev| {
+                                            ev.prevent_default();
+                                            if !loading.get() {
+                                                handle_add_config.dispatch(());
+                                            }
+                                        }>
+                                            <div class="grid grid-cols-1 md:grid-cols-2 gap-6">
+                                                {text_input("Institution Name *", "Example School District", "text", institution_name, set_institution_name)}
+                                                {text_input("Issuer *", "https://accounts.google.com", "url", issuer, set_issuer)}
+                                                {text_input("Client ID *", "", "text", client_id, set_client_id)}
+                                                {text_input("Client Secret", "Leave empty for public clients", "password", client_secret, set_client_secret)}
+                                                {text_input("Scopes", "openid email profile", "text", scopes, set_scopes)}
+                                                <label class="flex items-center space-x-2 text-sm text-gray-700 mt-8">
+                                                    <input
+                                                        type="checkbox"
+                                                        prop:checked=move || trust_unverified_email.get()
+                                                        on:change=move |ev| set_trust_unverified_email.set(event_target_checked(&ev))
+                                                    />
+                                                    <span>"Trust email addresses the provider has not verified"</span>
+                                                </label>
+                                            </div>
+
+                                            <div class="mt-6 flex justify-end space-x-3">
+                                                <button
+                                                    type="button"
+                                                    class="px-4 py-2 border border-gray-300 rounded-md text-gray-700 hover:bg-gray-50 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2"
+                                                    on:click=move |_| set_show_add_form.set(false)
+                                                    prop:disabled=move || loading.get()
+                                                >
+                                                    "Cancel"
+                                                </button>
+                                                <button
+                                                    type="submit"
+                                                    class="px-4 py-2 bg-blue-600 text-white rounded-md hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2 disabled:bg-gray-400 disabled:cursor-not-allowed"
+                                                    prop:disabled=move || loading.get()
+                                                >
+                                                    {move || if loading.get() { "Creating..." } else { "Create Configuration" }}
+                                                </button>
+                                            </div>
+                                        </form>
+                                    </div>
+                                })
+                            }}
+
+                            <div class="bg-white shadow rounded-lg">
+                                <div class="px-6 py-4 border-b border-gray-200">
+                                    <h3 class="text-lg font-medium text-gray-900">"Configured Institutions"</h3>
+                                </div>
+                                {move || {
+                                    let configs_list = configs.get();
+                                    if configs_list.is_empty() {
+                                        view! {
+                                            <div class="p-6 text-center text-gray-500">
+                                                <p>"No OIDC institutions configured yet."</p>
+                                            </div>
+                                        }.into_view()
+                                    } else {
+                                        view! {
+                                            <div class="divide-y divide-gray-200">
+                                                {configs_list.into_iter().map(|config| {
+                                                    let toggle_id = config.id.to_string();
+                                                    let delete_id = config.id.to_string();
+                                                    let (badge_class, badge_label) = if config.active {
+                                                        ("bg-green-100 text-green-800", "Active")
+                                                    } else {
+                                                        ("bg-red-100 text-red-800", "Inactive")
+                                                    };
+                                                    view! {
+                                                        <div class="p-6 hover:bg-gray-50 flex items-center justify-between">
+                                                            <div>
+                                                                <h4 class="text-lg font-medium text-gray-900">{config.institution_name}</h4>
+                                                                <p class="text-sm text-gray-500">{config.issuer}</p>
+                                                                <p class="text-sm text-gray-500">"Client ID: " {config.client_id}</p>
+                                                            </div>
+                                                            <div class="flex items-center space-x-2">
+                                                                <span class=format!("inline-flex items-center px-2.5 py-0.5 rounded-full text-xs font-medium {}", badge_class)>
+                                                                    {badge_label}
+                                                                </span>
+                                                                <button
+                                                                    class="text-indigo-600 hover:text-indigo-900 text-sm font-medium"
+                                                                    on:click=move |_| handle_toggle.dispatch(toggle_id.clone())
+                                                                >
+                                                                    {if config.active { "Disable" } else { "Enable" }}
+                                                                </button>
+                                                                <button
+                                                                    class="text-red-600 hover:text-red-900 text-sm font-medium"
+                                                                    on:click=move |_| handle_delete.dispatch(delete_id.clone())
+                                                                >
+                                                                    "Delete"
+                                                                </button>
+                                                            </div>
+                                                        </div>
+                                                    }
+                                                }).collect_view()}
+                                            </div>
+                                        }.into_view()
+                                    }
+                                }}
+                            </div>
+
+                            <div class="bg-blue-50 border border-blue-200 rounded-lg p-6">
+                                <h3 class="text-lg font-medium text-blue-900 mb-4">"Client Registration"</h3>
+                                <p class="text-blue-800 mb-4">
+                                    "Register this redirect URI with your identity provider:"
+                                </p>
+                                <div class="bg-white border border-blue-200 rounded p-4 font-mono text-sm text-gray-700">
+                                    {redirect_uri.clone()}
+                                </div>
+                            </div>
+                        </div>
+                    }.into_view()
+                }
+            }}
+        </div>
+    }
+}
@@ -1,3 +1,4 @@
+use crate::app::components::admin::saml_mapping_editor::SamlMappingEditor;
 use crate::app::models::user::{SessionUser, UserRole};
 use crate::app::server_functions::saml_auth::{
     create_saml_config, get_saml_institutions, SamlInstitution,
@@ -11,6 +12,7 @@ pub fn SamlAdminPanel() -> impl IntoView {
     let (show_add_form, set_show_add_form) = create_signal(false);
     let (loading, set_loading) = create_signal(false);
     let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+    let (mapping_config_id, set_mapping_config_id) = create_signal::<Option<String>>(None);
 
     // Form fields for adding new SAML config
     let (institution_name, set_institution_name) = create_signal("".to_string());
@@ -337,6 +339,8 @@ MIIEbzCCA1egAwIBAgIJAIYhQeZPzfH3MA0GCSqGSIb3DQEBCwUAMIGBMQswCQYD...
                                             view! {
                                                 <div class="divide-y divide-gray-200">
                                                     {institutions_list.into_iter().map(|institution| {
+                                                        let config_id = institution.id.clone();
+                                                        let toggle_id = institution.id.clone();
                                                         view! {
                                                             <div class="p-6 hover:bg-gray-50">
                                                                 <div class="flex items-center justify-between">
@@ -369,6 +373,17 @@ MIIEbzCCA1egAwIBAgIJAIYhQeZPzfH3MA0GCSqGSIb3DQEBCwUAMIGBMQswCQYD...
                                                                                 </span>
                                                                             }.into_view()
                                                                         }}
+                                                                        <button
+                                                                            class="text-indigo-600 hover:text-indigo-900 text-sm font-medium"
+                                                                            on:click=move |_| {
+                                                                                let id = toggle_id.clone();
+                                                                                set_mapping_config_id.update(|current| {
+                                                                                    *current = if current.as_ref() == Some(&id) { None } else { Some(id) };
+                                                                                });
+                                                                            }
+                                                                        >
+                                                                            "Attribute Mapping"
+                                                                        </button>
                                                                         <button class="text-indigo-600 hover:text-indigo-900 text-sm font-medium">
                                                                             "Edit"
                                                                         </button>
@@ -377,6 +392,14 @@ MIIEbzCCA1egAwIBAgIJAIYhQeZPzfH3MA0GCSqGSIb3DQEBCwUAMIGBMQswCQYD...
                                                                         </button>
                                                                     </div>
                                                                 </div>
+                                                                {move || {
+                                                                    (mapping_config_id.get().as_ref() == Some(&config_id)).then(|| view! {
+                                                                        <SamlMappingEditor
+                                                                            config_id=config_id.clone()
+                                                                            on_close=move |_| set_mapping_config_id.set(None)
+                                                                        />
+                                                                    })
+                                                                }}
                                                             </div>
                                                         }
                                                     }).collect::<Vec<_>>()}
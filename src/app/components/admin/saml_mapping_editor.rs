@@ -0,0 +1,330 @@
+use crate::app::models::auth::{
+    SamlMappedField, SamlMappingPreview, SamlMappingSettings, SamlRoleRule, SsoAttributeMapping,
+};
+use crate::app::models::user::UserRole;
+use crate::app::server_functions::saml_auth::{
+    get_saml_config_details, preview_saml_mapping, update_saml_mapping,
+};
+use leptos::*;
+use std::str::FromStr;
+
+// Roles an IdP attribute may grant. SuperAdmin is only ever assigned by hand.
+const ASSIGNABLE_ROLES: [(UserRole, &str); 4] = [
+    (UserRole::Guest, "Guest"),
+    (UserRole::User, "User"),
+    (UserRole::Teacher, "Teacher"),
+    (UserRole::Admin, "Admin"),
+];
+
+// Attribute and role mapping for one institution, with a preview that runs a
+// pasted SAML response through the unsaved settings.
+#[component]
+pub fn SamlMappingEditor(config_id: String, #[prop(into)] on_close: Callback<()>) -> impl IntoView {
+    let config = create_resource(
+        move || config_id.clone(),
+        |config_id| async move { get_saml_config_details(config_id).await },
+    );
+
+    view! {
+        <div class="bg-gray-50 border border-gray-200 rounded-lg p-6 mt-4">
+            <Suspense fallback=move || view! { <p class="text-sm text-gray-500">"Loading mapping..."</p> }>
+                {move || config.get().map(|result| match result {
+                    Ok(config) => view! {
+                        <SamlMappingForm
+                            config_id=config.id.to_string()
+                            institution_name=config.institution_name.clone()
+                            initial=config.mapping_settings()
+                            on_close=on_close
+                        />
+                    }.into_view(),
+                    Err(e) => view! {
+                        <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded">
+                            {format!("Failed to load SAML configuration: {}", e)}
+                        </div>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
+
+#[component]
+fn SamlMappingForm(
+    config_id: String,
+    institution_name: String,
+    initial: SamlMappingSettings,
+    on_close: Callback<()>,
+) -> impl IntoView {
+    let (attributes, set_attributes) = create_signal(initial.attributes);
+    let (role_rules, set_role_rules) = create_signal(initial.role_rules);
+    let (default_role, set_default_role) = create_signal(initial.default_role);
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+    let (saml_response, set_saml_response) = create_signal(String::new());
+    let (preview, set_preview) = create_signal::<Option<SamlMappingPreview>>(None);
+
+    let current_settings = move || SamlMappingSettings {
+        attributes: attributes.get(),
+        role_rules: role_rules.get(),
+        default_role: default_role.get(),
+    };
+
+    let save_config_id = config_id.clone();
+    let save_mapping = create_action(move |settings: &SamlMappingSettings| {
+        let config_id = save_config_id.clone();
+        let settings = settings.clone();
+        async move {
+            match update_saml_mapping(config_id, settings).await {
+                Ok(response) => set_message.set(Some((response.message, response.success))),
+                Err(e) => set_message.set(Some((format!("Failed to save mapping: {}", e), false))),
+            }
+        }
+    });
+
+    let run_preview = create_action(
+        move |(settings, response): &(SamlMappingSettings, String)| {
+            let config_id = config_id.clone();
+            let settings = settings.clone();
+            let response = response.clone();
+            async move {
+                match preview_saml_mapping(config_id, settings, response).await {
+                    Ok(result) => {
+                        set_message.set(None);
+                        set_preview.set(Some(result));
+                    }
+                    Err(e) => {
+                        set_preview.set(None);
+                        set_message.set(Some((format!("Preview failed: {}", e), false)));
+                    }
+                }
+            }
+        },
+    );
+
+    let role_select = move |selected: UserRole, on_select: Box<dyn Fn(UserRole)>| {
+        view! {
+            <select
+                class="px-3 py-2 border border-gray-300 rounded-md text-sm bg-white focus:outline-none focus:ring-2 focus:ring-blue-500"
+                on:change=move |ev| {
+                    if let Ok(role) = UserRole::from_str(&event_target_value(&ev)) {
+                        on_select(role);
+                    }
+                }
+            >
+                {ASSIGNABLE_ROLES.iter().map(|(role, label)| view! {
+                    <option value=role.to_string() selected=*role == selected>{*label}</option>
+                }).collect_view()}
+            </select>
+        }
+    };
+
+    view! {
+        <div class="space-y-6">
+            <div class="flex justify-between items-center">
+                <h3 class="text-lg font-medium text-gray-900">
+                    {format!("Attribute Mapping: {}", institution_name)}
+                </h3>
+                <button
+                    class="text-sm text-gray-600 hover:text-gray-900"
+                    on:click=move |_| on_close(())
+                >
+                    "Close"
+                </button>
+            </div>
+
+            {move || message.get().map(|(msg, is_success)| {
+                let bg_class = if is_success { "bg-green-100 border-green-400 text-green-700" } else { "bg-red-100 border-red-400 text-red-700" };
+                view! { <div class={format!("border px-4 py-3 rounded {}", bg_class)}>{msg}</div> }
+            })}
+
+            // Which IdP attribute holds each profile field
+            <div>
+                <h4 class="text-sm font-semibold text-gray-700 mb-2">"Attributes"</h4>
+                <p class="text-xs text-gray-500 mb-3">
+                    "Leave a field blank to fall back to the common attribute names shown."
+                </p>
+                <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    {SamlMappedField::ALL.iter().map(|field| {
+                        let field = *field;
+                        let hint = field
+                            .default_attributes()
+                            .iter()
+                            .filter(|name| !name.starts_with("http"))
+                            .take(3)
+                            .copied()
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        view! {
+                            <div>
+                                <label class="block text-sm font-medium text-gray-700 mb-1">{field.label()}</label>
+                                <input
+                                    type="text"
+                                    class="w-full px-3 py-2 border border-gray-300 rounded-md text-sm font-mono focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                                    placeholder=hint
+                                    prop:value=move || attributes.get().get(field.key()).cloned().unwrap_or_default()
+                                    on:input=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_attributes.update(|attributes| {
+                                            if value.trim().is_empty() {
+                                                attributes.remove(field.key());
+                                            } else {
+                                                attributes.insert(field.key().to_string(), value);
+                                            }
+                                        });
+                                    }
+                                />
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+            </div>
+
+            // Role attribute values and the role each one grants
+            <div>
+                <h4 class="text-sm font-semibold text-gray-700 mb-2">"Role Rules"</h4>
+                <p class="text-xs text-gray-500 mb-3">
+                    "Values are matched exactly (ignoring case) against the role attribute. The most privileged match wins. Roles are applied when an account is first created."
+                </p>
+                <div class="space-y-2">
+                    {move || role_rules.get().into_iter().enumerate().map(|(index, rule)| {
+                        view! {
+                            <div class="flex items-center space-x-2">
+                                <input
+                                    type="text"
+                                    class="flex-1 px-3 py-2 border border-gray-300 rounded-md text-sm font-mono focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                                    placeholder="faculty"
+                                    prop:value=rule.value.clone()
+                                    on:change=move |ev| {
+                                        let value = event_target_value(&ev);
+                                        set_role_rules.update(|rules| rules[index].value = value);
+                                    }
+                                />
+                                <span class="text-gray-400">"→"</span>
+                                {role_select(rule.role, Box::new(move |role| {
+                                    set_role_rules.update(|rules| rules[index].role = role);
+                                }))}
+                                <button
+                                    class="text-red-600 hover:text-red-900 text-sm font-medium"
+                                    on:click=move |_| set_role_rules.update(|rules| { rules.remove(index); })
+                                >
+                                    "Remove"
+                                </button>
+                            </div>
+                        }
+                    }).collect_view()}
+                </div>
+                <div class="mt-3 flex items-center justify-between">
+                    <button
+                        class="text-indigo-600 hover:text-indigo-900 text-sm font-medium"
+                        on:click=move |_| set_role_rules.update(|rules| rules.push(SamlRoleRule {
+                            value: String::new(),
+                            role: UserRole::Teacher,
+                        }))
+                    >
+                        "+ Add Rule"
+                    </button>
+                    <div class="flex items-center space-x-2">
+                        <span class="text-sm text-gray-700">"When nothing matches:"</span>
+                        {move || role_select(default_role.get(), Box::new(move |role| set_default_role.set(role)))}
+                    </div>
+                </div>
+            </div>
+
+            <div class="flex justify-end">
+                <button
+                    class="px-4 py-2 bg-blue-600 text-white rounded-md hover:bg-blue-700 focus:outline-none focus:ring-2 focus:ring-blue-500 focus:ring-offset-2 disabled:bg-gray-400 disabled:cursor-not-allowed"
+                    prop:disabled=move || save_mapping.pending().get()
+                    on:click=move |_| save_mapping.dispatch(current_settings())
+                >
+                    {move || if save_mapping.pending().get() { "Saving..." } else { "Save Mapping" }}
+                </button>
+            </div>
+
+            // Test the current (unsaved) settings against a real response
+            <div class="border-t border-gray-200 pt-6">
+                <h4 class="text-sm font-semibold text-gray-700 mb-2">"Test Mapping"</h4>
+                <textarea
+                    rows="6"
+                    class="w-full px-3 py-2 border border-gray-300 rounded-md font-mono text-xs focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent"
+                    placeholder="Paste a SAML response (XML or the base64 SAMLResponse value)"
+                    prop:value=move || saml_response.get()
+                    on:input=move |ev| set_saml_response.set(event_target_value(&ev))
+                ></textarea>
+                <div class="mt-2 flex justify-end">
+                    <button
+                        class="px-4 py-2 border border-gray-300 rounded-md text-gray-700 hover:bg-gray-100 text-sm disabled:cursor-not-allowed"
+                        prop:disabled=move || run_preview.pending().get() || saml_response.get().trim().is_empty()
+                        on:click=move |_| run_preview.dispatch((current_settings(), saml_response.get()))
+                    >
+                        {move || if run_preview.pending().get() { "Testing..." } else { "Test Mapping" }}
+                    </button>
+                </div>
+
+                {move || preview.get().map(|preview| view! { <SamlMappingPreviewTable preview=preview /> })}
+            </div>
+        </div>
+    }
+}
+
+#[component]
+fn SamlMappingPreviewTable(preview: SamlMappingPreview) -> impl IntoView {
+    let response = preview.response;
+    let role_source = match preview.matched_role_value {
+        Some(value) => format!("{} (matched \"{}\")", response.role, value),
+        None => format!("{} (no rule matched)", response.role),
+    };
+    let fields = vec![
+        ("NameID", Some(response.name_id.clone())),
+        ("Email", response.email.clone()),
+        ("First Name", response.first_name.clone()),
+        ("Last Name", response.last_name.clone()),
+        ("Display Name", response.display_name.clone()),
+        ("School", response.school.clone()),
+        ("Employee ID", response.employee_id.clone()),
+        ("Role", Some(role_source)),
+    ];
+    let mut attributes: Vec<(String, Vec<String>)> = response.attributes.into_iter().collect();
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    view! {
+        <div class="mt-4 space-y-4">
+            {if preview.validation_passed {
+                view! {
+                    <div class="bg-green-100 border border-green-400 text-green-700 px-4 py-3 rounded text-sm">
+                        "Signature and conditions are valid for this institution."
+                    </div>
+                }
+            } else {
+                view! {
+                    <div class="bg-yellow-100 border border-yellow-400 text-yellow-800 px-4 py-3 rounded text-sm">
+                        {format!(
+                            "This response would be rejected at login: {}",
+                            preview.validation_message.unwrap_or_default()
+                        )}
+                    </div>
+                }
+            }}
+
+            <table class="min-w-full divide-y divide-gray-200 text-sm">
+                <tbody class="divide-y divide-gray-100">
+                    {fields.into_iter().map(|(label, value)| view! {
+                        <tr>
+                            <td class="py-2 pr-4 font-medium text-gray-700 whitespace-nowrap">{label}</td>
+                            <td class="py-2 text-gray-900 font-mono">
+                                {value.unwrap_or_else(|| "—".to_string())}
+                            </td>
+                        </tr>
+                    }).collect_view()}
+                </tbody>
+            </table>
+
+            <div>
+                <h5 class="text-xs font-semibold text-gray-500 uppercase mb-1">"Attributes in response"</h5>
+                <ul class="text-xs font-mono text-gray-700 space-y-1">
+                    {attributes.into_iter().map(|(name, values)| view! {
+                        <li>{format!("{} = {}", name, values.join(", "))}</li>
+                    }).collect_view()}
+                </ul>
+            </div>
+        </div>
+    }
+}
@@ -1,7 +1,10 @@
+pub mod api_tokens_panel;
 pub mod authorization_components;
 pub mod enhanced_login_form;
 pub mod login_form;
+pub mod mfa_components;
 pub mod reset_password_components;
 pub mod saml_login_form;
 pub mod server_auth_components;
+pub mod sessions_panel;
 pub mod test_saml;
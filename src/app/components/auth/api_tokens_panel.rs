@@ -0,0 +1,195 @@
+use crate::app::models::auth::{ApiToken, NewApiToken};
+use crate::app::models::permission::TokenScope;
+use crate::app::server_functions::api_tokens::{
+    create_api_token, get_my_api_tokens, revoke_my_api_token,
+};
+use leptos::*;
+use strum::IntoEnumIterator;
+
+const EXPIRY_OPTIONS: [(i64, &str); 4] = [
+    (7, "7 days"),
+    (30, "30 days"),
+    (90, "90 days"),
+    (365, "1 year"),
+];
+
+// "API Tokens" section of My Account: personal access tokens for scripts
+#[component]
+pub fn ApiTokensPanel() -> impl IntoView {
+    let tokens = create_resource(|| (), |_| async move { get_my_api_tokens().await });
+    let (name, set_name) = create_signal("".to_string());
+    let (scopes, set_scopes) = create_signal::<Vec<TokenScope>>(Vec::new());
+    let (expires_in_days, set_expires_in_days) = create_signal(90i64);
+    let (new_token, set_new_token) = create_signal::<Option<String>>(None);
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let create = create_action(
+        move |(name, scopes, days): &(String, Vec<TokenScope>, i64)| {
+            let (name, scopes, days) = (name.clone(), scopes.clone(), *days);
+            async move {
+                set_message.set(None);
+                match create_api_token(name, scopes, days).await {
+                    Ok(NewApiToken { token, .. }) => {
+                        set_name.set("".to_string());
+                        set_scopes.set(Vec::new());
+                        set_new_token.set(Some(token));
+                        tokens.refetch();
+                    }
+                    Err(e) => {
+                        set_message.set(Some((format!("Failed to create token: {}", e), false)))
+                    }
+                }
+            }
+        },
+    );
+
+    let revoke = create_action(move |token_id: &i64| {
+        let token_id = *token_id;
+        async move {
+            match revoke_my_api_token(token_id).await {
+                Ok(()) => set_message.set(Some(("Token revoked".to_string(), true))),
+                Err(e) => set_message.set(Some((format!("Failed to revoke token: {}", e), false))),
+            }
+            tokens.refetch();
+        }
+    });
+
+    let toggle_scope = move |scope: TokenScope, checked: bool| {
+        set_scopes.update(|scopes| {
+            scopes.retain(|s| *s != scope);
+            if checked {
+                scopes.push(scope);
+            }
+        });
+    };
+
+    let render_token = move |token: ApiToken| {
+        let token_id = token.id;
+        let expired = token.expires_at < chrono::Utc::now();
+        let details = format!(
+            "{}… · {} · expires {} · {}",
+            token.token_prefix,
+            token
+                .scopes
+                .iter()
+                .map(|scope| scope.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            token
+                .expires_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d"),
+            token
+                .last_used_at
+                .map(|at| format!(
+                    "last used {}",
+                    at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+                ))
+                .unwrap_or_else(|| "never used".to_string()),
+        );
+        view! {
+            <li class="flex items-center justify-between py-2">
+                <div>
+                    <p class="font-medium">
+                        {token.name}
+                        {expired.then(|| view! {
+                            <span class="ml-2 text-xs bg-gray-200 text-gray-700 py-1 px-2 rounded-full">"Expired"</span>
+                        })}
+                    </p>
+                    <p class="text-sm text-gray-600 font-mono">{details}</p>
+                </div>
+                <button
+                    class="text-sm text-red-600 hover:underline"
+                    on:click=move |_| revoke.dispatch(token_id)
+                >
+                    "Revoke"
+                </button>
+            </li>
+        }
+    };
+
+    view! {
+        <div>
+            <h2 class="text-xl font-semibold text-[#2E3A59] mb-2">"API Tokens"</h2>
+            <p class="mb-3 text-sm text-gray-600">
+                "Send a token as \"Authorization: Bearer <token>\" to call the API from scripts. Tokens act as you, limited to their scopes."
+            </p>
+
+            {move || message.get().map(|(msg, success)| {
+                let class = if success { "mb-2 text-sm text-green-700" } else { "mb-2 text-sm text-red-700" };
+                view! { <p class=class>{msg}</p> }
+            })}
+
+            {move || new_token.get().map(|token| view! {
+                <div class="mb-3 p-3 bg-yellow-50 border border-yellow-300 rounded-md text-gray-800">
+                    <p class="text-sm font-medium mb-2">"Copy this token now. It will not be shown again."</p>
+                    <code class="block p-2 bg-gray-100 rounded font-mono text-xs break-all">{token}</code>
+                    <button
+                        class="mt-2 text-sm text-gray-600 hover:underline"
+                        on:click=move |_| set_new_token.set(None)
+                    >
+                        "Done"
+                    </button>
+                </div>
+            })}
+
+            <div class="mb-4 space-y-2">
+                <input
+                    type="text"
+                    maxlength="100"
+                    class="w-[20rem] p-2 border border-gray-300 rounded-md"
+                    prop:value=move || name.get()
+                    on:input=move |ev| set_name.set(event_target_value(&ev))
+                    placeholder="Token name, e.g. Nightly score export"
+                />
+                <div class="space-y-1">
+                    {TokenScope::iter().map(|scope| view! {
+                        <label class="flex items-center text-sm">
+                            <input
+                                type="checkbox"
+                                class="mr-2"
+                                prop:checked=move || scopes.get().contains(&scope)
+                                on:change=move |ev| toggle_scope(scope, event_target_checked(&ev))
+                            />
+                            <span class="font-mono mr-2">{scope.to_string()}</span>
+                            <span class="text-gray-600">{scope.description()}</span>
+                        </label>
+                    }).collect_view()}
+                </div>
+                <select
+                    class="p-2 border border-gray-300 rounded-md"
+                    on:change=move |ev| {
+                        if let Ok(days) = event_target_value(&ev).parse::<i64>() {
+                            set_expires_in_days.set(days);
+                        }
+                    }
+                >
+                    {EXPIRY_OPTIONS.iter().map(|(days, label)| view! {
+                        <option value=days.to_string() selected=*days == expires_in_days.get_untracked()>
+                            {*label}
+                        </option>
+                    }).collect_view()}
+                </select>
+                <button
+                    class="block bg-[#2E3A59] text-white py-2 px-4 rounded-md hover:bg-[#DADADA] transition disabled:opacity-50"
+                    prop:disabled=move || create.pending().get()
+                    on:click=move |_| create.dispatch((name.get(), scopes.get(), expires_in_days.get()))
+                >
+                    "Create token"
+                </button>
+            </div>
+
+            <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                {move || tokens.get().map(|result| match result {
+                    Ok(list) if list.is_empty() => view! { <p class="text-sm text-gray-600">"No active tokens."</p> }.into_view(),
+                    Ok(list) => view! {
+                        <ul class="divide-y divide-gray-200">
+                            {list.into_iter().map(render_token).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! { <p class="text-red-700">{format!("Failed to load tokens: {}", e)}</p> }.into_view(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
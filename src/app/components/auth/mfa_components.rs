@@ -0,0 +1,324 @@
+use crate::app::components::auth::authorization_components::perform_post_login_redirect;
+use crate::app::models::mfa::{MfaChallenge, MfaEnrollment, MfaStatus};
+use crate::app::models::user::SessionUser;
+use crate::app::server_functions::mfa::{
+    begin_mfa_enrollment, confirm_mfa_enrollment, disable_mfa, get_mfa_status,
+    regenerate_recovery_codes, verify_mfa_login,
+};
+use leptos::*;
+
+// QR code and manual-entry secret for adding the account to an authenticator
+#[component]
+pub fn MfaEnrollmentInstructions(enrollment: MfaEnrollment) -> impl IntoView {
+    view! {
+        <div class="mb-4 text-sm text-gray-700">
+            <p class="mb-2">
+                "Scan this QR code with an authenticator app such as Google Authenticator, Microsoft Authenticator or 1Password."
+            </p>
+            <div class="flex justify-center bg-white p-2 mb-2" inner_html=enrollment.qr_svg></div>
+            <p class="mb-1">"Or enter this key manually:"</p>
+            <code class="block p-2 bg-gray-100 rounded font-mono text-xs text-gray-800 break-all">
+                {enrollment.secret}
+            </code>
+        </div>
+    }
+}
+
+#[component]
+pub fn RecoveryCodesList(codes: Vec<String>) -> impl IntoView {
+    view! {
+        <div class="p-3 bg-yellow-50 border border-yellow-300 rounded-md text-gray-800">
+            <p class="text-sm font-medium mb-2">
+                "Save these recovery codes somewhere safe. Each can be used once if you lose your authenticator, and they will not be shown again."
+            </p>
+            <ul class="grid grid-cols-2 gap-1 font-mono text-sm">
+                {codes.into_iter().map(|code| view! { <li>{code}</li> }).collect_view()}
+            </ul>
+        </div>
+    }
+}
+
+// Second login step shown after the password is accepted
+#[component]
+pub fn MfaChallengeForm(challenge: MfaChallenge, on_cancel: Callback<()>) -> impl IntoView {
+    let set_current_user = use_context::<WriteSignal<Option<SessionUser>>>().unwrap();
+    let (code, set_code) = create_signal("".to_string());
+    let (error, set_error) = create_signal::<Option<String>>(None);
+    let (recovery_codes, set_recovery_codes) = create_signal::<Vec<String>>(Vec::new());
+    let (signed_in_user, set_signed_in_user) = create_signal::<Option<SessionUser>>(None);
+    let enrolling = challenge.enrollment.is_some();
+    let token = challenge.token.clone();
+
+    let verify_action = create_action(move |code: &String| {
+        let token = token.clone();
+        let code = code.clone();
+        async move {
+            set_error.set(None);
+            match verify_mfa_login(token, code).await {
+                Ok(response) if response.success => {
+                    // Hold the redirect until newly issued recovery codes are acknowledged
+                    if response.recovery_codes.is_empty() {
+                        set_current_user.set(response.user);
+                        perform_post_login_redirect();
+                    } else {
+                        set_signed_in_user.set(response.user);
+                        set_recovery_codes.set(response.recovery_codes);
+                    }
+                }
+                Ok(response) => set_error.set(Some(response.message)),
+                Err(e) => set_error.set(Some(format!("Verification failed: {}", e))),
+            }
+        }
+    });
+
+    let finish = move |_| {
+        set_current_user.set(signed_in_user.get());
+        perform_post_login_redirect();
+    };
+
+    view! {
+        <div>
+            <h3 class="text-lg font-semibold text-gray-800 mb-2">
+                {if enrolling { "Set up two-factor authentication" } else { "Two-factor authentication" }}
+            </h3>
+
+            {move || error.get().map(|err| view! {
+                <div class="mb-4 p-3 bg-red-100 border border-red-400 text-red-700 rounded-md text-sm">{err}</div>
+            })}
+
+            {move || {
+                let codes = recovery_codes.get();
+                if !codes.is_empty() {
+                    view! {
+                        <div>
+                            <RecoveryCodesList codes=codes />
+                            <button
+                                class="w-full mt-4 p-3 bg-blue-600 text-white rounded-md hover:bg-blue-700"
+                                on:click=finish
+                            >
+                                "I have saved my recovery codes"
+                            </button>
+                        </div>
+                    }.into_view()
+                } else {
+                    view! {
+                        <form on:submit=move |ev| {
+                            ev.prevent_default();
+                            if !verify_action.pending().get() {
+                                verify_action.dispatch(code.get());
+                            }
+                        }>
+                            {challenge.enrollment.clone().map(|enrollment| view! {
+                                <p class="mb-2 text-sm text-gray-700">
+                                    "Your administrator requires two-factor authentication for this account."
+                                </p>
+                                <MfaEnrollmentInstructions enrollment=enrollment />
+                            })}
+                            <label class="block text-gray-700 text-sm font-medium mb-2" for="mfa-code">
+                                {if enrolling { "Code from your app" } else { "Authentication or recovery code" }}
+                            </label>
+                            <input
+                                id="mfa-code"
+                                type="text"
+                                autocomplete="one-time-code"
+                                class="w-full p-3 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent mb-4"
+                                prop:value=move || code.get()
+                                on:input=move |ev| set_code.set(event_target_value(&ev))
+                                placeholder="123456"
+                            />
+                            <button
+                                type="submit"
+                                class="w-full p-3 bg-blue-600 text-white rounded-md hover:bg-blue-700 disabled:bg-gray-400 disabled:cursor-not-allowed"
+                                prop:disabled=move || verify_action.pending().get()
+                            >
+                                "Verify"
+                            </button>
+                            <button
+                                type="button"
+                                class="w-full mt-2 p-2 text-sm text-gray-600 hover:underline"
+                                on:click=move |_| on_cancel(())
+                            >
+                                "Back to sign in"
+                            </button>
+                        </form>
+                    }.into_view()
+                }
+            }}
+        </div>
+    }
+}
+
+// Two-factor section of My Account: enroll, regenerate recovery codes, disable
+#[component]
+pub fn MfaSettingsPanel() -> impl IntoView {
+    let status = create_resource(|| (), |_| async move { get_mfa_status().await });
+    let (enrollment, set_enrollment) = create_signal::<Option<MfaEnrollment>>(None);
+    let (recovery_codes, set_recovery_codes) = create_signal::<Vec<String>>(Vec::new());
+    let (code, set_code) = create_signal("".to_string());
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let start_enrollment = create_action(move |_: &()| async move {
+        set_message.set(None);
+        set_recovery_codes.set(Vec::new());
+        match begin_mfa_enrollment().await {
+            Ok(pending) => set_enrollment.set(Some(pending)),
+            Err(e) => set_message.set(Some((format!("Failed to start setup: {}", e), false))),
+        }
+    });
+
+    let confirm_enrollment = create_action(move |code: &String| {
+        let code = code.clone();
+        async move {
+            match confirm_mfa_enrollment(code).await {
+                Ok(codes) => {
+                    set_enrollment.set(None);
+                    set_code.set("".to_string());
+                    set_recovery_codes.set(codes);
+                    set_message.set(Some(("Two-factor authentication is on".to_string(), true)));
+                    status.refetch();
+                }
+                Err(e) => set_message.set(Some((e.to_string(), false))),
+            }
+        }
+    });
+
+    let regenerate = create_action(move |code: &String| {
+        let code = code.clone();
+        async move {
+            match regenerate_recovery_codes(code).await {
+                Ok(codes) => {
+                    set_code.set("".to_string());
+                    set_recovery_codes.set(codes);
+                    set_message.set(Some(("New recovery codes issued".to_string(), true)));
+                    status.refetch();
+                }
+                Err(e) => set_message.set(Some((e.to_string(), false))),
+            }
+        }
+    });
+
+    let disable = create_action(move |code: &String| {
+        let code = code.clone();
+        async move {
+            match disable_mfa(code).await {
+                Ok(()) => {
+                    set_code.set("".to_string());
+                    set_recovery_codes.set(Vec::new());
+                    set_message.set(Some(("Two-factor authentication is off".to_string(), true)));
+                    status.refetch();
+                }
+                Err(e) => set_message.set(Some((e.to_string(), false))),
+            }
+        }
+    });
+
+    let code_input = move || {
+        view! {
+            <input
+                type="text"
+                autocomplete="one-time-code"
+                class="w-[20rem] p-2 border border-gray-300 rounded-md mb-2"
+                prop:value=move || code.get()
+                on:input=move |ev| set_code.set(event_target_value(&ev))
+                placeholder="Authentication code"
+            />
+        }
+    };
+
+    let render_status = move |status: MfaStatus| {
+        if status.enabled {
+            view! {
+                <div>
+                    <p class="mb-1">
+                        "Enabled"
+                        {status.enrolled_at.map(|at| format!(" since {}", at.format("%Y-%m-%d")))}
+                    </p>
+                    <p class="mb-3 text-sm">
+                        {format!("{} recovery codes remaining", status.recovery_codes_remaining)}
+                    </p>
+                    {code_input()}
+                    <div class="flex space-x-2">
+                        <button
+                            class="bg-[#2E3A59] text-white py-2 px-4 rounded-md hover:bg-[#DADADA] transition"
+                            on:click=move |_| regenerate.dispatch(code.get())
+                        >
+                            "New recovery codes"
+                        </button>
+                        {(!status.required).then(|| view! {
+                            <button
+                                class="bg-red-600 text-white py-2 px-4 rounded-md hover:bg-red-700 transition"
+                                on:click=move |_| disable.dispatch(code.get())
+                            >
+                                "Turn off"
+                            </button>
+                        })}
+                    </div>
+                    {status.required.then(|| view! {
+                        <p class="mt-2 text-sm text-gray-600">"Required for your role by school policy."</p>
+                    })}
+                </div>
+            }
+            .into_view()
+        } else if let Some(pending) = enrollment.get() {
+            view! {
+                <div>
+                    <MfaEnrollmentInstructions enrollment=pending />
+                    {code_input()}
+                    <button
+                        class="block bg-[#2E3A59] text-white py-2 px-4 rounded-md hover:bg-[#DADADA] transition"
+                        on:click=move |_| confirm_enrollment.dispatch(code.get())
+                    >
+                        "Confirm"
+                    </button>
+                </div>
+            }
+            .into_view()
+        } else {
+            view! {
+                <div>
+                    <p class="mb-3">
+                        {if status.required {
+                            "Not set up. School policy requires it for your role; you will be asked to set it up at your next sign-in."
+                        } else {
+                            "Not set up. Add an authenticator app to protect your account."
+                        }}
+                    </p>
+                    <button
+                        class="bg-[#2E3A59] text-white py-2 px-4 rounded-md hover:bg-[#DADADA] transition"
+                        on:click=move |_| start_enrollment.dispatch(())
+                    >
+                        "Set up two-factor authentication"
+                    </button>
+                </div>
+            }
+            .into_view()
+        }
+    };
+
+    view! {
+        <div>
+            <h2 class="text-xl font-semibold text-[#2E3A59] mb-2">"Two-Factor Authentication"</h2>
+
+            {move || message.get().map(|(msg, success)| {
+                let class = if success { "mb-3 text-sm text-green-700" } else { "mb-3 text-sm text-red-700" };
+                view! { <p class=class>{msg}</p> }
+            })}
+
+            <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                {move || status.get().map(|result| match result {
+                    Ok(status) => render_status(status),
+                    Err(e) => view! { <p class="text-red-700">{format!("Failed to load status: {}", e)}</p> }.into_view(),
+                })}
+            </Suspense>
+
+            {move || {
+                let codes = recovery_codes.get();
+                (!codes.is_empty()).then(|| view! {
+                    <div class="mt-4">
+                        <RecoveryCodesList codes=codes />
+                    </div>
+                })
+            }}
+        </div>
+    }
+}
@@ -1,6 +1,9 @@
 use crate::app::components::auth::authorization_components::perform_post_login_redirect;
+use crate::app::components::auth::mfa_components::MfaChallengeForm;
+use crate::app::models::mfa::MfaChallenge;
 use crate::app::models::user::SessionUser;
 use crate::app::server_functions::auth::{get_current_user, login};
+use crate::app::server_functions::oidc_auth::{get_oidc_institutions, OidcInstitution};
 use crate::app::server_functions::saml_auth::{
     get_saml_institutions, initiate_saml_login, SamlInstitution,
 };
@@ -16,7 +19,10 @@ pub fn SamlLoginForm() -> impl IntoView {
     let (selected_institution, set_selected_institution) = create_signal::<Option<String>>(None);
     let (saml_institutions, set_saml_institutions) =
         create_signal::<Vec<SamlInstitution>>(Vec::new());
+    let (oidc_institutions, set_oidc_institutions) =
+        create_signal::<Vec<OidcInstitution>>(Vec::new());
     let (loading, set_loading) = create_signal(false);
+    let (mfa_challenge, set_mfa_challenge) = create_signal::<Option<MfaChallenge>>(None);
 
     let set_current_user = use_context::<WriteSignal<Option<SessionUser>>>().unwrap();
     let navigate = use_navigate();
@@ -32,6 +38,14 @@ pub fn SamlLoginForm() -> impl IntoView {
                     logging::log!("Failed to load SAML institutions: {:?}", e);
                 }
             }
+            match get_oidc_institutions().await {
+                Ok(institutions) => {
+                    set_oidc_institutions.set(institutions);
+                }
+                Err(e) => {
+                    logging::log!("Failed to load OIDC institutions: {:?}", e);
+                }
+            }
         });
     });
 
@@ -55,6 +69,9 @@ pub fn SamlLoginForm() -> impl IntoView {
                     if response.success {
                         set_current_user.set(response.user);
                         perform_post_login_redirect();
+                    } else if response.mfa.is_some() {
+                        set_password.set("".to_string());
+                        set_mfa_challenge.set(response.mfa);
                     } else {
                         set_error.set(Some(response.message));
                     }
@@ -87,8 +104,15 @@ pub fn SamlLoginForm() -> impl IntoView {
                 })
             }}
 
+            {move || mfa_challenge.get().map(|challenge| view! {
+                <MfaChallengeForm
+                    challenge=challenge
+                    on_cancel=Callback::new(move |_| set_mfa_challenge.set(None))
+                />
+            })}
+
             // Login method selector
-            <div class="mb-6">
+            <div class="mb-6" class:hidden=move || mfa_challenge.get().is_some()>
                 <div class="flex border rounded-lg overflow-hidden">
                     <button
                         class=move || {
@@ -122,7 +146,9 @@ pub fn SamlLoginForm() -> impl IntoView {
             </div>
 
             {move || {
-                if login_mode.get() == "local" {
+                if mfa_challenge.get().is_some() {
+                    ().into_view()
+                } else if login_mode.get() == "local" {
                     view! {
                         <form on:submit=move |ev| {
                             ev.prevent_default();
@@ -191,11 +217,12 @@ pub fn SamlLoginForm() -> impl IntoView {
                         <div class="space-y-4">
                             {move || {
                                 let institutions_list = saml_institutions.get();
-                                if institutions_list.is_empty() {
+                                let oidc_list = oidc_institutions.get();
+                                if institutions_list.is_empty() && oidc_list.is_empty() {
                                     view! {
                                         <div class="p-4 bg-gray-100 rounded-md">
                                             <p class="text-sm text-gray-600 text-center">
-                                                "No institutions configured for SAML login."
+                                                "No institutions configured for single sign-on."
                                             </p>
                                         </div>
                                     }.into_view()
@@ -219,18 +246,22 @@ pub fn SamlLoginForm() -> impl IntoView {
                                             >
                                                 <option value="">"-- Select Institution --"</option>
                                                 {institutions_list.into_iter().map(|institution| {
-                                                    let url_safe_name = institution.to_url_safe();
+                                                    let login_url = format!("/saml/login?institution={}&relay_state=/dashboard", institution.to_url_safe());
                                                     view! {
-                                                        <option value={url_safe_name}>{institution.name}</option>
+                                                        <option value={login_url}>{institution.name}</option>
+                                                    }
+                                                }).collect::<Vec<_>>()}
+                                                {oidc_list.into_iter().map(|institution| {
+                                                    let login_url = format!("{}&redirect=/dashboard", institution.login_url());
+                                                    view! {
+                                                        <option value={login_url}>{institution.name}</option>
                                                     }
                                                 }).collect::<Vec<_>>()}
                                             </select>
 
                                             // Use Leptos navigation for SPA routing with hydration guards
                                             {move || {
-                                                if let Some(institution_id) = selected_institution.get() {
-                                                    let login_url = format!("/saml/login?institution={}&relay_state=/dashboard", institution_id);
-
+                                                if let Some(login_url) = selected_institution.get() {
                                                     view! {
                                                         <a
                                                             href={login_url}
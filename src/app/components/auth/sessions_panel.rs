@@ -0,0 +1,105 @@
+use crate::app::models::auth::ActiveSession;
+use crate::app::server_functions::sessions::{
+    get_my_sessions, revoke_my_other_sessions, revoke_my_session,
+};
+use leptos::*;
+
+// "My sessions" section of My Account: every signed-in browser, with revoke
+#[component]
+pub fn SessionsPanel() -> impl IntoView {
+    let sessions = create_resource(|| (), |_| async move { get_my_sessions().await });
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let revoke = create_action(move |session_id: &i64| {
+        let session_id = *session_id;
+        async move {
+            match revoke_my_session(session_id).await {
+                Ok(()) => set_message.set(Some(("Session signed out".to_string(), true))),
+                Err(e) => set_message.set(Some((format!("Failed to sign out: {}", e), false))),
+            }
+            sessions.refetch();
+        }
+    });
+
+    let revoke_others = create_action(move |_: &()| async move {
+        match revoke_my_other_sessions().await {
+            Ok(count) => set_message.set(Some((
+                format!("Signed out {} other session(s)", count),
+                true,
+            ))),
+            Err(e) => set_message.set(Some((format!("Failed to sign out: {}", e), false))),
+        }
+        sessions.refetch();
+    });
+
+    let render_session = move |session: ActiveSession| {
+        let session_id = session.id;
+        let details = format!(
+            "{} · {} · signed in {} · last active {}",
+            session.provider.label(),
+            session
+                .ip_address
+                .clone()
+                .unwrap_or_else(|| "unknown address".to_string()),
+            session
+                .created_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M"),
+            session
+                .last_seen_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M"),
+        );
+        view! {
+            <li class="flex items-center justify-between py-2">
+                <div>
+                    <p class="font-medium">
+                        {session.device_label()}
+                        {session.current.then(|| view! {
+                            <span class="ml-2 text-xs bg-green-100 text-green-800 py-1 px-2 rounded-full">"This device"</span>
+                        })}
+                    </p>
+                    <p class="text-sm text-gray-600">{details}</p>
+                </div>
+                {(!session.current).then(|| view! {
+                    <button
+                        class="text-sm text-red-600 hover:underline"
+                        on:click=move |_| revoke.dispatch(session_id)
+                    >
+                        "Sign out"
+                    </button>
+                })}
+            </li>
+        }
+    };
+
+    view! {
+        <div>
+            <div class="flex items-center justify-between mb-2">
+                <h2 class="text-xl font-semibold text-[#2E3A59]">"My Sessions"</h2>
+                <button
+                    class="bg-[#2E3A59] text-white py-2 px-4 rounded-md hover:bg-[#DADADA] transition"
+                    on:click=move |_| revoke_others.dispatch(())
+                >
+                    "Sign out other sessions"
+                </button>
+            </div>
+
+            {move || message.get().map(|(msg, success)| {
+                let class = if success { "mb-2 text-sm text-green-700" } else { "mb-2 text-sm text-red-700" };
+                view! { <p class=class>{msg}</p> }
+            })}
+
+            <Suspense fallback=move || view! { <p>"Loading..."</p> }>
+                {move || sessions.get().map(|result| match result {
+                    Ok(list) => view! {
+                        <ul class="divide-y divide-gray-200">
+                            {list.into_iter().map(render_session).collect_view()}
+                        </ul>
+                    }.into_view(),
+                    Err(e) => view! { <p class="text-red-700">{format!("Failed to load sessions: {}", e)}</p> }.into_view(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
@@ -1,18 +1,19 @@
 use crate::app::components::auth::enhanced_login_form::{
-    use_student_mapping_service, StudentMapping, StudentMappingData, StudentMappingService,
+    use_student_mapping_service, StudentMapping, StudentMappingService,
 };
 use crate::app::middleware::global_settings::use_settings;
+use crate::app::server_functions::globals::get_sealed_student_mapping;
+use crate::app::utils::mapping_crypto::open_mapping;
 use icondata::BsKeyFill;
 use leptos::*;
 use leptos_icons::Icon;
 
-#[cfg(feature = "hydrate")]
-use wasm_bindgen::{closure::Closure, JsCast};
-
 #[component]
 pub fn DashboardDeanonymizer() -> impl IntoView {
-    let (student_mapping_file, set_student_mapping_file) = create_signal::<Option<String>>(None);
-    let (file_upload_status, set_file_upload_status) = create_signal::<Option<String>>(None);
+    let (passphrase, set_passphrase) = create_signal(String::new());
+    let (second_passphrase, set_second_passphrase) = create_signal(String::new());
+    let (split_key, set_split_key) = create_signal(false);
+    let (unlock_status, set_unlock_status) = create_signal::<Option<String>>(None);
     let (error, set_error) = create_signal::<Option<String>>(None);
     let (is_expanded, set_is_expanded) = create_signal(false);
 
@@ -30,177 +31,67 @@ pub fn DashboardDeanonymizer() -> impl IntoView {
             .unwrap_or(0)
     };
 
-    // CSV parsing (copy from enhanced_login_form.rs)
-    let parse_csv_content = move |content: String| -> Result<StudentMappingData, String> {
-        let lines: Vec<&str> = content.lines().collect();
-
-        if lines.is_empty() {
-            return Err("Empty file".to_string());
-        }
-
-        if lines.len() < 2 {
-            return Err("File must contain header and at least one data row".to_string());
-        }
-
-        let header = lines[0];
-        let expected_headers = [
-            "app_id",
-            "original_student_id",
-            "firstname",
-            "lastname",
-            "pin",
-            "created_at",
-        ];
-        let actual_headers: Vec<&str> = header.split(',').map(|h| h.trim()).collect();
-
-        if actual_headers.len() != expected_headers.len() {
-            return Err(format!(
-                "Invalid header format: expected {} columns, found {}",
-                expected_headers.len(),
-                actual_headers.len()
-            ));
-        }
-
-        // Parse data rows
-        let data_lines = &lines[1..];
-        let mut mappings = Vec::new();
-
-        for (line_num, line) in data_lines.iter().enumerate() {
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            let parts: Vec<&str> = line.split(',').collect();
-            if parts.len() != 6 {
-                return Err(format!(
-                    "Invalid CSV format at line {}: expected 6 columns, found {}",
-                    line_num + 2,
-                    parts.len()
-                ));
-            }
-
-            let app_id = parts[0]
-                .trim()
-                .parse::<i32>()
-                .map_err(|_| format!("Invalid app_id at line {}: '{}'", line_num + 2, parts[0]))?;
-
-            let original_student_id = parts[1].trim().parse::<i32>().map_err(|_| {
-                format!(
-                    "Invalid original_student_id at line {}: '{}'",
-                    line_num + 2,
-                    parts[1]
-                )
-            })?;
-
-            if app_id <= 0 || original_student_id <= 0 {
-                return Err(format!(
-                    "Invalid ID values at line {}: IDs must be positive integers",
-                    line_num + 2
-                ));
-            }
-
-            mappings.push(StudentMapping {
-                app_id,
-                original_student_id,
-                firstname: parts[2].trim().to_string(),
-                lastname: parts[3].trim().to_string(),
-                pin: parts[4].trim().to_string(),
-                created_at: parts[5].trim().to_string(),
-            });
-        }
-
-        if mappings.is_empty() {
-            return Err("No valid data rows found in CSV file".to_string());
-        }
-
-        Ok(StudentMappingData { mappings })
-    };
-
-    // File upload handler
-    #[cfg(feature = "hydrate")]
-    let handle_file_upload = move |ev: web_sys::Event| {
-        let input = ev
-            .target()
-            .unwrap()
-            .dyn_into::<web_sys::HtmlInputElement>()
-            .unwrap();
-
-        if let Some(files) = input.files() {
-            if files.length() > 0 {
-                let file = files.get(0).unwrap();
-
-                if !file.name().ends_with(".csv") {
-                    set_error.set(Some("Please select a CSV file".to_string()));
-                    return;
+    // The mapping is fetched still encrypted and opened here in the browser;
+    // the plaintext never crosses the network and is dropped with this view
+    let unlock_action = create_action(move |passphrases: &Vec<String>| {
+        let passphrases = passphrases.clone();
+        async move {
+            set_error.set(None);
+            set_unlock_status.set(Some("Decrypting mapping...".to_string()));
+
+            let result = match get_sealed_student_mapping().await {
+                Ok(Some(sealed)) => open_mapping(&sealed, &passphrases).map(|mapping| {
+                    let created_at = sealed.created_at.to_rfc3339();
+                    mapping
+                        .into_iter()
+                        .map(|entry| StudentMapping {
+                            app_id: entry.app_id,
+                            original_student_id: entry.student_id,
+                            firstname: entry.firstname,
+                            lastname: entry.lastname,
+                            pin: entry.pin.to_string(),
+                            created_at: created_at.clone(),
+                        })
+                        .collect::<Vec<_>>()
+                }),
+                Ok(None) => Err("No encrypted student mapping is stored".to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+
+            match result {
+                Ok(mappings) => {
+                    let mapping_service = StudentMappingService::new(mappings);
+                    let count = mapping_service.get_mapping_count();
+                    set_student_mapping_service.set(Some(mapping_service));
+                    set_unlock_status.set(Some(format!(
+                        "✓ De-anonymization active ({} mappings loaded)",
+                        count
+                    )));
+                    logging::log!("Student mapping service activated with {} mappings", count);
                 }
-
-                if file.size() > 10_000_000.0 {
-                    set_error.set(Some("File too large. Maximum size is 10MB".to_string()));
-                    return;
+                Err(e) => {
+                    set_error.set(Some(e));
+                    set_unlock_status.set(None);
                 }
-
-                let file_reader = web_sys::FileReader::new().unwrap();
-                set_file_upload_status.set(Some("Processing file...".to_string()));
-                set_error.set(None);
-
-                let onload = Closure::wrap(Box::new({
-                    let set_error = set_error.clone();
-                    let set_file_upload_status = set_file_upload_status.clone();
-                    let set_student_mapping_service = set_student_mapping_service.clone();
-                    let parse_csv_content = parse_csv_content.clone();
-
-                    move |event: web_sys::Event| {
-                        let file_reader = event
-                            .target()
-                            .unwrap()
-                            .dyn_into::<web_sys::FileReader>()
-                            .unwrap();
-
-                        if let Ok(result) = file_reader.result() {
-                            if let Some(content) = result.as_string() {
-                                match parse_csv_content(content) {
-                                    Ok(mapping_data) => {
-                                        let mapping_service =
-                                            StudentMappingService::new(mapping_data.mappings);
-                                        let count = mapping_service.get_mapping_count();
-                                        set_student_mapping_service.set(Some(mapping_service));
-                                        set_error.set(None);
-                                        set_file_upload_status.set(Some(format!(
-                                            "✓ De-anonymization active ({} mappings loaded)",
-                                            count
-                                        )));
-                                        logging::log!(
-                                            "Student mapping service activated with {} mappings",
-                                            count
-                                        );
-                                    }
-                                    Err(e) => {
-                                        set_error.set(Some(format!("Invalid CSV: {}", e)));
-                                        set_file_upload_status.set(None);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }) as Box<dyn Fn(web_sys::Event)>);
-
-                file_reader.set_onload(Some(onload.as_ref().unchecked_ref()));
-                onload.forget();
-                let _ = file_reader.read_as_text(&file);
             }
+            set_passphrase.set(String::new());
+            set_second_passphrase.set(String::new());
         }
-    };
+    });
 
-    #[cfg(not(feature = "hydrate"))]
-    let handle_file_upload = move |_| {
-        set_error.set(Some(
-            "File upload not supported in this environment".to_string(),
-        ));
+    let unlock = move || {
+        let mut passphrases = vec![passphrase.get()];
+        if split_key.get() {
+            passphrases.push(second_passphrase.get());
+        }
+        unlock_action.dispatch(passphrases);
     };
 
+    on_cleanup(move || set_student_mapping_service.set(None));
+
     let clear_mapping = move |_| {
         set_student_mapping_service.set(None);
-        set_file_upload_status.set(None);
+        set_unlock_status.set(None);
         set_error.set(None);
         logging::log!("Student mapping service cleared");
     };
@@ -291,21 +182,48 @@ pub fn DashboardDeanonymizer() -> impl IntoView {
                                                     <div class="space-y-4">
                                                         <div class="bg-blue-50 border border-blue-200 rounded-md p-4">
                                                             <h4 class="text-sm font-medium text-blue-800 mb-2">
-                                                                "Upload De-anonymization File"
+                                                                "Unlock Student Mapping"
                                                             </h4>
                                                             <p class="text-sm text-blue-700 mb-3">
-                                                                "Upload a CSV file to convert anonymized student IDs back to real names and original IDs."
+                                                                "Enter the mapping passphrase to show real names and original IDs. The mapping is decrypted in this browser and only kept while this page is open."
                                                             </p>
 
-                                                            <input
-                                                                type="file"
-                                                                accept=".csv"
-                                                                class="w-full p-2 border border-gray-300 rounded-md file:mr-4 file:py-2 file:px-4 file:rounded-md file:border-0 file:text-sm file:font-medium file:bg-blue-50 file:text-blue-700 hover:file:bg-blue-100"
-                                                                on:change=handle_file_upload
-                                                            />
+                                                            <div class="space-y-2">
+                                                                <input
+                                                                    type="password"
+                                                                    class="w-full p-2 border border-gray-300 rounded-md"
+                                                                    placeholder="Mapping passphrase"
+                                                                    prop:value=move || passphrase.get()
+                                                                    on:input=move |ev| set_passphrase.set(event_target_value(&ev))
+                                                                />
+                                                                <label class="flex items-center space-x-2 text-sm text-blue-700">
+                                                                    <input
+                                                                        type="checkbox"
+                                                                        prop:checked=move || split_key.get()
+                                                                        on:change=move |ev| set_split_key.set(event_target_checked(&ev))
+                                                                    />
+                                                                    <span>"The key is split between two admins"</span>
+                                                                </label>
+                                                                <Show when=move || split_key.get()>
+                                                                    <input
+                                                                        type="password"
+                                                                        class="w-full p-2 border border-gray-300 rounded-md"
+                                                                        placeholder="Second admin's passphrase"
+                                                                        prop:value=move || second_passphrase.get()
+                                                                        on:input=move |ev| set_second_passphrase.set(event_target_value(&ev))
+                                                                    />
+                                                                </Show>
+                                                                <button
+                                                                    class="text-sm bg-blue-600 text-white px-3 py-1 rounded hover:bg-blue-700 transition-colors disabled:opacity-50"
+                                                                    prop:disabled=move || passphrase.get().is_empty() || unlock_action.pending().get()
+                                                                    on:click=move |_| unlock()
+                                                                >
+                                                                    "Unlock"
+                                                                </button>
+                                                            </div>
 
                                                             {move || {
-                                                                if let Some(status) = file_upload_status.get() {
+                                                                if let Some(status) = unlock_status.get() {
                                                                     view! {
                                                                         <p class="text-sm text-blue-600 mt-2">{status}</p>
                                                                     }.into_view()
@@ -314,15 +232,6 @@ pub fn DashboardDeanonymizer() -> impl IntoView {
                                                                 }
                                                             }}
                                                         </div>
-
-                                                        <div class="bg-gray-50 rounded-md p-4">
-                                                            <h4 class="text-sm font-semibold text-gray-700 mb-2">
-                                                                "Required CSV Format:"
-                                                            </h4>
-                                                            <div class="bg-gray-800 text-gray-100 p-3 rounded text-xs font-mono overflow-x-auto">
-                                                                <pre>{"app_id,original_student_id,firstname,lastname,pin,created_at\n100000,12345,John,Doe,1234,2025-06-09 19:52:19.862183\n100001,52884,Thien,Le,1234,2025-06-09 19:52:19.862183"}</pre>
-                                                            </div>
-                                                        </div>
                                                     </div>
                                                 }.into_view()
                                             }
@@ -2,17 +2,26 @@ use crate::app::components::dashboard::color_utils::ColorUtils; // Import your n
 use crate::app::components::enhanced_login_form::{
     use_student_mapping_service, DeAnonymizedStudent, StudentMappingService,
 };
+use crate::app::components::student_page::custom_attribute_fields::AttributeFilterControl;
 use crate::app::middleware::global_settings::use_settings;
 use crate::app::models::score::{DeleteScoreRequest, Score};
 use crate::app::models::student::Student;
+use crate::app::models::student_attribute::AttributeFilter;
 use crate::app::models::test::{BenchmarkCategory, Test};
 use crate::app::server_functions::scores::{delete_score, get_scores};
+use crate::app::server_functions::student_attributes::{
+    get_attribute_definitions, get_student_attribute_values,
+};
 use crate::app::server_functions::students::get_students;
 use crate::app::server_functions::tests::get_tests;
 use chrono::DateTime;
 use leptos::*;
+use std::collections::HashMap;
 use std::rc::Rc;
 
+// Rows shown in the ledger, from the most recent
+const LEDGER_ROWS: usize = 4;
+
 #[component]
 pub fn ScoresLedger() -> impl IntoView {
     let navigate = leptos_router::use_navigate();
@@ -29,13 +38,7 @@ pub fn ScoresLedger() -> impl IntoView {
         || (),
         |_| async {
             match get_scores().await {
-                Ok(mut scores) => {
-                    if scores.len() > 4 {
-                        scores.truncate(4);
-                        scores.reverse();
-                    }
-                    Ok(scores)
-                }
+                Ok(scores) => Ok(scores),
                 Err(e) => {
                     log::error!("Failed to load scores: {}", e);
                     Err(ServerFnError::new("Failed to load scores"))
@@ -81,6 +84,48 @@ pub fn ScoresLedger() -> impl IntoView {
         }
     });
 
+    // Custom attribute filter, applied to the students the scores belong to
+    let (attribute_filter, set_attribute_filter) = create_signal(Option::<AttributeFilter>::None);
+    let attribute_definitions = create_local_resource(
+        || (),
+        |_| async { get_attribute_definitions().await.unwrap_or_default() },
+    );
+    let attribute_values = create_local_resource(
+        move || students_resource.get().flatten(),
+        |students| async move {
+            let student_ids = students
+                .unwrap_or_default()
+                .iter()
+                .map(|student| student.student_id)
+                .collect();
+            match get_student_attribute_values(student_ids).await {
+                Ok(values) => values,
+                Err(e) => {
+                    log::error!("Failed to fetch custom attribute values: {}", e);
+                    HashMap::new()
+                }
+            }
+        },
+    );
+    let attribute_definition_list =
+        Signal::derive(move || attribute_definitions.get().unwrap_or_default());
+
+    // The most recent scores whose students pass the attribute filter
+    let visible_scores = move |scores: Vec<Score>| -> Vec<Score> {
+        let filter = attribute_filter.get();
+        let definitions = attribute_definition_list.get();
+        let values = attribute_values.get().unwrap_or_default();
+        scores
+            .into_iter()
+            .filter(|score| {
+                filter.as_ref().map_or(true, |filter| {
+                    filter.matches(&definitions, values.get(&score.student_id))
+                })
+            })
+            .take(LEDGER_ROWS)
+            .collect()
+    };
+
     let tests_resource = create_local_resource(
         || (),
         |_| async {
@@ -110,7 +155,12 @@ pub fn ScoresLedger() -> impl IntoView {
         }}>
             <div class="flex items-center justify-between mb-2 p-2">
                 <h2 class="text-xl font-bold">Recent Scores</h2>
-                <div>
+                <div class="flex items-center gap-4">
+                    <AttributeFilterControl
+                        definitions=attribute_definition_list
+                        filter=attribute_filter
+                        set_filter=set_attribute_filter
+                    />
                     <button
                         class="text-indigo-600 hover:text-indigo-800 text-sm font-medium"
                         on:click=toggle_expanded_view
@@ -170,6 +220,7 @@ pub fn ScoresLedger() -> impl IntoView {
                                 scores_resource.get().map(|result| {
                                     match result {
                                         Ok(scores) => {
+                                            let scores = visible_scores(scores);
                                             if scores.is_empty() {
                                                 view! {
                                                     <tr>
@@ -180,7 +231,7 @@ pub fn ScoresLedger() -> impl IntoView {
                                                 }
                                                 .into_view()
                                             } else {
-                                                scores.iter().rev().map(|score| {
+                                                scores.iter().map(|score| {
                                                     let student_id = score.student_id;
                                                     let test_id = score.test_id.clone();
                                                     let test_variant = score.test_variant;
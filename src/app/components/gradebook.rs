@@ -1,5 +1,7 @@
 pub mod gradebook_side_panel;
 pub use gradebook_side_panel::*;
 
+pub mod score_import_modal;
+
 //pub mod gradebook_filtering;
 //pub use gradebook_filtering::*;
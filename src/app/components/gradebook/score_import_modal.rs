@@ -0,0 +1,210 @@
+use crate::app::components::student_page::import_validation_report::ImportValidationReport;
+use crate::app::models::import_validation::ImportValidation;
+use crate::app::models::score_import::ScoreImportFile;
+use leptos::ev::Event;
+use leptos::*;
+
+#[cfg(feature = "hydrate")]
+use {
+    crate::app::components::settings::roster_settings::read_archive,
+    crate::app::components::student_page::bulk_upload_modal::read_file_text,
+    crate::app::server_functions::score_import::{upload_score_import, validate_score_import},
+    js_sys::Array,
+    wasm_bindgen::JsCast,
+    web_sys::HtmlInputElement,
+};
+
+// Imports historical and paper-administered scores from a CSV or Excel
+// workbook, one row per score with its original date and evaluator
+#[component]
+pub fn ScoreImportModal(
+    set_show_modal: WriteSignal<bool>,
+    set_refresh_trigger: WriteSignal<i32>,
+) -> impl IntoView {
+    let (upload_status, set_upload_status) = create_signal(String::new());
+    let (is_uploading, set_is_uploading) = create_signal(false);
+    let (file_contents, set_file_contents) = create_signal::<Option<ScoreImportFile>>(None);
+
+    // A dry run of the chosen file, checked as soon as it is picked
+    let (validation, set_validation) = create_signal::<Option<ImportValidation>>(None);
+    let (is_validating, set_is_validating) = create_signal(false);
+    let (valid_rows_only, set_valid_rows_only) = create_signal(false);
+
+    let on_file_change = move |ev: Event| {
+        set_file_contents(None);
+        set_validation(None);
+        set_valid_rows_only(false);
+        set_upload_status(String::new());
+
+        #[cfg(feature = "hydrate")]
+        {
+            let file = ev
+                .target()
+                .and_then(|target| target.dyn_into::<HtmlInputElement>().ok())
+                .and_then(|input| input.files())
+                .and_then(|files| files.item(0));
+            if let Some(file) = file {
+                set_is_validating(true);
+                spawn_local(async move {
+                    let contents = if file.name().to_lowercase().ends_with(".xlsx") {
+                        read_archive(&file).await.map(ScoreImportFile::Xlsx)
+                    } else {
+                        read_file_text(file).await.map(ScoreImportFile::Csv)
+                    };
+                    match contents {
+                        Ok(contents) => {
+                            match validate_score_import(contents.clone()).await {
+                                Ok(report) => set_validation(Some(report)),
+                                Err(e) => set_upload_status(format!("Validation failed: {}", e)),
+                            }
+                            set_file_contents(Some(contents));
+                        }
+                        Err(e) => set_upload_status(format!("Reading the file failed: {}", e)),
+                    }
+                    set_is_validating(false);
+                });
+            }
+        }
+        #[cfg(not(feature = "hydrate"))]
+        let _ = (ev, set_is_validating);
+    };
+
+    let handle_upload = move |_| {
+        let Some(contents) = file_contents.get_untracked() else {
+            set_upload_status("Please select a file first".to_string());
+            return;
+        };
+        set_is_uploading(true);
+        set_upload_status(String::new());
+
+        #[cfg(feature = "hydrate")]
+        {
+            let valid_rows_only = valid_rows_only.get_untracked();
+            spawn_local(async move {
+                match upload_score_import(contents, valid_rows_only).await {
+                    Ok(count) => {
+                        set_upload_status(format!("Successfully imported {} scores", count));
+                        set_refresh_trigger.update(|count| *count += 1);
+                        set_show_modal(false);
+                    }
+                    Err(e) => set_upload_status(format!("Upload failed: {}", e)),
+                }
+                set_is_uploading(false);
+            });
+        }
+        #[cfg(not(feature = "hydrate"))]
+        {
+            let _ = (contents, set_refresh_trigger);
+            set_is_uploading(false);
+        }
+    };
+
+    let download_template = move |_| {
+        #[cfg(feature = "hydrate")]
+        {
+            let template_content = include_str!("score_import_template.csv");
+            let blob = web_sys::Blob::new_with_str_sequence(&Array::of1(&template_content.into()))
+                .unwrap_or_else(|_| web_sys::Blob::new().unwrap());
+
+            let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap_or_default();
+
+            if let Some(window) = web_sys::window() {
+                if let Some(document) = window.document() {
+                    if let Ok(a) = document.create_element("a") {
+                        let _ = a.set_attribute("href", &url);
+                        let _ = a.set_attribute("download", "score_import_template.csv");
+
+                        if let Some(html_element) = a.dyn_ref::<web_sys::HtmlElement>() {
+                            html_element.click();
+                        }
+                    }
+                }
+            }
+            let _ = web_sys::Url::revoke_object_url(&url);
+        }
+    };
+
+    view! {
+        <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+            <div class=move || if validation().is_some_and(|report| !report.is_valid()) {
+                "bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-3xl w-full max-h-[90vh] overflow-y-auto"
+            } else {
+                "bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-lg w-full"
+            }>
+                <h3 class="text-xl font-bold mb-4 text-[#2E3A59]">"Import Scores"</h3>
+
+                <input
+                    type="file"
+                    accept=".csv,.xlsx"
+                    on:change=on_file_change
+                    class="w-full p-2 border rounded mb-4"
+                />
+
+                <div class="text-sm text-gray-600 mb-4 space-y-2">
+                    <div class="flex justify-between items-start gap-4">
+                        <span>
+                            "One row per score: student_id, test (name or ID), date_administered, and either a total or points per question in columns q1, q2 and so on. Variant and evaluator are optional."
+                        </span>
+                        <button
+                            class="text-blue-500 hover:underline whitespace-nowrap"
+                            on:click=download_template
+                        >
+                            "Download Template"
+                        </button>
+                    </div>
+                    <p>
+                        "Dates are kept as given, and each score becomes the student's next attempt at the test. Excel workbooks are read from their first sheet."
+                    </p>
+                </div>
+
+                {move || validation().map(|report| view! {
+                    <ImportValidationReport
+                        report=report
+                        valid_rows_only=valid_rows_only
+                        set_valid_rows_only=set_valid_rows_only
+                    />
+                })}
+
+                {move || (!upload_status().is_empty()).then(|| {
+                    let status_class = if upload_status().contains("failed") {
+                        "text-red-500"
+                    } else {
+                        "text-green-500"
+                    };
+                    view! { <div class=format!("mt-2 text-sm {}", status_class)>{upload_status}</div> }
+                })}
+
+                <div class="flex justify-end gap-2 mt-4">
+                    <button
+                        type="button"
+                        class="px-4 py-2 text-white bg-[#F44336] rounded hover:bg-[#D32F2F]"
+                        on:click=move |_| set_show_modal(false)
+                    >
+                        "Cancel"
+                    </button>
+                    <button
+                        type="button"
+                        class="px-4 py-2 bg-[#4CAF50] text-white rounded hover:bg-[#388E3C] disabled:opacity-50"
+                        disabled=move || {
+                            file_contents().is_none()
+                                || is_uploading()
+                                || is_validating()
+                                || validation().is_some_and(|report| {
+                                    !report.is_valid() && !valid_rows_only()
+                                })
+                        }
+                        on:click=handle_upload
+                    >
+                        {move || if is_uploading() {
+                            "Importing..."
+                        } else if is_validating() {
+                            "Checking file..."
+                        } else {
+                            "Import"
+                        }}
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
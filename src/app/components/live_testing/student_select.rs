@@ -1,7 +1,10 @@
 use crate::app::components::auth::enhanced_login_form::{
     use_student_mapping_service, DeAnonymizedStudent,
 };
+use crate::app::components::student_page::student_group_filter::StudentGroupFilter;
+use crate::app::components::student_page::student_search::use_student_search_ranks;
 use crate::app::middleware::global_settings::use_settings;
+use crate::app::server_functions::student_groups::get_student_groups;
 use crate::app::server_functions::students::get_students;
 use leptos::*;
 use log;
@@ -22,14 +25,55 @@ pub fn StudentSelect(set_selected_student_id: WriteSignal<Option<i32>>) -> impl
         }
     });
 
+    // A student group can stand in as the session roster
+    let (group_filter, set_group_filter) = create_signal(None::<i32>);
+    let student_groups = create_local_resource(
+        || (),
+        |_| async move { get_student_groups().await.unwrap_or_default() },
+    );
+    let student_group_list = Signal::derive(move || student_groups.get().unwrap_or_default());
+
+    // Narrows the picker to typo-tolerant server matches, best first
+    let (search_term, set_search_term) = create_signal(String::new());
+    let search_ranks = use_student_search_ranks(search_term.into());
+
     let enhanced_students = create_memo(move |_| {
-        let students_data = get_students_action
+        let mut students_data = get_students_action
             .value()
             .get()
             .as_ref()
             .cloned()
             .unwrap_or_default();
 
+        if let Some(group) = group_filter.get().and_then(|group_id| {
+            student_group_list
+                .get()
+                .into_iter()
+                .find(|group| group.id == group_id)
+        }) {
+            let members = group.member_ids_on(chrono::Utc::now().date_naive());
+            students_data.retain(|student| members.contains(&student.student_id));
+        }
+
+        let search = search_term.get().trim().to_lowercase();
+        let ranks = search_ranks.get();
+        if !search.is_empty() {
+            students_data.retain(|student| {
+                student.student_id.to_string().contains(&search)
+                    || ranks
+                        .as_ref()
+                        .is_some_and(|ranks| ranks.contains_key(&student.student_id))
+            });
+            if let Some(ranks) = &ranks {
+                students_data.sort_by_key(|student| {
+                    ranks
+                        .get(&student.student_id)
+                        .copied()
+                        .unwrap_or(usize::MAX)
+                });
+            }
+        }
+
         if anonymization_enabled() {
             let mapping_service = student_mapping_service.get();
             students_data
@@ -57,6 +101,21 @@ pub fn StudentSelect(set_selected_student_id: WriteSignal<Option<i32>>) -> impl
     view! {
         <div class="mb-2 max-w-[20rem]">
             <label class="block text-sm font-medium mb-1">"Select Student:"</label>
+            <div class="mb-1">
+                <StudentGroupFilter
+                    groups=student_group_list
+                    selected=group_filter
+                    set_selected=set_group_filter
+                    class="w-full p-1 border rounded-md text-sm"
+                />
+            </div>
+            <input
+                type="text"
+                placeholder="Search by name or ID..."
+                class="w-full mb-1 p-1 border rounded-md text-sm"
+                prop:value=move || search_term.get()
+                on:input=move |ev| set_search_term.set(event_target_value(&ev))
+            />
             <select
                 class="w-full p-2 border rounded-md"
                 on:change=move |ev| {
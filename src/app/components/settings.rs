@@ -1,2 +1,7 @@
 pub mod bulk_enrollment_modal;
+pub mod login_security_settings;
+pub mod retention_settings;
+pub mod roster_settings;
 pub mod settings_modal;
+pub mod student_attribute_settings;
+pub mod student_encryption_settings;
@@ -1,3 +1,7 @@
+use crate::app::components::student_page::column_mapping_panel::ColumnMappingPanel;
+use crate::app::components::student_page::import_validation_report::ImportValidationReport;
+use crate::app::models::import_mapping::{ColumnMapping, ImportKind, MappingSuggestion};
+use crate::app::models::import_validation::ImportValidation;
 use crate::app::server_functions::bulk_enrollment::upload_bulk_enrollment;
 
 use leptos::ev::Event;
@@ -20,11 +24,47 @@ pub fn BulkUploadModal(
     let (is_uploading, set_is_uploading) = create_signal(false);
     let (imported_count, set_imported_count) = create_signal(0);
 
-    // Define these signals and handlers for all feature configurations
-    let (file_selected, set_file_selected) = create_signal(false);
+    // The chosen file as read, and how its columns map onto the template
+    let (raw_csv, set_raw_csv) = create_signal::<Option<String>>(None);
+    let (suggestion, set_suggestion) = create_signal::<Option<MappingSuggestion>>(None);
+    let (mapping, set_mapping) = create_signal(ColumnMapping::default());
+    let mapped_csv = create_memo(move |_| {
+        raw_csv.with(|raw| {
+            raw.as_ref()
+                .map(|raw| mapping.with(|mapping| mapping.apply(ImportKind::Enrollments, raw)))
+        })
+    });
 
+    // A dry run of the mapped file, checked again whenever the mapping changes
+    let (validation, set_validation) = create_signal::<Option<ImportValidation>>(None);
     #[cfg(feature = "hydrate")]
-    let (file, set_file) = create_signal::<Option<web_sys::File>>(None);
+    let (is_validating, set_is_validating) = create_signal(false);
+    let (valid_rows_only, set_valid_rows_only) = create_signal(false);
+
+    #[cfg(feature = "hydrate")]
+    create_effect(move |_| {
+        let Some(Ok(contents)) = mapped_csv.get() else {
+            set_validation(None);
+            set_is_validating(false);
+            return;
+        };
+        set_is_validating(true);
+        spawn_local(async move {
+            let result = crate::app::server_functions::bulk_enrollment::validate_bulk_enrollment(
+                contents.clone(),
+            )
+            .await;
+            // A newer mapping's dry run supersedes this one
+            if mapped_csv.get_untracked().and_then(Result::ok) != Some(contents) {
+                return;
+            }
+            match result {
+                Ok(report) => set_validation(Some(report)),
+                Err(e) => set_upload_status(format!("Validation failed: {}", e)),
+            }
+            set_is_validating(false);
+        });
+    });
 
     // Create the event handlers - they need to exist in all configurations
     let on_file_change = {
@@ -39,8 +79,22 @@ pub fn BulkUploadModal(
                     if let Some(files) = files {
                         if files.length() > 0 {
                             if let Some(first_file) = files.item(0) {
-                                set_file(Some(first_file));
-                                set_file_selected(true);
+                                set_raw_csv(None);
+                                set_suggestion(None);
+                                set_valid_rows_only(false);
+                                set_upload_status(String::new());
+                                spawn_local(async move {
+                                    match suggest_file_mapping(first_file).await {
+                                        Ok((contents, suggestion)) => {
+                                            set_mapping(suggestion.mapping.clone());
+                                            set_suggestion(Some(suggestion));
+                                            set_raw_csv(Some(contents));
+                                        }
+                                        Err(e) => {
+                                            set_upload_status(format!("Mapping failed: {}", e))
+                                        }
+                                    }
+                                });
                             }
                         }
                     }
@@ -51,7 +105,9 @@ pub fn BulkUploadModal(
         {
             move |_ev: Event| {
                 // Server-side fallback - just update the UI state
-                set_file_selected(true);
+                set_raw_csv(None);
+                set_suggestion(None);
+                set_validation(None);
             }
         }
     };
@@ -64,9 +120,10 @@ pub fn BulkUploadModal(
                 set_upload_status(String::new());
                 set_imported_count(0);
 
-                if let Some(selected_file) = file() {
+                if let Some(Ok(contents)) = mapped_csv.get_untracked() {
+                    let valid_rows_only = valid_rows_only();
                     spawn_local(async move {
-                        match upload_file(selected_file).await {
+                        match upload_contents(contents, valid_rows_only).await {
                             Ok(count) => {
                                 set_upload_status(format!(
                                     "Successfully imported {} enrollments",
@@ -120,7 +177,11 @@ pub fn BulkUploadModal(
 
     view! {
         <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
-            <div class="bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-md w-full">
+            <div class=move || if suggestion().is_some() {
+                "bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-3xl w-full max-h-[90vh] overflow-y-auto"
+            } else {
+                "bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-md w-full"
+            }>
                 <h3 class="text-xl font-bold mb-4">"Bulk Student Upload"</h3>
 
                 <input
@@ -154,6 +215,27 @@ pub fn BulkUploadModal(
                     }}
                 </div>
 
+                {move || suggestion().map(|suggestion| view! {
+                    <ColumnMappingPanel
+                        kind=ImportKind::Enrollments
+                        headers=suggestion.headers
+                        profile_id=suggestion.profile_id
+                        mapping=mapping
+                        set_mapping=set_mapping
+                    />
+                })}
+                {move || mapped_csv().and_then(Result::err).map(|e| view! {
+                    <div class="mb-4 text-sm text-red-500">{e}</div>
+                })}
+
+                {move || validation().map(|report| view! {
+                    <ImportValidationReport
+                        report=report
+                        valid_rows_only=valid_rows_only
+                        set_valid_rows_only=set_valid_rows_only
+                    />
+                })}
+
                 {move || {
                     if !upload_status().is_empty() {
                         let status_class = if upload_status().contains("failed") {
@@ -194,10 +276,23 @@ pub fn BulkUploadModal(
                                     <button
                                         type="button"
                                         class="px-4 py-2 bg-[#4CAF50] text-white rounded hover:bg-[#388E3C]"
-                                        disabled=move || !file_selected() || is_uploading()
+                                        disabled=move || {
+                                            !mapped_csv().is_some_and(|mapped| mapped.is_ok())
+                                                || is_uploading()
+                                                || is_validating()
+                                                || validation().is_some_and(|report| {
+                                                    !report.is_valid() && !valid_rows_only()
+                                                })
+                                        }
                                         on:click=handle_upload
                                     >
-                                        {move || if is_uploading() { "Uploading..." } else { "Upload" }}
+                                        {move || if is_uploading() {
+                                            "Uploading..."
+                                        } else if is_validating() {
+                                            "Checking file..."
+                                        } else {
+                                            "Upload"
+                                        }}
                                     </button>
                                 }.into_view()
                             } else {
@@ -220,7 +315,7 @@ pub fn BulkUploadModal(
 }
 
 #[cfg(feature = "hydrate")]
-async fn upload_file(file: web_sys::File) -> Result<usize, String> {
+async fn read_file_text(file: web_sys::File) -> Result<String, String> {
     // Create a future that resolves when the file is read
     let file_content_future =
         wasm_bindgen_futures::JsFuture::from(js_sys::Promise::new(&mut |resolve, reject| {
@@ -259,12 +354,31 @@ async fn upload_file(file: web_sys::File) -> Result<usize, String> {
         .map_err(|e| format!("Error reading file: {:?}", e))?;
 
     // Extract the file content as a string
-    let file_contents = file_content_future
+    file_content_future
         .as_string()
-        .ok_or_else(|| "Failed to convert file content to string".to_string())?;
+        .ok_or_else(|| "Failed to convert file content to string".to_string())
+}
 
-    // Call the server function with the file contents
-    crate::app::server_functions::bulk_enrollment::upload_bulk_enrollment(file_contents)
-        .await
-        .map_err(|e| e.to_string())
+// Reads the file and the server's suggested mapping of its columns
+#[cfg(feature = "hydrate")]
+async fn suggest_file_mapping(file: web_sys::File) -> Result<(String, MappingSuggestion), String> {
+    let file_contents = read_file_text(file).await?;
+    let suggestion = crate::app::server_functions::import_mapping::suggest_import_mapping(
+        ImportKind::Enrollments,
+        file_contents.clone(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok((file_contents, suggestion))
+}
+
+#[cfg(feature = "hydrate")]
+async fn upload_contents(file_contents: String, valid_rows_only: bool) -> Result<usize, String> {
+    // Call the server function with the mapped file contents
+    crate::app::server_functions::bulk_enrollment::upload_bulk_enrollment(
+        file_contents,
+        valid_rows_only,
+    )
+    .await
+    .map_err(|e| e.to_string())
 }
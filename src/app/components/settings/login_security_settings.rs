@@ -0,0 +1,182 @@
+use crate::app::middleware::global_settings::try_use_settings;
+use crate::app::models::global::{LoginThrottleSettings, SessionSettings, REQUIRE_ADMIN_MFA_KEY};
+use crate::app::server_functions::globals::update_global_setting_api;
+use leptos::*;
+
+// Login throttling, session expiry and MFA policy, stored in global settings
+#[component]
+pub fn LoginSecuritySettings() -> impl IntoView {
+    let settings_context = try_use_settings();
+    let initial = settings_context
+        .map(|(settings, _)| settings.get_untracked().login_throttle)
+        .unwrap_or_default();
+
+    let (max_failures, set_max_failures) = create_signal(initial.max_failures);
+    let (lockout_minutes, set_lockout_minutes) = create_signal(initial.lockout_minutes);
+    let (backoff_base_seconds, set_backoff_base_seconds) =
+        create_signal(initial.backoff_base_seconds);
+    let (ip_max_failures, set_ip_max_failures) = create_signal(initial.ip_max_failures);
+    let initial_session = settings_context
+        .map(|(settings, _)| settings.get_untracked().session)
+        .unwrap_or_default();
+    let (idle_timeout_minutes, set_idle_timeout_minutes) =
+        create_signal(initial_session.idle_timeout_minutes);
+    let (max_lifetime_hours, set_max_lifetime_hours) =
+        create_signal(initial_session.max_lifetime_hours);
+    let (require_admin_mfa, set_require_admin_mfa) = create_signal(
+        settings_context
+            .map(|(settings, _)| settings.get_untracked().require_admin_mfa)
+            .unwrap_or(false),
+    );
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let save_action = create_action(
+        move |(throttle, session, require_mfa): &(LoginThrottleSettings, SessionSettings, bool)| {
+            let throttle = throttle.clone();
+            let session = session.clone();
+            let require_mfa = *require_mfa;
+            async move {
+                let throttle_values = [
+                    serde_json::json!(throttle.max_failures),
+                    serde_json::json!(throttle.lockout_minutes),
+                    serde_json::json!(throttle.backoff_base_seconds),
+                    serde_json::json!(throttle.ip_max_failures),
+                ];
+                let session_values = [
+                    serde_json::json!(session.idle_timeout_minutes),
+                    serde_json::json!(session.max_lifetime_hours),
+                ];
+                let values = LoginThrottleSettings::KEYS
+                    .iter()
+                    .zip(throttle_values)
+                    .chain(SessionSettings::KEYS.iter().zip(session_values))
+                    .chain([(&REQUIRE_ADMIN_MFA_KEY, serde_json::json!(require_mfa))]);
+                for (key, value) in values {
+                    if let Err(e) = update_global_setting_api(key.to_string(), value).await {
+                        set_status_message
+                            .set(Some((format!("Failed to save {}: {}", key, e), false)));
+                        return;
+                    }
+                }
+                if let Some((_, set_settings)) = settings_context {
+                    set_settings.update(|settings| {
+                        settings.login_throttle = throttle;
+                        settings.session = session;
+                        settings.require_admin_mfa = require_mfa;
+                    });
+                }
+                set_status_message.set(Some(("Sign-in security settings saved".to_string(), true)));
+            }
+        },
+    );
+
+    let number_input = move |label: &'static str,
+                             description: &'static str,
+                             value: Signal<i64>,
+                             on_input: Callback<i64>| {
+        view! {
+            <div class="flex items-center justify-between py-3 px-4 bg-gray-700 rounded border border-gray-600">
+                <div class="flex-1 pr-4">
+                    <div class="text-gray-200 font-medium">{label}</div>
+                    <div class="text-sm text-gray-400 mt-1">{description}</div>
+                </div>
+                <input
+                    type="number"
+                    min="0"
+                    class="w-24 px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100 text-right"
+                    prop:value=move || value.get().to_string()
+                    on:change=move |ev| {
+                        if let Ok(parsed) = event_target_value(&ev).parse::<i64>() {
+                            on_input(parsed.max(0));
+                        }
+                    }
+                />
+            </div>
+        }
+    };
+
+    view! {
+        <div class="space-y-2">
+            {number_input(
+                "Failed attempts before lockout",
+                "Consecutive failed passwords for one username before it is locked. 0 disables lockout.",
+                Signal::derive(move || max_failures.get() as i64),
+                Callback::new(move |value: i64| set_max_failures.set(value as i32)),
+            )}
+            {number_input(
+                "Lockout duration (minutes)",
+                "How long a locked username or IP must wait. Failures older than this are forgotten.",
+                lockout_minutes.into(),
+                Callback::new(move |value| set_lockout_minutes.set(value)),
+            )}
+            {number_input(
+                "Backoff base (seconds)",
+                "Delay after the first failure, doubling with each further failure.",
+                backoff_base_seconds.into(),
+                Callback::new(move |value| set_backoff_base_seconds.set(value)),
+            )}
+            {number_input(
+                "Failed attempts per IP before lockout",
+                "Failures from one address across all usernames. 0 disables it.",
+                Signal::derive(move || ip_max_failures.get() as i64),
+                Callback::new(move |value: i64| set_ip_max_failures.set(value as i32)),
+            )}
+            {number_input(
+                "Session idle timeout (minutes)",
+                "Sign out after this long without activity. 0 disables it. Applies to new sign-ins.",
+                idle_timeout_minutes.into(),
+                Callback::new(move |value| set_idle_timeout_minutes.set(value)),
+            )}
+            {number_input(
+                "Maximum session length (hours)",
+                "Sign out this long after signing in, however active. Applies to new sign-ins.",
+                max_lifetime_hours.into(),
+                Callback::new(move |value| set_max_lifetime_hours.set(value)),
+            )}
+
+            <div class="flex items-center justify-between py-3 px-4 bg-gray-700 rounded border border-gray-600">
+                <div class="flex-1 pr-4">
+                    <div class="text-gray-200 font-medium">"Require two-factor authentication for administrators"</div>
+                    <div class="text-sm text-gray-400 mt-1">
+                        "Admins and super admins without an authenticator must set one up at their next password sign-in."
+                    </div>
+                </div>
+                <input
+                    type="checkbox"
+                    class="h-5 w-5"
+                    prop:checked=move || require_admin_mfa.get()
+                    on:change=move |ev| set_require_admin_mfa.set(event_target_checked(&ev))
+                />
+            </div>
+
+            {move || status_message.get().map(|(message, success)| {
+                let class = if success { "text-sm text-green-400" } else { "text-sm text-red-400" };
+                view! { <div class=class>{message}</div> }
+            })}
+
+            <div class="flex justify-end">
+                <button
+                    class="px-4 py-2 bg-blue-600 hover:bg-blue-700 text-white rounded disabled:opacity-50"
+                    prop:disabled=move || save_action.pending().get()
+                    on:click=move |_| {
+                        save_action.dispatch((
+                            LoginThrottleSettings {
+                                max_failures: max_failures.get(),
+                                lockout_minutes: lockout_minutes.get(),
+                                backoff_base_seconds: backoff_base_seconds.get(),
+                                ip_max_failures: ip_max_failures.get(),
+                            },
+                            SessionSettings {
+                                idle_timeout_minutes: idle_timeout_minutes.get(),
+                                max_lifetime_hours: max_lifetime_hours.get(),
+                            },
+                            require_admin_mfa.get(),
+                        ));
+                    }
+                >
+                    "Save"
+                </button>
+            </div>
+        </div>
+    }
+}
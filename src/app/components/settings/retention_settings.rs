@@ -0,0 +1,372 @@
+use crate::app::middleware::global_settings::try_use_settings;
+use crate::app::models::enrollment::EnrollmentStatus;
+use crate::app::models::retention::{
+    RetentionPolicy, RetentionPreview, SaveRetentionPolicyRequest, RETENTION_PURGE_ENABLED_KEY,
+};
+use crate::app::server_functions::globals::update_global_setting_api;
+use crate::app::server_functions::retention::{
+    delete_retention_policy, get_retention_policies, preview_retention, run_retention_purge,
+    save_retention_policy,
+};
+use leptos::*;
+use strum::IntoEnumIterator;
+
+fn status_label(status: &EnrollmentStatus) -> String {
+    let status = status.to_string();
+    let mut chars = status.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+// Retention policies, the scheduled purge switch, and a preview of what a
+// purge would remove before running one
+#[component]
+pub fn RetentionSettings() -> impl IntoView {
+    let settings_context = try_use_settings();
+    let (purge_enabled, set_purge_enabled) = create_signal(
+        settings_context
+            .map(|(settings, _)| settings.get_untracked().retention_purge_enabled)
+            .unwrap_or(false),
+    );
+    let (refresh, set_refresh) = create_signal(0);
+    let policies = create_resource(
+        move || refresh.get(),
+        |_| async move { get_retention_policies().await },
+    );
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+    let (preview, set_preview) = create_signal::<Option<RetentionPreview>>(None);
+
+    // Policy form; editing_id is None for a new policy
+    let (editing_id, set_editing_id) = create_signal::<Option<i32>>(None);
+    let (name, set_name) = create_signal(String::new());
+    let (statuses, set_statuses) = create_signal::<Vec<EnrollmentStatus>>(vec![
+        EnrollmentStatus::Graduated,
+        EnrollmentStatus::Transferred,
+        EnrollmentStatus::Dropped,
+    ]);
+    let (retain_years, set_retain_years) = create_signal(5);
+    let (keep_scores, set_keep_scores) = create_signal(true);
+    let (policy_enabled, set_policy_enabled) = create_signal(true);
+
+    let edit_policy = move |policy: RetentionPolicy| {
+        set_editing_id.set(Some(policy.id));
+        set_name.set(policy.name);
+        set_statuses.set(policy.statuses);
+        set_retain_years.set(policy.retain_years);
+        set_keep_scores.set(policy.keep_deidentified_scores);
+        set_policy_enabled.set(policy.enabled);
+    };
+    let reset_form = move || {
+        set_editing_id.set(None);
+        set_name.set(String::new());
+    };
+
+    let save_action = create_action(move |request: &SaveRetentionPolicyRequest| {
+        let request = request.clone();
+        async move {
+            match save_retention_policy(request).await {
+                Ok(policy) => {
+                    set_status_message
+                        .set(Some((format!("Saved policy \"{}\"", policy.name), true)));
+                    reset_form();
+                    set_preview.set(None);
+                    set_refresh.update(|count| *count += 1);
+                }
+                Err(e) => {
+                    set_status_message.set(Some((format!("Failed to save policy: {}", e), false)))
+                }
+            }
+        }
+    });
+
+    let delete_action = create_action(move |id: &i32| {
+        let id = *id;
+        async move {
+            match delete_retention_policy(id).await {
+                Ok(policy) => {
+                    set_status_message
+                        .set(Some((format!("Deleted policy \"{}\"", policy.name), true)));
+                    set_preview.set(None);
+                    set_refresh.update(|count| *count += 1);
+                }
+                Err(e) => {
+                    set_status_message.set(Some((format!("Failed to delete policy: {}", e), false)))
+                }
+            }
+        }
+    });
+
+    let schedule_action = create_action(move |enabled: &bool| {
+        let enabled = *enabled;
+        async move {
+            match update_global_setting_api(
+                RETENTION_PURGE_ENABLED_KEY.to_string(),
+                serde_json::json!(enabled),
+            )
+            .await
+            {
+                Ok(_) => {
+                    set_purge_enabled.set(enabled);
+                    if let Some((_, set_settings)) = settings_context {
+                        set_settings.update(|settings| settings.retention_purge_enabled = enabled);
+                    }
+                }
+                Err(e) => {
+                    set_status_message.set(Some((format!("Failed to save schedule: {}", e), false)))
+                }
+            }
+        }
+    });
+
+    let preview_action = create_action(move |_: &()| async move {
+        match preview_retention().await {
+            Ok(result) => set_preview.set(Some(result)),
+            Err(e) => set_status_message.set(Some((format!("Failed to preview: {}", e), false))),
+        }
+    });
+
+    let purge_action = create_action(move |_: &()| async move {
+        match run_retention_purge().await {
+            Ok(report) => {
+                set_status_message.set(Some((report.summary(), true)));
+                set_preview.set(None);
+            }
+            Err(e) => set_status_message.set(Some((format!("Purge failed: {}", e), false))),
+        }
+    });
+
+    view! {
+        <div class="space-y-4">
+            <div class="flex items-center justify-between py-3 px-4 bg-gray-700 rounded border border-gray-600">
+                <div class="flex-1 pr-4">
+                    <div class="text-gray-200 font-medium">"Purge expired students daily"</div>
+                    <div class="text-sm text-gray-400 mt-1">
+                        "Runs the enabled policies once a day. Students on legal hold are never purged."
+                    </div>
+                </div>
+                <input
+                    type="checkbox"
+                    class="h-5 w-5"
+                    prop:checked=move || purge_enabled.get()
+                    prop:disabled=move || schedule_action.pending().get()
+                    on:change=move |ev| schedule_action.dispatch(event_target_checked(&ev))
+                />
+            </div>
+
+            <Suspense fallback=|| view! { <div class="text-sm text-gray-400">"Loading policies..."</div> }>
+                {move || policies.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="text-sm text-red-400">{format!("Failed to load policies: {}", e)}</div>
+                    }.into_view(),
+                    Ok(policies) if policies.is_empty() => view! {
+                        <div class="text-sm text-gray-400">"No retention policies yet."</div>
+                    }.into_view(),
+                    Ok(policies) => view! {
+                        <table class="w-full text-sm text-gray-300">
+                            <thead>
+                                <tr class="text-left text-gray-400">
+                                    <th>"Policy"</th>
+                                    <th>"Statuses"</th>
+                                    <th>"Keep for"</th>
+                                    <th>"Scores"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {policies.into_iter().map(|policy| {
+                                    let id = policy.id;
+                                    let for_edit = policy.clone();
+                                    view! {
+                                        <tr>
+                                            <td>
+                                                {policy.name.clone()}
+                                                {(!policy.enabled).then(|| view! { <span class="text-gray-500">" (disabled)"</span> })}
+                                            </td>
+                                            <td>{policy.statuses.iter().map(status_label).collect::<Vec<_>>().join(", ")}</td>
+                                            <td>{format!("{} years", policy.retain_years)}</td>
+                                            <td>{if policy.keep_deidentified_scores { "Kept de-identified" } else { "Deleted" }}</td>
+                                            <td class="text-right space-x-2">
+                                                <button class="text-blue-400 hover:underline" on:click=move |_| edit_policy(for_edit.clone())>
+                                                    "Edit"
+                                                </button>
+                                                <button class="text-red-400 hover:underline" on:click=move |_| delete_action.dispatch(id)>
+                                                    "Delete"
+                                                </button>
+                                            </td>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }.into_view(),
+                })}
+            </Suspense>
+
+            <div class="py-3 px-4 bg-gray-700 rounded border border-gray-600 space-y-3">
+                <div class="text-gray-200 font-medium">
+                    {move || if editing_id.get().is_some() { "Edit policy" } else { "New policy" }}
+                </div>
+                <input
+                    type="text"
+                    placeholder="Policy name"
+                    class="w-full px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100"
+                    prop:value=move || name.get()
+                    on:input=move |ev| set_name.set(event_target_value(&ev))
+                />
+                <div class="flex flex-wrap gap-4 text-sm text-gray-300">
+                    {EnrollmentStatus::iter()
+                        .filter(|status| !matches!(status, EnrollmentStatus::Active))
+                        .map(|status| {
+                            let label = status_label(&status);
+                            let checked_status = status.clone();
+                            view! {
+                                <label class="flex items-center gap-1">
+                                    <input
+                                        type="checkbox"
+                                        prop:checked=move || statuses.get().contains(&checked_status)
+                                        on:change=move |ev| {
+                                            let status = status.clone();
+                                            set_statuses.update(|statuses| {
+                                                statuses.retain(|s| *s != status);
+                                                if event_target_checked(&ev) {
+                                                    statuses.push(status);
+                                                }
+                                            });
+                                        }
+                                    />
+                                    {label}
+                                </label>
+                            }
+                        })
+                        .collect_view()}
+                </div>
+                <div class="flex flex-wrap items-center gap-4 text-sm text-gray-300">
+                    <label class="flex items-center gap-2">
+                        "Purge after"
+                        <input
+                            type="number"
+                            min="0"
+                            class="w-20 px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100 text-right"
+                            prop:value=move || retain_years.get().to_string()
+                            on:change=move |ev| {
+                                if let Ok(years) = event_target_value(&ev).parse::<i32>() {
+                                    set_retain_years.set(years.max(0));
+                                }
+                            }
+                        />
+                        "years since the last enrollment ended"
+                    </label>
+                    <label class="flex items-center gap-1">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || keep_scores.get()
+                            on:change=move |ev| set_keep_scores.set(event_target_checked(&ev))
+                        />
+                        "Keep de-identified scores"
+                    </label>
+                    <label class="flex items-center gap-1">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || policy_enabled.get()
+                            on:change=move |ev| set_policy_enabled.set(event_target_checked(&ev))
+                        />
+                        "Enabled"
+                    </label>
+                </div>
+                <div class="flex justify-end gap-2">
+                    <Show when=move || editing_id.get().is_some()>
+                        <button class="px-3 py-1.5 text-sm text-gray-300 hover:text-white" on:click=move |_| reset_form()>
+                            "Cancel"
+                        </button>
+                    </Show>
+                    <button
+                        class="px-3 py-1.5 bg-blue-600 hover:bg-blue-700 text-white text-sm rounded disabled:opacity-50"
+                        prop:disabled=move || save_action.pending().get()
+                        on:click=move |_| {
+                            save_action.dispatch(SaveRetentionPolicyRequest {
+                                id: editing_id.get(),
+                                name: name.get(),
+                                statuses: statuses.get(),
+                                retain_years: retain_years.get(),
+                                keep_deidentified_scores: keep_scores.get(),
+                                enabled: policy_enabled.get(),
+                            });
+                        }
+                    >
+                        "Save policy"
+                    </button>
+                </div>
+            </div>
+
+            <div class="flex justify-end gap-2">
+                <button
+                    class="px-3 py-1.5 bg-gray-600 hover:bg-gray-500 text-white text-sm rounded disabled:opacity-50"
+                    prop:disabled=move || preview_action.pending().get()
+                    on:click=move |_| preview_action.dispatch(())
+                >
+                    "Preview affected students"
+                </button>
+            </div>
+
+            {move || preview.get().map(|preview| {
+                let count = preview.candidates.len();
+                view! {
+                    <div class="py-3 px-4 bg-gray-700 rounded border border-gray-600 space-y-2">
+                        <div class="text-gray-200 font-medium">
+                            {format!("{} students would be purged; {} on legal hold would be kept.", count, preview.held_students)}
+                        </div>
+                        <table class="w-full text-sm text-gray-300">
+                            <thead>
+                                <tr class="text-left text-gray-400">
+                                    <th>"Student"</th>
+                                    <th>"Last status"</th>
+                                    <th>"Left"</th>
+                                    <th>"Policy"</th>
+                                    <th>"Scores"</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {preview.candidates.into_iter().map(|candidate| view! {
+                                    <tr>
+                                        <td>{format!(
+                                            "{} {} ({})",
+                                            candidate.firstname.unwrap_or_default(),
+                                            candidate.lastname.unwrap_or_default(),
+                                            candidate.student_id
+                                        )}</td>
+                                        <td>{status_label(&candidate.last_status)}</td>
+                                        <td>{candidate.last_active.to_string()}</td>
+                                        <td>{candidate.policy_name}</td>
+                                        <td>{format!(
+                                            "{} {}",
+                                            candidate.scores,
+                                            if candidate.keep_deidentified_scores { "kept de-identified" } else { "deleted" }
+                                        )}</td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                        <Show when=move || { count > 0 }>
+                            <div class="flex justify-end">
+                                <button
+                                    class="px-3 py-1.5 bg-red-600 hover:bg-red-700 text-white text-sm rounded disabled:opacity-50"
+                                    prop:disabled=move || purge_action.pending().get()
+                                    on:click=move |_| purge_action.dispatch(())
+                                >
+                                    {format!("Purge {} students now", count)}
+                                </button>
+                            </div>
+                        </Show>
+                    </div>
+                }
+            })}
+
+            {move || status_message.get().map(|(message, success)| {
+                let class = if success { "text-sm text-green-400" } else { "text-sm text-red-400" };
+                view! { <div class=class>{message}</div> }
+            })}
+        </div>
+    }
+}
@@ -0,0 +1,247 @@
+use crate::app::models::oneroster::RosterImportSummary;
+use crate::app::server_functions::oneroster::{export_oneroster_bundle, import_oneroster_bundle};
+use leptos::ev::Event;
+use leptos::*;
+
+#[cfg(feature = "hydrate")]
+use {
+    base64::Engine as _,
+    js_sys::{Array, Uint8Array},
+    wasm_bindgen::{closure::Closure, JsCast},
+    web_sys::HtmlInputElement,
+};
+
+fn summary_rows(summary: &RosterImportSummary) -> Vec<(&'static str, String)> {
+    vec![
+        (
+            "Students",
+            format!(
+                "{} created, {} updated",
+                summary.students_created, summary.students_updated
+            ),
+        ),
+        (
+            "Teachers",
+            format!(
+                "{} created, {} updated",
+                summary.teachers_created, summary.teachers_updated
+            ),
+        ),
+        (
+            "Classes",
+            format!(
+                "{} created, {} updated",
+                summary.classes_created, summary.classes_updated
+            ),
+        ),
+        (
+            "Enrollments",
+            format!(
+                "{} created, {} updated",
+                summary.enrollments_created, summary.enrollments_updated
+            ),
+        ),
+        (
+            "Schools and terms",
+            format!(
+                "{} orgs, {} academic sessions",
+                summary.orgs, summary.academic_sessions
+            ),
+        ),
+        (
+            "Other users",
+            format!("{} skipped (parents, aides, ...)", summary.users_skipped),
+        ),
+    ]
+}
+
+// Import and export of OneRoster 1.2 CSV bundles, the zip files student
+// information systems exchange rosters in
+#[component]
+pub fn RosterSettings() -> impl IntoView {
+    // The chosen zip, base64 encoded, and its name
+    let (archive, set_archive) = create_signal::<Option<(String, String)>>(None);
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+    let (summary, set_summary) = create_signal::<Option<RosterImportSummary>>(None);
+
+    let on_file_change = move |ev: Event| {
+        set_archive.set(None);
+        set_summary.set(None);
+        #[cfg(feature = "hydrate")]
+        {
+            let file = ev
+                .target()
+                .and_then(|target| target.dyn_into::<HtmlInputElement>().ok())
+                .and_then(|input| input.files())
+                .and_then(|files| files.item(0));
+            if let Some(file) = file {
+                spawn_local(async move {
+                    match read_archive(&file).await {
+                        Ok(contents) => set_archive.set(Some((file.name(), contents))),
+                        Err(e) => set_status_message.set(Some((e, false))),
+                    }
+                });
+            }
+        }
+        #[cfg(not(feature = "hydrate"))]
+        let _ = ev;
+    };
+
+    let import_action = create_action(move |archive_base64: &String| {
+        let archive_base64 = archive_base64.clone();
+        async move {
+            set_status_message.set(None);
+            match import_oneroster_bundle(archive_base64).await {
+                Ok(result) => {
+                    set_status_message.set(Some((
+                        match &result.source_system {
+                            Some(source) => format!("Imported the roster from {}", source),
+                            None => "Imported the roster".to_string(),
+                        },
+                        true,
+                    )));
+                    set_summary.set(Some(result));
+                }
+                Err(e) => set_status_message.set(Some((format!("Import failed: {}", e), false))),
+            }
+        }
+    });
+
+    let export_action = create_action(move |_: &()| async move {
+        set_status_message.set(None);
+        match export_oneroster_bundle().await {
+            Ok(bundle) => {
+                #[cfg(feature = "hydrate")]
+                {
+                    let bytes = base64::engine::general_purpose::STANDARD
+                        .decode(&bundle.archive_base64)
+                        .unwrap_or_default();
+                    let blob = web_sys::Blob::new_with_u8_array_sequence(&Array::of1(
+                        &Uint8Array::from(bytes.as_slice()),
+                    ))
+                    .unwrap_or_else(|_| web_sys::Blob::new().unwrap());
+
+                    let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap_or_default();
+
+                    if let Some(window) = web_sys::window() {
+                        if let Some(document) = window.document() {
+                            if let Ok(a) = document.create_element("a") {
+                                let _ = a.set_attribute("href", &url);
+                                let _ = a.set_attribute("download", &bundle.filename);
+
+                                if let Some(html_element) = a.dyn_ref::<web_sys::HtmlElement>() {
+                                    html_element.click();
+                                }
+                            }
+                        }
+                    }
+                    let _ = web_sys::Url::revoke_object_url(&url);
+                }
+                #[cfg(not(feature = "hydrate"))]
+                let _ = bundle;
+            }
+            Err(e) => set_status_message.set(Some((format!("Export failed: {}", e), false))),
+        }
+    });
+
+    view! {
+        <div class="space-y-4">
+            <div class="py-3 px-4 bg-gray-700 rounded border border-gray-600 space-y-3">
+                <div>
+                    <div class="text-gray-200 font-medium">"Import a OneRoster bundle"</div>
+                    <div class="text-sm text-gray-400 mt-1">
+                        "A OneRoster 1.2 CSV zip from your SIS. Students, teachers, classes and enrollments are added or updated; nothing is deleted. If any row can't be imported, nothing is."
+                    </div>
+                </div>
+                <input
+                    type="file"
+                    accept=".zip"
+                    class="w-full text-sm text-gray-300"
+                    on:change=on_file_change
+                />
+                <button
+                    class="px-3 py-1 rounded bg-blue-600 text-white text-sm disabled:opacity-50"
+                    prop:disabled=move || archive.get().is_none() || import_action.pending().get()
+                    on:click=move |_| {
+                        if let Some((_, contents)) = archive.get() {
+                            import_action.dispatch(contents);
+                        }
+                    }
+                >
+                    {move || match (import_action.pending().get(), archive.get()) {
+                        (true, _) => "Importing...".to_string(),
+                        (false, Some((name, _))) => format!("Import {}", name),
+                        (false, None) => "Import".to_string(),
+                    }}
+                </button>
+            </div>
+
+            {move || summary.get().map(|summary| view! {
+                <table class="w-full text-sm text-gray-300">
+                    <tbody>
+                        {summary_rows(&summary).into_iter().map(|(label, value)| view! {
+                            <tr>
+                                <td class="text-gray-400 pr-4">{label}</td>
+                                <td>{value}</td>
+                            </tr>
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            })}
+
+            <div class="flex items-center justify-between py-3 px-4 bg-gray-700 rounded border border-gray-600">
+                <div class="flex-1 pr-4">
+                    <div class="text-gray-200 font-medium">"Export a OneRoster bundle"</div>
+                    <div class="text-sm text-gray-400 mt-1">
+                        "Every student, staff member, course and enrollment as a OneRoster 1.2 CSV zip."
+                    </div>
+                </div>
+                <button
+                    class="px-3 py-1 rounded bg-gray-600 text-gray-100 text-sm disabled:opacity-50"
+                    prop:disabled=move || export_action.pending().get()
+                    on:click=move |_| export_action.dispatch(())
+                >
+                    {move || if export_action.pending().get() { "Exporting..." } else { "Export" }}
+                </button>
+            </div>
+
+            {move || status_message.get().map(|(message, ok)| view! {
+                <div class=if ok { "text-sm text-green-400 whitespace-pre-line" } else { "text-sm text-red-400 whitespace-pre-line" }>
+                    {message}
+                </div>
+            })}
+        </div>
+    }
+}
+
+// Reads a file as base64, the form zip uploads travel to the server in
+#[cfg(feature = "hydrate")]
+pub async fn read_archive(file: &web_sys::File) -> Result<String, String> {
+    let data_url =
+        wasm_bindgen_futures::JsFuture::from(js_sys::Promise::new(&mut |resolve, reject| {
+            let reader = web_sys::FileReader::new().unwrap();
+            let reader_clone = reader.clone();
+
+            let onload_callback = Closure::once(move |_event: web_sys::ProgressEvent| {
+                match reader_clone.result() {
+                    Ok(result) => resolve.call1(&wasm_bindgen::JsValue::NULL, &result),
+                    Err(e) => reject.call1(&wasm_bindgen::JsValue::NULL, &e),
+                }
+                .unwrap();
+            });
+
+            reader.set_onload(Some(onload_callback.as_ref().unchecked_ref()));
+            let _ = reader.read_as_data_url(file);
+            onload_callback.forget();
+        }))
+        .await
+        .map_err(|e| format!("Error reading file: {:?}", e))?
+        .as_string()
+        .ok_or_else(|| "Failed to read the file".to_string())?;
+
+    // A data URL is "data:<type>;base64,<contents>"
+    data_url
+        .split_once(',')
+        .map(|(_, contents)| contents.to_string())
+        .ok_or_else(|| "Failed to read the file".to_string())
+}
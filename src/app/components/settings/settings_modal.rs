@@ -1,16 +1,24 @@
 use crate::app::components::admin::saml_admin_content::SamlAdminContent;
 use crate::app::components::settings::bulk_enrollment_modal::BulkUploadModal;
+use crate::app::components::settings::login_security_settings::LoginSecuritySettings;
+use crate::app::components::settings::retention_settings::RetentionSettings;
+use crate::app::components::settings::roster_settings::RosterSettings;
+use crate::app::components::settings::student_attribute_settings::StudentAttributeSettings;
+use crate::app::components::settings::student_encryption_settings::StudentEncryptionSettings;
 use crate::app::middleware::global_settings::{try_use_settings, try_use_settings_loading};
 use crate::app::models::global::{GlobalSetting, SettingsCache};
 use crate::app::models::setting_data::UserSettings;
+use crate::app::models::student_protection::ProtectionProgress;
 use crate::app::models::user::SessionUser;
 use crate::app::models::user::UserRole;
 use crate::app::server_functions::globals::{
-    get_global_settings, restore_student_ids_from_file, toggle_student_protection,
+    get_global_settings, get_student_protection_progress, restore_student_ids_from_file,
+    toggle_student_protection,
 };
 use crate::app::server_functions::user_settings::{
     get_user_settings, update_dark_mode, update_pinned_sidebar,
 };
+use crate::app::utils::mapping_crypto::{validate_passphrases, MIN_PASSPHRASE_LENGTH};
 use leptos::*;
 #[cfg(feature = "hydrate")]
 use {
@@ -315,6 +323,32 @@ fn SettingsContent(
                     </div>
                 }.into_view(),
 
+                "School-wide Settings" => view! {
+                    <div class="space-y-4">
+                        <Show
+                            when=move || user.get().map(|u| matches!(u.role, UserRole::Admin | UserRole::SuperAdmin)).unwrap_or(false)
+                            fallback=|| view! {
+                                <div class="bg-red-100 border border-red-400 text-red-700 px-4 py-3 rounded">
+                                    "You do not have permission to view school-wide settings."
+                                </div>
+                            }
+                        >
+                            <SettingsSection title="Sign-in Security">
+                                <LoginSecuritySettings />
+                            </SettingsSection>
+                            <SettingsSection title="Data Retention">
+                                <RetentionSettings />
+                            </SettingsSection>
+                            <SettingsSection title="Custom Student Attributes">
+                                <StudentAttributeSettings />
+                            </SettingsSection>
+                            <SettingsSection title="Student Information System">
+                                <RosterSettings />
+                            </SettingsSection>
+                        </Show>
+                    </div>
+                }.into_view(),
+
                 "Promote Students" => view! {
                     <div class="space-y-4">
                         <SettingsSection title="Promote Students">
@@ -342,6 +376,11 @@ fn SettingsContent(
                                 <StudentProtectionToggleSafe />
                             </Show>
                         </SettingsSection>
+                        <Show when=move || user.get().map(|u| u.is_super_admin()).unwrap_or(false)>
+                            <SettingsSection title="Student Data Encryption">
+                                <StudentEncryptionSettings />
+                            </SettingsSection>
+                        </Show>
                     </div>
                 }.into_view(),
 
@@ -440,12 +479,43 @@ fn ToggleSwitch(
 
 #[component]
 fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl IntoView {
-    let (show_key_modal, set_show_key_modal) = create_signal(false);
+    // Some(true) while choosing passphrases to enable, Some(false) to restore
+    let (passphrase_modal, set_passphrase_modal) = create_signal(Option::<bool>::None);
     let (show_file_modal, set_show_file_modal) = create_signal(false); // NEW
-    let (mapping_key, set_mapping_key) = create_signal(String::new());
+    let (passphrase, set_passphrase) = create_signal(String::new());
+    let (passphrase_confirm, set_passphrase_confirm) = create_signal(String::new());
+    let (split_key, set_split_key) = create_signal(false);
+    let (second_passphrase, set_second_passphrase) = create_signal(String::new());
+    let (second_confirm, set_second_confirm) = create_signal(String::new());
     let (selected_file, set_selected_file) = create_signal(Option::<String>::None); // NEW
     let (is_processing, set_is_processing) = create_signal(false);
     let (status_message, set_status_message) = create_signal(Option::<String>::None);
+    let (dry_run, set_dry_run) = create_signal(false);
+    let (progress, set_progress) = create_signal(Option::<ProtectionProgress>::None);
+
+    // Poll the server for progress while an anonymize or restore is running
+    create_effect(
+        move |handle: Option<Option<leptos_dom::helpers::IntervalHandle>>| {
+            if let Some(Some(handle)) = handle {
+                handle.clear();
+            }
+            if !is_processing.get() {
+                set_progress.set(None);
+                return None;
+            }
+            set_interval_with_handle(
+                move || {
+                    spawn_local(async move {
+                        if let Ok(current) = get_student_protection_progress().await {
+                            set_progress.set(current);
+                        }
+                    });
+                },
+                std::time::Duration::from_millis(750),
+            )
+            .ok()
+        },
+    );
 
     // Create a local signal to track the toggle state that can be updated
     let (protection_enabled, set_protection_enabled) =
@@ -456,44 +526,71 @@ fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl Int
         set_protection_enabled.set(settings.get().student_protections);
     });
 
-    let toggle_protection_action = create_action(move |(enable, key): &(bool, Option<String>)| {
-        let enable = *enable;
-        let key = key.clone();
-        async move {
-            set_is_processing.set(true);
-            set_status_message.set(None);
+    let close_passphrase_modal = move || {
+        set_passphrase_modal.set(None);
+        set_passphrase.set(String::new());
+        set_passphrase_confirm.set(String::new());
+        set_second_passphrase.set(String::new());
+        set_second_confirm.set(String::new());
+    };
 
-            match toggle_student_protection(enable, key).await {
-                Ok(message) => {
-                    set_status_message.set(Some(message.clone()));
-                    set_protection_enabled.set(enable);
+    // Passphrases as entered, or why they can't be used yet
+    let entered_passphrases = move || -> Result<Vec<String>, String> {
+        let enabling = passphrase_modal.get() == Some(true);
+        let mut passphrases = vec![passphrase.get()];
+        if split_key.get() {
+            passphrases.push(second_passphrase.get());
+        }
+        validate_passphrases(&passphrases)?;
+        if enabling
+            && (passphrase_confirm.get() != passphrases[0]
+                || (split_key.get() && second_confirm.get() != passphrases[1]))
+        {
+            return Err("Passphrases do not match their confirmation".to_string());
+        }
+        Ok(passphrases)
+    };
 
-                    if !enable && message.contains("mapping") {
-                        set_status_message.set(Some(format!("{}\n\nPlease check your downloads folder or server logs for the mapping file location.", message)));
+    let toggle_protection_action =
+        create_action(move |(enable, passphrases): &(bool, Vec<String>)| {
+            let enable = *enable;
+            let passphrases = passphrases.clone();
+            let dry_run = dry_run.get_untracked();
+            async move {
+                set_is_processing.set(true);
+                set_status_message.set(None);
+
+                match toggle_student_protection(enable, passphrases, dry_run).await {
+                    Ok(report) => {
+                        set_status_message.set(Some(report.summary()));
+                        if !report.dry_run {
+                            set_protection_enabled.set(enable);
+                        }
+                    }
+                    Err(e) => {
+                        set_status_message.set(Some(format!("Error: {}", e)));
                     }
                 }
-                Err(e) => {
-                    set_status_message.set(Some(format!("Error: {}", e)));
-                }
-            }
 
-            set_is_processing.set(false);
-            set_show_key_modal.set(false);
-            set_mapping_key.set(String::new());
-        }
-    });
+                set_is_processing.set(false);
+                close_passphrase_modal();
+            }
+        });
 
     // NEW: File upload action
     let restore_from_file_action = create_action(move |file_content: &String| {
         let file_content = file_content.clone();
+        let dry_run = dry_run.get_untracked();
         async move {
             set_is_processing.set(true);
             set_status_message.set(None);
 
-            match restore_student_ids_from_file(file_content).await {
-                Ok(message) => {
-                    set_status_message.set(Some(message));
-                    set_protection_enabled.set(false);
+            match restore_student_ids_from_file(file_content, dry_run).await {
+                Ok(report) => {
+                    set_status_message.set(Some(report.summary()));
+                    if !report.dry_run {
+                        set_protection_enabled.set(false);
+                    }
                 }
                 Err(e) => {
                     set_status_message.set(Some(format!("Error: {}", e)));
@@ -583,11 +680,7 @@ fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl Int
     };
 
     let handle_toggle = move |enable: bool| {
-        if enable {
-            toggle_protection_action.dispatch((true, None));
-        } else {
-            set_show_key_modal.set(true);
-        }
+        set_passphrase_modal.set(Some(enable));
     };
 
     view! {
@@ -600,12 +693,21 @@ fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl Int
                     <div>
                         <h3 class="text-lg font-medium text-red-200">"DANGER ZONE"</h3>
                         <p class="text-sm text-red-300 mt-1">
-                            "Student Data Protection Mode will replace all student IDs with anonymized app IDs. This operation affects the entire database and requires a mapping key to restore."
+                            "Student Data Protection Mode will replace all student IDs with anonymized app IDs. This operation affects the entire database and requires the passphrase chosen when enabling it to restore."
                         </p>
                     </div>
                 </div>
             </div>
 
+            <label class="flex items-center space-x-2 text-sm text-gray-300">
+                <input
+                    type="checkbox"
+                    prop:checked=move || dry_run.get()
+                    on:change=move |ev| set_dry_run.set(event_target_checked(&ev))
+                />
+                <span>"Dry run: check what would change without saving anything"</span>
+            </label>
+
             <ToggleSwitch
                 label="Student Data Protection Mode"
                 checked=protection_enabled
@@ -615,19 +717,26 @@ fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl Int
                 description="When enabled, replaces student IDs with anonymized app IDs"
             />
 
-            // NEW: File upload button (only show when protection is enabled)
             <Show when=move || protection_enabled.get()>
-                <div class="p-4 bg-blue-900 border border-blue-700 rounded-lg">
-                    <h4 class="text-blue-200 font-medium mb-2">"Restore from Mapping File"</h4>
-                    <p class="text-sm text-blue-300 mb-3">
-                        "Upload the CSV mapping file that was exported when protection was enabled to restore original student IDs."
+                <div class="p-4 bg-blue-900 border border-blue-700 rounded-lg space-y-3">
+                    <h4 class="text-blue-200 font-medium">"Mapping"</h4>
+                    <p class="text-sm text-blue-300">
+                        "The mapping is encrypted with the passphrase chosen when protection was enabled. Restore with that passphrase, or enter it on the dashboard to see real names for the current view only."
                     </p>
-                    <button
-                        class="px-4 py-2 bg-blue-600 hover:bg-blue-500 rounded text-white transition-colors"
-                        on:click=move |_| set_show_file_modal.set(true)
-                    >
-                        "Upload Mapping File"
-                    </button>
+                    <div class="flex space-x-3">
+                        <button
+                            class="px-4 py-2 bg-blue-600 hover:bg-blue-500 rounded text-white transition-colors"
+                            on:click=move |_| set_passphrase_modal.set(Some(false))
+                        >
+                            "Restore with Passphrase"
+                        </button>
+                        <button
+                            class="px-4 py-2 bg-gray-600 hover:bg-gray-500 rounded text-gray-100 transition-colors"
+                            on:click=move |_| set_show_file_modal.set(true)
+                        >
+                            "Upload Legacy Mapping File"
+                        </button>
+                    </div>
                 </div>
             </Show>
 
@@ -646,7 +755,112 @@ fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl Int
 
             <Show when=move || is_processing.get()>
                 <div class="p-3 bg-blue-900 border border-blue-700 rounded text-blue-200 text-sm">
-                    "Processing... This may take a few moments."
+                    {move || match progress.get() {
+                        Some(progress) if progress.students_total > 0 => format!(
+                            "{}{}... {} of {} students",
+                            if progress.dry_run { "Dry run: " } else { "" },
+                            progress.stage.label(),
+                            progress.students_done,
+                            progress.students_total
+                        ),
+                        Some(progress) => format!("{}...", progress.stage.label()),
+                        None => "Processing... This may take a few moments.".to_string(),
+                    }}
+                </div>
+            </Show>
+
+            <Show when=move || passphrase_modal.get().is_some()>
+                <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+                    <div class="bg-gray-800 p-6 rounded-lg border border-gray-700 max-w-md w-full mx-4 space-y-3">
+                        <h3 class="text-lg font-semibold text-gray-100">
+                            {move || if passphrase_modal.get() == Some(true) {
+                                "Choose Mapping Passphrase"
+                            } else {
+                                "Restore Student IDs"
+                            }}
+                        </h3>
+                        <p class="text-sm text-gray-300">
+                            {move || if passphrase_modal.get() == Some(true) {
+                                format!(
+                                    "The mapping back to real students is encrypted with this passphrase (at least {} characters). It is not stored anywhere; if it is lost, original IDs and names cannot be restored.",
+                                    MIN_PASSPHRASE_LENGTH
+                                )
+                            } else {
+                                "Enter the passphrase chosen when protection was enabled.".to_string()
+                            }}
+                        </p>
+                        <input
+                            type="password"
+                            class="w-full px-3 py-2 bg-gray-900 border border-gray-600 rounded text-gray-100"
+                            placeholder="Passphrase"
+                            prop:value=move || passphrase.get()
+                            on:input=move |ev| set_passphrase.set(event_target_value(&ev))
+                        />
+                        <Show when=move || passphrase_modal.get() == Some(true)>
+                            <input
+                                type="password"
+                                class="w-full px-3 py-2 bg-gray-900 border border-gray-600 rounded text-gray-100"
+                                placeholder="Confirm passphrase"
+                                prop:value=move || passphrase_confirm.get()
+                                on:input=move |ev| set_passphrase_confirm.set(event_target_value(&ev))
+                            />
+                        </Show>
+                        <label class="flex items-center space-x-2 text-sm text-gray-300">
+                            <input
+                                type="checkbox"
+                                prop:checked=move || split_key.get()
+                                on:change=move |ev| set_split_key.set(event_target_checked(&ev))
+                            />
+                            <span>"Split between two admins (both passphrases required)"</span>
+                        </label>
+                        <Show when=move || split_key.get()>
+                            <input
+                                type="password"
+                                class="w-full px-3 py-2 bg-gray-900 border border-gray-600 rounded text-gray-100"
+                                placeholder="Second admin's passphrase"
+                                prop:value=move || second_passphrase.get()
+                                on:input=move |ev| set_second_passphrase.set(event_target_value(&ev))
+                            />
+                            <Show when=move || passphrase_modal.get() == Some(true)>
+                                <input
+                                    type="password"
+                                    class="w-full px-3 py-2 bg-gray-900 border border-gray-600 rounded text-gray-100"
+                                    placeholder="Confirm second passphrase"
+                                    prop:value=move || second_confirm.get()
+                                    on:input=move |ev| set_second_confirm.set(event_target_value(&ev))
+                                />
+                            </Show>
+                        </Show>
+                        <p class="text-xs text-gray-400">
+                            {move || entered_passphrases().err().unwrap_or_default()}
+                        </p>
+                        <div class="flex justify-end space-x-3">
+                            <button
+                                class="px-4 py-2 bg-gray-600 hover:bg-gray-500 rounded text-gray-100 transition-colors"
+                                on:click=move |_| close_passphrase_modal()
+                            >
+                                "Cancel"
+                            </button>
+                            <button
+                                class="px-4 py-2 bg-red-600 hover:bg-red-500 rounded text-white transition-colors"
+                                prop:disabled=move || entered_passphrases().is_err() || is_processing.get()
+                                on:click=move |_| {
+                                    if let (Some(enable), Ok(passphrases)) =
+                                        (passphrase_modal.get(), entered_passphrases())
+                                    {
+                                        toggle_protection_action.dispatch((enable, passphrases));
+                                    }
+                                }
+                            >
+                                {move || match (passphrase_modal.get() == Some(true), dry_run.get()) {
+                                    (true, true) => "Check Protection",
+                                    (true, false) => "Enable Protection",
+                                    (false, true) => "Check Restore",
+                                    (false, false) => "Restore Students",
+                                }}
+                            </button>
+                        </div>
+                    </div>
                 </div>
             </Show>
 
@@ -657,7 +871,7 @@ fn StudentProtectionToggleInner(settings: ReadSignal<SettingsCache>) -> impl Int
                             "Upload Mapping File"
                         </h3>
                         <p class="text-sm text-gray-300 mb-4">
-                            "Select the CSV file that was exported when student protection was enabled (student_id_mapping.csv)."
+                            "Select a student_id_mapping.csv file exported by the old protection scripts."
                         </p>
 
                         <div class="mb-4">
@@ -0,0 +1,237 @@
+use crate::app::models::student_attribute::{
+    AttributeDefinition, AttributeKind, SaveAttributeDefinitionRequest,
+};
+use crate::app::server_functions::student_attributes::{
+    delete_attribute_definition, get_attribute_definitions, save_attribute_definition,
+};
+use leptos::*;
+use std::str::FromStr;
+
+// District-defined student fields shown on the student forms, read from
+// bulk uploads by key, and offered as gradebook and roster filters
+#[component]
+pub fn StudentAttributeSettings() -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0);
+    let definitions = create_resource(
+        move || refresh.get(),
+        |_| async move { get_attribute_definitions().await },
+    );
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+
+    // Attribute form; editing_id is None for a new attribute
+    let (editing_id, set_editing_id) = create_signal::<Option<i32>>(None);
+    let (key, set_key) = create_signal(String::new());
+    let (label, set_label) = create_signal(String::new());
+    let (kind, set_kind) = create_signal(AttributeKind::Bool);
+    let (options, set_options) = create_signal(String::new());
+    let (sort_order, set_sort_order) = create_signal(0);
+    let (active, set_active) = create_signal(true);
+
+    let edit_definition = move |definition: AttributeDefinition| {
+        set_editing_id.set(Some(definition.id));
+        set_key.set(definition.key);
+        set_label.set(definition.label);
+        set_kind.set(definition.kind);
+        set_options.set(definition.options.join(", "));
+        set_sort_order.set(definition.sort_order);
+        set_active.set(definition.active);
+    };
+    let reset_form = move || {
+        set_editing_id.set(None);
+        set_key.set(String::new());
+        set_label.set(String::new());
+        set_options.set(String::new());
+        set_active.set(true);
+    };
+
+    let save_action = create_action(move |request: &SaveAttributeDefinitionRequest| {
+        let request = request.clone();
+        async move {
+            match save_attribute_definition(request).await {
+                Ok(definition) => {
+                    set_status_message.set(Some((format!("Saved \"{}\"", definition.label), true)));
+                    reset_form();
+                    set_refresh.update(|count| *count += 1);
+                }
+                Err(e) => set_status_message
+                    .set(Some((format!("Failed to save attribute: {}", e), false))),
+            }
+        }
+    });
+
+    let delete_action = create_action(move |id: &i32| {
+        let id = *id;
+        async move {
+            match delete_attribute_definition(id).await {
+                Ok(definition) => {
+                    set_status_message.set(Some((
+                        format!("Deleted \"{}\" and its values", definition.label),
+                        true,
+                    )));
+                    set_refresh.update(|count| *count += 1);
+                }
+                Err(e) => set_status_message
+                    .set(Some((format!("Failed to delete attribute: {}", e), false))),
+            }
+        }
+    });
+
+    view! {
+        <div class="space-y-4">
+            <Suspense fallback=|| view! { <div class="text-sm text-gray-400">"Loading attributes..."</div> }>
+                {move || definitions.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="text-sm text-red-400">{format!("Failed to load attributes: {}", e)}</div>
+                    }.into_view(),
+                    Ok(definitions) if definitions.is_empty() => view! {
+                        <div class="text-sm text-gray-400">"No custom student attributes yet."</div>
+                    }.into_view(),
+                    Ok(definitions) => view! {
+                        <table class="w-full text-sm text-gray-300">
+                            <thead>
+                                <tr class="text-left text-gray-400">
+                                    <th>"Label"</th>
+                                    <th>"CSV column"</th>
+                                    <th>"Type"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {definitions.into_iter().map(|definition| {
+                                    let id = definition.id;
+                                    let for_edit = definition.clone();
+                                    let kind = if definition.kind == AttributeKind::Enum {
+                                        format!("{}: {}", definition.kind.label(), definition.options.join(", "))
+                                    } else {
+                                        definition.kind.label().to_string()
+                                    };
+                                    view! {
+                                        <tr>
+                                            <td>
+                                                {definition.label.clone()}
+                                                {(!definition.active).then(|| view! { <span class="text-gray-500">" (inactive)"</span> })}
+                                            </td>
+                                            <td class="font-mono">{definition.key.clone()}</td>
+                                            <td>{kind}</td>
+                                            <td class="text-right space-x-2">
+                                                <button class="text-blue-400 hover:underline" on:click=move |_| edit_definition(for_edit.clone())>
+                                                    "Edit"
+                                                </button>
+                                                <button class="text-red-400 hover:underline" on:click=move |_| delete_action.dispatch(id)>
+                                                    "Delete"
+                                                </button>
+                                            </td>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }.into_view(),
+                })}
+            </Suspense>
+
+            <div class="py-3 px-4 bg-gray-700 rounded border border-gray-600 space-y-3">
+                <div class="text-gray-200 font-medium">
+                    {move || if editing_id.get().is_some() { "Edit attribute" } else { "New attribute" }}
+                </div>
+                <div class="grid grid-cols-2 gap-3">
+                    <input
+                        type="text"
+                        placeholder="Label, e.g. Title I"
+                        class="px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100"
+                        prop:value=move || label.get()
+                        on:input=move |ev| set_label.set(event_target_value(&ev))
+                    />
+                    <input
+                        type="text"
+                        placeholder="CSV column, e.g. title_i"
+                        class="px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100 font-mono"
+                        prop:value=move || key.get()
+                        on:input=move |ev| set_key.set(event_target_value(&ev))
+                    />
+                </div>
+                <div class="flex flex-wrap items-center gap-4 text-sm text-gray-300">
+                    <label class="flex items-center gap-2">
+                        "Type"
+                        <select
+                            class="px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100"
+                            prop:disabled=move || editing_id.get().is_some()
+                            on:change=move |ev| {
+                                if let Ok(kind) = AttributeKind::from_str(&event_target_value(&ev)) {
+                                    set_kind.set(kind);
+                                }
+                            }
+                        >
+                            {AttributeKind::ALL.into_iter().map(|option| view! {
+                                <option value=option.as_str() selected=move || kind.get() == option>{option.label()}</option>
+                            }).collect_view()}
+                        </select>
+                    </label>
+                    <label class="flex items-center gap-2">
+                        "Order"
+                        <input
+                            type="number"
+                            class="w-20 px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100 text-right"
+                            prop:value=move || sort_order.get().to_string()
+                            on:change=move |ev| {
+                                if let Ok(order) = event_target_value(&ev).parse::<i32>() {
+                                    set_sort_order.set(order);
+                                }
+                            }
+                        />
+                    </label>
+                    <label class="flex items-center gap-1">
+                        <input
+                            type="checkbox"
+                            prop:checked=move || active.get()
+                            on:change=move |ev| set_active.set(event_target_checked(&ev))
+                        />
+                        "Active"
+                    </label>
+                </div>
+                <Show when=move || kind.get() == AttributeKind::Enum>
+                    <input
+                        type="text"
+                        placeholder="Choices, separated by commas"
+                        class="w-full px-2 py-1 rounded bg-gray-800 border border-gray-600 text-gray-100"
+                        prop:value=move || options.get()
+                        on:input=move |ev| set_options.set(event_target_value(&ev))
+                    />
+                </Show>
+                <div class="flex justify-end gap-2">
+                    <Show when=move || editing_id.get().is_some()>
+                        <button class="px-3 py-1.5 text-sm text-gray-300 hover:text-white" on:click=move |_| reset_form()>
+                            "Cancel"
+                        </button>
+                    </Show>
+                    <button
+                        class="px-3 py-1.5 bg-blue-600 hover:bg-blue-700 text-white text-sm rounded disabled:opacity-50"
+                        prop:disabled=move || save_action.pending().get()
+                        on:click=move |_| {
+                            let options = if kind.get() == AttributeKind::Enum {
+                                options.get().split(',').map(|option| option.trim().to_string()).collect()
+                            } else {
+                                Vec::new()
+                            };
+                            save_action.dispatch(SaveAttributeDefinitionRequest {
+                                id: editing_id.get(),
+                                key: key.get(),
+                                label: label.get(),
+                                kind: kind.get(),
+                                options,
+                                sort_order: sort_order.get(),
+                                active: active.get(),
+                            });
+                        }
+                    >
+                        "Save attribute"
+                    </button>
+                </div>
+            </div>
+
+            {move || status_message.get().map(|(message, ok)| view! {
+                <div class=if ok { "text-sm text-green-400" } else { "text-sm text-red-400" }>{message}</div>
+            })}
+        </div>
+    }
+}
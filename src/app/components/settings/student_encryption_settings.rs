@@ -0,0 +1,122 @@
+use crate::app::models::student_encryption::KeyRotationReport;
+use crate::app::server_functions::student_encryption::{
+    encrypt_pending_students, get_student_encryption_status, rewrap_student_data_keys,
+    rotate_student_data_key,
+};
+use leptos::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum KeyOperation {
+    EncryptPending,
+    RotateDataKey,
+    RewrapDataKeys,
+}
+
+// Key provider state, data keys and rotation for student PII encryption
+#[component]
+pub fn StudentEncryptionSettings() -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0);
+    let status = create_resource(
+        move || refresh.get(),
+        |_| async move { get_student_encryption_status().await },
+    );
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let key_action = create_action(move |operation: &KeyOperation| {
+        let operation = *operation;
+        async move {
+            let result: Result<KeyRotationReport, ServerFnError> = match operation {
+                KeyOperation::EncryptPending => encrypt_pending_students().await,
+                KeyOperation::RotateDataKey => rotate_student_data_key().await,
+                KeyOperation::RewrapDataKeys => rewrap_student_data_keys().await,
+            };
+            match result {
+                Ok(report) => set_status_message.set(Some((report.summary(), true))),
+                Err(e) => set_status_message.set(Some((format!("Failed: {}", e), false))),
+            }
+            set_refresh.update(|count| *count += 1);
+        }
+    });
+
+    let action_button = move |label: &'static str, operation: KeyOperation| {
+        view! {
+            <button
+                class="px-3 py-1.5 bg-blue-600 hover:bg-blue-700 text-white text-sm rounded disabled:opacity-50"
+                prop:disabled=move || key_action.pending().get()
+                on:click=move |_| key_action.dispatch(operation)
+            >
+                {label}
+            </button>
+        }
+    };
+
+    view! {
+        <div class="space-y-3">
+            <Suspense fallback=|| view! { <div class="text-sm text-gray-400">"Loading..."</div> }>
+                {move || status.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="text-sm text-red-400">{format!("Failed to load encryption status: {}", e)}</div>
+                    }.into_view(),
+                    Ok(status) => {
+                        let pending = status.pending_students();
+                        let provider = match (&status.provider, &status.provider_error) {
+                            (_, Some(error)) => format!("Key provider error: {}", error),
+                            (Some(provider), None) => format!(
+                                "Key provider: {} (master key {})",
+                                provider,
+                                status.active_master_key_id.clone().unwrap_or_default()
+                            ),
+                            (None, None) => "No key provider configured; student PII is stored in plaintext.".to_string(),
+                        };
+                        let configured = status.provider.is_some() && status.provider_error.is_none();
+                        view! {
+                            <div class="py-3 px-4 bg-gray-700 rounded border border-gray-600 space-y-2">
+                                <div class="text-gray-200 font-medium">{provider}</div>
+                                <div class="text-sm text-gray-400">
+                                    {format!(
+                                        "{} students not yet encrypted, {} under a retired data key.",
+                                        status.plaintext_students, status.stale_students
+                                    )}
+                                </div>
+                                <table class="w-full text-sm text-gray-300">
+                                    <thead>
+                                        <tr class="text-left text-gray-400">
+                                            <th>"Data key"</th>
+                                            <th>"Master key"</th>
+                                            <th>"Students"</th>
+                                            <th>"Created"</th>
+                                        </tr>
+                                    </thead>
+                                    <tbody>
+                                        {status.keys.into_iter().map(|key| view! {
+                                            <tr>
+                                                <td>{format!("#{}{}", key.id, if key.active { " (active)" } else { "" })}</td>
+                                                <td>{key.master_key_id}</td>
+                                                <td>{key.students}</td>
+                                                <td>{key.created_at.format("%Y-%m-%d").to_string()}</td>
+                                            </tr>
+                                        }).collect_view()}
+                                    </tbody>
+                                </table>
+                            </div>
+                            <Show when=move || configured>
+                                <div class="flex flex-wrap gap-2 justify-end">
+                                    <Show when=move || { pending > 0 }>
+                                        {action_button("Encrypt pending students", KeyOperation::EncryptPending)}
+                                    </Show>
+                                    {action_button("Rotate data key", KeyOperation::RotateDataKey)}
+                                    {action_button("Re-wrap under current master key", KeyOperation::RewrapDataKeys)}
+                                </div>
+                            </Show>
+                        }.into_view()
+                    }
+                })}
+            </Suspense>
+
+            {move || status_message.get().map(|(message, success)| {
+                let class = if success { "text-sm text-green-400" } else { "text-sm text-red-400" };
+                view! { <div class=class>{message}</div> }
+            })}
+        </div>
+    }
+}
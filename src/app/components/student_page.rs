@@ -1,7 +1,17 @@
 pub mod add_student_form;
 pub mod bulk_upload_modal;
+pub mod column_mapping_panel;
+pub mod custom_attribute_fields;
 pub mod delete_student_confirmation;
+pub mod duplicate_students_modal;
+pub mod flag_history_panel;
+pub mod import_validation_report;
+pub mod legal_hold_panel;
+pub mod roster_sync_preview;
 pub mod student_details;
+pub mod student_group_filter;
+pub mod student_groups_modal;
+pub mod student_search;
 pub mod student_search_filter;
 pub mod student_table;
 pub mod update_student_form;
@@ -1,5 +1,7 @@
+use crate::app::components::student_page::custom_attribute_fields::CustomAttributeFields;
 use crate::app::models::student::InterventionEnum;
 use crate::app::models::student::{AddStudentRequest, ESLEnum, GenderEnum, GradeEnum};
+use crate::app::models::student_attribute::AttributeValues;
 use crate::app::models::EmployeeRole;
 use chrono::NaiveDate;
 use leptos::ev::SubmitEvent;
@@ -46,6 +48,7 @@ pub fn AddStudentForm(
     let (new_eye_glasses, set_new_eye_glasses) = create_signal(false);
     let (new_notes, set_new_notes) = create_signal(String::new());
     let (new_pin, set_new_pin) = create_signal(String::new());
+    let (new_attributes, set_new_attributes) = create_signal(AttributeValues::new());
 
     // Create a resource to fetch teachers
     let teachers = create_resource(
@@ -183,6 +186,7 @@ pub fn AddStudentForm(
             eye_glasses: new_eye_glasses(),
             notes: new_notes(),
             pin: validated_pin,
+            custom_attributes: new_attributes(),
         };
 
         let is_valid = add_student_request.validate();
@@ -475,6 +479,7 @@ pub fn AddStudentForm(
                             </div>
                         </div>
                     </div>
+                    <CustomAttributeFields values=new_attributes set_values=set_new_attributes />
                 </div>
                 <div class=BUTTON_CONTAINER_STYLE>
                     <button
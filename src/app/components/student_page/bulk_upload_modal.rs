@@ -1,5 +1,14 @@
-use crate::app::server_functions::bulk_enrollment::upload_bulk_enrollment;
-use crate::app::server_functions::bulk_students::upload_students_bulk;
+use crate::app::components::student_page::column_mapping_panel::ColumnMappingPanel;
+use crate::app::components::student_page::import_validation_report::ImportValidationReport;
+use crate::app::components::student_page::roster_sync_preview::{RosterSyncPreview, SyncPreview};
+use crate::app::models::enrollment::EnrollmentStatus;
+use crate::app::models::import_mapping::{ColumnMapping, ImportKind, MappingSuggestion};
+use crate::app::models::import_validation::ImportValidation;
+use crate::app::models::roster_sync::ApplySyncRequest;
+use crate::app::server_functions::bulk_enrollment::{
+    apply_enrollment_sync, upload_bulk_enrollment,
+};
+use crate::app::server_functions::bulk_students::{apply_student_sync, upload_students_bulk};
 use leptos::ev::{Event, MouseEvent};
 use leptos::*;
 
@@ -37,6 +46,13 @@ impl ImportType {
         }
     }
 
+    pub fn kind(&self) -> ImportKind {
+        match self {
+            ImportType::Students => ImportKind::Students,
+            ImportType::Enrollments => ImportKind::Enrollments,
+        }
+    }
+
     pub fn template_filename(&self) -> &'static str {
         match self {
             ImportType::Students => "student_template.csv",
@@ -62,8 +78,30 @@ pub fn BulkUploadModal(
     let (imported_count, set_imported_count) = create_signal(0);
     let (import_type, set_import_type) = create_signal(ImportType::Students);
 
+    // Sync mode previews the changes the file would make, including
+    // withdrawing students it leaves out, and applies them once approved
+    let (sync_mode, set_sync_mode) = create_signal(false);
+    let (sync_preview, set_sync_preview) = create_signal::<Option<(String, SyncPreview)>>(None);
+    let (withdrawal_status, set_withdrawal_status) = create_signal(EnrollmentStatus::Transferred);
+
+    // The picked file as read, and how its columns map onto the template.
+    // Validation, upload and sync all work from the mapped CSV.
+    let (raw_csv, set_raw_csv) = create_signal::<Option<String>>(None);
+    let (suggestion, set_suggestion) = create_signal::<Option<MappingSuggestion>>(None);
+    let (mapping, set_mapping) = create_signal(ColumnMapping::default());
+    let mapped_csv = create_memo(move |_| {
+        raw_csv.with(|raw| {
+            raw.as_ref()
+                .map(|raw| mapping.with(|mapping| mapping.apply(import_type().kind(), raw)))
+        })
+    });
+
+    // Outside sync mode the mapped file gets a dry run, listing every
+    // problem by row and column
+    let (validation, set_validation) = create_signal::<Option<ImportValidation>>(None);
     #[cfg(feature = "hydrate")]
-    let (file, set_file) = create_signal::<Option<web_sys::File>>(None);
+    let (is_validating, set_is_validating) = create_signal(false);
+    let (valid_rows_only, set_valid_rows_only) = create_signal(false);
 
     let on_file_change = move |ev: Event| {
         #[cfg(feature = "hydrate")]
@@ -76,7 +114,22 @@ pub fn BulkUploadModal(
                 if let Some(files) = files {
                     if files.length() > 0 {
                         if let Some(first_file) = files.item(0) {
-                            set_file(Some(first_file));
+                            set_raw_csv(None);
+                            set_suggestion(None);
+                            set_sync_preview(None);
+                            set_valid_rows_only(false);
+                            set_upload_status(String::new());
+                            let kind = import_type().kind();
+                            spawn_local(async move {
+                                match suggest_file_mapping(first_file, kind).await {
+                                    Ok((contents, suggestion)) => {
+                                        set_mapping(suggestion.mapping.clone());
+                                        set_suggestion(Some(suggestion));
+                                        set_raw_csv(Some(contents));
+                                    }
+                                    Err(e) => set_upload_status(format!("Mapping failed: {}", e)),
+                                }
+                            });
                         }
                     }
                 }
@@ -92,10 +145,21 @@ pub fn BulkUploadModal(
 
         #[cfg(feature = "hydrate")]
         {
-            if let Some(selected_file) = file() {
+            if let Some(Ok(contents)) = mapped_csv.get_untracked() {
                 let current_import_type = import_type();
+                if sync_mode() {
+                    spawn_local(async move {
+                        match preview_contents(contents, current_import_type).await {
+                            Ok(preview) => set_sync_preview(Some(preview)),
+                            Err(e) => set_upload_status(format!("Preview failed: {}", e)),
+                        }
+                        set_is_uploading(false);
+                    });
+                    return;
+                }
+                let valid_rows_only = valid_rows_only();
                 spawn_local(async move {
-                    match upload_file(selected_file, current_import_type).await {
+                    match upload_contents(contents, current_import_type, valid_rows_only).await {
                         Ok(count) => {
                             set_upload_status(format!(
                                 "Successfully imported {} {}",
@@ -129,6 +193,33 @@ pub fn BulkUploadModal(
         }
     };
 
+    let apply_sync = create_action(move |_: &()| {
+        let current_import_type = import_type();
+        let preview = sync_preview.get_untracked();
+        let request_status = withdrawal_status.get_untracked();
+        async move {
+            let Some((file_contents, preview)) = preview else {
+                return;
+            };
+            let request = ApplySyncRequest {
+                fingerprint: preview.fingerprint(),
+                withdrawal_status: request_status,
+            };
+            let result = match current_import_type {
+                ImportType::Students => apply_student_sync(file_contents, request).await,
+                ImportType::Enrollments => apply_enrollment_sync(file_contents, request).await,
+            };
+            match result {
+                Ok(report) => {
+                    set_upload_status(report.summary());
+                    set_sync_preview(None);
+                    set_refresh_trigger.update(|count| *count += 1);
+                }
+                Err(e) => set_upload_status(format!("Sync failed: {}", e)),
+            }
+        }
+    });
+
     let download_template = move |_| {
         #[cfg(feature = "hydrate")]
         {
@@ -156,17 +247,50 @@ pub fn BulkUploadModal(
         }
     };
 
+    // Dry run of the mapped file, again whenever the mapping changes
+    #[cfg(feature = "hydrate")]
+    create_effect(move |_| {
+        let contents = mapped_csv.get().and_then(Result::ok);
+        let Some(contents) = contents.filter(|_| !sync_mode()) else {
+            set_validation(None);
+            set_is_validating(false);
+            return;
+        };
+        let current_import_type = import_type.get_untracked();
+        set_is_validating(true);
+        spawn_local(async move {
+            let result = validate_contents(contents.clone(), current_import_type).await;
+            // A newer mapping's dry run supersedes this one
+            if mapped_csv.get_untracked().and_then(Result::ok) != Some(contents) {
+                return;
+            }
+            match result {
+                Ok(report) => set_validation(Some(report)),
+                Err(e) => set_upload_status(format!("Validation failed: {}", e)),
+            }
+            set_is_validating(false);
+        });
+    });
+
     // Reset file when import type changes
     create_effect(move |_| {
         import_type();
-        #[cfg(feature = "hydrate")]
-        set_file(None);
+        sync_mode();
+        set_raw_csv(None);
+        set_suggestion(None);
         set_upload_status(String::new());
+        set_sync_preview(None);
+        set_validation(None);
+        set_valid_rows_only(false);
     });
 
     view! {
         <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
-            <div class="bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-lg w-full">
+            <div class=move || if sync_preview().is_some() || suggestion().is_some() {
+                "bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-3xl w-full max-h-[90vh] overflow-y-auto"
+            } else {
+                "bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-lg w-full"
+            }>
                 <h3 class="text-xl font-bold mb-4">"Bulk Data Upload"</h3>
 
                 // Import type selection
@@ -201,6 +325,19 @@ pub fn BulkUploadModal(
                     </p>
                 </div>
 
+                // Sync mode
+                <label class="mb-4 flex items-start gap-2 text-sm text-gray-700">
+                    <input
+                        type="checkbox"
+                        class="mt-1"
+                        prop:checked=sync_mode
+                        on:change=move |ev| set_sync_mode(event_target_checked(&ev))
+                    />
+                    <span>
+                        "Sync with the SIS export: preview new, changed and missing records, then apply them together. Missing students are withdrawn, not deleted."
+                    </span>
+                </label>
+
                 // File input
                 <input
                     type="file"
@@ -233,6 +370,7 @@ pub fn BulkUploadModal(
                                     <li>"All boolean fields: true/false"</li>
                                     <li>"Date format: YYYY-MM-DD"</li>
                                     <li>"Intervention: None, Literacy, Math, or 'Literacy and Math'"</li>
+                                    <li>"Custom attributes: add a column headed with the attribute's key"</li>
                                 </ul>
                             </div>
                         },
@@ -250,6 +388,38 @@ pub fn BulkUploadModal(
                     }}
                 </div>
 
+                // Sync preview
+                {move || sync_preview().map(|(_, preview)| view! {
+                    <RosterSyncPreview
+                        preview=preview
+                        withdrawal_status=withdrawal_status
+                        set_withdrawal_status=set_withdrawal_status
+                    />
+                })}
+
+                // Column mapping
+                {move || suggestion().map(|suggestion| view! {
+                    <ColumnMappingPanel
+                        kind=import_type().kind()
+                        headers=suggestion.headers
+                        profile_id=suggestion.profile_id
+                        mapping=mapping
+                        set_mapping=set_mapping
+                    />
+                })}
+                {move || mapped_csv().and_then(Result::err).map(|e| view! {
+                    <div class="mb-4 text-sm text-red-500">{e}</div>
+                })}
+
+                // Dry run report
+                {move || validation().map(|report| view! {
+                    <ImportValidationReport
+                        report=report
+                        valid_rows_only=valid_rows_only
+                        set_valid_rows_only=set_valid_rows_only
+                    />
+                })}
+
                 // Status message
                 {move || {
                     if !upload_status().is_empty() {
@@ -291,18 +461,42 @@ pub fn BulkUploadModal(
                                 <button
                                     type="button"
                                     class="px-4 py-2 bg-[#4CAF50] text-white rounded hover:bg-[#388E3C] disabled:opacity-50 disabled:cursor-not-allowed"
-                                    disabled=move || file().is_none() || is_uploading()
+                                    disabled=move || {
+                                        !mapped_csv().is_some_and(|mapped| mapped.is_ok())
+                                            || is_uploading()
+                                            || is_validating()
+                                            || validation().is_some_and(|report| {
+                                                !report.is_valid() && !valid_rows_only()
+                                            })
+                                    }
                                     on:click=handle_upload
                                 >
-                                    {move || if is_uploading() {
-                                        format!("Uploading {}...", import_type().display_name())
-                                    } else {
-                                        format!("Upload {}", import_type().display_name())
+                                    {move || match (is_uploading(), sync_mode()) {
+                                        (false, false) if is_validating() => "Checking file...".to_string(),
+                                        (true, true) => "Previewing...".to_string(),
+                                        (true, false) => format!("Uploading {}...", import_type().display_name()),
+                                        (false, true) if sync_preview().is_some() => "Preview again".to_string(),
+                                        (false, true) => format!("Preview {} sync", import_type().display_name()),
+                                        (false, false) => format!("Upload {}", import_type().display_name()),
                                     }}
                                 </button>
                             }
                         }
                     }
+
+                    {move || sync_preview().map(|(_, preview)| {
+                        let empty = preview.is_empty();
+                        view! {
+                            <button
+                                type="button"
+                                class="px-4 py-2 bg-[#2E3A59] text-white rounded hover:bg-opacity-80 disabled:opacity-50 disabled:cursor-not-allowed"
+                                disabled=move || empty || apply_sync.pending().get()
+                                on:click=move |_| apply_sync.dispatch(())
+                            >
+                                {move || if apply_sync.pending().get() { "Applying..." } else { "Apply changes" }}
+                            </button>
+                        }
+                    })}
                 </div>
             </div>
         </div>
@@ -310,7 +504,7 @@ pub fn BulkUploadModal(
 }
 
 #[cfg(feature = "hydrate")]
-async fn upload_file(file: web_sys::File, import_type: ImportType) -> Result<usize, String> {
+pub async fn read_file_text(file: web_sys::File) -> Result<String, String> {
     // Create a future that resolves when the file is read
     let file_content_future =
         wasm_bindgen_futures::JsFuture::from(js_sys::Promise::new(&mut |resolve, reject| {
@@ -349,21 +543,90 @@ async fn upload_file(file: web_sys::File, import_type: ImportType) -> Result<usi
         .map_err(|e| format!("Error reading file: {:?}", e))?;
 
     // Extract the file content as a string
-    let file_contents = file_content_future
+    file_content_future
         .as_string()
-        .ok_or_else(|| "Failed to convert file content to string".to_string())?;
+        .ok_or_else(|| "Failed to convert file content to string".to_string())
+}
 
-    // Call the appropriate server function based on import type
+// Reads the file and the server's suggested mapping of its columns
+#[cfg(feature = "hydrate")]
+async fn suggest_file_mapping(
+    file: web_sys::File,
+    kind: ImportKind,
+) -> Result<(String, MappingSuggestion), String> {
+    let file_contents = read_file_text(file).await?;
+    let suggestion = crate::app::server_functions::import_mapping::suggest_import_mapping(
+        kind,
+        file_contents.clone(),
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok((file_contents, suggestion))
+}
+
+// Checks the mapped file without importing it
+#[cfg(feature = "hydrate")]
+async fn validate_contents(
+    file_contents: String,
+    import_type: ImportType,
+) -> Result<ImportValidation, String> {
     match import_type {
         ImportType::Students => {
-            crate::app::server_functions::bulk_students::upload_students_bulk(file_contents)
+            crate::app::server_functions::bulk_students::validate_students_bulk(file_contents).await
+        }
+        ImportType::Enrollments => {
+            crate::app::server_functions::bulk_enrollment::validate_bulk_enrollment(file_contents)
                 .await
-                .map_err(|e| e.to_string())
         }
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "hydrate")]
+async fn upload_contents(
+    file_contents: String,
+    import_type: ImportType,
+    valid_rows_only: bool,
+) -> Result<usize, String> {
+    // Call the appropriate server function based on import type
+    match import_type {
+        ImportType::Students => crate::app::server_functions::bulk_students::upload_students_bulk(
+            file_contents,
+            valid_rows_only,
+        )
+        .await
+        .map_err(|e| e.to_string()),
         ImportType::Enrollments => {
-            crate::app::server_functions::bulk_enrollment::upload_bulk_enrollment(file_contents)
+            crate::app::server_functions::bulk_enrollment::upload_bulk_enrollment(
+                file_contents,
+                valid_rows_only,
+            )
+            .await
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Previews a sync of the mapped file, returning it for applying later
+#[cfg(feature = "hydrate")]
+async fn preview_contents(
+    file_contents: String,
+    import_type: ImportType,
+) -> Result<(String, SyncPreview), String> {
+    let preview = match import_type {
+        ImportType::Students => {
+            crate::app::server_functions::bulk_students::preview_student_sync(file_contents.clone())
                 .await
-                .map_err(|e| e.to_string())
+                .map(SyncPreview::Students)
+        }
+        ImportType::Enrollments => {
+            crate::app::server_functions::bulk_enrollment::preview_enrollment_sync(
+                file_contents.clone(),
+            )
+            .await
+            .map(SyncPreview::Enrollments)
         }
     }
+    .map_err(|e| e.to_string())?;
+    Ok((file_contents, preview))
 }
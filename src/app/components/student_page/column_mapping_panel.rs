@@ -0,0 +1,252 @@
+use crate::app::models::import_mapping::{
+    ColumnMapping, DateFormat, ImportKind, SaveMappingProfileRequest,
+};
+use crate::app::server_functions::import_mapping::{
+    delete_import_mapping_profile, list_import_mapping_profiles, save_import_mapping_profile,
+};
+use leptos::*;
+
+const LABEL: &str = "text-sm text-[#2E3A59]";
+const SELECT: &str = "p-1 border rounded text-sm bg-white";
+
+// Which column of the uploaded file feeds each template column, how values
+// are rewritten, and the saved profiles for known sources
+#[component]
+pub fn ColumnMappingPanel(
+    kind: ImportKind,
+    headers: Vec<String>,
+    profile_id: Option<i32>,
+    mapping: ReadSignal<ColumnMapping>,
+    set_mapping: WriteSignal<ColumnMapping>,
+) -> impl IntoView {
+    let (selected_profile, set_selected_profile) = create_signal(profile_id);
+    let (profile_name, set_profile_name) = create_signal(String::new());
+    let (message, set_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let profiles = create_resource(
+        || (),
+        move |_| async move { list_import_mapping_profiles(kind).await.unwrap_or_default() },
+    );
+
+    // Name the save box after the profile in use
+    create_effect(move |_| {
+        let name = profiles.with(|profiles| {
+            profiles.as_ref().and_then(|profiles| {
+                profiles
+                    .iter()
+                    .find(|profile| Some(profile.id) == selected_profile.get())
+                    .map(|profile| profile.name.clone())
+            })
+        });
+        if let Some(name) = name {
+            set_profile_name.set(name);
+        }
+    });
+
+    let save_profile = create_action(move |_: &()| {
+        let request = SaveMappingProfileRequest {
+            name: profile_name.get_untracked(),
+            kind,
+            mapping: mapping.get_untracked(),
+        };
+        async move {
+            match save_import_mapping_profile(request).await {
+                Ok(profile) => {
+                    set_message.set(Some((format!("Saved profile {}", profile.name), true)));
+                    set_selected_profile.set(Some(profile.id));
+                    profiles.refetch();
+                }
+                Err(e) => set_message.set(Some((format!("Save failed: {}", e), false))),
+            }
+        }
+    });
+
+    let delete_profile = create_action(move |id: &i32| {
+        let id = *id;
+        async move {
+            match delete_import_mapping_profile(id).await {
+                Ok(()) => {
+                    set_message.set(Some(("Deleted the profile".to_string(), true)));
+                    set_selected_profile.set(None);
+                    profiles.refetch();
+                }
+                Err(e) => set_message.set(Some((format!("Delete failed: {}", e), false))),
+            }
+        }
+    });
+
+    let on_profile_change = move |ev: ev::Event| {
+        let id = event_target_value(&ev).parse::<i32>().ok();
+        set_selected_profile.set(id);
+        let profile = profiles.with(|profiles| {
+            profiles
+                .as_ref()
+                .and_then(|profiles| profiles.iter().find(|profile| Some(profile.id) == id))
+                .cloned()
+        });
+        if let Some(profile) = profile {
+            set_mapping.set(profile.mapping);
+        }
+    };
+
+    let field_rows = kind
+        .fields()
+        .iter()
+        .map(|field| {
+            let column = field.column;
+            let options = headers.clone();
+            view! {
+                <tr class="border-t border-[#DADADA]">
+                    <td class="px-2 py-1 text-[#2E3A59]">
+                        {field.label}
+                        <span class="ml-1 text-xs text-gray-500 font-mono">{column}</span>
+                    </td>
+                    <td class="px-2 py-1">
+                        <select
+                            class=format!("{} w-full", SELECT)
+                            on:change=move |ev| {
+                                let source = event_target_value(&ev);
+                                set_mapping.update(|mapping| {
+                                    if source.is_empty() {
+                                        mapping.columns.remove(column);
+                                    } else {
+                                        mapping.columns.insert(column.to_string(), source);
+                                    }
+                                });
+                            }
+                        >
+                            <option value="" selected=move || mapping.with(|m| m.source_for(column).is_none())>
+                                "Not in the file"
+                            </option>
+                            {options.into_iter().map(|header| {
+                                let selected_header = header.clone();
+                                view! {
+                                    <option
+                                        value=header.clone()
+                                        selected=move || mapping.with(|m| m.source_for(column) == Some(selected_header.as_str()))
+                                    >
+                                        {header}
+                                    </option>
+                                }
+                            }).collect_view()}
+                        </select>
+                    </td>
+                </tr>
+            }
+        })
+        .collect_view();
+
+    view! {
+        <div class="mb-4 p-3 bg-gray-50 rounded border border-[#DADADA] space-y-3">
+            <div class="flex items-center justify-between gap-2">
+                <span class="text-sm font-semibold text-[#2E3A59]">"Column mapping"</span>
+                <label class=format!("{} flex items-center gap-2", LABEL)>
+                    "Profile"
+                    <select class=SELECT on:change=on_profile_change>
+                        <option value="" selected=move || selected_profile.get().is_none()>
+                            "Matched by header"
+                        </option>
+                        {move || profiles.get().unwrap_or_default().into_iter().map(|profile| view! {
+                            <option
+                                value=profile.id.to_string()
+                                selected=move || selected_profile.get() == Some(profile.id)
+                            >
+                                {profile.name}
+                            </option>
+                        }).collect_view()}
+                    </select>
+                </label>
+            </div>
+
+            <div class="max-h-60 overflow-y-auto bg-white border border-[#DADADA] rounded">
+                <table class="w-full text-sm">
+                    <thead class="bg-gray-100 sticky top-0">
+                        <tr>
+                            <th class="px-2 py-1 text-left font-medium text-[#2E3A59]">"Import field"</th>
+                            <th class="px-2 py-1 text-left font-medium text-[#2E3A59]">"File column"</th>
+                        </tr>
+                    </thead>
+                    <tbody>{field_rows}</tbody>
+                </table>
+            </div>
+
+            <div class="flex flex-wrap items-center gap-4">
+                <label class=format!("{} flex items-center gap-2", LABEL)>
+                    "Dates in the file"
+                    <select
+                        class=SELECT
+                        on:change=move |ev| {
+                            let label = event_target_value(&ev);
+                            if let Some(format) = DateFormat::ALL.into_iter().find(|format| format.label() == label) {
+                                set_mapping.update(|mapping| mapping.transforms.date_format = format);
+                            }
+                        }
+                    >
+                        {DateFormat::ALL.into_iter().map(|format| view! {
+                            <option
+                                value=format.label()
+                                selected=move || mapping.with(|m| m.transforms.date_format == format)
+                            >
+                                {format.label()}
+                            </option>
+                        }).collect_view()}
+                    </select>
+                </label>
+                <label class=format!("{} flex items-center gap-2", LABEL)>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || mapping.with(|m| m.transforms.grade_aliases)
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            set_mapping.update(|mapping| mapping.transforms.grade_aliases = checked);
+                        }
+                    />
+                    "Read K, 00, 03, 3rd as grades"
+                </label>
+                <label class=format!("{} flex items-center gap-2", LABEL)>
+                    <input
+                        type="checkbox"
+                        prop:checked=move || mapping.with(|m| m.transforms.yes_no_booleans)
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            set_mapping.update(|mapping| mapping.transforms.yes_no_booleans = checked);
+                        }
+                    />
+                    "Read Y/N and blanks as true/false"
+                </label>
+            </div>
+
+            <div class="flex items-center gap-2">
+                <input
+                    type="text"
+                    class="flex-1 p-1 border rounded text-sm"
+                    placeholder="Profile name, e.g. the SIS the file comes from"
+                    prop:value=profile_name
+                    on:input=move |ev| set_profile_name.set(event_target_value(&ev))
+                />
+                <button
+                    type="button"
+                    class="px-3 py-1 text-sm bg-[#2E3A59] text-white rounded hover:bg-opacity-80 disabled:opacity-50"
+                    prop:disabled=move || save_profile.pending().get() || profile_name.get().trim().is_empty()
+                    on:click=move |_| save_profile.dispatch(())
+                >
+                    "Save profile"
+                </button>
+                {move || selected_profile.get().map(|id| view! {
+                    <button
+                        type="button"
+                        class="px-3 py-1 text-sm text-[#F44336] border border-[#F44336] rounded hover:bg-red-50 disabled:opacity-50"
+                        prop:disabled=move || delete_profile.pending().get()
+                        on:click=move |_| delete_profile.dispatch(id)
+                    >
+                        "Delete profile"
+                    </button>
+                })}
+            </div>
+
+            {move || message.get().map(|(text, ok)| view! {
+                <div class=if ok { "text-sm text-green-600" } else { "text-sm text-red-500" }>{text}</div>
+            })}
+        </div>
+    }
+}
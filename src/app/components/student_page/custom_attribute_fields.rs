@@ -0,0 +1,191 @@
+use crate::app::models::student_attribute::{
+    AttributeDefinition, AttributeFilter, AttributeKind, AttributeValues,
+};
+use crate::app::server_functions::student_attributes::get_attribute_definitions;
+use leptos::*;
+
+const INFO_TITLE_STYLE: &str = "text-stone-400 text-xs";
+const INFO_GROUP_STYLE: &str = "mb-2";
+
+fn set_value(set_values: WriteSignal<AttributeValues>, key: &str, value: String) {
+    set_values.update(|values| {
+        if value.is_empty() {
+            values.remove(key);
+        } else {
+            values.insert(key.to_string(), value);
+        }
+    });
+}
+
+// One input for a custom attribute, shaped by its kind
+fn attribute_input(
+    definition: AttributeDefinition,
+    values: Signal<AttributeValues>,
+    set_values: WriteSignal<AttributeValues>,
+) -> View {
+    let key = definition.key.clone();
+    let current = Signal::derive(move || values.get().get(&key).cloned().unwrap_or_default());
+    let id = format!("attribute-{}", definition.key);
+    let label = definition.label.clone();
+    let key = definition.key.clone();
+
+    match definition.kind {
+        AttributeKind::Bool => view! {
+            <div class=INFO_GROUP_STYLE>
+                <label class="flex items-center gap-2">
+                    <input
+                        type="checkbox"
+                        class="form-checkbox h-5 w-5"
+                        prop:checked=move || current.get() == "true"
+                        on:change=move |ev| set_value(set_values, &key, event_target_checked(&ev).to_string())
+                    />
+                    <span class=INFO_TITLE_STYLE>{label}</span>
+                </label>
+            </div>
+        }
+        .into_view(),
+        AttributeKind::Enum => view! {
+            <div class=INFO_GROUP_STYLE>
+                <label class=INFO_TITLE_STYLE for=id.clone()>{label}</label>
+                <select
+                    id=id
+                    class="mt-1 w-full rounded-md border p-2"
+                    on:change=move |ev| set_value(set_values, &key, event_target_value(&ev))
+                >
+                    <option value="">"Not set"</option>
+                    {definition.options.into_iter().map(|option| {
+                        let selected = option.clone();
+                        view! {
+                            <option value=option.clone() selected=move || current.get() == selected>{option}</option>
+                        }
+                    }).collect_view()}
+                </select>
+            </div>
+        }
+        .into_view(),
+        kind => view! {
+            <div class=INFO_GROUP_STYLE>
+                <label class=INFO_TITLE_STYLE for=id.clone()>{label}</label>
+                <input
+                    id=id
+                    type=if kind == AttributeKind::Date { "date" } else { "text" }
+                    class="mt-1 w-full rounded-md border p-2"
+                    prop:value=current
+                    on:input=move |ev| set_value(set_values, &key, event_target_value(&ev))
+                />
+            </div>
+        }
+        .into_view(),
+    }
+}
+
+// The district's active custom attributes as a form section; nothing is
+// shown until an admin defines some
+#[component]
+pub fn CustomAttributeFields(
+    #[prop(into)] values: Signal<AttributeValues>,
+    set_values: WriteSignal<AttributeValues>,
+) -> impl IntoView {
+    let definitions = create_resource(|| (), |_| async move { get_attribute_definitions().await });
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || definitions.get().map(|result| match result {
+                Err(e) => view! {
+                    <div class="col-span-2 text-xs text-red-600">{format!("Failed to load district fields: {}", e)}</div>
+                }.into_view(),
+                Ok(definitions) => {
+                    let active: Vec<AttributeDefinition> = definitions.into_iter().filter(|definition| definition.active).collect();
+                    if active.is_empty() {
+                        return ().into_view();
+                    }
+                    view! {
+                        <div class="col-span-2">
+                            <h3 class="text-sm font-semibold text-gray-600 mb-2">"District Fields"</h3>
+                            <div class="grid grid-cols-2 gap-4 bg-gray-50 p-4 rounded-lg">
+                                {active.into_iter().map(|definition| attribute_input(definition, values, set_values)).collect_view()}
+                            </div>
+                        </div>
+                    }.into_view()
+                }
+            })}
+        </Suspense>
+    }
+}
+
+// Picks a custom attribute and the value to filter students by
+#[component]
+pub fn AttributeFilterControl(
+    #[prop(into)] definitions: Signal<Vec<AttributeDefinition>>,
+    #[prop(into)] filter: Signal<Option<AttributeFilter>>,
+    set_filter: WriteSignal<Option<AttributeFilter>>,
+) -> impl IntoView {
+    // Keyed on the attribute alone so typing a value doesn't redraw its input
+    let selected_key = create_memo(move |_| filter.get().map(|filter| filter.key));
+    let selected = move || {
+        selected_key.get().and_then(|key| {
+            definitions
+                .get()
+                .into_iter()
+                .find(|definition| definition.key == key)
+        })
+    };
+    let set_filter_value = move |value: String| {
+        set_filter.update(|filter| {
+            if let Some(filter) = filter {
+                filter.value = value;
+            }
+        })
+    };
+
+    view! {
+        <Show when=move || definitions.with(|definitions| definitions.iter().any(|definition| definition.active))>
+            <div class="flex items-center gap-2">
+                <select
+                    class="border border-gray-300 rounded px-2 py-1 text-sm bg-white"
+                    prop:value=move || selected_key.get().unwrap_or_default()
+                    on:change=move |ev| {
+                        let key = event_target_value(&ev);
+                        set_filter.set((!key.is_empty()).then(|| AttributeFilter {
+                            key,
+                            value: String::new(),
+                        }));
+                    }
+                >
+                    <option value="">"Any district field"</option>
+                    {move || definitions.get().into_iter().filter(|definition| definition.active).map(|definition| view! {
+                        <option value=definition.key>{definition.label}</option>
+                    }).collect_view()}
+                </select>
+                {move || selected().map(|definition| match definition.kind {
+                    AttributeKind::Bool | AttributeKind::Enum => {
+                        let choices: Vec<(String, String)> = if definition.kind == AttributeKind::Bool {
+                            vec![("true".to_string(), "Yes".to_string()), ("false".to_string(), "No".to_string())]
+                        } else {
+                            definition.options.iter().map(|option| (option.clone(), option.clone())).collect()
+                        };
+                        view! {
+                            <select
+                                class="border border-gray-300 rounded px-2 py-1 text-sm bg-white"
+                                on:change=move |ev| set_filter_value(event_target_value(&ev))
+                            >
+                                <option value="">"Any"</option>
+                                {choices.into_iter().map(|(value, label)| view! {
+                                    <option value=value>{label}</option>
+                                }).collect_view()}
+                            </select>
+                        }.into_view()
+                    }
+                    kind => view! {
+                        <input
+                            type=if kind == AttributeKind::Date { "date" } else { "text" }
+                            placeholder="Value"
+                            class="border border-gray-300 rounded px-2 py-1 text-sm"
+                            on:input=move |ev| set_filter_value(event_target_value(&ev))
+                        />
+                    }.into_view(),
+                })}
+            </div>
+        </Show>
+    }
+}
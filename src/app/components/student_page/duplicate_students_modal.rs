@@ -0,0 +1,253 @@
+use crate::app::models::student::Student;
+use crate::app::models::student_merge::{DuplicateCandidate, MergeStudentsRequest};
+use crate::app::server_functions::student_merge::{
+    find_duplicate_students, get_student_merges, merge_students, unmerge_students,
+};
+use leptos::*;
+
+const STUDENT_CARD: &str =
+    "flex-1 p-3 bg-white rounded border border-[#DADADA] text-sm text-[#2E3A59]";
+const KEEP_BUTTON: &str = "mt-2 px-3 py-1 text-xs rounded border border-[#2E3A59] text-[#2E3A59] hover:bg-[#2E3A59] hover:text-white disabled:opacity-50";
+
+fn student_name(student: &Student) -> String {
+    format!(
+        "{} {}",
+        student.firstname.clone().unwrap_or_default(),
+        student.lastname.clone().unwrap_or_default()
+    )
+}
+
+// Likely duplicate students side by side. Choosing which record to keep
+// merges the other into it; recent merges can be undone from here too.
+#[component]
+pub fn DuplicateStudentsModal(
+    set_show_modal: WriteSignal<bool>,
+    set_refresh_trigger: WriteSignal<i32>,
+) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0);
+    let duplicates = create_local_resource(
+        move || refresh.get(),
+        |_| async move { find_duplicate_students(None).await },
+    );
+    let merges = create_local_resource(
+        move || refresh.get(),
+        |_| async move { get_student_merges(None).await },
+    );
+
+    // (survivor, merged) awaiting confirmation
+    let (pending, set_pending) = create_signal::<Option<(Student, Student)>>(None);
+    let (reason, set_reason) = create_signal(String::new());
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+
+    let after_change = move |message: String| {
+        set_status_message.set(Some((message, true)));
+        set_pending.set(None);
+        set_reason.set(String::new());
+        set_refresh.update(|count| *count += 1);
+        set_refresh_trigger.update(|count| *count += 1);
+    };
+
+    let merge_action = create_action(move |request: &MergeStudentsRequest| {
+        let request = request.clone();
+        async move {
+            match merge_students(request).await {
+                Ok(merge) => after_change(format!(
+                    "Merged student {} into {}",
+                    merge.merged_id, merge.survivor_id
+                )),
+                Err(e) => set_status_message.set(Some((format!("Merge failed: {}", e), false))),
+            }
+        }
+    });
+
+    let unmerge_action = create_action(move |merge_id: &i32| {
+        let merge_id = *merge_id;
+        async move {
+            match unmerge_students(merge_id).await {
+                Ok(merge) => after_change(format!(
+                    "Restored student {} from {}",
+                    merge.merged_id, merge.survivor_id
+                )),
+                Err(e) => set_status_message.set(Some((format!("Undo failed: {}", e), false))),
+            }
+        }
+    });
+
+    let candidate_view = move |candidate: DuplicateCandidate| {
+        let side = move |keep: Student, other: Student| {
+            let label = format!("Keep {}", keep.student_id);
+            let name = student_name(&keep);
+            let id = format!("ID {}", keep.student_id);
+            let born = format!("Born {}", keep.date_of_birth.format("%Y-%m-%d"));
+            let grade = keep.current_grade_level.to_string();
+            view! {
+                <div class=STUDENT_CARD>
+                    <div class="font-semibold">{name}</div>
+                    <div>{id}</div>
+                    <div>{born}</div>
+                    <div>{grade}</div>
+                    <button
+                        class=KEEP_BUTTON
+                        prop:disabled=move || merge_action.pending().get()
+                        on:click=move |_| set_pending.set(Some((keep.clone(), other.clone())))
+                    >
+                        {label}
+                    </button>
+                </div>
+            }
+        };
+        view! {
+            <div class="p-3 rounded border border-[#DADADA] bg-[#F9F9F8]">
+                <div class="flex justify-between text-xs text-[#2E3A59] text-opacity-70 mb-2">
+                    <span>{candidate.reasons.join(", ")}</span>
+                    <span>{format!("{:.0}% match", candidate.score * 100.0)}</span>
+                </div>
+                <div class="flex gap-3">
+                    {side(candidate.first.clone(), candidate.second.clone())}
+                    {side(candidate.second, candidate.first)}
+                </div>
+            </div>
+        }
+    };
+
+    view! {
+        <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+            <div class="bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-3xl w-full max-h-[90vh] overflow-y-auto">
+                <h3 class="text-xl font-bold mb-4 text-[#2E3A59]">"Possible Duplicate Students"</h3>
+
+                {move || pending.get().map(|(survivor, merged)| {
+                    let request = MergeStudentsRequest {
+                        survivor_id: survivor.student_id,
+                        merged_id: merged.student_id,
+                        reason: None,
+                    };
+                    view! {
+                        <div class="mb-4 p-3 rounded border border-[#F44336] bg-white text-sm text-[#2E3A59] space-y-2">
+                            <div>
+                                {format!(
+                                    "Move the enrollments and scores of {} ({}) to {} ({}) and remove {}?",
+                                    student_name(&merged),
+                                    merged.student_id,
+                                    student_name(&survivor),
+                                    survivor.student_id,
+                                    merged.student_id
+                                )}
+                            </div>
+                            <input
+                                type="text"
+                                placeholder="Reason (optional)"
+                                class="w-full px-2 py-1 rounded border border-[#DADADA]"
+                                prop:value=move || reason.get()
+                                on:input=move |ev| set_reason.set(event_target_value(&ev))
+                            />
+                            <div class="flex justify-end gap-2">
+                                <button class="px-3 py-1 text-sm" on:click=move |_| set_pending.set(None)>
+                                    "Cancel"
+                                </button>
+                                <button
+                                    class="px-3 py-1 text-sm rounded bg-[#F44336] text-white hover:bg-[#D32F2F] disabled:opacity-50"
+                                    prop:disabled=move || merge_action.pending().get()
+                                    on:click=move |_| {
+                                        merge_action.dispatch(MergeStudentsRequest {
+                                            reason: Some(reason.get()),
+                                            ..request.clone()
+                                        })
+                                    }
+                                >
+                                    "Merge"
+                                </button>
+                            </div>
+                        </div>
+                    }
+                })}
+
+                <Suspense fallback=|| view! { <div class="text-sm text-[#2E3A59]">"Looking for duplicates..."</div> }>
+                    {move || duplicates.get().map(|result| match result {
+                        Err(e) => view! {
+                            <div class="text-sm text-red-600">{format!("Failed to find duplicates: {}", e)}</div>
+                        }.into_view(),
+                        Ok(candidates) if candidates.is_empty() => view! {
+                            <div class="text-sm text-[#2E3A59]">"No likely duplicates found."</div>
+                        }.into_view(),
+                        Ok(candidates) => view! {
+                            <div class="space-y-3">
+                                {candidates.into_iter().map(candidate_view).collect_view()}
+                            </div>
+                        }.into_view(),
+                    })}
+                </Suspense>
+
+                <h4 class="mt-6 mb-2 font-semibold text-[#2E3A59]">"Recent Merges"</h4>
+                <Suspense fallback=|| ()>
+                    {move || merges.get().map(|result| match result {
+                        Err(e) => view! {
+                            <div class="text-sm text-red-600">{format!("Failed to load merges: {}", e)}</div>
+                        }.into_view(),
+                        Ok(merges) if merges.is_empty() => view! {
+                            <div class="text-sm text-[#2E3A59]">"No students have been merged."</div>
+                        }.into_view(),
+                        Ok(merges) => view! {
+                            <table class="w-full text-sm text-[#2E3A59]">
+                                <thead>
+                                    <tr class="text-left text-xs text-opacity-70">
+                                        <th>"Kept"</th>
+                                        <th>"Merged"</th>
+                                        <th>"Moved"</th>
+                                        <th>"By"</th>
+                                        <th></th>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {merges.into_iter().map(|merge| {
+                                        let merge_id = merge.id;
+                                        view! {
+                                            <tr>
+                                                <td>{merge.survivor_id}</td>
+                                                <td>{merge.merged_id}</td>
+                                                <td>{format!("{} enrollments, {} scores", merge.enrollments_moved, merge.scores_moved)}</td>
+                                                <td>{format!("{} on {}", merge.merged_by, merge.merged_at.format("%Y-%m-%d"))}</td>
+                                                <td class="text-right">
+                                                    {if merge.is_active() {
+                                                        view! {
+                                                            <button
+                                                                class="text-[#2E3A59] underline disabled:opacity-50"
+                                                                prop:disabled=move || unmerge_action.pending().get()
+                                                                on:click=move |_| unmerge_action.dispatch(merge_id)
+                                                            >
+                                                                "Undo"
+                                                            </button>
+                                                        }.into_view()
+                                                    } else {
+                                                        view! {
+                                                            <span class="text-xs text-opacity-60">
+                                                                {format!("Undone by {}", merge.unmerged_by.clone().unwrap_or_default())}
+                                                            </span>
+                                                        }.into_view()
+                                                    }}
+                                                </td>
+                                            </tr>
+                                        }
+                                    }).collect_view()}
+                                </tbody>
+                            </table>
+                        }.into_view(),
+                    })}
+                </Suspense>
+
+                {move || status_message.get().map(|(message, success)| {
+                    let class = if success { "mt-4 text-sm text-green-700" } else { "mt-4 text-sm text-red-600" };
+                    view! { <div class=class>{message}</div> }
+                })}
+
+                <div class="mt-6 flex justify-end">
+                    <button
+                        class="px-4 py-2 bg-[#DADADA] text-[#2E3A59] rounded hover:bg-opacity-80"
+                        on:click=move |_| set_show_modal.set(false)
+                    >
+                        "Close"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,103 @@
+use crate::app::models::student::Student;
+use crate::app::models::student_history::flag_changes;
+use crate::app::server_functions::student_history::{get_student_as_of, get_student_history};
+use chrono::NaiveDate;
+use leptos::*;
+
+fn flag_summary(student: &Student) -> String {
+    let mut flags: Vec<String> = [
+        ("IEP", student.iep),
+        ("BIP", student.bip),
+        ("504", student.student_504),
+        ("Read plan", student.readplan),
+        ("GT", student.gt),
+    ]
+    .into_iter()
+    .filter(|(_, set)| *set)
+    .map(|(flag, _)| flag.to_string())
+    .collect();
+    if let Some(intervention) = &student.intervention {
+        flags.push(format!("{} intervention", intervention));
+    }
+    flags.push(format!("ESL: {}", student.esl));
+    format!(
+        "{}, {}; {}",
+        student.current_grade_level,
+        student.teacher,
+        flags.join(", ")
+    )
+}
+
+// When the student's program flags and placement changed, newest first,
+// and how they stood on a chosen date
+#[component]
+pub fn FlagHistoryPanel(#[prop(into)] student_id: Signal<i32>) -> impl IntoView {
+    let history = create_resource(
+        move || student_id.get(),
+        |student_id| async move { get_student_history(student_id).await },
+    );
+    let (as_of_date, set_as_of_date) = create_signal::<Option<NaiveDate>>(None);
+    let as_of = create_resource(
+        move || (student_id.get(), as_of_date.get()),
+        |(student_id, date)| async move {
+            match date {
+                Some(date) => get_student_as_of(student_id, date).await,
+                None => Ok(None),
+            }
+        },
+    );
+
+    view! {
+        <div class="mb-3 sm:mb-4">
+            <div class="text-xs text-[#2E3A59] text-opacity-70 font-medium">"Flag History"</div>
+            <Suspense fallback=|| view! { <div class="text-xs text-[#2E3A59] mt-1">"Loading..."</div> }>
+                {move || history.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="text-xs text-red-600 mt-1">{format!("Failed to load history: {}", e)}</div>
+                    }.into_view(),
+                    Ok(versions) => {
+                        let changes = flag_changes(&versions);
+                        let started = versions.first().map(|version| version.valid_from.format("%Y-%m-%d").to_string());
+                        view! {
+                            <ul class="mt-1 space-y-1 text-xs sm:text-sm text-[#2E3A59]">
+                                {changes.into_iter().map(|change| view! {
+                                    <li>
+                                        <span class="text-[#2E3A59] text-opacity-60">{change.changed_at.format("%Y-%m-%d").to_string()}</span>
+                                        {format!(" {}: {} → {} ", change.flag, change.from, change.to)}
+                                        <span class="text-[#2E3A59] text-opacity-60">{format!("by {}", change.changed_by)}</span>
+                                    </li>
+                                }).collect_view()}
+                                {started.map(|started| view! {
+                                    <li class="text-xs text-[#2E3A59] text-opacity-60">{format!("History recorded since {}", started)}</li>
+                                })}
+                            </ul>
+                        }.into_view()
+                    }
+                })}
+            </Suspense>
+            <div class="mt-2 flex items-center gap-2 text-xs text-[#2E3A59]">
+                <label for="flag-history-as-of">"As of"</label>
+                <input
+                    id="flag-history-as-of"
+                    type="date"
+                    class="px-2 py-1 rounded border border-[#DADADA]"
+                    on:change=move |ev| set_as_of_date.set(NaiveDate::parse_from_str(&event_target_value(&ev), "%Y-%m-%d").ok())
+                />
+            </div>
+            <Suspense fallback=|| ()>
+                {move || as_of.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="mt-1 text-xs text-red-600">{format!("Failed to load: {}", e)}</div>
+                    }.into_view(),
+                    Ok(None) if as_of_date.get().is_some() => view! {
+                        <div class="mt-1 text-xs text-[#2E3A59] text-opacity-60">"No history recorded for that date."</div>
+                    }.into_view(),
+                    Ok(None) => ().into_view(),
+                    Ok(Some(student)) => view! {
+                        <div class="mt-1 text-xs sm:text-sm text-[#2E3A59]">{flag_summary(&student)}</div>
+                    }.into_view(),
+                })}
+            </Suspense>
+        </div>
+    }
+}
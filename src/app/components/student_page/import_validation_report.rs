@@ -0,0 +1,103 @@
+use crate::app::models::import_validation::ImportValidation;
+use leptos::*;
+
+#[cfg(feature = "hydrate")]
+use {js_sys::Array, wasm_bindgen::JsCast};
+
+const HEADER_CELL: &str = "px-2 py-1 text-left font-medium text-[#2E3A59]";
+const CELL: &str = "px-2 py-1 align-top text-[#2E3A59]";
+
+// Saves the annotated CSV, the upload with a column listing each row's
+// problems
+#[cfg(feature = "hydrate")]
+fn download_annotated_csv(contents: &str, filename: &str) {
+    let blob = web_sys::Blob::new_with_str_sequence(&Array::of1(&contents.into()))
+        .unwrap_or_else(|_| web_sys::Blob::new().unwrap());
+
+    let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap_or_default();
+
+    if let Some(window) = web_sys::window() {
+        if let Some(document) = window.document() {
+            if let Ok(a) = document.create_element("a") {
+                let _ = a.set_attribute("href", &url);
+                let _ = a.set_attribute("download", filename);
+
+                if let Some(html_element) = a.dyn_ref::<web_sys::HtmlElement>() {
+                    html_element.click();
+                }
+            }
+        }
+    }
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
+
+// The problems a dry run of a bulk upload found, row by row, with the
+// choice to import only the rows without any
+#[component]
+pub fn ImportValidationReport(
+    report: ImportValidation,
+    valid_rows_only: ReadSignal<bool>,
+    set_valid_rows_only: WriteSignal<bool>,
+) -> impl IntoView {
+    if report.is_valid() {
+        return view! {
+            <div class="mb-4 p-3 bg-green-50 rounded border border-green-200 text-sm text-green-800">
+                {report.summary()}
+            </div>
+        }
+        .into_view();
+    }
+
+    let summary = report.summary();
+    let valid_rows = report.valid_rows();
+    let annotated_csv = report.annotated_csv.clone();
+    let download = move |_| {
+        #[cfg(feature = "hydrate")]
+        download_annotated_csv(&annotated_csv, "upload_with_errors.csv");
+        #[cfg(not(feature = "hydrate"))]
+        let _ = &annotated_csv;
+    };
+
+    view! {
+        <div class="mb-4 p-3 bg-gray-50 rounded border border-[#DADADA]">
+            <div class="flex justify-between items-center mb-2 text-sm">
+                <span class="font-semibold text-red-600">{summary}</span>
+                <button class="text-blue-500 hover:underline" on:click=download>
+                    "Download annotated CSV"
+                </button>
+            </div>
+            <div class="max-h-60 overflow-y-auto bg-white border border-[#DADADA] rounded">
+                <table class="w-full text-xs">
+                    <thead class="bg-gray-100 sticky top-0">
+                        <tr>
+                            <th class=HEADER_CELL>"Row"</th>
+                            <th class=HEADER_CELL>"Column"</th>
+                            <th class=HEADER_CELL>"Value"</th>
+                            <th class=HEADER_CELL>"Problem"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {report.issues.into_iter().map(|issue| view! {
+                            <tr class="border-t border-[#DADADA]">
+                                <td class=CELL>{issue.row}</td>
+                                <td class=CELL>{if issue.column.is_empty() { "(row)".to_string() } else { issue.column }}</td>
+                                <td class=format!("{} font-mono", CELL)>{issue.value}</td>
+                                <td class=CELL>{issue.message}</td>
+                            </tr>
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            </div>
+            <label class="mt-3 flex items-center gap-2 text-sm text-[#2E3A59]">
+                <input
+                    type="checkbox"
+                    prop:checked=valid_rows_only
+                    prop:disabled=valid_rows == 0
+                    on:change=move |ev| set_valid_rows_only(event_target_checked(&ev))
+                />
+                {format!("Import valid rows only ({} rows)", valid_rows)}
+            </label>
+        </div>
+    }
+    .into_view()
+}
@@ -0,0 +1,94 @@
+use crate::app::models::retention::LegalHold;
+use crate::app::server_functions::retention::{get_student_legal_hold, set_student_legal_hold};
+use leptos::*;
+
+// Places or lifts a legal hold, which keeps the student out of every
+// retention purge
+#[component]
+pub fn LegalHoldPanel(#[prop(into)] student_id: Signal<i32>) -> impl IntoView {
+    let hold = create_resource(
+        move || student_id.get(),
+        |student_id| async move { get_student_legal_hold(student_id).await },
+    );
+    let (reason, set_reason) = create_signal(String::new());
+    let (error, set_error) = create_signal::<Option<String>>(None);
+
+    let update_hold = create_action(move |held: &bool| {
+        let held = *held;
+        let reason = Some(reason.get_untracked());
+        async move {
+            set_error.set(None);
+            match set_student_legal_hold(student_id.get_untracked(), held, reason).await {
+                Ok(updated) => {
+                    set_reason.set(String::new());
+                    hold.set(Ok(updated));
+                }
+                Err(e) => set_error.set(Some(format!("Failed to update legal hold: {}", e))),
+            }
+        }
+    });
+
+    let describe = |hold: &LegalHold| match (&hold.set_by, hold.set_at) {
+        (Some(set_by), Some(set_at)) => format!(
+            "{} by {} on {}",
+            if hold.held { "Placed" } else { "Lifted" },
+            set_by,
+            set_at.format("%Y-%m-%d")
+        ),
+        _ => String::new(),
+    };
+
+    view! {
+        <div class="mb-3 sm:mb-4">
+            <div class="text-xs text-[#2E3A59] text-opacity-70 font-medium">"Legal Hold"</div>
+            <Suspense fallback=|| view! { <div class="text-xs text-[#2E3A59] mt-1">"Loading..."</div> }>
+                {move || hold.get().map(|result| match result {
+                    Err(e) => view! {
+                        <div class="text-xs text-red-600 mt-1">{format!("Failed to load legal hold: {}", e)}</div>
+                    }.into_view(),
+                    Ok(current) if current.held => view! {
+                        <div class="mt-1 space-y-2">
+                            <div class="text-xs sm:text-sm text-[#2E3A59]">
+                                <span class="font-medium text-red-700">"On hold: "</span>
+                                {current.reason.clone().unwrap_or_default()}
+                            </div>
+                            <div class="text-xs text-[#2E3A59] text-opacity-60">{describe(&current)}</div>
+                            <button
+                                class="px-3 py-1 text-xs rounded border border-[#DADADA] text-[#2E3A59] hover:bg-gray-100 disabled:opacity-50"
+                                prop:disabled=move || update_hold.pending().get()
+                                on:click=move |_| update_hold.dispatch(false)
+                            >
+                                "Lift hold"
+                            </button>
+                        </div>
+                    }.into_view(),
+                    Ok(current) => view! {
+                        <div class="mt-1 space-y-2">
+                            <div class="text-xs sm:text-sm text-[#2E3A59]">"Not on hold"</div>
+                            <div class="text-xs text-[#2E3A59] text-opacity-60">{describe(&current)}</div>
+                            <div class="flex gap-2">
+                                <input
+                                    type="text"
+                                    placeholder="Reason for hold"
+                                    class="flex-1 px-2 py-1 text-xs sm:text-sm rounded border border-[#DADADA] text-[#2E3A59]"
+                                    prop:value=move || reason.get()
+                                    on:input=move |ev| set_reason.set(event_target_value(&ev))
+                                />
+                                <button
+                                    class="px-3 py-1 text-xs rounded bg-[#2E3A59] text-white hover:bg-opacity-90 disabled:opacity-50"
+                                    prop:disabled=move || update_hold.pending().get() || reason.get().trim().is_empty()
+                                    on:click=move |_| update_hold.dispatch(true)
+                                >
+                                    "Place hold"
+                                </button>
+                            </div>
+                        </div>
+                    }.into_view(),
+                })}
+            </Suspense>
+            {move || error.get().map(|message| view! {
+                <div class="mt-1 text-xs text-red-600">{message}</div>
+            })}
+        </div>
+    }
+}
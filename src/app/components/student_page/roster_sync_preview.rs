@@ -0,0 +1,217 @@
+use crate::app::models::enrollment::EnrollmentStatus;
+use crate::app::models::roster_sync::{
+    EnrollmentSyncDiff, FieldChange, StudentSyncDiff, SyncWithdrawal,
+};
+use leptos::*;
+
+const SECTION_TITLE: &str = "mt-3 mb-1 text-sm font-semibold text-[#2E3A59]";
+const LIST: &str = "max-h-40 overflow-y-auto text-xs text-[#2E3A59] space-y-1";
+
+// A previewed sync of either import type
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncPreview {
+    Students(StudentSyncDiff),
+    Enrollments(EnrollmentSyncDiff),
+}
+
+impl SyncPreview {
+    pub fn fingerprint(&self) -> String {
+        match self {
+            SyncPreview::Students(diff) => diff.fingerprint.clone(),
+            SyncPreview::Enrollments(diff) => diff.fingerprint.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            SyncPreview::Students(diff) => diff.is_empty(),
+            SyncPreview::Enrollments(diff) => diff.is_empty(),
+        }
+    }
+}
+
+fn name(firstname: &Option<String>, lastname: &Option<String>, student_id: i32) -> String {
+    match (firstname, lastname) {
+        (None, None) => student_id.to_string(),
+        _ => format!(
+            "{} {} ({})",
+            firstname.clone().unwrap_or_default(),
+            lastname.clone().unwrap_or_default(),
+            student_id
+        ),
+    }
+}
+
+fn changes_text(changes: &[FieldChange]) -> String {
+    changes
+        .iter()
+        .map(|change| format!("{}: {} → {}", change.field, change.before, change.after))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+fn counts_view(counts: Vec<(&'static str, usize)>) -> impl IntoView {
+    view! {
+        <div class="grid grid-cols-3 gap-2 text-center">
+            {counts.into_iter().map(|(label, count)| view! {
+                <div class="p-2 bg-white rounded border border-[#DADADA]">
+                    <div class="text-lg font-semibold text-[#2E3A59]">{count}</div>
+                    <div class="text-xs text-[#2E3A59] text-opacity-70">{label}</div>
+                </div>
+            }).collect_view()}
+        </div>
+    }
+}
+
+fn withdrawn_view(withdrawn: Vec<SyncWithdrawal>) -> impl IntoView {
+    (!withdrawn.is_empty()).then(|| {
+        view! {
+            <div class=SECTION_TITLE>"Missing from the file"</div>
+            <ul class=LIST>
+                {withdrawn.into_iter().map(|withdrawal| view! {
+                    <li>
+                        {format!(
+                            "{}: {} enrollment(s) in {}",
+                            name(&withdrawal.firstname, &withdrawal.lastname, withdrawal.student_id),
+                            withdrawal.enrollment_ids.len(),
+                            withdrawal.academic_years.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+                        )}
+                    </li>
+                }).collect_view()}
+            </ul>
+        }
+    })
+}
+
+// The changes a sync would make, and the status students missing from the
+// file are withdrawn with
+#[component]
+pub fn RosterSyncPreview(
+    preview: SyncPreview,
+    withdrawal_status: ReadSignal<EnrollmentStatus>,
+    set_withdrawal_status: WriteSignal<EnrollmentStatus>,
+) -> impl IntoView {
+    let has_withdrawals = match &preview {
+        SyncPreview::Students(diff) => !diff.withdrawn.is_empty(),
+        SyncPreview::Enrollments(diff) => !diff.withdrawn.is_empty(),
+    };
+    let empty = preview.is_empty();
+
+    let details = match preview {
+        SyncPreview::Students(diff) => view! {
+            {counts_view(vec![
+                ("New", diff.new_students.len()),
+                ("Changed", diff.changed.len()),
+                ("Withdrawn", diff.withdrawn.len()),
+                ("Grade changes", diff.grade_changes()),
+                ("Teacher changes", diff.teacher_changes()),
+                ("Unchanged", diff.unchanged),
+            ])}
+            {(!diff.new_students.is_empty()).then(|| view! {
+                <div class=SECTION_TITLE>"New students"</div>
+                <ul class=LIST>
+                    {diff.new_students.iter().map(|student| view! {
+                        <li>
+                            {format!(
+                                "{} {} ({}), {}, {}",
+                                student.firstname,
+                                student.lastname,
+                                student.student_id,
+                                student.current_grade_level,
+                                student.teacher
+                            )}
+                        </li>
+                    }).collect_view()}
+                </ul>
+            })}
+            {(!diff.changed.is_empty()).then(|| view! {
+                <div class=SECTION_TITLE>"Changed students"</div>
+                <ul class=LIST>
+                    {diff.changed.iter().map(|change| view! {
+                        <li>
+                            <span class="font-medium">{name(&change.firstname, &change.lastname, change.student_id)}</span>
+                            {format!(": {}", changes_text(&change.changes))}
+                        </li>
+                    }).collect_view()}
+                </ul>
+            })}
+            {withdrawn_view(diff.withdrawn)}
+        }
+        .into_view(),
+        SyncPreview::Enrollments(diff) => view! {
+            {counts_view(vec![
+                ("New", diff.new_enrollments.len()),
+                ("Changed", diff.changed.len()),
+                ("Withdrawn", diff.withdrawn.len()),
+                ("Grade changes", diff.grade_changes()),
+                ("Teacher changes", diff.teacher_changes()),
+                ("Unchanged", diff.unchanged),
+            ])}
+            {(!diff.new_enrollments.is_empty()).then(|| view! {
+                <div class=SECTION_TITLE>"New enrollments"</div>
+                <ul class=LIST>
+                    {diff.new_enrollments.iter().map(|enrollment| view! {
+                        <li>
+                            {format!(
+                                "Student {}: {}, {}, teacher {}",
+                                enrollment.student_id,
+                                enrollment.academic_year,
+                                enrollment.grade_level,
+                                enrollment.teacher_id
+                            )}
+                        </li>
+                    }).collect_view()}
+                </ul>
+            })}
+            {(!diff.changed.is_empty()).then(|| view! {
+                <div class=SECTION_TITLE>"Changed enrollments"</div>
+                <ul class=LIST>
+                    {diff.changed.iter().map(|change| view! {
+                        <li>
+                            <span class="font-medium">
+                                {format!("Student {} ({})", change.student_id, change.academic_year)}
+                            </span>
+                            {format!(": {}", changes_text(&change.changes))}
+                        </li>
+                    }).collect_view()}
+                </ul>
+            })}
+            {withdrawn_view(diff.withdrawn)}
+        }
+        .into_view(),
+    };
+
+    view! {
+        <div class="mb-4 p-3 bg-gray-50 rounded border border-[#DADADA]">
+            {if empty {
+                view! {
+                    <div class="text-sm text-[#2E3A59]">"The file matches the database; there is nothing to sync."</div>
+                }
+                .into_view()
+            } else {
+                details
+            }}
+            {has_withdrawals.then(|| view! {
+                <label class="mt-3 flex items-center gap-2 text-sm text-[#2E3A59]">
+                    "Mark missing students as"
+                    <select
+                        class="p-1 border rounded"
+                        on:change=move |ev| {
+                            set_withdrawal_status.set(match event_target_value(&ev).as_str() {
+                                "dropped" => EnrollmentStatus::Dropped,
+                                _ => EnrollmentStatus::Transferred,
+                            })
+                        }
+                    >
+                        <option value="transferred" selected=move || withdrawal_status.get() == EnrollmentStatus::Transferred>
+                            "Transferred"
+                        </option>
+                        <option value="dropped" selected=move || withdrawal_status.get() == EnrollmentStatus::Dropped>
+                            "Dropped"
+                        </option>
+                    </select>
+                </label>
+            })}
+        </div>
+    }
+}
@@ -1,10 +1,17 @@
 use crate::app::models::student::Student;
 use crate::app::middleware::global_settings::use_settings;
 use crate::app::components::auth::enhanced_login_form::{use_student_mapping_service, DeAnonymizedStudent};
+use crate::app::components::student_page::flag_history_panel::FlagHistoryPanel;
+use crate::app::components::student_page::legal_hold_panel::LegalHoldPanel;
+use crate::app::models::user::SessionUser;
+use crate::app::server_functions::student_export::export_student_records;
 use leptos::*;
 use leptos_router::*;
 use std::rc::Rc;
 
+#[cfg(feature = "hydrate")]
+use {base64::Engine as _, js_sys::{Array, Uint8Array}, wasm_bindgen::JsCast};
+
 // Updated color scheme to match the palette
 const THEME_PRIMARY: &str = "#2E3A59"; // Navy blue
 const THEME_SECONDARY: &str = "#DADADA"; // Light gray
@@ -55,6 +62,53 @@ pub fn StudentDetails(
     // Create a memo for the student to ensure stable references
     let student_memo = create_memo(move |_| student_for_memo.clone());
 
+    // Records request export, offered to admins
+    let current_user = use_context::<ReadSignal<Option<SessionUser>>>();
+    let can_export = move || {
+        current_user
+            .and_then(|user| user.get())
+            .map(|user| user.is_admin())
+            .unwrap_or(false)
+    };
+    let (export_error, set_export_error) = create_signal::<Option<String>>(None);
+    let export_records = create_action(move |student_id: &i32| {
+        let student_id = *student_id;
+        async move {
+            set_export_error.set(None);
+            match export_student_records(student_id).await {
+                Ok(bundle) => {
+                    #[cfg(feature = "hydrate")]
+                    {
+                        let bytes = base64::engine::general_purpose::STANDARD
+                            .decode(&bundle.archive_base64)
+                            .unwrap_or_default();
+                        let blob = web_sys::Blob::new_with_u8_array_sequence(&Array::of1(&Uint8Array::from(bytes.as_slice())))
+                            .unwrap_or_else(|_| web_sys::Blob::new().unwrap());
+
+                        let url = web_sys::Url::create_object_url_with_blob(&blob).unwrap_or_default();
+
+                        if let Some(window) = web_sys::window() {
+                            if let Some(document) = window.document() {
+                                if let Ok(a) = document.create_element("a") {
+                                    let _ = a.set_attribute("href", &url);
+                                    let _ = a.set_attribute("download", &bundle.filename);
+
+                                    if let Some(html_element) = a.dyn_ref::<web_sys::HtmlElement>() {
+                                        html_element.click();
+                                    }
+                                }
+                            }
+                        }
+                        let _ = web_sys::Url::revoke_object_url(&url);
+                    }
+                    #[cfg(not(feature = "hydrate"))]
+                    let _ = bundle;
+                }
+                Err(e) => set_export_error.set(Some(format!("Failed to export records: {}", e))),
+            }
+        }
+    });
+
     // Function to get display name based on anonymization settings
     let get_display_name = move || {
         if anonymization_enabled() {
@@ -304,12 +358,28 @@ pub fn StudentDetails(
                                 }}
                             </div>
                         </div>
+                        <FlagHistoryPanel student_id=Signal::derive(move || student_memo().student_id) />
+                        <Show when=can_export>
+                            <LegalHoldPanel student_id=Signal::derive(move || student_memo().student_id) />
+                        </Show>
                     </div>
                 </div>
             </div>
 
+            {move || export_error.get().map(|message| view! {
+                <div class="mt-2 text-xs sm:text-sm text-red-600">{message}</div>
+            })}
+
             // Button container at the bottom - stacked on mobile
             <div class=BUTTON_CONTAINER>
+                <Show when=can_export>
+                    <button class=BUTTON_SECONDARY
+                        prop:disabled=move || export_records.pending().get()
+                        on:click=move |_| export_records.dispatch(student_memo().student_id)
+                    >
+                        {move || if export_records.pending().get() { "Exporting..." } else { "Export Records" }}
+                    </button>
+                </Show>
                 <button class=BUTTON_ACCENT
                     on:click=move |_| {
                         if let Some(callback) = on_edit_student {
@@ -0,0 +1,30 @@
+use crate::app::models::student_group::StudentGroup;
+use leptos::*;
+
+// Picks one of the caller's active student groups to narrow a student list
+// to its current members; hidden when there are none
+#[component]
+pub fn StudentGroupFilter(
+    #[prop(into)] groups: Signal<Vec<StudentGroup>>,
+    #[prop(into)] selected: Signal<Option<i32>>,
+    set_selected: WriteSignal<Option<i32>>,
+    #[prop(optional, into)] class: Option<String>,
+) -> impl IntoView {
+    let class = class
+        .unwrap_or_else(|| "border border-gray-300 rounded px-2 py-1 text-sm bg-white".to_string());
+
+    view! {
+        <Show when=move || groups.with(|groups| groups.iter().any(|group| group.active))>
+            <select
+                class=class.clone()
+                prop:value=move || selected.get().map(|id| id.to_string()).unwrap_or_default()
+                on:change=move |ev| set_selected.set(event_target_value(&ev).parse().ok())
+            >
+                <option value="">"All students"</option>
+                {move || groups.get().into_iter().filter(|group| group.active).map(|group| view! {
+                    <option value=group.id.to_string()>{format!("Group: {}", group.name)}</option>
+                }).collect_view()}
+            </select>
+        </Show>
+    }
+}
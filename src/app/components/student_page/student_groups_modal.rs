@@ -0,0 +1,452 @@
+use crate::app::models::employee::Employee;
+use crate::app::models::student::Student;
+use crate::app::models::student_group::{
+    GroupMember, GroupPurpose, GroupTestSummary, SaveStudentGroupRequest, StudentGroup,
+};
+use crate::app::server_functions::student_groups::{
+    delete_student_group, get_group_test_summaries, get_student_groups, save_student_group,
+};
+use chrono::{NaiveDate, Utc};
+use leptos::*;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const FIELD: &str = "px-2 py-1 rounded border border-[#DADADA] bg-white text-sm text-[#2E3A59]";
+const LINK_BUTTON: &str = "text-xs text-[#2E3A59] underline hover:no-underline";
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+// Named groups of students across grades and classes, such as intervention
+// or ELL groups. Admins create groups and set their members; a group's
+// owner and teachers of its students can open its results.
+#[component]
+pub fn StudentGroupsModal(
+    set_show_modal: WriteSignal<bool>,
+    #[prop(into)] students: Signal<Vec<Student>>,
+    #[prop(into)] teachers: Signal<Vec<Employee>>,
+    #[prop(into)] can_manage: Signal<bool>,
+) -> impl IntoView {
+    let (refresh, set_refresh) = create_signal(0);
+    let groups = create_local_resource(
+        move || refresh.get(),
+        |_| async move { get_student_groups().await },
+    );
+    let (status_message, set_status_message) = create_signal::<Option<(String, bool)>>(None);
+    let today = Utc::now().date_naive();
+
+    let student_names = create_memo(move |_| {
+        students
+            .get()
+            .into_iter()
+            .map(|student| {
+                let name = format!(
+                    "{} {}",
+                    student.firstname.unwrap_or_default(),
+                    student.lastname.unwrap_or_default()
+                );
+                (student.student_id, name)
+            })
+            .collect::<HashMap<i32, String>>()
+    });
+    let student_name = move |student_id: i32| {
+        student_names
+            .with(|names| names.get(&student_id).cloned())
+            .unwrap_or_else(|| format!("Student {}", student_id))
+    };
+
+    // Group form; editing_id is None for a new group
+    let (editing, set_editing) = create_signal(false);
+    let (editing_id, set_editing_id) = create_signal::<Option<i32>>(None);
+    let (name, set_name) = create_signal(String::new());
+    let (purpose, set_purpose) = create_signal(GroupPurpose::Intervention);
+    let (owner_id, set_owner_id) = create_signal::<Option<i32>>(None);
+    let (description, set_description) = create_signal(String::new());
+    let (active, set_active) = create_signal(true);
+    let (members, set_members) = create_signal::<Vec<GroupMember>>(Vec::new());
+    let (new_member, set_new_member) = create_signal::<Option<i32>>(None);
+
+    let edit_group = move |group: Option<StudentGroup>| {
+        let group = group.unwrap_or_else(|| StudentGroup {
+            id: 0,
+            name: String::new(),
+            owner_id: None,
+            owner_name: None,
+            purpose: GroupPurpose::Intervention,
+            description: None,
+            active: true,
+            members: Vec::new(),
+        });
+        set_editing_id.set((group.id != 0).then_some(group.id));
+        set_name.set(group.name);
+        set_purpose.set(group.purpose);
+        set_owner_id.set(group.owner_id);
+        set_description.set(group.description.unwrap_or_default());
+        set_active.set(group.active);
+        set_members.set(group.members);
+        set_editing.set(true);
+    };
+    let update_member = move |student_id: i32, update: Box<dyn Fn(&mut GroupMember)>| {
+        set_members.update(|members| {
+            if let Some(member) = members.iter_mut().find(|m| m.student_id == student_id) {
+                update(member);
+            }
+        })
+    };
+
+    // Group whose results are shown
+    let (report_group, set_report_group) = create_signal::<Option<(i32, String)>>(None);
+    let report = create_local_resource(
+        move || report_group.get().map(|(id, _)| id),
+        |group_id| async move {
+            match group_id {
+                Some(group_id) => get_group_test_summaries(group_id).await.map(Some),
+                None => Ok(None),
+            }
+        },
+    );
+
+    let save_action = create_action(move |request: &SaveStudentGroupRequest| {
+        let request = request.clone();
+        async move {
+            match save_student_group(request).await {
+                Ok(group) => {
+                    set_status_message.set(Some((format!("Saved \"{}\"", group.name), true)));
+                    set_editing.set(false);
+                    set_refresh.update(|count| *count += 1);
+                }
+                Err(e) => {
+                    set_status_message.set(Some((format!("Failed to save group: {}", e), false)))
+                }
+            }
+        }
+    });
+
+    let delete_action = create_action(move |id: &i32| {
+        let id = *id;
+        async move {
+            match delete_student_group(id).await {
+                Ok(group) => {
+                    set_status_message.set(Some((format!("Deleted \"{}\"", group.name), true)));
+                    set_refresh.update(|count| *count += 1);
+                }
+                Err(e) => {
+                    set_status_message.set(Some((format!("Failed to delete group: {}", e), false)))
+                }
+            }
+        }
+    });
+
+    let group_row = move |group: StudentGroup| {
+        let id = group.id;
+        let current = group.member_ids_on(today).len();
+        let for_edit = group.clone();
+        let for_report = (group.id, group.name.clone());
+        let details = format!(
+            "{} · {} · {} current member{}",
+            group.purpose.label(),
+            group
+                .owner_name
+                .clone()
+                .unwrap_or_else(|| "No owner".to_string()),
+            current,
+            if current == 1 { "" } else { "s" }
+        );
+        view! {
+            <div class="flex items-center justify-between p-3 rounded border border-[#DADADA] bg-white">
+                <div>
+                    <div class="font-semibold text-sm text-[#2E3A59]">
+                        {group.name.clone()}
+                        {(!group.active).then(|| view! { <span class="font-normal text-gray-500">" (inactive)"</span> })}
+                    </div>
+                    <div class="text-xs text-[#2E3A59] text-opacity-70">{details}</div>
+                </div>
+                <div class="flex gap-3">
+                    <button class=LINK_BUTTON on:click=move |_| set_report_group.set(Some(for_report.clone()))>
+                        "Results"
+                    </button>
+                    <Show when=move || can_manage.get()>
+                        <button class=LINK_BUTTON on:click={
+                            let for_edit = for_edit.clone();
+                            move |_| edit_group(Some(for_edit.clone()))
+                        }>
+                            "Edit"
+                        </button>
+                        <button class="text-xs text-red-600 underline hover:no-underline" on:click=move |_| delete_action.dispatch(id)>
+                            "Delete"
+                        </button>
+                    </Show>
+                </div>
+            </div>
+        }
+    };
+
+    let summary_row = |summary: GroupTestSummary| {
+        let percent = summary
+            .average_percent()
+            .map(|percent| format!("{:.0}%", percent))
+            .unwrap_or_default();
+        view! {
+            <tr class="border-t border-[#DADADA]">
+                <td class="py-1">{summary.test_name}</td>
+                <td class="py-1 text-right">{summary.students_tested}</td>
+                <td class="py-1 text-right">{format!("{:.1} / {}", summary.average_score, summary.max_score)}</td>
+                <td class="py-1 text-right">{percent}</td>
+            </tr>
+        }
+    };
+
+    view! {
+        <div class="fixed inset-0 bg-black bg-opacity-50 flex items-center justify-center z-50">
+            <div class="bg-[#F9F9F8] p-6 rounded-lg shadow-xl max-w-3xl w-full max-h-[90vh] overflow-y-auto space-y-4">
+                <h3 class="text-xl font-bold text-[#2E3A59]">"Student Groups"</h3>
+
+                <Show when=move || !editing.get()>
+                    <Suspense fallback=|| view! { <div class="text-sm text-gray-500">"Loading groups..."</div> }>
+                        {move || groups.get().map(|result| match result {
+                            Err(e) => view! {
+                                <div class="text-sm text-red-600">{format!("Failed to load groups: {}", e)}</div>
+                            }.into_view(),
+                            Ok(groups) if groups.is_empty() => view! {
+                                <div class="text-sm text-gray-500">"No student groups yet."</div>
+                            }.into_view(),
+                            Ok(groups) => view! {
+                                <div class="space-y-2">{groups.into_iter().map(group_row).collect_view()}</div>
+                            }.into_view(),
+                        })}
+                    </Suspense>
+                    <Show when=move || can_manage.get()>
+                        <button
+                            class="px-3 py-1.5 text-sm rounded border border-[#2E3A59] text-[#2E3A59] hover:bg-[#2E3A59] hover:text-white"
+                            on:click=move |_| edit_group(None)
+                        >
+                            "New Group"
+                        </button>
+                    </Show>
+                </Show>
+
+                <Show when=move || editing.get()>
+                    <div class="p-4 rounded border border-[#DADADA] bg-white space-y-3">
+                        <div class="font-semibold text-[#2E3A59]">
+                            {move || if editing_id.get().is_some() { "Edit group" } else { "New group" }}
+                        </div>
+                        <div class="grid grid-cols-2 gap-3">
+                            <input
+                                type="text"
+                                placeholder="Group name"
+                                class=FIELD
+                                prop:value=move || name.get()
+                                on:input=move |ev| set_name.set(event_target_value(&ev))
+                            />
+                            <select
+                                class=FIELD
+                                prop:value=move || purpose.get().as_str()
+                                on:change=move |ev| {
+                                    if let Ok(purpose) = GroupPurpose::from_str(&event_target_value(&ev)) {
+                                        set_purpose.set(purpose);
+                                    }
+                                }
+                            >
+                                {GroupPurpose::ALL.into_iter().map(|option| view! {
+                                    <option value=option.as_str()>{option.label()}</option>
+                                }).collect_view()}
+                            </select>
+                            <select
+                                class=FIELD
+                                prop:value=move || owner_id.get().map(|id| id.to_string()).unwrap_or_default()
+                                on:change=move |ev| set_owner_id.set(event_target_value(&ev).parse().ok())
+                            >
+                                <option value="">"No owner"</option>
+                                {move || teachers.get().into_iter().map(|teacher| view! {
+                                    <option value=teacher.id.to_string()>
+                                        {format!("{} {}", teacher.firstname, teacher.lastname)}
+                                    </option>
+                                }).collect_view()}
+                            </select>
+                            <label class="flex items-center gap-2 text-sm text-[#2E3A59]">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || active.get()
+                                    on:change=move |ev| set_active.set(event_target_checked(&ev))
+                                />
+                                "Active"
+                            </label>
+                        </div>
+                        <textarea
+                            placeholder="Description"
+                            class=format!("{} w-full", FIELD)
+                            prop:value=move || description.get()
+                            on:input=move |ev| set_description.set(event_target_value(&ev))
+                        />
+
+                        <div class="text-sm font-semibold text-[#2E3A59]">"Members"</div>
+                        <table class="w-full text-sm text-[#2E3A59]">
+                            <thead>
+                                <tr class="text-left text-xs text-opacity-70">
+                                    <th>"Student"</th>
+                                    <th>"Joined"</th>
+                                    <th>"Left"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                <For
+                                    each=move || members.get()
+                                    key=|member| member.student_id
+                                    children=move |member| {
+                                        let student_id = member.student_id;
+                                        view! {
+                                            <tr>
+                                                <td>{student_name(student_id)}</td>
+                                                <td>
+                                                    <input
+                                                        type="date"
+                                                        class=FIELD
+                                                        prop:value=member.start_date.format("%Y-%m-%d").to_string()
+                                                        on:change=move |ev| {
+                                                            if let Some(date) = parse_date(&event_target_value(&ev)) {
+                                                                update_member(student_id, Box::new(move |member| member.start_date = date));
+                                                            }
+                                                        }
+                                                    />
+                                                </td>
+                                                <td>
+                                                    <input
+                                                        type="date"
+                                                        class=FIELD
+                                                        prop:value=member.end_date.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_default()
+                                                        on:change=move |ev| {
+                                                            let date = parse_date(&event_target_value(&ev));
+                                                            update_member(student_id, Box::new(move |member| member.end_date = date));
+                                                        }
+                                                    />
+                                                </td>
+                                                <td class="text-right">
+                                                    <button
+                                                        class="text-xs text-red-600 underline hover:no-underline"
+                                                        on:click=move |_| set_members.update(|members| members.retain(|m| m.student_id != student_id))
+                                                    >
+                                                        "Remove"
+                                                    </button>
+                                                </td>
+                                            </tr>
+                                        }
+                                    }
+                                />
+                            </tbody>
+                        </table>
+                        <div class="flex gap-2">
+                            <select
+                                class=format!("{} flex-1", FIELD)
+                                prop:value=move || new_member.get().map(|id| id.to_string()).unwrap_or_default()
+                                on:change=move |ev| set_new_member.set(event_target_value(&ev).parse().ok())
+                            >
+                                <option value="">"Add a student..."</option>
+                                {move || {
+                                    let current = members.get();
+                                    students.get().into_iter()
+                                        .filter(|student| !current.iter().any(|m| m.student_id == student.student_id))
+                                        .map(|student| view! {
+                                            <option value=student.student_id.to_string()>
+                                                {format!("{} - {}", student_name(student.student_id), student.student_id)}
+                                            </option>
+                                        })
+                                        .collect_view()
+                                }}
+                            </select>
+                            <button
+                                class="px-3 py-1 text-sm rounded border border-[#2E3A59] text-[#2E3A59] disabled:opacity-50"
+                                prop:disabled=move || new_member.get().is_none()
+                                on:click=move |_| {
+                                    if let Some(student_id) = new_member.get() {
+                                        set_members.update(|members| members.push(GroupMember {
+                                            student_id,
+                                            start_date: today,
+                                            end_date: None,
+                                        }));
+                                        set_new_member.set(None);
+                                    }
+                                }
+                            >
+                                "Add"
+                            </button>
+                        </div>
+
+                        <div class="flex justify-end gap-2">
+                            <button class="px-3 py-1.5 text-sm text-[#2E3A59]" on:click=move |_| set_editing.set(false)>
+                                "Cancel"
+                            </button>
+                            <button
+                                class="px-3 py-1.5 bg-[#2E3A59] text-white text-sm rounded disabled:opacity-50"
+                                prop:disabled=move || save_action.pending().get()
+                                on:click=move |_| {
+                                    let description = description.get();
+                                    save_action.dispatch(SaveStudentGroupRequest {
+                                        id: editing_id.get(),
+                                        name: name.get(),
+                                        owner_id: owner_id.get(),
+                                        purpose: purpose.get(),
+                                        description: (!description.trim().is_empty()).then_some(description),
+                                        active: active.get(),
+                                        members: members.get(),
+                                    });
+                                }
+                            >
+                                "Save group"
+                            </button>
+                        </div>
+                    </div>
+                </Show>
+
+                {move || report_group.get().map(|(_, group_name)| view! {
+                    <div class="p-4 rounded border border-[#DADADA] bg-white space-y-2">
+                        <div class="flex justify-between">
+                            <div class="font-semibold text-[#2E3A59]">{format!("Results: {}", group_name)}</div>
+                            <button class=LINK_BUTTON on:click=move |_| set_report_group.set(None)>"Close"</button>
+                        </div>
+                        <div class="text-xs text-[#2E3A59] text-opacity-70">
+                            "Each member's latest attempt taken while they were in the group"
+                        </div>
+                        <Suspense fallback=|| view! { <div class="text-sm text-gray-500">"Loading results..."</div> }>
+                            {move || report.get().map(|result| match result {
+                                Err(e) => view! {
+                                    <div class="text-sm text-red-600">{format!("Failed to load results: {}", e)}</div>
+                                }.into_view(),
+                                Ok(Some(summaries)) if !summaries.is_empty() => view! {
+                                    <table class="w-full text-sm text-[#2E3A59]">
+                                        <thead>
+                                            <tr class="text-left text-xs">
+                                                <th>"Test"</th>
+                                                <th class="text-right">"Students tested"</th>
+                                                <th class="text-right">"Average score"</th>
+                                                <th class="text-right">"Average %"</th>
+                                            </tr>
+                                        </thead>
+                                        <tbody>{summaries.into_iter().map(summary_row).collect_view()}</tbody>
+                                    </table>
+                                }.into_view(),
+                                Ok(_) => view! {
+                                    <div class="text-sm text-gray-500">"No scores recorded for this group yet."</div>
+                                }.into_view(),
+                            })}
+                        </Suspense>
+                    </div>
+                })}
+
+                {move || status_message.get().map(|(message, ok)| view! {
+                    <div class=if ok { "text-sm text-green-700" } else { "text-sm text-red-600" }>{message}</div>
+                })}
+
+                <div class="flex justify-end">
+                    <button
+                        class="px-4 py-2 bg-[#F9F9F8] hover:bg-[#DADADA] text-[#2E3A59] border border-[#DADADA] rounded-md text-sm"
+                        on:click=move |_| set_show_modal.set(false)
+                    >
+                        "Close"
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
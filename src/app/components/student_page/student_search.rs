@@ -0,0 +1,38 @@
+use crate::app::models::student_search::{StudentSearchRequest, MAX_SEARCH_PAGE_SIZE};
+use crate::app::server_functions::students::search_students;
+use leptos::*;
+use std::collections::HashMap;
+
+// Server-ranked matches for `query` as each student's position in the
+// ranking, or None while the query is blank. Student lists already loaded
+// on the page filter and sort by it.
+pub fn use_student_search_ranks(query: Signal<String>) -> Signal<Option<HashMap<i32, usize>>> {
+    let trimmed = create_memo(move |_| query.get().trim().to_string());
+    let results = create_local_resource(
+        move || trimmed.get(),
+        |query| async move {
+            if query.is_empty() {
+                return None;
+            }
+            let request = StudentSearchRequest {
+                per_page: MAX_SEARCH_PAGE_SIZE,
+                ..StudentSearchRequest::new(query)
+            };
+            match search_students(request).await {
+                Ok(results) => Some(results.ranks()),
+                Err(e) => {
+                    log::error!("Student search failed: {}", e);
+                    None
+                }
+            }
+        },
+    );
+
+    Signal::derive(move || {
+        if trimmed.with(String::is_empty) {
+            None
+        } else {
+            results.get().flatten()
+        }
+    })
+}
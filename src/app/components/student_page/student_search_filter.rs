@@ -1,3 +1,5 @@
+use crate::app::components::student_page::custom_attribute_fields::AttributeFilterControl;
+use crate::app::models::student_attribute::{AttributeDefinition, AttributeFilter};
 use leptos::*;
 
 // More responsive container style with padding adjustments for small screens
@@ -45,6 +47,9 @@ pub fn SearchFilter(
     #[prop(into)] search_term: Signal<String>,
     #[prop(into)] on_clear_filters: Callback<()>,
     #[prop(into)] is_panel_expanded: Signal<bool>,
+    #[prop(into)] attribute_definitions: Signal<Vec<AttributeDefinition>>,
+    #[prop(into)] attribute_filter: Signal<Option<AttributeFilter>>,
+    set_attribute_filter: WriteSignal<Option<AttributeFilter>>,
 ) -> impl IntoView {
     let iep_checkbox_ref = create_node_ref::<html::Input>();
     let esl_checkbox_ref = create_node_ref::<html::Input>();
@@ -236,6 +241,12 @@ pub fn SearchFilter(
                     />
                     <label for="bip-filter" class="text-xs sm:text-sm text-gray-700">"BEH"</label>
                 </div>
+
+                <AttributeFilterControl
+                    definitions=attribute_definitions
+                    filter=attribute_filter
+                    set_filter=set_attribute_filter
+                />
             </div>
 
             // Clear filters button
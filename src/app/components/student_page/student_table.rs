@@ -4,7 +4,11 @@ use crate::app::components::enhanced_login_form::{
 use crate::app::middleware::global_settings::use_settings;
 use crate::app::models::student::ESLEnum;
 use crate::app::models::student::Student;
+use crate::app::models::student_attribute::{
+    AttributeDefinition, AttributeFilter, AttributeValues,
+};
 use leptos::*;
+use std::collections::HashMap;
 use std::rc::Rc;
 
 // Base colors
@@ -38,6 +42,8 @@ const SELECTED_ROW_STYLE: &str =
 pub fn StudentTable(
     #[prop(into)] students: Resource<i32, Option<Vec<Student>>>,
     #[prop(into)] search_term: Signal<String>,
+    // Server-side search ranking for `search_term`, see use_student_search_ranks
+    #[prop(into)] search_ranks: Signal<Option<HashMap<i32, usize>>>,
     #[prop(into)] grade_filter: Signal<String>,
     #[prop(into)] teacher_filter: Signal<String>,
     #[prop(into)] iep_filter: Signal<bool>,
@@ -47,6 +53,9 @@ pub fn StudentTable(
     #[prop(into)] readplan_filter: Signal<bool>,
     #[prop(into)] gt_filter: Signal<bool>,
     #[prop(into)] bip_filter: Signal<bool>,
+    #[prop(into)] attribute_definitions: Signal<Vec<AttributeDefinition>>,
+    #[prop(into)] attribute_values: Signal<HashMap<i32, AttributeValues>>,
+    #[prop(into)] attribute_filter: Signal<Option<AttributeFilter>>,
     #[prop(into)] is_panel_expanded: Signal<bool>,
     #[prop(into)] selected_student: Signal<Option<Rc<Student>>>,
     #[prop(into)] set_selected_student: WriteSignal<Option<Rc<Student>>>,
@@ -83,6 +92,7 @@ pub fn StudentTable(
 
     let filtered_students = create_memo(move |_| {
         let search = search_term().trim().to_lowercase();
+        let ranks = search_ranks();
         let current_grade_level = grade_filter();
         let teacher = teacher_filter();
         let show_iep = iep_filter();
@@ -92,8 +102,11 @@ pub fn StudentTable(
         let show_readplan = readplan_filter();
         let show_gt = gt_filter();
         let show_bip = bip_filter();
+        let attribute_filter = attribute_filter();
+        let definitions = attribute_definitions();
+        let values = attribute_values();
 
-        enhanced_students()
+        let mut filtered = enhanced_students()
             .into_iter()
             .filter(|(student, de_anon_opt)| {
                 // Use de-anonymized data for search if available
@@ -121,11 +134,15 @@ pub fn StudentTable(
                     )
                 };
 
-                // Filter by search term (now using de-anonymized data when available)
+                // Filter by search term (now using de-anonymized data when available),
+                // or by the server's typo-tolerant match
                 let matches_search = search.is_empty()
                     || search_firstname.to_lowercase().contains(&search)
                     || search_lastname.to_lowercase().contains(&search)
-                    || search_id.to_lowercase().contains(&search);
+                    || search_id.to_lowercase().contains(&search)
+                    || ranks
+                        .as_ref()
+                        .is_some_and(|ranks| ranks.contains_key(&student.student_id));
 
                 // Filter by grade
                 let matches_grade = current_grade_level.is_empty()
@@ -159,6 +176,11 @@ pub fn StudentTable(
                 let matches_gt = !show_gt || student.gt;
                 let matches_bip = !show_bip || student.bip;
 
+                // Filter by custom attribute
+                let matches_attribute = attribute_filter.as_ref().map_or(true, |filter| {
+                    filter.matches(&definitions, values.get(&student.student_id))
+                });
+
                 matches_search
                     && matches_grade
                     && matches_teacher
@@ -169,8 +191,20 @@ pub fn StudentTable(
                     && matches_readplan
                     && matches_gt
                     && matches_bip
+                    && matches_attribute
             })
-            .collect::<Vec<_>>()
+            .collect::<Vec<_>>();
+
+        // Best server matches first; students only matched locally follow
+        if let Some(ranks) = &ranks {
+            filtered.sort_by_key(|(student, _)| {
+                ranks
+                    .get(&student.student_id)
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        filtered
     });
 
     view! {
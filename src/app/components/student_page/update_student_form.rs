@@ -1,5 +1,8 @@
+use crate::app::components::student_page::custom_attribute_fields::CustomAttributeFields;
 use crate::app::models::student::{ESLEnum, GenderEnum, GradeEnum, InterventionEnum, Student};
+use crate::app::models::student_attribute::AttributeValues;
 use crate::app::models::UpdateStudentRequest;
+use crate::app::server_functions::student_attributes::get_student_attribute_values;
 use crate::app::server_functions::students::edit_student;
 use leptos::*;
 use std::rc::Rc;
@@ -57,6 +60,23 @@ pub fn UpdateStudent(
     let (notes, set_notes) = create_signal(student.notes.clone());
     let (pin, set_pin) = create_signal(student.pin.unwrap_or(0).to_string());
 
+    // Custom attribute values; left unchanged on save until they have loaded
+    let (attributes, set_attributes) = create_signal(AttributeValues::new());
+    let (attributes_loaded, set_attributes_loaded) = create_signal(false);
+    let attribute_student_id = student.student_id;
+    let saved_attributes = create_local_resource(
+        || (),
+        move |_| async move { get_student_attribute_values(vec![attribute_student_id]).await },
+    );
+    create_effect(move |_| match saved_attributes.get() {
+        Some(Ok(mut values)) => {
+            set_attributes(values.remove(&attribute_student_id).unwrap_or_default());
+            set_attributes_loaded(true);
+        }
+        Some(Err(e)) => log::error!("Failed to load custom attributes: {}", e),
+        None => {}
+    });
+
     // For handling form submission
     let (is_submitting, set_is_submitting) = create_signal(false);
     let (error_message, set_error_message) = create_signal(String::new());
@@ -221,6 +241,7 @@ pub fn UpdateStudent(
             eye_glasses: eye_glasses(),
             notes: notes(),
             pin: validated_pin,
+            custom_attributes: attributes_loaded().then(|| attributes()),
         };
 
         spawn_local(async move {
@@ -538,6 +559,7 @@ pub fn UpdateStudent(
                             </div>
                         </div>
                     </div>
+                    <CustomAttributeFields values=attributes set_values=set_attributes />
                 </div>
                 <div class=BUTTON_CONTAINER_STYLE>
                     <button
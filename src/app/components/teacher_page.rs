@@ -1,6 +1,7 @@
 pub mod add_employee_form;
 pub mod delete_confirmation;
 pub mod employee_details;
+pub mod employee_link_selector;
 pub mod employee_table;
 pub mod role_selector;
 pub mod search_filter;
@@ -11,6 +12,7 @@ pub mod user_table;
 pub use add_employee_form::*;
 pub use delete_confirmation::*;
 pub use employee_details::*;
+pub use employee_link_selector::*;
 pub use employee_table::*;
 pub use role_selector::*;
 pub use search_filter::*;
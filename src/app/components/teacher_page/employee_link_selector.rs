@@ -0,0 +1,59 @@
+use crate::app::models::Employee;
+use crate::app::server_functions::users::link_user_to_employee;
+use leptos::*;
+
+// Lets an admin pick which employee record a teacher account teaches as.
+// Teachers only see students enrolled with that employee, so an unlinked
+// teacher account sees no students at all.
+#[component]
+pub fn EmployeeLinkSelector(
+    user_id: i64,
+    linked_employee_id: Option<i32>,
+    teachers: Vec<Employee>,
+    #[prop(into)] on_link_updated: Callback<()>,
+) -> impl IntoView {
+    let (is_updating, set_is_updating) = create_signal(false);
+
+    let update_link = create_action(move |employee_id: &Option<i32>| {
+        let employee_id = *employee_id;
+
+        async move {
+            set_is_updating(true);
+
+            match link_user_to_employee(user_id, employee_id).await {
+                Ok(_) => {
+                    log::info!("Successfully linked user {} to employee", user_id);
+                    on_link_updated(());
+                }
+                Err(e) => {
+                    log::error!("Failed to link user to employee: {:?}", e);
+                }
+            }
+
+            set_is_updating(false);
+        }
+    });
+
+    view! {
+        <select
+            class="px-2 py-1 text-xs border border-gray-300 rounded-md bg-white text-[#2E3A59] focus:outline-none focus:ring-1 focus:ring-[#2E3A59]"
+            class:opacity-50=is_updating
+            disabled=is_updating
+            on:click=|e| e.stop_propagation()
+            on:change=move |ev| {
+                let employee_id = event_target_value(&ev).parse::<i32>().ok();
+                update_link.dispatch(employee_id);
+            }
+        >
+            <option value="" selected=linked_employee_id.is_none()>"Not linked"</option>
+            {teachers.into_iter().map(|teacher| {
+                let is_selected = linked_employee_id == Some(teacher.id);
+                view! {
+                    <option value=teacher.id.to_string() selected=is_selected>
+                        {format!("{} {}", teacher.firstname, teacher.lastname)}
+                    </option>
+                }
+            }).collect_view()}
+        </select>
+    }
+}
@@ -1,5 +1,11 @@
+use crate::app::components::teacher_page::employee_link_selector::EmployeeLinkSelector;
 use crate::app::components::teacher_page::role_selector::RoleSelector;
 use crate::app::models::user::{User, UserRole};
+use crate::app::server_functions::teachers::get_teachers;
+use crate::app::server_functions::users::{
+    get_locked_users, get_mfa_enabled_users, get_user_employee_links, reset_user_mfa,
+    sign_out_user_everywhere, unlock_user_login,
+};
 use leptos::*;
 
 const TABLE_CONTAINER_STYLE: &str =
@@ -55,6 +61,55 @@ pub fn UserTable(
             .collect::<Vec<_>>()
     });
 
+    // Teacher accounts are linked to an employee record to scope their caseload
+    let (links_refresh, set_links_refresh) = create_signal(0);
+    let employee_links = create_resource(links_refresh, |_| async move {
+        get_user_employee_links().await.unwrap_or_default()
+    });
+    let teachers = create_resource(
+        || (),
+        |_| async move { get_teachers().await.unwrap_or_default() },
+    );
+
+    // Accounts locked out after repeated failed password logins
+    let (locks_refresh, set_locks_refresh) = create_signal(0);
+    let locked_users = create_resource(locks_refresh, |_| async move {
+        get_locked_users().await.unwrap_or_default()
+    });
+    let unlock_user = create_action(move |user_id: &i64| {
+        let user_id = *user_id;
+        async move {
+            if let Err(e) = unlock_user_login(user_id).await {
+                log::error!("Failed to unlock user {}: {}", user_id, e);
+            }
+            set_locks_refresh.update(|n| *n += 1);
+        }
+    });
+
+    // Accounts with an authenticator enrolled, which an admin can reset
+    let (mfa_refresh, set_mfa_refresh) = create_signal(0);
+    let mfa_users = create_resource(mfa_refresh, |_| async move {
+        get_mfa_enabled_users().await.unwrap_or_default()
+    });
+    let reset_mfa = create_action(move |user_id: &i64| {
+        let user_id = *user_id;
+        async move {
+            if let Err(e) = reset_user_mfa(user_id).await {
+                log::error!("Failed to reset MFA for user {}: {}", user_id, e);
+            }
+            set_mfa_refresh.update(|n| *n += 1);
+        }
+    });
+
+    let sign_out_everywhere = create_action(move |user_id: &i64| {
+        let user_id = *user_id;
+        async move {
+            if let Err(e) = sign_out_user_everywhere(user_id).await {
+                log::error!("Failed to sign out user {}: {}", user_id, e);
+            }
+        }
+    });
+
     let container_class = create_memo(move |_| {
         format!(
             "{} transition-all duration-300 ease-in-out",
@@ -86,11 +141,13 @@ pub fn UserTable(
                             <th class=HEADER_CELL_STYLE>"Phone"</th>
                             <th class=HEADER_CELL_STYLE>"Account Status"</th>
                             <th class=HEADER_CELL_STYLE>"Role"</th>
+                            <th class=HEADER_CELL_STYLE>"Teaches As"</th>
+                            <th class=HEADER_CELL_STYLE>"Sign-in"</th>
                         </tr>
                     </thead>
                     <Suspense fallback=move || view! {
                         <tr>
-                            <td colspan="7" class="text-center p-8">
+                            <td colspan="9" class="text-center p-8">
                                 <div class="inline-block h-6 w-6 animate-spin rounded-full border-2 border-[#DADADA] border-t-[#2E3A59]"></div>
                             </td>
                         </tr>
@@ -101,7 +158,7 @@ pub fn UserTable(
                                 if users.is_empty() {
                                     view! {
                                         <tr>
-                                            <td colspan="7" class="px-6 py-12 text-center text-sm text-gray-500">
+                                            <td colspan="9" class="px-6 py-12 text-center text-sm text-gray-500">
                                                 "No users match your search criteria"
                                             </td>
                                         </tr>
@@ -115,6 +172,8 @@ pub fn UserTable(
                                         let phone = user.phone_number.clone().unwrap_or_default();
                                         let status = user.account_status.to_string();
                                         let user_for_role_selector = user.clone();
+                                        let user_id = user.id;
+                                        let is_teacher = user.role == UserRole::Teacher;
 
                                         view! {
                                             <tr class="hover:bg-[#DADADA] hover:bg-opacity-70 cursor-pointer border-b border-[#DADADA]">
@@ -135,6 +194,71 @@ pub fn UserTable(
                                                         })
                                                     />
                                                 </td>
+                                                <td class=format!("{} {}", CELL_STYLE, "font-medium text-[#2E3A59]")>
+                                                    {move || if is_teacher {
+                                                        let linked_employee_id = employee_links
+                                                            .get()
+                                                            .and_then(|links| links.get(&user_id).copied());
+                                                        view! {
+                                                            <EmployeeLinkSelector
+                                                                user_id=user_id
+                                                                linked_employee_id=linked_employee_id
+                                                                teachers=teachers.get().unwrap_or_default()
+                                                                on_link_updated=Callback::new(move |_: ()| {
+                                                                    set_links_refresh.update(|n| *n += 1);
+                                                                })
+                                                            />
+                                                        }.into_view()
+                                                    } else {
+                                                        view! { <span class="text-gray-400">"—"</span> }.into_view()
+                                                    }}
+                                                </td>
+                                                <td class=format!("{} {}", CELL_STYLE, "font-medium text-[#2E3A59]")>
+                                                    {move || match locked_users.get().and_then(|locked| locked.get(&user_id).copied()) {
+                                                        Some(locked_until) => {
+                                                            let label = format!("Locked until {}", locked_until.with_timezone(&chrono::Local).format("%H:%M"));
+                                                            view! {
+                                                                <div class="flex items-center gap-2">
+                                                                    <span class="text-red-600">{label}</span>
+                                                                    <button
+                                                                        class="px-2 py-1 text-xs rounded border border-[#2E3A59] text-[#2E3A59] hover:bg-[#2E3A59] hover:text-white"
+                                                                        on:click=move |ev| {
+                                                                            ev.stop_propagation();
+                                                                            unlock_user.dispatch(user_id);
+                                                                        }
+                                                                    >
+                                                                        "Unlock"
+                                                                    </button>
+                                                                </div>
+                                                            }.into_view()
+                                                        }
+                                                        None => view! { <span class="text-gray-400">"—"</span> }.into_view(),
+                                                    }}
+                                                    {move || mfa_users.get().filter(|ids| ids.contains(&user_id)).map(|_| view! {
+                                                        <div class="flex items-center gap-2 mt-1">
+                                                            <span class="text-green-700">"MFA on"</span>
+                                                            <button
+                                                                class="px-2 py-1 text-xs rounded border border-[#2E3A59] text-[#2E3A59] hover:bg-[#2E3A59] hover:text-white"
+                                                                on:click=move |ev| {
+                                                                    ev.stop_propagation();
+                                                                    reset_mfa.dispatch(user_id);
+                                                                }
+                                                            >
+                                                                "Reset MFA"
+                                                            </button>
+                                                        </div>
+                                                    })}
+                                                    <button
+                                                        class="mt-1 px-2 py-1 text-xs rounded border border-[#2E3A59] text-[#2E3A59] hover:bg-[#2E3A59] hover:text-white"
+                                                        title="End every active session for this user"
+                                                        on:click=move |ev| {
+                                                            ev.stop_propagation();
+                                                            sign_out_everywhere.dispatch(user_id);
+                                                        }
+                                                    >
+                                                        "Sign out everywhere"
+                                                    </button>
+                                                </td>
                                             </tr>
                                         }
                                     }).collect_view()
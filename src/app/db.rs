@@ -1,26 +1,56 @@
+pub mod api_token_database;
 pub mod assessment_database;
+pub mod audit_database;
 pub mod course_database;
 pub mod database;
 pub mod enrollment_database;
 pub mod global_database;
+pub mod login_throttle_database;
+pub mod mfa_database;
+pub mod oidc_database;
 pub mod question_database;
 pub mod saml_database;
 pub mod score_database;
 pub mod student_database;
+pub mod student_protection_database;
+pub mod student_encryption_database;
+pub mod retention_database;
+pub mod student_merge_database;
+pub mod student_history_database;
+pub mod student_attribute_database;
+pub mod student_group_database;
+pub mod oneroster_database;
+pub mod roster_sync_database;
+pub mod import_mapping_database;
 pub mod teacher_database;
 pub mod test_database;
 pub mod user_database;
 pub mod websocket_session_database;
 
+pub use api_token_database::*;
 pub use assessment_database::*;
+pub use audit_database::*;
 pub use course_database::*;
 pub use database::*;
 pub use enrollment_database::*;
 pub use global_database::*;
+pub use login_throttle_database::*;
+pub use mfa_database::*;
+pub use oidc_database::*;
 pub use question_database::*;
 pub use saml_database::*;
 pub use score_database::*;
 pub use student_database::*;
+pub use student_protection_database::*;
+pub use student_encryption_database::*;
+pub use retention_database::*;
+pub use student_merge_database::*;
+pub use student_history_database::*;
+pub use student_attribute_database::*;
+pub use student_group_database::*;
+pub use oneroster_database::*;
+pub use roster_sync_database::*;
+pub use import_mapping_database::*;
 pub use teacher_database::*;
 pub use test_database::*;
 pub use user_database::*;
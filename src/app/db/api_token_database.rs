@@ -0,0 +1,124 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::auth::ApiToken;
+        use crate::app::models::permission::TokenScope;
+        use crate::app::models::user::SessionUser;
+        use sqlx::{postgres::PgRow, Pool, Postgres, Row};
+        use leptos::ServerFnError;
+        use chrono::{DateTime, Utc};
+        use std::str::FromStr;
+
+        fn token_from_row(row: &PgRow) -> ApiToken {
+            let scopes: Vec<String> = row.get("scopes");
+            ApiToken {
+                id: row.get("id"),
+                name: row.get("name"),
+                token_prefix: row.get("token_prefix"),
+                // Unknown scopes from an older release grant nothing
+                scopes: scopes.iter().filter_map(|scope| TokenScope::from_str(scope).ok()).collect(),
+                created_at: row.get("created_at"),
+                expires_at: row.get("expires_at"),
+                last_used_at: row.get("last_used_at"),
+            }
+        }
+
+        pub async fn create_api_token(
+            pool: &Pool<Postgres>,
+            user_id: i64,
+            name: &str,
+            token_hash: &str,
+            token_prefix: &str,
+            scopes: &[TokenScope],
+            expires_at: DateTime<Utc>,
+        ) -> Result<ApiToken, ServerFnError> {
+            let scopes: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+            let row = sqlx::query(
+                "INSERT INTO api_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 RETURNING id, name, token_prefix, scopes, created_at, expires_at, last_used_at"
+            )
+            .bind(user_id)
+            .bind(name)
+            .bind(token_hash)
+            .bind(token_prefix)
+            .bind(&scopes)
+            .bind(expires_at)
+            .fetch_one(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(token_from_row(&row))
+        }
+
+        // Unrevoked tokens for a user, including expired ones so they can be cleaned up
+        pub async fn list_api_tokens(pool: &Pool<Postgres>, user_id: i64) -> Result<Vec<ApiToken>, ServerFnError> {
+            let rows = sqlx::query(
+                "SELECT id, name, token_prefix, scopes, created_at, expires_at, last_used_at
+                 FROM api_tokens
+                 WHERE user_id = $1 AND revoked_at IS NULL
+                 ORDER BY created_at DESC"
+            )
+            .bind(user_id)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(rows.iter().map(token_from_row).collect())
+        }
+
+        pub async fn revoke_api_token(pool: &Pool<Postgres>, user_id: i64, token_id: i64) -> Result<bool, ServerFnError> {
+            let result = sqlx::query(
+                "UPDATE api_tokens SET revoked_at = NOW()
+                 WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+            )
+            .bind(token_id)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(result.rows_affected() > 0)
+        }
+
+        // Resolves a bearer token to its owner, limited to the token's scopes
+        pub async fn authenticate_api_token(pool: &Pool<Postgres>, token_hash: &str) -> Result<Option<SessionUser>, ServerFnError> {
+            let row = sqlx::query(
+                "SELECT u.id, u.username, u.email, u.role, u.display_name, u.first_name, u.last_name,
+                        t.id AS token_id, t.scopes, t.last_used_at
+                 FROM api_tokens t
+                 JOIN users u ON u.id = t.user_id
+                 WHERE t.token_hash = $1 AND t.revoked_at IS NULL AND t.expires_at > NOW()"
+            )
+            .bind(token_hash)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Error validating API token: {}", e)))?;
+
+            let Some(row) = row else {
+                return Ok(None);
+            };
+
+            // Record use, writing at most once a minute per token
+            let last_used_at: Option<DateTime<Utc>> = row.get("last_used_at");
+            if last_used_at.map_or(true, |at| Utc::now() - at > chrono::Duration::minutes(1)) {
+                sqlx::query("UPDATE api_tokens SET last_used_at = NOW() WHERE id = $1")
+                    .bind(row.get::<i64, _>("token_id"))
+                    .execute(pool)
+                    .await
+                    .map_err(|e| ServerFnError::new(format!("Error updating API token: {}", e)))?;
+            }
+
+            let scopes: Vec<String> = row.get("scopes");
+            Ok(Some(SessionUser {
+                id: row.get("id"),
+                username: row.get("username"),
+                email: row.get("email"),
+                role: row.get("role"),
+                display_name: row.try_get("display_name").unwrap_or(None),
+                first_name: row.try_get("first_name").unwrap_or(None),
+                last_name: row.try_get("last_name").unwrap_or(None),
+                token_scopes: Some(scopes.iter().filter_map(|scope| TokenScope::from_str(scope).ok()).collect()),
+            }))
+        }
+    }
+}
@@ -204,23 +204,23 @@ cfg_if::cfg_if! {
             Ok(assessment)
         }
 
-        pub async fn update_all_assessments_referencing_test(test_id: &String, pool: &sqlx::PgPool) -> Result<(), ServerFnError> {
+        // Returns (assessment id, old composite score, new composite score) for each assessment touched
+        pub async fn update_all_assessments_referencing_test(test_id: &String, pool: &sqlx::PgPool) -> Result<Vec<(Uuid, Option<i32>, i32)>, ServerFnError> {
             // Convert the string to a UUID
             let test_uuid = Uuid::parse_str(test_id)
                 .map_err(|e| ServerFnError::new(format!("Invalid UUID format: {}", e)))?;
 
             // Find all assessments that reference this test
-            let assessment_ids: Vec<Uuid> = sqlx::query_as::<_, (Uuid,)>("SELECT id FROM assessments WHERE $1 = ANY(tests)")
+            let assessments: Vec<(Uuid, Option<i32>)> = sqlx::query_as::<_, (Uuid, Option<i32>)>("SELECT id, composite_score FROM assessments WHERE $1 = ANY(tests)")
                 .bind(test_uuid)
                 .fetch_all(pool)
                 .await
-                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?
-                .into_iter()
-                .map(|(id,)| id)
-                .collect();
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let mut changes = Vec::new();
 
             // Update each assessment's composite score
-            for assessment_id in assessment_ids {
+            for (assessment_id, previous_score) in assessments {
                 // Get the test IDs for this assessment
                 let test_ids: Vec<Uuid> = sqlx::query_scalar::<_, Uuid>("SELECT unnest(tests) FROM assessments WHERE id = $1")
                     .bind(assessment_id)
@@ -244,9 +244,11 @@ cfg_if::cfg_if! {
                     .execute(pool)
                     .await
                     .map_err(|e| ServerFnError::new(format!("Database error updating assessment: {}", e)))?;
+
+                changes.push((assessment_id, previous_score, total));
             }
 
-            Ok(())
+            Ok(changes)
         }
 
         pub async fn update_assessment(assessment: &Assessment, pool: &sqlx::PgPool) -> Result<Assessment, ServerFnError> {
@@ -0,0 +1,241 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::audit::{AuditAction, AuditChainStatus, AuditChange, AuditEntity, AuditEvent, AuditEventFilter};
+        use crate::app::models::user::SessionUser;
+        use crate::app::services::audit::{event_hash, find_broken_link, GENESIS_HASH};
+        use chrono::{DurationRound, TimeDelta, Utc};
+        use sqlx::{postgres::PgRow, PgConnection, Pool, Postgres, Row};
+        use leptos::ServerFnError;
+        use std::str::FromStr;
+
+        // Serialises appends so each event links to the one inserted before it
+        const AUDIT_CHAIN_LOCK: i64 = 0x6175_6469_745f_6c6f;
+
+        const VERIFY_PAGE_SIZE: i64 = 1000;
+
+        const EVENT_COLUMNS: &str = "id, occurred_at, actor_id, actor_username, action, entity_type, entity_id,
+                                     before_data::TEXT AS before_data, after_data::TEXT AS after_data, prev_hash, hash";
+
+        fn parse_json(row: &PgRow, column: &str) -> Result<Option<serde_json::Value>, ServerFnError> {
+            row.get::<Option<String>, _>(column)
+                .map(|text| serde_json::from_str(&text))
+                .transpose()
+                .map_err(|e| ServerFnError::new(format!("Invalid audit data: {}", e)))
+        }
+
+        fn event_from_row(row: &PgRow) -> Result<AuditEvent, ServerFnError> {
+            Ok(AuditEvent {
+                id: row.get("id"),
+                occurred_at: row.get("occurred_at"),
+                actor_id: row.get("actor_id"),
+                actor_username: row.get("actor_username"),
+                action: AuditAction::from_str(row.get("action")).map_err(ServerFnError::new)?,
+                entity_type: AuditEntity::from_str(row.get("entity_type")).map_err(ServerFnError::new)?,
+                entity_id: row.get("entity_id"),
+                before: parse_json(row, "before_data")?,
+                after: parse_json(row, "after_data")?,
+                prev_hash: row.get("prev_hash"),
+                hash: row.get("hash"),
+            })
+        }
+
+        pub async fn append_audit_event(
+            pool: &Pool<Postgres>,
+            actor: &SessionUser,
+            action: AuditAction,
+            entity_type: AuditEntity,
+            entity_id: &str,
+            change: AuditChange,
+        ) -> Result<(), ServerFnError> {
+            append_audit_event_as(pool, Some(actor.id), &actor.username, action, entity_type, entity_id, change).await
+        }
+
+        // For changes made by the server itself, such as scheduled jobs,
+        // which have no signed-in actor
+        pub async fn append_audit_event_as(
+            pool: &Pool<Postgres>,
+            actor_id: Option<i64>,
+            actor_username: &str,
+            action: AuditAction,
+            entity_type: AuditEntity,
+            entity_id: &str,
+            change: AuditChange,
+        ) -> Result<(), ServerFnError> {
+            let mut tx = pool.begin().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            write_audit_event_as(&mut tx, actor_id, actor_username, action, entity_type, entity_id, change).await?;
+            tx.commit().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Appends inside the caller's transaction, so the event commits or
+        // rolls back with the change it records. Other appends wait on the
+        // chain lock until that transaction ends, so call this last.
+        pub async fn write_audit_event(
+            conn: &mut PgConnection,
+            actor: &SessionUser,
+            action: AuditAction,
+            entity_type: AuditEntity,
+            entity_id: &str,
+            change: AuditChange,
+        ) -> Result<(), ServerFnError> {
+            write_audit_event_as(conn, Some(actor.id), &actor.username, action, entity_type, entity_id, change).await
+        }
+
+        pub async fn write_audit_event_as(
+            conn: &mut PgConnection,
+            actor_id: Option<i64>,
+            actor_username: &str,
+            action: AuditAction,
+            entity_type: AuditEntity,
+            entity_id: &str,
+            change: AuditChange,
+        ) -> Result<(), ServerFnError> {
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(AUDIT_CHAIN_LOCK)
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let prev_hash: Option<String> = sqlx::query_scalar("SELECT hash FROM audit_events ORDER BY id DESC LIMIT 1")
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            // Truncated to what TIMESTAMPTZ keeps so the stored value re-hashes the same
+            let occurred_at = Utc::now()
+                .duration_trunc(TimeDelta::microseconds(1))
+                .map_err(|e| ServerFnError::new(format!("Invalid timestamp: {}", e)))?;
+
+            let mut event = AuditEvent {
+                id: 0,
+                occurred_at,
+                actor_id,
+                actor_username: actor_username.to_string(),
+                action,
+                entity_type,
+                entity_id: entity_id.to_string(),
+                before: change.before,
+                after: change.after,
+                prev_hash: prev_hash.unwrap_or_else(|| GENESIS_HASH.to_string()),
+                hash: String::new(),
+            };
+            event.hash = event_hash(&event);
+
+            sqlx::query(
+                "INSERT INTO audit_events
+                    (occurred_at, actor_id, actor_username, action, entity_type, entity_id, before_data, after_data, prev_hash, hash)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7::TEXT::JSONB, $8::TEXT::JSONB, $9, $10)"
+            )
+            .bind(event.occurred_at)
+            .bind(event.actor_id)
+            .bind(&event.actor_username)
+            .bind(event.action.as_str())
+            .bind(event.entity_type.as_str())
+            .bind(&event.entity_id)
+            .bind(event.before.as_ref().map(|value| value.to_string()))
+            .bind(event.after.as_ref().map(|value| value.to_string()))
+            .bind(&event.prev_hash)
+            .bind(&event.hash)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Newest first. `limit` of None returns every match, for export.
+        pub async fn list_audit_events(
+            pool: &Pool<Postgres>,
+            filter: &AuditEventFilter,
+            limit: Option<i64>,
+        ) -> Result<Vec<AuditEvent>, ServerFnError> {
+            let actor = filter.actor.as_deref().map(str::trim).filter(|actor| !actor.is_empty());
+            let entity_id = filter.entity_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+
+            let rows = sqlx::query(&format!(
+                "SELECT {}
+                 FROM audit_events
+                 WHERE ($1::TEXT IS NULL OR actor_username ILIKE '%' || $1 || '%')
+                   AND ($2::TEXT IS NULL OR action = $2)
+                   AND ($3::TEXT IS NULL OR entity_type = $3)
+                   AND ($4::TEXT IS NULL OR entity_id = $4)
+                   AND ($5::DATE IS NULL OR occurred_at >= $5::DATE)
+                   AND ($6::DATE IS NULL OR occurred_at < $6::DATE + 1)
+                 ORDER BY id DESC
+                 LIMIT $7",
+                EVENT_COLUMNS
+            ))
+            .bind(actor)
+            .bind(filter.action.map(|action| action.as_str()))
+            .bind(filter.entity_type.map(|entity| entity.as_str()))
+            .bind(entity_id)
+            .bind(filter.from)
+            .bind(filter.to)
+            .bind(limit)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            rows.iter().map(event_from_row).collect()
+        }
+
+        // Oldest first: events on the student and on their scores and
+        // enrollments, whose ids start with the student id
+        pub async fn list_student_audit_events(pool: &Pool<Postgres>, student_id: i32) -> Result<Vec<AuditEvent>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT {}
+                 FROM audit_events
+                 WHERE (entity_type = $1 AND entity_id = $3)
+                    OR (entity_type = ANY($2) AND entity_id LIKE $3 || '/%')
+                 ORDER BY id",
+                EVENT_COLUMNS
+            ))
+            .bind(AuditEntity::Student.as_str())
+            .bind(vec![AuditEntity::Score.as_str(), AuditEntity::Enrollment.as_str()])
+            .bind(student_id.to_string())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            rows.iter().map(event_from_row).collect()
+        }
+
+        // Re-hashes the whole log in id order, a page at a time
+        pub async fn verify_audit_chain(pool: &Pool<Postgres>) -> Result<AuditChainStatus, ServerFnError> {
+            let mut previous_hash = GENESIS_HASH.to_string();
+            let mut last_id = 0i64;
+            let mut events_checked = 0i64;
+
+            loop {
+                let rows = sqlx::query(&format!(
+                    "SELECT {} FROM audit_events WHERE id > $1 ORDER BY id LIMIT $2",
+                    EVENT_COLUMNS
+                ))
+                .bind(last_id)
+                .bind(VERIFY_PAGE_SIZE)
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+                let events = rows.iter().map(event_from_row).collect::<Result<Vec<_>, _>>()?;
+                let Some(last) = events.last() else {
+                    break;
+                };
+
+                if let Some(broken_id) = find_broken_link(&previous_hash, &events) {
+                    events_checked += events.iter().take_while(|event| event.id < broken_id).count() as i64;
+                    return Ok(AuditChainStatus { events_checked, first_invalid_id: Some(broken_id) });
+                }
+
+                events_checked += events.len() as i64;
+                previous_hash = last.hash.clone();
+                last_id = last.id;
+            }
+
+            Ok(AuditChainStatus { events_checked, first_invalid_id: None })
+        }
+    }
+}
@@ -2,7 +2,12 @@ use leptos::ServerFnError;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")]{
+        use crate::app::db::audit_database::write_audit_event;
+        use crate::app::db::student_database::caseload_filter;
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::user::SessionUser;
         use crate::app::models::enrollment::{Enrollment, EnrollmentStatus, AcademicYear};
+        use crate::app::models::DataScope;
         use crate::app::models::student::GradeEnum;
         use log::{debug, error, info, warn};
         use chrono::NaiveDate;
@@ -10,8 +15,11 @@ cfg_if::cfg_if! {
         use sqlx::PgPool;
         use sqlx::prelude::*;
 
-        pub async fn get_all_enrollments(pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
-            let rows = sqlx::query("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments ORDER BY enrollment_date DESC")
+        pub async fn get_all_enrollments(scope: &DataScope, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
+            let query = format!("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE {} ORDER BY enrollment_date DESC", caseload_filter("student_id", 1, 2));
+            let rows = sqlx::query(&query)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await?;
 
@@ -42,9 +50,12 @@ cfg_if::cfg_if! {
             Ok(enrollments)
         }
 
-        pub async fn get_enrollments_by_student(student_id: &i32, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
-            let rows = sqlx::query("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE student_id = $1 ORDER BY enrollment_date DESC")
+        pub async fn get_enrollments_by_student(student_id: &i32, scope: &DataScope, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
+            let query = format!("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE student_id = $1 AND {} ORDER BY enrollment_date DESC", caseload_filter("student_id", 2, 3));
+            let rows = sqlx::query(&query)
                 .bind(student_id)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await?;
 
@@ -70,10 +81,14 @@ cfg_if::cfg_if! {
             pool: &PgPool,
             student_id: i32,
             academic_year: AcademicYear,
+            scope: &DataScope,
         ) -> Result<Enrollment, ServerFnError> {
-            let row = sqlx::query("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE student_id = $1 AND academic_year = $2 LIMIT 1")
+            let query = format!("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE student_id = $1 AND academic_year = $2 AND {} LIMIT 1", caseload_filter("student_id", 3, 4));
+            let row = sqlx::query(&query)
                 .bind(student_id)
                 .bind(academic_year)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_one(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
@@ -92,9 +107,12 @@ cfg_if::cfg_if! {
             Ok(enrollment)
         }
 
-        pub async fn get_enrollments_by_academic_year(academic_year: &AcademicYear, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
-            let rows = sqlx::query("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE academic_year = $1 ORDER BY enrollment_date DESC")
+        pub async fn get_enrollments_by_academic_year(academic_year: &AcademicYear, scope: &DataScope, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
+            let query = format!("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE academic_year = $1 AND {} ORDER BY enrollment_date DESC", caseload_filter("student_id", 2, 3));
+            let rows = sqlx::query(&query)
                 .bind(academic_year)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await?;
 
@@ -116,9 +134,12 @@ cfg_if::cfg_if! {
             Ok(enrollments)
         }
 
-        pub async fn get_enrollments_by_teacher(teacher_id: i32, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
-            let rows = sqlx::query("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE teacher_id = $1 ORDER BY enrollment_date DESC")
+        pub async fn get_enrollments_by_teacher(teacher_id: i32, scope: &DataScope, pool: &PgPool) -> Result<Vec<Enrollment>, ServerFnError> {
+            let query = format!("SELECT student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes FROM student_enrollments WHERE teacher_id = $1 AND {} ORDER BY enrollment_date DESC", caseload_filter("student_id", 2, 3));
+            let rows = sqlx::query(&query)
                 .bind(teacher_id)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await?;
 
@@ -298,25 +319,35 @@ cfg_if::cfg_if! {
             }
         }
 
-        pub async fn bulk_insert_enrollments(pool: &PgPool, enrollments: &[Enrollment]) -> Result<usize, ServerFnError> {
+        // Inserts the enrollments and their audit events in one transaction
+        pub async fn bulk_insert_enrollments(pool: &PgPool, enrollments: &[Enrollment], actor: &SessionUser) -> Result<usize, ServerFnError> {
             if enrollments.is_empty() {
                 return Ok(0);
             }
 
             let mut tx = pool.begin().await?;
 
-            let result = bulk_insert_with_unnest(enrollments, &mut tx).await;
-
-            match result {
-                Ok(count) => {
-                    tx.commit().await?;
-                    Ok(count)
-                }
+            let count = match bulk_insert_with_unnest(enrollments, &mut tx).await {
+                Ok(count) => count,
                 Err(e) => {
                     tx.rollback().await?;
-                    Err(ServerFnError::new(format!("Bulk insert failed: {}", e)))
+                    return Err(ServerFnError::new(format!("Bulk insert failed: {}", e)));
                 }
+            };
+            for enrollment in enrollments {
+                write_audit_event(
+                    &mut tx,
+                    actor,
+                    AuditAction::Create,
+                    AuditEntity::Enrollment,
+                    &enrollment.audit_id(),
+                    AuditChange::created(enrollment),
+                )
+                .await?;
             }
+
+            tx.commit().await?;
+            Ok(count)
         }
 
         pub async fn bulk_insert_enrollments_batch(pool: &PgPool, enrollments: &[Enrollment]) -> Result<usize, ServerFnError> {
@@ -2,7 +2,7 @@ cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
         use sqlx::PgPool;
         use leptos::*;
-        use crate::app::models::global::{GlobalSetting, SettingsCache};
+        use crate::app::models::global::{self, GlobalSetting, SettingsCache};
         use sqlx::Row;
 
         pub async fn get_all_global_settings(pool: &sqlx::PgPool) -> Result<SettingsCache, ServerFnError> {
@@ -58,7 +58,13 @@ cfg_if::cfg_if! {
             Ok(result)
         }
 
+        // Engine-owned keys such as student_protections are refused here; they
+        // change only through their engine so its state stays consistent
         pub async fn update_global_setting(pool: &sqlx::PgPool, key: &str, value: serde_json::Value, updated_by: i32) -> Result<(), ServerFnError> {
+            if global::is_engine_owned(key) {
+                return Err(ServerFnError::new(format!("The '{}' setting can only be changed through its own settings page", key)));
+            }
+
             sqlx::query(
                 r#"
                 INSERT INTO global_settings (key, value, updated_by, updated_at)
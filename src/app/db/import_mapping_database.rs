@@ -0,0 +1,69 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::import_mapping::{ColumnMapping, ImportKind, MappingProfile, SaveMappingProfileRequest};
+        use leptos::ServerFnError;
+        use sqlx::postgres::PgRow;
+        use sqlx::types::Json;
+        use sqlx::{PgPool, Row};
+        use std::str::FromStr;
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn profile_from_row(row: &PgRow) -> Result<MappingProfile, ServerFnError> {
+            Ok(MappingProfile {
+                id: row.get("id"),
+                name: row.get("name"),
+                kind: ImportKind::from_str(row.get::<&str, _>("import_kind")).map_err(ServerFnError::new)?,
+                mapping: row.get::<Json<ColumnMapping>, _>("mapping").0,
+            })
+        }
+
+        pub async fn list_mapping_profiles(pool: &PgPool, kind: ImportKind) -> Result<Vec<MappingProfile>, ServerFnError> {
+            let rows = sqlx::query("SELECT id, name, import_kind, mapping FROM import_mapping_profiles WHERE import_kind = $1 ORDER BY name")
+                .bind(kind.as_str())
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+            rows.iter().map(profile_from_row).collect()
+        }
+
+        pub async fn get_mapping_profile(pool: &PgPool, id: i32) -> Result<Option<MappingProfile>, ServerFnError> {
+            let row = sqlx::query("SELECT id, name, import_kind, mapping FROM import_mapping_profiles WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?;
+            row.as_ref().map(profile_from_row).transpose()
+        }
+
+        // Saves the profile, replacing the one of the same name and kind
+        pub async fn save_mapping_profile(pool: &PgPool, request: &SaveMappingProfileRequest, username: &str) -> Result<MappingProfile, ServerFnError> {
+            let row = sqlx::query(
+                "INSERT INTO import_mapping_profiles (name, import_kind, mapping, updated_by)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (name, import_kind) DO UPDATE
+                 SET mapping = EXCLUDED.mapping, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+                 RETURNING id, name, import_kind, mapping"
+            )
+            .bind(request.name.trim())
+            .bind(request.kind.as_str())
+            .bind(Json(&request.mapping))
+            .bind(username)
+            .fetch_one(pool)
+            .await
+            .map_err(db_error)?;
+            profile_from_row(&row)
+        }
+
+        pub async fn delete_mapping_profile(pool: &PgPool, id: i32) -> Result<Option<MappingProfile>, ServerFnError> {
+            let row = sqlx::query("DELETE FROM import_mapping_profiles WHERE id = $1 RETURNING id, name, import_kind, mapping")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?;
+            row.as_ref().map(profile_from_row).transpose()
+        }
+    }
+}
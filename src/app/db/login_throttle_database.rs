@@ -0,0 +1,187 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use sqlx::{Pool, Postgres, Row, Transaction};
+        use leptos::ServerFnError;
+        use chrono::{DateTime, Duration, Utc};
+        use crate::app::models::global::LoginThrottleSettings;
+
+        const USERNAME_SCOPE: &str = "username";
+        const IP_SCOPE: &str = "ip";
+
+        // How long this login must wait, checked before the password is hashed.
+        // Usernames get backoff and lockout; IPs only lockout, since a school
+        // often puts every student behind one address.
+        pub async fn login_retry_after(
+            pool: &Pool<Postgres>,
+            settings: &LoginThrottleSettings,
+            username: &str,
+            ip_address: Option<&str>,
+        ) -> Result<Option<Duration>, ServerFnError> {
+            let rows = sqlx::query(
+                "SELECT scope, failure_count, last_failure_at, locked_until FROM login_throttles
+                 WHERE (scope = $1 AND subject = $2) OR (scope = $3 AND subject = $4)"
+            )
+            .bind(USERNAME_SCOPE)
+            .bind(username)
+            .bind(IP_SCOPE)
+            .bind(ip_address.unwrap_or_default())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let now = Utc::now();
+            let wait = rows.iter().filter_map(|row| {
+                let scope: String = row.get("scope");
+                let locked_until: Option<DateTime<Utc>> = row.get("locked_until");
+                if scope == USERNAME_SCOPE {
+                    settings.retry_after(row.get("failure_count"), row.get("last_failure_at"), locked_until, now)
+                } else {
+                    locked_until.filter(|until| *until > now).map(|until| until - now)
+                }
+            }).max();
+
+            Ok(wait)
+        }
+
+        // Counts one failure against a scope, locking it when the threshold is
+        // reached. Returns the lock expiry if this failure caused a lockout.
+        async fn register_failure(
+            tx: &mut Transaction<'_, Postgres>,
+            settings: &LoginThrottleSettings,
+            scope: &str,
+            subject: &str,
+            threshold: i32,
+            ip_address: Option<&str>,
+        ) -> Result<Option<DateTime<Utc>>, ServerFnError> {
+            sqlx::query("INSERT INTO login_throttles (scope, subject) VALUES ($1, $2) ON CONFLICT DO NOTHING")
+                .bind(scope)
+                .bind(subject)
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let row = sqlx::query("SELECT failure_count, last_failure_at FROM login_throttles WHERE scope = $1 AND subject = $2 FOR UPDATE")
+                .bind(scope)
+                .bind(subject)
+                .fetch_one(&mut **tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let now = Utc::now();
+            let failures = settings.active_failures(row.get("failure_count"), row.get("last_failure_at"), now) + 1;
+            let locked_until = settings.should_lock(failures, threshold).then(|| now + settings.lockout_duration());
+
+            // A lockout resets the count so backoff starts over once it expires
+            sqlx::query(
+                "UPDATE login_throttles SET failure_count = $3, last_failure_at = $4, locked_until = $5
+                 WHERE scope = $1 AND subject = $2"
+            )
+            .bind(scope)
+            .bind(subject)
+            .bind(if locked_until.is_some() { 0 } else { failures })
+            .bind(now)
+            .bind(locked_until)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            if let Some(until) = locked_until {
+                sqlx::query(
+                    "INSERT INTO login_lockout_events (scope, subject, event, failure_count, locked_until, ip_address)
+                     VALUES ($1, $2, 'locked', $3, $4, $5)"
+                )
+                .bind(scope)
+                .bind(subject)
+                .bind(failures)
+                .bind(until)
+                .bind(ip_address)
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+                log::warn!("Login locked for {} '{}' until {} after {} failures", scope, subject, until, failures);
+            }
+
+            Ok(locked_until)
+        }
+
+        // Record a failed password attempt against the username and the client IP
+        pub async fn record_login_failure(
+            pool: &Pool<Postgres>,
+            settings: &LoginThrottleSettings,
+            username: &str,
+            ip_address: Option<&str>,
+        ) -> Result<(), ServerFnError> {
+            let mut tx = pool.begin().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            register_failure(&mut tx, settings, USERNAME_SCOPE, username, settings.max_failures, ip_address).await?;
+            if let Some(ip) = ip_address {
+                register_failure(&mut tx, settings, IP_SCOPE, ip, settings.ip_max_failures, ip_address).await?;
+            }
+
+            tx.commit().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        // A successful login clears the username's failures but not the IP's,
+        // so one valid account can't be used to reset an address being sprayed from
+        pub async fn clear_login_failures(pool: &Pool<Postgres>, username: &str) -> Result<(), ServerFnError> {
+            sqlx::query("DELETE FROM login_throttles WHERE scope = $1 AND subject = $2")
+                .bind(USERNAME_SCOPE)
+                .bind(username)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Users whose accounts are currently locked, with the lock expiry.
+        // Usernames are stored as typed, matching get_user_by_username.
+        pub async fn get_locked_users(pool: &Pool<Postgres>) -> Result<Vec<(i64, DateTime<Utc>)>, ServerFnError> {
+            let rows = sqlx::query(
+                "SELECT u.id, t.locked_until FROM login_throttles t
+                 JOIN users u ON u.username = t.subject
+                 WHERE t.scope = $1 AND t.locked_until > NOW()"
+            )
+            .bind(USERNAME_SCOPE)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(rows.iter().map(|row| (row.get("id"), row.get("locked_until"))).collect())
+        }
+
+        // Admin unlock: forget the username's failures and record who lifted the lock
+        pub async fn unlock_login(pool: &Pool<Postgres>, username: &str, actor_user_id: i64) -> Result<(), ServerFnError> {
+            let mut tx = pool.begin().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            sqlx::query("DELETE FROM login_throttles WHERE scope = $1 AND subject = $2")
+                .bind(USERNAME_SCOPE)
+                .bind(username)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            sqlx::query(
+                "INSERT INTO login_lockout_events (scope, subject, event, actor_user_id)
+                 VALUES ($1, $2, 'unlocked', $3)"
+            )
+            .bind(USERNAME_SCOPE)
+            .bind(username)
+            .bind(actor_user_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            tx.commit().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,223 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use sqlx::{Pool, Postgres, Row};
+        use leptos::ServerFnError;
+        use chrono::{DateTime, Utc};
+
+        // Wrong codes allowed against one challenge before the password must be re-entered
+        pub const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+
+        pub struct UserMfa {
+            pub totp_secret: String,
+            pub enabled: bool,
+            pub enrolled_at: Option<DateTime<Utc>>,
+            pub last_used_step: Option<i64>,
+        }
+
+        pub async fn get_user_mfa(pool: &Pool<Postgres>, user_id: i64) -> Result<Option<UserMfa>, ServerFnError> {
+            let row = sqlx::query("SELECT totp_secret, enabled, enrolled_at, last_used_step FROM user_mfa WHERE user_id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(row.map(|row| UserMfa {
+                totp_secret: row.get("totp_secret"),
+                enabled: row.get("enabled"),
+                enrolled_at: row.get("enrolled_at"),
+                last_used_step: row.get("last_used_step"),
+            }))
+        }
+
+        // Stores a secret awaiting confirmation. An enabled factor is never overwritten.
+        pub async fn start_mfa_enrollment(pool: &Pool<Postgres>, user_id: i64, secret: &str) -> Result<(), ServerFnError> {
+            let result = sqlx::query(
+                "INSERT INTO user_mfa (user_id, totp_secret) VALUES ($1, $2)
+                 ON CONFLICT (user_id) DO UPDATE SET totp_secret = EXCLUDED.totp_secret, created_at = NOW()
+                 WHERE user_mfa.enabled = FALSE"
+            )
+            .bind(user_id)
+            .bind(secret)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            if result.rows_affected() == 0 {
+                return Err(ServerFnError::new("Two-factor authentication is already enabled"));
+            }
+            Ok(())
+        }
+
+        // Confirms a pending enrollment and issues its recovery codes
+        pub async fn enable_mfa(pool: &Pool<Postgres>, user_id: i64, step: i64, recovery_code_hashes: &[String]) -> Result<(), ServerFnError> {
+            let mut tx = pool.begin().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let result = sqlx::query(
+                "UPDATE user_mfa SET enabled = TRUE, enrolled_at = NOW(), last_used_step = $2
+                 WHERE user_id = $1 AND enabled = FALSE"
+            )
+            .bind(user_id)
+            .bind(step)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            if result.rows_affected() == 0 {
+                return Err(ServerFnError::new("No pending two-factor enrollment"));
+            }
+
+            sqlx::query("DELETE FROM user_mfa_recovery_codes WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            sqlx::query("INSERT INTO user_mfa_recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::VARCHAR[])")
+                .bind(user_id)
+                .bind(recovery_code_hashes)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            tx.commit().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Marks a TOTP step as used. False means another request already used
+        // this or a later step, so the code is a replay.
+        pub async fn record_mfa_step(pool: &Pool<Postgres>, user_id: i64, step: i64) -> Result<bool, ServerFnError> {
+            let result = sqlx::query(
+                "UPDATE user_mfa SET last_used_step = $2
+                 WHERE user_id = $1 AND enabled AND (last_used_step IS NULL OR last_used_step < $2)"
+            )
+            .bind(user_id)
+            .bind(step)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(result.rows_affected() > 0)
+        }
+
+        pub async fn replace_recovery_codes(pool: &Pool<Postgres>, user_id: i64, recovery_code_hashes: &[String]) -> Result<(), ServerFnError> {
+            let mut tx = pool.begin().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            sqlx::query("DELETE FROM user_mfa_recovery_codes WHERE user_id = $1")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            sqlx::query("INSERT INTO user_mfa_recovery_codes (user_id, code_hash) SELECT $1, UNNEST($2::VARCHAR[])")
+                .bind(user_id)
+                .bind(recovery_code_hashes)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            tx.commit().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Each recovery code works once
+        pub async fn consume_recovery_code(pool: &Pool<Postgres>, user_id: i64, code_hash: &str) -> Result<bool, ServerFnError> {
+            let result = sqlx::query(
+                "UPDATE user_mfa_recovery_codes SET used_at = NOW()
+                 WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+            )
+            .bind(user_id)
+            .bind(code_hash)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(result.rows_affected() > 0)
+        }
+
+        pub async fn count_recovery_codes(pool: &Pool<Postgres>, user_id: i64) -> Result<i64, ServerFnError> {
+            sqlx::query_scalar("SELECT COUNT(*) FROM user_mfa_recovery_codes WHERE user_id = $1 AND used_at IS NULL")
+                .bind(user_id)
+                .fetch_one(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))
+        }
+
+        // Removes the factor, its recovery codes and any pending challenges
+        pub async fn reset_mfa(pool: &Pool<Postgres>, user_id: i64) -> Result<(), ServerFnError> {
+            let mut tx = pool.begin().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            for statement in [
+                "DELETE FROM user_mfa WHERE user_id = $1",
+                "DELETE FROM user_mfa_recovery_codes WHERE user_id = $1",
+                "DELETE FROM mfa_challenges WHERE user_id = $1",
+            ] {
+                sqlx::query(statement)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            }
+
+            tx.commit().await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+
+        pub async fn get_mfa_enabled_users(pool: &Pool<Postgres>) -> Result<Vec<i64>, ServerFnError> {
+            sqlx::query_scalar("SELECT user_id FROM user_mfa WHERE enabled")
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))
+        }
+
+        pub async fn create_mfa_challenge(pool: &Pool<Postgres>, user_id: i64) -> Result<String, ServerFnError> {
+            sqlx::query("DELETE FROM mfa_challenges WHERE created_at < NOW() - INTERVAL '10 minutes'")
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let token = uuid::Uuid::new_v4().to_string();
+            sqlx::query("INSERT INTO mfa_challenges (token, user_id) VALUES ($1, $2)")
+                .bind(&token)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(token)
+        }
+
+        // Counts an attempt against a live challenge and returns its user.
+        // Challenges last five minutes and allow MAX_CHALLENGE_ATTEMPTS codes.
+        pub async fn take_mfa_challenge_attempt(pool: &Pool<Postgres>, token: &str) -> Result<Option<i64>, ServerFnError> {
+            sqlx::query_scalar(
+                "UPDATE mfa_challenges SET attempts = attempts + 1
+                 WHERE token = $1 AND created_at > NOW() - INTERVAL '5 minutes' AND attempts < $2
+                 RETURNING user_id"
+            )
+            .bind(token)
+            .bind(MAX_CHALLENGE_ATTEMPTS)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))
+        }
+
+        pub async fn delete_mfa_challenge(pool: &Pool<Postgres>, token: &str) -> Result<(), ServerFnError> {
+            sqlx::query("DELETE FROM mfa_challenges WHERE token = $1")
+                .bind(token)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(())
+        }
+    }
+}
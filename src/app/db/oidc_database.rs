@@ -0,0 +1,259 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use sqlx::{Pool, Postgres, Row};
+        use sqlx::postgres::PgRow;
+        use leptos::ServerFnError;
+        use crate::app::db::saml_database;
+        use crate::app::models::auth::{OidcConfig, SamlMappedField, SamlResponse, SsoAttributeMapping};
+        use crate::app::models::user::SessionUser;
+        use crate::app::services::oidc::IdTokenClaims;
+        use uuid::Uuid;
+
+        const OIDC_CONFIG_COLUMNS: &str = "id, institution_name, issuer, client_id, client_secret, scopes, active,
+            created_at, updated_at, attribute_mapping, role_mapping, auto_provision, trust_unverified_email";
+
+        // A login started by /oidc/login, waiting for the IdP to call back
+        pub struct PendingOidcLogin {
+            pub config_id: Uuid,
+            pub nonce: String,
+            pub code_verifier: String,
+            pub redirect_to: String,
+        }
+
+        fn oidc_config_from_row(row: &PgRow) -> OidcConfig {
+            let attribute_mapping: serde_json::Value = row.get("attribute_mapping");
+            let role_mapping: serde_json::Value = row.get("role_mapping");
+
+            OidcConfig {
+                id: row.get("id"),
+                institution_name: row.get("institution_name"),
+                issuer: row.get("issuer"),
+                client_id: row.get("client_id"),
+                client_secret: row.get("client_secret"),
+                scopes: row.get("scopes"),
+                active: row.get("active"),
+                created_at: row.get("created_at"),
+                updated_at: row.get("updated_at"),
+                attribute_mapping: serde_json::from_value(attribute_mapping).unwrap_or_default(),
+                role_mapping: serde_json::from_value(role_mapping).unwrap_or_default(),
+                auto_provision: row.get("auto_provision"),
+                trust_unverified_email: row.get("trust_unverified_email"),
+            }
+        }
+
+        pub async fn get_oidc_configs(pool: &Pool<Postgres>, active_only: bool) -> Result<Vec<OidcConfig>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT {} FROM oidc_configs WHERE active OR NOT $1 ORDER BY institution_name",
+                OIDC_CONFIG_COLUMNS
+            ))
+            .bind(active_only)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(rows.iter().map(oidc_config_from_row).collect())
+        }
+
+        pub async fn get_oidc_config_by_id(pool: &Pool<Postgres>, config_id: Uuid) -> Result<Option<OidcConfig>, ServerFnError> {
+            let row = sqlx::query(&format!("SELECT {} FROM oidc_configs WHERE id = $1", OIDC_CONFIG_COLUMNS))
+                .bind(config_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(row.as_ref().map(oidc_config_from_row))
+        }
+
+        pub async fn create_oidc_config(pool: &Pool<Postgres>, config: &OidcConfig) -> Result<(), ServerFnError> {
+            let attribute_mapping_json = serde_json::to_value(&config.attribute_mapping)
+                .map_err(|e| ServerFnError::new(format!("Failed to serialize attribute mapping: {}", e)))?;
+            let role_mapping_json = serde_json::to_value(&config.role_mapping)
+                .map_err(|e| ServerFnError::new(format!("Failed to serialize role mapping: {}", e)))?;
+
+            sqlx::query(
+                "INSERT INTO oidc_configs (id, institution_name, issuer, client_id, client_secret, scopes, active,
+                                           created_at, updated_at, attribute_mapping, role_mapping, auto_provision,
+                                           trust_unverified_email)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)"
+            )
+            .bind(config.id)
+            .bind(&config.institution_name)
+            .bind(&config.issuer)
+            .bind(&config.client_id)
+            .bind(&config.client_secret)
+            .bind(&config.scopes)
+            .bind(config.active)
+            .bind(config.created_at)
+            .bind(config.updated_at)
+            .bind(attribute_mapping_json)
+            .bind(role_mapping_json)
+            .bind(config.auto_provision)
+            .bind(config.trust_unverified_email)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to create OIDC config: {}", e)))?;
+
+            Ok(())
+        }
+
+        // An empty client_secret keeps the stored one, so the secret never has
+        // to be sent back to the browser for editing
+        pub async fn update_oidc_config(pool: &Pool<Postgres>, config: &OidcConfig) -> Result<(), ServerFnError> {
+            sqlx::query(
+                "UPDATE oidc_configs SET
+                    institution_name = $2,
+                    issuer = $3,
+                    client_id = $4,
+                    client_secret = CASE WHEN $5 = '' THEN client_secret ELSE $5 END,
+                    scopes = $6,
+                    active = $7,
+                    trust_unverified_email = $8,
+                    updated_at = NOW()
+                 WHERE id = $1"
+            )
+            .bind(config.id)
+            .bind(&config.institution_name)
+            .bind(&config.issuer)
+            .bind(&config.client_id)
+            .bind(&config.client_secret)
+            .bind(&config.scopes)
+            .bind(config.active)
+            .bind(config.trust_unverified_email)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to update OIDC config: {}", e)))?;
+
+            Ok(())
+        }
+
+        pub async fn update_oidc_mapping(pool: &Pool<Postgres>, config: &OidcConfig) -> Result<(), ServerFnError> {
+            let attribute_mapping_json = serde_json::to_value(&config.attribute_mapping)
+                .map_err(|e| ServerFnError::new(format!("Failed to serialize attribute mapping: {}", e)))?;
+            let role_mapping_json = serde_json::to_value(&config.role_mapping)
+                .map_err(|e| ServerFnError::new(format!("Failed to serialize role mapping: {}", e)))?;
+
+            sqlx::query("UPDATE oidc_configs SET attribute_mapping = $2, role_mapping = $3, updated_at = NOW() WHERE id = $1")
+                .bind(config.id)
+                .bind(attribute_mapping_json)
+                .bind(role_mapping_json)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to update OIDC mapping: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Returns the institution name of the deleted config
+        pub async fn delete_oidc_config(pool: &Pool<Postgres>, config_id: Uuid) -> Result<String, ServerFnError> {
+            let institution_name: String = sqlx::query_scalar("DELETE FROM oidc_configs WHERE id = $1 RETURNING institution_name")
+                .bind(config_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to delete OIDC config: {}", e)))?
+                .ok_or_else(|| ServerFnError::new("OIDC configuration not found"))?;
+
+            // Account links are keyed by config ID, like SAML's
+            sqlx::query("DELETE FROM saml_user_mappings WHERE institution_id = $1")
+                .bind(config_id.to_string())
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to remove OIDC account links: {}", e)))?;
+
+            Ok(institution_name)
+        }
+
+        pub async fn toggle_oidc_config(pool: &Pool<Postgres>, config_id: Uuid) -> Result<(String, bool), ServerFnError> {
+            let row = sqlx::query("UPDATE oidc_configs SET active = NOT active, updated_at = NOW() WHERE id = $1 RETURNING institution_name, active")
+                .bind(config_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to update OIDC config: {}", e)))?
+                .ok_or_else(|| ServerFnError::new("OIDC configuration not found"))?;
+
+            Ok((row.get("institution_name"), row.get("active")))
+        }
+
+        pub async fn record_oidc_login(
+            pool: &Pool<Postgres>,
+            state: &str,
+            config_id: Uuid,
+            nonce: &str,
+            code_verifier: &str,
+            redirect_to: &str,
+        ) -> Result<(), ServerFnError> {
+            sqlx::query("DELETE FROM oidc_pending_logins WHERE created_at < NOW() - INTERVAL '1 hour'")
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to prune pending OIDC logins: {}", e)))?;
+
+            sqlx::query("INSERT INTO oidc_pending_logins (state, config_id, nonce, code_verifier, redirect_to) VALUES ($1, $2, $3, $4, $5)")
+                .bind(state)
+                .bind(config_id)
+                .bind(nonce)
+                .bind(code_verifier)
+                .bind(redirect_to)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to record OIDC login: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Each state value can be used once, within ten minutes of the redirect
+        pub async fn consume_oidc_login(pool: &Pool<Postgres>, state: &str) -> Result<Option<PendingOidcLogin>, ServerFnError> {
+            let row = sqlx::query(
+                "DELETE FROM oidc_pending_logins
+                 WHERE state = $1 AND created_at > NOW() - INTERVAL '10 minutes'
+                 RETURNING config_id, nonce, code_verifier, redirect_to"
+            )
+            .bind(state)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to look up OIDC login: {}", e)))?;
+
+            Ok(row.map(|row| PendingOidcLogin {
+                config_id: row.get("config_id"),
+                nonce: row.get("nonce"),
+                code_verifier: row.get("code_verifier"),
+                redirect_to: row.get("redirect_to"),
+            }))
+        }
+
+        // Runs validated ID token claims through the institution's mapping and
+        // hands them to the same linking/JIT path SAML logins use. The config
+        // ID stands in for the SAML institution ID and `sub` for the NameID.
+        pub async fn provision_oidc_user(
+            pool: &Pool<Postgres>,
+            config: &OidcConfig,
+            claims: &IdTokenClaims,
+        ) -> Result<SessionUser, ServerFnError> {
+            let attributes = &claims.attributes;
+
+            // An unverified address must not be able to claim an existing account
+            let email = config
+                .mapped_value(SamlMappedField::Email, attributes)
+                .filter(|_| claims.email_verified || config.trust_unverified_email)
+                .ok_or_else(|| ServerFnError::new("Identity provider did not supply a verified email address"))?;
+
+            let first_name = config.mapped_value(SamlMappedField::FirstName, attributes);
+            let last_name = config.mapped_value(SamlMappedField::LastName, attributes);
+            let display_name = config.mapped_value(SamlMappedField::DisplayName, attributes);
+            let (role, _) = config.resolve_role(attributes);
+
+            let profile = SamlResponse {
+                name_id: claims.subject.clone(),
+                email: Some(email),
+                first_name,
+                last_name,
+                display_name,
+                attributes: attributes.clone(),
+                session_index: None,
+                school: config.mapped_value(SamlMappedField::School, attributes),
+                employee_id: config.mapped_value(SamlMappedField::EmployeeId, attributes),
+                role,
+            };
+
+            saml_database::provision_saml_user(pool, &profile, &config.id.to_string(), config.auto_provision).await
+        }
+    }
+}
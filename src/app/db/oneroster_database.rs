@@ -0,0 +1,814 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::audit_database::write_audit_event;
+        use crate::app::db::student_database::{read_all_students, upsert_students};
+        use crate::app::db::student_protection_database::lock_unprotected;
+        use crate::app::db::student_history_database::write_student_versions;
+        use crate::app::db::student_encryption_database::student_keyring;
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::employee::{EmployeeRole, StatusEnum};
+        use crate::app::models::enrollment::{AcademicYear, EnrollmentStatus};
+        use crate::app::models::oneroster::{
+            grade_code, grade_from_code, roster_role_of, school_year_of, sex_of, RosterAcademicSession, RosterBundle, RosterClass,
+            RosterCourse, RosterEnrollment, RosterImportSummary, RosterOrg, RosterUser, ROLE_STUDENT, ROLE_TEACHER,
+        };
+        use crate::app::models::student::{ESLEnum, GradeEnum, Student};
+        use crate::app::models::user::SessionUser;
+        use crate::app::services::student_encryption::PiiKeyring;
+        use chrono::NaiveDate;
+        use leptos::ServerFnError;
+        use sqlx::{PgPool, Postgres, Row, Transaction};
+        use std::collections::{HashMap, HashSet};
+
+        // Longest values the courses table takes
+        const MAX_COURSE_CODE: usize = 50;
+        const MAX_SEMESTER_PERIOD: usize = 20;
+        const MAX_ROOM_NUMBER: usize = 20;
+        // Errors listed before the rest are only counted
+        const MAX_REPORTED_ERRORS: usize = 25;
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn truncate(value: &str, length: usize) -> String {
+            value.chars().take(length).collect()
+        }
+
+        // A class as saved: the course row and what its enrollments inherit
+        struct ImportedClass {
+            course_id: i32,
+            academic_year: AcademicYear,
+            teacher_id: i32,
+            teacher_lastname: String,
+        }
+
+        async fn save_orgs_and_sessions(bundle: &RosterBundle, tx: &mut Transaction<'_, Postgres>) -> Result<(), ServerFnError> {
+            for org in &bundle.orgs {
+                sqlx::query(
+                    "INSERT INTO roster_orgs (sourced_id, name, org_type, identifier, parent_sourced_id) VALUES ($1, $2, $3, $4, $5)
+                     ON CONFLICT (sourced_id) DO UPDATE SET name = EXCLUDED.name, org_type = EXCLUDED.org_type, identifier = EXCLUDED.identifier,
+                         parent_sourced_id = EXCLUDED.parent_sourced_id, updated_at = NOW()"
+                )
+                .bind(&org.sourced_id)
+                .bind(&org.name)
+                .bind(&org.org_type)
+                .bind(&org.identifier)
+                .bind(&org.parent_sourced_id)
+                .execute(&mut **tx)
+                .await
+                .map_err(db_error)?;
+            }
+            for session in &bundle.academic_sessions {
+                sqlx::query(
+                    "INSERT INTO roster_academic_sessions (sourced_id, title, session_type, start_date, end_date, parent_sourced_id, school_year) VALUES ($1, $2, $3, $4, $5, $6, $7)
+                     ON CONFLICT (sourced_id) DO UPDATE SET title = EXCLUDED.title, session_type = EXCLUDED.session_type, start_date = EXCLUDED.start_date,
+                         end_date = EXCLUDED.end_date, parent_sourced_id = EXCLUDED.parent_sourced_id, school_year = EXCLUDED.school_year, updated_at = NOW()"
+                )
+                .bind(&session.sourced_id)
+                .bind(&session.title)
+                .bind(&session.session_type)
+                .bind(session.start_date)
+                .bind(session.end_date)
+                .bind(&session.parent_sourced_id)
+                .bind(session.school_year)
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Academic session {}: {}", session.sourced_id, e)))?;
+            }
+            Ok(())
+        }
+
+        // Teachers are matched on sourcedId, then on name among teachers not yet
+        // tied to the SIS. App accounts with the teacher's email are linked to
+        // them when not linked already.
+        async fn save_teachers(bundle: &RosterBundle, summary: &mut RosterImportSummary, tx: &mut Transaction<'_, Postgres>) -> Result<HashMap<String, (i32, String)>, ServerFnError> {
+            let rows = sqlx::query("SELECT id, firstname, lastname, role::TEXT = 'Teacher' AS is_teacher, sourced_id FROM employees")
+                .fetch_all(&mut **tx)
+                .await
+                .map_err(db_error)?;
+            let mut employees: Vec<(i32, String, String, bool, Option<String>)> = rows
+                .iter()
+                .map(|row| (row.get("id"), row.get("firstname"), row.get("lastname"), row.get("is_teacher"), row.get("sourced_id")))
+                .collect();
+
+            let mut teachers = HashMap::new();
+            for user in bundle.users.iter().filter(|user| user.is_teacher()) {
+                let matched = employees
+                    .iter()
+                    .position(|(_, _, _, _, sourced_id)| sourced_id.as_deref() == Some(user.sourced_id.as_str()))
+                    .or_else(|| {
+                        employees.iter().position(|(_, firstname, lastname, is_teacher, sourced_id)| {
+                            *is_teacher && sourced_id.is_none() && firstname.eq_ignore_ascii_case(&user.given_name) && lastname.eq_ignore_ascii_case(&user.family_name)
+                        })
+                    });
+                let grade = user.grade();
+                let id = match matched {
+                    Some(index) => {
+                        let (id, firstname, lastname, _, sourced_id) = &mut employees[index];
+                        if *firstname != user.given_name || *lastname != user.family_name || sourced_id.as_deref() != Some(user.sourced_id.as_str()) || grade.is_some() {
+                            sqlx::query("UPDATE employees SET firstname = $2, lastname = $3, grade = COALESCE($4, grade), sourced_id = $5 WHERE id = $1")
+                                .bind(*id)
+                                .bind(&user.given_name)
+                                .bind(&user.family_name)
+                                .bind(&grade)
+                                .bind(&user.sourced_id)
+                                .execute(&mut **tx)
+                                .await
+                                .map_err(|e| ServerFnError::new(format!("Teacher {}: {}", user.sourced_id, e)))?;
+                            summary.teachers_updated += 1;
+                        }
+                        *sourced_id = Some(user.sourced_id.clone());
+                        *id
+                    }
+                    None => {
+                        let id: i32 = sqlx::query_scalar(
+                            "INSERT INTO employees (firstname, lastname, status, role, grade, sourced_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id"
+                        )
+                        .bind(&user.given_name)
+                        .bind(&user.family_name)
+                        .bind(StatusEnum::Active)
+                        .bind(EmployeeRole::Teacher { grade: grade.clone() })
+                        .bind(&grade)
+                        .bind(&user.sourced_id)
+                        .fetch_one(&mut **tx)
+                        .await
+                        .map_err(|e| ServerFnError::new(format!("Teacher {}: {}", user.sourced_id, e)))?;
+                        summary.teachers_created += 1;
+                        id
+                    }
+                };
+                if let Some(email) = &user.email {
+                    sqlx::query("UPDATE users SET employee_id = $1, updated_at = NOW() WHERE employee_id IS NULL AND LOWER(email) = LOWER($2)")
+                        .bind(id)
+                        .bind(email)
+                        .execute(&mut **tx)
+                        .await
+                        .map_err(db_error)?;
+                }
+                teachers.insert(user.sourced_id.clone(), (id, user.family_name.clone()));
+            }
+            Ok(teachers)
+        }
+
+        // The session a class runs in: its first listed term, else its
+        // course's school year
+        fn class_session<'a>(class: &RosterClass, courses: &HashMap<&str, &'a RosterCourse>, sessions: &HashMap<&str, &'a RosterAcademicSession>) -> Option<&'a RosterAcademicSession> {
+            class
+                .term_sourced_ids
+                .iter()
+                .find_map(|id| sessions.get(id.as_str()).copied())
+                .or_else(|| {
+                    courses
+                        .get(class.course_sourced_id.as_str())
+                        .and_then(|course| course.school_year_sourced_id.as_deref())
+                        .and_then(|id| sessions.get(id).copied())
+                })
+        }
+
+        // Classes are matched on sourcedId, then on course code within the year
+        // among courses not yet tied to the SIS
+        async fn save_classes(
+            bundle: &RosterBundle,
+            teachers: &HashMap<String, (i32, String)>,
+            summary: &mut RosterImportSummary,
+            errors: &mut Vec<String>,
+            tx: &mut Transaction<'_, Postgres>,
+        ) -> Result<HashMap<String, ImportedClass>, ServerFnError> {
+            let courses: HashMap<&str, &RosterCourse> = bundle.courses.iter().map(|course| (course.sourced_id.as_str(), course)).collect();
+            let sessions: HashMap<&str, &RosterAcademicSession> = bundle.academic_sessions.iter().map(|session| (session.sourced_id.as_str(), session)).collect();
+            // Each class's teacher, preferring the one marked primary
+            let mut class_teachers: HashMap<&str, (&str, bool)> = HashMap::new();
+            for enrollment in bundle.enrollments.iter().filter(|enrollment| enrollment.role == ROLE_TEACHER) {
+                let entry = class_teachers.entry(enrollment.class_sourced_id.as_str()).or_insert((enrollment.user_sourced_id.as_str(), enrollment.primary));
+                if enrollment.primary && !entry.1 {
+                    *entry = (enrollment.user_sourced_id.as_str(), true);
+                }
+            }
+
+            let rows = sqlx::query("SELECT id, course_code, academic_year, sourced_id FROM courses")
+                .fetch_all(&mut **tx)
+                .await
+                .map_err(db_error)?;
+            let mut existing: Vec<(i32, Option<String>, AcademicYear, Option<String>)> = rows
+                .iter()
+                .map(|row| (row.get("id"), row.get("course_code"), row.get("academic_year"), row.get("sourced_id")))
+                .collect();
+
+            let mut classes = HashMap::new();
+            for class in &bundle.classes {
+                let course = courses.get(class.course_sourced_id.as_str());
+                let Some(session) = class_session(class, &courses, &sessions) else {
+                    errors.push(format!("Class {}: none of its terms are in academicSessions.csv", class.sourced_id));
+                    continue;
+                };
+                let Some(academic_year) = session.academic_year() else {
+                    errors.push(format!("Class {}: school year {} is outside the years the app supports", class.sourced_id, session.school_year));
+                    continue;
+                };
+                let Some((teacher_id, teacher_lastname)) = class_teachers.get(class.sourced_id.as_str()).and_then(|(user, _)| teachers.get(*user)).cloned() else {
+                    errors.push(format!("Class {}: no teacher in users.csv is enrolled in it", class.sourced_id));
+                    continue;
+                };
+                let grades = if class.grades.is_empty() { course.map(|course| course.grades.as_slice()).unwrap_or_default() } else { class.grades.as_slice() };
+                let Some(course_level) = grades.iter().find_map(|code| grade_from_code(code)) else {
+                    errors.push(format!("Class {}: no grade the app supports in its grades", class.sourced_id));
+                    continue;
+                };
+                let subject = class.subjects.first().or_else(|| course.and_then(|course| course.subjects.first())).cloned().unwrap_or_default();
+                let course_code = truncate(
+                    class.class_code.as_deref().or_else(|| course.and_then(|course| course.course_code.as_deref())).unwrap_or(&class.sourced_id),
+                    MAX_COURSE_CODE,
+                );
+                let semester_period = if session.session_type == "schoolYear" { "Full Year".to_string() } else { truncate(&session.title, MAX_SEMESTER_PERIOD) };
+                let room_number = class.location.as_deref().map(|room| truncate(room, MAX_ROOM_NUMBER));
+
+                let matched = existing
+                    .iter()
+                    .position(|(_, _, _, sourced_id)| sourced_id.as_deref() == Some(class.sourced_id.as_str()))
+                    .or_else(|| {
+                        existing.iter().position(|(_, code, year, sourced_id)| {
+                            sourced_id.is_none() && code.as_deref() == Some(course_code.as_str()) && *year == academic_year
+                        })
+                    });
+                let course_id = match matched {
+                    Some(index) => {
+                        let id = existing[index].0;
+                        sqlx::query(
+                            "UPDATE courses SET name = $2, subject = $3, course_code = $4, course_level = $5, teacher_id = $6, academic_year = $7, semester_period = $8,
+                                 room_number = $9, sourced_id = $10, course_sourced_id = $11, term_sourced_id = $12, updated_at = NOW()
+                             WHERE id = $1"
+                        )
+                        .bind(id)
+                        .bind(&class.title)
+                        .bind(&subject)
+                        .bind(&course_code)
+                        .bind(&course_level)
+                        .bind(teacher_id)
+                        .bind(&academic_year)
+                        .bind(&semester_period)
+                        .bind(&room_number)
+                        .bind(&class.sourced_id)
+                        .bind(&class.course_sourced_id)
+                        .bind(&session.sourced_id)
+                        .execute(&mut **tx)
+                        .await
+                        .map_err(|e| ServerFnError::new(format!("Class {}: {}", class.sourced_id, e)))?;
+                        existing[index].3 = Some(class.sourced_id.clone());
+                        summary.classes_updated += 1;
+                        id
+                    }
+                    None => {
+                        let id: i32 = sqlx::query_scalar(
+                            "INSERT INTO courses (name, subject, course_code, course_level, teacher_id, academic_year, semester_period, credits, description, max_students,
+                                 room_number, sourced_id, course_sourced_id, term_sourced_id)
+                             VALUES ($1, $2, $3, $4, $5, $6, $7, 1.00, '', 30, $8, $9, $10, $11) RETURNING id"
+                        )
+                        .bind(&class.title)
+                        .bind(&subject)
+                        .bind(&course_code)
+                        .bind(&course_level)
+                        .bind(teacher_id)
+                        .bind(&academic_year)
+                        .bind(&semester_period)
+                        .bind(&room_number)
+                        .bind(&class.sourced_id)
+                        .bind(&class.course_sourced_id)
+                        .bind(&session.sourced_id)
+                        .fetch_one(&mut **tx)
+                        .await
+                        .map_err(|e| ServerFnError::new(format!("Class {}: {}", class.sourced_id, e)))?;
+                        summary.classes_created += 1;
+                        id
+                    }
+                };
+                classes.insert(class.sourced_id.clone(), ImportedClass { course_id, academic_year, teacher_id, teacher_lastname });
+            }
+            Ok(classes)
+        }
+
+        // The SIS's fields merged over the stored student, or a new student
+        // with the app's own fields at their defaults
+        fn merge_student(user: &RosterUser, student_id: i32, existing: Option<&Student>, teacher: Option<&str>) -> Result<Student, String> {
+            let label = format!("Student {} ({} {})", user.sourced_id, user.given_name, user.family_name);
+            let current_grade_level = user
+                .grade()
+                .or_else(|| existing.map(|student| student.current_grade_level.clone()))
+                .ok_or_else(|| format!("{}: no grade the app supports in its grades", label))?;
+            let date_of_birth = user
+                .birth_date
+                .or_else(|| existing.map(|student| student.date_of_birth))
+                .ok_or_else(|| format!("{}: no birthDate in demographics.csv", label))?;
+            let gender = user
+                .gender()
+                .or_else(|| existing.map(|student| student.gender.clone()))
+                .ok_or_else(|| format!("{}: no sex in demographics.csv", label))?;
+            let preferred = user.preferred_given_name.clone().unwrap_or_else(|| user.given_name.clone());
+
+            Ok(match existing {
+                Some(student) => Student {
+                    firstname: Some(user.given_name.clone()),
+                    lastname: Some(user.family_name.clone()),
+                    preferred,
+                    gender,
+                    date_of_birth,
+                    current_grade_level,
+                    teacher: teacher.map(str::to_string).unwrap_or_else(|| student.teacher.clone()),
+                    ..student.clone()
+                },
+                None => Student::new(
+                    Some(user.given_name.clone()),
+                    Some(user.family_name.clone()),
+                    preferred,
+                    gender,
+                    date_of_birth,
+                    student_id,
+                    ESLEnum::NotApplicable,
+                    current_grade_level,
+                    teacher.unwrap_or_default().to_string(),
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    None,
+                    false,
+                    String::new(),
+                    None,
+                ),
+            })
+        }
+
+        // Students are matched on sourcedId, then on their identifier as a
+        // student ID. Returns each student's ID and grade by sourcedId, and the
+        // IDs of students created or changed.
+        async fn save_students(
+            bundle: &RosterBundle,
+            teachers: &HashMap<&str, &str>,
+            existing: &HashMap<i32, Student>,
+            keyring: Option<&PiiKeyring>,
+            summary: &mut RosterImportSummary,
+            errors: &mut Vec<String>,
+            tx: &mut Transaction<'_, Postgres>,
+        ) -> Result<(HashMap<String, (i32, GradeEnum)>, Vec<i32>), ServerFnError> {
+            let rows = sqlx::query("SELECT sourced_id, student_id FROM students WHERE sourced_id IS NOT NULL")
+                .fetch_all(&mut **tx)
+                .await
+                .map_err(db_error)?;
+            let sourced: HashMap<String, i32> = rows.iter().map(|row| (row.get("sourced_id"), row.get("student_id"))).collect();
+
+            let mut students = Vec::new();
+            let mut sourced_ids = Vec::new();
+            let mut imported = HashMap::new();
+            let mut seen = HashSet::new();
+            for user in bundle.users.iter().filter(|user| user.is_student()) {
+                let label = format!("Student {} ({} {})", user.sourced_id, user.given_name, user.family_name);
+                let student_id = match (sourced.get(&user.sourced_id), user.student_id()) {
+                    (Some(&stored), Some(identifier)) if stored != identifier => {
+                        errors.push(format!("{}: identifier {} differs from student {} it was imported as", label, identifier, stored));
+                        continue;
+                    }
+                    (Some(&stored), _) => stored,
+                    (None, Some(identifier)) => identifier,
+                    (None, None) => {
+                        errors.push(format!("{}: identifier must be the numeric student ID", label));
+                        continue;
+                    }
+                };
+                if !seen.insert(student_id) {
+                    errors.push(format!("{}: student ID {} is used by another student in the bundle", label, student_id));
+                    continue;
+                }
+                let current = existing.get(&student_id);
+                match merge_student(user, student_id, current, teachers.get(user.sourced_id.as_str()).copied()) {
+                    Ok(student) => {
+                        imported.insert(user.sourced_id.clone(), (student_id, student.current_grade_level.clone()));
+                        if sourced.get(&user.sourced_id) != Some(&student_id) {
+                            sourced_ids.push((student_id, user.sourced_id.clone()));
+                        }
+                        match current {
+                            None => summary.students_created += 1,
+                            Some(current) if *current != student => summary.students_updated += 1,
+                            Some(_) => continue,
+                        }
+                        students.push(student);
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+
+            upsert_students(&students, keyring, tx).await?;
+            let (ids, sourced_ids): (Vec<i32>, Vec<String>) = sourced_ids.into_iter().unzip();
+            sqlx::query("UPDATE students s SET sourced_id = u.sourced_id FROM UNNEST($1::INT[], $2::TEXT[]) AS u(student_id, sourced_id) WHERE s.student_id = u.student_id")
+                .bind(&ids)
+                .bind(&sourced_ids)
+                .execute(&mut **tx)
+                .await
+                .map_err(db_error)?;
+
+            Ok((imported, students.iter().map(|student| student.student_id).collect()))
+        }
+
+        // Enrollments are matched on sourcedId, then on student and course among
+        // enrollments not yet tied to the SIS. Ended enrollments are kept as
+        // inactive.
+        async fn save_enrollments(
+            bundle: &RosterBundle,
+            students: &HashMap<String, (i32, GradeEnum)>,
+            classes: &HashMap<String, ImportedClass>,
+            today: NaiveDate,
+            summary: &mut RosterImportSummary,
+            errors: &mut Vec<String>,
+            tx: &mut Transaction<'_, Postgres>,
+        ) -> Result<(), ServerFnError> {
+            let student_users: HashSet<&str> = bundle.users.iter().filter(|user| user.is_student()).map(|user| user.sourced_id.as_str()).collect();
+            let class_ids: HashSet<&str> = bundle.classes.iter().map(|class| class.sourced_id.as_str()).collect();
+
+            let rows = sqlx::query("SELECT id, student_id, course_id, sourced_id FROM student_enrollments")
+                .fetch_all(&mut **tx)
+                .await
+                .map_err(db_error)?;
+            let mut existing: Vec<(i32, i32, Option<i32>, Option<String>)> = rows
+                .iter()
+                .map(|row| (row.get("id"), row.get("student_id"), row.get("course_id"), row.get("sourced_id")))
+                .collect();
+
+            for enrollment in bundle.enrollments.iter().filter(|enrollment| enrollment.role == ROLE_STUDENT) {
+                // Students and classes that failed to import have been reported already
+                let Some((student_id, grade_level)) = students.get(&enrollment.user_sourced_id) else {
+                    if !student_users.contains(enrollment.user_sourced_id.as_str()) {
+                        errors.push(format!("Enrollment {}: {} is not a student in users.csv", enrollment.sourced_id, enrollment.user_sourced_id));
+                    }
+                    continue;
+                };
+                let Some(class) = classes.get(&enrollment.class_sourced_id) else {
+                    if !class_ids.contains(enrollment.class_sourced_id.as_str()) {
+                        errors.push(format!("Enrollment {}: class {} is not in classes.csv", enrollment.sourced_id, enrollment.class_sourced_id));
+                    }
+                    continue;
+                };
+                let ended = enrollment.end_date.filter(|end| *end < today);
+                let status = if ended.is_some() { EnrollmentStatus::Inactive } else { EnrollmentStatus::Active };
+                let enrollment_date = enrollment.begin_date.unwrap_or(today);
+
+                let matched = existing
+                    .iter()
+                    .position(|(_, _, _, sourced_id)| sourced_id.as_deref() == Some(enrollment.sourced_id.as_str()))
+                    .or_else(|| {
+                        existing.iter().position(|(_, student, course, sourced_id)| {
+                            sourced_id.is_none() && student == student_id && *course == Some(class.course_id)
+                        })
+                    });
+                match matched {
+                    Some(index) => {
+                        let updated = sqlx::query(
+                            "UPDATE student_enrollments SET student_id = $2, academic_year = $3, grade_level = $4, teacher_id = $5, course_id = $6,
+                                 status = $7, enrollment_date = $8, status_change_date = COALESCE($9, status_change_date), sourced_id = $10, updated_at = NOW()
+                             WHERE id = $1 AND (student_id, academic_year, grade_level, teacher_id, course_id, status, enrollment_date, sourced_id)
+                                 IS DISTINCT FROM ($2, $3, $4, $5, $6, $7, $8, $10)"
+                        )
+                        .bind(existing[index].0)
+                        .bind(student_id)
+                        .bind(&class.academic_year)
+                        .bind(grade_level)
+                        .bind(class.teacher_id)
+                        .bind(class.course_id)
+                        .bind(&status)
+                        .bind(enrollment_date)
+                        .bind(ended)
+                        .bind(&enrollment.sourced_id)
+                        .execute(&mut **tx)
+                        .await
+                        .map_err(|e| ServerFnError::new(format!("Enrollment {}: {}", enrollment.sourced_id, e)))?
+                        .rows_affected();
+                        if updated > 0 {
+                            summary.enrollments_updated += 1;
+                        }
+                        existing[index].3 = Some(enrollment.sourced_id.clone());
+                    }
+                    None => {
+                        sqlx::query(
+                            "INSERT INTO student_enrollments (student_id, academic_year, grade_level, teacher_id, course_id, status, enrollment_date, status_change_date, sourced_id)
+                             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+                        )
+                        .bind(student_id)
+                        .bind(&class.academic_year)
+                        .bind(grade_level)
+                        .bind(class.teacher_id)
+                        .bind(class.course_id)
+                        .bind(&status)
+                        .bind(enrollment_date)
+                        .bind(ended)
+                        .bind(&enrollment.sourced_id)
+                        .execute(&mut **tx)
+                        .await
+                        .map_err(|e| ServerFnError::new(format!("Enrollment {}: {}", enrollment.sourced_id, e)))?;
+                        summary.enrollments_created += 1;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        // Applies a OneRoster bundle in one transaction with the versions of
+        // students it changes and its audit event: nothing is saved unless
+        // every org, session, teacher, class, student and enrollment maps
+        // cleanly.
+        pub async fn import_roster_bundle(pool: &PgPool, bundle: &RosterBundle, today: NaiveDate, actor: &SessionUser) -> Result<RosterImportSummary, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut summary = RosterImportSummary {
+                source_system: bundle.source_system.clone(),
+                orgs: bundle.orgs.len(),
+                academic_sessions: bundle.academic_sessions.len(),
+                users_skipped: bundle.users.iter().filter(|user| !user.is_student() && !user.is_teacher()).count(),
+                ..Default::default()
+            };
+            let mut errors = Vec::new();
+
+            let mut tx = pool.begin().await?;
+            lock_unprotected(&mut tx, "Turn off student protections before importing a OneRoster bundle").await?;
+            let existing: HashMap<i32, Student> = read_all_students(&mut tx, keyring.as_deref())
+                .await?
+                .into_iter()
+                .map(|student| (student.student_id, student))
+                .collect();
+            save_orgs_and_sessions(bundle, &mut tx).await?;
+            let teachers = save_teachers(bundle, &mut summary, &mut tx).await?;
+            let classes = save_classes(bundle, &teachers, &mut summary, &mut errors, &mut tx).await?;
+            // Students are named after the teacher of the first class they are in today
+            let mut student_teachers: HashMap<&str, &str> = HashMap::new();
+            for enrollment in bundle.enrollments.iter().filter(|enrollment| enrollment.role == ROLE_STUDENT && enrollment.is_active_on(today)) {
+                if let Some(class) = classes.get(&enrollment.class_sourced_id) {
+                    student_teachers.entry(enrollment.user_sourced_id.as_str()).or_insert(class.teacher_lastname.as_str());
+                }
+            }
+            let (students, changed) = save_students(bundle, &student_teachers, &existing, keyring.as_deref(), &mut summary, &mut errors, &mut tx).await?;
+            save_enrollments(bundle, &students, &classes, today, &mut summary, &mut errors, &mut tx).await?;
+
+            if !errors.is_empty() {
+                tx.rollback().await?;
+                let mut message = errors.iter().take(MAX_REPORTED_ERRORS).cloned().collect::<Vec<_>>().join("\n");
+                if errors.len() > MAX_REPORTED_ERRORS {
+                    message.push_str(&format!("\n...and {} more", errors.len() - MAX_REPORTED_ERRORS));
+                }
+                return Err(ServerFnError::new(format!("Nothing was imported:\n{}", message)));
+            }
+            write_student_versions(&mut tx, &changed, &actor.username).await?;
+            write_audit_event(
+                &mut tx,
+                actor,
+                AuditAction::Create,
+                AuditEntity::Roster,
+                summary.source_system.as_deref().unwrap_or("oneroster"),
+                AuditChange::created(&summary),
+            )
+            .await?;
+            tx.commit().await?;
+            Ok(summary)
+        }
+
+        fn school_year_session(academic_year: &AcademicYear) -> RosterAcademicSession {
+            let school_year = school_year_of(academic_year);
+            RosterAcademicSession {
+                sourced_id: format!("school-year-{}", school_year),
+                title: academic_year.to_string(),
+                session_type: "schoolYear".to_string(),
+                start_date: NaiveDate::from_ymd_opt(school_year - 1, 8, 1).unwrap_or_default(),
+                end_date: NaiveDate::from_ymd_opt(school_year, 6, 30).unwrap_or_default(),
+                parent_sourced_id: None,
+                school_year,
+            }
+        }
+
+        // Everything the app holds as a OneRoster bundle. Records never imported
+        // get sourcedIds made from their local IDs. Enrollments outside any
+        // course have no class to belong to and are left out.
+        pub async fn load_roster_bundle(pool: &PgPool) -> Result<RosterBundle, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_unprotected(&mut tx, "Turn off student protections before exporting a OneRoster bundle").await?;
+
+            let mut orgs: Vec<RosterOrg> = sqlx::query("SELECT sourced_id, name, org_type, identifier, parent_sourced_id FROM roster_orgs ORDER BY sourced_id")
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(db_error)?
+                .iter()
+                .map(|row| RosterOrg {
+                    sourced_id: row.get("sourced_id"),
+                    name: row.get("name"),
+                    org_type: row.get("org_type"),
+                    identifier: row.get("identifier"),
+                    parent_sourced_id: row.get("parent_sourced_id"),
+                })
+                .collect();
+            if orgs.is_empty() {
+                orgs.push(RosterOrg {
+                    sourced_id: "school".to_string(),
+                    name: "School".to_string(),
+                    org_type: "school".to_string(),
+                    identifier: None,
+                    parent_sourced_id: None,
+                });
+            }
+            let school = orgs
+                .iter()
+                .find(|org| org.org_type == "school")
+                .unwrap_or(&orgs[0])
+                .sourced_id
+                .clone();
+
+            let mut academic_sessions: Vec<RosterAcademicSession> = sqlx::query(
+                "SELECT sourced_id, title, session_type, start_date, end_date, parent_sourced_id, school_year FROM roster_academic_sessions ORDER BY start_date, sourced_id"
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .iter()
+            .map(|row| RosterAcademicSession {
+                sourced_id: row.get("sourced_id"),
+                title: row.get("title"),
+                session_type: row.get("session_type"),
+                start_date: row.get("start_date"),
+                end_date: row.get("end_date"),
+                parent_sourced_id: row.get("parent_sourced_id"),
+                school_year: row.get("school_year"),
+            })
+            .collect();
+
+            let employee_rows = sqlx::query(
+                "SELECT DISTINCT ON (e.id) e.id, e.firstname, e.lastname, e.role, e.grade, e.sourced_id, u.username, u.email
+                 FROM employees e LEFT JOIN users u ON u.employee_id = e.id
+                 ORDER BY e.id, u.id"
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let mut users = Vec::new();
+            let mut employee_ids = HashMap::new();
+            for row in &employee_rows {
+                let id: i32 = row.get("id");
+                let sourced_id = row.get::<Option<String>, _>("sourced_id").unwrap_or_else(|| format!("employee-{}", id));
+                let role: EmployeeRole = row.get("role");
+                let grade: Option<GradeEnum> = row.get("grade");
+                employee_ids.insert(id, sourced_id.clone());
+                users.push(RosterUser {
+                    enabled: true,
+                    username: row.get::<Option<String>, _>("username").unwrap_or_else(|| sourced_id.clone()),
+                    identifier: None,
+                    given_name: row.get("firstname"),
+                    family_name: row.get("lastname"),
+                    preferred_given_name: None,
+                    email: row.get("email"),
+                    grades: grade.iter().map(|grade| grade_code(grade).to_string()).collect(),
+                    role: roster_role_of(&role).to_string(),
+                    org_sourced_id: Some(school.clone()),
+                    birth_date: None,
+                    sex: None,
+                    sourced_id,
+                });
+            }
+
+            let student_sourced: HashMap<i32, String> = sqlx::query("SELECT student_id, sourced_id FROM students WHERE sourced_id IS NOT NULL")
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(db_error)?
+                .iter()
+                .map(|row| (row.get("student_id"), row.get("sourced_id")))
+                .collect();
+            let mut student_ids = HashMap::new();
+            for student in read_all_students(&mut tx, keyring.as_deref()).await? {
+                let sourced_id = student_sourced.get(&student.student_id).cloned().unwrap_or_else(|| format!("student-{}", student.student_id));
+                student_ids.insert(student.student_id, sourced_id.clone());
+                let given_name = student.firstname.clone().unwrap_or_else(|| student.preferred.clone());
+                users.push(RosterUser {
+                    enabled: true,
+                    username: student.student_id.to_string(),
+                    identifier: Some(student.student_id.to_string()),
+                    preferred_given_name: Some(student.preferred.clone()).filter(|preferred| !preferred.is_empty() && *preferred != given_name),
+                    given_name,
+                    family_name: student.lastname.clone().unwrap_or_default(),
+                    email: None,
+                    grades: vec![grade_code(&student.current_grade_level).to_string()],
+                    role: ROLE_STUDENT.to_string(),
+                    org_sourced_id: Some(school.clone()),
+                    birth_date: Some(student.date_of_birth),
+                    sex: Some(sex_of(&student.gender).to_string()),
+                    sourced_id,
+                });
+            }
+
+            let course_rows = sqlx::query(
+                "SELECT id, name, subject, course_code, course_level, teacher_id, academic_year, room_number, sourced_id, course_sourced_id, term_sourced_id
+                 FROM courses ORDER BY id"
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let mut courses: Vec<RosterCourse> = Vec::new();
+            let mut classes = Vec::new();
+            let mut enrollments = Vec::new();
+            let mut class_ids = HashMap::new();
+            for row in &course_rows {
+                let id: i32 = row.get("id");
+                let academic_year: AcademicYear = row.get("academic_year");
+                let school_year = school_year_of(&academic_year);
+                let year_session = match academic_sessions.iter().find(|session| session.session_type == "schoolYear" && session.school_year == school_year) {
+                    Some(session) => session.sourced_id.clone(),
+                    None => {
+                        let session = school_year_session(&academic_year);
+                        let sourced_id = session.sourced_id.clone();
+                        academic_sessions.push(session);
+                        sourced_id
+                    }
+                };
+                let term = row
+                    .get::<Option<String>, _>("term_sourced_id")
+                    .filter(|term| academic_sessions.iter().any(|session| session.sourced_id == *term))
+                    .unwrap_or_else(|| year_session.clone());
+                let class_sourced_id = row.get::<Option<String>, _>("sourced_id").unwrap_or_else(|| format!("class-{}", id));
+                let course_sourced_id = row.get::<Option<String>, _>("course_sourced_id").unwrap_or_else(|| format!("course-{}", id));
+                let grades: Vec<String> = row.get::<Option<GradeEnum>, _>("course_level").iter().map(|grade| grade_code(grade).to_string()).collect();
+                let subjects: Vec<String> = row.get::<Option<String>, _>("subject").into_iter().filter(|subject| !subject.is_empty()).collect();
+                let name: String = row.get("name");
+                let course_code: Option<String> = row.get("course_code");
+
+                if !courses.iter().any(|course| course.sourced_id == course_sourced_id) {
+                    courses.push(RosterCourse {
+                        sourced_id: course_sourced_id.clone(),
+                        title: name.clone(),
+                        course_code: course_code.clone(),
+                        school_year_sourced_id: Some(year_session),
+                        org_sourced_id: school.clone(),
+                        grades: grades.clone(),
+                        subjects: subjects.clone(),
+                    });
+                }
+                if let Some(teacher) = row.get::<Option<i32>, _>("teacher_id").and_then(|teacher_id| employee_ids.get(&teacher_id)) {
+                    enrollments.push(RosterEnrollment {
+                        sourced_id: format!("{}-{}", class_sourced_id, teacher),
+                        class_sourced_id: class_sourced_id.clone(),
+                        school_sourced_id: school.clone(),
+                        user_sourced_id: teacher.clone(),
+                        role: ROLE_TEACHER.to_string(),
+                        primary: true,
+                        begin_date: None,
+                        end_date: None,
+                    });
+                }
+                class_ids.insert(id, class_sourced_id.clone());
+                classes.push(RosterClass {
+                    sourced_id: class_sourced_id,
+                    title: name,
+                    course_sourced_id,
+                    class_code: course_code,
+                    class_type: "scheduled".to_string(),
+                    location: row.get("room_number"),
+                    school_sourced_id: school.clone(),
+                    term_sourced_ids: vec![term],
+                    grades,
+                    subjects,
+                });
+            }
+
+            let enrollment_rows = sqlx::query(
+                "SELECT id, student_id, course_id, status, enrollment_date, status_change_date, sourced_id
+                 FROM student_enrollments WHERE course_id IS NOT NULL ORDER BY id"
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            for row in &enrollment_rows {
+                let id: i32 = row.get("id");
+                let student_id: i32 = row.get("student_id");
+                let (Some(user), Some(class)) = (student_ids.get(&student_id), class_ids.get(&row.get::<i32, _>("course_id"))) else {
+                    continue;
+                };
+                let status: EnrollmentStatus = row.get("status");
+                let enrollment_date: NaiveDate = row.get("enrollment_date");
+                let end_date = (status != EnrollmentStatus::Active)
+                    .then(|| row.get::<Option<NaiveDate>, _>("status_change_date").unwrap_or(enrollment_date));
+                enrollments.push(RosterEnrollment {
+                    sourced_id: row.get::<Option<String>, _>("sourced_id").unwrap_or_else(|| format!("enrollment-{}", id)),
+                    class_sourced_id: class.clone(),
+                    school_sourced_id: school.clone(),
+                    user_sourced_id: user.clone(),
+                    role: ROLE_STUDENT.to_string(),
+                    primary: false,
+                    begin_date: Some(enrollment_date),
+                    end_date,
+                });
+            }
+
+            tx.rollback().await.map_err(db_error)?;
+            Ok(RosterBundle {
+                source_system: None,
+                orgs,
+                academic_sessions,
+                courses,
+                classes,
+                users,
+                enrollments,
+            })
+        }
+    }
+}
@@ -0,0 +1,309 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::audit_database::write_audit_event_as;
+        use crate::app::db::student_encryption_database::{stored_pii_from_row, student_keyring, PII_COLUMNS};
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::enrollment::EnrollmentStatus;
+        use crate::app::models::retention::{LegalHold, PurgeReport, RetentionCandidate, RetentionPolicy, RetentionPreview, SaveRetentionPolicyRequest};
+        use crate::app::services::student_encryption::open_pii;
+        use leptos::ServerFnError;
+        use sqlx::postgres::PgRow;
+        use sqlx::{PgConnection, PgPool, Row};
+        use std::str::FromStr;
+        use uuid::Uuid;
+
+        // Only one purge runs at a time, scheduled or manual
+        const RETENTION_LOCK: i64 = 0x7265_7465_6e74_696f;
+
+        const POLICY_COLUMNS: &str = "id, name, statuses, retain_years, keep_deidentified_scores, enabled, updated_at";
+
+        // Each student's latest enrollment, and the policy that selects them
+        // now. When several policies match, one keeping scores wins.
+        const CANDIDATES: &str = "
+            WITH latest AS (
+                SELECT DISTINCT ON (student_id) student_id, status, academic_year, grade_level,
+                       COALESCE(status_change_date, enrollment_date) AS last_active
+                FROM student_enrollments
+                ORDER BY student_id, academic_year DESC, COALESCE(status_change_date, enrollment_date) DESC
+            )
+            SELECT l.student_id, l.status, l.academic_year::TEXT AS academic_year, l.grade_level::TEXT AS grade_level,
+                   l.last_active, s.legal_hold, p.id AS policy_id, p.name AS policy_name, p.keep_deidentified_scores,
+                   (SELECT COUNT(*) FROM scores sc WHERE sc.student_id = l.student_id) AS score_count";
+
+        const CANDIDATES_FROM: &str = "
+            FROM latest l
+            JOIN students s ON s.student_id = l.student_id
+            JOIN LATERAL (
+                SELECT id, name, keep_deidentified_scores
+                FROM retention_policies p
+                WHERE p.enabled
+                  AND l.status::TEXT = ANY(p.statuses)
+                  AND l.last_active + MAKE_INTERVAL(years => p.retain_years) <= CURRENT_DATE
+                ORDER BY p.keep_deidentified_scores DESC, p.id
+                LIMIT 1
+            ) p ON TRUE
+            ORDER BY l.last_active, l.student_id";
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn policy_from_row(row: &PgRow) -> Result<RetentionPolicy, ServerFnError> {
+            let statuses = row
+                .get::<Vec<String>, _>("statuses")
+                .iter()
+                .map(|status| EnrollmentStatus::from_str(status))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(ServerFnError::new)?;
+            Ok(RetentionPolicy {
+                id: row.get("id"),
+                name: row.get("name"),
+                statuses,
+                retain_years: row.get("retain_years"),
+                keep_deidentified_scores: row.get("keep_deidentified_scores"),
+                enabled: row.get("enabled"),
+                updated_at: row.get("updated_at"),
+            })
+        }
+
+        fn candidate_from_row(row: &PgRow) -> RetentionCandidate {
+            RetentionCandidate {
+                student_id: row.get("student_id"),
+                firstname: None,
+                lastname: None,
+                last_status: row.get("status"),
+                last_active: row.get("last_active"),
+                policy_id: row.get("policy_id"),
+                policy_name: row.get("policy_name"),
+                keep_deidentified_scores: row.get("keep_deidentified_scores"),
+                scores: row.get("score_count"),
+            }
+        }
+
+        pub async fn list_retention_policies(pool: &PgPool) -> Result<Vec<RetentionPolicy>, ServerFnError> {
+            let rows = sqlx::query(&format!("SELECT {} FROM retention_policies ORDER BY id", POLICY_COLUMNS))
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+
+            rows.iter().map(policy_from_row).collect()
+        }
+
+        pub async fn save_retention_policy(pool: &PgPool, request: &SaveRetentionPolicyRequest, updated_by: i32) -> Result<RetentionPolicy, ServerFnError> {
+            let statuses: Vec<String> = request.statuses.iter().map(|status| status.to_string()).collect();
+            let sql = match request.id {
+                Some(_) => format!(
+                    "UPDATE retention_policies
+                     SET name = $1, statuses = $2, retain_years = $3, keep_deidentified_scores = $4, enabled = $5, updated_by = $6, updated_at = NOW()
+                     WHERE id = $7
+                     RETURNING {}",
+                    POLICY_COLUMNS
+                ),
+                None => format!(
+                    "INSERT INTO retention_policies (name, statuses, retain_years, keep_deidentified_scores, enabled, updated_by)
+                     VALUES ($1, $2, $3, $4, $5, $6)
+                     RETURNING {}",
+                    POLICY_COLUMNS
+                ),
+            };
+            let mut query = sqlx::query(&sql)
+                .bind(request.name.trim())
+                .bind(statuses)
+                .bind(request.retain_years)
+                .bind(request.keep_deidentified_scores)
+                .bind(request.enabled)
+                .bind(updated_by);
+            if let Some(id) = request.id {
+                query = query.bind(id);
+            }
+            let row = query
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServerFnError::new("Retention policy not found"))?;
+
+            policy_from_row(&row)
+        }
+
+        pub async fn delete_retention_policy(pool: &PgPool, id: i32) -> Result<RetentionPolicy, ServerFnError> {
+            let row = sqlx::query(&format!("DELETE FROM retention_policies WHERE id = $1 RETURNING {}", POLICY_COLUMNS))
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServerFnError::new("Retention policy not found"))?;
+
+            policy_from_row(&row)
+        }
+
+        // Students the enabled policies select today, including held ones
+        async fn find_candidates(conn: &mut PgConnection, with_names: bool) -> Result<Vec<PgRow>, ServerFnError> {
+            let names = if with_names { format!(", {}", PII_COLUMNS) } else { String::new() };
+            sqlx::query(&format!("{}{}{}", CANDIDATES, names, CANDIDATES_FROM))
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(db_error)
+        }
+
+        pub async fn preview_retention(pool: &PgPool) -> Result<RetentionPreview, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut conn = pool.acquire().await.map_err(db_error)?;
+            let rows = find_candidates(&mut conn, true).await?;
+
+            let mut preview = RetentionPreview::default();
+            for row in &rows {
+                if row.get::<bool, _>("legal_hold") {
+                    preview.held_students += 1;
+                    continue;
+                }
+                let mut candidate = candidate_from_row(row);
+                let pii = open_pii(keyring.as_deref(), &stored_pii_from_row(row))
+                    .map_err(|e| ServerFnError::new(format!("Student {}: {}", candidate.student_id, e)))?;
+                candidate.firstname = pii.firstname;
+                candidate.lastname = pii.lastname;
+                preview.candidates.push(candidate);
+            }
+            Ok(preview)
+        }
+
+        // Deletes every student the enabled policies select, except those on
+        // legal hold, in one transaction with their audit events. Scores are
+        // first copied to deidentified_scores where the policy keeps them.
+        // The audit log is append-only, so earlier events about a purged
+        // student remain; they record only IDs and changed field names, never
+        // the student's PII.
+        pub async fn purge_expired_students(pool: &PgPool, actor_id: Option<i64>, actor_username: &str) -> Result<PurgeReport, ServerFnError> {
+            let mut tx = pool.begin().await.map_err(db_error)?;
+
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(RETENTION_LOCK)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            let rows = find_candidates(&mut tx, false).await?;
+            let mut report = PurgeReport::default();
+            let mut purged = Vec::new();
+            for row in &rows {
+                if row.get::<bool, _>("legal_hold") {
+                    report.held_students += 1;
+                } else {
+                    purged.push((candidate_from_row(row), row.get::<String, _>("grade_level"), row.get::<Option<String>, _>("academic_year")));
+                }
+            }
+            if purged.is_empty() {
+                tx.rollback().await.map_err(db_error)?;
+                return Ok(report);
+            }
+
+            // Lock the rows, and drop any placed on hold since they were selected
+            let ids: Vec<i32> = sqlx::query_scalar(
+                "SELECT student_id FROM students WHERE student_id = ANY($1) AND NOT legal_hold FOR UPDATE"
+            )
+            .bind(purged.iter().map(|(candidate, ..)| candidate.student_id).collect::<Vec<_>>())
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            report.held_students += (purged.len() - ids.len()) as i64;
+            purged.retain(|(candidate, ..)| ids.contains(&candidate.student_id));
+
+            let kept: Vec<_> = purged.iter().filter(|(candidate, ..)| candidate.keep_deidentified_scores).collect();
+            report.scores_deidentified = sqlx::query(
+                "INSERT INTO deidentified_scores (subject_key, test_id, date_administered, test_scores, test_variant, attempt, grade_level, academic_year, policy_id)
+                 SELECT k.subject_key, sc.test_id, sc.date_administered, sc.test_scores, sc.test_variant, sc.attempt,
+                        k.grade_level::grade_enum, k.academic_year::school_year_enum, k.policy_id
+                 FROM scores sc
+                 JOIN UNNEST($1::INT[], $2::UUID[], $3::TEXT[], $4::TEXT[], $5::INT[])
+                      AS k(student_id, subject_key, grade_level, academic_year, policy_id)
+                   ON k.student_id = sc.student_id"
+            )
+            .bind(kept.iter().map(|(candidate, ..)| candidate.student_id).collect::<Vec<_>>())
+            .bind(kept.iter().map(|_| Uuid::new_v4()).collect::<Vec<_>>())
+            .bind(kept.iter().map(|(_, grade, _)| grade.clone()).collect::<Vec<_>>())
+            .bind(kept.iter().map(|(_, _, year)| year.clone()).collect::<Vec<_>>())
+            .bind(kept.iter().map(|(candidate, ..)| candidate.policy_id).collect::<Vec<_>>())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .rows_affected() as i64;
+
+            let (scores, enrollments): (i64, i64) = sqlx::query_as(
+                "SELECT (SELECT COUNT(*) FROM scores WHERE student_id = ANY($1)),
+                        (SELECT COUNT(*) FROM student_enrollments WHERE student_id = ANY($1))"
+            )
+            .bind(&ids)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            report.scores_deleted = scores - report.scores_deidentified;
+            report.enrollments_deleted = enrollments;
+
+            // Scores, enrollments and merges into the student, with the merged
+            // records they keep, go with it through ON DELETE CASCADE
+            report.students_purged = sqlx::query("DELETE FROM students WHERE student_id = ANY($1)")
+                .bind(&ids)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?
+                .rows_affected() as i64;
+
+            for (candidate, ..) in &purged {
+                write_audit_event_as(
+                    &mut tx,
+                    actor_id,
+                    actor_username,
+                    AuditAction::Delete,
+                    AuditEntity::Student,
+                    &candidate.student_id.to_string(),
+                    AuditChange::deleted(&candidate.purge_audit_record()),
+                )
+                .await?;
+            }
+            tx.commit().await.map_err(db_error)?;
+
+            Ok(report)
+        }
+
+        fn legal_hold_from_row(row: &PgRow) -> LegalHold {
+            LegalHold {
+                student_id: row.get("student_id"),
+                held: row.get("legal_hold"),
+                reason: row.get("legal_hold_reason"),
+                set_by: row.get("legal_hold_set_by"),
+                set_at: row.get("legal_hold_set_at"),
+            }
+        }
+
+        pub async fn get_legal_hold(pool: &PgPool, student_id: i32) -> Result<LegalHold, ServerFnError> {
+            let row = sqlx::query(
+                "SELECT student_id, legal_hold, legal_hold_reason, legal_hold_set_by, legal_hold_set_at FROM students WHERE student_id = $1"
+            )
+            .bind(student_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServerFnError::new("Student not found"))?;
+
+            Ok(legal_hold_from_row(&row))
+        }
+
+        pub async fn set_legal_hold(pool: &PgPool, student_id: i32, held: bool, reason: Option<String>, set_by: &str) -> Result<LegalHold, ServerFnError> {
+            let row = sqlx::query(
+                "UPDATE students
+                 SET legal_hold = $2, legal_hold_reason = CASE WHEN $2 THEN $3 END, legal_hold_set_by = $4, legal_hold_set_at = NOW()
+                 WHERE student_id = $1
+                 RETURNING student_id, legal_hold, legal_hold_reason, legal_hold_set_by, legal_hold_set_at"
+            )
+            .bind(student_id)
+            .bind(held)
+            .bind(reason)
+            .bind(set_by)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServerFnError::new("Student not found"))?;
+
+            Ok(legal_hold_from_row(&row))
+        }
+    }
+}
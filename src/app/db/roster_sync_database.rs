@@ -0,0 +1,275 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::audit_database::write_audit_event;
+        use crate::app::db::student_attribute_database::{read_all_attribute_values, write_student_attribute_values};
+        use crate::app::db::student_database::{read_all_students, upsert_students};
+        use crate::app::db::student_encryption_database::student_keyring;
+        use crate::app::db::student_history_database::write_student_versions;
+        use crate::app::db::student_protection_database::lock_unprotected;
+        use crate::app::models::enrollment::{Enrollment, EnrollmentStatus};
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::roster_sync::{EnrollmentSyncDiff, StudentSyncDiff, SyncReport};
+        use crate::app::models::student::{AddStudentRequest, Student};
+        use crate::app::models::user::SessionUser;
+        use crate::app::services::roster_sync::{self, student_from_request, SyncEnrollment};
+        use crate::app::services::student_encryption::PiiKeyring;
+        use chrono::NaiveDate;
+        use leptos::ServerFnError;
+        use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
+        use std::collections::HashSet;
+
+        // The feed carries real SIS IDs, which match no student while they
+        // are anonymized
+        const PROTECTED_REFUSAL: &str = "Turn off student protections before syncing the roster";
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        // Every active enrollment and every enrollment outside a course: what
+        // a student sync may withdraw and an enrollment sync may match
+        pub async fn list_sync_enrollments(conn: &mut PgConnection) -> Result<Vec<SyncEnrollment>, ServerFnError> {
+            let rows = sqlx::query(
+                "SELECT id, student_id, academic_year, grade_level, teacher_id, status, notes, course_id
+                 FROM student_enrollments
+                 WHERE status = 'active' OR course_id IS NULL
+                 ORDER BY id"
+            )
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            Ok(rows
+                .iter()
+                .map(|row| SyncEnrollment {
+                    id: row.get("id"),
+                    student_id: row.get("student_id"),
+                    academic_year: row.get("academic_year"),
+                    grade_level: row.get("grade_level"),
+                    teacher_id: row.get("teacher_id"),
+                    status: row.get("status"),
+                    notes: row.get("notes"),
+                    course_id: row.get("course_id"),
+                })
+                .collect())
+        }
+
+        pub async fn list_teacher_ids(conn: &mut PgConnection) -> Result<HashSet<i32>, ServerFnError> {
+            let ids: Vec<i32> = sqlx::query_scalar("SELECT id FROM employees WHERE role::TEXT = 'Teacher'")
+                .fetch_all(&mut *conn)
+                .await
+                .map_err(db_error)?;
+            Ok(ids.into_iter().collect())
+        }
+
+        pub async fn list_student_ids(pool: &PgPool) -> Result<HashSet<i32>, ServerFnError> {
+            let ids: Vec<i32> = sqlx::query_scalar("SELECT student_id FROM students")
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+            Ok(ids.into_iter().collect())
+        }
+
+        // Closes withdrawn students' enrollments. Ones no longer active are
+        // left alone. Returns how many were closed.
+        async fn withdraw_enrollments(enrollment_ids: &[i32], status: &EnrollmentStatus, today: NaiveDate, tx: &mut Transaction<'_, Postgres>) -> Result<usize, ServerFnError> {
+            let result = sqlx::query(
+                "UPDATE student_enrollments SET status = $2, status_change_date = $3, updated_at = NOW()
+                 WHERE id = ANY($1) AND status = 'active'"
+            )
+            .bind(enrollment_ids)
+            .bind(status)
+            .bind(today)
+            .execute(&mut **tx)
+            .await
+            .map_err(db_error)?;
+            Ok(result.rows_affected() as usize)
+        }
+
+        // Holds off other writers to the tables a sync diffs until it commits,
+        // so the diff it checks is the one it applies
+        async fn lock_for_sync(conn: &mut PgConnection) -> Result<(), ServerFnError> {
+            lock_unprotected(conn, PROTECTED_REFUSAL).await?;
+            sqlx::query("LOCK TABLE students, student_attribute_values, student_enrollments IN SHARE ROW EXCLUSIVE MODE")
+                .execute(&mut *conn)
+                .await
+                .map_err(db_error)?;
+            Ok(())
+        }
+
+        async fn diff_student_feed(conn: &mut PgConnection, keyring: Option<&PiiKeyring>, feed: &[AddStudentRequest], attribute_keys: &[String]) -> Result<StudentSyncDiff, ServerFnError> {
+            let students = read_all_students(conn, keyring).await?;
+            let attributes = read_all_attribute_values(conn).await?;
+            let enrollments = list_sync_enrollments(conn).await?;
+            roster_sync::diff_students(feed, attribute_keys, &students, &attributes, &enrollments).map_err(ServerFnError::new)
+        }
+
+        async fn diff_enrollment_feed(conn: &mut PgConnection, keyring: Option<&PiiKeyring>, feed: &[Enrollment]) -> Result<EnrollmentSyncDiff, ServerFnError> {
+            let students = read_all_students(conn, keyring).await?;
+            let teacher_ids = list_teacher_ids(conn).await?;
+            let enrollments = list_sync_enrollments(conn).await?;
+            roster_sync::diff_enrollments(feed, &students, &teacher_ids, &enrollments).map_err(ServerFnError::new)
+        }
+
+        // What syncing the student feed would change. Refused while student
+        // protection is on.
+        pub async fn preview_student_sync(pool: &PgPool, feed: &[AddStudentRequest], attribute_keys: &[String]) -> Result<StudentSyncDiff, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_unprotected(&mut tx, PROTECTED_REFUSAL).await?;
+            let diff = diff_student_feed(&mut tx, keyring.as_deref(), feed, attribute_keys).await?;
+            tx.rollback().await.map_err(db_error)?;
+            Ok(diff)
+        }
+
+        // Diffs the feed again and, if it matches the previewed `fingerprint`,
+        // writes new and changed students with their attributes and versions
+        // and withdraws the missing ones, all in one transaction with the
+        // audit event. Returns what was applied and how many enrollments were
+        // closed.
+        pub async fn apply_student_sync(
+            pool: &PgPool,
+            feed: &[AddStudentRequest],
+            attribute_keys: &[String],
+            fingerprint: &str,
+            withdrawal_status: &EnrollmentStatus,
+            today: NaiveDate,
+            actor: &SessionUser,
+        ) -> Result<(SyncReport, usize), ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_for_sync(&mut tx).await?;
+
+            let diff = diff_student_feed(&mut tx, keyring.as_deref(), feed, attribute_keys).await?;
+            if diff.fingerprint != fingerprint {
+                return Err(ServerFnError::new("Students or enrollments changed since the preview; preview the sync again"));
+            }
+
+            let written: HashSet<i32> = diff
+                .new_students
+                .iter()
+                .map(|student| student.student_id)
+                .chain(diff.changed.iter().map(|change| change.student_id))
+                .collect();
+            let writes: Vec<&AddStudentRequest> = feed.iter().filter(|student| written.contains(&student.student_id)).collect();
+            let students: Vec<Student> = writes.iter().map(|student| student_from_request(student)).collect();
+            let attribute_values: Vec<_> = writes
+                .iter()
+                .map(|student| (student.student_id, student.custom_attributes.clone()))
+                .collect();
+            let withdrawn_enrollment_ids: Vec<i32> = diff
+                .withdrawn
+                .iter()
+                .flat_map(|withdrawal| withdrawal.enrollment_ids.iter().copied())
+                .collect();
+
+            upsert_students(&students, keyring.as_deref(), &mut tx).await?;
+            write_student_attribute_values(&mut tx, attribute_keys, &attribute_values).await?;
+            let withdrawn = withdraw_enrollments(&withdrawn_enrollment_ids, withdrawal_status, today, &mut tx).await?;
+            let student_ids: Vec<i32> = written.into_iter().collect();
+            write_student_versions(&mut tx, &student_ids, &actor.username).await?;
+
+            let report = SyncReport {
+                created: diff.new_students.len(),
+                updated: diff.changed.len(),
+                withdrawn: diff.withdrawn.len(),
+            };
+            write_audit_event(&mut tx, actor, AuditAction::Update, AuditEntity::Roster, "student_sync", AuditChange::created(&report)).await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok((report, withdrawn))
+        }
+
+        // What syncing the enrollment feed would change. Refused while
+        // student protection is on.
+        pub async fn preview_enrollment_sync(pool: &PgPool, feed: &[Enrollment]) -> Result<EnrollmentSyncDiff, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_unprotected(&mut tx, PROTECTED_REFUSAL).await?;
+            let diff = diff_enrollment_feed(&mut tx, keyring.as_deref(), feed).await?;
+            tx.rollback().await.map_err(db_error)?;
+            Ok(diff)
+        }
+
+        // Diffs the feed again and, if it matches the previewed `fingerprint`,
+        // inserts new enrollments, rewrites changed ones by id and withdraws
+        // the missing ones in one transaction with the audit event. Returns
+        // what was applied and how many enrollments were closed.
+        pub async fn apply_enrollment_sync(
+            pool: &PgPool,
+            feed: &[Enrollment],
+            fingerprint: &str,
+            withdrawal_status: &EnrollmentStatus,
+            today: NaiveDate,
+            actor: &SessionUser,
+        ) -> Result<(SyncReport, usize), ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_for_sync(&mut tx).await?;
+
+            let diff = diff_enrollment_feed(&mut tx, keyring.as_deref(), feed).await?;
+            if diff.fingerprint != fingerprint {
+                return Err(ServerFnError::new("Enrollments changed since the preview; preview the sync again"));
+            }
+
+            let changed: Vec<(i32, &Enrollment)> = diff
+                .changed
+                .iter()
+                .filter_map(|change| {
+                    feed.iter()
+                        .find(|enrollment| enrollment.student_id == change.student_id && enrollment.academic_year == change.academic_year)
+                        .map(|enrollment| (change.enrollment_id, enrollment))
+                })
+                .collect();
+            let withdrawn_enrollment_ids: Vec<i32> = diff
+                .withdrawn
+                .iter()
+                .flat_map(|withdrawal| withdrawal.enrollment_ids.iter().copied())
+                .collect();
+
+            for enrollment in &diff.new_enrollments {
+                sqlx::query(
+                    "INSERT INTO student_enrollments (student_id, academic_year, grade_level, teacher_id, status, enrollment_date, status_change_date, notes)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+                )
+                .bind(enrollment.student_id)
+                .bind(&enrollment.academic_year)
+                .bind(&enrollment.grade_level)
+                .bind(enrollment.teacher_id)
+                .bind(&enrollment.status)
+                .bind(enrollment.enrollment_date)
+                .bind(enrollment.status_change_date)
+                .bind(&enrollment.notes)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to add the enrollment of student {}: {}", enrollment.student_id, e)))?;
+            }
+            for (id, enrollment) in changed {
+                sqlx::query(
+                    "UPDATE student_enrollments SET grade_level = $2, teacher_id = $3,
+                         status_change_date = CASE WHEN status = $4 THEN status_change_date ELSE $5 END,
+                         status = $4, notes = COALESCE($6, notes), updated_at = NOW()
+                     WHERE id = $1"
+                )
+                .bind(id)
+                .bind(&enrollment.grade_level)
+                .bind(enrollment.teacher_id)
+                .bind(&enrollment.status)
+                .bind(today)
+                .bind(&enrollment.notes)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to update the enrollment of student {}: {}", enrollment.student_id, e)))?;
+            }
+            let withdrawn = withdraw_enrollments(&withdrawn_enrollment_ids, withdrawal_status, today, &mut tx).await?;
+
+            let report = SyncReport {
+                created: diff.new_enrollments.len(),
+                updated: diff.changed.len(),
+                withdrawn: diff.withdrawn.len(),
+            };
+            write_audit_event(&mut tx, actor, AuditAction::Update, AuditEntity::Roster, "enrollment_sync", AuditChange::created(&report)).await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok((report, withdrawn))
+        }
+    }
+}
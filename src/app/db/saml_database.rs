@@ -2,11 +2,11 @@ cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
         use sqlx::{Pool, Postgres, Row};
         use leptos::ServerFnError;
-        use crate::app::models::auth::{SamlConfig, SamlResponse, AuthProvider};
+        use crate::app::models::auth::{self, SamlConfig, SamlResponse, SamlMappedField, SamlMappingPreview, SsoAttributeMapping, AuthProvider};
         use crate::app::models::user::{SessionUser, UserRole, AccountStatus};
         use crate::app::db::user_database;
+        use crate::app::services::saml_validation::{self, SamlValidationContext, SamlValidationError, ValidatedAssertion};
         use uuid::Uuid;
-        use std::collections::HashMap;
         use roxmltree::Document;
         use base64::{Engine as _, engine::general_purpose};
         use chrono::{DateTime, Utc};
@@ -22,9 +22,23 @@ cfg_if::cfg_if! {
                 })
             }
 
-            pub fn generate_auth_request(&self, institution_id: &str, relay_state: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
+            // Entity ID IdPs must list as the assertion audience
+            pub fn sp_entity_id(&self) -> String {
+                format!("{}/saml/metadata", self.base_url)
+            }
+
+            pub fn acs_url(&self) -> String {
+                format!("{}/saml/acs", self.base_url)
+            }
+
+            pub fn new_request_id() -> String {
+                format!("_{}", uuid::Uuid::new_v4().simple())
+            }
+
+            // request_id should come from new_request_id() and be stored with
+            // record_saml_request so the response can be matched to it
+            pub fn generate_auth_request(&self, request_id: &str, relay_state: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
                 // Generate a simple SAML AuthnRequest
-                let request_id = format!("_{}", uuid::Uuid::new_v4().simple());
                 let issue_instant = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
 
                 let auth_request_xml = format!(r#"<?xml version="1.0" encoding="UTF-8"?>
@@ -63,67 +77,27 @@ cfg_if::cfg_if! {
                 Ok(url.to_string())
             }
 
-            pub fn parse_saml_response(&self, saml_xml: &str, institution_id: &str) -> Result<SamlResponse, Box<dyn std::error::Error>> {
+            // Only call this on XML that has passed verify_saml_response. Data is
+            // read from the (single, signed) Assertion only, never from the
+            // unsigned parts of the Response around it.
+            pub fn parse_saml_response(&self, saml_xml: &str, config: &SamlConfig) -> Result<SamlResponse, Box<dyn std::error::Error>> {
                 let doc = Document::parse(saml_xml)?;
+                let assertion = saml_validation::signed_assertion(&doc)?;
 
-                // Extract NameID
-                let name_id = doc
-                    .descendants()
-                    .find(|n| n.has_tag_name("NameID") || n.has_tag_name("saml:NameID"))
-                    .and_then(|n| n.text())
-                    .ok_or("NameID not found in SAML response")?
-                    .to_string();
-
-                // Extract attributes
-                let mut attributes = HashMap::new();
-                for attr_node in doc.descendants().filter(|n| n.has_tag_name("Attribute") || n.has_tag_name("saml:Attribute")) {
-                    if let Some(name) = attr_node.attribute("Name") {
-                        let values: Vec<String> = attr_node
-                            .descendants()
-                            .filter(|n| n.has_tag_name("AttributeValue") || n.has_tag_name("saml:AttributeValue"))
-                            .filter_map(|n| n.text())
-                            .map(|s| s.to_string())
-                            .collect();
-                        if !values.is_empty() {
-                            attributes.insert(name.to_string(), values);
-                        }
-                    }
-                }
+                let name_id = saml_validation::assertion_name_id(assertion)
+                    .ok_or("NameID not found in SAML response")?;
+                let attributes = saml_validation::assertion_attributes(assertion);
 
-                // Extract common attributes with fallbacks
-                let email = self.extract_attribute(&attributes, &[
-                    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress",
-                    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/name",
-                    "mail",
-                    "email",
-                    "emailAddress"
-                ]).or_else(|| {
+                // Map profile fields through the institution's attribute mapping
+                let email = config.mapped_value(SamlMappedField::Email, &attributes).or_else(|| {
                     // If no email attribute, try to use NameID if it looks like an email
                     if name_id.contains('@') { Some(name_id.clone()) } else { None }
                 });
 
-                let first_name = self.extract_attribute(&attributes, &[
-                    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/givenname",
-                    "givenName",
-                    "firstName",
-                    "given_name"
-                ]);
-
-                let last_name = self.extract_attribute(&attributes, &[
-                    "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/surname",
-                    "sn",
-                    "surname",
-                    "lastName",
-                    "last_name",
-                    "familyName"
-                ]);
-
-                let display_name = self.extract_attribute(&attributes, &[
-                    "http://schemas.microsoft.com/identity/claims/displayname",
-                    "displayName",
-                    "cn",
-                    "commonName"
-                ]).or_else(|| {
+                let first_name = config.mapped_value(SamlMappedField::FirstName, &attributes);
+                let last_name = config.mapped_value(SamlMappedField::LastName, &attributes);
+
+                let display_name = config.mapped_value(SamlMappedField::DisplayName, &attributes).or_else(|| {
                     // Construct display name from first and last name if available
                     match (first_name.as_ref(), last_name.as_ref()) {
                         (Some(first), Some(last)) => Some(format!("{} {}", first, last)),
@@ -133,14 +107,14 @@ cfg_if::cfg_if! {
                     }
                 });
 
-                let session_index = doc
-                    .descendants()
-                    .find(|n| n.has_tag_name("AuthnStatement") || n.has_tag_name("saml:AuthnStatement"))
-                    .and_then(|n| n.attribute("SessionIndex"))
-                    .map(|s| s.to_string());
+                let school = config.mapped_value(SamlMappedField::School, &attributes);
+                let employee_id = config.mapped_value(SamlMappedField::EmployeeId, &attributes);
+                let (role, _) = config.resolve_role(&attributes);
 
-                log::info!("Parsed SAML response - NameID: {}, Email: {:?}, DisplayName: {:?}",
-                    name_id, email, display_name);
+                let session_index = saml_validation::assertion_session_index(assertion);
+
+                log::info!("Parsed SAML response - NameID: {}, Email: {:?}, DisplayName: {:?}, Role: {}",
+                    name_id, email, display_name, role);
 
                 Ok(SamlResponse {
                     name_id,
@@ -150,22 +124,12 @@ cfg_if::cfg_if! {
                     display_name,
                     attributes,
                     session_index,
+                    school,
+                    employee_id,
+                    role,
                 })
             }
 
-            fn extract_attribute(&self, attributes: &HashMap<String, Vec<String>>, keys: &[&str]) -> Option<String> {
-                for key in keys {
-                    if let Some(values) = attributes.get(*key) {
-                        if let Some(value) = values.first() {
-                            if !value.trim().is_empty() {
-                                return Some(value.clone());
-                            }
-                        }
-                    }
-                }
-                None
-            }
-
             pub fn generate_logout_request(&self, name_id: &str, session_index: Option<&str>) -> Result<String, Box<dyn std::error::Error>> {
                 let request_id = format!("_{}", uuid::Uuid::new_v4().simple());
                 let issue_instant = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
@@ -295,16 +259,139 @@ cfg_if::cfg_if! {
             }
         }
 
-        // Just-in-time user provisioning
+        // Remember an AuthnRequest we sent so its response can be matched later
+        pub async fn record_saml_request(pool: &Pool<Postgres>, request_id: &str, institution_id: &str) -> Result<(), ServerFnError> {
+            sqlx::query("DELETE FROM saml_pending_requests WHERE created_at < NOW() - INTERVAL '1 hour'")
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to prune SAML requests: {}", e)))?;
+
+            sqlx::query("INSERT INTO saml_pending_requests (request_id, institution_id) VALUES ($1, $2)")
+                .bind(request_id)
+                .bind(institution_id)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to record SAML request: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Single use: a request can only be answered once, within ten minutes
+        async fn consume_saml_request(pool: &Pool<Postgres>, request_id: &str, institution_id: &str) -> Result<bool, ServerFnError> {
+            let row = sqlx::query("DELETE FROM saml_pending_requests WHERE request_id = $1 AND institution_id = $2 AND created_at > NOW() - INTERVAL '10 minutes' RETURNING request_id")
+                .bind(request_id)
+                .bind(institution_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to look up SAML request: {}", e)))?;
+
+            Ok(row.is_some())
+        }
+
+        // Returns false if the assertion ID has been seen before
+        async fn record_consumed_assertion(pool: &Pool<Postgres>, assertion: &ValidatedAssertion, institution_id: &str) -> Result<bool, ServerFnError> {
+            sqlx::query("DELETE FROM saml_consumed_assertions WHERE expires_at < NOW()")
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to prune replay cache: {}", e)))?;
+
+            let row = sqlx::query("INSERT INTO saml_consumed_assertions (assertion_id, institution_id, expires_at) VALUES ($1, $2, $3) ON CONFLICT (assertion_id) DO NOTHING RETURNING assertion_id")
+                .bind(&assertion.assertion_id)
+                .bind(institution_id)
+                .bind(assertion.expires_at)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to record SAML assertion: {}", e)))?;
+
+            Ok(row.is_some())
+        }
+
+        // Signature, conditions, InResponseTo and replay checks. Must pass before
+        // anything in the response is parsed or provisioned.
+        pub async fn verify_saml_response(
+            pool: &Pool<Postgres>,
+            saml_manager: &SamlManager,
+            config: &SamlConfig,
+            saml_xml: &str,
+        ) -> Result<ValidatedAssertion, ServerFnError> {
+            let institution_id = config.id.to_string();
+            let sp_entity_id = saml_manager.sp_entity_id();
+            let acs_url = saml_manager.acs_url();
+            let context = SamlValidationContext {
+                idp_certificate: &config.x509_cert,
+                idp_entity_id: &config.entity_id,
+                sp_entity_id: &sp_entity_id,
+                acs_url: &acs_url,
+                now: Utc::now(),
+            };
+
+            let rejected = |e: SamlValidationError| {
+                log::warn!("Rejected SAML response for {}: {}", config.institution_name, e);
+                ServerFnError::new(format!("SAML response rejected: {}", e))
+            };
+
+            let assertion = saml_validation::validate_saml_response(saml_xml, &context).map_err(rejected)?;
+
+            // Unsolicited (IdP-initiated) responses carry no InResponseTo
+            if let Some(request_id) = assertion.in_response_to.as_deref() {
+                if !consume_saml_request(pool, request_id, &institution_id).await? {
+                    return Err(rejected(SamlValidationError::InResponseToMismatch));
+                }
+            }
+
+            if !record_consumed_assertion(pool, &assertion, &institution_id).await? {
+                return Err(rejected(SamlValidationError::Replayed(assertion.assertion_id.clone())));
+            }
+
+            Ok(assertion)
+        }
+
+        // Runs a pasted response through validation and the config's mapping
+        // without consuming it or touching any accounts. Validation failures
+        // are reported rather than returned so the mapping can still be tested
+        // with an old or captured assertion.
+        pub fn preview_saml_mapping(
+            saml_manager: &SamlManager,
+            config: &SamlConfig,
+            saml_xml: &str,
+        ) -> Result<SamlMappingPreview, ServerFnError> {
+            let sp_entity_id = saml_manager.sp_entity_id();
+            let acs_url = saml_manager.acs_url();
+            let context = SamlValidationContext {
+                idp_certificate: &config.x509_cert,
+                idp_entity_id: &config.entity_id,
+                sp_entity_id: &sp_entity_id,
+                acs_url: &acs_url,
+                now: Utc::now(),
+            };
+            let validation = saml_validation::validate_saml_response(saml_xml, &context);
+
+            let response = saml_manager
+                .parse_saml_response(saml_xml, config)
+                .map_err(|e| ServerFnError::new(format!("Failed to parse SAML response: {}", e)))?;
+            let (_, matched_role_value) = config.resolve_role(&response.attributes);
+
+            Ok(SamlMappingPreview {
+                response,
+                matched_role_value,
+                validation_passed: validation.is_ok(),
+                validation_message: validation.err().map(|e| e.to_string()),
+            })
+        }
+
+        // Just-in-time user provisioning. The role from the institution's role
+        // rules is only applied when the account is created.
         pub async fn provision_saml_user(
             pool: &Pool<Postgres>,
             saml_response: &SamlResponse,
             institution_id: &str,
+            auto_provision: bool,
         ) -> Result<SessionUser, ServerFnError> {
             // First check if user already exists with SAML mapping
             if let Some(existing_user) = get_user_by_saml_mapping(pool, institution_id, &saml_response.name_id).await? {
                 // Update last login time
                 update_saml_user_mapping_login(pool, existing_user.id, institution_id).await?;
+                sync_saml_directory_fields(pool, existing_user.id, institution_id, saml_response).await?;
                 return Ok(existing_user);
             }
 
@@ -313,27 +400,53 @@ cfg_if::cfg_if! {
             if let Some(existing_user) = user_database::get_user_by_email(pool, email).await? {
                 // Link existing user to SAML
                 link_user_to_saml(pool, existing_user.id, institution_id, &saml_response.name_id).await?;
+                sync_saml_directory_fields(pool, existing_user.id, institution_id, saml_response).await?;
                 return Ok(existing_user.to_session_user());
             }
 
+            // Anyone else is only let in when the institution creates accounts
+            auth::check_new_account(auto_provision, email).map_err(ServerFnError::new)?;
+
             // Create new user with SAML data
             let username = email.split('@').next().unwrap_or(&saml_response.name_id).to_string();
 
             // Generate a random password (user won't use it for SAML login)
             let temp_password = uuid::Uuid::new_v4().to_string();
 
-            // Determine role from SAML attributes
-            let role = determine_role_from_saml_attributes(&saml_response.attributes);
-
             // Create user with profile data from SAML
-            let user = create_user_from_saml(pool, username, email.clone(), temp_password, role, saml_response).await?;
+            let user = create_user_from_saml(pool, username, email.clone(), temp_password, saml_response.role, saml_response).await?;
 
             // Store SAML association
             store_saml_user_association(pool, user.id, institution_id, &saml_response.name_id).await?;
+            sync_saml_directory_fields(pool, user.id, institution_id, saml_response).await?;
 
             Ok(user)
         }
 
+        // Records the school and employee number the IdP reports on each login.
+        // The employee number is the district's, not an employees row ID, so
+        // linking the account to its employee record is left to an admin.
+        async fn sync_saml_directory_fields(
+            pool: &Pool<Postgres>,
+            user_id: i64,
+            institution_id: &str,
+            saml_response: &SamlResponse,
+        ) -> Result<(), ServerFnError> {
+            sqlx::query(
+                "UPDATE saml_user_mappings SET school = $3, employee_number = $4
+                 WHERE user_id = $1 AND institution_id = $2"
+            )
+            .bind(user_id)
+            .bind(institution_id)
+            .bind(&saml_response.school)
+            .bind(&saml_response.employee_id)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to store SAML directory fields: {}", e)))?;
+
+            Ok(())
+        }
+
         async fn create_user_from_saml(
             pool: &Pool<Postgres>,
             username: String,
@@ -434,55 +547,10 @@ cfg_if::cfg_if! {
                 display_name: Some(row.get("display_name")),
                 first_name: Some(row.get("first_name")),
                 last_name: Some(row.get("last_name")),
+                token_scopes: None,
             })
         }
 
-        fn determine_role_from_saml_attributes(attributes: &HashMap<String, Vec<String>>) -> UserRole {
-            // Check for role attributes in common SAML claim formats
-            let role_claims = [
-                "http://schemas.microsoft.com/ws/2008/06/identity/claims/role",
-                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/role",
-                "eduPersonAffiliation",
-                "memberOf",
-                "groups",
-                "role",
-                "roles",
-            ];
-
-            for claim in &role_claims {
-                if let Some(roles) = attributes.get(*claim) {
-                    for role in roles {
-                        let role_lower = role.to_lowercase();
-
-                        // Check for admin roles
-                        if role_lower.contains("admin") ||
-                           role_lower.contains("administrator") ||
-                           role_lower.contains("superadmin") {
-                            return UserRole::Admin;
-                        }
-
-                        // Check for teacher/instructor roles
-                        if role_lower.contains("teacher") ||
-                           role_lower.contains("instructor") ||
-                           role_lower.contains("faculty") ||
-                           role_lower.contains("staff") ||
-                           role_lower.contains("educator") {
-                            return UserRole::Teacher;
-                        }
-
-                        // Check for student roles
-                        if role_lower.contains("student") ||
-                           role_lower.contains("learner") {
-                            return UserRole::User;  // Changed from UserRole::User to map to DB 'user'
-                        }
-                    }
-                }
-            }
-
-            // Default to Guest role (safest default)
-            UserRole::Guest
-        }
-
         async fn get_user_by_saml_mapping(
             pool: &Pool<Postgres>,
             institution_id: &str,
@@ -513,6 +581,7 @@ cfg_if::cfg_if! {
                         display_name: row.get("display_name"),
                         first_name: row.get("first_name"),
                         last_name: row.get("last_name"),
+                        token_scopes: None,
                     }))
                 }
                 None => Ok(None),
@@ -610,6 +679,7 @@ cfg_if::cfg_if! {
                     display_name: row.get("display_name"),
                     first_name: row.get("first_name"),
                     last_name: row.get("last_name"),
+                    token_scopes: None,
                 });
             }
 
@@ -837,6 +907,33 @@ cfg_if::cfg_if! {
             Ok(())
         }
 
+        // Save an institution's attribute and role mapping
+        pub async fn update_saml_mapping(
+            pool: &Pool<Postgres>,
+            config: &SamlConfig,
+        ) -> Result<(), ServerFnError> {
+            let attribute_mapping_json = serde_json::to_value(&config.attribute_mapping)
+                .map_err(|e| ServerFnError::new(format!("Failed to serialize attribute mapping: {}", e)))?;
+            let role_mapping_json = serde_json::to_value(&config.role_mapping)
+                .map_err(|e| ServerFnError::new(format!("Failed to serialize role mapping: {}", e)))?;
+
+            sqlx::query(
+                "UPDATE saml_configs SET
+                    attribute_mapping = $2,
+                    role_mapping = $3,
+                    updated_at = NOW()
+                 WHERE id = $1"
+            )
+            .bind(config.id)
+            .bind(attribute_mapping_json)
+            .bind(role_mapping_json)
+            .execute(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to update SAML mapping: {}", e)))?;
+
+            Ok(())
+        }
+
         // Delete SAML config
         pub async fn delete_saml_config(
             pool: &Pool<Postgres>,
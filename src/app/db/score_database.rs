@@ -2,7 +2,12 @@ cfg_if::cfg_if! {
 
     if #[cfg(feature = "ssr")] {
 
-        use crate::app::models::{Score, CreateScoreRequest};
+        use crate::app::db::audit_database::write_audit_event;
+        use crate::app::db::student_database::caseload_filter;
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::user::SessionUser;
+        use crate::app::models::{Score, CreateScoreRequest, DataScope};
+        use crate::app::models::score_import::HistoricalScore;
         use chrono::{Local, DateTime, Utc, NaiveDateTime};
         use leptos::*;
         use uuid::Uuid;
@@ -10,8 +15,11 @@ cfg_if::cfg_if! {
         use sqlx::prelude::*;
         use sqlx::PgPool;
 
-        pub async fn get_all_scores(pool: &PgPool) -> Result<Vec<Score>, ServerFnError> {
-            let row = sqlx::query("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores ORDER BY date_administered DESC")
+        pub async fn get_all_scores(scope: &DataScope, pool: &PgPool) -> Result<Vec<Score>, ServerFnError> {
+            let query = format!("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE {} ORDER BY date_administered DESC", caseload_filter("student_id", 1, 2));
+            let row = sqlx::query(&query)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await?;
 
@@ -46,9 +54,12 @@ cfg_if::cfg_if! {
             Ok(scores)
         }
 
-        pub async fn get_scores_by_test(test_ids: Vec<Uuid>, pool: &PgPool) -> Result<Vec<Score>, ServerFnError> {
-            let row = sqlx::query("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE test_id = ANY($1) ORDER BY date_administered DESC")
+        pub async fn get_scores_by_test(test_ids: Vec<Uuid>, scope: &DataScope, pool: &PgPool) -> Result<Vec<Score>, ServerFnError> {
+            let query = format!("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE test_id = ANY($1) AND {} ORDER BY date_administered DESC", caseload_filter("student_id", 2, 3));
+            let row = sqlx::query(&query)
                 .bind(&test_ids)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await?;
 
@@ -82,10 +93,11 @@ cfg_if::cfg_if! {
             Ok(scores)
         }
 
-        pub async fn get_score(student_id: i32, test_id: String, test_variant: i32, attempt: i32, pool: &PgPool)-> Result<Score, ServerFnError> {
+        pub async fn get_score(student_id: i32, test_id: String, test_variant: i32, attempt: i32, scope: &DataScope, pool: &PgPool)-> Result<Score, ServerFnError> {
             let ID = Uuid::parse_str(&test_id).expect("Invalid UUID format");
 
-            let row = sqlx::query("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE student_id = $1 AND test_id = $2 AND test_variant = $3 AND attempt = $4").bind(&student_id).bind(ID).bind(&test_variant).bind(&attempt).fetch_one(pool)
+            let query = format!("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE student_id = $1 AND test_id = $2 AND test_variant = $3 AND attempt = $4 AND {}", caseload_filter("student_id", 5, 6));
+            let row = sqlx::query(&query).bind(&student_id).bind(ID).bind(&test_variant).bind(&attempt).bind(scope.is_restricted()).bind(scope.teacher_id()).fetch_one(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
 
@@ -107,9 +119,12 @@ cfg_if::cfg_if! {
             Ok(score)
         }
 
-        pub async fn get_all_student_scores(student_id: i32, pool: &PgPool) -> Result<Vec<Score>, ServerFnError> {
-            let row = sqlx::query("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE student_id = $1 ORDER BY date_administered DESC")
+        pub async fn get_all_student_scores(student_id: i32, scope: &DataScope, pool: &PgPool) -> Result<Vec<Score>, ServerFnError> {
+            let query = format!("SELECT student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt FROM scores WHERE student_id = $1 AND {} ORDER BY date_administered DESC", caseload_filter("student_id", 2, 3));
+            let row = sqlx::query(&query)
                 .bind(&student_id)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_all(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
@@ -176,6 +191,47 @@ cfg_if::cfg_if! {
             Ok(score)
         }
 
+        // Writes imported scores and their audit events in one transaction,
+        // keeping their dates and evaluators. Attempts are numbered per
+        // student, test and variant in the order given, after any attempts
+        // already on file.
+        pub async fn import_scores(scores: &[HistoricalScore], actor: &SessionUser, pool: &sqlx::PgPool) -> Result<Vec<Score>, ServerFnError> {
+            let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            let mut imported = Vec::with_capacity(scores.len());
+            for score in scores {
+                let test_id = Uuid::parse_str(&score.test_id).map_err(|e| ServerFnError::new(format!("Invalid test ID {}: {}", score.test_id, e)))?;
+                let row = sqlx::query("INSERT INTO scores (student_id, date_administered, test_id, test_scores, comments, test_variant, evaluator, attempt) VALUES($1, $2, $3, $4, $5, $6, $7, next_attempt_number($1, $3, $6)) RETURNING student_id, date_administered, test_id::text, test_scores, comments, test_variant, evaluator, attempt")
+                    .bind(score.student_id)
+                    .bind(score.date_administered.naive_utc())
+                    .bind(test_id)
+                    .bind(&score.test_scores)
+                    .bind(&score.comments)
+                    .bind(score.test_variant)
+                    .bind(&score.evaluator)
+                    .fetch_one(&mut *tx)
+                    .await
+                    .map_err(|e| ServerFnError::new(format!("Failed to import the score of student {}: {}", score.student_id, e)))?;
+
+                let naive_datetime: NaiveDateTime = row.get("date_administered");
+
+                imported.push(Score {
+                    student_id: row.get("student_id"),
+                    date_administered: DateTime::<Utc>::from_naive_utc_and_offset(naive_datetime, Utc),
+                    test_id: row.get("test_id"),
+                    test_scores: row.get("test_scores"),
+                    comments: row.get("comments"),
+                    test_variant: row.get("test_variant"),
+                    evaluator: row.get("evaluator"),
+                    attempt: row.get("attempt"),
+                });
+            }
+            for score in &imported {
+                write_audit_event(&mut tx, actor, AuditAction::Create, AuditEntity::Score, &score.audit_id(), AuditChange::created(score)).await?;
+            }
+            tx.commit().await.map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            Ok(imported)
+        }
+
         pub async fn delete_score(student_id: i32, test_id: String, test_variant: i32, attempt: i32, pool: &sqlx::PgPool) -> Result<Score, ServerFnError> {
             let ID = Uuid::parse_str(&test_id).expect("Invalid UUID format");
 
@@ -0,0 +1,212 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::student_database::caseload_filter;
+        use crate::app::models::permission::DataScope;
+        use crate::app::models::student_attribute::{AttributeDefinition, AttributeKind, AttributeValues, SaveAttributeDefinitionRequest};
+        use leptos::ServerFnError;
+        use sqlx::postgres::PgRow;
+        use sqlx::{PgConnection, PgPool, Row};
+        use std::collections::HashMap;
+        use std::str::FromStr;
+
+        const DEFINITION_COLUMNS: &str = "id, key, label, kind, options, sort_order, active";
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn definition_from_row(row: &PgRow) -> Result<AttributeDefinition, ServerFnError> {
+            Ok(AttributeDefinition {
+                id: row.get("id"),
+                key: row.get("key"),
+                label: row.get("label"),
+                kind: AttributeKind::from_str(row.get::<&str, _>("kind")).map_err(ServerFnError::new)?,
+                options: row.get("options"),
+                sort_order: row.get("sort_order"),
+                active: row.get("active"),
+            })
+        }
+
+        // Every attribute, active or not, in display order
+        pub async fn list_attribute_definitions(pool: &PgPool) -> Result<Vec<AttributeDefinition>, ServerFnError> {
+            let rows = sqlx::query(&format!("SELECT {} FROM student_attribute_definitions ORDER BY sort_order, label, id", DEFINITION_COLUMNS))
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+
+            rows.iter().map(definition_from_row).collect()
+        }
+
+        pub async fn save_attribute_definition(pool: &PgPool, request: &SaveAttributeDefinitionRequest) -> Result<AttributeDefinition, ServerFnError> {
+            let options: Vec<String> = request
+                .options
+                .iter()
+                .map(|option| option.trim().to_string())
+                .filter(|option| !option.is_empty())
+                .collect();
+            let sql = match request.id {
+                Some(_) => format!(
+                    "UPDATE student_attribute_definitions
+                     SET key = $1, label = $2, options = $4, sort_order = $5, active = $6, updated_at = NOW()
+                     WHERE id = $7 AND kind = $3
+                     RETURNING {}",
+                    DEFINITION_COLUMNS
+                ),
+                None => format!(
+                    "INSERT INTO student_attribute_definitions (key, label, kind, options, sort_order, active)
+                     VALUES ($1, $2, $3, $4, $5, $6)
+                     RETURNING {}",
+                    DEFINITION_COLUMNS
+                ),
+            };
+            let mut query = sqlx::query(&sql)
+                .bind(request.key.trim())
+                .bind(request.label.trim())
+                .bind(request.kind.as_str())
+                .bind(&options)
+                .bind(request.sort_order)
+                .bind(request.active);
+            if let Some(id) = request.id {
+                query = query.bind(id);
+            }
+            let row = query
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| match &e {
+                    sqlx::Error::Database(db) if db.is_unique_violation() => {
+                        ServerFnError::new(format!("Another attribute already uses the key '{}'", request.key.trim()))
+                    }
+                    _ => db_error(e),
+                })?
+                .ok_or_else(|| ServerFnError::new("Attribute not found, or its kind was changed"))?;
+
+            let definition = definition_from_row(&row)?;
+
+            // Values no longer among a choice attribute's options are cleared
+            if definition.kind == AttributeKind::Enum {
+                sqlx::query("DELETE FROM student_attribute_values WHERE attribute_id = $1 AND NOT (value = ANY($2))")
+                    .bind(definition.id)
+                    .bind(&definition.options)
+                    .execute(pool)
+                    .await
+                    .map_err(db_error)?;
+            }
+            Ok(definition)
+        }
+
+        // Deletes the attribute and every student's value for it
+        pub async fn delete_attribute_definition(pool: &PgPool, id: i32) -> Result<AttributeDefinition, ServerFnError> {
+            let row = sqlx::query(&format!("DELETE FROM student_attribute_definitions WHERE id = $1 RETURNING {}", DEFINITION_COLUMNS))
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServerFnError::new("Attribute not found"))?;
+
+            definition_from_row(&row)
+        }
+
+        // Attribute values of the given students within `scope`, by student
+        // then attribute key. Students with no values are left out.
+        pub async fn list_student_attribute_values(pool: &PgPool, student_ids: &[i32], scope: &DataScope) -> Result<HashMap<i32, AttributeValues>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT v.student_id, d.key, v.value
+                 FROM student_attribute_values v
+                 JOIN student_attribute_definitions d ON d.id = v.attribute_id
+                 WHERE v.student_id = ANY($1) AND {}",
+                caseload_filter("v.student_id", 2, 3)
+            ))
+            .bind(student_ids)
+            .bind(scope.is_restricted())
+            .bind(scope.teacher_id())
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            Ok(values_from_rows(&rows))
+        }
+
+        // Every student's attribute values, read on `conn`
+        pub async fn read_all_attribute_values(conn: &mut PgConnection) -> Result<HashMap<i32, AttributeValues>, ServerFnError> {
+            let rows = sqlx::query(
+                "SELECT v.student_id, d.key, v.value
+                 FROM student_attribute_values v
+                 JOIN student_attribute_definitions d ON d.id = v.attribute_id",
+            )
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            Ok(values_from_rows(&rows))
+        }
+
+        fn values_from_rows(rows: &[PgRow]) -> HashMap<i32, AttributeValues> {
+            let mut values: HashMap<i32, AttributeValues> = HashMap::new();
+            for row in rows {
+                values
+                    .entry(row.get("student_id"))
+                    .or_default()
+                    .insert(row.get("key"), row.get("value"));
+            }
+            values
+        }
+
+        // Sets each student's values for the attributes in `keys` to what
+        // `values` holds, clearing those it leaves out. Values must already
+        // be normalized. Attributes outside `keys` are untouched, so an import
+        // only changes the columns it has.
+        pub async fn set_student_attribute_values(pool: &PgPool, keys: &[String], values: &[(i32, AttributeValues)]) -> Result<(), ServerFnError> {
+            if keys.is_empty() || values.is_empty() {
+                return Ok(());
+            }
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            write_student_attribute_values(&mut tx, keys, values).await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok(())
+        }
+
+        // set_student_attribute_values inside the caller's transaction
+        pub async fn write_student_attribute_values(conn: &mut PgConnection, keys: &[String], values: &[(i32, AttributeValues)]) -> Result<(), ServerFnError> {
+            if keys.is_empty() || values.is_empty() {
+                return Ok(());
+            }
+
+            let student_ids: Vec<i32> = values.iter().map(|(student_id, _)| *student_id).collect();
+            sqlx::query(
+                "DELETE FROM student_attribute_values v
+                 USING student_attribute_definitions d
+                 WHERE d.id = v.attribute_id AND v.student_id = ANY($1) AND d.key = ANY($2)",
+            )
+            .bind(&student_ids)
+            .bind(keys)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            let mut rows_student = Vec::new();
+            let mut rows_key = Vec::new();
+            let mut rows_value = Vec::new();
+            for (student_id, student_values) in values {
+                for (key, value) in student_values.iter().filter(|(key, _)| keys.contains(key)) {
+                    rows_student.push(*student_id);
+                    rows_key.push(key.clone());
+                    rows_value.push(value.clone());
+                }
+            }
+            sqlx::query(
+                "INSERT INTO student_attribute_values (student_id, attribute_id, value)
+                 SELECT u.student_id, d.id, u.value
+                 FROM UNNEST($1::INT[], $2::TEXT[], $3::TEXT[]) AS u(student_id, key, value)
+                 JOIN student_attribute_definitions d ON d.key = u.key",
+            )
+            .bind(&rows_student)
+            .bind(&rows_key)
+            .bind(&rows_value)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            Ok(())
+        }
+    }
+}
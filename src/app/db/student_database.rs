@@ -5,80 +5,72 @@ use crate::app::models::student;
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")]{
 
-        use crate::app::models::{Student, AddStudentRequest};
+        use crate::app::models::{Student, AddStudentRequest, DataScope};
         use crate::app::models::student::{GradeEnum, ESLEnum, GenderEnum, InterventionEnum};
+        use crate::app::models::student_search::{StudentSearchHit, StudentSearchRequest, StudentSearchResults};
+        use crate::app::db::audit_database::write_audit_event;
+        use crate::app::db::student_attribute_database::write_student_attribute_values;
+        use crate::app::db::student_history_database::write_student_versions;
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::user::SessionUser;
+        use crate::app::services::roster_sync::student_from_request;
+        use crate::app::db::student_encryption_database::{bind_pii, stored_pii_from_row, student_keyring, PII_COLUMNS, PII_WRITE_COLUMNS};
+        use crate::app::services::student_encryption::{open_pii, search_words, seal_pii, PiiKeyring, StoredPii, StudentPii};
+        use sqlx::postgres::PgRow;
         use log::{debug, error, info, warn};
         use chrono::NaiveDate;
         use leptos::*;
         use sqlx::PgPool;
         use sqlx::prelude::*;
 
-        pub async fn get_all_students(pool: &PgPool) -> Result<Vec<Student>, ServerFnError>{
-            let rows  = sqlx::query("SELECT firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin FROM students ORDER BY lastname")
-                .fetch_all(pool)
-                .await?;
-
-            let students: Vec<Student> = rows
-                .into_iter()
-                .map(|row| {
-                    let firstname: Option<String> = row.get("firstname");
-                    let lastname: Option<String> = row.get("lastname");
-                    let preferred: String = row.get("preferred");
-                    let gender: GenderEnum = row.get("gender");
-                    let date_of_birth: chrono::NaiveDate = row.get::<NaiveDate, _>("date_of_birth");
-                    let student_id: i32 = row.get("student_id");
-                    let esl: ESLEnum = row.get("esl");
-                    let current_grade_level: GradeEnum = row.get("current_grade_level");
-                    let teacher: String = row.get("teacher");
-                    let iep: bool = row.get("iep");
-                    let bip: bool = row.get("bip");
-                    let student_504: bool = row.get("student_504");
-                    let readplan: bool = row.get("readplan");
-                    let gt: bool = row.get("gt");
-                    let intervention: Option<InterventionEnum> = row.get("intervention");
-                    let eye_glasses: bool = row.get("eye_glasses");
-                    let notes: String = row.get("notes");
-                    let pin: Option<i32> = row.get("pin");
-
-                    Student {
-                        firstname,
-                        lastname,
-                        preferred,
-                        gender,
-                        date_of_birth,
-                        student_id,
-                        esl,
-                        current_grade_level,
-                        teacher,
-                        iep,
-                        bip,
-                        student_504,
-                        readplan,
-                        gt,
-                        intervention,
-                        eye_glasses,
-                        notes,
-                        pin,
-                    }
-                })
-                .collect();
-            Ok(students)
+        // SQL predicate limiting `column` to the caseload described by a DataScope.
+        // $restricted_param binds DataScope::is_restricted() and $teacher_param binds
+        // DataScope::teacher_id(); a NULL teacher id matches nothing. Current
+        // members of the teacher's active student groups count as caseload too.
+        pub fn caseload_filter(column: &str, restricted_param: usize, teacher_param: usize) -> String {
+            format!(
+                "(NOT ${restricted} OR {column} IN (SELECT se.student_id FROM student_enrollments se LEFT JOIN courses c ON c.id = se.course_id WHERE se.status = 'active' AND (se.teacher_id = ${teacher} OR c.teacher_id = ${teacher})) OR {column} IN (SELECT gm.student_id FROM student_group_members gm JOIN student_groups g ON g.id = gm.group_id WHERE g.active AND g.owner_id = ${teacher} AND gm.start_date <= CURRENT_DATE AND (gm.end_date IS NULL OR gm.end_date >= CURRENT_DATE)))",
+                restricted = restricted_param,
+                column = column,
+                teacher = teacher_param,
+            )
         }
 
-        pub async fn get_certain_student(student_id: i32, pool: &PgPool) -> Result<Student, ServerFnError> {
-            let row = sqlx::query("SELECT firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin FROM students WHERE student_id = $1")
-                .bind(&student_id)
+        pub async fn is_student_in_scope(student_id: i32, scope: &DataScope, pool: &PgPool) -> Result<bool, ServerFnError> {
+            if !scope.is_restricted() {
+                return Ok(true);
+            }
+
+            let query = format!("SELECT EXISTS (SELECT 1 FROM students WHERE student_id = $3 AND {})", caseload_filter("student_id", 1, 2));
+            let in_scope: bool = sqlx::query_scalar(&query)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
+                .bind(student_id)
                 .fetch_one(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
 
-            let student = Student {
-                firstname: row.get("firstname"),
-                lastname: row.get("lastname"),
+            Ok(in_scope)
+        }
+
+        // Non-PII columns; PII comes from PII_COLUMNS and goes through the keyring
+        const STUDENT_COLUMNS: &str = "preferred, gender, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses";
+
+        const INSERT_STUDENT: &str = "INSERT INTO students (preferred, gender, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, firstname, lastname, pin, date_of_birth, notes, pii_key_id, firstname_enc, lastname_enc, pin_enc, date_of_birth_enc, notes_enc, firstname_bidx, lastname_bidx, date_of_birth_bidx, name_tokens) VALUES($1, $2::gender_enum, $3, $4::esl_enum, $5::grade_enum, $6, $7, $8, $9, $10, $11, $12::intervention_enum, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28)";
+
+        fn student_from_row(row: &PgRow, keyring: Option<&PiiKeyring>) -> Result<Student, ServerFnError> {
+            let student_id: i32 = row.get("student_id");
+            let pii = open_pii(keyring, &stored_pii_from_row(row))
+                .map_err(|e| ServerFnError::new(format!("Student {}: {}", student_id, e)))?;
+
+            Ok(Student {
+                firstname: pii.firstname,
+                lastname: pii.lastname,
                 preferred: row.get("preferred"),
                 gender: row.get("gender"),
-                date_of_birth: row.get("date_of_birth"),
-                student_id: row.get("student_id"),
+                date_of_birth: pii.date_of_birth
+                    .ok_or_else(|| ServerFnError::new(format!("Student {} has no date of birth", student_id)))?,
+                student_id,
                 esl: row.get("esl"),
                 current_grade_level: row.get("current_grade_level"),
                 teacher: row.get("teacher"),
@@ -89,198 +81,390 @@ cfg_if::cfg_if! {
                 gt: row.get("gt"),
                 intervention: row.get("intervention"),
                 eye_glasses: row.get("eye_glasses"),
-                notes: row.get("notes"),
-                pin: row.get("pin"),
-            };
+                notes: pii.notes.unwrap_or_default(),
+                pin: pii.pin,
+            })
+        }
 
-            Ok(student)
+        fn students_from_rows(rows: Vec<PgRow>, keyring: Option<&PiiKeyring>) -> Result<Vec<Student>, ServerFnError> {
+            let mut students = rows
+                .iter()
+                .map(|row| student_from_row(row, keyring))
+                .collect::<Result<Vec<Student>, _>>()?;
+            // Encrypted names can't be ordered in SQL
+            students.sort_by(|a, b| a.lastname.cmp(&b.lastname));
+            Ok(students)
         }
 
-        pub async fn add_student(new_student: &Student, pool: &PgPool) -> Result<Student, ServerFnError>{
-            let row = sqlx::query("INSERT INTO students (firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin) VALUES($1, $2, $3, $4::gender_enum, $5, $6, $7::esl_enum, $8::grade_enum, $9, $10, $11, $12, $13, $14, $15::intervention_enum, $16, $17, $18) RETURNING firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin")
-                .bind(&new_student.firstname)
-                .bind(&new_student.lastname)
-                .bind(&new_student.preferred)
-                .bind(&new_student.gender.to_string())
-                .bind(&new_student.date_of_birth)
-                .bind(&new_student.student_id)
-                .bind(&new_student.esl.to_string())
-                .bind(&new_student.current_grade_level.to_string())
-                .bind(&new_student.teacher)
-                .bind(&new_student.iep)
-                .bind(&new_student.bip)
-                .bind(&new_student.student_504)
-                .bind(&new_student.readplan)
-                .bind(&new_student.gt)
-                .bind(&new_student.intervention)
-                .bind(&new_student.eye_glasses)
-                .bind(&new_student.notes)
-                .bind(&new_student.pin)
+        fn seal_student_pii(keyring: Option<&PiiKeyring>, firstname: Option<String>, lastname: Option<String>, pin: Option<i32>, date_of_birth: NaiveDate, notes: String) -> Result<StoredPii, ServerFnError> {
+            seal_pii(keyring, StudentPii {
+                firstname,
+                lastname,
+                pin,
+                date_of_birth: Some(date_of_birth),
+                notes: Some(notes),
+            })
+            .map_err(ServerFnError::new)
+        }
+
+        pub async fn get_all_students(scope: &DataScope, pool: &PgPool) -> Result<Vec<Student>, ServerFnError>{
+            let keyring = student_keyring(pool).await?;
+            let query = format!("SELECT {}, {} FROM students WHERE {}", STUDENT_COLUMNS, PII_COLUMNS, caseload_filter("student_id", 1, 2));
+            let rows  = sqlx::query(&query)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
+                .fetch_all(pool)
+                .await?;
+
+            students_from_rows(rows, keyring.as_deref())
+        }
+
+        // Every student, read on `conn` so a transaction diffing against
+        // them sees the rows it then writes
+        pub async fn read_all_students(conn: &mut sqlx::PgConnection, keyring: Option<&PiiKeyring>) -> Result<Vec<Student>, ServerFnError> {
+            let query = format!("SELECT {}, {} FROM students", STUDENT_COLUMNS, PII_COLUMNS);
+            let rows = sqlx::query(&query)
+                .fetch_all(&mut *conn)
+                .await?;
+
+            students_from_rows(rows, keyring)
+        }
+
+        pub async fn read_student(conn: &mut sqlx::PgConnection, keyring: Option<&PiiKeyring>, student_id: i32) -> Result<Student, ServerFnError> {
+            let query = format!("SELECT {}, {} FROM students WHERE student_id = $1", STUDENT_COLUMNS, PII_COLUMNS);
+            let row = sqlx::query(&query)
+                .bind(student_id)
+                .fetch_optional(&mut *conn)
+                .await?
+                .ok_or_else(|| ServerFnError::new(format!("Student {} not found", student_id)))?;
+
+            student_from_row(&row, keyring)
+        }
+
+        // Students whose first or last name has a word starting with each word
+        // of `fragment`, or whose ID is `fragment`. Encrypted rows are matched
+        // through their name tokens, plaintext rows directly.
+        pub async fn search_students(fragment: &str, scope: &DataScope, pool: &PgPool) -> Result<Vec<Student>, ServerFnError> {
+            let words = search_words(fragment);
+            if words.is_empty() {
+                return get_all_students(scope, pool).await;
+            }
+            let keyring = student_keyring(pool).await?;
+            let tokens = keyring.as_ref().map(|keyring| keyring.search_tokens(&words)).unwrap_or_default();
+
+            let patterns: Vec<String> = words
+                .iter()
+                .map(|word| word.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+                .collect();
+            let mut matches = vec![
+                "NOT EXISTS (SELECT 1 FROM UNNEST($3::TEXT[]) w WHERE NOT (LOWER(firstname) LIKE w || '%' OR LOWER(firstname) LIKE '% ' || w || '%' OR LOWER(lastname) LIKE w || '%' OR LOWER(lastname) LIKE '% ' || w || '%'))".to_string(),
+                "student_id = $4".to_string(),
+            ];
+            for index in 0..tokens.len() {
+                matches.push(format!("(pii_key_id = ${} AND name_tokens @> ${})", 5 + index * 2, 6 + index * 2));
+            }
+
+            let query = format!(
+                "SELECT {}, {} FROM students WHERE {} AND ({})",
+                STUDENT_COLUMNS,
+                PII_COLUMNS,
+                caseload_filter("student_id", 1, 2),
+                matches.join(" OR ")
+            );
+            let mut query = sqlx::query(&query)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
+                .bind(&patterns)
+                .bind(fragment.trim().parse::<i32>().ok());
+            for (key_id, key_tokens) in &tokens {
+                query = query.bind(key_id).bind(key_tokens);
+            }
+            let rows = query
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            students_from_rows(rows, keyring.as_deref())
+        }
+
+        // Lowest score a ranked search hit may have. Trigram similarity of a
+        // one-letter typo in a short name sits around 0.3.
+        const MIN_SEARCH_SCORE: f64 = 0.3;
+
+        // Lowercased preferred, first and last name, matching the trigram index
+        const SEARCH_NAME: &str = "LOWER(COALESCE(s.preferred, '') || ' ' || COALESCE(s.firstname, '') || ' ' || COALESCE(s.lastname, ''))";
+
+        // Typo-tolerant search over preferred name, first and last name, student
+        // ID and teacher, best match first. Plaintext fields go through pg_trgm;
+        // encrypted names can only be matched by their name tokens, so they score
+        // by how much of each word a stored prefix covers.
+        pub async fn rank_student_search(request: &StudentSearchRequest, scope: &DataScope, pool: &PgPool) -> Result<StudentSearchResults, ServerFnError> {
+            let page_size = request.page_size();
+            let query_text = request.query.trim().to_lowercase();
+            let words = search_words(&query_text);
+            if words.is_empty() {
+                return Ok(StudentSearchResults { hits: Vec::new(), total: 0, page: request.page, per_page: page_size });
+            }
+            let keyring = student_keyring(pool).await?;
+            let prefix_tokens = keyring.as_ref().map(|keyring| keyring.prefix_search_tokens(&words)).unwrap_or_default();
+            let id_prefix = format!("{}%", query_text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+
+            let query = format!(
+                "SELECT {columns}, {pii}, ranked.score, COUNT(*) OVER () AS total_matches
+                 FROM (
+                     SELECT s.student_id, GREATEST(
+                         CASE WHEN s.student_id::text = $3 THEN 2.0 WHEN s.student_id::text LIKE $4 THEN 1.5 ELSE 0 END,
+                         word_similarity($3, s.student_id::text),
+                         word_similarity($3, {name}),
+                         word_similarity($3, LOWER(s.teacher)) * 0.8,
+                         COALESCE((
+                             SELECT SUM(best) / $9
+                             FROM (
+                                 SELECT MAX(t.coverage) AS best
+                                 FROM UNNEST($5::INT[], $6::INT[], $7::FLOAT8[], $8::BYTEA[]) AS t(key_id, word, coverage, token)
+                                 WHERE t.key_id = s.pii_key_id AND t.token = ANY(s.name_tokens)
+                                 GROUP BY t.word
+                             ) words
+                         ), 0)
+                     )::float8 AS score
+                     FROM students s
+                     WHERE {caseload}
+                       AND ($3 <% {name} OR $3 <% LOWER(s.teacher) OR $3 <% s.student_id::text OR s.student_id::text LIKE $4 OR s.name_tokens && $8::BYTEA[])
+                 ) ranked
+                 JOIN students USING (student_id)
+                 WHERE ranked.score >= $10
+                 ORDER BY ranked.score DESC, student_id
+                 LIMIT $11 OFFSET $12",
+                columns = STUDENT_COLUMNS,
+                pii = PII_COLUMNS,
+                name = SEARCH_NAME,
+                caseload = caseload_filter("s.student_id", 1, 2),
+            );
+
+            let mut tx = pool.begin().await.map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            // Lets the trigram indexes prefilter at our threshold rather than the default 0.6
+            sqlx::query("SELECT set_config('pg_trgm.word_similarity_threshold', $1, true)")
+                .bind(MIN_SEARCH_SCORE.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            let rows = sqlx::query(&query)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
+                .bind(&query_text)
+                .bind(&id_prefix)
+                .bind(prefix_tokens.iter().map(|(key_id, _, _, _)| *key_id).collect::<Vec<i32>>())
+                .bind(prefix_tokens.iter().map(|(_, word, _, _)| *word).collect::<Vec<i32>>())
+                .bind(prefix_tokens.iter().map(|(_, _, coverage, _)| *coverage).collect::<Vec<f64>>())
+                .bind(prefix_tokens.iter().map(|(_, _, _, token)| token.clone()).collect::<Vec<Vec<u8>>>())
+                .bind(words.len() as f64)
+                .bind(MIN_SEARCH_SCORE)
+                .bind(page_size as i64)
+                .bind(request.offset())
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            tx.commit().await.map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            let total = rows.first().map(|row| row.get::<i64, _>("total_matches")).unwrap_or(0);
+            let hits = rows
+                .iter()
+                .map(|row| {
+                    Ok(StudentSearchHit {
+                        student: student_from_row(row, keyring.as_deref())?,
+                        score: row.get("score"),
+                    })
+                })
+                .collect::<Result<Vec<_>, ServerFnError>>()?;
+
+            Ok(StudentSearchResults { hits, total, page: request.page, per_page: page_size })
+        }
+
+        pub async fn get_certain_student(student_id: i32, scope: &DataScope, pool: &PgPool) -> Result<Student, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let query = format!("SELECT {}, {} FROM students WHERE student_id = $1 AND {}", STUDENT_COLUMNS, PII_COLUMNS, caseload_filter("student_id", 2, 3));
+            let row = sqlx::query(&query)
+                .bind(&student_id)
+                .bind(scope.is_restricted())
+                .bind(scope.teacher_id())
                 .fetch_one(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
 
-            let student = Student {
-                firstname: row.get("firstname"),
-                lastname: row.get("lastname"),
-                preferred: row.get("preferred"),
-                gender: row.get("gender"),
-                date_of_birth: row.get("date_of_birth"),
-                student_id: row.get("student_id"),
-                esl: row.get("esl"),
-                current_grade_level: row.get("current_grade_level"),
-                teacher: row.get("teacher"),
-                iep: row.get("iep"),
-                bip: row.get("bip"),
-                student_504: row.get("student_504"),
-                readplan: row.get("readplan"),
-                gt: row.get("gt"),
-                intervention: row.get("intervention"),
-                eye_glasses: row.get("eye_glasses"),
-                notes: row.get("notes"),
-                pin: row.get("pin"),
-            };
+            student_from_row(&row, keyring.as_deref())
+        }
 
-            Ok(student)
+        pub async fn add_student(new_student: &Student, pool: &PgPool) -> Result<Student, ServerFnError>{
+            let keyring = student_keyring(pool).await?;
+            let pii = seal_student_pii(keyring.as_deref(), new_student.firstname.clone(), new_student.lastname.clone(), new_student.pin, new_student.date_of_birth, new_student.notes.clone())?;
+
+            let query = format!("{} RETURNING {}, {}", INSERT_STUDENT, STUDENT_COLUMNS, PII_COLUMNS);
+            let row = bind_pii(
+                sqlx::query(&query)
+                    .bind(&new_student.preferred)
+                    .bind(&new_student.gender.to_string())
+                    .bind(&new_student.student_id)
+                    .bind(&new_student.esl.to_string())
+                    .bind(&new_student.current_grade_level.to_string())
+                    .bind(&new_student.teacher)
+                    .bind(&new_student.iep)
+                    .bind(&new_student.bip)
+                    .bind(&new_student.student_504)
+                    .bind(&new_student.readplan)
+                    .bind(&new_student.gt)
+                    .bind(&new_student.intervention)
+                    .bind(&new_student.eye_glasses),
+                &pii,
+            )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            student_from_row(&row, keyring.as_deref())
         }
 
         pub async fn delete_student(firstname: String, lastname: String, student_id: i32, pool: &PgPool) -> Result<Student, ServerFnError> {
-            let row = sqlx::query("DELETE FROM students WHERE firstname = $1 AND lastname = $2 AND student_id =$3 RETURNING firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin")
+            let keyring = student_keyring(pool).await?;
+            // Encrypted rows are matched on the names' blind indexes
+            let (firstname_keys, firstname_bidx): (Vec<i32>, Vec<Vec<u8>>) = keyring
+                .as_ref()
+                .map(|keyring| keyring.blind_indexes("firstname", &firstname).into_iter().unzip())
+                .unwrap_or_default();
+            let (lastname_keys, lastname_bidx): (Vec<i32>, Vec<Vec<u8>>) = keyring
+                .as_ref()
+                .map(|keyring| keyring.blind_indexes("lastname", &lastname).into_iter().unzip())
+                .unwrap_or_default();
+
+            let query = format!(
+                "DELETE FROM students WHERE student_id = $3
+                 AND (firstname = $1 OR (pii_key_id, firstname_bidx) IN (SELECT * FROM UNNEST($4::INT[], $5::BYTEA[])))
+                 AND (lastname = $2 OR (pii_key_id, lastname_bidx) IN (SELECT * FROM UNNEST($6::INT[], $7::BYTEA[])))
+                 RETURNING {}, {}",
+                STUDENT_COLUMNS, PII_COLUMNS
+            );
+            let row = sqlx::query(&query)
                 .bind(firstname)
                 .bind(lastname)
                 .bind(student_id)
+                .bind(firstname_keys)
+                .bind(firstname_bidx)
+                .bind(lastname_keys)
+                .bind(lastname_bidx)
                 .fetch_one(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
 
-            let student = Student {
-                firstname: row.get("firstname"),
-                lastname: row.get("lastname"),
-                preferred: row.get("preferred"),
-                gender: row.get("gender"),
-                date_of_birth: row.get::<chrono::NaiveDate,_>("date_of_birth"),
-                student_id: row.get("student_id"),
-                esl: row.get("esl"),
-                current_grade_level: row.get("current_grade_level"),
-                teacher: row.get("teacher"),
-                iep: row.get("iep"),
-                bip: row.get("bip"),
-                student_504: row.get("student_504"),
-                readplan: row.get("readplan"),
-                gt: row.get("gt"),
-                intervention: row.get("intervention"),
-                eye_glasses: row.get("eye_glasses"),
-                notes: row.get("notes"),
-                pin: row.get("pin"),
-            };
+            student_from_row(&row, keyring.as_deref())
+        }
 
-            Ok(student)
+        pub async fn update_student(firstname: String, lastname: String, preferred: String, gender: GenderEnum, date_of_birth: NaiveDate, student_id: i32, esl: ESLEnum, current_grade_level: GradeEnum, teacher: String, iep: bool, bip: bool, student_504: bool, readplan: bool, gt: bool, intervention: Option<InterventionEnum>, eye_glasses: bool, notes: String, pin: i32, pool: &PgPool) -> Result<Option<Student>, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let pii = seal_student_pii(keyring.as_deref(), Some(firstname), Some(lastname), Some(pin), date_of_birth, notes)?;
 
+            let query = format!(
+                "UPDATE students SET preferred =$1, gender =$2::gender_enum, student_id=$3, esl =$4::esl_enum, current_grade_level =$5::grade_enum, teacher =$6, iep =$7, bip =$8, student_504 =$9, readplan =$10, gt =$11, intervention =$12::intervention_enum, eye_glasses =$13, ({}) = ($14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27, $28) WHERE student_id = $3 RETURNING {}, {}",
+                PII_WRITE_COLUMNS, STUDENT_COLUMNS, PII_COLUMNS
+            );
+            let row = bind_pii(
+                sqlx::query(&query)
+                    .bind(preferred)
+                    .bind(gender)
+                    .bind(student_id)
+                    .bind(esl)
+                    .bind(current_grade_level)
+                    .bind(teacher)
+                    .bind(iep)
+                    .bind(bip)
+                    .bind(student_504)
+                    .bind(readplan)
+                    .bind(gt)
+                    .bind(intervention)
+                    .bind(eye_glasses),
+                &pii,
+            )
+            .fetch_one(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+            Ok(Some(student_from_row(&row, keyring.as_deref())?))
         }
 
-        pub async fn update_student(firstname: String, lastname: String, preferred: String, gender: GenderEnum, date_of_birth: NaiveDate, student_id: i32, esl: ESLEnum, current_grade_level: GradeEnum, teacher: String, iep: bool, bip: bool, student_504: bool, readplan: bool, gt: bool, intervention: Option<InterventionEnum>, eye_glasses: bool, notes: String, pin: i32, pool: &PgPool) -> Result<Option<Student>, ServerFnError> {
+        // Writes whole student rows inside the caller's transaction, inserting
+        // new student IDs and overwriting existing ones. Roster imports merge
+        // the SIS's fields into the stored record before calling this.
+        pub async fn upsert_students(students: &[Student], keyring: Option<&PiiKeyring>, tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), ServerFnError> {
+            let updates = STUDENT_COLUMNS
+                .split(", ")
+                .chain(PII_WRITE_COLUMNS.split(", "))
+                .filter(|column| *column != "student_id")
+                .map(|column| format!("{column} = EXCLUDED.{column}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let query = format!("{} ON CONFLICT (student_id) DO UPDATE SET {}", INSERT_STUDENT, updates);
 
-            let row = sqlx::query("UPDATE students SET firstname =$1, lastname =$2, preferred =$3, gender =$4::gender_enum, date_of_birth=$5::DATE, student_id=$6, esl =$7::esl_enum, current_grade_level =$8::grade_enum, teacher =$9, iep =$10, bip =$11, student_504 =$12, readplan =$13, gt =$14, intervention =$15::intervention_enum, eye_glasses =$16, notes =$17, pin =$18 WHERE student_id = $6 RETURNING firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin")
-                .bind(firstname)
-                .bind(lastname)
-                .bind(preferred)
-                .bind(gender)
-                .bind(date_of_birth)
-                .bind(student_id)
-                .bind(esl)
-                .bind(current_grade_level)
-                .bind(teacher)
-                .bind(iep)
-                .bind(bip)
-                .bind(student_504)
-                .bind(readplan)
-                .bind(gt)
-                .bind(intervention)
-                .bind(eye_glasses)
-                .bind(notes)
-                .bind(pin)
-                .fetch_one(pool)
+            for student in students {
+                let pii = seal_student_pii(keyring, student.firstname.clone(), student.lastname.clone(), student.pin, student.date_of_birth, student.notes.clone())?;
+                bind_pii(
+                    sqlx::query(&query)
+                        .bind(&student.preferred)
+                        .bind(student.gender.to_string())
+                        .bind(student.student_id)
+                        .bind(student.esl.to_string())
+                        .bind(student.current_grade_level.to_string())
+                        .bind(&student.teacher)
+                        .bind(student.iep)
+                        .bind(student.bip)
+                        .bind(student.student_504)
+                        .bind(student.readplan)
+                        .bind(student.gt)
+                        .bind(&student.intervention)
+                        .bind(student.eye_glasses),
+                    &pii,
+                )
+                .execute(&mut **tx)
                 .await
-                .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
-
-            let student = Student {
-                firstname: row.get("firstname"),
-                lastname: row.get("lastname"),
-                preferred: row.get("preferred"),
-                gender: row.get("gender"),
-                date_of_birth: row.get::<chrono::NaiveDate,_>("date_of_birth"),
-                student_id: row.get("student_id"),
-                esl: row.get("esl"),
-                current_grade_level: row.get("current_grade_level"),
-                teacher: row.get("teacher"),
-                iep: row.get("iep"),
-                bip: row.get("bip"),
-                student_504: row.get("student_504"),
-                readplan: row.get("readplan"),
-                gt: row.get("gt"),
-                intervention: row.get("intervention"),
-                eye_glasses: row.get("eye_glasses"),
-                notes: row.get("notes"),
-                pin: row.get("pin"),
-            };
+                .map_err(|e| ServerFnError::new(format!("Failed to save student {}: {}", student.student_id, e)))?;
+            }
+            Ok(())
+        }
 
-            Ok(Some(student))
+        fn seal_request_pii(keyring: Option<&PiiKeyring>, student: &AddStudentRequest) -> Result<StoredPii, ServerFnError> {
+            seal_student_pii(keyring, Some(student.firstname.clone()), Some(student.lastname.clone()), Some(student.pin), student.date_of_birth, student.notes.clone())
         }
 
         pub async fn bulk_insert_students(students: Vec<AddStudentRequest>, pool: &PgPool) -> Result<Vec<Student>, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
             // Start a database transaction for bulk insert
             let mut tx = pool.begin().await?;
 
             let mut inserted_students = Vec::new();
+            let query = format!("{} RETURNING {}, {}", INSERT_STUDENT, STUDENT_COLUMNS, PII_COLUMNS);
 
-            // Prepare the bulk insert query
             for student in students {
                 // Use the existing add_student logic within the transaction
-                let row = sqlx::query("INSERT INTO students (firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin) VALUES($1, $2, $3, $4::gender_enum, $5, $6, $7::esl_enum, $8::grade_enum, $9, $10, $11, $12, $13, $14, $15::intervention_enum, $16, $17, $18) RETURNING firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin")
-                    .bind(&student.firstname)
-                    .bind(&student.lastname)
-                    .bind(&student.preferred)
-                    .bind(&student.gender.to_string())
-                    .bind(&student.date_of_birth)
-                    .bind(&student.student_id)
-                    .bind(&student.esl.to_string())
-                    .bind(&student.current_grade_level.to_string())
-                    .bind(&student.teacher)
-                    .bind(&student.iep)
-                    .bind(&student.bip)
-                    .bind(&student.student_504)
-                    .bind(&student.readplan)
-                    .bind(&student.gt)
-                    .bind(&student.intervention)
-                    .bind(&student.eye_glasses)
-                    .bind(&student.notes)
-                    .bind(&student.pin)
-                    .fetch_one(&mut *tx)
-                    .await
-                    .map_err(|e| ServerFnError::new(format!("Bulk insert error: {}", e)))?;
-
-                let inserted_student = Student {
-                    firstname: row.get("firstname"),
-                    lastname: row.get("lastname"),
-                    preferred: row.get("preferred"),
-                    gender: row.get("gender"),
-                    date_of_birth: row.get("date_of_birth"),
-                    student_id: row.get("student_id"),
-                    esl: row.get("esl"),
-                    current_grade_level: row.get("current_grade_level"),
-                    teacher: row.get("teacher"),
-                    iep: row.get("iep"),
-                    bip: row.get("bip"),
-                    student_504: row.get("student_504"),
-                    readplan: row.get("readplan"),
-                    gt: row.get("gt"),
-                    intervention: row.get("intervention"),
-                    eye_glasses: row.get("eye_glasses"),
-                    notes: row.get("notes"),
-                    pin: row.get("pin"),
-                };
-
-                inserted_students.push(inserted_student);
+                let pii = seal_request_pii(keyring.as_deref(), &student)?;
+                let row = bind_pii(
+                    sqlx::query(&query)
+                        .bind(&student.preferred)
+                        .bind(&student.gender.to_string())
+                        .bind(&student.student_id)
+                        .bind(&student.esl.to_string())
+                        .bind(&student.current_grade_level.to_string())
+                        .bind(&student.teacher)
+                        .bind(&student.iep)
+                        .bind(&student.bip)
+                        .bind(&student.student_504)
+                        .bind(&student.readplan)
+                        .bind(&student.gt)
+                        .bind(&student.intervention)
+                        .bind(&student.eye_glasses),
+                    &pii,
+                )
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Bulk insert error: {}", e)))?;
+
+                inserted_students.push(student_from_row(&row, keyring.as_deref())?);
             }
 
             // Commit the transaction
@@ -289,27 +473,53 @@ cfg_if::cfg_if! {
             Ok(inserted_students)
         }
 
-        pub async fn bulk_insert_students_optimized(students: Vec<AddStudentRequest>, pool: &PgPool) -> Result<usize, ServerFnError> {
+        // Inserts the students with their values for the custom attributes
+        // in `attribute_keys`, first versions and audit events, all in one
+        // transaction
+        pub async fn bulk_insert_students_optimized(students: Vec<AddStudentRequest>, attribute_keys: &[String], actor: &SessionUser, pool: &PgPool) -> Result<usize, ServerFnError> {
             if students.is_empty() {
                 return Ok(0);
             }
 
+            let keyring = student_keyring(pool).await?;
+            let sealed = students
+                .iter()
+                .map(|student| seal_request_pii(keyring.as_deref(), student))
+                .collect::<Result<Vec<_>, _>>()?;
+
             // Start a database transaction
             let mut tx = pool.begin().await?;
 
             // Method 1: Using UNNEST for maximum efficiency (PostgreSQL specific)
-            let result = bulk_insert_with_unnest(&students, &mut tx).await;
-
-            match result {
-                Ok(count) => {
-                    tx.commit().await?;
-                    Ok(count)
-                }
+            let count = match bulk_insert_with_unnest(&students, &sealed, &mut tx).await {
+                Ok(count) => count,
                 Err(e) => {
                     tx.rollback().await?;
-                    Err(ServerFnError::new(format!("Bulk insert failed: {}", e)))
+                    return Err(ServerFnError::new(format!("Bulk insert failed: {}", e)));
                 }
+            };
+
+            let attribute_values: Vec<_> = students
+                .iter()
+                .map(|student| (student.student_id, student.custom_attributes.clone()))
+                .collect();
+            write_student_attribute_values(&mut tx, attribute_keys, &attribute_values).await?;
+            let student_ids: Vec<i32> = students.iter().map(|student| student.student_id).collect();
+            write_student_versions(&mut tx, &student_ids, &actor.username).await?;
+            for student in &students {
+                write_audit_event(
+                    &mut tx,
+                    actor,
+                    AuditAction::Create,
+                    AuditEntity::Student,
+                    &student.student_id.to_string(),
+                    AuditChange::created(&student_from_request(student)).redacted(&["student_id"]),
+                )
+                .await?;
             }
+
+            tx.commit().await?;
+            Ok(count)
         }
 
         // Alternative method using batch inserts if UNNEST doesn't work
@@ -318,12 +528,18 @@ cfg_if::cfg_if! {
                 return Ok(0);
             }
 
+            let keyring = student_keyring(pool).await?;
+            let sealed = students
+                .iter()
+                .map(|student| seal_request_pii(keyring.as_deref(), student))
+                .collect::<Result<Vec<_>, _>>()?;
+
             let mut tx = pool.begin().await?;
             let batch_size = 100; // Adjust based on your needs
             let mut total_inserted = 0;
 
-            for chunk in students.chunks(batch_size) {
-                let count = insert_batch(chunk, &mut tx).await?;
+            for (chunk, pii) in students.chunks(batch_size).zip(sealed.chunks(batch_size)) {
+                let count = insert_batch(chunk, pii, &mut tx).await?;
                 total_inserted += count;
             }
 
@@ -331,12 +547,20 @@ cfg_if::cfg_if! {
             Ok(total_inserted)
         }
 
-        async fn bulk_insert_with_unnest(students: &[AddStudentRequest], tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<usize, sqlx::Error> {
-            let mut firstnames = Vec::new();
-            let mut lastnames = Vec::new();
+        // Name tokens as space separated hex, since UNNEST can't take an array of arrays
+        fn tokens_as_text(tokens: &Option<Vec<Vec<u8>>>) -> Option<String> {
+            tokens.as_ref().map(|tokens| {
+                tokens
+                    .iter()
+                    .map(|token| token.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+        }
+
+        async fn bulk_insert_with_unnest(students: &[AddStudentRequest], sealed: &[StoredPii], tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<usize, sqlx::Error> {
             let mut preferreds = Vec::new();
             let mut genders = Vec::new();
-            let mut dates_of_birth = Vec::new();
             let mut student_ids = Vec::new();
             let mut esls = Vec::new();
             let mut grades = Vec::new();
@@ -348,15 +572,10 @@ cfg_if::cfg_if! {
             let mut gts = Vec::new();
             let mut interventions = Vec::new();
             let mut eye_glasses = Vec::new();
-            let mut notes = Vec::new();
-            let mut pins = Vec::new();
 
             for student in students {
-                firstnames.push(&student.firstname);
-                lastnames.push(&student.lastname);
                 preferreds.push(&student.preferred);
                 genders.push(student.gender.to_string());
-                dates_of_birth.push(student.date_of_birth);
                 student_ids.push(student.student_id);
                 esls.push(student.esl.to_string());
                 grades.push(student.current_grade_level.to_string());
@@ -368,27 +587,39 @@ cfg_if::cfg_if! {
                 gts.push(student.gt);
                 interventions.push(student.intervention.as_ref().map(|i| i.to_string()));
                 eye_glasses.push(student.eye_glasses);
-                notes.push(&student.notes);
-                pins.push(student.pin);
             }
 
             let query = r#"
                 INSERT INTO students (
-                    firstname, lastname, preferred, gender, date_of_birth, student_id, 
-                    esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, 
-                    intervention, eye_glasses, notes, pin
+                    preferred, gender, student_id, esl, current_grade_level, teacher, iep, bip, student_504,
+                    readplan, gt, intervention, eye_glasses,
+                    firstname, lastname, pin, date_of_birth, notes, pii_key_id,
+                    firstname_enc, lastname_enc, pin_enc, date_of_birth_enc, notes_enc,
+                    firstname_bidx, lastname_bidx, date_of_birth_bidx, name_tokens
                 )
-                SELECT * FROM UNNEST(
-                    $1::text[], $2::text[], $3::text[], $4::gender_enum[], $5::date[], $6::int4[],
-                    $7::esl_enum[], $8::grade_enum[], $9::text[], $10::bool[], $11::bool[], $12::bool[],
-                    $13::bool[], $14::bool[], $15::intervention_enum[], $16::bool[], $17::text[], $18::int4[]
+                SELECT
+                    u.preferred, u.gender, u.student_id, u.esl, u.current_grade_level, u.teacher, u.iep, u.bip, u.student_504,
+                    u.readplan, u.gt, u.intervention, u.eye_glasses,
+                    u.firstname, u.lastname, u.pin, u.date_of_birth, u.notes, u.pii_key_id,
+                    u.firstname_enc, u.lastname_enc, u.pin_enc, u.date_of_birth_enc, u.notes_enc,
+                    u.firstname_bidx, u.lastname_bidx, u.date_of_birth_bidx,
+                    (SELECT ARRAY_AGG(DECODE(t, 'hex')) FROM UNNEST(STRING_TO_ARRAY(u.name_tokens, ' ')) t)
+                FROM UNNEST(
+                    $1::text[], $2::gender_enum[], $3::int4[], $4::esl_enum[], $5::grade_enum[], $6::text[],
+                    $7::bool[], $8::bool[], $9::bool[], $10::bool[], $11::bool[], $12::intervention_enum[], $13::bool[],
+                    $14::text[], $15::text[], $16::int4[], $17::date[], $18::text[], $19::int4[],
+                    $20::bytea[], $21::bytea[], $22::bytea[], $23::bytea[], $24::bytea[],
+                    $25::bytea[], $26::bytea[], $27::bytea[], $28::text[]
+                ) AS u(
+                    preferred, gender, student_id, esl, current_grade_level, teacher, iep, bip, student_504,
+                    readplan, gt, intervention, eye_glasses,
+                    firstname, lastname, pin, date_of_birth, notes, pii_key_id,
+                    firstname_enc, lastname_enc, pin_enc, date_of_birth_enc, notes_enc,
+                    firstname_bidx, lastname_bidx, date_of_birth_bidx, name_tokens
                 )
                 ON CONFLICT (student_id) DO UPDATE SET
-                    firstname = EXCLUDED.firstname,
-                    lastname = EXCLUDED.lastname,
                     preferred = EXCLUDED.preferred,
                     gender = EXCLUDED.gender,
-                    date_of_birth = EXCLUDED.date_of_birth,
                     esl = EXCLUDED.esl,
                     current_grade_level = EXCLUDED.current_grade_level,
                     teacher = EXCLUDED.teacher,
@@ -399,16 +630,27 @@ cfg_if::cfg_if! {
                     gt = EXCLUDED.gt,
                     intervention = EXCLUDED.intervention,
                     eye_glasses = EXCLUDED.eye_glasses,
+                    firstname = EXCLUDED.firstname,
+                    lastname = EXCLUDED.lastname,
+                    pin = EXCLUDED.pin,
+                    date_of_birth = EXCLUDED.date_of_birth,
                     notes = EXCLUDED.notes,
-                    pin = EXCLUDED.pin
+                    pii_key_id = EXCLUDED.pii_key_id,
+                    firstname_enc = EXCLUDED.firstname_enc,
+                    lastname_enc = EXCLUDED.lastname_enc,
+                    pin_enc = EXCLUDED.pin_enc,
+                    date_of_birth_enc = EXCLUDED.date_of_birth_enc,
+                    notes_enc = EXCLUDED.notes_enc,
+                    firstname_bidx = EXCLUDED.firstname_bidx,
+                    lastname_bidx = EXCLUDED.lastname_bidx,
+                    date_of_birth_bidx = EXCLUDED.date_of_birth_bidx,
+                    name_tokens = EXCLUDED.name_tokens
             "#;
 
+            let pii_column = |field: fn(&StoredPii) -> Option<Vec<u8>>| sealed.iter().map(field).collect::<Vec<_>>();
             let result = sqlx::query(query)
-                .bind(&firstnames)
-                .bind(&lastnames)
                 .bind(&preferreds)
                 .bind(&genders)
-                .bind(&dates_of_birth)
                 .bind(&student_ids)
                 .bind(&esls)
                 .bind(&grades)
@@ -420,25 +662,36 @@ cfg_if::cfg_if! {
                 .bind(&gts)
                 .bind(&interventions)
                 .bind(&eye_glasses)
-                .bind(&notes)
-                .bind(&pins)
+                .bind(sealed.iter().map(|pii| pii.plaintext.firstname.clone()).collect::<Vec<_>>())
+                .bind(sealed.iter().map(|pii| pii.plaintext.lastname.clone()).collect::<Vec<_>>())
+                .bind(sealed.iter().map(|pii| pii.plaintext.pin).collect::<Vec<_>>())
+                .bind(sealed.iter().map(|pii| pii.plaintext.date_of_birth).collect::<Vec<_>>())
+                .bind(sealed.iter().map(|pii| pii.plaintext.notes.clone()).collect::<Vec<_>>())
+                .bind(sealed.iter().map(|pii| pii.key_id).collect::<Vec<_>>())
+                .bind(pii_column(|pii| pii.firstname_enc.clone()))
+                .bind(pii_column(|pii| pii.lastname_enc.clone()))
+                .bind(pii_column(|pii| pii.pin_enc.clone()))
+                .bind(pii_column(|pii| pii.date_of_birth_enc.clone()))
+                .bind(pii_column(|pii| pii.notes_enc.clone()))
+                .bind(pii_column(|pii| pii.firstname_bidx.clone()))
+                .bind(pii_column(|pii| pii.lastname_bidx.clone()))
+                .bind(pii_column(|pii| pii.date_of_birth_bidx.clone()))
+                .bind(sealed.iter().map(|pii| tokens_as_text(&pii.name_tokens)).collect::<Vec<_>>())
                 .execute(&mut **tx)
                 .await?;
 
             Ok(result.rows_affected() as usize)
         }
 
-        async fn insert_batch(students: &[AddStudentRequest], tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<usize, sqlx::Error> {
-            let mut query_builder = sqlx::QueryBuilder::new(
-                "INSERT INTO students (firstname, lastname, preferred, gender, date_of_birth, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, notes, pin) "
-            );
+        async fn insert_batch(students: &[AddStudentRequest], sealed: &[StoredPii], tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<usize, sqlx::Error> {
+            let mut query_builder = sqlx::QueryBuilder::new(format!(
+                "INSERT INTO students (preferred, gender, student_id, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses, {}) ",
+                PII_WRITE_COLUMNS
+            ));
 
-            query_builder.push_values(students, |mut b, student| {
-                b.push_bind(&student.firstname)
-                 .push_bind(&student.lastname)
-                 .push_bind(&student.preferred)
+            query_builder.push_values(students.iter().zip(sealed), |mut b, (student, pii)| {
+                b.push_bind(&student.preferred)
                  .push_bind(student.gender.to_string())
-                 .push_bind(student.date_of_birth)
                  .push_bind(student.student_id)
                  .push_bind(student.esl.to_string())
                  .push_bind(student.current_grade_level.to_string())
@@ -450,12 +703,25 @@ cfg_if::cfg_if! {
                  .push_bind(student.gt)
                  .push_bind(&student.intervention)
                  .push_bind(student.eye_glasses)
-                 .push_bind(&student.notes)
-                 .push_bind(student.pin);
+                 .push_bind(&pii.plaintext.firstname)
+                 .push_bind(&pii.plaintext.lastname)
+                 .push_bind(pii.plaintext.pin)
+                 .push_bind(pii.plaintext.date_of_birth)
+                 .push_bind(&pii.plaintext.notes)
+                 .push_bind(pii.key_id)
+                 .push_bind(&pii.firstname_enc)
+                 .push_bind(&pii.lastname_enc)
+                 .push_bind(&pii.pin_enc)
+                 .push_bind(&pii.date_of_birth_enc)
+                 .push_bind(&pii.notes_enc)
+                 .push_bind(&pii.firstname_bidx)
+                 .push_bind(&pii.lastname_bidx)
+                 .push_bind(&pii.date_of_birth_bidx)
+                 .push_bind(&pii.name_tokens);
             });
 
             // Add conflict resolution if needed
-            query_builder.push(" ON CONFLICT (student_id) DO UPDATE SET firstname = EXCLUDED.firstname, lastname = EXCLUDED.lastname");
+            query_builder.push(" ON CONFLICT (student_id) DO UPDATE SET firstname = EXCLUDED.firstname, lastname = EXCLUDED.lastname, pii_key_id = EXCLUDED.pii_key_id, firstname_enc = EXCLUDED.firstname_enc, lastname_enc = EXCLUDED.lastname_enc, firstname_bidx = EXCLUDED.firstname_bidx, lastname_bidx = EXCLUDED.lastname_bidx, name_tokens = EXCLUDED.name_tokens");
 
             let query = query_builder.build();
             let result = query.execute(&mut **tx).await?;
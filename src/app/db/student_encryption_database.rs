@@ -0,0 +1,356 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::student_encryption::{KeyRotationReport, StudentDataKeyInfo, StudentEncryptionStatus};
+        use crate::app::services::student_encryption::{
+            cache_keyring, cached_keyring, generate_key, key_provider, open_pii, DataKey, KeyProvider, PiiKeyring, StoredPii, StudentPii,
+        };
+        use leptos::ServerFnError;
+        use sqlx::postgres::{PgArguments, PgRow};
+        use sqlx::query::Query;
+        use sqlx::{PgConnection, PgPool, Postgres, Row};
+        use std::sync::Arc;
+
+        // Only one server creates, rotates or re-wraps data keys at a time
+        const DATA_KEY_LOCK: i64 = 0x7374_7564_5f6b_6579;
+
+        pub const RESEAL_BATCH_SIZE: i64 = 500;
+
+        // PII columns read back from students, see stored_pii_from_row
+        pub const PII_COLUMNS: &str = "firstname, lastname, pin, date_of_birth, notes, pii_key_id, firstname_enc, lastname_enc, pin_enc, date_of_birth_enc, notes_enc";
+
+        // PII columns written by bind_pii, in bind order
+        pub const PII_WRITE_COLUMNS: &str = "firstname, lastname, pin, date_of_birth, notes, pii_key_id, firstname_enc, lastname_enc, pin_enc, date_of_birth_enc, notes_enc, firstname_bidx, lastname_bidx, date_of_birth_bidx, name_tokens";
+        pub const PII_WRITE_COLUMN_COUNT: usize = 15;
+
+        // Rows holding plaintext PII or sealed under a key other than $1
+        const NEEDS_RESEAL: &str = "(pii_key_id IS DISTINCT FROM $1 OR firstname IS NOT NULL OR lastname IS NOT NULL OR pin IS NOT NULL OR date_of_birth IS NOT NULL OR notes IS NOT NULL)";
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn configured_provider() -> Result<Option<Arc<dyn KeyProvider>>, ServerFnError> {
+            key_provider().map_err(ServerFnError::new)
+        }
+
+        pub fn stored_pii_from_row(row: &PgRow) -> StoredPii {
+            StoredPii {
+                key_id: row.get("pii_key_id"),
+                plaintext: StudentPii {
+                    firstname: row.get("firstname"),
+                    lastname: row.get("lastname"),
+                    pin: row.get("pin"),
+                    date_of_birth: row.get("date_of_birth"),
+                    notes: row.get("notes"),
+                },
+                firstname_enc: row.get("firstname_enc"),
+                lastname_enc: row.get("lastname_enc"),
+                pin_enc: row.get("pin_enc"),
+                date_of_birth_enc: row.get("date_of_birth_enc"),
+                notes_enc: row.get("notes_enc"),
+                ..Default::default()
+            }
+        }
+
+        pub fn bind_pii<'q>(query: Query<'q, Postgres, PgArguments>, pii: &'q StoredPii) -> Query<'q, Postgres, PgArguments> {
+            query
+                .bind(&pii.plaintext.firstname)
+                .bind(&pii.plaintext.lastname)
+                .bind(pii.plaintext.pin)
+                .bind(pii.plaintext.date_of_birth)
+                .bind(&pii.plaintext.notes)
+                .bind(pii.key_id)
+                .bind(&pii.firstname_enc)
+                .bind(&pii.lastname_enc)
+                .bind(&pii.pin_enc)
+                .bind(&pii.date_of_birth_enc)
+                .bind(&pii.notes_enc)
+                .bind(&pii.firstname_bidx)
+                .bind(&pii.lastname_bidx)
+                .bind(&pii.date_of_birth_bidx)
+                .bind(&pii.name_tokens)
+        }
+
+        async fn load_keyring(pool: &PgPool, provider: &dyn KeyProvider) -> Result<Option<PiiKeyring>, ServerFnError> {
+            let rows = sqlx::query("SELECT id, master_key_id, wrapped_key, wrapped_index_key, active FROM student_data_keys")
+                .fetch_all(pool)
+                .await
+                .map_err(db_error)?;
+
+            let mut active = None;
+            let mut keys = Vec::new();
+            for row in rows {
+                let key = DataKey::unwrap(
+                    provider,
+                    row.get("id"),
+                    &row.get::<String, _>("master_key_id"),
+                    &row.get::<Vec<u8>, _>("wrapped_key"),
+                    &row.get::<Vec<u8>, _>("wrapped_index_key"),
+                )
+                .map_err(ServerFnError::new)?;
+                if row.get::<bool, _>("active") {
+                    active = Some(key.id);
+                }
+                keys.push(key);
+            }
+
+            match active {
+                Some(active) => PiiKeyring::new(keys, active).map(Some).map_err(ServerFnError::new),
+                None => Ok(None),
+            }
+        }
+
+        // Generates a data key and makes it the one new writes use
+        async fn insert_active_key(conn: &mut PgConnection, provider: &dyn KeyProvider) -> Result<i32, ServerFnError> {
+            let wrapped_key = provider.wrap(&generate_key()).map_err(ServerFnError::new)?;
+            let wrapped_index_key = provider.wrap(&generate_key()).map_err(ServerFnError::new)?;
+
+            sqlx::query("UPDATE student_data_keys SET active = FALSE WHERE active")
+                .execute(&mut *conn)
+                .await
+                .map_err(db_error)?;
+            sqlx::query_scalar(
+                "INSERT INTO student_data_keys (master_key_id, wrapped_key, wrapped_index_key, active)
+                 VALUES ($1, $2, $3, TRUE) RETURNING id"
+            )
+            .bind(provider.active_key_id())
+            .bind(wrapped_key)
+            .bind(wrapped_index_key)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error)
+        }
+
+        // Keys for reading and writing student PII, or None when encryption is
+        // not configured. Creates the first data key once a provider is set up.
+        pub async fn student_keyring(pool: &PgPool) -> Result<Option<Arc<PiiKeyring>>, ServerFnError> {
+            let Some(provider) = configured_provider()? else {
+                return Ok(None);
+            };
+            if let Some(keyring) = cached_keyring() {
+                return Ok(Some(keyring));
+            }
+
+            let keyring = match load_keyring(pool, provider.as_ref()).await? {
+                Some(keyring) => keyring,
+                None => {
+                    let mut tx = pool.begin().await.map_err(db_error)?;
+                    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                        .bind(DATA_KEY_LOCK)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM student_data_keys WHERE active)")
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(db_error)?;
+                    if !exists {
+                        let id = insert_active_key(&mut tx, provider.as_ref()).await?;
+                        log::info!("Created student data key {} under master key {}", id, provider.active_key_id());
+                    }
+                    tx.commit().await.map_err(db_error)?;
+
+                    load_keyring(pool, provider.as_ref())
+                        .await?
+                        .ok_or_else(|| ServerFnError::new("No active student data key"))?
+                }
+            };
+
+            let keyring = Arc::new(keyring);
+            cache_keyring(Some(keyring.clone()));
+            Ok(Some(keyring))
+        }
+
+        // Seals each row's PII under the keyring's active key
+        async fn seal_rows(conn: &mut PgConnection, keyring: &PiiKeyring, rows: &[PgRow]) -> Result<(), ServerFnError> {
+            let statement = format!(
+                "UPDATE students SET ({}) = ({}) WHERE student_id = ${}",
+                PII_WRITE_COLUMNS,
+                (1..=PII_WRITE_COLUMN_COUNT).map(|n| format!("${}", n)).collect::<Vec<_>>().join(", "),
+                PII_WRITE_COLUMN_COUNT + 1
+            );
+            for row in rows {
+                let student_id: i32 = row.get("student_id");
+                let pii = open_pii(Some(keyring), &stored_pii_from_row(row))
+                    .map_err(|e| ServerFnError::new(format!("Student {}: {}", student_id, e)))?;
+                let sealed = keyring.seal(&pii).map_err(ServerFnError::new)?;
+                bind_pii(sqlx::query(&statement), &sealed)
+                    .bind(student_id)
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(db_error)?;
+            }
+            Ok(())
+        }
+
+        // Re-encrypts up to `limit` rows that hold plaintext PII or an older
+        // key's ciphertext, under the keyring's active key
+        pub async fn reseal_students(conn: &mut PgConnection, keyring: &PiiKeyring, limit: i64) -> Result<i64, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT student_id, {} FROM students WHERE {} ORDER BY student_id LIMIT $2 FOR UPDATE SKIP LOCKED",
+                PII_COLUMNS, NEEDS_RESEAL
+            ))
+            .bind(keyring.active_key_id())
+            .bind(limit)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            seal_rows(conn, keyring, &rows).await?;
+            Ok(rows.len() as i64)
+        }
+
+        // The same for one student, such as a record put back from a snapshot
+        pub async fn reseal_student(conn: &mut PgConnection, keyring: &PiiKeyring, student_id: i32) -> Result<(), ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT student_id, {} FROM students WHERE {} AND student_id = $2 FOR UPDATE",
+                PII_COLUMNS, NEEDS_RESEAL
+            ))
+            .bind(keyring.active_key_id())
+            .bind(student_id)
+            .fetch_all(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            seal_rows(conn, keyring, &rows).await
+        }
+
+        // Moves every student onto the active key in batches, then deletes data
+        // keys nothing is encrypted with any more
+        pub async fn reseal_all_students(pool: &PgPool) -> Result<KeyRotationReport, ServerFnError> {
+            let keyring = student_keyring(pool)
+                .await?
+                .ok_or_else(|| ServerFnError::new("Student PII encryption is not configured"))?;
+
+            let mut report = KeyRotationReport::default();
+            loop {
+                let mut tx = pool.begin().await.map_err(db_error)?;
+                let resealed = reseal_students(&mut tx, &keyring, RESEAL_BATCH_SIZE).await?;
+                tx.commit().await.map_err(db_error)?;
+                report.students_resealed += resealed;
+                if resealed < RESEAL_BATCH_SIZE {
+                    break;
+                }
+            }
+
+            report.keys_removed = sqlx::query(
+                "DELETE FROM student_data_keys k WHERE NOT active
+                 AND NOT EXISTS (SELECT 1 FROM students s WHERE s.pii_key_id = k.id)"
+            )
+            .execute(pool)
+            .await
+            .map_err(db_error)?
+            .rows_affected() as i64;
+            if report.keys_removed > 0 {
+                cache_keyring(None);
+            }
+            Ok(report)
+        }
+
+        // Replaces the active data key; existing rows move over on the next reseal
+        pub async fn rotate_student_data_key(pool: &PgPool) -> Result<i32, ServerFnError> {
+            let provider = configured_provider()?
+                .ok_or_else(|| ServerFnError::new("Student PII encryption is not configured"))?;
+
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(DATA_KEY_LOCK)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            let id = insert_active_key(&mut tx, provider.as_ref()).await?;
+            tx.commit().await.map_err(db_error)?;
+
+            cache_keyring(None);
+            Ok(id)
+        }
+
+        // Re-wraps every data key under the provider's active master key, so
+        // older master keys can be taken out of the provider
+        pub async fn rewrap_student_data_keys(pool: &PgPool) -> Result<i64, ServerFnError> {
+            let provider = configured_provider()?
+                .ok_or_else(|| ServerFnError::new("Student PII encryption is not configured"))?;
+
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(DATA_KEY_LOCK)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            let rows = sqlx::query("SELECT id, master_key_id, wrapped_key, wrapped_index_key FROM student_data_keys WHERE master_key_id <> $1")
+                .bind(provider.active_key_id())
+                .fetch_all(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            for row in &rows {
+                let master_key_id: String = row.get("master_key_id");
+                let rewrap = |column: &str| -> Result<Vec<u8>, ServerFnError> {
+                    let key = provider
+                        .unwrap(&master_key_id, &row.get::<Vec<u8>, _>(column))
+                        .map_err(ServerFnError::new)?;
+                    provider.wrap(&key).map_err(ServerFnError::new)
+                };
+                sqlx::query(
+                    "UPDATE student_data_keys SET master_key_id = $1, wrapped_key = $2, wrapped_index_key = $3, rewrapped_at = NOW() WHERE id = $4"
+                )
+                .bind(provider.active_key_id())
+                .bind(rewrap("wrapped_key")?)
+                .bind(rewrap("wrapped_index_key")?)
+                .bind(row.get::<i32, _>("id"))
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            }
+            tx.commit().await.map_err(db_error)?;
+
+            Ok(rows.len() as i64)
+        }
+
+        pub async fn get_student_encryption_status(pool: &PgPool) -> Result<StudentEncryptionStatus, ServerFnError> {
+            let mut status = StudentEncryptionStatus::default();
+            match key_provider() {
+                Ok(Some(provider)) => {
+                    status.provider = Some(provider.name().to_string());
+                    status.active_master_key_id = Some(provider.active_key_id().to_string());
+                }
+                Ok(None) => {}
+                Err(e) => status.provider_error = Some(e),
+            }
+
+            status.keys = sqlx::query(
+                "SELECT k.id, k.master_key_id, k.active, k.created_at, k.rewrapped_at,
+                        (SELECT COUNT(*) FROM students s WHERE s.pii_key_id = k.id) AS students
+                 FROM student_data_keys k ORDER BY k.id DESC"
+            )
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|row| StudentDataKeyInfo {
+                id: row.get("id"),
+                master_key_id: row.get("master_key_id"),
+                active: row.get("active"),
+                students: row.get("students"),
+                created_at: row.get("created_at"),
+                rewrapped_at: row.get("rewrapped_at"),
+            })
+            .collect();
+
+            let row = sqlx::query(
+                "SELECT
+                    COUNT(*) FILTER (WHERE pii_key_id IS NULL) AS plaintext,
+                    COUNT(*) FILTER (WHERE pii_key_id IS NOT NULL AND pii_key_id NOT IN (SELECT id FROM student_data_keys WHERE active)) AS stale
+                 FROM students"
+            )
+            .fetch_one(pool)
+            .await
+            .map_err(db_error)?;
+            status.plaintext_students = row.get("plaintext");
+            status.stale_students = row.get("stale");
+
+            Ok(status)
+        }
+    }
+}
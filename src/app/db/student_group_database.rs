@@ -0,0 +1,223 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::student_database::caseload_filter;
+        use crate::app::models::permission::DataScope;
+        use crate::app::models::student_group::{GroupMember, GroupPurpose, GroupTestSummary, SaveStudentGroupRequest, StudentGroup};
+        use chrono::NaiveDate;
+        use leptos::ServerFnError;
+        use sqlx::postgres::PgRow;
+        use sqlx::{PgPool, Row};
+        use std::collections::HashMap;
+        use std::str::FromStr;
+
+        const GROUP_COLUMNS: &str = "g.id, g.name, g.owner_id, NULLIF(TRIM(COALESCE(e.firstname, '') || ' ' || COALESCE(e.lastname, '')), '') AS owner_name, g.purpose, g.description, g.active";
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn group_from_row(row: &PgRow) -> Result<StudentGroup, ServerFnError> {
+            Ok(StudentGroup {
+                id: row.get("id"),
+                name: row.get("name"),
+                owner_id: row.get("owner_id"),
+                owner_name: row.get("owner_name"),
+                purpose: GroupPurpose::from_str(row.get::<&str, _>("purpose")).map_err(ServerFnError::new)?,
+                description: row.get("description"),
+                active: row.get("active"),
+                members: Vec::new(),
+            })
+        }
+
+        // Groups visible within `scope`: all of them when unrestricted,
+        // otherwise those the teacher owns or that hold one of their students.
+        // Owners see every member; other teachers only their own students.
+        pub async fn list_student_groups(pool: &PgPool, scope: &DataScope) -> Result<Vec<StudentGroup>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT {}
+                 FROM student_groups g
+                 LEFT JOIN employees e ON e.id = g.owner_id
+                 WHERE NOT $1 OR g.owner_id = $2
+                    OR EXISTS (SELECT 1 FROM student_group_members gm WHERE gm.group_id = g.id AND {})
+                 ORDER BY g.active DESC, g.name, g.id",
+                GROUP_COLUMNS,
+                caseload_filter("gm.student_id", 1, 2)
+            ))
+            .bind(scope.is_restricted())
+            .bind(scope.teacher_id())
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            let mut groups = rows.iter().map(group_from_row).collect::<Result<Vec<_>, _>>()?;
+            attach_members(pool, &mut groups, scope).await?;
+            Ok(groups)
+        }
+
+        pub async fn get_student_group(pool: &PgPool, id: i32, scope: &DataScope) -> Result<Option<StudentGroup>, ServerFnError> {
+            Ok(list_student_groups(pool, scope).await?.into_iter().find(|group| group.id == id))
+        }
+
+        async fn attach_members(pool: &PgPool, groups: &mut [StudentGroup], scope: &DataScope) -> Result<(), ServerFnError> {
+            let group_ids: Vec<i32> = groups.iter().map(|group| group.id).collect();
+            let rows = sqlx::query(&format!(
+                "SELECT gm.group_id, gm.student_id, gm.start_date, gm.end_date
+                 FROM student_group_members gm
+                 JOIN student_groups g ON g.id = gm.group_id
+                 WHERE gm.group_id = ANY($1) AND (g.owner_id = $3 OR {})
+                 ORDER BY gm.start_date, gm.student_id",
+                caseload_filter("gm.student_id", 2, 3)
+            ))
+            .bind(&group_ids)
+            .bind(scope.is_restricted())
+            .bind(scope.teacher_id())
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            let mut members: HashMap<i32, Vec<GroupMember>> = HashMap::new();
+            for row in &rows {
+                members.entry(row.get("group_id")).or_default().push(GroupMember {
+                    student_id: row.get("student_id"),
+                    start_date: row.get("start_date"),
+                    end_date: row.get("end_date"),
+                });
+            }
+            for group in groups.iter_mut() {
+                group.members = members.remove(&group.id).unwrap_or_default();
+            }
+            Ok(())
+        }
+
+        // Creates or updates the group and replaces its membership list
+        pub async fn save_student_group(pool: &PgPool, request: &SaveStudentGroupRequest) -> Result<StudentGroup, ServerFnError> {
+            let description = request
+                .description
+                .as_deref()
+                .map(str::trim)
+                .filter(|description| !description.is_empty());
+            let mut tx = pool.begin().await.map_err(db_error)?;
+
+            let query = match request.id {
+                Some(_) => sqlx::query(
+                    "UPDATE student_groups
+                     SET name = $1, owner_id = $2, purpose = $3, description = $4, active = $5, updated_at = NOW()
+                     WHERE id = $6
+                     RETURNING id",
+                ),
+                None => sqlx::query(
+                    "INSERT INTO student_groups (name, owner_id, purpose, description, active)
+                     VALUES ($1, $2, $3, $4, $5)
+                     RETURNING id",
+                ),
+            };
+            let mut query = query
+                .bind(request.name.trim())
+                .bind(request.owner_id)
+                .bind(request.purpose.as_str())
+                .bind(description)
+                .bind(request.active);
+            if let Some(id) = request.id {
+                query = query.bind(id);
+            }
+            let id: i32 = query
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServerFnError::new("Group not found"))?
+                .get("id");
+
+            sqlx::query("DELETE FROM student_group_members WHERE group_id = $1")
+                .bind(id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            let student_ids: Vec<i32> = request.members.iter().map(|member| member.student_id).collect();
+            let start_dates: Vec<NaiveDate> = request.members.iter().map(|member| member.start_date).collect();
+            let end_dates: Vec<Option<NaiveDate>> = request.members.iter().map(|member| member.end_date).collect();
+            sqlx::query(
+                "INSERT INTO student_group_members (group_id, student_id, start_date, end_date)
+                 SELECT $1, u.student_id, u.start_date, u.end_date
+                 FROM UNNEST($2::INT[], $3::DATE[], $4::DATE[]) AS u(student_id, start_date, end_date)",
+            )
+            .bind(id)
+            .bind(&student_ids)
+            .bind(&start_dates)
+            .bind(&end_dates)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| match &e {
+                sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+                    ServerFnError::new("One of the group's students no longer exists")
+                }
+                _ => db_error(e),
+            })?;
+
+            tx.commit().await.map_err(db_error)?;
+
+            get_student_group(pool, id, &DataScope::Unrestricted)
+                .await?
+                .ok_or_else(|| ServerFnError::new("Group not found"))
+        }
+
+        // Deletes the group and its membership history
+        pub async fn delete_student_group(pool: &PgPool, id: i32) -> Result<StudentGroup, ServerFnError> {
+            let group = get_student_group(pool, id, &DataScope::Unrestricted)
+                .await?
+                .ok_or_else(|| ServerFnError::new("Group not found"))?;
+
+            sqlx::query("DELETE FROM student_groups WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+                .map_err(db_error)?;
+
+            Ok(group)
+        }
+
+        // Per-test results of the group's members, counting each member's
+        // latest attempt taken while they were in the group. Owners see every
+        // member's results; other teachers only their own students'.
+        pub async fn get_group_test_summaries(pool: &PgPool, group_id: i32, scope: &DataScope) -> Result<Vec<GroupTestSummary>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "WITH latest AS (
+                     SELECT DISTINCT ON (s.student_id, s.test_id)
+                            s.student_id, s.test_id,
+                            (SELECT COALESCE(SUM(points), 0) FROM UNNEST(s.test_scores) AS points) AS total
+                     FROM scores s
+                     JOIN student_group_members gm ON gm.student_id = s.student_id AND gm.group_id = $1
+                     JOIN student_groups g ON g.id = gm.group_id
+                     WHERE s.date_administered::date >= gm.start_date
+                       AND (gm.end_date IS NULL OR s.date_administered::date <= gm.end_date)
+                       AND (g.owner_id = $3 OR {})
+                     ORDER BY s.student_id, s.test_id, s.date_administered DESC, s.attempt DESC
+                 )
+                 SELECT t.test_id::text AS test_id, t.name, t.score AS max_score,
+                        COUNT(*) AS students_tested, AVG(latest.total)::float8 AS average_score
+                 FROM latest
+                 JOIN tests t ON t.test_id = latest.test_id
+                 GROUP BY t.test_id, t.name, t.score
+                 ORDER BY t.name",
+                caseload_filter("s.student_id", 2, 3)
+            ))
+            .bind(group_id)
+            .bind(scope.is_restricted())
+            .bind(scope.teacher_id())
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            Ok(rows
+                .iter()
+                .map(|row| GroupTestSummary {
+                    test_id: row.get("test_id"),
+                    test_name: row.get("name"),
+                    max_score: row.get("max_score"),
+                    students_tested: row.get("students_tested"),
+                    average_score: row.get("average_score"),
+                })
+                .collect())
+        }
+    }
+}
@@ -0,0 +1,124 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::student_history::StudentVersion;
+        use chrono::{DateTime, Utc};
+        use leptos::ServerFnError;
+        use sqlx::postgres::PgRow;
+        use sqlx::{PgConnection, PgPool, Row};
+
+        // Non-PII columns of students that are versioned
+        const VERSIONED_COLUMNS: &str = "preferred, gender, esl, current_grade_level, teacher, iep, bip, student_504, readplan, gt, intervention, eye_glasses";
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn version_from_row(row: &PgRow) -> StudentVersion {
+            StudentVersion {
+                student_id: row.get("student_id"),
+                valid_from: row.get("valid_from"),
+                valid_to: row.get("valid_to"),
+                changed_by: row.get("changed_by"),
+                preferred: row.get::<Option<String>, _>("preferred").unwrap_or_default(),
+                gender: row.get("gender"),
+                esl: row.get("esl"),
+                current_grade_level: row.get("current_grade_level"),
+                teacher: row.get::<Option<String>, _>("teacher").unwrap_or_default(),
+                iep: row.get::<Option<bool>, _>("iep").unwrap_or_default(),
+                bip: row.get::<Option<bool>, _>("bip").unwrap_or_default(),
+                student_504: row.get::<Option<bool>, _>("student_504").unwrap_or_default(),
+                readplan: row.get::<Option<bool>, _>("readplan").unwrap_or_default(),
+                gt: row.get::<Option<bool>, _>("gt").unwrap_or_default(),
+                intervention: row.get("intervention"),
+                eye_glasses: row.get::<Option<bool>, _>("eye_glasses").unwrap_or_default(),
+            }
+        }
+
+        // Closes the current version of each student whose versioned columns
+        // changed and opens a new one. Call after every write to students;
+        // students that did not change are left alone.
+        pub async fn record_student_versions(pool: &PgPool, student_ids: &[i32], changed_by: &str) -> Result<u64, ServerFnError> {
+            if student_ids.is_empty() {
+                return Ok(0);
+            }
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            let opened = write_student_versions(&mut tx, student_ids, changed_by).await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok(opened)
+        }
+
+        // record_student_versions inside the caller's transaction
+        pub async fn write_student_versions(conn: &mut PgConnection, student_ids: &[i32], changed_by: &str) -> Result<u64, ServerFnError> {
+            if student_ids.is_empty() {
+                return Ok(0);
+            }
+
+            sqlx::query("SELECT student_id FROM students WHERE student_id = ANY($1) ORDER BY student_id FOR UPDATE")
+                .bind(student_ids)
+                .execute(&mut *conn)
+                .await
+                .map_err(db_error)?;
+
+            sqlx::query(&format!(
+                "UPDATE student_versions v SET valid_to = NOW()
+                 FROM students s
+                 WHERE s.student_id = v.student_id AND v.student_id = ANY($1) AND v.valid_to IS NULL
+                   AND ({columns}) IS DISTINCT FROM ({current})",
+                columns = VERSIONED_COLUMNS.split(", ").map(|column| format!("v.{}", column)).collect::<Vec<_>>().join(", "),
+                current = VERSIONED_COLUMNS.split(", ").map(|column| format!("s.{}", column)).collect::<Vec<_>>().join(", "),
+            ))
+            .bind(student_ids)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            let opened = sqlx::query(&format!(
+                "INSERT INTO student_versions (student_id, valid_from, changed_by, {columns})
+                 SELECT student_id, NOW(), $2, {columns} FROM students s
+                 WHERE s.student_id = ANY($1)
+                   AND NOT EXISTS (SELECT 1 FROM student_versions v WHERE v.student_id = s.student_id AND v.valid_to IS NULL)",
+                columns = VERSIONED_COLUMNS
+            ))
+            .bind(student_ids)
+            .bind(changed_by)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?
+            .rows_affected();
+
+            Ok(opened)
+        }
+
+        // Every version of a student, oldest first
+        pub async fn list_student_versions(pool: &PgPool, student_id: i32) -> Result<Vec<StudentVersion>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT student_id, valid_from, valid_to, changed_by, {} FROM student_versions WHERE student_id = $1 ORDER BY valid_from, id",
+                VERSIONED_COLUMNS
+            ))
+            .bind(student_id)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            Ok(rows.iter().map(version_from_row).collect())
+        }
+
+        // The version in effect at `at`; None before history began
+        pub async fn student_version_as_of(pool: &PgPool, student_id: i32, at: DateTime<Utc>) -> Result<Option<StudentVersion>, ServerFnError> {
+            let row = sqlx::query(&format!(
+                "SELECT student_id, valid_from, valid_to, changed_by, {} FROM student_versions
+                 WHERE student_id = $1 AND valid_from <= $2 AND (valid_to IS NULL OR valid_to > $2)
+                 ORDER BY valid_from DESC, id DESC
+                 LIMIT 1",
+                VERSIONED_COLUMNS
+            ))
+            .bind(student_id)
+            .bind(at)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?;
+
+            Ok(row.as_ref().map(version_from_row))
+        }
+    }
+}
@@ -0,0 +1,470 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::audit_database::write_audit_event;
+        use crate::app::db::student_database::read_student;
+        use crate::app::db::student_encryption_database::{reseal_student, student_keyring};
+        use crate::app::db::student_history_database::write_student_versions;
+        use crate::app::db::student_protection_database::lock_unprotected;
+        use crate::app::models::audit::{AuditAction, AuditChange, AuditEntity};
+        use crate::app::models::student::Student;
+        use crate::app::models::student_merge::{MergeStudentsRequest, StudentMerge};
+        use crate::app::models::user::SessionUser;
+        use leptos::ServerFnError;
+        use sqlx::postgres::PgRow;
+        use sqlx::{PgConnection, PgPool, Row};
+
+        const MERGE_COLUMNS: &str = "id, survivor_id, merged_id,
+            CARDINALITY(moved_enrollment_ids) AS enrollments_moved,
+            jsonb_array_length(moved_scores) AS scores_moved,
+            CARDINALITY(moved_session_ids) AS sessions_moved,
+            reason, merged_by, merged_at, unmerged_by, unmerged_at";
+
+        // A student's rows in `table` as a JSONB array, for restoring later
+        async fn snapshot_rows(conn: &mut PgConnection, table: &str, student_id: i32) -> Result<serde_json::Value, ServerFnError> {
+            sqlx::query_scalar(&format!(
+                "SELECT COALESCE(jsonb_agg(to_jsonb(t)), '[]'::JSONB) FROM {} t WHERE student_id = $1",
+                table
+            ))
+            .bind(student_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error)
+        }
+
+        // Puts snapshot rows back exactly as they were
+        async fn restore_rows(conn: &mut PgConnection, table: &str, rows: serde_json::Value) -> Result<(), ServerFnError> {
+            sqlx::query(&format!(
+                "INSERT INTO {table} SELECT * FROM jsonb_populate_recordset(NULL::{table}, $1) ON CONFLICT DO NOTHING",
+                table = table
+            ))
+            .bind(rows)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+            Ok(())
+        }
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn merge_from_row(row: &PgRow) -> StudentMerge {
+            StudentMerge {
+                id: row.get("id"),
+                survivor_id: row.get("survivor_id"),
+                merged_id: row.get("merged_id"),
+                enrollments_moved: row.get("enrollments_moved"),
+                scores_moved: row.get("scores_moved"),
+                sessions_moved: row.get("sessions_moved"),
+                reason: row.get("reason"),
+                merged_by: row.get("merged_by"),
+                merged_at: row.get("merged_at"),
+                unmerged_by: row.get("unmerged_by"),
+                unmerged_at: row.get("unmerged_at"),
+            }
+        }
+
+        // Merging re-keys rows the protection engine also re-keys, so the two
+        // share a lock, and merges wait until real IDs are back
+        async fn lock_for_merge(conn: &mut PgConnection) -> Result<(), ServerFnError> {
+            lock_unprotected(conn, "Turn off student protections before merging or unmerging students").await
+        }
+
+        // Merges involving `student_id`, or every merge when None; newest first
+        pub async fn list_student_merges(pool: &PgPool, student_id: Option<i32>) -> Result<Vec<StudentMerge>, ServerFnError> {
+            let rows = sqlx::query(&format!(
+                "SELECT {} FROM student_merges
+                 WHERE $1::INT IS NULL OR survivor_id = $1 OR merged_id = $1
+                 ORDER BY merged_at DESC, id DESC",
+                MERGE_COLUMNS
+            ))
+            .bind(student_id)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error)?;
+
+            Ok(rows.iter().map(merge_from_row).collect())
+        }
+
+        // Moves the merged student's enrollments, scores and sessions to the
+        // survivor and deletes the merged student, in one transaction. Score
+        // attempts are renumbered after the survivor's own. Attribute values
+        // and group memberships move where the survivor has none of its own;
+        // the merged student's history and all three are kept on the merge.
+        // `merged_student` is that student as read beforehand, for the audit
+        // events written in the same transaction.
+        pub async fn merge_students(pool: &PgPool, request: &MergeStudentsRequest, merged_student: &Student, actor: &SessionUser) -> Result<StudentMerge, ServerFnError> {
+            let (survivor_id, merged_id) = (request.survivor_id, request.merged_id);
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_for_merge(&mut tx).await?;
+
+            let rows = sqlx::query(
+                "SELECT student_id, legal_hold, to_jsonb(s) AS snapshot FROM students s WHERE student_id IN ($1, $2) FOR UPDATE"
+            )
+            .bind(survivor_id)
+            .bind(merged_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let find = |id: i32| rows.iter().find(|row| row.get::<i32, _>("student_id") == id);
+            if find(survivor_id).is_none() {
+                return Err(ServerFnError::new(format!("Student {} not found", survivor_id)));
+            }
+            let merged = find(merged_id).ok_or_else(|| ServerFnError::new(format!("Student {} not found", merged_id)))?;
+            if merged.get::<bool, _>("legal_hold") {
+                return Err(ServerFnError::new(format!(
+                    "Student {} is on legal hold; merge the other record into it instead",
+                    merged_id
+                )));
+            }
+            let snapshot: serde_json::Value = merged.get("snapshot");
+
+            // Undoing that merge later would need this record back
+            let survives_earlier: Option<i32> = sqlx::query_scalar(
+                "SELECT id FROM student_merges WHERE survivor_id = $1 AND unmerged_at IS NULL LIMIT 1"
+            )
+            .bind(merged_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if let Some(earlier) = survives_earlier {
+                return Err(ServerFnError::new(format!(
+                    "Student {} kept the records of merge #{}; undo that merge first or keep this student instead",
+                    merged_id, earlier
+                )));
+            }
+
+            let versions = snapshot_rows(&mut tx, "student_versions", merged_id).await?;
+            let attribute_values = snapshot_rows(&mut tx, "student_attribute_values", merged_id).await?;
+            let group_memberships = snapshot_rows(&mut tx, "student_group_members", merged_id).await?;
+
+            let attribute_ids: Vec<i32> = sqlx::query_scalar(
+                "UPDATE student_attribute_values a SET student_id = $2, updated_at = NOW()
+                 WHERE a.student_id = $1
+                   AND NOT EXISTS (SELECT 1 FROM student_attribute_values x WHERE x.student_id = $2 AND x.attribute_id = a.attribute_id)
+                 RETURNING attribute_id"
+            )
+            .bind(merged_id)
+            .bind(survivor_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            let group_ids: Vec<i32> = sqlx::query_scalar(
+                "UPDATE student_group_members m SET student_id = $2
+                 WHERE m.student_id = $1
+                   AND NOT EXISTS (SELECT 1 FROM student_group_members x WHERE x.student_id = $2 AND x.group_id = m.group_id)
+                 RETURNING group_id"
+            )
+            .bind(merged_id)
+            .bind(survivor_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            let enrollment_ids: Vec<i32> = sqlx::query_scalar(
+                "UPDATE student_enrollments SET student_id = $2, updated_at = NOW() WHERE student_id = $1 RETURNING id"
+            )
+            .bind(merged_id)
+            .bind(survivor_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            let moved_scores: serde_json::Value = sqlx::query_scalar(
+                "WITH moved AS (
+                    SELECT s.test_id, s.test_variant, s.attempt,
+                           s.attempt + COALESCE((
+                               SELECT MAX(x.attempt) FROM scores x
+                               WHERE x.student_id = $2 AND x.test_id = s.test_id AND x.test_variant = s.test_variant
+                           ), 0) AS merged_attempt
+                    FROM scores s
+                    WHERE s.student_id = $1
+                 ), updated AS (
+                    UPDATE scores s SET student_id = $2, attempt = m.merged_attempt
+                    FROM moved m
+                    WHERE s.student_id = $1 AND s.test_id = m.test_id AND s.test_variant = m.test_variant AND s.attempt = m.attempt
+                    RETURNING s.test_id
+                 )
+                 SELECT COALESCE(jsonb_agg(to_jsonb(m)), '[]'::JSONB) FROM moved m"
+            )
+            .bind(merged_id)
+            .bind(survivor_id)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            let session_ids: Vec<uuid::Uuid> = sqlx::query_scalar(
+                "UPDATE websocket_sessions
+                 SET metadata = jsonb_set(metadata, '{student_id}',
+                     CASE jsonb_typeof(metadata->'student_id') WHEN 'number' THEN to_jsonb($2::INT) ELSE to_jsonb($2::TEXT) END)
+                 WHERE metadata->>'student_id' = $1::TEXT
+                 RETURNING id"
+            )
+            .bind(merged_id)
+            .bind(survivor_id)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            // Whatever still cascades is kept on the merge row
+            sqlx::query("DELETE FROM students WHERE student_id = $1")
+                .bind(merged_id)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            let row = sqlx::query(&format!(
+                "INSERT INTO student_merges (survivor_id, merged_id, merged_student, moved_enrollment_ids, moved_scores, moved_session_ids, reason, merged_by,
+                                             merged_versions, merged_attribute_values, merged_group_memberships, moved_attribute_ids, moved_group_ids)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                 RETURNING {}",
+                MERGE_COLUMNS
+            ))
+            .bind(survivor_id)
+            .bind(merged_id)
+            .bind(snapshot)
+            .bind(enrollment_ids)
+            .bind(moved_scores)
+            .bind(session_ids)
+            .bind(request.reason.as_deref().map(str::trim).filter(|reason| !reason.is_empty()))
+            .bind(&actor.username)
+            .bind(versions)
+            .bind(attribute_values)
+            .bind(group_memberships)
+            .bind(attribute_ids)
+            .bind(group_ids)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let merge = merge_from_row(&row);
+
+            write_audit_event(&mut tx, actor, AuditAction::Create, AuditEntity::StudentMerge, &merge.id.to_string(), AuditChange::created(&merge)).await?;
+            write_audit_event(
+                &mut tx,
+                actor,
+                AuditAction::Delete,
+                AuditEntity::Student,
+                &merged_id.to_string(),
+                AuditChange::deleted(merged_student).redacted(&["student_id"]),
+            )
+            .await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok(merge)
+        }
+
+        // Recreates the merged student as it was, with its history, attribute
+        // values and group memberships, and moves back whatever the merge
+        // moved that is still with the survivor. The restored student's
+        // version and the audit events are written in the same transaction.
+        pub async fn unmerge_students(pool: &PgPool, merge_id: i32, actor: &SessionUser) -> Result<StudentMerge, ServerFnError> {
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+            lock_for_merge(&mut tx).await?;
+
+            let merge = sqlx::query(&format!(
+                "SELECT {}, merged_student, moved_enrollment_ids, moved_scores, moved_session_ids,
+                        merged_versions, merged_attribute_values, merged_group_memberships, moved_attribute_ids, moved_group_ids
+                 FROM student_merges WHERE id = $1 FOR UPDATE",
+                MERGE_COLUMNS
+            ))
+            .bind(merge_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServerFnError::new("Merge not found"))?;
+            if merge.get::<Option<chrono::DateTime<chrono::Utc>>, _>("unmerged_at").is_some() {
+                return Err(ServerFnError::new("This merge has already been undone"));
+            }
+            let survivor_id: i32 = merge.get("survivor_id");
+            let merged_id: i32 = merge.get("merged_id");
+            let snapshot: serde_json::Value = merge.get("merged_student");
+
+            let in_use: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM students WHERE student_id = $1)")
+                .bind(merged_id)
+                .fetch_one(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            if in_use {
+                return Err(ServerFnError::new(format!("Student ID {} is in use again; nothing was changed", merged_id)));
+            }
+
+            // Only columns the snapshot has, so ones added since keep their defaults
+            let columns: Vec<String> = sqlx::query_scalar(
+                "SELECT column_name::TEXT FROM information_schema.columns
+                 WHERE table_schema = current_schema() AND table_name = 'students'
+                 ORDER BY ordinal_position"
+            )
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let columns = columns
+                .into_iter()
+                .filter(|column| snapshot.get(column).is_some())
+                .map(|column| format!("\"{}\"", column))
+                .collect::<Vec<_>>()
+                .join(", ");
+            sqlx::query(&format!(
+                "INSERT INTO students ({columns}) SELECT {columns} FROM jsonb_populate_record(NULL::students, $1)",
+                columns = columns
+            ))
+            .bind(&snapshot)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            // A snapshot put back by a protection restore holds plaintext PII
+            if let Some(keyring) = &keyring {
+                reseal_student(&mut tx, keyring, merged_id).await?;
+            }
+
+            // Moved rows the survivor still has unchanged go back; the merged
+            // student's own copies then fill in the rest
+            sqlx::query(
+                "DELETE FROM student_attribute_values a
+                 USING jsonb_populate_recordset(NULL::student_attribute_values, $3) m
+                 WHERE a.student_id = $1 AND a.attribute_id = ANY($2) AND a.attribute_id = m.attribute_id AND a.value = m.value"
+            )
+            .bind(survivor_id)
+            .bind(merge.get::<Vec<i32>, _>("moved_attribute_ids"))
+            .bind(merge.get::<serde_json::Value, _>("merged_attribute_values"))
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            sqlx::query(
+                "DELETE FROM student_group_members g
+                 USING jsonb_populate_recordset(NULL::student_group_members, $3) m
+                 WHERE g.student_id = $1 AND g.group_id = ANY($2) AND g.group_id = m.group_id
+                   AND g.start_date = m.start_date AND g.end_date IS NOT DISTINCT FROM m.end_date"
+            )
+            .bind(survivor_id)
+            .bind(merge.get::<Vec<i32>, _>("moved_group_ids"))
+            .bind(merge.get::<serde_json::Value, _>("merged_group_memberships"))
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            restore_rows(&mut tx, "student_versions", merge.get("merged_versions")).await?;
+            restore_rows(&mut tx, "student_attribute_values", merge.get("merged_attribute_values")).await?;
+            restore_rows(&mut tx, "student_group_members", merge.get("merged_group_memberships")).await?;
+
+            sqlx::query("UPDATE student_enrollments SET student_id = $2, updated_at = NOW() WHERE id = ANY($3) AND student_id = $1")
+                .bind(survivor_id)
+                .bind(merged_id)
+                .bind(merge.get::<Vec<i32>, _>("moved_enrollment_ids"))
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            sqlx::query(
+                "UPDATE scores s SET student_id = $2, attempt = m.attempt
+                 FROM jsonb_to_recordset($3) AS m(test_id UUID, test_variant INT, attempt INT, merged_attempt INT)
+                 WHERE s.student_id = $1 AND s.test_id = m.test_id AND s.test_variant = m.test_variant AND s.attempt = m.merged_attempt"
+            )
+            .bind(survivor_id)
+            .bind(merged_id)
+            .bind(merge.get::<serde_json::Value, _>("moved_scores"))
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            sqlx::query(
+                "UPDATE websocket_sessions
+                 SET metadata = jsonb_set(metadata, '{student_id}',
+                     CASE jsonb_typeof(metadata->'student_id') WHEN 'number' THEN to_jsonb($2::INT) ELSE to_jsonb($2::TEXT) END)
+                 WHERE id = ANY($3) AND metadata->>'student_id' = $1::TEXT"
+            )
+            .bind(survivor_id)
+            .bind(merged_id)
+            .bind(merge.get::<Vec<uuid::Uuid>, _>("moved_session_ids"))
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            // The record is back, so the merge keeps only which rows moved
+            let row = sqlx::query(&format!(
+                "UPDATE student_merges
+                 SET unmerged_by = $2, unmerged_at = NOW(), merged_student = jsonb_build_object('student_id', merged_id),
+                     merged_versions = '[]', merged_attribute_values = '[]', merged_group_memberships = '[]'
+                 WHERE id = $1 RETURNING {}",
+                MERGE_COLUMNS
+            ))
+            .bind(merge_id)
+            .bind(&actor.username)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let unmerged = merge_from_row(&row);
+
+            // History came back with the record; this only opens a version if
+            // the restored row differs from its last one
+            write_student_versions(&mut tx, &[merged_id], &actor.username).await?;
+            let restored = read_student(&mut tx, keyring.as_deref(), merged_id).await?;
+            write_audit_event(
+                &mut tx,
+                actor,
+                AuditAction::Update,
+                AuditEntity::StudentMerge,
+                &merge_id.to_string(),
+                AuditChange::updated(&merge_from_row(&merge), &unmerged),
+            )
+            .await?;
+            write_audit_event(
+                &mut tx,
+                actor,
+                AuditAction::Create,
+                AuditEntity::Student,
+                &merged_id.to_string(),
+                AuditChange::created(&restored).redacted(&["student_id"]),
+            )
+            .await?;
+            tx.commit().await.map_err(db_error)?;
+            Ok(unmerged)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use std::collections::BTreeSet;
+
+    // Table named by a migration statement that adds a cascading student key
+    fn cascading_table(statement: &str) -> Option<String> {
+        let statement = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !statement.contains("REFERENCES students(student_id)") || !statement.contains("ON DELETE CASCADE") {
+            return None;
+        }
+        let rest = statement
+            .split_once("CREATE TABLE ")
+            .or_else(|| statement.split_once("ALTER TABLE "))?
+            .1;
+        let rest = rest.strip_prefix("IF NOT EXISTS ").unwrap_or(rest);
+        rest.split_whitespace().next().map(str::to_string)
+    }
+
+    // Deleting the merged student cascades to every one of these tables, so
+    // a new one needs carrying over by merge_students and unmerge_students
+    #[test]
+    fn merges_carry_every_cascading_table() {
+        let carried = [
+            "scores",
+            "student_enrollments",
+            "student_versions",
+            "student_attribute_values",
+            "student_group_members",
+            // References the survivor, which is never deleted by a merge
+            "student_merges",
+        ];
+
+        let migrations = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations");
+        let mut cascading = BTreeSet::new();
+        for entry in std::fs::read_dir(migrations).unwrap() {
+            let sql = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            cascading.extend(sql.split(';').filter_map(cascading_table));
+        }
+
+        let missing: Vec<&String> = cascading.iter().filter(|table| !carried.contains(&table.as_str())).collect();
+        assert!(missing.is_empty(), "merges do not carry {:?}", missing);
+        assert!(cascading.contains("student_group_members"));
+    }
+}
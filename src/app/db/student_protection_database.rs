@@ -0,0 +1,494 @@
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::student_protection::{KdfParams, ProtectionProgress, ProtectionReport, ProtectionStage, SealedMapping, StudentIdMapping};
+        use crate::app::services::student_protection::{assign_app_ids, seal_mapping, set_progress};
+        use crate::app::utils::mapping_crypto::validate_passphrases;
+        use crate::app::db::student_encryption_database::{reseal_students, stored_pii_from_row, student_keyring, PII_COLUMNS, RESEAL_BATCH_SIZE};
+        use crate::app::services::student_encryption::open_pii;
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use leptos::ServerFnError;
+        use sqlx::{PgConnection, Pool, Postgres, Row};
+        use std::collections::HashMap;
+
+        // Only one anonymize or restore may run at a time
+        pub const PROTECTION_LOCK: i64 = 0x7374_7564_5f70_726f;
+
+        // Students re-keyed per statement, so progress moves on large schools
+        const REKEY_BATCH_SIZE: usize = 500;
+
+        fn db_error(e: sqlx::Error) -> ServerFnError {
+            ServerFnError::new(format!("Database error: {}", e))
+        }
+
+        fn report_progress(enabling: bool, dry_run: bool, stage: ProtectionStage, students_done: usize, students_total: usize) {
+            set_progress(ProtectionProgress {
+                enabling,
+                dry_run,
+                stage,
+                students_done: students_done as i64,
+                students_total: students_total as i64,
+            });
+        }
+
+        pub async fn protection_enabled(conn: &mut PgConnection) -> Result<bool, ServerFnError> {
+            let value: Option<String> = sqlx::query_scalar("SELECT value::TEXT FROM global_settings WHERE key = 'student_protections'")
+                .fetch_optional(&mut *conn)
+                .await
+                .map_err(db_error)?;
+            Ok(value.as_deref() == Some("true") || value.as_deref() == Some("\"true\""))
+        }
+
+        // Takes the protection lock for the rest of the transaction and fails
+        // with `refusal` while student protection is on. For writes keyed on
+        // real student IDs, which anonymized rows no longer carry.
+        pub async fn lock_unprotected(conn: &mut PgConnection, refusal: &str) -> Result<(), ServerFnError> {
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(PROTECTION_LOCK)
+                .execute(&mut *conn)
+                .await
+                .map_err(db_error)?;
+
+            if protection_enabled(conn).await? {
+                return Err(ServerFnError::new(refusal.to_string()));
+            }
+            Ok(())
+        }
+
+        async fn set_protection_setting(conn: &mut PgConnection, enabled: bool, updated_by: i32) -> Result<(), ServerFnError> {
+            sqlx::query(
+                "INSERT INTO global_settings (key, value, updated_by, updated_at)
+                 VALUES ('student_protections', $1, $2, CURRENT_TIMESTAMP)
+                 ON CONFLICT (key)
+                 DO UPDATE SET value = EXCLUDED.value, updated_by = EXCLUDED.updated_by, updated_at = CURRENT_TIMESTAMP"
+            )
+            .bind(serde_json::Value::Bool(enabled))
+            .bind(updated_by)
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+            Ok(())
+        }
+
+        // Loads the mapping into a temp table that disappears with the transaction
+        async fn stage_mapping(conn: &mut PgConnection, mapping: &[StudentIdMapping]) -> Result<(), ServerFnError> {
+            sqlx::query(
+                "CREATE TEMPORARY TABLE protection_mapping (
+                    app_id INT PRIMARY KEY,
+                    student_id INT NOT NULL UNIQUE,
+                    firstname TEXT NOT NULL,
+                    lastname TEXT NOT NULL,
+                    pin INT NOT NULL,
+                    sourced_id TEXT
+                 ) ON COMMIT DROP"
+            )
+            .execute(&mut *conn)
+            .await
+            .map_err(db_error)?;
+
+            sqlx::query(
+                "INSERT INTO protection_mapping (app_id, student_id, firstname, lastname, pin, sourced_id)
+                 SELECT * FROM UNNEST($1::INT[], $2::INT[], $3::TEXT[], $4::TEXT[], $5::INT[], $6::TEXT[])"
+            )
+            .bind(mapping.iter().map(|m| m.app_id).collect::<Vec<_>>())
+            .bind(mapping.iter().map(|m| m.student_id).collect::<Vec<_>>())
+            .bind(mapping.iter().map(|m| m.firstname.clone()).collect::<Vec<_>>())
+            .bind(mapping.iter().map(|m| m.lastname.clone()).collect::<Vec<_>>())
+            .bind(mapping.iter().map(|m| m.pin).collect::<Vec<_>>())
+            .bind(mapping.iter().map(|m| m.sourced_id.clone()).collect::<Vec<_>>())
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Mapping has duplicate IDs: {}", e)))?;
+
+            Ok(())
+        }
+
+        // Rows in each table whose student_id is one of the mapping's `column` IDs
+        async fn count_mapped_rows(conn: &mut PgConnection, column: &str) -> Result<(i64, i64, i64), ServerFnError> {
+            let row = sqlx::query(&format!(
+                "SELECT
+                    (SELECT COUNT(*) FROM students t JOIN protection_mapping m ON t.student_id = m.{column}) AS students,
+                    (SELECT COUNT(*) FROM scores t JOIN protection_mapping m ON t.student_id = m.{column}) AS scores,
+                    (SELECT COUNT(*) FROM student_enrollments t JOIN protection_mapping m ON t.student_id = m.{column}) AS enrollments"
+            ))
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error)?;
+            Ok((row.get("students"), row.get("scores"), row.get("enrollments")))
+        }
+
+        async fn count_orphans(conn: &mut PgConnection) -> Result<(i64, i64), ServerFnError> {
+            let row = sqlx::query(
+                "SELECT
+                    (SELECT COUNT(*) FROM scores t WHERE NOT EXISTS (SELECT 1 FROM students s WHERE s.student_id = t.student_id)) AS scores,
+                    (SELECT COUNT(*) FROM student_enrollments t WHERE NOT EXISTS (SELECT 1 FROM students s WHERE s.student_id = t.student_id)) AS enrollments"
+            )
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error)?;
+            Ok((row.get("scores"), row.get("enrollments")))
+        }
+
+        // Moves students from the mapping's `from` IDs to its `to` IDs in
+        // batches. Scores and enrollments follow through ON UPDATE CASCADE.
+        async fn rekey_students(
+            conn: &mut PgConnection,
+            mapping: &[StudentIdMapping],
+            restoring: bool,
+            dry_run: bool,
+        ) -> Result<(), ServerFnError> {
+            let (from, to, pii) = if restoring {
+                // Mapping files from the old psql scripts never cleared sourced_id
+                (
+                    "app_id",
+                    "student_id",
+                    "firstname = m.firstname, lastname = m.lastname, pin = m.pin, sourced_id = COALESCE(m.sourced_id, s.sourced_id)",
+                )
+            } else {
+                (
+                    "student_id",
+                    "app_id",
+                    "firstname = NULL, lastname = NULL, pin = NULL, sourced_id = NULL, firstname_enc = NULL, lastname_enc = NULL, pin_enc = NULL, firstname_bidx = NULL, lastname_bidx = NULL, name_tokens = NULL",
+                )
+            };
+            let statement = format!(
+                "UPDATE students s SET student_id = m.{to}, {pii}
+                 FROM protection_mapping m
+                 WHERE s.student_id = m.{from} AND m.app_id = ANY($1)"
+            );
+
+            let mut done = 0;
+            for batch in mapping.chunks(REKEY_BATCH_SIZE) {
+                sqlx::query(&statement)
+                    .bind(batch.iter().map(|m| m.app_id).collect::<Vec<_>>())
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(db_error)?;
+                done += batch.len();
+                report_progress(!restoring, dry_run, ProtectionStage::Rekeying, done, mapping.len());
+            }
+            Ok(())
+        }
+
+        // Snapshot keys holding the PII anonymizing clears from students
+        const MERGED_PII_KEYS: &str = "'{firstname, lastname, pin, sourced_id, firstname_enc, lastname_enc, pin_enc, firstname_bidx, lastname_bidx, name_tokens}'::TEXT[]";
+
+        // Merges still in force keep the merged record and its history to put
+        // back on undo. Both move between IDs with the mapping, and the
+        // record's PII is cleared or restored as for students.
+        async fn rekey_merged_records(conn: &mut PgConnection, restoring: bool) -> Result<u64, ServerFnError> {
+            let (from, to, snapshot) = if restoring {
+                (
+                    "app_id",
+                    "student_id",
+                    "g.merged_student || jsonb_build_object('student_id', m.student_id, 'firstname', m.firstname, 'lastname', m.lastname, 'pin', m.pin,
+                         'sourced_id', COALESCE(to_jsonb(m.sourced_id), g.merged_student->'sourced_id'))"
+                        .to_string(),
+                )
+            } else {
+                ("student_id", "app_id", format!("(g.merged_student - {}) || jsonb_build_object('student_id', m.app_id)", MERGED_PII_KEYS))
+            };
+            let rekeyed = |column: &str| {
+                format!(
+                    "(SELECT COALESCE(jsonb_agg(r || jsonb_build_object('student_id', m.{to}) ORDER BY n), '[]'::JSONB)
+                      FROM jsonb_array_elements(g.{column}) WITH ORDINALITY AS e(r, n))"
+                )
+            };
+            let statement = format!(
+                "UPDATE student_merges g
+                 SET merged_id = m.{to}, merged_student = {snapshot}, merged_versions = {versions},
+                     merged_attribute_values = {attribute_values}, merged_group_memberships = {group_memberships}
+                 FROM protection_mapping m
+                 WHERE g.merged_id = m.{from} AND g.unmerged_at IS NULL",
+                versions = rekeyed("merged_versions"),
+                attribute_values = rekeyed("merged_attribute_values"),
+                group_memberships = rekeyed("merged_group_memberships"),
+            );
+            Ok(sqlx::query(&statement).execute(&mut *conn).await.map_err(db_error)?.rows_affected())
+        }
+
+        // Checks every row that had a source ID now has the matching target ID
+        async fn verify_rekey(
+            conn: &mut PgConnection,
+            before: (i64, i64, i64),
+            restoring: bool,
+            dry_run: bool,
+        ) -> Result<ProtectionReport, ServerFnError> {
+            let (from, to) = if restoring { ("app_id", "student_id") } else { ("student_id", "app_id") };
+            let left_behind = count_mapped_rows(conn, from).await?;
+            let after = count_mapped_rows(conn, to).await?;
+
+            if left_behind != (0, 0, 0) || after != before {
+                return Err(ServerFnError::new(format!(
+                    "Verification failed, nothing was changed. Expected {:?} students/scores/enrollments to move, found {:?} moved and {:?} left behind.",
+                    before, after, left_behind
+                )));
+            }
+
+            let (orphaned_scores, orphaned_enrollments) = count_orphans(conn).await?;
+            Ok(ProtectionReport {
+                dry_run,
+                students: after.0,
+                scores: after.1,
+                enrollments: after.2,
+                orphaned_scores,
+                orphaned_enrollments,
+            })
+        }
+
+        // Anonymizes every student, and each record merged into one, in one
+        // transaction and seals the mapping under a key derived from the
+        // admins' passphrases.
+        pub async fn anonymize_students(
+            pool: &Pool<Postgres>,
+            passphrases: &[String],
+            updated_by: i32,
+            dry_run: bool,
+        ) -> Result<ProtectionReport, ServerFnError> {
+            validate_passphrases(passphrases).map_err(ServerFnError::new)?;
+            report_progress(true, dry_run, ProtectionStage::Preparing, 0, 0);
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(PROTECTION_LOCK)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            if protection_enabled(&mut tx).await? {
+                return Err(ServerFnError::new("Student protection is already enabled"));
+            }
+
+            let rows = sqlx::query(&format!(
+                "SELECT student_id, sourced_id, {} FROM students FOR UPDATE",
+                PII_COLUMNS
+            ))
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if rows.is_empty() {
+                return Err(ServerFnError::new("There are no students to protect"));
+            }
+            let merged = sqlx::query(&format!(
+                "SELECT g.merged_id AS student_id, s.sourced_id, {}
+                 FROM student_merges g, jsonb_populate_record(NULL::students, g.merged_student) s
+                 WHERE g.unmerged_at IS NULL FOR UPDATE OF g",
+                PII_COLUMNS
+            ))
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            let rows: Vec<_> = rows.iter().chain(&merged).collect();
+
+            let mut sourced_ids: HashMap<i32, String> = rows
+                .iter()
+                .filter_map(|row| Some((row.get("student_id"), row.get::<Option<String>, _>("sourced_id")?)))
+                .collect();
+            let students: Vec<(i32, String, String, i32)> = rows
+                .iter()
+                .map(|row| {
+                    let student_id: i32 = row.get("student_id");
+                    let pii = open_pii(keyring.as_deref(), &stored_pii_from_row(row))
+                        .map_err(|e| ServerFnError::new(format!("Student {}: {}", student_id, e)))?;
+                    Ok((
+                        student_id,
+                        pii.firstname.unwrap_or_default(),
+                        pii.lastname.unwrap_or_default(),
+                        pii.pin.unwrap_or_default(),
+                    ))
+                })
+                .collect::<Result<_, ServerFnError>>()?;
+
+            report_progress(true, dry_run, ProtectionStage::Mapping, 0, students.len());
+            let mut mapping = assign_app_ids(students);
+            for entry in &mut mapping {
+                entry.sourced_id = sourced_ids.remove(&entry.student_id);
+            }
+            stage_mapping(&mut tx, &mapping).await?;
+            let before = count_mapped_rows(&mut tx, "student_id").await?;
+
+            rekey_students(&mut tx, &mapping, false, dry_run).await?;
+            if rekey_merged_records(&mut tx, false).await? != merged.len() as u64 {
+                return Err(ServerFnError::new("Verification failed, nothing was changed. Merged records changed while anonymizing."));
+            }
+
+            report_progress(true, dry_run, ProtectionStage::Verifying, mapping.len(), mapping.len());
+            let report = verify_rekey(&mut tx, before, false, dry_run).await?;
+
+            report_progress(true, dry_run, ProtectionStage::Sealing, mapping.len(), mapping.len());
+            let kdf = KdfParams::default();
+            let sealed = seal_mapping(&mapping, passphrases, &kdf).map_err(ServerFnError::new)?;
+
+            sqlx::query("DELETE FROM student_protection_vault")
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            sqlx::query(
+                "INSERT INTO student_protection_vault
+                    (nonce, ciphertext, kdf_salt, kdf_memory_kib, kdf_iterations, kdf_parallelism, key_shares, student_count, created_by)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"
+            )
+            .bind(sealed.nonce)
+            .bind(sealed.ciphertext)
+            .bind(sealed.salt)
+            .bind(kdf.memory_kib as i32)
+            .bind(kdf.iterations as i32)
+            .bind(kdf.parallelism as i32)
+            .bind(passphrases.len() as i16)
+            .bind(mapping.len() as i32)
+            .bind(updated_by)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+
+            // Plaintext mapping left behind by the old psql scripts
+            sqlx::query("DROP TABLE IF EXISTS student_id_mapping")
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            set_protection_setting(&mut tx, true, updated_by).await?;
+
+            if dry_run {
+                tx.rollback().await.map_err(db_error)?;
+            } else {
+                tx.commit().await.map_err(db_error)?;
+            }
+            report_progress(true, dry_run, ProtectionStage::Finished, mapping.len(), mapping.len());
+
+            Ok(report)
+        }
+
+        // Puts original IDs, names and PINs back from a decrypted or uploaded
+        // mapping, in one transaction. Every anonymized student must be covered.
+        pub async fn restore_students(
+            pool: &Pool<Postgres>,
+            mapping: &[StudentIdMapping],
+            updated_by: i32,
+            dry_run: bool,
+        ) -> Result<ProtectionReport, ServerFnError> {
+            report_progress(false, dry_run, ProtectionStage::Preparing, 0, mapping.len());
+            let keyring = student_keyring(pool).await?;
+            let mut tx = pool.begin().await.map_err(db_error)?;
+
+            sqlx::query("SELECT pg_advisory_xact_lock($1)")
+                .bind(PROTECTION_LOCK)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            sqlx::query("LOCK TABLE students IN SHARE ROW EXCLUSIVE MODE")
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            report_progress(false, dry_run, ProtectionStage::Mapping, 0, mapping.len());
+            stage_mapping(&mut tx, mapping).await?;
+
+            let conflicts: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM students s JOIN protection_mapping m ON s.student_id = m.student_id
+                 WHERE NOT EXISTS (SELECT 1 FROM protection_mapping o WHERE o.app_id = s.student_id)"
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if conflicts > 0 {
+                return Err(ServerFnError::new(format!(
+                    "{} original student IDs in the mapping are already in use; nothing was changed",
+                    conflicts
+                )));
+            }
+
+            let before = count_mapped_rows(&mut tx, "app_id").await?;
+            rekey_students(&mut tx, mapping, true, dry_run).await?;
+            rekey_merged_records(&mut tx, true).await?;
+
+            report_progress(false, dry_run, ProtectionStage::Verifying, mapping.len(), mapping.len());
+            let still_anonymous: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM students
+                 WHERE (firstname IS NULL AND firstname_enc IS NULL)
+                    OR (lastname IS NULL AND lastname_enc IS NULL)
+                    OR (pin IS NULL AND pin_enc IS NULL)"
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if still_anonymous > 0 {
+                return Err(ServerFnError::new(format!(
+                    "{} anonymized students are not in the mapping; nothing was changed",
+                    still_anonymous
+                )));
+            }
+            let merged_still_anonymous: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM student_merges WHERE unmerged_at IS NULL AND NOT merged_student ?| ARRAY['lastname', 'lastname_enc']"
+            )
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error)?;
+            if merged_still_anonymous > 0 {
+                return Err(ServerFnError::new(format!(
+                    "{} anonymized merged records are not in the mapping; nothing was changed",
+                    merged_still_anonymous
+                )));
+            }
+
+            let report = verify_rekey(&mut tx, before, true, dry_run).await?;
+
+            // The restored names and PINs are plaintext until sealed again
+            if let Some(keyring) = &keyring {
+                while reseal_students(&mut tx, keyring, RESEAL_BATCH_SIZE).await? > 0 {}
+            }
+
+            report_progress(false, dry_run, ProtectionStage::Sealing, mapping.len(), mapping.len());
+            sqlx::query("DELETE FROM student_protection_vault")
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+            sqlx::query("DROP TABLE IF EXISTS student_id_mapping")
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error)?;
+
+            set_protection_setting(&mut tx, false, updated_by).await?;
+
+            if dry_run {
+                tx.rollback().await.map_err(db_error)?;
+            } else {
+                tx.commit().await.map_err(db_error)?;
+            }
+            report_progress(false, dry_run, ProtectionStage::Finished, mapping.len(), mapping.len());
+
+            Ok(report)
+        }
+
+        pub async fn get_protection_vault(pool: &Pool<Postgres>) -> Result<Option<SealedMapping>, ServerFnError> {
+            let row = sqlx::query(
+                "SELECT nonce, ciphertext, kdf_salt, kdf_memory_kib, kdf_iterations, kdf_parallelism, key_shares, student_count, created_at
+                 FROM student_protection_vault WHERE id = 1"
+            )
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error)?;
+
+            Ok(row.map(|row| {
+                let defaults = KdfParams::default();
+                let param = |column: &str, default: u32| {
+                    row.get::<Option<i32>, _>(column).map(|value| value as u32).unwrap_or(default)
+                };
+                SealedMapping {
+                    kdf_salt: row.get::<Option<Vec<u8>>, _>("kdf_salt").map(|salt| STANDARD.encode(salt)),
+                    kdf: KdfParams {
+                        memory_kib: param("kdf_memory_kib", defaults.memory_kib),
+                        iterations: param("kdf_iterations", defaults.iterations),
+                        parallelism: param("kdf_parallelism", defaults.parallelism),
+                    },
+                    key_shares: row.get("key_shares"),
+                    nonce: STANDARD.encode(row.get::<Vec<u8>, _>("nonce")),
+                    ciphertext: STANDARD.encode(row.get::<Vec<u8>, _>("ciphertext")),
+                    student_count: row.get("student_count"),
+                    created_at: row.get("created_at"),
+                }
+            }))
+        }
+    }
+}
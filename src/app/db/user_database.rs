@@ -12,6 +12,8 @@ cfg_if::cfg_if! {
         use chrono::{DateTime, Utc};
         use crate::app::models::user::AccountStatus;
         use crate::app::models::setting_data::{UserSettings, UserSettingsUpdate};
+        use crate::app::models::auth::{ActiveSession, SessionMetadata};
+        use crate::app::models::global::SessionSettings;
 
         // Hash a password
         pub fn hash_password(password: &str) -> Result<String, ServerFnError> {
@@ -248,6 +250,7 @@ cfg_if::cfg_if! {
                         display_name: row.try_get("display_name").unwrap_or(None),
                         first_name: row.try_get("first_name").unwrap_or(None),
                         last_name: row.try_get("last_name").unwrap_or(None),
+                        token_scopes: None,
                     };
                     Ok(Some(user))
                 },
@@ -301,12 +304,26 @@ cfg_if::cfg_if! {
         }
 
         // Session Management
-        pub async fn create_session(pool: &Pool<Postgres>, user_id: i64) -> Result<String, ServerFnError> {
+        // Absolute lifetime and idle limit are fixed on the row at creation
+        pub async fn create_session(
+            pool: &Pool<Postgres>,
+            user_id: i64,
+            metadata: &SessionMetadata,
+            settings: &SessionSettings,
+        ) -> Result<String, ServerFnError> {
             let session_token = uuid::Uuid::new_v4().to_string();
 
-            sqlx::query("INSERT INTO sessions (user_id, token, expires_at) VALUES ($1, $2, NOW() + INTERVAL '7 days')")
+            sqlx::query(
+                "INSERT INTO sessions (user_id, token, expires_at, idle_timeout_minutes, ip_address, user_agent, provider)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)"
+            )
                 .bind(user_id)
                 .bind(&session_token)
+                .bind(Utc::now() + settings.max_lifetime())
+                .bind(settings.idle_timeout_minutes())
+                .bind(&metadata.ip_address)
+                .bind(&metadata.user_agent)
+                .bind(metadata.provider.as_str())
                 .execute(pool)
                 .await
                 .map_err(|e| ServerFnError::new(format!("Error creating session: {}", e)))?;
@@ -321,10 +338,13 @@ cfg_if::cfg_if! {
             session_token: &str,
         ) -> Result<Option<SessionUser>, ServerFnError> {
             let row = sqlx::query(
-                "SELECT u.id, u.username, u.email, u.role, u.display_name, u.first_name, u.last_name
+                "SELECT u.id, u.username, u.email, u.role, u.display_name, u.first_name, u.last_name,
+                        s.id AS session_id, s.last_seen_at
                  FROM users u
                  JOIN sessions s ON u.id = s.user_id
-                 WHERE s.token = $1 AND s.expires_at > NOW()"
+                 WHERE s.token = $1 AND s.expires_at > NOW()
+                   AND (s.idle_timeout_minutes IS NULL
+                        OR s.last_seen_at > NOW() - make_interval(mins => s.idle_timeout_minutes))"
             )
             .bind(&session_token)
             .fetch_optional(pool)
@@ -333,6 +353,16 @@ cfg_if::cfg_if! {
 
             match row {
                 Some(row) => {
+                    // Slide the idle window, writing at most once a minute per session
+                    let last_seen_at: DateTime<Utc> = row.get("last_seen_at");
+                    if Utc::now() - last_seen_at > chrono::Duration::minutes(1) {
+                        sqlx::query("UPDATE sessions SET last_seen_at = NOW() WHERE id = $1")
+                            .bind(row.get::<i64, _>("session_id"))
+                            .execute(pool)
+                            .await
+                            .map_err(|e| ServerFnError::new(format!("Error updating session: {}", e)))?;
+                    }
+
                     let user = SessionUser {
                         id: row.get("id"),
                         username: row.get("username"),
@@ -341,6 +371,7 @@ cfg_if::cfg_if! {
                         display_name: row.try_get("display_name").unwrap_or(None),
                         first_name: row.try_get("first_name").unwrap_or(None),
                         last_name: row.try_get("last_name").unwrap_or(None),
+                        token_scopes: None,
                     };
                     Ok(Some(user))
                 },
@@ -348,6 +379,85 @@ cfg_if::cfg_if! {
             }
         }
 
+        // Live sessions for a user, newest activity first
+        pub async fn list_user_sessions(
+            pool: &Pool<Postgres>,
+            user_id: i64,
+            current_token: Option<&str>,
+        ) -> Result<Vec<ActiveSession>, ServerFnError> {
+            let rows = sqlx::query(
+                "SELECT id, created_at, last_seen_at, expires_at, ip_address, user_agent, provider,
+                        token = $2 AS current
+                 FROM sessions
+                 WHERE user_id = $1 AND expires_at > NOW()
+                   AND (idle_timeout_minutes IS NULL
+                        OR last_seen_at > NOW() - make_interval(mins => idle_timeout_minutes))
+                 ORDER BY last_seen_at DESC"
+            )
+            .bind(user_id)
+            .bind(current_token.unwrap_or_default())
+            .fetch_all(pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Error listing sessions: {}", e)))?;
+
+            rows.iter().map(|row| {
+                let provider: String = row.get("provider");
+                Ok(ActiveSession {
+                    id: row.get("id"),
+                    created_at: row.get("created_at"),
+                    last_seen_at: row.get("last_seen_at"),
+                    expires_at: row.get("expires_at"),
+                    ip_address: row.get("ip_address"),
+                    user_agent: row.get("user_agent"),
+                    provider: provider.parse().map_err(ServerFnError::new)?,
+                    current: row.get("current"),
+                })
+            }).collect()
+        }
+
+        // Revoke one of the user's own sessions. False if it wasn't theirs.
+        pub async fn revoke_user_session(pool: &Pool<Postgres>, user_id: i64, session_id: i64) -> Result<bool, ServerFnError> {
+            let result = sqlx::query("DELETE FROM sessions WHERE id = $1 AND user_id = $2")
+                .bind(session_id)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Error revoking session: {}", e)))?;
+
+            Ok(result.rows_affected() > 0)
+        }
+
+        // Sign a user out everywhere, optionally keeping the session making the request
+        pub async fn revoke_all_user_sessions(
+            pool: &Pool<Postgres>,
+            user_id: i64,
+            except_token: Option<&str>,
+        ) -> Result<u64, ServerFnError> {
+            let result = sqlx::query("DELETE FROM sessions WHERE user_id = $1 AND token <> $2")
+                .bind(user_id)
+                .bind(except_token.unwrap_or_default())
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Error revoking sessions: {}", e)))?;
+
+            Ok(result.rows_affected())
+        }
+
+        // Swap a live session's token for a fresh one, keeping its metadata.
+        // Used when the account's privileges change so an old token can't carry them.
+        pub async fn rotate_session(pool: &Pool<Postgres>, session_token: &str) -> Result<Option<String>, ServerFnError> {
+            let new_token = uuid::Uuid::new_v4().to_string();
+
+            let result = sqlx::query("UPDATE sessions SET token = $2, last_seen_at = NOW() WHERE token = $1 AND expires_at > NOW()")
+                .bind(session_token)
+                .bind(&new_token)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Error rotating session: {}", e)))?;
+
+            Ok((result.rows_affected() > 0).then_some(new_token))
+        }
+
         // Delete a session (logout)
         pub async fn delete_session(pool: &Pool<Postgres>, token: &str) -> Result<(), ServerFnError> {
             sqlx::query("DELETE FROM sessions WHERE token = $1")
@@ -522,5 +632,39 @@ cfg_if::cfg_if! {
             };
             Ok(user)
         }
+
+        // Employee record a login account teaches as; drives caseload scoping
+        pub async fn get_user_employee_id(user_id: i64, pool: &sqlx::PgPool) -> Result<Option<i32>, ServerFnError> {
+            let row = sqlx::query("SELECT employee_id FROM users WHERE id = $1")
+                .bind(user_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to look up linked employee: {}", e)))?;
+
+            Ok(row.and_then(|row| row.get::<Option<i32>, _>("employee_id")))
+        }
+
+        pub async fn get_all_user_employee_links(pool: &sqlx::PgPool) -> Result<Vec<(i64, i32)>, ServerFnError> {
+            let rows = sqlx::query("SELECT id, employee_id FROM users WHERE employee_id IS NOT NULL")
+                .fetch_all(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to fetch employee links: {}", e)))?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| (row.get("id"), row.get("employee_id")))
+                .collect())
+        }
+
+        pub async fn set_user_employee_id(user_id: i64, employee_id: Option<i32>, pool: &sqlx::PgPool) -> Result<(), ServerFnError> {
+            sqlx::query("UPDATE users SET employee_id = $1, updated_at = NOW() WHERE id = $2")
+                .bind(employee_id)
+                .bind(user_id)
+                .execute(pool)
+                .await
+                .map_err(|e| ServerFnError::new(format!("Failed to link user to employee: {}", e)))?;
+
+            Ok(())
+        }
     }
 }
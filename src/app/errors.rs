@@ -12,3 +12,6 @@ pub mod question_errors;
 pub use question_errors::ErrorMessageQuestion;
 pub use question_errors::QuestionError;
 pub use question_errors::ResponseErrorTraitQuestion;
+
+pub mod auth_errors;
+pub use auth_errors::AuthError;
@@ -0,0 +1,12 @@
+use crate::app::models::permission::Permission;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    #[error("Unauthorized: Login required")]
+    NotAuthenticated,
+    #[error("Forbidden: {0} permission required")]
+    Forbidden(Permission),
+    #[error("Forbidden: API token does not cover {0}")]
+    OutOfScope(Permission),
+}
@@ -2,6 +2,7 @@
 mod server {
     use actix_web::{
         dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+        http::header,
         web, Error, HttpMessage, HttpResponse,
     };
     use futures::future::{ready, LocalBoxFuture, Ready};
@@ -10,7 +11,8 @@ mod server {
     use std::rc::Rc;
     use std::task::{Context, Poll};
 
-    use crate::app::db::user_database;
+    use crate::app::db::{api_token_database, user_database};
+    use crate::app::services::api_tokens;
     use crate::app::models::user::{SessionUser, UserRole};
 
     pub struct Authentication;
@@ -85,6 +87,34 @@ mod server {
                             }
                         }
                     }
+
+                    // Scripts authenticate with a personal access token instead
+                    if authenticated_user.is_none() {
+                        let bearer = req
+                            .headers()
+                            .get(header::AUTHORIZATION)
+                            .and_then(|value| value.to_str().ok())
+                            .and_then(api_tokens::bearer_token);
+                        if let Some(token) = bearer {
+                            match api_token_database::authenticate_api_token(
+                                &pool,
+                                &api_tokens::hash_token(token),
+                            )
+                            .await
+                            {
+                                Ok(Some(user)) => {
+                                    debug!("Valid API token found for user: {}", user.username);
+                                    authenticated_user = Some(user);
+                                }
+                                Ok(None) => {
+                                    debug!("Invalid, revoked or expired API token");
+                                }
+                                Err(e) => {
+                                    error!("Error validating API token: {:?}", e);
+                                }
+                            }
+                        }
+                    }
                 } else {
                     error!("Database pool not found in middleware");
                 }
@@ -108,18 +138,12 @@ mod server {
 
     // Helper function to check if user has required role
     pub fn user_has_role(user: &SessionUser, required_role: UserRole) -> bool {
-        match required_role {
-            UserRole::Guest => true, // All users can access guest-level content
-            UserRole::User => user.is_user(),
-            UserRole::Teacher => user.is_teacher(),
-            UserRole::Admin => user.is_admin(),
-            UserRole::SuperAdmin => user.is_super_admin(),
-        }
+        user.has_role(required_role)
     }
 
     // Helper to check if user has any of the required roles
     pub fn user_has_any_role(user: &SessionUser, required_roles: &[UserRole]) -> bool {
-        required_roles.iter().any(|role| user_has_role(user, *role))
+        user.has_any_role(required_roles)
     }
 
     // Helper to get user ID from request
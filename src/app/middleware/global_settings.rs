@@ -1,4 +1,5 @@
 use crate::app::models::global::SettingsCache;
+use crate::app::models::user::SessionUser;
 use crate::app::server_functions::globals::get_global_settings;
 use leptos::*;
 
@@ -7,8 +8,13 @@ pub fn SettingsProvider(children: Children) -> impl IntoView {
     let (settings, set_settings) = create_signal(SettingsCache::default());
     let (loading, set_loading) = create_signal(true);
 
-    // Load settings on mount
+    // Load settings on mount, and again on sign-in and sign-out since
+    // signed-out visitors only get the public subset
+    let current_user = use_context::<ReadSignal<Option<SessionUser>>>();
     create_effect(move |_| {
+        if let Some(current_user) = current_user {
+            current_user.with(|user| user.as_ref().map(|user| user.id));
+        }
         set_loading.set(true);
 
         spawn_local(async move {
@@ -35,6 +35,9 @@ pub use employee::StatusEnum;
 pub mod user;
 pub use user::User;
 
+pub mod permission;
+pub use permission::{DataScope, Permission};
+
 pub mod bulk_student;
 pub use bulk_student::BulkStudentImportRequest;
 pub use bulk_student::StudentCsvRow;
@@ -69,8 +72,40 @@ pub use course::UpdateCourseRequest;
 
 pub mod global;
 
+pub mod mfa;
+
 pub mod auth;
 pub use auth::*;
 
+pub mod audit;
+
+pub mod student_protection;
+
+pub mod student_encryption;
+
+pub mod student_export;
+
+pub mod retention;
+
+pub mod student_merge;
+
+pub mod student_history;
+
+pub mod student_attribute;
+
+pub mod student_group;
+
+pub mod student_search;
+
+pub mod oneroster;
+
+pub mod roster_sync;
+
+pub mod import_validation;
+
+pub mod import_mapping;
+
+pub mod score_import;
+
 pub mod assessment_sequences;
 pub use assessment_sequences::{SequenceBehavior, TestSequenceItem};
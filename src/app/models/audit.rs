@@ -0,0 +1,246 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    // A record disclosed outside the app, e.g. a records request export
+    Export,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::Export => "export",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AuditAction::iter()
+            .find(|action| action.as_str() == s)
+            .ok_or_else(|| format!("Unknown audit action: {}", s))
+    }
+}
+
+// Kinds of records whose changes are audited
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum AuditEntity {
+    Score,
+    Student,
+    Test,
+    Question,
+    Assessment,
+    Enrollment,
+    User,
+    GlobalSetting,
+    SamlConfig,
+    OidcConfig,
+    StudentDataKey,
+    RetentionPolicy,
+    StudentMerge,
+    StudentAttribute,
+    StudentGroup,
+    Roster,
+}
+
+impl AuditEntity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditEntity::Score => "score",
+            AuditEntity::Student => "student",
+            AuditEntity::Test => "test",
+            AuditEntity::Question => "question",
+            AuditEntity::Assessment => "assessment",
+            AuditEntity::Enrollment => "enrollment",
+            AuditEntity::User => "user",
+            AuditEntity::GlobalSetting => "global_setting",
+            AuditEntity::SamlConfig => "saml_config",
+            AuditEntity::OidcConfig => "oidc_config",
+            AuditEntity::StudentDataKey => "student_data_key",
+            AuditEntity::RetentionPolicy => "retention_policy",
+            AuditEntity::StudentMerge => "student_merge",
+            AuditEntity::StudentAttribute => "student_attribute",
+            AuditEntity::StudentGroup => "student_group",
+            AuditEntity::Roster => "roster",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditEntity::Score => "Score",
+            AuditEntity::Student => "Student",
+            AuditEntity::Test => "Test",
+            AuditEntity::Question => "Question",
+            AuditEntity::Assessment => "Assessment",
+            AuditEntity::Enrollment => "Enrollment",
+            AuditEntity::User => "User",
+            AuditEntity::GlobalSetting => "Global setting",
+            AuditEntity::SamlConfig => "SAML configuration",
+            AuditEntity::OidcConfig => "OIDC configuration",
+            AuditEntity::StudentDataKey => "Student data key",
+            AuditEntity::RetentionPolicy => "Retention policy",
+            AuditEntity::StudentMerge => "Student merge",
+            AuditEntity::StudentAttribute => "Student attribute",
+            AuditEntity::StudentGroup => "Student group",
+            AuditEntity::Roster => "Roster",
+        }
+    }
+}
+
+impl fmt::Display for AuditEntity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AuditEntity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AuditEntity::iter()
+            .find(|entity| entity.as_str() == s)
+            .ok_or_else(|| format!("Unknown audit entity: {}", s))
+    }
+}
+
+// The state of a record either side of a change; None for the side that did
+// not exist (before a create, after a delete).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditChange {
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditChange {
+    pub fn created<T: Serialize>(after: &T) -> Self {
+        AuditChange {
+            before: None,
+            after: serde_json::to_value(after).ok(),
+        }
+    }
+
+    pub fn updated<B: Serialize, A: Serialize>(before: &B, after: &A) -> Self {
+        AuditChange {
+            before: serde_json::to_value(before).ok(),
+            after: serde_json::to_value(after).ok(),
+        }
+    }
+
+    pub fn deleted<T: Serialize>(before: &T) -> Self {
+        AuditChange {
+            before: serde_json::to_value(before).ok(),
+            after: None,
+        }
+    }
+
+    // The change reduced to the names of the fields it touched, for records
+    // holding PII that must stay out of the append-only log. Values are kept
+    // only for the `keep` fields, such as the record's ID.
+    pub fn redacted(self, keep: &[&str]) -> Self {
+        let object = |side: &Option<Value>| {
+            side.as_ref()
+                .and_then(Value::as_object)
+                .cloned()
+                .unwrap_or_default()
+        };
+        let (before, after) = (object(&self.before), object(&self.after));
+        fn set<'a>(side: &'a Map<String, Value>, field: &str) -> Option<&'a Value> {
+            side.get(field).filter(|v| !v.is_null())
+        }
+        let changed: BTreeSet<&String> = before
+            .keys()
+            .chain(after.keys())
+            .filter(|field| set(&before, field) != set(&after, field))
+            .collect();
+
+        let summary = |side: &Map<String, Value>| {
+            let mut summary: Map<String, Value> = keep
+                .iter()
+                .filter_map(|field| side.get(*field).map(|v| (field.to_string(), v.clone())))
+                .collect();
+            summary.insert("changed_fields".to_string(), serde_json::json!(changed));
+            Value::Object(summary)
+        };
+        AuditChange {
+            before: self.before.as_ref().map(|_| summary(&before)),
+            after: self.after.as_ref().map(|_| summary(&after)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_id: Option<i64>,
+    pub actor_username: String,
+    pub action: AuditAction,
+    pub entity_type: AuditEntity,
+    pub entity_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+// Filters for the audit log page; every field is optional
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditEventFilter {
+    pub actor: Option<String>,
+    pub action: Option<AuditAction>,
+    pub entity_type: Option<AuditEntity>,
+    pub entity_id: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+// Result of re-hashing the whole chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditChainStatus {
+    pub events_checked: i64,
+    // First event whose stored hashes do not match its contents or predecessor
+    pub first_invalid_id: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacted_changes_keep_field_names_only() {
+        let before = json!({ "student_id": 1001, "firstname": "Jane", "lastname": "Doe", "iep": false, "pin": null });
+        let after = json!({ "student_id": 1001, "firstname": "Janet", "lastname": "Doe", "iep": true, "pin": null });
+
+        let change = AuditChange::updated(&before, &after).redacted(&["student_id"]);
+        let expected = json!({ "student_id": 1001, "changed_fields": ["firstname", "iep"] });
+        assert_eq!(change.after, Some(expected.clone()));
+        assert_eq!(change.before, Some(expected));
+
+        let change = AuditChange::deleted(&before).redacted(&["student_id"]);
+        assert_eq!(change.after, None);
+        let recorded = change.before.unwrap().to_string();
+        assert!(!recorded.contains("Jane") && !recorded.contains("Doe"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::app::models::permission::TokenScope;
 use crate::app::models::user::UserRole;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -9,6 +10,7 @@ use uuid::Uuid;
 pub enum AuthProvider {
     Local,
     Saml(String), // Institution ID
+    Oidc(String), // OIDC config ID
     Google,
     Microsoft,
 }
@@ -46,6 +48,28 @@ pub struct SamlConfig {
     pub require_encrypted_assertions: bool,
 }
 
+// Per-institution OpenID Connect client. Endpoints come from the issuer's
+// discovery document, so only the issuer URL is stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    pub id: Uuid,
+    pub institution_name: String,
+    pub issuer: String,
+    pub client_id: String,
+    #[serde(skip_serializing, default)]
+    pub client_secret: String,
+    pub scopes: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub attribute_mapping: HashMap<String, String>,
+    pub role_mapping: HashMap<String, String>,
+    pub auto_provision: bool,
+    // Azure AD and some other IdPs never send email_verified; only set this
+    // when the IdP controls every address in its directory
+    pub trust_unverified_email: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SamlResponse {
     pub name_id: String,
@@ -55,6 +79,383 @@ pub struct SamlResponse {
     pub display_name: Option<String>,
     pub attributes: HashMap<String, Vec<String>>,
     pub session_index: Option<String>,
+    pub school: Option<String>,
+    pub employee_id: Option<String>,
+    pub role: UserRole,
+}
+
+// An SSO login that matches no existing account may only create one when the
+// institution provisions accounts automatically
+pub fn check_new_account(auto_provision: bool, email: &str) -> Result<(), String> {
+    if auto_provision {
+        Ok(())
+    } else {
+        Err(format!(
+            "No account exists for {} and this institution does not create accounts automatically",
+            email
+        ))
+    }
+}
+
+// Profile fields an institution can map to one of its IdP attributes. Stored
+// in SamlConfig.attribute_mapping as key() -> attribute name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SamlMappedField {
+    Email,
+    FirstName,
+    LastName,
+    DisplayName,
+    Role,
+    School,
+    EmployeeId,
+}
+
+impl SamlMappedField {
+    pub const ALL: [SamlMappedField; 7] = [
+        SamlMappedField::Email,
+        SamlMappedField::FirstName,
+        SamlMappedField::LastName,
+        SamlMappedField::DisplayName,
+        SamlMappedField::Role,
+        SamlMappedField::School,
+        SamlMappedField::EmployeeId,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SamlMappedField::Email => "email",
+            SamlMappedField::FirstName => "first_name",
+            SamlMappedField::LastName => "last_name",
+            SamlMappedField::DisplayName => "display_name",
+            SamlMappedField::Role => "role",
+            SamlMappedField::School => "school",
+            SamlMappedField::EmployeeId => "employee_id",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SamlMappedField::Email => "Email",
+            SamlMappedField::FirstName => "First Name",
+            SamlMappedField::LastName => "Last Name",
+            SamlMappedField::DisplayName => "Display Name",
+            SamlMappedField::Role => "Role",
+            SamlMappedField::School => "School",
+            SamlMappedField::EmployeeId => "Employee ID",
+        }
+    }
+
+    // Attributes tried, in order, when an institution has not mapped the field
+    pub fn default_attributes(&self) -> &'static [&'static str] {
+        match self {
+            SamlMappedField::Email => &[
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/emailaddress",
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/name",
+                "mail",
+                "email",
+                "emailAddress",
+            ],
+            SamlMappedField::FirstName => &[
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/givenname",
+                "givenName",
+                "firstName",
+                "given_name",
+            ],
+            SamlMappedField::LastName => &[
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/surname",
+                "sn",
+                "surname",
+                "lastName",
+                "last_name",
+                "familyName",
+                "family_name",
+            ],
+            SamlMappedField::DisplayName => &[
+                "http://schemas.microsoft.com/identity/claims/displayname",
+                "displayName",
+                "cn",
+                "commonName",
+                "name",
+            ],
+            SamlMappedField::Role => &[
+                "http://schemas.microsoft.com/ws/2008/06/identity/claims/role",
+                "http://schemas.xmlsoap.org/ws/2005/05/identity/claims/role",
+                "eduPersonAffiliation",
+                "memberOf",
+                "groups",
+                "role",
+                "roles",
+            ],
+            SamlMappedField::School => &["school", "ou", "department", "eduPersonOrgUnitDN"],
+            SamlMappedField::EmployeeId => &["employeeID", "employeeNumber", "employee_id"],
+        }
+    }
+}
+
+// Assigns `role` to anyone whose role attribute contains `value`
+// (case-insensitive exact match)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SamlRoleRule {
+    pub value: String,
+    pub role: UserRole,
+}
+
+// Editable form of a config's attribute and role mapping. role_mapping keeps
+// the default role under DEFAULT_ROLE_KEY; a config with no role_mapping at
+// all still uses the built-in keyword matching.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SamlMappingSettings {
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+    #[serde(default)]
+    pub role_rules: Vec<SamlRoleRule>,
+    pub default_role: UserRole,
+}
+
+impl Default for SamlMappingSettings {
+    fn default() -> Self {
+        Self {
+            attributes: HashMap::new(),
+            role_rules: Vec::new(),
+            default_role: UserRole::Guest,
+        }
+    }
+}
+
+impl SamlMappingSettings {
+    pub const DEFAULT_ROLE_KEY: &'static str = "*";
+
+    pub fn validate(&self) -> Result<(), String> {
+        let roles = self
+            .role_rules
+            .iter()
+            .map(|rule| rule.role)
+            .chain(std::iter::once(self.default_role));
+        for role in roles {
+            if role == UserRole::SuperAdmin {
+                return Err("SAML role rules cannot grant the superadmin role".to_string());
+            }
+        }
+        for rule in &self.role_rules {
+            if rule.value.trim().is_empty() {
+                return Err("Role rules need an attribute value to match".to_string());
+            }
+            if rule.value.trim() == Self::DEFAULT_ROLE_KEY {
+                return Err(format!(
+                    "'{}' is reserved for the default role",
+                    Self::DEFAULT_ROLE_KEY
+                ));
+            }
+        }
+        for key in self.attributes.keys() {
+            if !SamlMappedField::ALL.iter().any(|field| field.key() == key) {
+                return Err(format!("Unknown mapped field: {}", key));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Attribute and role mapping shared by the SSO providers. Both SamlConfig and
+// OidcConfig keep the mapping in two JSONB maps; SAML attributes and OIDC
+// claims are run through the same rules.
+pub trait SsoAttributeMapping {
+    fn attribute_mapping(&self) -> &HashMap<String, String>;
+    fn role_mapping(&self) -> &HashMap<String, String>;
+    fn set_mappings(
+        &mut self,
+        attribute_mapping: HashMap<String, String>,
+        role_mapping: HashMap<String, String>,
+    );
+
+    fn mapping_settings(&self) -> SamlMappingSettings {
+        let mut role_rules: Vec<SamlRoleRule> = self
+            .role_mapping()
+            .iter()
+            .filter(|(value, _)| value.as_str() != SamlMappingSettings::DEFAULT_ROLE_KEY)
+            .filter_map(|(value, role)| {
+                UserRole::from_str(role).ok().map(|role| SamlRoleRule {
+                    value: value.clone(),
+                    role,
+                })
+            })
+            .collect();
+        role_rules.sort_by(|a, b| a.value.cmp(&b.value));
+
+        SamlMappingSettings {
+            attributes: self.attribute_mapping().clone(),
+            role_rules,
+            default_role: self
+                .role_mapping()
+                .get(SamlMappingSettings::DEFAULT_ROLE_KEY)
+                .and_then(|role| UserRole::from_str(role).ok())
+                .unwrap_or(UserRole::Guest),
+        }
+    }
+
+    fn apply_mapping_settings(&mut self, settings: &SamlMappingSettings) {
+        let attribute_mapping = settings
+            .attributes
+            .iter()
+            .filter(|(_, attribute)| !attribute.trim().is_empty())
+            .map(|(field, attribute)| (field.clone(), attribute.trim().to_string()))
+            .collect();
+
+        let mut role_mapping: HashMap<String, String> = settings
+            .role_rules
+            .iter()
+            .map(|rule| (rule.value.trim().to_string(), rule.role.to_string()))
+            .collect();
+        role_mapping.insert(
+            SamlMappingSettings::DEFAULT_ROLE_KEY.to_string(),
+            settings.default_role.to_string(),
+        );
+        self.set_mappings(attribute_mapping, role_mapping);
+    }
+
+    // The mapped attribute if one is configured, otherwise the defaults
+    fn attribute_names(&self, field: SamlMappedField) -> Vec<&str> {
+        match self.attribute_mapping().get(field.key()) {
+            Some(attribute) if !attribute.trim().is_empty() => vec![attribute.as_str()],
+            _ => field.default_attributes().to_vec(),
+        }
+    }
+
+    fn mapped_value(
+        &self,
+        field: SamlMappedField,
+        attributes: &HashMap<String, Vec<String>>,
+    ) -> Option<String> {
+        self.attribute_names(field)
+            .into_iter()
+            .filter_map(|name| attributes.get(name))
+            .flat_map(|values| values.first())
+            .find(|value| !value.trim().is_empty())
+            .cloned()
+    }
+
+    // Resolves the role for a set of attributes, along with the attribute
+    // value that decided it. The most privileged matching rule wins.
+    fn resolve_role(
+        &self,
+        attributes: &HashMap<String, Vec<String>>,
+    ) -> (UserRole, Option<String>) {
+        let values: Vec<&String> = self
+            .attribute_names(SamlMappedField::Role)
+            .into_iter()
+            .filter_map(|name| attributes.get(name))
+            .flatten()
+            .collect();
+
+        if self.role_mapping().is_empty() {
+            return legacy_role_from_values(&values);
+        }
+
+        let settings = self.mapping_settings();
+        let mut resolved: Option<(UserRole, String)> = None;
+        for value in values {
+            let matched = settings
+                .role_rules
+                .iter()
+                .find(|rule| rule.value.trim().eq_ignore_ascii_case(value.trim()));
+            if let Some(rule) = matched {
+                let better = resolved
+                    .as_ref()
+                    .map(|(role, _)| role_priority(rule.role) > role_priority(*role))
+                    .unwrap_or(true);
+                if better {
+                    resolved = Some((rule.role, value.clone()));
+                }
+            }
+        }
+
+        match resolved {
+            Some((role, value)) => (role, Some(value)),
+            None => (settings.default_role, None),
+        }
+    }
+}
+
+impl SsoAttributeMapping for OidcConfig {
+    fn attribute_mapping(&self) -> &HashMap<String, String> {
+        &self.attribute_mapping
+    }
+
+    fn role_mapping(&self) -> &HashMap<String, String> {
+        &self.role_mapping
+    }
+
+    fn set_mappings(
+        &mut self,
+        attribute_mapping: HashMap<String, String>,
+        role_mapping: HashMap<String, String>,
+    ) {
+        self.attribute_mapping = attribute_mapping;
+        self.role_mapping = role_mapping;
+    }
+}
+
+impl SsoAttributeMapping for SamlConfig {
+    fn attribute_mapping(&self) -> &HashMap<String, String> {
+        &self.attribute_mapping
+    }
+
+    fn role_mapping(&self) -> &HashMap<String, String> {
+        &self.role_mapping
+    }
+
+    fn set_mappings(
+        &mut self,
+        attribute_mapping: HashMap<String, String>,
+        role_mapping: HashMap<String, String>,
+    ) {
+        self.attribute_mapping = attribute_mapping;
+        self.role_mapping = role_mapping;
+    }
+}
+
+fn role_priority(role: UserRole) -> u8 {
+    match role {
+        UserRole::Guest => 0,
+        UserRole::User => 1,
+        UserRole::Teacher => 2,
+        UserRole::Admin => 3,
+        UserRole::SuperAdmin => 4,
+    }
+}
+
+// Keyword matching used before institutions could configure role rules
+fn legacy_role_from_values(values: &[&String]) -> (UserRole, Option<String>) {
+    for value in values {
+        let role_lower = value.to_lowercase();
+
+        if role_lower.contains("admin") {
+            return (UserRole::Admin, Some((*value).clone()));
+        }
+
+        if ["teacher", "instructor", "faculty", "staff", "educator"]
+            .iter()
+            .any(|keyword| role_lower.contains(keyword))
+        {
+            return (UserRole::Teacher, Some((*value).clone()));
+        }
+
+        if role_lower.contains("student") || role_lower.contains("learner") {
+            return (UserRole::User, Some((*value).clone()));
+        }
+    }
+
+    // Default to Guest role (safest default)
+    (UserRole::Guest, None)
+}
+
+// Result of running an institution's mapping over a pasted assertion
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamlMappingPreview {
+    pub response: SamlResponse,
+    pub matched_role_value: Option<String>,
+    pub validation_passed: bool,
+    pub validation_message: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,6 +468,120 @@ pub struct AuthSession {
     pub created_at: DateTime<Utc>,
 }
 
+// How a browser session was signed in, stored as text on the session row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionProvider {
+    Password,
+    Saml,
+    Oidc,
+}
+
+impl SessionProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionProvider::Password => "password",
+            SessionProvider::Saml => "saml",
+            SessionProvider::Oidc => "oidc",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SessionProvider::Password => "Password",
+            SessionProvider::Saml => "SAML single sign-on",
+            SessionProvider::Oidc => "OpenID Connect",
+        }
+    }
+}
+
+impl FromStr for SessionProvider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "password" => Ok(SessionProvider::Password),
+            "saml" => Ok(SessionProvider::Saml),
+            "oidc" => Ok(SessionProvider::Oidc),
+            other => Err(format!("Unknown session provider: {}", other)),
+        }
+    }
+}
+
+// Recorded when a session is created so users can recognise their devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub provider: SessionProvider,
+}
+
+// A signed-in browser as listed under My Sessions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveSession {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub provider: SessionProvider,
+    // The session making this request
+    pub current: bool,
+}
+
+impl ActiveSession {
+    // Short "Browser on OS" description from the user agent
+    pub fn device_label(&self) -> String {
+        let Some(agent) = self.user_agent.as_deref() else {
+            return "Unknown device".to_string();
+        };
+        let browser = [
+            ("Edg/", "Edge"),
+            ("OPR/", "Opera"),
+            ("Firefox/", "Firefox"),
+            ("Chrome/", "Chrome"),
+            ("Safari/", "Safari"),
+        ]
+        .iter()
+        .find(|(marker, _)| agent.contains(marker))
+        .map(|(_, name)| *name)
+        .unwrap_or("Unknown browser");
+        let os = [
+            ("iPhone", "iOS"),
+            ("iPad", "iPadOS"),
+            ("Android", "Android"),
+            ("CrOS", "ChromeOS"),
+            ("Windows", "Windows"),
+            ("Mac OS X", "macOS"),
+            ("Linux", "Linux"),
+        ]
+        .iter()
+        .find(|(marker, _)| agent.contains(marker))
+        .map(|(_, name)| *name)
+        .unwrap_or("unknown OS");
+        format!("{} on {}", browser, os)
+    }
+}
+
+// A personal access token as listed in My Account. The secret itself is only
+// returned once, in NewApiToken.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewApiToken {
+    pub token: String,
+    pub details: ApiToken,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AccountStatus {
     Pending,
@@ -120,3 +635,147 @@ pub struct RegisterRequest {
     pub first_name: Option<String>,
     pub last_name: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_with(settings: &SamlMappingSettings) -> SamlConfig {
+        let mut config = SamlConfig {
+            id: Uuid::new_v4(),
+            institution_name: "Example District".to_string(),
+            entity_id: "https://idp.example.edu/metadata".to_string(),
+            sso_url: "https://idp.example.edu/sso".to_string(),
+            slo_url: None,
+            x509_cert: String::new(),
+            metadata_url: None,
+            active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            attribute_mapping: HashMap::new(),
+            role_mapping: HashMap::new(),
+            auto_provision: true,
+            require_encrypted_assertions: false,
+        };
+        config.apply_mapping_settings(settings);
+        config
+    }
+
+    fn attributes(pairs: &[(&str, &[&str])]) -> HashMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(name, values)| {
+                (
+                    name.to_string(),
+                    values.iter().map(|v| v.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_role_rules_pick_most_privileged_match() {
+        let settings = SamlMappingSettings {
+            attributes: HashMap::from([("role".to_string(), "groups".to_string())]),
+            role_rules: vec![
+                SamlRoleRule {
+                    value: "Staff-Teachers".to_string(),
+                    role: UserRole::Teacher,
+                },
+                SamlRoleRule {
+                    value: "district-admins".to_string(),
+                    role: UserRole::Admin,
+                },
+            ],
+            default_role: UserRole::User,
+        };
+        let config = config_with(&settings);
+        // Rules round-trip through the stored map, sorted by value
+        assert_eq!(config.mapping_settings(), settings);
+
+        let attrs = attributes(&[("groups", &["staff-teachers", "District-Admins"])]);
+        assert_eq!(
+            config.resolve_role(&attrs),
+            (UserRole::Admin, Some("District-Admins".to_string()))
+        );
+
+        // Role attribute is mapped, so the default claim names are ignored
+        let attrs = attributes(&[("eduPersonAffiliation", &["staff-teachers"])]);
+        assert_eq!(config.resolve_role(&attrs), (UserRole::User, None));
+    }
+
+    #[test]
+    fn test_unconfigured_role_mapping_uses_keywords() {
+        let mut config = config_with(&SamlMappingSettings::default());
+        config.role_mapping.clear();
+
+        let attrs = attributes(&[("eduPersonAffiliation", &["faculty"])]);
+        assert_eq!(config.resolve_role(&attrs).0, UserRole::Teacher);
+    }
+
+    #[test]
+    fn test_mapped_attributes_override_defaults() {
+        let settings = SamlMappingSettings {
+            attributes: HashMap::from([("email".to_string(), "upn".to_string())]),
+            ..Default::default()
+        };
+        let config = config_with(&settings);
+        let attrs = attributes(&[
+            ("mail", &["other@example.edu"]),
+            ("upn", &["jdoe@example.edu"]),
+        ]);
+
+        assert_eq!(
+            config.mapped_value(SamlMappedField::Email, &attrs),
+            Some("jdoe@example.edu".to_string())
+        );
+        assert_eq!(config.mapped_value(SamlMappedField::School, &attrs), None);
+    }
+
+    #[test]
+    fn test_settings_reject_superadmin_and_reserved_values() {
+        let mut settings = SamlMappingSettings::default();
+        settings.default_role = UserRole::SuperAdmin;
+        assert!(settings.validate().is_err());
+
+        settings.default_role = UserRole::Guest;
+        settings.role_rules.push(SamlRoleRule {
+            value: "*".to_string(),
+            role: UserRole::Teacher,
+        });
+        assert!(settings.validate().is_err());
+    }
+
+    #[test]
+    fn unknown_users_need_auto_provision() {
+        let mut config = config_with(&SamlMappingSettings::default());
+        assert!(check_new_account(config.auto_provision, "jdoe@example.edu").is_ok());
+
+        config.auto_provision = false;
+        let error = check_new_account(config.auto_provision, "jdoe@example.edu").unwrap_err();
+        assert!(error.contains("jdoe@example.edu"));
+    }
+
+    #[test]
+    fn device_label_names_browser_and_os() {
+        let session = |agent: Option<&str>| ActiveSession {
+            id: 1,
+            created_at: Utc::now(),
+            last_seen_at: Utc::now(),
+            expires_at: Utc::now(),
+            ip_address: None,
+            user_agent: agent.map(str::to_string),
+            provider: SessionProvider::Password,
+            current: false,
+        };
+        let chrome_windows = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+        let safari_iphone = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.0 Mobile/15E148 Safari/604.1";
+
+        assert_eq!(
+            session(Some(chrome_windows)).device_label(),
+            "Chrome on Windows"
+        );
+        assert_eq!(session(Some(safari_iphone)).device_label(), "Safari on iOS");
+        assert_eq!(session(None).device_label(), "Unknown device");
+    }
+}
@@ -123,6 +123,11 @@ impl Enrollment {
             notes,
         }
     }
+
+    // An enrollment is keyed by student and academic year
+    pub fn audit_id(&self) -> String {
+        format!("{}/{}", self.student_id, self.academic_year)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
@@ -1,3 +1,6 @@
+use crate::app::models::retention::RETENTION_PURGE_ENABLED_KEY;
+use crate::app::models::user::UserRole;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -15,16 +18,39 @@ pub struct SettingsCache {
     // Add other global settings here as needed
     pub maintenance_mode: bool,
     pub max_upload_size: i64,
+    #[serde(default)]
+    pub login_throttle: LoginThrottleSettings,
+    #[serde(default)]
+    pub require_admin_mfa: bool,
+    #[serde(default)]
+    pub session: SessionSettings,
+    #[serde(default)]
+    pub retention_purge_enabled: bool,
     // etc.
 }
 
+// Global setting key for the admin MFA policy
+pub const REQUIRE_ADMIN_MFA_KEY: &str = "require_admin_mfa";
+
+// Global setting key for student anonymization. Only the protection engine
+// may change it, since it must always agree with the state of the vault.
+pub const STUDENT_PROTECTIONS_KEY: &str = "student_protections";
+
+// Keys owned by an engine that keeps other state in step with them; they are
+// never written through the generic settings setter
+pub const ENGINE_OWNED_KEYS: [&str; 1] = [STUDENT_PROTECTIONS_KEY];
+
+pub fn is_engine_owned(key: &str) -> bool {
+    ENGINE_OWNED_KEYS.contains(&key)
+}
+
 impl SettingsCache {
     pub fn from_settings(settings: Vec<GlobalSetting>) -> Self {
         let mut cache = SettingsCache::default();
 
         for setting in settings {
             match setting.key_name.as_str() {
-                "student_protections" => {
+                STUDENT_PROTECTIONS_KEY => {
                     cache.student_protections = setting.value.parse().unwrap_or(false);
                 }
                 "maintenance_mode" => {
@@ -34,10 +60,261 @@ impl SettingsCache {
                     cache.max_upload_size = setting.value.parse().unwrap_or(10485760);
                     // 10MB default
                 }
+                REQUIRE_ADMIN_MFA_KEY => {
+                    cache.require_admin_mfa = setting.value.parse().unwrap_or(false);
+                }
+                "login_max_failures" => {
+                    cache.login_throttle.max_failures = setting.value.parse().unwrap_or(5);
+                }
+                "login_lockout_minutes" => {
+                    cache.login_throttle.lockout_minutes = setting.value.parse().unwrap_or(15);
+                }
+                "login_backoff_base_seconds" => {
+                    cache.login_throttle.backoff_base_seconds = setting.value.parse().unwrap_or(1);
+                }
+                "login_ip_max_failures" => {
+                    cache.login_throttle.ip_max_failures = setting.value.parse().unwrap_or(50);
+                }
+                "session_idle_timeout_minutes" => {
+                    cache.session.idle_timeout_minutes = setting.value.parse().unwrap_or(120);
+                }
+                "session_max_lifetime_hours" => {
+                    cache.session.max_lifetime_hours = setting.value.parse().unwrap_or(168);
+                }
+                RETENTION_PURGE_ENABLED_KEY => {
+                    cache.retention_purge_enabled = setting.value.parse().unwrap_or(false);
+                }
                 _ => {} // Ignore unknown settings
             }
         }
 
         cache
     }
+
+    // What signed-out visitors may read: the switches the login page needs.
+    // Throttle, session, MFA and retention policy stay with signed-in users.
+    pub fn public_subset(&self) -> Self {
+        SettingsCache {
+            student_protections: self.student_protections,
+            maintenance_mode: self.maintenance_mode,
+            ..SettingsCache::default()
+        }
+    }
+
+    // MFA policy: when enabled, admin accounts must enroll before signing in
+    pub fn requires_mfa(&self, role: &UserRole) -> bool {
+        self.require_admin_mfa && matches!(role, UserRole::Admin | UserRole::SuperAdmin)
+    }
+}
+
+// Password login throttling. Failures are counted per username and per client
+// IP; a username waits out an exponential backoff between failures and both
+// are locked for `lockout_minutes` once they hit their threshold. Failures
+// older than the lockout window are forgotten. A threshold of 0 disables it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoginThrottleSettings {
+    pub max_failures: i32,
+    pub lockout_minutes: i64,
+    pub backoff_base_seconds: i64,
+    pub ip_max_failures: i32,
+}
+
+impl Default for LoginThrottleSettings {
+    fn default() -> Self {
+        LoginThrottleSettings {
+            max_failures: 5,
+            lockout_minutes: 15,
+            backoff_base_seconds: 1,
+            ip_max_failures: 50,
+        }
+    }
+}
+
+impl LoginThrottleSettings {
+    pub const KEYS: [&'static str; 4] = [
+        "login_max_failures",
+        "login_lockout_minutes",
+        "login_backoff_base_seconds",
+        "login_ip_max_failures",
+    ];
+
+    pub fn lockout_duration(&self) -> Duration {
+        Duration::minutes(self.lockout_minutes.max(1))
+    }
+
+    // Failures still inside the counting window
+    pub fn active_failures(
+        &self,
+        failure_count: i32,
+        last_failure_at: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> i32 {
+        match last_failure_at {
+            Some(last) if now - last < self.lockout_duration() => failure_count,
+            _ => 0,
+        }
+    }
+
+    // Wait imposed after the given number of consecutive failures
+    pub fn backoff(&self, failures: i32) -> Duration {
+        if failures <= 0 || self.backoff_base_seconds <= 0 {
+            return Duration::zero();
+        }
+        let exponent = (failures - 1).min(20) as u32;
+        let seconds = self.backoff_base_seconds.saturating_mul(1i64 << exponent);
+        Duration::seconds(seconds).min(self.lockout_duration())
+    }
+
+    // Whether this many failures within the window should lock the subject
+    pub fn should_lock(&self, failures: i32, threshold: i32) -> bool {
+        threshold > 0 && failures >= threshold
+    }
+
+    // How long a username must wait before its next attempt, if at all
+    pub fn retry_after(
+        &self,
+        failure_count: i32,
+        last_failure_at: Option<DateTime<Utc>>,
+        locked_until: Option<DateTime<Utc>>,
+        now: DateTime<Utc>,
+    ) -> Option<Duration> {
+        if let Some(until) = locked_until.filter(|until| *until > now) {
+            return Some(until - now);
+        }
+        let failures = self.active_failures(failure_count, last_failure_at, now);
+        let ready_at = last_failure_at? + self.backoff(failures);
+        (ready_at > now).then(|| ready_at - now)
+    }
+}
+
+// Sign-in session expiry. A session ends after `idle_timeout_minutes` without
+// a request and in any case `max_lifetime_hours` after sign-in; an idle
+// timeout of 0 disables it. Both are fixed when the session is created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSettings {
+    pub idle_timeout_minutes: i64,
+    pub max_lifetime_hours: i64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        SessionSettings {
+            idle_timeout_minutes: 120,
+            max_lifetime_hours: 168,
+        }
+    }
+}
+
+impl SessionSettings {
+    pub const KEYS: [&'static str; 2] = [
+        "session_idle_timeout_minutes",
+        "session_max_lifetime_hours",
+    ];
+
+    pub fn max_lifetime(&self) -> Duration {
+        Duration::hours(self.max_lifetime_hours.max(1))
+    }
+
+    // Idle limit stored on new sessions, None when disabled. Never longer
+    // than the absolute lifetime.
+    pub fn idle_timeout_minutes(&self) -> Option<i32> {
+        (self.idle_timeout_minutes > 0)
+            .then(|| self.idle_timeout_minutes.min(self.max_lifetime().num_minutes()) as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_is_capped_by_lockout() {
+        let settings = LoginThrottleSettings::default();
+        assert_eq!(settings.backoff(0), Duration::zero());
+        assert_eq!(settings.backoff(1), Duration::seconds(1));
+        assert_eq!(settings.backoff(4), Duration::seconds(8));
+        assert_eq!(settings.backoff(40), Duration::minutes(15));
+    }
+
+    #[test]
+    fn retry_after_honours_lock_backoff_and_window() {
+        let settings = LoginThrottleSettings::default();
+        let now = Utc::now();
+        let locked = Some(now + Duration::minutes(3));
+        assert_eq!(
+            settings.retry_after(0, None, locked, now),
+            Some(Duration::minutes(3))
+        );
+
+        let last = Some(now - Duration::seconds(1));
+        assert_eq!(
+            settings.retry_after(3, last, None, now),
+            Some(Duration::seconds(3))
+        );
+
+        // Failures outside the window no longer count
+        let stale = Some(now - Duration::minutes(20));
+        assert_eq!(settings.retry_after(4, stale, None, now), None);
+        assert_eq!(settings.active_failures(4, stale, now), 0);
+
+        assert!(settings.should_lock(5, settings.max_failures));
+        assert!(!settings.should_lock(5, 0));
+    }
+
+    #[test]
+    fn thresholds_are_read_from_global_settings() {
+        let setting = |key: &str, value: &str| GlobalSetting {
+            key_name: key.to_string(),
+            value: value.to_string(),
+            updated_by: 1,
+            updated_at: Utc::now(),
+        };
+        let cache = SettingsCache::from_settings(vec![
+            setting("login_max_failures", "3"),
+            setting("login_lockout_minutes", "30"),
+        ]);
+        assert_eq!(cache.login_throttle.max_failures, 3);
+        assert_eq!(cache.login_throttle.lockout_minutes, 30);
+        assert_eq!(cache.login_throttle.ip_max_failures, 50);
+    }
+
+    #[test]
+    fn public_subset_hides_security_policy() {
+        let setting = |key: &str, value: &str| GlobalSetting {
+            key_name: key.to_string(),
+            value: value.to_string(),
+            updated_by: 1,
+            updated_at: Utc::now(),
+        };
+        let cache = SettingsCache::from_settings(vec![
+            setting(STUDENT_PROTECTIONS_KEY, "true"),
+            setting(REQUIRE_ADMIN_MFA_KEY, "true"),
+            setting("login_max_failures", "3"),
+        ]);
+
+        let public = cache.public_subset();
+        assert!(public.student_protections);
+        assert!(!public.require_admin_mfa);
+        assert_eq!(public.login_throttle, LoginThrottleSettings::default());
+    }
+
+    #[test]
+    fn student_protections_is_engine_owned() {
+        assert!(is_engine_owned("student_protections"));
+        assert!(!is_engine_owned(REQUIRE_ADMIN_MFA_KEY));
+        assert!(!is_engine_owned(RETENTION_PURGE_ENABLED_KEY));
+    }
+
+    #[test]
+    fn session_idle_timeout_is_optional_and_capped() {
+        let mut settings = SessionSettings::default();
+        assert_eq!(settings.idle_timeout_minutes(), Some(120));
+
+        settings.idle_timeout_minutes = 0;
+        assert_eq!(settings.idle_timeout_minutes(), None);
+
+        settings.idle_timeout_minutes = 600;
+        settings.max_lifetime_hours = 2;
+        assert_eq!(settings.idle_timeout_minutes(), Some(120));
+    }
 }
@@ -0,0 +1,562 @@
+use crate::app::models::student::GradeEnum;
+use chrono::NaiveDate;
+use csv::{ReaderBuilder, WriterBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ImportKind {
+    Students,
+    Enrollments,
+}
+
+impl ImportKind {
+    pub const ALL: [ImportKind; 2] = [ImportKind::Students, ImportKind::Enrollments];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportKind::Students => "students",
+            ImportKind::Enrollments => "enrollments",
+        }
+    }
+
+    // The template columns the bulk import reads
+    pub fn fields(&self) -> &'static [ImportField] {
+        match self {
+            ImportKind::Students => &STUDENT_FIELDS,
+            ImportKind::Enrollments => &ENROLLMENT_FIELDS,
+        }
+    }
+}
+
+impl fmt::Display for ImportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ImportKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ImportKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("Unknown import kind: {}", s))
+    }
+}
+
+// How a column's values are rewritten on the way in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Text,
+    Date,
+    Grade,
+    Boolean,
+}
+
+// A template column, with other names SIS exports commonly give it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportField {
+    pub column: &'static str,
+    pub label: &'static str,
+    pub field_type: FieldType,
+    pub aliases: &'static [&'static str],
+}
+
+const fn field(
+    column: &'static str,
+    label: &'static str,
+    field_type: FieldType,
+    aliases: &'static [&'static str],
+) -> ImportField {
+    ImportField {
+        column,
+        label,
+        field_type,
+        aliases,
+    }
+}
+
+pub const STUDENT_FIELDS: [ImportField; 18] = [
+    field(
+        "student_id",
+        "Student ID",
+        FieldType::Text,
+        &["student number", "student no", "sis id", "local id", "id"],
+    ),
+    field(
+        "firstname",
+        "First name",
+        FieldType::Text,
+        &["first", "first name", "given name", "legal first name"],
+    ),
+    field(
+        "lastname",
+        "Last name",
+        FieldType::Text,
+        &[
+            "last",
+            "last name",
+            "surname",
+            "family name",
+            "legal last name",
+        ],
+    ),
+    field(
+        "preferred",
+        "Preferred name",
+        FieldType::Text,
+        &["preferred name", "nickname"],
+    ),
+    field("gender", "Gender", FieldType::Text, &["sex"]),
+    field(
+        "date_of_birth",
+        "Date of birth",
+        FieldType::Date,
+        &["dob", "birthdate", "birth date"],
+    ),
+    field(
+        "current_grade_level",
+        "Grade",
+        FieldType::Grade,
+        &["grade", "grade level", "grade lvl", "gr"],
+    ),
+    field(
+        "teacher",
+        "Teacher",
+        FieldType::Text,
+        &["homeroom teacher", "teacher name", "homeroom"],
+    ),
+    field(
+        "esl",
+        "ESL language",
+        FieldType::Text,
+        &["ell", "home language", "language"],
+    ),
+    field(
+        "iep",
+        "IEP",
+        FieldType::Boolean,
+        &["sped", "special education"],
+    ),
+    field("bip", "BIP", FieldType::Boolean, &["behavior plan"]),
+    field("student_504", "504 plan", FieldType::Boolean, &["504"]),
+    field(
+        "readplan",
+        "READ plan",
+        FieldType::Boolean,
+        &["read plan", "reading plan"],
+    ),
+    field(
+        "gt",
+        "Gifted and talented",
+        FieldType::Boolean,
+        &["gifted", "gate"],
+    ),
+    field(
+        "intervention",
+        "Intervention",
+        FieldType::Text,
+        &["intervention program"],
+    ),
+    field(
+        "eye_glasses",
+        "Glasses",
+        FieldType::Boolean,
+        &["glasses", "eyeglasses"],
+    ),
+    field("notes", "Notes", FieldType::Text, &["comments"]),
+    field("pin", "PIN", FieldType::Text, &["student pin", "login pin"]),
+];
+
+pub const ENROLLMENT_FIELDS: [ImportField; 5] = [
+    field(
+        "student_id",
+        "Student ID",
+        FieldType::Text,
+        &["student number", "student no", "sis id", "local id"],
+    ),
+    field(
+        "academic_year",
+        "Academic year",
+        FieldType::Text,
+        &["school year", "year"],
+    ),
+    field(
+        "grade_level",
+        "Grade",
+        FieldType::Grade,
+        &["grade", "grade lvl", "gr"],
+    ),
+    field(
+        "teacher_id",
+        "Teacher ID",
+        FieldType::Text,
+        &["staff id", "teacher number", "homeroom teacher id"],
+    ),
+    field("notes", "Notes", FieldType::Text, &["comments"]),
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DateFormat {
+    #[default]
+    Iso,
+    MonthDayYear,
+    DayMonthYear,
+    MonthDayShortYear,
+    Compact,
+}
+
+impl DateFormat {
+    // Two-digit years come before four-digit ones, which would otherwise
+    // read "09/15/15" as the year 15
+    pub const ALL: [DateFormat; 5] = [
+        DateFormat::Iso,
+        DateFormat::MonthDayShortYear,
+        DateFormat::MonthDayYear,
+        DateFormat::DayMonthYear,
+        DateFormat::Compact,
+    ];
+
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "%Y-%m-%d",
+            DateFormat::MonthDayYear => "%m/%d/%Y",
+            DateFormat::DayMonthYear => "%d/%m/%Y",
+            DateFormat::MonthDayShortYear => "%m/%d/%y",
+            DateFormat::Compact => "%Y%m%d",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "YYYY-MM-DD",
+            DateFormat::MonthDayYear => "MM/DD/YYYY",
+            DateFormat::DayMonthYear => "DD/MM/YYYY",
+            DateFormat::MonthDayShortYear => "MM/DD/YY",
+            DateFormat::Compact => "YYYYMMDD",
+        }
+    }
+
+    // The first format every sample parses as. Month-first wins over
+    // day-first when both fit.
+    pub fn detect<'a>(samples: impl IntoIterator<Item = &'a str>) -> Option<DateFormat> {
+        let samples: Vec<&str> = samples
+            .into_iter()
+            .filter(|sample| !sample.is_empty())
+            .collect();
+        if samples.is_empty() {
+            return None;
+        }
+        DateFormat::ALL.into_iter().find(|format| {
+            samples
+                .iter()
+                .all(|sample| NaiveDate::parse_from_str(sample, format.pattern()).is_ok())
+        })
+    }
+}
+
+// Value rewrites applied by column type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValueTransforms {
+    // Dates are read in this format and written as YYYY-MM-DD
+    pub date_format: DateFormat,
+    // "K", "00", "3", "03", "3rd" and the like become the grade's full name
+    pub grade_aliases: bool,
+    // Y/N, Yes/No, 1/0 and blank become true and false
+    pub yes_no_booleans: bool,
+}
+
+impl Default for ValueTransforms {
+    fn default() -> Self {
+        ValueTransforms {
+            date_format: DateFormat::Iso,
+            grade_aliases: true,
+            yes_no_booleans: true,
+        }
+    }
+}
+
+pub fn normalize_grade(value: &str) -> Option<GradeEnum> {
+    if let Ok(grade) = GradeEnum::from_str(value) {
+        return Some(grade);
+    }
+    let value: String = value
+        .to_lowercase()
+        .replace("grade", "")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect();
+    if matches!(
+        value.as_str(),
+        "k" | "kg" | "kn" | "kinder" | "kindergarten"
+    ) {
+        return Some(GradeEnum::Kindergarten);
+    }
+    let number = ["st", "nd", "rd", "th", "gr"]
+        .iter()
+        .find_map(|suffix| value.strip_suffix(suffix))
+        .unwrap_or(&value);
+    match number.parse::<u8>().ok()? {
+        0 => Some(GradeEnum::Kindergarten),
+        1 => Some(GradeEnum::First),
+        2 => Some(GradeEnum::Second),
+        3 => Some(GradeEnum::Third),
+        4 => Some(GradeEnum::Fourth),
+        5 => Some(GradeEnum::Fifth),
+        6 => Some(GradeEnum::Sixth),
+        7 => Some(GradeEnum::Seventh),
+        8 => Some(GradeEnum::Eighth),
+        9 => Some(GradeEnum::Ninth),
+        10 => Some(GradeEnum::Tenth),
+        11 => Some(GradeEnum::Eleventh),
+        12 => Some(GradeEnum::Twelfth),
+        _ => None,
+    }
+}
+
+pub fn normalize_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "y" | "yes" | "true" | "t" | "1" | "x" => Some(true),
+        "n" | "no" | "false" | "f" | "0" | "" => Some(false),
+        _ => None,
+    }
+}
+
+// Which column of an uploaded file feeds each template column, and how
+// its values are rewritten
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    // Template column to the file's header
+    pub columns: BTreeMap<String, String>,
+    pub transforms: ValueTransforms,
+}
+
+impl ColumnMapping {
+    pub fn source_for(&self, column: &str) -> Option<&str> {
+        self.columns.get(column).map(String::as_str)
+    }
+
+    fn transform(&self, field: &ImportField, value: &str) -> String {
+        let value = value.trim();
+        let rewritten = match field.field_type {
+            FieldType::Text => None,
+            FieldType::Date => {
+                NaiveDate::parse_from_str(value, self.transforms.date_format.pattern())
+                    .ok()
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+            }
+            FieldType::Grade if self.transforms.grade_aliases => {
+                normalize_grade(value).map(|grade| grade.to_string())
+            }
+            FieldType::Boolean if self.transforms.yes_no_booleans => {
+                normalize_bool(value).map(|value| value.to_string())
+            }
+            FieldType::Grade | FieldType::Boolean => None,
+        };
+        // Values that don't fit are passed on for validation to report
+        rewritten.unwrap_or_else(|| value.to_string())
+    }
+
+    // Rewrites `file_contents` under the template headers the bulk import
+    // reads. Template columns left unmapped come out empty; file columns the
+    // mapping doesn't use are kept, so custom attribute columns still reach
+    // the import.
+    pub fn apply(&self, kind: ImportKind, file_contents: &str) -> Result<String, String> {
+        let mut reader = ReaderBuilder::new()
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(file_contents.as_bytes());
+        let headers = reader
+            .headers()
+            .map_err(|e| format!("Failed to read CSV headers: {}", e))?
+            .clone();
+        let position = |header: &str| headers.iter().position(|h| h.trim() == header);
+
+        let fields = kind.fields();
+        let sources: Vec<Option<usize>> = fields
+            .iter()
+            .map(|field| self.source_for(field.column).and_then(position))
+            .collect();
+        let used: HashSet<usize> = sources.iter().flatten().copied().collect();
+        let template: HashSet<&str> = fields.iter().map(|field| field.column).collect();
+        let kept: Vec<usize> = headers
+            .iter()
+            .enumerate()
+            .filter(|(index, header)| !used.contains(index) && !template.contains(header.trim()))
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut writer = WriterBuilder::new().from_writer(Vec::new());
+        let write_error = |e: csv::Error| format!("Failed to write the mapped CSV: {}", e);
+        writer
+            .write_record(
+                fields.iter().map(|field| field.column).chain(
+                    kept.iter()
+                        .map(|index| headers.get(*index).unwrap_or_default()),
+                ),
+            )
+            .map_err(write_error)?;
+
+        for record in reader.records() {
+            let record = record.map_err(|e| format!("Failed to read the CSV: {}", e))?;
+            let mapped = fields.iter().zip(&sources).map(|(field, source)| {
+                let value = source
+                    .and_then(|index| record.get(index))
+                    .unwrap_or_default();
+                self.transform(field, value)
+            });
+            let passed = kept
+                .iter()
+                .map(|index| record.get(*index).unwrap_or_default().to_string());
+            writer
+                .write_record(mapped.chain(passed).collect::<Vec<_>>())
+                .map_err(write_error)?;
+        }
+
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| format!("Failed to write the mapped CSV: {}", e))?;
+        String::from_utf8(bytes).map_err(|e| format!("Failed to write the mapped CSV: {}", e))
+    }
+}
+
+// A mapping saved for one source system, such as a district's SIS export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappingProfile {
+    pub id: i32,
+    pub name: String,
+    pub kind: ImportKind,
+    pub mapping: ColumnMapping,
+}
+
+impl MappingProfile {
+    // Whether every column the profile reads is among `headers`
+    pub fn fits(&self, headers: &[String]) -> bool {
+        !self.mapping.columns.is_empty()
+            && self
+                .mapping
+                .columns
+                .values()
+                .all(|source| headers.iter().any(|header| header == source))
+    }
+}
+
+// Saving under an existing name for the same kind replaces that profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveMappingProfileRequest {
+    pub name: String,
+    pub kind: ImportKind,
+    pub mapping: ColumnMapping,
+}
+
+impl SaveMappingProfileRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Give the profile a name, such as the SIS the file comes from".to_string());
+        }
+        if self.mapping.columns.is_empty() {
+            return Err("Map at least one column before saving".to_string());
+        }
+        let fields = self.kind.fields();
+        if let Some(column) = self
+            .mapping
+            .columns
+            .keys()
+            .find(|column| !fields.iter().any(|field| field.column == column.as_str()))
+        {
+            return Err(format!("{} is not a {} column", column, self.kind));
+        }
+        Ok(())
+    }
+}
+
+// What the mapping step starts from: the file's headers, and either a saved
+// profile that fits them or columns matched by header name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MappingSuggestion {
+    pub headers: Vec<String>,
+    pub mapping: ColumnMapping,
+    pub profile_id: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grade_aliases_name_the_grade() {
+        for alias in ["K", "KG", "Kindergarten", "00", "0"] {
+            assert_eq!(
+                normalize_grade(alias),
+                Some(GradeEnum::Kindergarten),
+                "{}",
+                alias
+            );
+        }
+        for alias in ["3", "03", "3rd", "Grade 3", "3rd Grade"] {
+            assert_eq!(normalize_grade(alias), Some(GradeEnum::Third), "{}", alias);
+        }
+        assert_eq!(normalize_grade("13"), None);
+        assert_eq!(normalize_grade("PK"), None);
+    }
+
+    #[test]
+    fn date_formats_are_detected_from_samples() {
+        assert_eq!(DateFormat::detect(["2015-09-15"]), Some(DateFormat::Iso));
+        assert_eq!(
+            DateFormat::detect(["9/15/2015", "12/1/2014"]),
+            Some(DateFormat::MonthDayYear)
+        );
+        assert_eq!(
+            DateFormat::detect(["09/15/15"]),
+            Some(DateFormat::MonthDayShortYear)
+        );
+        assert_eq!(
+            DateFormat::detect(["15/09/2015"]),
+            Some(DateFormat::DayMonthYear)
+        );
+        assert_eq!(DateFormat::detect(["20150915"]), Some(DateFormat::Compact));
+        assert_eq!(DateFormat::detect(["soon"]), None);
+    }
+
+    #[test]
+    fn mapping_rewrites_columns_and_values() {
+        let csv = "Student Number,Grade Lvl,DOB,SPED,Bus Route\n1001,K,9/15/2015,Y,12\n1002,03,12/01/2014,,7\n";
+        let mapping = ColumnMapping {
+            columns: BTreeMap::from([
+                ("student_id".to_string(), "Student Number".to_string()),
+                ("current_grade_level".to_string(), "Grade Lvl".to_string()),
+                ("date_of_birth".to_string(), "DOB".to_string()),
+                ("iep".to_string(), "SPED".to_string()),
+            ]),
+            transforms: ValueTransforms {
+                date_format: DateFormat::detect(["9/15/2015", "12/01/2014"]).unwrap(),
+                ..ValueTransforms::default()
+            },
+        };
+        let mapped = mapping.apply(ImportKind::Students, csv).unwrap();
+
+        let mut reader = ReaderBuilder::new().from_reader(mapped.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(headers.len(), STUDENT_FIELDS.len() + 1);
+        assert_eq!(headers.get(STUDENT_FIELDS.len()), Some("Bus Route"));
+
+        let rows: Vec<_> = reader.records().map(Result::unwrap).collect();
+        let cell = |row: usize, column: &str| {
+            let index = headers.iter().position(|h| h == column).unwrap();
+            rows[row].get(index).unwrap().to_string()
+        };
+        assert_eq!(cell(0, "student_id"), "1001");
+        assert_eq!(cell(0, "current_grade_level"), "Kindergarten");
+        assert_eq!(cell(0, "date_of_birth"), "2015-09-15");
+        assert_eq!(cell(0, "iep"), "true");
+        assert_eq!(cell(1, "current_grade_level"), "3rd Grade");
+        assert_eq!(cell(1, "iep"), "false");
+        assert_eq!(cell(1, "firstname"), "");
+        assert_eq!(cell(1, "Bus Route"), "7");
+    }
+}
@@ -0,0 +1,306 @@
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Column the annotated CSV lists each row's problems under
+pub const ANNOTATION_COLUMN: &str = "import_errors";
+
+// One problem with an uploaded CSV. Rows are numbered the way a spreadsheet
+// shows them, with the header on row 1; an empty column means the whole row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowIssue {
+    pub row: usize,
+    pub column: String,
+    pub value: String,
+    pub message: String,
+}
+
+impl RowIssue {
+    pub fn describe(&self) -> String {
+        match (self.column.is_empty(), self.value.is_empty()) {
+            (true, _) => format!("Row {}: {}", self.row, self.message),
+            (false, true) => format!("Row {}, {}: {}", self.row, self.column, self.message),
+            (false, false) => format!(
+                "Row {}, {} '{}': {}",
+                self.row, self.column, self.value, self.message
+            ),
+        }
+    }
+}
+
+// Every problem a bulk upload would run into, found without writing anything
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportValidation {
+    pub total_rows: usize,
+    pub issues: Vec<RowIssue>,
+    // The uploaded CSV with each row's problems in an extra column
+    pub annotated_csv: String,
+}
+
+impl ImportValidation {
+    pub fn invalid_rows(&self) -> usize {
+        self.issues
+            .iter()
+            .map(|issue| issue.row)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    pub fn valid_rows(&self) -> usize {
+        self.total_rows.saturating_sub(self.invalid_rows())
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_valid() {
+            format!("All {} rows are ready to import", self.total_rows)
+        } else {
+            format!(
+                "{} of {} rows have problems; {} can be imported",
+                self.invalid_rows(),
+                self.total_rows,
+                self.valid_rows()
+            )
+        }
+    }
+}
+
+// The rows of an upload that parsed, with the problems found in any row.
+// A row can parse and still have problems, such as a duplicate ID.
+#[derive(Debug)]
+pub struct ParsedRows<T> {
+    pub rows: Vec<(usize, T)>,
+    pub issues: Vec<RowIssue>,
+    pub total_rows: usize,
+}
+
+impl<T> Default for ParsedRows<T> {
+    fn default() -> Self {
+        ParsedRows {
+            rows: Vec::new(),
+            issues: Vec::new(),
+            total_rows: 0,
+        }
+    }
+}
+
+impl<T> ParsedRows<T> {
+    pub fn flag(&mut self, row: usize, column: &str, value: impl ToString, message: String) {
+        self.issues.push(RowIssue {
+            row,
+            column: column.to_string(),
+            value: value.to_string(),
+            message,
+        });
+    }
+
+    // Rows without any problem
+    pub fn valid(self) -> Vec<T> {
+        let invalid: BTreeSet<usize> = self.issues.iter().map(|issue| issue.row).collect();
+        self.rows
+            .into_iter()
+            .filter(|(row, _)| !invalid.contains(row))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    // Every row, or every problem if there are any
+    pub fn into_all(mut self) -> Result<Vec<T>, String> {
+        if self.issues.is_empty() {
+            return Ok(self.rows.into_iter().map(|(_, value)| value).collect());
+        }
+        self.issues.sort_by_key(|issue| issue.row);
+        Err(format!(
+            "Validation errors:\n{}",
+            self.issues
+                .iter()
+                .map(RowIssue::describe)
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+    }
+
+    pub fn report(&self, file_contents: &str) -> Result<ImportValidation, String> {
+        let mut issues = self.issues.clone();
+        issues.sort_by_key(|issue| issue.row);
+        Ok(ImportValidation {
+            total_rows: self.total_rows,
+            annotated_csv: annotate_csv(file_contents, &issues)?,
+            issues,
+        })
+    }
+}
+
+// Reads one data row's cells by column name, keeping a RowIssue for every
+// cell that fails to parse
+pub struct RowReader<'a> {
+    headers: &'a StringRecord,
+    record: &'a StringRecord,
+    row: usize,
+    issues: Vec<RowIssue>,
+}
+
+impl<'a> RowReader<'a> {
+    pub fn new(headers: &'a StringRecord, record: &'a StringRecord, row: usize) -> Self {
+        RowReader {
+            headers,
+            record,
+            row,
+            issues: Vec::new(),
+        }
+    }
+
+    // The cell under `column`, or "" if the file or the row has none
+    pub fn text(&self, column: &str) -> &'a str {
+        self.headers
+            .iter()
+            .position(|header| header == column)
+            .and_then(|index| self.record.get(index))
+            .map(str::trim)
+            .unwrap_or_default()
+    }
+
+    pub fn parse<T>(
+        &mut self,
+        column: &str,
+        parse: impl FnOnce(&str) -> Result<T, String>,
+    ) -> Option<T> {
+        match parse(self.text(column)) {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.flag(column, message);
+                None
+            }
+        }
+    }
+
+    pub fn flag(&mut self, column: &str, message: String) {
+        self.issues.push(RowIssue {
+            row: self.row,
+            column: column.to_string(),
+            value: self.text(column).to_string(),
+            message,
+        });
+    }
+
+    pub fn finish(self) -> Vec<RowIssue> {
+        self.issues
+    }
+}
+
+// Required columns the header row leaves out
+pub fn missing_columns<'a>(headers: &StringRecord, required: &[&'a str]) -> Vec<&'a str> {
+    required
+        .iter()
+        .filter(|column| !headers.iter().any(|header| header == **column))
+        .copied()
+        .collect()
+}
+
+pub fn parse_bool(value: &str) -> Result<bool, String> {
+    if value.eq_ignore_ascii_case("true") {
+        Ok(true)
+    } else if value.eq_ignore_ascii_case("false") {
+        Ok(false)
+    } else {
+        Err("Expected true or false".to_string())
+    }
+}
+
+pub fn parse_id(value: &str, label: &str, min: i32, max: i32) -> Result<i32, String> {
+    let id = value
+        .parse::<i32>()
+        .map_err(|_| format!("{} must be a whole number", label))?;
+    if id < min || id > max {
+        return Err(format!(
+            "{} is out of the valid range ({}-{})",
+            label, min, max
+        ));
+    }
+    Ok(id)
+}
+
+// The uploaded CSV with each row's problems appended in ANNOTATION_COLUMN,
+// so the file can be fixed in a spreadsheet and uploaded again
+pub fn annotate_csv(file_contents: &str, issues: &[RowIssue]) -> Result<String, String> {
+    let mut by_row: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for issue in issues {
+        let text = if issue.column.is_empty() {
+            issue.message.clone()
+        } else {
+            format!("{}: {}", issue.column, issue.message)
+        };
+        by_row.entry(issue.row).or_default().push(text);
+    }
+
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_reader(file_contents.as_bytes());
+    let mut writer = WriterBuilder::new().flexible(true).from_writer(Vec::new());
+    let write_error = |e: csv::Error| format!("Failed to write the annotated CSV: {}", e);
+
+    let mut headers = reader
+        .headers()
+        .map_err(|e| format!("Failed to read CSV headers: {}", e))?
+        .clone();
+    headers.push_field(ANNOTATION_COLUMN);
+    writer.write_record(&headers).map_err(write_error)?;
+
+    for (index, record) in reader.records().enumerate() {
+        let row = index + 2;
+        let mut record = record.unwrap_or_default();
+        record.push_field(
+            &by_row
+                .get(&row)
+                .map(|messages| messages.join("; "))
+                .unwrap_or_default(),
+        );
+        writer.write_record(&record).map_err(write_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| format!("Failed to write the annotated CSV: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Failed to write the annotated CSV: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn annotated_csv_lists_problems_by_row() {
+        let csv = "student_id,grade\n1,Kindergarten\n2,Sixth\n3,1st Grade\n";
+        let issues = vec![
+            RowIssue {
+                row: 3,
+                column: "grade".to_string(),
+                value: "Sixth".to_string(),
+                message: "Invalid grade".to_string(),
+            },
+            RowIssue {
+                row: 3,
+                column: String::new(),
+                value: String::new(),
+                message: "Duplicate".to_string(),
+            },
+        ];
+        let annotated = annotate_csv(csv, &issues).unwrap();
+        let lines: Vec<&str> = annotated.lines().collect();
+        assert_eq!(lines[0], "student_id,grade,import_errors");
+        assert_eq!(lines[1], "1,Kindergarten,");
+        assert_eq!(lines[2], "2,Sixth,grade: Invalid grade; Duplicate");
+
+        let report = ImportValidation {
+            total_rows: 3,
+            issues,
+            annotated_csv: annotated,
+        };
+        assert_eq!(report.invalid_rows(), 1);
+        assert_eq!(report.valid_rows(), 2);
+    }
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+// Number of one-time recovery codes issued at enrollment
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+// Returned by login when the password was right but a second factor is needed.
+// `enrollment` is set when policy requires MFA and the account has none yet,
+// so the user can set up an authenticator before the session is created.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfaChallenge {
+    pub token: String,
+    pub enrollment: Option<MfaEnrollment>,
+}
+
+// What the user needs to add the account to an authenticator app
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfaEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_svg: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MfaStatus {
+    pub enabled: bool,
+    pub enrolled_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub recovery_codes_remaining: i64,
+    // Policy requires MFA for this account, so it can't be turned off
+    pub required: bool,
+}
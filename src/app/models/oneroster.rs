@@ -0,0 +1,279 @@
+use crate::app::models::employee::EmployeeRole;
+use crate::app::models::enrollment::AcademicYear;
+use crate::app::models::student::{GenderEnum, GradeEnum};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub const ROLE_STUDENT: &str = "student";
+pub const ROLE_TEACHER: &str = "teacher";
+
+// OneRoster 1.2 records as read from or written to a CSV bundle. Only the
+// columns the app maps are kept; the rest are written blank.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterOrg {
+    pub sourced_id: String,
+    pub name: String,
+    // school, district, ...
+    pub org_type: String,
+    pub identifier: Option<String>,
+    pub parent_sourced_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterAcademicSession {
+    pub sourced_id: String,
+    pub title: String,
+    // schoolYear, term, semester or gradingPeriod
+    pub session_type: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub parent_sourced_id: Option<String>,
+    // The calendar year the school year ends in
+    pub school_year: i32,
+}
+
+impl RosterAcademicSession {
+    pub fn academic_year(&self) -> Option<AcademicYear> {
+        academic_year_ending(self.school_year)
+    }
+}
+
+// A person in users.csv, with their primary role from roles.csv and their
+// birth date and sex from demographics.csv
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterUser {
+    pub sourced_id: String,
+    pub enabled: bool,
+    pub username: String,
+    // The SIS's own number for the person; a student's ID
+    pub identifier: Option<String>,
+    pub given_name: String,
+    pub family_name: String,
+    pub preferred_given_name: Option<String>,
+    pub email: Option<String>,
+    pub grades: Vec<String>,
+    pub role: String,
+    pub org_sourced_id: Option<String>,
+    pub birth_date: Option<NaiveDate>,
+    pub sex: Option<String>,
+}
+
+impl RosterUser {
+    pub fn is_student(&self) -> bool {
+        self.role == ROLE_STUDENT
+    }
+
+    pub fn is_teacher(&self) -> bool {
+        self.role == ROLE_TEACHER
+    }
+
+    pub fn student_id(&self) -> Option<i32> {
+        self.identifier
+            .as_deref()
+            .and_then(|identifier| identifier.trim().parse().ok())
+            .filter(|id| (0..=2_000_000_000).contains(id))
+    }
+
+    pub fn grade(&self) -> Option<GradeEnum> {
+        self.grades.iter().find_map(|code| grade_from_code(code))
+    }
+
+    pub fn gender(&self) -> Option<GenderEnum> {
+        self.sex.as_deref().and_then(gender_from_sex)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterCourse {
+    pub sourced_id: String,
+    pub title: String,
+    pub course_code: Option<String>,
+    pub school_year_sourced_id: Option<String>,
+    pub org_sourced_id: String,
+    pub grades: Vec<String>,
+    pub subjects: Vec<String>,
+}
+
+// A OneRoster class is what the app calls a course: one section taught by
+// one teacher in one year
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterClass {
+    pub sourced_id: String,
+    pub title: String,
+    pub course_sourced_id: String,
+    pub class_code: Option<String>,
+    // homeroom or scheduled
+    pub class_type: String,
+    pub location: Option<String>,
+    pub school_sourced_id: String,
+    pub term_sourced_ids: Vec<String>,
+    pub grades: Vec<String>,
+    pub subjects: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterEnrollment {
+    pub sourced_id: String,
+    pub class_sourced_id: String,
+    pub school_sourced_id: String,
+    pub user_sourced_id: String,
+    pub role: String,
+    pub primary: bool,
+    pub begin_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+impl RosterEnrollment {
+    pub fn is_active_on(&self, date: NaiveDate) -> bool {
+        self.begin_date.is_none_or(|begin| begin <= date)
+            && self.end_date.is_none_or(|end| end >= date)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterBundle {
+    // source.systemName from the manifest
+    pub source_system: Option<String>,
+    pub orgs: Vec<RosterOrg>,
+    pub academic_sessions: Vec<RosterAcademicSession>,
+    pub courses: Vec<RosterCourse>,
+    pub classes: Vec<RosterClass>,
+    pub users: Vec<RosterUser>,
+    pub enrollments: Vec<RosterEnrollment>,
+}
+
+// What a OneRoster import changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterImportSummary {
+    pub source_system: Option<String>,
+    pub orgs: usize,
+    pub academic_sessions: usize,
+    pub students_created: usize,
+    pub students_updated: usize,
+    pub teachers_created: usize,
+    pub teachers_updated: usize,
+    pub classes_created: usize,
+    pub classes_updated: usize,
+    pub enrollments_created: usize,
+    pub enrollments_updated: usize,
+    // Users in a role the app doesn't keep, such as parents and aides
+    pub users_skipped: usize,
+}
+
+// A OneRoster CSV bundle handed to the browser, base64 encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RosterExportBundle {
+    pub filename: String,
+    pub archive_base64: String,
+}
+
+// CEDS grade codes as used by OneRoster, plus the bare numbers and "K" some
+// SIS exports send instead
+pub fn grade_from_code(code: &str) -> Option<GradeEnum> {
+    match code.trim().to_ascii_uppercase().as_str() {
+        "KG" | "K" | "0" | "00" => Some(GradeEnum::Kindergarten),
+        "01" | "1" => Some(GradeEnum::First),
+        "02" | "2" => Some(GradeEnum::Second),
+        "03" | "3" => Some(GradeEnum::Third),
+        "04" | "4" => Some(GradeEnum::Fourth),
+        "05" | "5" => Some(GradeEnum::Fifth),
+        "06" | "6" => Some(GradeEnum::Sixth),
+        "07" | "7" => Some(GradeEnum::Seventh),
+        "08" | "8" => Some(GradeEnum::Eighth),
+        "09" | "9" => Some(GradeEnum::Ninth),
+        "10" => Some(GradeEnum::Tenth),
+        "11" => Some(GradeEnum::Eleventh),
+        "12" => Some(GradeEnum::Twelfth),
+        _ => None,
+    }
+}
+
+pub fn grade_code(grade: &GradeEnum) -> &'static str {
+    match grade {
+        GradeEnum::Kindergarten => "KG",
+        GradeEnum::First => "01",
+        GradeEnum::Second => "02",
+        GradeEnum::Third => "03",
+        GradeEnum::Fourth => "04",
+        GradeEnum::Fifth => "05",
+        GradeEnum::Sixth => "06",
+        GradeEnum::Seventh => "07",
+        GradeEnum::Eighth => "08",
+        GradeEnum::Ninth => "09",
+        GradeEnum::Tenth => "10",
+        GradeEnum::Eleventh => "11",
+        GradeEnum::Twelfth => "12",
+    }
+}
+
+pub fn gender_from_sex(sex: &str) -> Option<GenderEnum> {
+    match sex.trim().to_ascii_lowercase().as_str() {
+        "male" | "m" => Some(GenderEnum::Male),
+        "female" | "f" => Some(GenderEnum::Female),
+        "other" | "x" | "nonbinary" | "non-binary" => Some(GenderEnum::Nonbinary),
+        _ => None,
+    }
+}
+
+pub fn sex_of(gender: &GenderEnum) -> &'static str {
+    match gender {
+        GenderEnum::Male => "male",
+        GenderEnum::Female => "female",
+        GenderEnum::Nonbinary => "other",
+    }
+}
+
+// The OneRoster role an employee is exported with. Only teachers are read
+// back on import.
+pub fn roster_role_of(role: &EmployeeRole) -> &'static str {
+    match role {
+        EmployeeRole::Teacher { .. }
+        | EmployeeRole::Interventionist
+        | EmployeeRole::IntegratedServices
+        | EmployeeRole::Speech
+        | EmployeeRole::OT => ROLE_TEACHER,
+        EmployeeRole::Principal => "principal",
+        EmployeeRole::AssistantPrincipal => "siteAdministrator",
+        EmployeeRole::Psychologist => "counselor",
+        EmployeeRole::ParaProf => "aide",
+        EmployeeRole::AssessmentCoordinator | EmployeeRole::Other => "administrator",
+    }
+}
+
+// 2025 is the 2024-2025 school year
+pub fn academic_year_ending(school_year: i32) -> Option<AcademicYear> {
+    AcademicYear::from_str(&format!("{}-{}", school_year - 1, school_year)).ok()
+}
+
+pub fn school_year_of(academic_year: &AcademicYear) -> i32 {
+    academic_year
+        .to_string()
+        .rsplit('-')
+        .next()
+        .and_then(|year| year.parse().ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grade_codes_round_trip() {
+        for code in ["KG", "01", "05", "09", "12"] {
+            assert_eq!(grade_code(&grade_from_code(code).unwrap()), code);
+        }
+        assert_eq!(grade_from_code("k"), Some(GradeEnum::Kindergarten));
+        assert_eq!(grade_from_code(" 3 "), Some(GradeEnum::Third));
+        assert_eq!(grade_from_code("PK"), None);
+    }
+
+    #[test]
+    fn school_years_map_to_academic_years() {
+        let year = academic_year_ending(2025).unwrap();
+        assert_eq!(year, AcademicYear::Year2024_2025);
+        assert_eq!(school_year_of(&year), 2025);
+        assert_eq!(academic_year_ending(2040), None);
+    }
+}
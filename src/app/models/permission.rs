@@ -0,0 +1,372 @@
+use crate::app::errors::AuthError;
+use crate::app::models::user::{SessionUser, UserRole};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+// Every #[server] fn declares one of these before it touches the database.
+// The role each permission maps to lives here so the policy can be read (and
+// tested) in one place instead of being scattered across the endpoints.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, EnumIter)]
+pub enum Permission {
+    // No session needed: login/registration and anonymous student test delivery
+    Public,
+    // Any signed in account, including guests
+    Authenticated,
+    ViewStudents,
+    ManageStudents,
+    ViewScores,
+    ManageScores,
+    ViewTests,
+    ManageTests,
+    ViewAssessments,
+    ManageAssessments,
+    ViewCourses,
+    ManageCourses,
+    ViewEnrollments,
+    ManageEnrollments,
+    ViewStaff,
+    ManageStaff,
+    ConductTestSessions,
+    ManageUsers,
+    ManageSettings,
+    ManageSso,
+    ViewAuditLog,
+    ManageStudentProtection,
+}
+
+impl Permission {
+    // Lowest role that holds this permission; None means no session is needed
+    pub fn minimum_role(&self) -> Option<UserRole> {
+        match self {
+            Permission::Public => None,
+            Permission::Authenticated => Some(UserRole::Guest),
+            Permission::ViewStudents
+            | Permission::ViewScores
+            | Permission::ManageScores
+            | Permission::ViewTests
+            | Permission::ManageTests
+            | Permission::ViewAssessments
+            | Permission::ManageAssessments
+            | Permission::ViewCourses
+            | Permission::ViewEnrollments
+            | Permission::ViewStaff
+            | Permission::ConductTestSessions => Some(UserRole::Teacher),
+            Permission::ManageStudents
+            | Permission::ManageCourses
+            | Permission::ManageEnrollments
+            | Permission::ManageStaff
+            | Permission::ManageUsers
+            | Permission::ManageSettings
+            | Permission::ManageSso
+            | Permission::ViewAuditLog => Some(UserRole::Admin),
+            Permission::ManageStudentProtection => Some(UserRole::SuperAdmin),
+        }
+    }
+
+    pub fn is_granted_to(&self, user: &SessionUser) -> bool {
+        self.is_granted_to_role(user) && self.is_in_token_scope(user)
+    }
+
+    fn is_granted_to_role(&self, user: &SessionUser) -> bool {
+        match self.minimum_role() {
+            Some(role) => user.has_role(role),
+            None => true,
+        }
+    }
+
+    // Browser sessions carry no token scopes; API tokens only reach
+    // permissions one of their scopes covers
+    fn is_in_token_scope(&self, user: &SessionUser) -> bool {
+        match &user.token_scopes {
+            Some(scopes) => {
+                self.minimum_role().is_none() || scopes.iter().any(|scope| scope.grants(*self))
+            }
+            None => true,
+        }
+    }
+
+    // Pure policy check used by the server-side guards
+    pub fn check(&self, user: Option<&SessionUser>) -> Result<(), AuthError> {
+        match (self.minimum_role(), user) {
+            (None, _) => Ok(()),
+            (Some(_), None) => Err(AuthError::NotAuthenticated),
+            (Some(_), Some(user)) if !self.is_granted_to_role(user) => {
+                Err(AuthError::Forbidden(*self))
+            }
+            (Some(_), Some(user)) if !self.is_in_token_scope(user) => {
+                Err(AuthError::OutOfScope(*self))
+            }
+            (Some(_), Some(_)) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Permission::Public => "public",
+            Permission::Authenticated => "authenticated",
+            Permission::ViewStudents => "students:read",
+            Permission::ManageStudents => "students:write",
+            Permission::ViewScores => "scores:read",
+            Permission::ManageScores => "scores:write",
+            Permission::ViewTests => "tests:read",
+            Permission::ManageTests => "tests:write",
+            Permission::ViewAssessments => "assessments:read",
+            Permission::ManageAssessments => "assessments:write",
+            Permission::ViewCourses => "courses:read",
+            Permission::ManageCourses => "courses:write",
+            Permission::ViewEnrollments => "enrollments:read",
+            Permission::ManageEnrollments => "enrollments:write",
+            Permission::ViewStaff => "staff:read",
+            Permission::ManageStaff => "staff:write",
+            Permission::ConductTestSessions => "sessions:conduct",
+            Permission::ManageUsers => "users:manage",
+            Permission::ManageSettings => "settings:manage",
+            Permission::ManageSso => "sso:manage",
+            Permission::ViewAuditLog => "audit:read",
+            Permission::ManageStudentProtection => "protection:manage",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Scopes a personal access token can carry. Each covers a fixed set of
+// permissions; the owner's role still applies on top, so a scope never grants
+// more than the account itself has.
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, EnumIter)]
+pub enum TokenScope {
+    ScoresRead,
+    ScoresWrite,
+    RosterRead,
+    RosterWrite,
+}
+
+impl TokenScope {
+    pub fn permissions(&self) -> &'static [Permission] {
+        match self {
+            TokenScope::ScoresRead => &[
+                Permission::ViewScores,
+                Permission::ViewTests,
+                Permission::ViewAssessments,
+            ],
+            TokenScope::ScoresWrite => &[
+                Permission::ViewScores,
+                Permission::ManageScores,
+                Permission::ViewTests,
+                Permission::ViewAssessments,
+            ],
+            TokenScope::RosterRead => &[
+                Permission::ViewStudents,
+                Permission::ViewCourses,
+                Permission::ViewEnrollments,
+            ],
+            TokenScope::RosterWrite => &[
+                Permission::ViewStudents,
+                Permission::ManageStudents,
+                Permission::ViewCourses,
+                Permission::ManageCourses,
+                Permission::ViewEnrollments,
+                Permission::ManageEnrollments,
+            ],
+        }
+    }
+
+    pub fn grants(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+
+    // Only scopes the account's role could use in full can be minted
+    pub fn is_available_to(&self, user: &SessionUser) -> bool {
+        self.permissions()
+            .iter()
+            .all(|permission| permission.is_granted_to_role(user))
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            TokenScope::ScoresRead => "Read scores, tests and assessments",
+            TokenScope::ScoresWrite => "Read and record scores",
+            TokenScope::RosterRead => "Read students, courses and enrollments",
+            TokenScope::RosterWrite => "Read and update students, courses and enrollments",
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TokenScope::ScoresRead => "scores:read",
+            TokenScope::ScoresWrite => "scores:write",
+            TokenScope::RosterRead => "roster:read",
+            TokenScope::RosterWrite => "roster:write",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TokenScope::iter()
+            .find(|scope| scope.to_string() == s)
+            .ok_or_else(|| format!("Unknown token scope: {}", s))
+    }
+}
+
+// Which students' records a caller may read. Admins see every student; teachers
+// only see their caseload, i.e. students actively enrolled with them either
+// directly or through one of their courses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DataScope {
+    Unrestricted,
+    // None when the account is not linked to an employee yet, which leaves the
+    // caseload empty rather than falling back to everything
+    Caseload(Option<i32>),
+}
+
+impl DataScope {
+    pub fn for_user(user: &SessionUser, employee_id: Option<i32>) -> Self {
+        if user.is_admin() {
+            DataScope::Unrestricted
+        } else {
+            DataScope::Caseload(employee_id)
+        }
+    }
+
+    pub fn is_restricted(&self) -> bool {
+        matches!(self, DataScope::Caseload(_))
+    }
+
+    pub fn teacher_id(&self) -> Option<i32> {
+        match self {
+            DataScope::Unrestricted => None,
+            DataScope::Caseload(teacher_id) => *teacher_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    fn user_with_role(role: UserRole) -> SessionUser {
+        SessionUser {
+            id: 1,
+            username: "tester".to_string(),
+            email: "tester@example.com".to_string(),
+            role,
+            display_name: None,
+            first_name: None,
+            last_name: None,
+            token_scopes: None,
+        }
+    }
+
+    #[test]
+    fn test_unauthenticated_calls_are_rejected() {
+        for permission in Permission::iter().filter(|p| *p != Permission::Public) {
+            assert_eq!(
+                permission.check(None),
+                Err(AuthError::NotAuthenticated),
+                "{} should require a session",
+                permission
+            );
+        }
+        assert!(Permission::Public.check(None).is_ok());
+    }
+
+    #[test]
+    fn test_under_privileged_calls_are_rejected() {
+        let guest = user_with_role(UserRole::Guest);
+        let teacher = user_with_role(UserRole::Teacher);
+        let admin = user_with_role(UserRole::Admin);
+
+        assert!(Permission::Authenticated.check(Some(&guest)).is_ok());
+        assert_eq!(
+            Permission::ViewStudents.check(Some(&guest)),
+            Err(AuthError::Forbidden(Permission::ViewStudents))
+        );
+        assert_eq!(
+            Permission::ManageStudents.check(Some(&teacher)),
+            Err(AuthError::Forbidden(Permission::ManageStudents))
+        );
+        assert_eq!(
+            Permission::ManageUsers.check(Some(&teacher)),
+            Err(AuthError::Forbidden(Permission::ManageUsers))
+        );
+        assert_eq!(
+            Permission::ManageStudentProtection.check(Some(&admin)),
+            Err(AuthError::Forbidden(Permission::ManageStudentProtection))
+        );
+    }
+
+    #[test]
+    fn test_roles_inherit_lower_permissions() {
+        let teacher = user_with_role(UserRole::Teacher);
+        let super_admin = user_with_role(UserRole::SuperAdmin);
+
+        assert!(Permission::ManageScores.check(Some(&teacher)).is_ok());
+        assert!(Permission::ConductTestSessions
+            .check(Some(&teacher))
+            .is_ok());
+        for permission in Permission::iter() {
+            assert!(permission.check(Some(&super_admin)).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_api_tokens_are_limited_to_their_scopes() {
+        let mut teacher = user_with_role(UserRole::Teacher);
+        teacher.token_scopes = Some(vec![TokenScope::ScoresRead]);
+
+        assert!(Permission::ViewScores.check(Some(&teacher)).is_ok());
+        assert!(Permission::Public.check(Some(&teacher)).is_ok());
+        assert_eq!(
+            Permission::ManageScores.check(Some(&teacher)),
+            Err(AuthError::OutOfScope(Permission::ManageScores))
+        );
+        assert_eq!(
+            Permission::Authenticated.check(Some(&teacher)),
+            Err(AuthError::OutOfScope(Permission::Authenticated))
+        );
+
+        // A scope never lifts a token above its owner's role
+        let mut guest = user_with_role(UserRole::Guest);
+        guest.token_scopes = Some(vec![TokenScope::RosterWrite]);
+        assert_eq!(
+            Permission::ManageStudents.check(Some(&guest)),
+            Err(AuthError::Forbidden(Permission::ManageStudents))
+        );
+        assert!(!TokenScope::RosterWrite.is_available_to(&guest));
+        assert!(!TokenScope::RosterWrite.is_available_to(&teacher));
+        assert!(TokenScope::ScoresWrite.is_available_to(&teacher));
+    }
+
+    #[test]
+    fn test_token_scopes_round_trip_through_strings() {
+        for scope in TokenScope::iter() {
+            assert_eq!(scope.to_string().parse::<TokenScope>(), Ok(scope));
+        }
+        assert!("admin:all".parse::<TokenScope>().is_err());
+    }
+
+    #[test]
+    fn test_only_admins_get_unrestricted_data_scope() {
+        let teacher = user_with_role(UserRole::Teacher);
+        let admin = user_with_role(UserRole::Admin);
+
+        assert_eq!(DataScope::for_user(&admin, None), DataScope::Unrestricted);
+        assert_eq!(
+            DataScope::for_user(&teacher, Some(7)),
+            DataScope::Caseload(Some(7))
+        );
+        assert!(DataScope::for_user(&teacher, None).is_restricted());
+        assert_eq!(DataScope::for_user(&teacher, None).teacher_id(), None);
+    }
+}
@@ -100,6 +100,11 @@ impl Question {
             weighted_options: None, // Default to None, can be set later if needed
         }
     }
+
+    // A question is keyed by its test and number
+    pub fn audit_id(&self) -> String {
+        format!("{}/{}", self.testlinker, self.qnumber)
+    }
     //
     // Helper methods for weighted options
     pub fn get_weighted_options(&self) -> Vec<WeightedOption> {
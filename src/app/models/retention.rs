@@ -0,0 +1,220 @@
+use crate::app::models::enrollment::EnrollmentStatus;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+// Global setting key turning the daily scheduled purge on
+pub const RETENTION_PURGE_ENABLED_KEY: &str = "retention_purge_enabled";
+
+pub const MAX_RETAIN_YEARS: i32 = 100;
+
+// Students whose latest enrollment has one of `statuses` are purged
+// `retain_years` after that enrollment ended
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub id: i32,
+    pub name: String,
+    pub statuses: Vec<EnrollmentStatus>,
+    pub retain_years: i32,
+    // Keep scores, stripped of anything identifying, for trend reporting
+    pub keep_deidentified_scores: bool,
+    pub enabled: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+// A new policy when `id` is None
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveRetentionPolicyRequest {
+    pub id: Option<i32>,
+    pub name: String,
+    pub statuses: Vec<EnrollmentStatus>,
+    pub retain_years: i32,
+    pub keep_deidentified_scores: bool,
+    pub enabled: bool,
+}
+
+impl SaveRetentionPolicyRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Give the policy a name".to_string());
+        }
+        if self.statuses.is_empty() {
+            return Err("Choose at least one enrollment status".to_string());
+        }
+        if self
+            .statuses
+            .iter()
+            .any(|status| matches!(status, EnrollmentStatus::Active))
+        {
+            return Err("Active students cannot be purged".to_string());
+        }
+        if !(0..=MAX_RETAIN_YEARS).contains(&self.retain_years) {
+            return Err(format!(
+                "Retention must be between 0 and {} years",
+                MAX_RETAIN_YEARS
+            ));
+        }
+        Ok(())
+    }
+}
+
+// A student a purge would remove, and the policy that selects them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionCandidate {
+    pub student_id: i32,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub last_status: EnrollmentStatus,
+    pub last_active: NaiveDate,
+    pub policy_id: i32,
+    pub policy_name: String,
+    pub keep_deidentified_scores: bool,
+    pub scores: i64,
+}
+
+impl RetentionCandidate {
+    // All the audit log keeps of a purged student: which policy removed them
+    // and why, and nothing that identifies them
+    pub fn purge_audit_record(&self) -> serde_json::Value {
+        serde_json::json!({
+            "retention_policy": self.policy_name,
+            "last_status": self.last_status.to_string(),
+            "last_active": self.last_active,
+            "scores_deidentified": self.keep_deidentified_scores,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPreview {
+    pub candidates: Vec<RetentionCandidate>,
+    // Students a policy selects who are kept because of a legal hold
+    pub held_students: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub students_purged: i64,
+    pub scores_deidentified: i64,
+    pub scores_deleted: i64,
+    pub enrollments_deleted: i64,
+    pub held_students: i64,
+}
+
+impl PurgeReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Purged {} students: kept {} de-identified scores, deleted {} scores and {} enrollments. {} students on legal hold were kept.",
+            self.students_purged,
+            self.scores_deidentified,
+            self.scores_deleted,
+            self.enrollments_deleted,
+            self.held_students
+        )
+    }
+}
+
+// A held student is never purged, whatever the retention policies say
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub student_id: i32,
+    pub held: bool,
+    pub reason: Option<String>,
+    // Who last placed or lifted the hold, and when
+    pub set_by: Option<String>,
+    pub set_at: Option<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::models::audit::AuditChange;
+    use crate::app::models::student::{ESLEnum, GenderEnum, GradeEnum, Student};
+
+    #[test]
+    fn policies_cannot_select_active_students() {
+        let mut request = SaveRetentionPolicyRequest {
+            id: None,
+            name: "Graduates".to_string(),
+            statuses: vec![EnrollmentStatus::Graduated, EnrollmentStatus::Transferred],
+            retain_years: 5,
+            keep_deidentified_scores: true,
+            enabled: true,
+        };
+        assert!(request.validate().is_ok());
+
+        request.statuses.push(EnrollmentStatus::Active);
+        assert!(request.validate().is_err());
+
+        request.statuses = vec![];
+        assert!(request.validate().is_err());
+
+        request.statuses = vec![EnrollmentStatus::Dropped];
+        request.retain_years = -1;
+        assert!(request.validate().is_err());
+    }
+
+    // Everything the audit log holds about a student once it is purged:
+    // its create, edit and purge events
+    #[test]
+    fn purged_students_leave_no_pii_in_the_audit_log() {
+        let student = Student::new(
+            Some("Janelle".to_string()),
+            Some("Okonkwo".to_string()),
+            "Nellie".to_string(),
+            GenderEnum::Female,
+            NaiveDate::from_ymd_opt(2015, 3, 4).unwrap(),
+            1001,
+            ESLEnum::NotApplicable,
+            GradeEnum::Third,
+            "Room 12".to_string(),
+            false,
+            false,
+            false,
+            false,
+            false,
+            None,
+            false,
+            "Allergic to peanuts".to_string(),
+            Some(4321),
+        );
+        let mut edited = student.clone();
+        edited.lastname = Some("Whitfield".to_string());
+        edited.iep = true;
+        let candidate = RetentionCandidate {
+            student_id: 1001,
+            firstname: student.firstname.clone(),
+            lastname: edited.lastname.clone(),
+            last_status: EnrollmentStatus::Graduated,
+            last_active: NaiveDate::from_ymd_opt(2019, 6, 1).unwrap(),
+            policy_id: 1,
+            policy_name: "Graduates".to_string(),
+            keep_deidentified_scores: true,
+            scores: 12,
+        };
+
+        let events = [
+            AuditChange::created(&student).redacted(&["student_id"]),
+            AuditChange::updated(&student, &edited).redacted(&["student_id"]),
+            AuditChange::deleted(&candidate.purge_audit_record()),
+        ];
+        let pii = [
+            "Janelle",
+            "Okonkwo",
+            "Nellie",
+            "Whitfield",
+            "2015-03-04",
+            "peanuts",
+            "4321",
+        ];
+        for event in &events {
+            let recorded = serde_json::to_string(event).unwrap();
+            for value in pii {
+                assert!(!recorded.contains(value), "{} in {}", value, recorded);
+            }
+        }
+        assert_eq!(
+            events[1].after,
+            Some(serde_json::json!({ "student_id": 1001, "changed_fields": ["iep", "lastname"] }))
+        );
+    }
+}
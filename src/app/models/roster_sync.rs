@@ -0,0 +1,175 @@
+use crate::app::models::enrollment::{AcademicYear, Enrollment, EnrollmentStatus};
+use crate::app::models::student::AddStudentRequest;
+use serde::{Deserialize, Serialize};
+
+// CSV columns a grade or teacher change shows up under
+pub const STUDENT_GRADE_FIELD: &str = "current_grade_level";
+pub const STUDENT_TEACHER_FIELD: &str = "teacher";
+pub const ENROLLMENT_GRADE_FIELD: &str = "grade_level";
+pub const ENROLLMENT_TEACHER_FIELD: &str = "teacher_id";
+
+// One value a sync would overwrite, named by its CSV column
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentSyncChange {
+    pub student_id: i32,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub changes: Vec<FieldChange>,
+}
+
+impl StudentSyncChange {
+    pub fn changes_field(&self, field: &str) -> bool {
+        self.changes.iter().any(|change| change.field == field)
+    }
+}
+
+// A student missing from the feed, and the active enrollments a sync would
+// close for them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncWithdrawal {
+    pub student_id: i32,
+    pub firstname: Option<String>,
+    pub lastname: Option<String>,
+    pub enrollment_ids: Vec<i32>,
+    pub academic_years: Vec<AcademicYear>,
+}
+
+// What syncing a student CSV would do. Students are matched on student ID;
+// students with an active enrollment who are not in the feed are withdrawn.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentSyncDiff {
+    pub new_students: Vec<AddStudentRequest>,
+    pub changed: Vec<StudentSyncChange>,
+    pub withdrawn: Vec<SyncWithdrawal>,
+    pub unchanged: usize,
+    // Identifies this diff, so applying it fails if the database or the file
+    // changed after the preview
+    pub fingerprint: String,
+}
+
+impl StudentSyncDiff {
+    pub fn grade_changes(&self) -> usize {
+        self.changed
+            .iter()
+            .filter(|change| change.changes_field(STUDENT_GRADE_FIELD))
+            .count()
+    }
+
+    pub fn teacher_changes(&self) -> usize {
+        self.changed
+            .iter()
+            .filter(|change| change.changes_field(STUDENT_TEACHER_FIELD))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.new_students.is_empty() && self.changed.is_empty() && self.withdrawn.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrollmentSyncChange {
+    pub enrollment_id: i32,
+    pub student_id: i32,
+    pub academic_year: AcademicYear,
+    pub changes: Vec<FieldChange>,
+}
+
+impl EnrollmentSyncChange {
+    pub fn changes_field(&self, field: &str) -> bool {
+        self.changes.iter().any(|change| change.field == field)
+    }
+}
+
+// What syncing an enrollment CSV would do. Enrollments outside any course
+// are matched on student and academic year; active ones in the feed's years
+// that the feed leaves out are withdrawn.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnrollmentSyncDiff {
+    pub new_enrollments: Vec<Enrollment>,
+    pub changed: Vec<EnrollmentSyncChange>,
+    pub withdrawn: Vec<SyncWithdrawal>,
+    pub unchanged: usize,
+    pub fingerprint: String,
+}
+
+impl EnrollmentSyncDiff {
+    pub fn grade_changes(&self) -> usize {
+        self.changed
+            .iter()
+            .filter(|change| change.changes_field(ENROLLMENT_GRADE_FIELD))
+            .count()
+    }
+
+    pub fn teacher_changes(&self) -> usize {
+        self.changed
+            .iter()
+            .filter(|change| change.changes_field(ENROLLMENT_TEACHER_FIELD))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.new_enrollments.is_empty() && self.changed.is_empty() && self.withdrawn.is_empty()
+    }
+}
+
+// Approval of a previewed diff. Withdrawn students' enrollments are closed
+// with `withdrawal_status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApplySyncRequest {
+    pub fingerprint: String,
+    pub withdrawal_status: EnrollmentStatus,
+}
+
+impl ApplySyncRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if !matches!(
+            self.withdrawal_status,
+            EnrollmentStatus::Transferred | EnrollmentStatus::Dropped
+        ) {
+            return Err("Withdrawn students can only be marked transferred or dropped".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub created: usize,
+    pub updated: usize,
+    pub withdrawn: usize,
+}
+
+impl SyncReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Sync applied: {} added, {} updated, {} withdrawn",
+            self.created, self.updated, self.withdrawn
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn withdrawals_are_transfers_or_drops() {
+        let mut request = ApplySyncRequest {
+            fingerprint: String::new(),
+            withdrawal_status: EnrollmentStatus::Dropped,
+        };
+        assert!(request.validate().is_ok());
+        request.withdrawal_status = EnrollmentStatus::Graduated;
+        assert!(request.validate().is_err());
+        request.withdrawal_status = EnrollmentStatus::Active;
+        assert!(request.validate().is_err());
+    }
+}
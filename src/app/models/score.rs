@@ -41,6 +41,13 @@ impl Score {
             attempt,
         }
     }
+    // A score is keyed by student, test, variant and attempt
+    pub fn audit_id(&self) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.student_id, self.test_id, self.test_variant, self.attempt
+        )
+    }
     pub fn get_total(&self) -> i32 {
         self.test_scores.iter().sum()
     }
@@ -0,0 +1,56 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A score file as uploaded: CSV text, or an Excel workbook in base64
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoreImportFile {
+    Csv(String),
+    Xlsx(String),
+}
+
+// Points as a score CSV gives them: per question, keyed by qnumber, or a
+// single total for tests scored on paper
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScorePoints {
+    Questions(BTreeMap<i32, i32>),
+    Total(i32),
+}
+
+// One row of a score CSV as written. The test is named by its name or ID;
+// the variant only needs giving when several tests share a name.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreImportRow {
+    pub student_id: i32,
+    pub test: String,
+    pub variant: Option<i32>,
+    pub date_administered: NaiveDate,
+    pub evaluator: Option<String>,
+    pub points: ScorePoints,
+}
+
+// A score ready to write, keeping the date it was given and who scored it.
+// Its attempt is numbered when it is written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoricalScore {
+    pub student_id: i32,
+    pub test_id: String,
+    pub test_variant: i32,
+    pub date_administered: DateTime<Utc>,
+    pub test_scores: Vec<i32>,
+    pub comments: Vec<String>,
+    pub evaluator: String,
+}
+
+impl HistoricalScore {
+    // Scores for the same student and test are written oldest first, so
+    // attempts count up in the order they were given
+    pub fn attempt_order(&self) -> (i32, &str, i32, DateTime<Utc>) {
+        (
+            self.student_id,
+            self.test_id.as_str(),
+            self.test_variant,
+            self.date_administered,
+        )
+    }
+}
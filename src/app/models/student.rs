@@ -1,3 +1,4 @@
+use crate::app::models::student_attribute::AttributeValues;
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Display};
@@ -291,6 +292,9 @@ pub struct AddStudentRequest {
     pub eye_glasses: bool,
     pub notes: String,
     pub pin: i32,
+    // Custom attribute values by key, as entered
+    #[serde(default)]
+    pub custom_attributes: AttributeValues,
 }
 
 impl AddStudentRequest {
@@ -333,6 +337,7 @@ impl AddStudentRequest {
             eye_glasses,
             notes,
             pin,
+            custom_attributes: AttributeValues::new(),
         }
     }
 }
@@ -360,6 +365,9 @@ pub struct UpdateStudentRequest {
     pub eye_glasses: bool,
     pub notes: String,
     pub pin: i32,
+    // Custom attribute values by key, as entered; None leaves them as they are
+    #[serde(default)]
+    pub custom_attributes: Option<AttributeValues>,
 }
 
 impl UpdateStudentRequest {
@@ -402,6 +410,7 @@ impl UpdateStudentRequest {
             eye_glasses,
             notes,
             pin,
+            custom_attributes: None,
         }
     }
 }
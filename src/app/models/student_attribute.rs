@@ -0,0 +1,300 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+// A student's custom attribute values by attribute key, normalized
+pub type AttributeValues = BTreeMap<String, String>;
+
+// Column headers the bulk CSV import already uses; attribute keys can't
+// take them
+pub const RESERVED_ATTRIBUTE_KEYS: [&str; 20] = [
+    "firstname",
+    "lastname",
+    "preferred",
+    "gender",
+    "date_of_birth",
+    "student_id",
+    "esl",
+    "grade",
+    "current_grade_level",
+    "teacher",
+    "iep",
+    "bip",
+    "student_504",
+    "readplan",
+    "gt",
+    "intervention",
+    "eye_glasses",
+    "notes",
+    "pin",
+    "legal_hold",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AttributeKind {
+    Bool,
+    Text,
+    Enum,
+    Date,
+}
+
+impl AttributeKind {
+    pub const ALL: [AttributeKind; 4] = [
+        AttributeKind::Bool,
+        AttributeKind::Text,
+        AttributeKind::Enum,
+        AttributeKind::Date,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttributeKind::Bool => "bool",
+            AttributeKind::Text => "text",
+            AttributeKind::Enum => "enum",
+            AttributeKind::Date => "date",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AttributeKind::Bool => "Yes/No",
+            AttributeKind::Text => "Text",
+            AttributeKind::Enum => "Choice",
+            AttributeKind::Date => "Date",
+        }
+    }
+}
+
+impl fmt::Display for AttributeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for AttributeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        AttributeKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| format!("Unknown attribute kind: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeDefinition {
+    pub id: i32,
+    // Lowercase slug, also the bulk CSV column header
+    pub key: String,
+    pub label: String,
+    pub kind: AttributeKind,
+    // Allowed values of an Enum attribute
+    pub options: Vec<String>,
+    pub sort_order: i32,
+    pub active: bool,
+}
+
+impl AttributeDefinition {
+    // The stored form of `raw`, or None when it is blank. Yes/no values
+    // accept true/yes/y/1/x and false/no/n/0, dates YYYY-MM-DD or
+    // MM/DD/YYYY, and choices match an option ignoring case.
+    pub fn normalize_value(&self, raw: &str) -> Result<Option<String>, String> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return Ok(None);
+        }
+        let value = match self.kind {
+            AttributeKind::Bool => match raw.to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" | "x" => "true".to_string(),
+                "false" | "no" | "n" | "0" => "false".to_string(),
+                _ => return Err(format!("{}: '{}' is not yes or no", self.label, raw)),
+            },
+            AttributeKind::Text => raw.to_string(),
+            AttributeKind::Enum => self
+                .options
+                .iter()
+                .find(|option| option.eq_ignore_ascii_case(raw))
+                .cloned()
+                .ok_or_else(|| {
+                    format!(
+                        "{}: '{}' is not one of {}",
+                        self.label,
+                        raw,
+                        self.options.join(", ")
+                    )
+                })?,
+            AttributeKind::Date => NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(raw, "%m/%d/%Y"))
+                .map_err(|_| format!("{}: '{}' is not a date", self.label, raw))?
+                .format("%Y-%m-%d")
+                .to_string(),
+        };
+        Ok(Some(value))
+    }
+
+    // A stored value as shown to users
+    pub fn display_value(&self, value: &str) -> String {
+        match (self.kind, value) {
+            (AttributeKind::Bool, "true") => "Yes".to_string(),
+            (AttributeKind::Bool, "false") => "No".to_string(),
+            _ => value.to_string(),
+        }
+    }
+
+    // Whether a student's stored value satisfies a filter for `wanted`.
+    // An unset yes/no attribute counts as no; text matches a substring.
+    pub fn matches(&self, stored: Option<&str>, wanted: &str) -> bool {
+        match (self.kind, stored) {
+            (AttributeKind::Bool, stored) => stored.unwrap_or("false") == wanted,
+            (AttributeKind::Text, Some(stored)) => stored
+                .to_lowercase()
+                .contains(&wanted.trim().to_lowercase()),
+            (_, Some(stored)) => stored == wanted,
+            (_, None) => false,
+        }
+    }
+}
+
+// Normalizes submitted values against the definitions, dropping blanks.
+// Unknown keys are an error so a typo doesn't silently lose data.
+pub fn normalize_attribute_values(
+    definitions: &[AttributeDefinition],
+    raw: &AttributeValues,
+) -> Result<AttributeValues, String> {
+    let mut values = AttributeValues::new();
+    for (key, raw_value) in raw {
+        let definition = definitions
+            .iter()
+            .find(|definition| &definition.key == key)
+            .ok_or_else(|| format!("Unknown student attribute: {}", key))?;
+        if let Some(value) = definition.normalize_value(raw_value)? {
+            values.insert(key.clone(), value);
+        }
+    }
+    Ok(values)
+}
+
+// Narrows a student list to those whose attribute `key` matches `value`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributeFilter {
+    pub key: String,
+    pub value: String,
+}
+
+impl AttributeFilter {
+    // A filter on an unknown attribute or with no value yet lets everyone through
+    pub fn matches(
+        &self,
+        definitions: &[AttributeDefinition],
+        values: Option<&AttributeValues>,
+    ) -> bool {
+        if self.value.trim().is_empty() {
+            return true;
+        }
+        match definitions
+            .iter()
+            .find(|definition| definition.key == self.key)
+        {
+            Some(definition) => definition.matches(
+                values
+                    .and_then(|values| values.get(&self.key))
+                    .map(String::as_str),
+                &self.value,
+            ),
+            None => true,
+        }
+    }
+}
+
+// A new attribute when `id` is None. The kind of an existing attribute
+// can't change, since its stored values would no longer parse.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveAttributeDefinitionRequest {
+    pub id: Option<i32>,
+    pub key: String,
+    pub label: String,
+    pub kind: AttributeKind,
+    pub options: Vec<String>,
+    pub sort_order: i32,
+    pub active: bool,
+}
+
+impl SaveAttributeDefinitionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let key = self.key.trim();
+        let mut chars = key.chars();
+        if !chars.next().is_some_and(|c| c.is_ascii_lowercase())
+            || !chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(
+                "Keys start with a lowercase letter and use only lowercase letters, digits and _"
+                    .to_string(),
+            );
+        }
+        if RESERVED_ATTRIBUTE_KEYS.contains(&key) {
+            return Err(format!("'{}' is already a built-in student field", key));
+        }
+        if self.label.trim().is_empty() {
+            return Err("Give the attribute a label".to_string());
+        }
+        if self.kind == AttributeKind::Enum {
+            if self.options.iter().all(|option| option.trim().is_empty()) {
+                return Err("List at least one choice".to_string());
+            }
+        } else if !self.options.is_empty() {
+            return Err("Only choice attributes have options".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(kind: AttributeKind) -> AttributeDefinition {
+        AttributeDefinition {
+            id: 1,
+            key: "program".to_string(),
+            label: "Program".to_string(),
+            kind,
+            options: vec!["Title I".to_string(), "Migrant".to_string()],
+            sort_order: 0,
+            active: true,
+        }
+    }
+
+    #[test]
+    fn values_are_normalized_by_kind() {
+        let flag = definition(AttributeKind::Bool);
+        assert_eq!(flag.normalize_value(" Yes "), Ok(Some("true".to_string())));
+        assert_eq!(flag.normalize_value("0"), Ok(Some("false".to_string())));
+        assert!(flag.normalize_value("maybe").is_err());
+        assert_eq!(flag.normalize_value(""), Ok(None));
+
+        let choice = definition(AttributeKind::Enum);
+        assert_eq!(
+            choice.normalize_value("title i"),
+            Ok(Some("Title I".to_string()))
+        );
+        assert!(choice.normalize_value("Homeless").is_err());
+
+        let date = definition(AttributeKind::Date);
+        assert_eq!(
+            date.normalize_value("09/03/2024"),
+            Ok(Some("2024-09-03".to_string()))
+        );
+        assert!(date.normalize_value("2024-13-01").is_err());
+    }
+
+    #[test]
+    fn unset_yes_no_attributes_filter_as_no() {
+        let flag = definition(AttributeKind::Bool);
+        assert!(flag.matches(None, "false"));
+        assert!(!flag.matches(None, "true"));
+        assert!(definition(AttributeKind::Text).matches(Some("Speech, OT"), "speech"));
+    }
+}
@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentDataKeyInfo {
+    pub id: i32,
+    // Master key the data key is currently wrapped with
+    pub master_key_id: String,
+    pub active: bool,
+    pub students: i64,
+    pub created_at: DateTime<Utc>,
+    pub rewrapped_at: Option<DateTime<Utc>>,
+}
+
+// Where student PII encryption stands, for the settings page
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentEncryptionStatus {
+    // None when no key provider is configured
+    pub provider: Option<String>,
+    pub provider_error: Option<String>,
+    pub active_master_key_id: Option<String>,
+    pub keys: Vec<StudentDataKeyInfo>,
+    pub plaintext_students: i64,
+    // Rows still encrypted under a data key other than the active one
+    pub stale_students: i64,
+}
+
+impl StudentEncryptionStatus {
+    pub fn pending_students(&self) -> i64 {
+        self.plaintext_students + self.stale_students
+    }
+}
+
+// Result of re-encrypting students or re-wrapping data keys
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRotationReport {
+    pub students_resealed: i64,
+    pub keys_rewrapped: i64,
+    pub keys_removed: i64,
+}
+
+impl KeyRotationReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "Re-encrypted {} students, re-wrapped {} data keys, removed {} retired keys.",
+            self.students_resealed, self.keys_rewrapped, self.keys_removed
+        )
+    }
+}
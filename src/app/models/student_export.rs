@@ -0,0 +1,75 @@
+use crate::app::models::audit::AuditEvent;
+use crate::app::models::enrollment::Enrollment;
+use crate::app::models::student::Student;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// One question of a scored test, with what the student earned on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedAnswer {
+    pub qnumber: i32,
+    pub question: String,
+    pub point_value: i32,
+    pub points_earned: Option<i32>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedScore {
+    pub test_id: String,
+    // None when the test has since been deleted
+    pub test_name: Option<String>,
+    pub date_administered: DateTime<Utc>,
+    pub test_variant: i32,
+    pub attempt: i32,
+    pub evaluator: String,
+    pub total: i32,
+    pub answers: Vec<ExportedAnswer>,
+}
+
+// Programs and supports recorded on the student
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InterventionSummary {
+    pub intervention: Option<String>,
+    pub esl: String,
+    pub iep: bool,
+    pub bip: bool,
+    pub student_504: bool,
+    pub readplan: bool,
+    pub gt: bool,
+    pub eye_glasses: bool,
+}
+
+impl InterventionSummary {
+    pub fn from_student(student: &Student) -> Self {
+        InterventionSummary {
+            intervention: student.intervention.as_ref().map(|i| i.to_string()),
+            esl: student.esl.to_string(),
+            iep: student.iep,
+            bip: student.bip,
+            student_504: student.student_504,
+            readplan: student.readplan,
+            gt: student.gt,
+            eye_glasses: student.eye_glasses,
+        }
+    }
+}
+
+// Everything held on one student, as answered to a records request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudentRecordExport {
+    pub generated_at: DateTime<Utc>,
+    pub generated_by: String,
+    pub student: Student,
+    pub interventions: InterventionSummary,
+    pub enrollments: Vec<Enrollment>,
+    pub scores: Vec<ExportedScore>,
+    pub audit_events: Vec<AuditEvent>,
+}
+
+// The zip archive handed to the browser, base64 encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentExportBundle {
+    pub filename: String,
+    pub archive_base64: String,
+}
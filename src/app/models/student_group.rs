@@ -0,0 +1,209 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupPurpose {
+    Intervention,
+    Enrichment,
+    Ell,
+    Other,
+}
+
+impl GroupPurpose {
+    pub const ALL: [GroupPurpose; 4] = [
+        GroupPurpose::Intervention,
+        GroupPurpose::Enrichment,
+        GroupPurpose::Ell,
+        GroupPurpose::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupPurpose::Intervention => "intervention",
+            GroupPurpose::Enrichment => "enrichment",
+            GroupPurpose::Ell => "ell",
+            GroupPurpose::Other => "other",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupPurpose::Intervention => "Intervention",
+            GroupPurpose::Enrichment => "Enrichment",
+            GroupPurpose::Ell => "ELL",
+            GroupPurpose::Other => "Other",
+        }
+    }
+}
+
+impl fmt::Display for GroupPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for GroupPurpose {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        GroupPurpose::ALL
+            .into_iter()
+            .find(|purpose| purpose.as_str() == s)
+            .ok_or_else(|| format!("Unknown group purpose: {}", s))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupMember {
+    pub student_id: i32,
+    pub start_date: NaiveDate,
+    // None while the student is still in the group
+    pub end_date: Option<NaiveDate>,
+}
+
+impl GroupMember {
+    pub fn is_member_on(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && self.end_date.map_or(true, |end| end >= date)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentGroup {
+    pub id: i32,
+    pub name: String,
+    // Employee running the group; their caseload includes its current members
+    pub owner_id: Option<i32>,
+    pub owner_name: Option<String>,
+    pub purpose: GroupPurpose,
+    pub description: Option<String>,
+    pub active: bool,
+    // Past and present members the caller may see
+    pub members: Vec<GroupMember>,
+}
+
+impl StudentGroup {
+    // Students in the group on `date`, the roster used by filters and
+    // live test sessions
+    pub fn member_ids_on(&self, date: NaiveDate) -> HashSet<i32> {
+        self.members
+            .iter()
+            .filter(|member| member.is_member_on(date))
+            .map(|member| member.student_id)
+            .collect()
+    }
+}
+
+// A new group when `id` is None. `members` replaces the group's whole
+// membership list.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveStudentGroupRequest {
+    pub id: Option<i32>,
+    pub name: String,
+    pub owner_id: Option<i32>,
+    pub purpose: GroupPurpose,
+    pub description: Option<String>,
+    pub active: bool,
+    pub members: Vec<GroupMember>,
+}
+
+impl SaveStudentGroupRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Give the group a name".to_string());
+        }
+        let mut seen = HashSet::new();
+        for member in &self.members {
+            if !seen.insert(member.student_id) {
+                return Err(format!(
+                    "Student {} is listed in the group twice",
+                    member.student_id
+                ));
+            }
+            if member.end_date.is_some_and(|end| end < member.start_date) {
+                return Err(format!(
+                    "Student {} leaves the group before joining it",
+                    member.student_id
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// How a group's members did on one test, counting each member's latest
+// attempt taken while they were in the group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GroupTestSummary {
+    pub test_id: String,
+    pub test_name: String,
+    pub max_score: i32,
+    pub students_tested: i64,
+    pub average_score: f64,
+}
+
+impl GroupTestSummary {
+    pub fn average_percent(&self) -> Option<f64> {
+        (self.max_score > 0).then(|| self.average_score / self.max_score as f64 * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 9, day).unwrap()
+    }
+
+    fn member(student_id: i32, start: u32, end: Option<u32>) -> GroupMember {
+        GroupMember {
+            student_id,
+            start_date: date(start),
+            end_date: end.map(date),
+        }
+    }
+
+    #[test]
+    fn membership_follows_start_and_end_dates() {
+        let group = StudentGroup {
+            id: 1,
+            name: "Tier 2 reading".to_string(),
+            owner_id: Some(4),
+            owner_name: None,
+            purpose: GroupPurpose::Intervention,
+            description: None,
+            active: true,
+            members: vec![
+                member(10, 1, None),
+                member(11, 1, Some(10)),
+                member(12, 15, None),
+            ],
+        };
+        assert_eq!(group.member_ids_on(date(10)), HashSet::from([10, 11]));
+        assert_eq!(group.member_ids_on(date(11)), HashSet::from([10]));
+        assert_eq!(group.member_ids_on(date(20)), HashSet::from([10, 12]));
+    }
+
+    #[test]
+    fn requests_reject_duplicate_and_backwards_members() {
+        let mut request = SaveStudentGroupRequest {
+            id: None,
+            name: "Enrichment".to_string(),
+            owner_id: None,
+            purpose: GroupPurpose::Enrichment,
+            description: None,
+            active: true,
+            members: vec![member(10, 1, None)],
+        };
+        assert!(request.validate().is_ok());
+
+        request.members.push(member(10, 5, None));
+        assert!(request.validate().is_err());
+
+        request.members = vec![member(10, 5, Some(1))];
+        assert!(request.validate().is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::app::models::student::{ESLEnum, GenderEnum, GradeEnum, InterventionEnum, Student};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// A student's program flags and placement over one stretch of time. Sealed
+// PII (names, birth date, PIN, notes) is not versioned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentVersion {
+    pub student_id: i32,
+    pub valid_from: DateTime<Utc>,
+    // None while this is the current version
+    pub valid_to: Option<DateTime<Utc>>,
+    pub changed_by: String,
+    pub preferred: String,
+    pub gender: GenderEnum,
+    pub esl: ESLEnum,
+    pub current_grade_level: GradeEnum,
+    pub teacher: String,
+    pub iep: bool,
+    pub bip: bool,
+    pub student_504: bool,
+    pub readplan: bool,
+    pub gt: bool,
+    pub intervention: Option<InterventionEnum>,
+    pub eye_glasses: bool,
+}
+
+impl StudentVersion {
+    // The student as this version recorded them, keeping the current PII
+    pub fn apply_to(&self, mut student: Student) -> Student {
+        student.preferred = self.preferred.clone();
+        student.gender = self.gender.clone();
+        student.esl = self.esl.clone();
+        student.current_grade_level = self.current_grade_level.clone();
+        student.teacher = self.teacher.clone();
+        student.iep = self.iep;
+        student.bip = self.bip;
+        student.student_504 = self.student_504;
+        student.readplan = self.readplan;
+        student.gt = self.gt;
+        student.intervention = self.intervention.clone();
+        student.eye_glasses = self.eye_glasses;
+        student
+    }
+
+    fn flags(&self) -> [(&'static str, String); 9] {
+        let yes_no = |flag: bool| if flag { "Yes" } else { "No" }.to_string();
+        [
+            ("IEP", yes_no(self.iep)),
+            ("BIP", yes_no(self.bip)),
+            ("504", yes_no(self.student_504)),
+            ("Read plan", yes_no(self.readplan)),
+            ("GT", yes_no(self.gt)),
+            ("ESL", self.esl.to_string()),
+            (
+                "Intervention",
+                self.intervention
+                    .as_ref()
+                    .map(|intervention| intervention.to_string())
+                    .unwrap_or_else(|| "None".to_string()),
+            ),
+            ("Grade", self.current_grade_level.to_string()),
+            ("Teacher", self.teacher.clone()),
+        ]
+    }
+}
+
+// One flag or placement change between two consecutive versions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlagChange {
+    pub changed_at: DateTime<Utc>,
+    pub changed_by: String,
+    pub flag: String,
+    pub from: String,
+    pub to: String,
+}
+
+// Changes between versions given oldest first, newest change first
+pub fn flag_changes(versions: &[StudentVersion]) -> Vec<FlagChange> {
+    versions
+        .windows(2)
+        .rev()
+        .flat_map(|pair| {
+            let (before, after) = (&pair[0], &pair[1]);
+            before
+                .flags()
+                .into_iter()
+                .zip(after.flags())
+                .filter(|((_, from), (_, to))| from != to)
+                .map(|((flag, from), (_, to))| FlagChange {
+                    changed_at: after.valid_from,
+                    changed_by: after.changed_by.clone(),
+                    flag: flag.to_string(),
+                    from,
+                    to,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn version(day: u32, iep: bool, esl: ESLEnum) -> StudentVersion {
+        StudentVersion {
+            student_id: 1001,
+            valid_from: Utc.with_ymd_and_hms(2024, 9, day, 8, 0, 0).unwrap(),
+            valid_to: None,
+            changed_by: format!("user{}", day),
+            preferred: String::new(),
+            gender: GenderEnum::Male,
+            esl,
+            current_grade_level: GradeEnum::Third,
+            teacher: "Rivera".to_string(),
+            iep,
+            bip: false,
+            student_504: false,
+            readplan: false,
+            gt: false,
+            intervention: None,
+            eye_glasses: false,
+        }
+    }
+
+    #[test]
+    fn flag_changes_list_each_changed_flag_newest_first() {
+        let versions = vec![
+            version(1, false, ESLEnum::NotApplicable),
+            version(5, true, ESLEnum::NotApplicable),
+            version(9, false, ESLEnum::Spanish),
+        ];
+
+        let changes = flag_changes(&versions);
+        let summary: Vec<(&str, &str, &str, &str)> = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.changed_by.as_str(),
+                    change.flag.as_str(),
+                    change.from.as_str(),
+                    change.to.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("user9", "IEP", "Yes", "No"),
+                ("user9", "ESL", "Not Applicable", "Spanish"),
+                ("user5", "IEP", "No", "Yes"),
+            ]
+        );
+    }
+}
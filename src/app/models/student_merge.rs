@@ -0,0 +1,73 @@
+use crate::app::models::student::Student;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// Pairs scoring below this are not reported as likely duplicates
+pub const DEFAULT_DUPLICATE_THRESHOLD: f64 = 0.8;
+
+// Two students that look like the same child, most alike first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
+    pub first: Student,
+    pub second: Student,
+    // 0.0 to 1.0, weighted over name, date of birth and PIN
+    pub score: f64,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeStudentsRequest {
+    // The record that is kept; the other one is removed
+    pub survivor_id: i32,
+    pub merged_id: i32,
+    pub reason: Option<String>,
+}
+
+impl MergeStudentsRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.survivor_id == self.merged_id {
+            return Err("Choose two different students to merge".to_string());
+        }
+        Ok(())
+    }
+}
+
+// A merge, and whether it has been undone since
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentMerge {
+    pub id: i32,
+    pub survivor_id: i32,
+    pub merged_id: i32,
+    pub enrollments_moved: i32,
+    pub scores_moved: i32,
+    pub sessions_moved: i32,
+    pub reason: Option<String>,
+    pub merged_by: String,
+    pub merged_at: DateTime<Utc>,
+    pub unmerged_by: Option<String>,
+    pub unmerged_at: Option<DateTime<Utc>>,
+}
+
+impl StudentMerge {
+    pub fn is_active(&self) -> bool {
+        self.unmerged_at.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_student_cannot_merge_into_itself() {
+        let mut request = MergeStudentsRequest {
+            survivor_id: 1001,
+            merged_id: 1001,
+            reason: None,
+        };
+        assert!(request.validate().is_err());
+
+        request.merged_id = 2002;
+        assert!(request.validate().is_ok());
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// One student's entry in the protection mapping. `app_id` is the anonymized
+// id the student carries while protection is on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentIdMapping {
+    pub app_id: i32,
+    pub student_id: i32,
+    pub firstname: String,
+    pub lastname: String,
+    pub pin: i32,
+    // The SIS sourcedId, cleared while anonymized. Absent from mappings
+    // sealed before OneRoster import.
+    #[serde(default)]
+    pub sourced_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProtectionStage {
+    Preparing,
+    Mapping,
+    Rekeying,
+    Verifying,
+    Sealing,
+    Finished,
+}
+
+impl ProtectionStage {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProtectionStage::Preparing => "Preparing",
+            ProtectionStage::Mapping => "Building ID mapping",
+            ProtectionStage::Rekeying => "Updating student records",
+            ProtectionStage::Verifying => "Verifying scores and enrollments",
+            ProtectionStage::Sealing => "Saving encrypted mapping",
+            ProtectionStage::Finished => "Finished",
+        }
+    }
+}
+
+// Where a running anonymize or restore has got to, polled by the settings page
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectionProgress {
+    pub enabling: bool,
+    pub dry_run: bool,
+    pub stage: ProtectionStage,
+    pub students_done: i64,
+    pub students_total: i64,
+}
+
+// Row counts from an anonymize or restore. A dry run does all of the work
+// and rolls it back, so its counts are what a real run would change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtectionReport {
+    pub dry_run: bool,
+    pub students: i64,
+    pub scores: i64,
+    pub enrollments: i64,
+    pub orphaned_scores: i64,
+    pub orphaned_enrollments: i64,
+}
+
+impl ProtectionReport {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} {} students, {} scores and {} enrollments.",
+            if self.dry_run {
+                "Would update"
+            } else {
+                "Updated"
+            },
+            self.students,
+            self.scores,
+            self.enrollments
+        )
+    }
+}
+
+// Argon2id cost used to turn admin passphrases into the mapping key. Kept
+// with each sealed mapping so the defaults can be raised later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+// The encrypted mapping as stored, base64 encoded. The server hands this to
+// the browser as-is; only someone holding the passphrases can open it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SealedMapping {
+    // None for mappings sealed under a key issued by the server, before
+    // passphrases were used
+    pub kdf_salt: Option<String>,
+    pub kdf: KdfParams,
+    // How many admins' passphrases make up the key
+    pub key_shares: i16,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub student_count: i32,
+    pub created_at: DateTime<Utc>,
+}
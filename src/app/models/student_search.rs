@@ -0,0 +1,98 @@
+use crate::app::models::student::Student;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const DEFAULT_SEARCH_PAGE_SIZE: u32 = 25;
+// Large enough for the student pickers to ask for every likely match at once
+pub const MAX_SEARCH_PAGE_SIZE: u32 = 500;
+
+// One page of a ranked student search. Pages count from 0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StudentSearchRequest {
+    pub query: String,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl StudentSearchRequest {
+    pub fn new(query: impl Into<String>) -> Self {
+        StudentSearchRequest {
+            query: query.into(),
+            page: 0,
+            per_page: DEFAULT_SEARCH_PAGE_SIZE,
+        }
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.per_page.clamp(1, MAX_SEARCH_PAGE_SIZE)
+    }
+
+    pub fn offset(&self) -> i64 {
+        self.page as i64 * self.page_size() as i64
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudentSearchHit {
+    pub student: Student,
+    // Higher is closer; an exact student ID match outranks any name
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StudentSearchResults {
+    // Best match first
+    pub hits: Vec<StudentSearchHit>,
+    // Matches across every page
+    pub total: i64,
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl StudentSearchResults {
+    pub fn has_more(&self) -> bool {
+        (self.page as i64 + 1) * (self.per_page as i64) < self.total
+    }
+
+    // Position of each matched student in the overall ranking, for callers
+    // that filter and sort a student list they already hold
+    pub fn ranks(&self) -> HashMap<i32, usize> {
+        let first = self.page as usize * self.per_page as usize;
+        self.hits
+            .iter()
+            .enumerate()
+            .map(|(index, hit)| (hit.student.student_id, first + index))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn page_sizes_are_clamped() {
+        let mut request = StudentSearchRequest::new("ruiz");
+        assert_eq!(request.offset(), 0);
+
+        request.page = 2;
+        request.per_page = 10_000;
+        assert_eq!(request.page_size(), MAX_SEARCH_PAGE_SIZE);
+        assert_eq!(request.offset(), 2 * MAX_SEARCH_PAGE_SIZE as i64);
+
+        request.per_page = 0;
+        assert_eq!(request.page_size(), 1);
+    }
+
+    #[test]
+    fn results_know_when_more_pages_remain() {
+        let results = StudentSearchResults {
+            hits: Vec::new(),
+            total: 51,
+            page: 1,
+            per_page: 25,
+        };
+        assert!(results.has_more());
+        assert!(!StudentSearchResults { page: 2, ..results }.has_more());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::app::models::permission::TokenScope;
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "ssr")]
 use sqlx::FromRow;
@@ -151,6 +152,7 @@ impl User {
             display_name: self.display_name.clone(),
             first_name: self.first_name.clone(),
             last_name: self.last_name.clone(),
+            token_scopes: None,
         }
     }
 }
@@ -164,6 +166,9 @@ pub struct SessionUser {
     pub display_name: Option<String>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    // Set when the request was authenticated with a personal access token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_scopes: Option<Vec<TokenScope>>,
 }
 
 impl SessionUser {
@@ -192,6 +197,21 @@ impl SessionUser {
     pub fn is_guest(&self) -> bool {
         self.role == UserRole::Guest
     }
+
+    // Roles are hierarchical: an admin satisfies a teacher requirement, etc.
+    pub fn has_role(&self, required_role: UserRole) -> bool {
+        match required_role {
+            UserRole::Guest => true,
+            UserRole::User => self.is_user(),
+            UserRole::Teacher => self.is_teacher(),
+            UserRole::Admin => self.is_admin(),
+            UserRole::SuperAdmin => self.is_super_admin(),
+        }
+    }
+
+    pub fn has_any_role(&self, required_roles: &[UserRole]) -> bool {
+        required_roles.iter().any(|role| self.has_role(*role))
+    }
 }
 
 cfg_if::cfg_if! {
@@ -6,14 +6,23 @@ use crate::app::components::data_processing::{
 use crate::app::components::enhanced_login_form::{
     use_student_mapping_service, DeAnonymizedStudent, StudentMappingService,
 };
+use crate::app::components::gradebook::score_import_modal::ScoreImportModal;
 use crate::app::components::gradebook_side_panel::{ScorePanelType, StudentScorePanel};
 use crate::app::components::header::Header;
+use crate::app::components::student_page::custom_attribute_fields::AttributeFilterControl;
+use crate::app::components::student_page::student_group_filter::StudentGroupFilter;
+use crate::app::components::student_page::student_search::use_student_search_ranks;
 use crate::app::middleware::global_settings::use_settings;
 use crate::app::models::assessment::Assessment;
 use crate::app::models::student::Student;
+use crate::app::models::student_attribute::AttributeFilter;
 use crate::app::server_functions::assessments::get_assessments;
 use crate::app::server_functions::data_wrappers::get_student_results_batch;
 use crate::app::server_functions::scores::get_scores_by_test;
+use crate::app::server_functions::student_attributes::{
+    get_attribute_definitions, get_student_attribute_values,
+};
+use crate::app::server_functions::student_groups::get_student_groups;
 use crate::app::server_functions::students::get_students;
 use crate::app::server_functions::tests::get_tests_batch;
 use chrono::Utc;
@@ -37,11 +46,14 @@ pub fn GradebookContent() -> impl IntoView {
     let (refresh_trigger, set_refresh_trigger) = create_signal(0);
     let (selected_view, set_selected_view) = create_signal(SidebarSelected::Dashboard);
     let (search_term, set_search_term) = create_signal(String::new());
+    let search_ranks = use_student_search_ranks(search_term.into());
 
     // Store assessment ID instead of the whole assessment
     let (selected_assessment_id, set_selected_assessment_id) =
         create_signal(Option::<String>::None);
 
+    let (show_score_import, set_show_score_import) = create_signal(false);
+
     // Side panel state
     let (show_side_panel, set_show_side_panel) = create_signal(false);
     let (panel_type, set_panel_type) = create_signal(ScorePanelType::None);
@@ -218,23 +230,89 @@ pub fn GradebookContent() -> impl IntoView {
         )
     };
 
+    // Custom attribute filter, with values loaded once the students are
+    let (attribute_filter, set_attribute_filter) = create_signal(Option::<AttributeFilter>::None);
+    let attribute_definitions = create_local_resource(
+        move || refresh_trigger(),
+        |_| async move { get_attribute_definitions().await.unwrap_or_default() },
+    );
+    let attribute_values = create_local_resource(
+        move || students.get(),
+        |students| async move {
+            let student_ids = students.iter().map(|student| student.student_id).collect();
+            match get_student_attribute_values(student_ids).await {
+                Ok(values) => values,
+                Err(e) => {
+                    log::error!("Failed to fetch custom attribute values: {}", e);
+                    HashMap::new()
+                }
+            }
+        },
+    );
+    let attribute_definition_list =
+        Signal::derive(move || attribute_definitions.get().unwrap_or_default());
+
+    // Student group filter, matching the group's current members
+    let (group_filter, set_group_filter) = create_signal(Option::<i32>::None);
+    let student_groups = create_local_resource(
+        move || refresh_trigger(),
+        |_| async move { get_student_groups().await.unwrap_or_default() },
+    );
+    let student_group_list = Signal::derive(move || student_groups.get().unwrap_or_default());
+
     // OPTIMIZATION 4: Memoized filtered students with debouncing
     let filtered_students = create_memo(move |_| {
         let search = search_term().trim().to_lowercase();
+        let ranks = search_ranks.get();
         let students_list = students.get();
-
-        if search.is_empty() {
+        let attribute_filter = attribute_filter.get();
+        let group_members = group_filter.get().map(|group_id| {
+            student_group_list
+                .get()
+                .into_iter()
+                .find(|group| group.id == group_id)
+                .map(|group| group.member_ids_on(Utc::now().date_naive()))
+                .unwrap_or_default()
+        });
+
+        if search.is_empty() && attribute_filter.is_none() && group_members.is_none() {
             return students_list;
         }
 
-        students_list
+        let definitions = attribute_definition_list.get();
+        let values = attribute_values.get().unwrap_or_default();
+        let mut filtered = students_list
             .into_iter()
             .filter(|student| {
                 let (display_name, display_id) = get_student_display(student);
                 display_name.to_lowercase().contains(&search)
                     || display_id.to_lowercase().contains(&search)
+                    || ranks
+                        .as_ref()
+                        .is_some_and(|ranks| ranks.contains_key(&student.student_id))
+            })
+            .filter(|student| {
+                attribute_filter.as_ref().map_or(true, |filter| {
+                    filter.matches(&definitions, values.get(&student.student_id))
+                })
             })
-            .collect::<Vec<_>>()
+            .filter(|student| {
+                group_members
+                    .as_ref()
+                    .map_or(true, |members| members.contains(&student.student_id))
+            })
+            .collect::<Vec<_>>();
+
+        // Best server matches first; students only matched locally follow
+        if let Some(ranks) = &ranks {
+            filtered.sort_by_key(|student| {
+                ranks
+                    .get(&student.student_id)
+                    .copied()
+                    .unwrap_or(usize::MAX)
+            });
+        }
+        filtered
     });
 
     // Helper function to find the next test ID
@@ -368,6 +446,16 @@ pub fn GradebookContent() -> impl IntoView {
                                 on:input=move |ev| set_search_term(event_target_value(&ev))
                             />
                         </div>
+                        <AttributeFilterControl
+                            definitions=attribute_definition_list
+                            filter=attribute_filter
+                            set_filter=set_attribute_filter
+                        />
+                        <StudentGroupFilter
+                            groups=student_group_list
+                            selected=group_filter
+                            set_selected=set_group_filter
+                        />
                         <div class="w-[20rem]">
                             <select
                                 id="assessment-select"
@@ -393,6 +481,12 @@ pub fn GradebookContent() -> impl IntoView {
                                }}
                             </select>
                         </div>
+                        <button
+                            class="ml-4 px-3 py-1 text-sm whitespace-nowrap bg-[#2E3A59] text-white rounded hover:bg-opacity-80"
+                            on:click=move |_| set_show_score_import(true)
+                        >
+                            "Import Scores"
+                        </button>
                     </div>
 
                     // OPTIMIZATION 5: Show loading state
@@ -597,6 +691,13 @@ pub fn GradebookContent() -> impl IntoView {
                     next_test=next_test_id
                 />
             </div>
+
+            <Show when=move || show_score_import()>
+                <ScoreImportModal
+                    set_show_modal=set_show_score_import
+                    set_refresh_trigger=set_refresh_trigger
+                />
+            </Show>
         </div>
     }
 }
@@ -1,4 +1,7 @@
+use crate::app::components::auth::api_tokens_panel::ApiTokensPanel;
 use crate::app::components::auth::login_form::LogoutButton;
+use crate::app::components::auth::mfa_components::MfaSettingsPanel;
+use crate::app::components::auth::sessions_panel::SessionsPanel;
 use crate::app::components::update_user_modal::UpdateProfileModal; // Import the new modal component
 use crate::app::components::Header;
 use crate::app::models::user::SessionUser;
@@ -161,6 +164,18 @@ pub fn MyAccount() -> impl IntoView {
                                     </div>
                                 </div>
 
+                                <div class="bg-white text-[#2E3A59] p-4 rounded-md shadow-md mt-4">
+                                    <MfaSettingsPanel/>
+                                </div>
+
+                                <div class="bg-white text-[#2E3A59] p-4 rounded-md shadow-md mt-4">
+                                    <SessionsPanel/>
+                                </div>
+
+                                <div class="bg-white text-[#2E3A59] p-4 rounded-md shadow-md mt-4">
+                                    <ApiTokensPanel/>
+                                </div>
+
                                 // Render the update profile modal, hidden by default
                                 <UpdateProfileModal
                                     show=Signal::derive(move || show_update_modal.get())
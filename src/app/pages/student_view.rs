@@ -2,6 +2,9 @@ use crate::app::components::auth::server_auth_components::ServerAuthGuard;
 use crate::app::components::dashboard::dashboard_sidebar::{DashboardSidebar, SidebarSelected};
 use crate::app::components::header::Header;
 use crate::app::components::student_page::bulk_upload_modal::BulkUploadModal;
+use crate::app::components::student_page::duplicate_students_modal::DuplicateStudentsModal;
+use crate::app::components::student_page::student_groups_modal::StudentGroupsModal;
+use crate::app::components::student_page::student_search::use_student_search_ranks;
 use crate::app::components::student_page::student_search_filter::SearchFilter;
 use crate::app::components::student_page::student_table::StudentTable;
 use crate::app::components::student_page::update_student_form::UpdateStudent;
@@ -9,7 +12,11 @@ use crate::app::components::student_page::{
     add_student_form::AddStudentForm, student_details::StudentDetails,
 };
 use crate::app::models::student::{DeleteStudentRequest, ESLEnum, Student};
+use crate::app::models::student_attribute::AttributeFilter;
 use crate::app::models::user::SessionUser;
+use crate::app::server_functions::student_attributes::{
+    get_attribute_definitions, get_student_attribute_values,
+};
 use crate::app::server_functions::students::{delete_student, get_students};
 use crate::app::server_functions::teachers::get_teachers;
 use leptos::ev::SubmitEvent;
@@ -84,6 +91,7 @@ pub fn StudentViewContent() -> impl IntoView {
 
     // Filter state signals
     let (search_term, set_search_term) = create_signal(String::new());
+    let search_ranks = use_student_search_ranks(search_term.into());
     let (grade_filter, set_grade_filter) = create_signal(String::from("all"));
     let (iep_filter, set_iep_filter) = create_signal(false);
     let (esl_filter, set_esl_filter) = create_signal(false);
@@ -96,6 +104,32 @@ pub fn StudentViewContent() -> impl IntoView {
     let (gt_filter, set_gt_filter) = create_signal(false);
     let (bip_filter, set_bip_filter) = create_signal(false);
 
+    // Custom attribute filter and the values it is checked against
+    let (attribute_filter, set_attribute_filter) = create_signal(None::<AttributeFilter>);
+    let attribute_definitions = create_local_resource(
+        move || refresh_trigger(),
+        |_| async move { get_attribute_definitions().await.unwrap_or_default() },
+    );
+    let attribute_values = create_local_resource(
+        move || students.get().flatten(),
+        |students| async move {
+            let student_ids = students
+                .unwrap_or_default()
+                .iter()
+                .map(|student| student.student_id)
+                .collect();
+            match get_student_attribute_values(student_ids).await {
+                Ok(values) => values,
+                Err(e) => {
+                    log::error!("Failed to fetch custom attribute values: {}", e);
+                    Default::default()
+                }
+            }
+        },
+    );
+    let attribute_definition_list =
+        Signal::derive(move || attribute_definitions.get().unwrap_or_default());
+
     // Adding student state
     let (adding_student, set_adding_student) = create_signal(false);
 
@@ -106,6 +140,13 @@ pub fn StudentViewContent() -> impl IntoView {
     // Signal for showing bulk upload modal
     let (show_bulk_upload_modal, set_show_bulk_upload_modal) = create_signal(false);
 
+    // Duplicate finder, offered to admins
+    let (show_duplicates_modal, set_show_duplicates_modal) = create_signal(false);
+    let is_admin = move || user.get().map(|u| u.is_admin()).unwrap_or(false);
+
+    // Student groups; admins manage them, teachers view their results
+    let (show_groups_modal, set_show_groups_modal) = create_signal(false);
+
     // Panel visibility control
     let (show_side_panel, set_show_side_panel) = create_signal(true);
 
@@ -192,6 +233,7 @@ pub fn StudentViewContent() -> impl IntoView {
         set_readplan_filter(false);
         set_gt_filter(false);
         set_bip_filter(false);
+        set_attribute_filter(None);
     };
 
     // Grade filter transformer (converts "all" to empty string for matching logic)
@@ -265,6 +307,22 @@ pub fn StudentViewContent() -> impl IntoView {
                 />
             </Show>
 
+            <Show when=move || show_duplicates_modal()>
+                <DuplicateStudentsModal
+                    set_show_modal=set_show_duplicates_modal
+                    set_refresh_trigger=set_refresh_trigger
+                />
+            </Show>
+
+            <Show when=move || show_groups_modal()>
+                <StudentGroupsModal
+                    set_show_modal=set_show_groups_modal
+                    students=Signal::derive(move || students.get().flatten().unwrap_or_default())
+                    teachers=Signal::derive(move || teachers.get().flatten().unwrap_or_default())
+                    can_manage=Signal::derive(is_admin)
+                />
+            </Show>
+
             // Main content area with dynamic width based on panel state
             <div class=move || {
                 if panel_expanded() {
@@ -290,6 +348,9 @@ pub fn StudentViewContent() -> impl IntoView {
                         teachers=Signal::derive(move || teacher_names())
                         on_clear_filters=Callback::new(handle_clear_filters)
                         is_panel_expanded=Signal::derive(move || panel_expanded())
+                        attribute_definitions=attribute_definition_list
+                        attribute_filter=attribute_filter
+                        set_attribute_filter=set_attribute_filter
                     />
                 </div>
 
@@ -297,6 +358,7 @@ pub fn StudentViewContent() -> impl IntoView {
                 <StudentTable
                     students=students
                     search_term=search_term
+                    search_ranks=search_ranks
                     grade_filter=Signal::derive(move || transformed_grade_filter())
                     teacher_filter=teacher_filter
                     iep_filter=iep_filter
@@ -306,6 +368,9 @@ pub fn StudentViewContent() -> impl IntoView {
                     readplan_filter=readplan_filter
                     gt_filter=gt_filter
                     bip_filter=bip_filter
+                    attribute_definitions=attribute_definition_list
+                    attribute_values=Signal::derive(move || attribute_values.get().unwrap_or_default())
+                    attribute_filter=attribute_filter
                     is_panel_expanded=Signal::derive(move || panel_expanded())
                     selected_student=selected_student
                     set_selected_student=set_selected_student
@@ -320,6 +385,20 @@ pub fn StudentViewContent() -> impl IntoView {
                     >
                         "Bulk Upload"
                     </button>
+                    <button
+                        class="px-3 md:px-4 py-2 bg-[#F9F9F8] hover:bg-[#DADADA] hover:bg-opacity-30 font-bold text-[#2E3A59] border-[#DADADA] rounded-md border transition-colors text-sm md:text-base"
+                        on:click=move |_| set_show_groups_modal(true)
+                    >
+                        "Groups"
+                    </button>
+                    <Show when=is_admin>
+                        <button
+                            class="px-3 md:px-4 py-2 bg-[#F9F9F8] hover:bg-[#DADADA] hover:bg-opacity-30 font-bold text-[#2E3A59] border-[#DADADA] rounded-md border transition-colors text-sm md:text-base"
+                            on:click=move |_| set_show_duplicates_modal(true)
+                        >
+                            "Find Duplicates"
+                        </button>
+                    </Show>
                     <button
                         class="inline-flex items-center justify-center px-3 md:px-4 py-2 bg-[#F44336] text-white rounded-md font-semibold hover:bg-[#D32F2F] focus:outline-none focus:ring-2 focus:ring-[#F44336]/50 transition-colors duration-200 shadow-sm hover:shadow-md text-sm md:text-base"
                         class:opacity-50=move || selected_student().is_none()
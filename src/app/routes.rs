@@ -1,2 +1,5 @@
 pub mod saml_routes;
 pub use saml_routes::*;
+
+pub mod oidc_routes;
+pub use oidc_routes::*;
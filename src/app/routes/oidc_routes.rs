@@ -0,0 +1,203 @@
+// src/app/routes/oidc_routes.rs
+
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::{global_database, oidc_database, user_database};
+        use crate::app::models::auth::SessionProvider;
+        use crate::app::server_functions::auth::session_metadata;
+        use crate::app::services::oidc;
+        use crate::app::utils::html::escape_html;
+        use actix_web::{web, HttpRequest, HttpResponse, Result};
+        use serde::Deserialize;
+        use uuid::Uuid;
+
+        #[derive(Deserialize)]
+        pub struct OidcLoginQuery {
+            institution: String,
+            redirect: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        pub struct OidcCallbackQuery {
+            code: Option<String>,
+            state: Option<String>,
+            error: Option<String>,
+            error_description: Option<String>,
+        }
+
+        fn redirect_uri() -> String {
+            let base_url = std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+            format!("{}/oidc/callback", base_url.trim_end_matches('/'))
+        }
+
+        // Only same-site paths, so the login flow cannot be used as an open redirect
+        fn safe_redirect(redirect: Option<&str>) -> String {
+            match redirect {
+                Some(path) if path.starts_with('/') && !path.starts_with("//") && !path.contains('\\') => path.to_string(),
+                _ => "/dashboard".to_string(),
+            }
+        }
+
+        // Start the authorization-code flow for an institution
+        pub async fn oidc_login(
+            pool: web::Data<sqlx::PgPool>,
+            query: web::Query<OidcLoginQuery>,
+        ) -> Result<HttpResponse> {
+            let config_id = match Uuid::parse_str(&query.institution) {
+                Ok(id) => id,
+                Err(_) => return Ok(HttpResponse::BadRequest().body("Unknown institution")),
+            };
+
+            let config = match oidc_database::get_oidc_config_by_id(&pool, config_id).await {
+                Ok(Some(config)) if config.active => config,
+                Ok(_) => return Ok(HttpResponse::BadRequest().body("Institution not configured")),
+                Err(e) => {
+                    log::error!("Error getting OIDC config: {:?}", e);
+                    return Ok(HttpResponse::InternalServerError().body("Configuration error"));
+                }
+            };
+
+            let (metadata, _) = match oidc::provider(&config.issuer, false).await {
+                Ok(provider) => provider,
+                Err(e) => {
+                    log::error!("OIDC discovery failed for {}: {}", config.institution_name, e);
+                    return Ok(HttpResponse::BadGateway().body("Identity provider unavailable"));
+                }
+            };
+
+            let state = oidc::random_token();
+            let nonce = oidc::random_token();
+            let pkce = oidc::generate_pkce();
+            let redirect_to = safe_redirect(query.redirect.as_deref());
+
+            if let Err(e) = oidc_database::record_oidc_login(&pool, &state, config.id, &nonce, &pkce.verifier, &redirect_to).await {
+                log::error!("Failed to record OIDC login: {:?}", e);
+                return Ok(HttpResponse::InternalServerError().body("Failed to start login"));
+            }
+
+            match oidc::authorization_url(&metadata, &config.client_id, &redirect_uri(), &config.scopes, &state, &nonce, &pkce.challenge) {
+                Ok(url) => Ok(HttpResponse::Found().append_header(("Location", url)).finish()),
+                Err(e) => {
+                    log::error!("Failed to build OIDC authorization URL: {}", e);
+                    Ok(HttpResponse::InternalServerError().body("Failed to start login"))
+                }
+            }
+        }
+
+        // The IdP redirects back here with the authorization code
+        pub async fn oidc_callback(
+            pool: web::Data<sqlx::PgPool>,
+            req: HttpRequest,
+            query: web::Query<OidcCallbackQuery>,
+        ) -> Result<HttpResponse> {
+            if let Some(error) = &query.error {
+                log::warn!("OIDC provider returned an error: {} {:?}", error, query.error_description);
+                return Ok(HttpResponse::Found().append_header(("Location", "/login?error=sso")).finish());
+            }
+
+            let (code, state) = match (&query.code, &query.state) {
+                (Some(code), Some(state)) => (code, state),
+                _ => return Ok(HttpResponse::BadRequest().body("Missing code or state")),
+            };
+
+            let pending = match oidc_database::consume_oidc_login(&pool, state).await {
+                Ok(Some(pending)) => pending,
+                Ok(None) => return Ok(HttpResponse::BadRequest().body("Login request expired or already used")),
+                Err(e) => {
+                    log::error!("Failed to look up OIDC login: {:?}", e);
+                    return Ok(HttpResponse::InternalServerError().body("Login failed"));
+                }
+            };
+
+            let config = match oidc_database::get_oidc_config_by_id(&pool, pending.config_id).await {
+                Ok(Some(config)) if config.active => config,
+                Ok(_) => return Ok(HttpResponse::BadRequest().body("Institution not configured")),
+                Err(e) => {
+                    log::error!("Error getting OIDC config: {:?}", e);
+                    return Ok(HttpResponse::InternalServerError().body("Configuration error"));
+                }
+            };
+
+            let claims = match oidc::complete_login(
+                &config.issuer,
+                &config.client_id,
+                &config.client_secret,
+                &redirect_uri(),
+                code,
+                &pending.code_verifier,
+                &pending.nonce,
+            ).await {
+                Ok(claims) => claims,
+                Err(e) => {
+                    log::warn!("Rejected OIDC login for {}: {}", config.institution_name, e);
+                    return Ok(HttpResponse::Forbidden().body("Login could not be verified"));
+                }
+            };
+
+            let user = match oidc_database::provision_oidc_user(&pool, &config, &claims).await {
+                Ok(user) => user,
+                Err(e) => {
+                    log::error!("Failed to provision OIDC user: {:?}", e);
+                    return Ok(HttpResponse::Forbidden().body("User provisioning failed"));
+                }
+            };
+
+            let session_settings = global_database::get_all_global_settings(&pool)
+                .await
+                .unwrap_or_default()
+                .session;
+            let metadata = session_metadata(&req, SessionProvider::Oidc);
+            let session_token = match user_database::create_session(&pool, user.id, &metadata, &session_settings).await {
+                Ok(token) => token,
+                Err(e) => {
+                    log::error!("Failed to create session: {:?}", e);
+                    return Ok(HttpResponse::InternalServerError().body("Session creation failed"));
+                }
+            };
+
+            log::info!("OIDC login successful for user: {} ({})", user.username, user.id);
+
+            // Same-origin page hop so the SameSite=Strict cookie is sent on the
+            // next request, as in the SAML ACS handler
+            let html_response = format!(
+                r#"<!DOCTYPE html>
+        <html>
+        <head>
+            <title>Login Successful</title>
+            <meta http-equiv="refresh" content="0;url={}">
+        </head>
+        <body>
+            <p>Login successful! Redirecting...</p>
+        </body>
+        </html>"#,
+                escape_html(&pending.redirect_to)
+            );
+
+            Ok(HttpResponse::Ok()
+                .content_type("text/html")
+                .cookie(
+                    actix_web::cookie::Cookie::build("session", session_token)
+                        .path("/")
+                        .http_only(true)
+                        .same_site(actix_web::cookie::SameSite::Strict)
+                        .max_age(actix_web::cookie::time::Duration::seconds(session_settings.max_lifetime().num_seconds()))
+                        .secure(true)
+                        .finish(),
+                )
+                .body(html_response))
+        }
+
+        // Configure OIDC routes
+        pub fn configure_oidc_routes(cfg: &mut web::ServiceConfig) {
+            cfg.service(
+                web::scope("/oidc")
+                    .route("/login", web::get().to(oidc_login))
+                    .route("/callback", web::get().to(oidc_callback)),
+            );
+        }
+    }
+}
+
+// Non-SSR placeholder for client-side compilation
+#[cfg(not(feature = "ssr"))]
+pub fn configure_oidc_routes(_cfg: &mut ()) {}
@@ -2,7 +2,9 @@
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "ssr")] {
-        use crate::app::db::{saml_database, user_database};
+        use crate::app::db::{global_database, saml_database, user_database};
+        use crate::app::models::auth::SessionProvider;
+        use crate::app::server_functions::auth::session_metadata;
         use crate::app::models::user::SessionUser;
         use leptos::html::form as leptos_form;
         use actix_web::{web, HttpRequest, HttpResponse, Result};
@@ -92,8 +94,15 @@ cfg_if::cfg_if! {
                 }
             };
 
+            // Remember the request ID so the response's InResponseTo can be checked
+            let request_id = saml_database::SamlManager::new_request_id();
+            if let Err(e) = saml_database::record_saml_request(&pool, &request_id, &config.id.to_string()).await {
+                log::error!("Failed to record SAML request: {:?}", e);
+                return Ok(HttpResponse::InternalServerError().body("Failed to generate SAML request"));
+            }
+
             // Generate SAML auth request - institution info will be embedded in RelayState
-            let auth_url = match generate_auth_request_url(&saml_manager, &config, query.relay_state.as_deref()) {
+            let auth_url = match generate_auth_request_url(&saml_manager, &config, &request_id, query.relay_state.as_deref()) {
                 Ok(url) => url,
                 Err(e) => {
                     log::error!("Failed to generate SAML auth request: {:?}", e);
@@ -114,9 +123,9 @@ cfg_if::cfg_if! {
     fn generate_auth_request_url(
         saml_manager: &saml_database::SamlManager,
         config: &crate::app::models::auth::SamlConfig,
+        request_id: &str,
         relay_state: Option<&str>,
     ) -> Result<String, Box<dyn std::error::Error>> {
-        use uuid::Uuid;
         use chrono::Utc;
         use base64::{engine::general_purpose, Engine as _};
         use flate2::write::DeflateEncoder;
@@ -124,7 +133,6 @@ cfg_if::cfg_if! {
         use std::io::Write;
 
         // Build the SAML AuthnRequest with proper XML formatting
-        let request_id = Uuid::new_v4().to_string();
         let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
 
         let saml_request = format!(
@@ -135,8 +143,8 @@ cfg_if::cfg_if! {
             request_id,
             timestamp,
             config.sso_url,
-            format!("{}/saml/acs", saml_manager.base_url),
-            format!("{}/saml/metadata", saml_manager.base_url)
+            saml_manager.acs_url(),
+            saml_manager.sp_entity_id()
         );
 
         log::debug!("Generated SAML Request XML: {}", saml_request);
@@ -289,8 +297,14 @@ cfg_if::cfg_if! {
 
             log::debug!("SAML Response XML: {}", saml_xml);
 
+            // Verify signature, conditions and replay before trusting any of it
+            if let Err(e) = saml_database::verify_saml_response(&pool, &saml_manager, &config, &saml_xml).await {
+                log::error!("SAML response failed validation: {:?}", e);
+                return Ok(HttpResponse::Forbidden().body("SAML response could not be verified"));
+            }
+
             // Parse SAML response
-            let parsed_response = match saml_manager.parse_saml_response(&saml_xml, &institution_name) {
+            let parsed_response = match saml_manager.parse_saml_response(&saml_xml, &config) {
                 Ok(response) => response,
                 Err(e) => {
                     log::error!("Failed to parse SAML response: {:?}", e);
@@ -301,7 +315,7 @@ cfg_if::cfg_if! {
             log::info!("Parsed SAML response for user: {}", parsed_response.name_id);
 
             // Provision or get existing user
-            let user = match saml_database::provision_saml_user(&pool, &parsed_response, &institution_name).await {
+            let user = match saml_database::provision_saml_user(&pool, &parsed_response, &institution_name, config.auto_provision).await {
                 Ok(user) => user,
                 Err(e) => {
                     log::error!("Failed to provision SAML user: {:?}", e);
@@ -310,7 +324,12 @@ cfg_if::cfg_if! {
             };
 
             // Create session
-            let session_token = match user_database::create_session(&pool, user.id).await {
+            let session_settings = global_database::get_all_global_settings(&pool)
+                .await
+                .unwrap_or_default()
+                .session;
+            let metadata = session_metadata(&req, SessionProvider::Saml);
+            let session_token = match user_database::create_session(&pool, user.id, &metadata, &session_settings).await {
                 Ok(token) => token,
                 Err(e) => {
                     log::error!("Failed to create session: {:?}", e);
@@ -348,7 +367,7 @@ cfg_if::cfg_if! {
                         .path("/")
                         .http_only(true)
                         .same_site(actix_web::cookie::SameSite::Strict)
-                        .max_age(actix_web::cookie::time::Duration::days(7))
+                        .max_age(actix_web::cookie::time::Duration::seconds(session_settings.max_lifetime().num_seconds()))
                         .secure(true)
                         .finish(),
                 )
@@ -19,6 +19,14 @@ pub use employees::get_employees;
 
 pub mod auth;
 
+pub mod mfa;
+
+pub mod sessions;
+
+pub mod api_tokens;
+
+pub mod audit;
+
 pub mod bulk_students;
 pub use bulk_students::upload_students_bulk;
 
@@ -49,8 +57,31 @@ pub use enrollments::get_enrollments;
 pub mod globals;
 pub use globals::get_global_settings;
 
+pub mod student_encryption;
+
+pub mod student_export;
+
+pub mod retention;
+
+pub mod student_merge;
+
+pub mod student_history;
+
+pub mod student_attributes;
+
+pub mod student_groups;
+
+pub mod oneroster;
+
+pub mod import_mapping;
+
+pub mod score_import;
+
 pub mod authorization;
 pub use authorization::*;
 
 pub mod saml_auth;
 pub use saml_auth::*;
+
+pub mod oidc_auth;
+pub use oidc_auth::*;
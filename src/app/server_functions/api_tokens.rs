@@ -0,0 +1,132 @@
+use crate::app::models::auth::{ApiToken, NewApiToken};
+use crate::app::models::permission::TokenScope;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::api_token_database, models::permission::Permission,
+    server_functions::authorization::require_permission, services::api_tokens,
+};
+use leptos::*;
+
+// Mints a token for the caller. The secret is only ever returned here.
+#[server(CreateApiToken, "/api")]
+pub async fn create_api_token(
+    name: String,
+    scopes: Vec<TokenScope>,
+    expires_in_days: i64,
+) -> Result<NewApiToken, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        // Authenticated is outside every scope, so tokens cannot mint tokens
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let name = name.trim();
+        if name.is_empty() || name.len() > 100 {
+            return Err(ServerFnError::new(
+                "Token name must be between 1 and 100 characters",
+            ));
+        }
+        if scopes.is_empty() {
+            return Err(ServerFnError::new("Choose at least one scope"));
+        }
+        if let Some(scope) = scopes.iter().find(|scope| !scope.is_available_to(&user)) {
+            return Err(ServerFnError::new(format!(
+                "Your role cannot grant the {} scope",
+                scope
+            )));
+        }
+        if !(1..=api_tokens::MAX_LIFETIME_DAYS).contains(&expires_in_days) {
+            return Err(ServerFnError::new(format!(
+                "Tokens must expire within 1 to {} days",
+                api_tokens::MAX_LIFETIME_DAYS
+            )));
+        }
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let mut scopes = scopes;
+        scopes.sort_by_key(|scope| scope.to_string());
+        scopes.dedup();
+
+        let token = api_tokens::generate_token();
+        let details = api_token_database::create_api_token(
+            &pool,
+            user.id,
+            name,
+            &api_tokens::hash_token(&token),
+            &api_tokens::display_prefix(&token),
+            &scopes,
+            chrono::Utc::now() + chrono::Duration::days(expires_in_days),
+        )
+        .await?;
+
+        log::info!(
+            "User {} created API token {} with scopes {:?}",
+            user.username,
+            details.id,
+            scopes
+        );
+        Ok(NewApiToken { token, details })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(GetMyApiTokens, "/api")]
+pub async fn get_my_api_tokens() -> Result<Vec<ApiToken>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        api_token_database::list_api_tokens(&pool, user.id).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(RevokeMyApiToken, "/api")]
+pub async fn revoke_my_api_token(token_id: i64) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        if !api_token_database::revoke_api_token(&pool, user.id, token_id).await? {
+            return Err(ServerFnError::new("Token not found"));
+        }
+
+        log::info!("User {} revoked API token {}", user.username, token_id);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
@@ -3,6 +3,12 @@ use crate::app::models::assessment::SubjectEnum;
 use crate::app::models::assessment::{
     Assessment, CreateNewAssessmentRequest, DeleteAssessmentRequest, UpdateAssessmentRequest,
 };
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{audit::record_audit_event, authorization::require_permission},
+};
 use leptos::*;
 #[cfg(feature = "ssr")]
 use {
@@ -20,6 +26,9 @@ pub async fn get_assessments() -> Result<Vec<Assessment>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -52,6 +61,9 @@ pub async fn get_assessment(id: String) -> Result<Assessment, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -88,6 +100,8 @@ pub async fn add_assessment(
         use leptos_actix::extract;
         use uuid::Uuid;
 
+        let user = require_permission(Permission::ManageAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -128,12 +142,22 @@ pub async fn add_assessment(
             )
         };
 
-        assessment_database::add_assessment(&buffer_assessment, &pool)
+        let created_assessment = assessment_database::add_assessment(&buffer_assessment, &pool)
             .await
             .map_err(|e| {
                 log::error!("Database error while adding assessment: {}", e);
                 ServerFnError::new(format!("Database error: {}", e))
-            })
+            })?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Create,
+            AuditEntity::Assessment,
+            created_assessment.id,
+            AuditChange::created(&created_assessment),
+        )
+        .await?;
+        Ok(created_assessment)
     }
 
     #[cfg(not(feature = "ssr"))]
@@ -152,6 +176,9 @@ pub async fn delete_assessment(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -164,7 +191,18 @@ pub async fn delete_assessment(
         )
         .await
         {
-            Ok(deleted) => Ok(deleted),
+            Ok(deleted) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Delete,
+                    AuditEntity::Assessment,
+                    deleted.id,
+                    AuditChange::deleted(&deleted),
+                )
+                .await?;
+                Ok(deleted)
+            }
             Err(e) => Err(ServerFnError::new(format!(
                 "Error in deleting assessment: {}",
                 e
@@ -187,12 +225,30 @@ pub async fn update_assessment_score(test_id: String) -> Result<(), ServerFnErro
         use actix_web::web;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         match assessment_database::update_all_assessments_referencing_test(&test_id, &pool).await {
-            Ok(()) => Ok(()),
+            Ok(changes) => {
+                for (assessment_id, previous_score, composite_score) in changes {
+                    record_audit_event(
+                        &pool,
+                        &user,
+                        AuditAction::Update,
+                        AuditEntity::Assessment,
+                        assessment_id,
+                        AuditChange::updated(
+                            &serde_json::json!({ "composite_score": previous_score }),
+                            &serde_json::json!({ "composite_score": composite_score }),
+                        ),
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
             Err(e) => Err(ServerFnError::new(format!(
                 "Failed to update assessment scores: {}",
                 e
@@ -217,12 +273,18 @@ pub async fn update_assessment(
         use actix_web::web;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Attempting to update assessment");
 
+        let before =
+            assessment_database::get_assessment(update_assessment_request.id.to_string(), &pool)
+                .await?;
+
         let buffer_assessment = if update_assessment_request.test_sequence.is_some() {
             Assessment::new_with_sequence(
                 update_assessment_request.name,
@@ -256,7 +318,18 @@ pub async fn update_assessment(
         };
 
         match assessment_database::update_assessment(&buffer_assessment, &pool).await {
-            Ok(updated_assessment) => Ok(updated_assessment),
+            Ok(updated_assessment) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Assessment,
+                    updated_assessment.id,
+                    AuditChange::updated(&before, &updated_assessment),
+                )
+                .await?;
+                Ok(updated_assessment)
+            }
             Err(e) => Err(ServerFnError::new(format!(
                 "Failed to update assessment: {}",
                 e
@@ -281,6 +354,8 @@ pub async fn get_test_sequence(
         use actix_web::web;
         use leptos_actix::extract;
 
+        require_permission(Permission::ViewAssessments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
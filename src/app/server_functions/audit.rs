@@ -0,0 +1,122 @@
+use crate::app::models::audit::{AuditChainStatus, AuditEvent, AuditEventFilter};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::audit_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    models::user::SessionUser,
+    server_functions::authorization::require_permission,
+    services::audit,
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+// Events shown on the audit page at once; the CSV export has no cap
+pub const AUDIT_PAGE_LIMIT: i64 = 500;
+
+// Called by mutating server functions once their change is saved
+#[cfg(feature = "ssr")]
+pub async fn record_audit_event(
+    pool: &PgPool,
+    actor: &SessionUser,
+    action: AuditAction,
+    entity_type: AuditEntity,
+    entity_id: impl ToString,
+    change: AuditChange,
+) -> Result<(), ServerFnError> {
+    audit_database::append_audit_event(
+        pool,
+        actor,
+        action,
+        entity_type,
+        &entity_id.to_string(),
+        change,
+    )
+    .await
+    .map_err(|e| {
+        log::error!(
+            "Failed to record audit event for {} {}: {}",
+            entity_type,
+            entity_id.to_string(),
+            e
+        );
+        e
+    })
+}
+
+#[server(GetAuditEvents, "/api")]
+pub async fn get_audit_events(filter: AuditEventFilter) -> Result<Vec<AuditEvent>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        require_permission(Permission::ViewAuditLog).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        audit_database::list_audit_events(&pool, &filter, Some(AUDIT_PAGE_LIMIT)).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(ExportAuditEventsCsv, "/api")]
+pub async fn export_audit_events_csv(filter: AuditEventFilter) -> Result<String, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewAuditLog).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let events = audit_database::list_audit_events(&pool, &filter, None).await?;
+        log::info!(
+            "User {} exported {} audit events",
+            user.username,
+            events.len()
+        );
+        audit::events_to_csv(&events).map_err(ServerFnError::new)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(VerifyAuditChain, "/api")]
+pub async fn verify_audit_chain() -> Result<AuditChainStatus, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        require_permission(Permission::ViewAuditLog).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let status = audit_database::verify_audit_chain(&pool).await?;
+        if let Some(id) = status.first_invalid_id {
+            log::error!("Audit chain broken at event {}", id);
+        }
+        Ok(status)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
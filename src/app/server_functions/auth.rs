@@ -1,6 +1,18 @@
 use crate::app::db::user_database;
+#[cfg(feature = "ssr")]
+use crate::app::db::{global_database, login_throttle_database};
+#[cfg(feature = "ssr")]
+use crate::app::models::auth::{SessionMetadata, SessionProvider};
+#[cfg(feature = "ssr")]
+use crate::app::models::global::SessionSettings;
+use crate::app::models::mfa::MfaChallenge;
 use crate::app::models::user::{SessionUser, UserRole};
 #[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission,
+    server_functions::{authorization::authorize, mfa},
+};
+#[cfg(feature = "ssr")]
 use actix_web::{cookie::Cookie, http::header, HttpRequest, HttpResponse};
 use leptos::*;
 #[cfg(feature = "ssr")]
@@ -15,12 +27,133 @@ pub struct AuthResponse {
     pub success: bool,
     pub message: String,
     pub user: Option<SessionUser>,
+    // Set when the password was accepted but a second factor is still needed
+    #[serde(default)]
+    pub mfa: Option<MfaChallenge>,
+}
+
+// Client address for throttling and session records. Forwarded headers are
+// only trusted when TRUST_PROXY_HEADERS is set, since clients can forge them.
+#[cfg(feature = "ssr")]
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    if std::env::var("TRUST_PROXY_HEADERS").is_ok() {
+        req.connection_info()
+            .realip_remote_addr()
+            .map(|addr| addr.to_string())
+    } else {
+        req.peer_addr().map(|addr| addr.ip().to_string())
+    }
+}
+
+// Device details stored with a new session
+#[cfg(feature = "ssr")]
+pub fn session_metadata(req: &HttpRequest, provider: SessionProvider) -> SessionMetadata {
+    SessionMetadata {
+        ip_address: client_ip(req),
+        user_agent: req
+            .headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|agent| agent.chars().take(512).collect()),
+        provider,
+    }
+}
+
+// Token of the session making the current request, if any
+#[cfg(feature = "ssr")]
+pub async fn current_session_token() -> Result<Option<String>, ServerFnError> {
+    let req = extract::<HttpRequest>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
+    Ok(req
+        .cookie("session")
+        .map(|cookie| cookie.value().to_string()))
+}
+
+#[cfg(feature = "ssr")]
+pub fn set_session_cookie(session_token: &str, settings: &SessionSettings) {
+    let response = expect_context::<ResponseOptions>();
+    let cookie_value = format!(
+        "session={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}{}",
+        session_token,
+        settings.max_lifetime().num_seconds(),
+        if cfg!(debug_assertions) {
+            ""
+        } else {
+            "; Secure"
+        }
+    );
+
+    response.insert_header(
+        header::SET_COOKIE,
+        header::HeaderValue::from_str(&cookie_value).expect("Failed to create header value"),
+    );
+}
+
+// Creates the session once every login step has succeeded and sets its cookie
+#[cfg(feature = "ssr")]
+pub async fn start_session(
+    pool: &PgPool,
+    user_id: i64,
+    provider: SessionProvider,
+) -> Result<String, ServerFnError> {
+    let req = extract::<HttpRequest>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
+    let settings = global_database::get_all_global_settings(pool)
+        .await
+        .unwrap_or_default()
+        .session;
+
+    let session_token =
+        user_database::create_session(pool, user_id, &session_metadata(&req, provider), &settings)
+            .await?;
+    set_session_cookie(&session_token, &settings);
+
+    Ok(session_token)
+}
+
+// Issues a fresh token for the current session after the account's
+// privileges change, so a previously captured token can't carry them
+#[cfg(feature = "ssr")]
+pub async fn rotate_current_session(pool: &PgPool) -> Result<(), ServerFnError> {
+    let Some(token) = current_session_token().await? else {
+        return Ok(());
+    };
+
+    if let Some(new_token) = user_database::rotate_session(pool, &token).await? {
+        let settings = global_database::get_all_global_settings(pool)
+            .await
+            .unwrap_or_default()
+            .session;
+        set_session_cookie(&new_token, &settings);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub fn too_many_attempts_message(wait: chrono::Duration) -> String {
+    let seconds = wait.num_seconds().max(1);
+    if seconds < 60 {
+        format!(
+            "Too many failed login attempts. Try again in {} seconds.",
+            seconds
+        )
+    } else {
+        format!(
+            "Too many failed login attempts. Try again in {} minutes.",
+            (seconds + 59) / 60
+        )
+    }
 }
 
 #[server(Login, "/api")]
 pub async fn login(username: String, password: String) -> Result<AuthResponse, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        authorize(Permission::Public).await?;
+
         log::info!("Login attempt for user: {}", username);
 
         use actix_web::web;
@@ -30,6 +163,63 @@ pub async fn login(username: String, password: String) -> Result<AuthResponse, S
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
+        let req = extract::<HttpRequest>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
+        let ip_address = client_ip(&req);
+
+        let settings = global_database::get_all_global_settings(&pool)
+            .await
+            .unwrap_or_default();
+        let throttle = &settings.login_throttle;
+
+        // Refuse before touching argon2 while the username or IP is backing off
+        match login_throttle_database::login_retry_after(
+            &pool,
+            throttle,
+            &username,
+            ip_address.as_deref(),
+        )
+        .await
+        {
+            Ok(Some(wait)) => {
+                log::warn!(
+                    "Throttled login for user {} from {:?}",
+                    username,
+                    ip_address
+                );
+                return Ok(AuthResponse {
+                    success: false,
+                    message: too_many_attempts_message(wait),
+                    user: None,
+                    mfa: None,
+                });
+            }
+            Ok(None) => {}
+            Err(e) => {
+                log::error!("Failed to check login throttle: {:?}", e);
+                return Ok(AuthResponse {
+                    success: false,
+                    message: "Database error".to_string(),
+                    user: None,
+                    mfa: None,
+                });
+            }
+        }
+
+        let record_failure = || async {
+            if let Err(e) = login_throttle_database::record_login_failure(
+                &pool,
+                throttle,
+                &username,
+                ip_address.as_deref(),
+            )
+            .await
+            {
+                log::error!("Failed to record login failure: {:?}", e);
+            }
+        };
+
         log::info!("Looking up user in database: {}", username);
         let user_result = user_database::get_user_by_username(&pool, &username).await;
 
@@ -39,29 +229,47 @@ pub async fn login(username: String, password: String) -> Result<AuthResponse, S
                 if user_database::verify_password(&password, &user.password_hash) {
                     log::info!("Password verified for user: {}", username);
 
-                    match user_database::create_session(&pool, user.id).await {
-                        Ok(session_token) => {
-                            log::info!("Session created for user: {}", username);
+                    // Accounts with a second factor finish signing in through
+                    // verify_mfa_login, which clears the failure count only
+                    // once the second factor passes
+                    match mfa::begin_mfa_challenge(&pool, &user, &settings).await {
+                        Ok(Some(challenge)) => {
+                            log::info!("Second factor required for user: {}", username);
+                            return Ok(AuthResponse {
+                                success: false,
+                                message: "Enter the code from your authenticator app".to_string(),
+                                user: None,
+                                mfa: Some(challenge),
+                            });
+                        }
+                        Ok(None) => {
+                            if let Err(e) =
+                                login_throttle_database::clear_login_failures(&pool, &username)
+                                    .await
+                            {
+                                log::error!("Failed to clear login failures: {:?}", e);
+                            }
+                        }
+                        Err(e) => {
+                            log::error!("Failed to start MFA challenge: {:?}", e);
+                            return Ok(AuthResponse {
+                                success: false,
+                                message: "Failed to start two-factor authentication".to_string(),
+                                user: None,
+                                mfa: None,
+                            });
+                        }
+                    }
 
-                            // Set secure session cookie
-                            let response = expect_context::<ResponseOptions>();
-                            let cookie_value =
-                                format!(
-                                "session={}; Path=/; HttpOnly; SameSite=Strict; Max-Age=604800{}",
-                                session_token,
-                                if cfg!(debug_assertions) { "" } else { "; Secure" }
-                            );
-
-                            response.insert_header(
-                                header::SET_COOKIE,
-                                header::HeaderValue::from_str(&cookie_value)
-                                    .expect("Failed to create header value"),
-                            );
+                    match start_session(&pool, user.id, SessionProvider::Password).await {
+                        Ok(_) => {
+                            log::info!("Session created for user: {}", username);
 
                             Ok(AuthResponse {
                                 success: true,
                                 message: "Login successful".to_string(),
                                 user: Some(user.to_session_user()), // Convert to SessionUser
+                                mfa: None,
                             })
                         }
                         Err(e) => {
@@ -70,24 +278,29 @@ pub async fn login(username: String, password: String) -> Result<AuthResponse, S
                                 success: false,
                                 message: "Failed to create session".to_string(),
                                 user: None,
+                                mfa: None,
                             })
                         }
                     }
                 } else {
                     log::info!("Invalid password for user: {}", username);
+                    record_failure().await;
                     Ok(AuthResponse {
                         success: false,
                         message: "Invalid credentials".to_string(),
                         user: None,
+                        mfa: None,
                     })
                 }
             }
             Ok(None) => {
                 log::info!("User not found: {}", username);
+                record_failure().await;
                 Ok(AuthResponse {
                     success: false,
                     message: "Invalid credentials".to_string(),
                     user: None,
+                    mfa: None,
                 })
             }
             Err(e) => {
@@ -96,6 +309,7 @@ pub async fn login(username: String, password: String) -> Result<AuthResponse, S
                     success: false,
                     message: "Database error".to_string(),
                     user: None,
+                    mfa: None,
                 })
             }
         }
@@ -114,6 +328,8 @@ pub async fn logout() -> Result<AuthResponse, ServerFnError> {
         use actix_web::web;
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -130,6 +346,7 @@ pub async fn logout() -> Result<AuthResponse, ServerFnError> {
                     success: false,
                     message: "Failed to parse cookies".to_string(),
                     user: None,
+                    mfa: None,
                 });
             }
         };
@@ -162,6 +379,7 @@ pub async fn logout() -> Result<AuthResponse, ServerFnError> {
             success: true,
             message: "Logout successful".to_string(),
             user: None,
+            mfa: None,
         })
     }
 
@@ -178,6 +396,8 @@ pub async fn get_current_user() -> Result<Option<SessionUser>, ServerFnError> {
         use actix_web::web;
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -228,12 +448,15 @@ pub async fn register(
 ) -> Result<AuthResponse, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        authorize(Permission::Public).await?;
+
         // Input validation
         if username.trim().is_empty() || email.trim().is_empty() || password.len() < 8 {
             return Ok(AuthResponse {
                 success: false,
                 message: "Invalid input: username and email cannot be empty, password must be at least 8 characters".to_string(),
                 user: None,
+                mfa: None,
             });
         }
 
@@ -252,6 +475,7 @@ pub async fn register(
                 success: false,
                 message: "Username already exists".to_string(),
                 user: None,
+                mfa: None,
             });
         }
 
@@ -261,6 +485,7 @@ pub async fn register(
                 success: false,
                 message: "Email already exists".to_string(),
                 user: None,
+                mfa: None,
             });
         }
 
@@ -272,30 +497,13 @@ pub async fn register(
                 log::info!("User created successfully: {}", username);
 
                 // Create session
-                match user_database::create_session(&pool, user.id).await {
-                    Ok(session_token) => {
-                        // Set session cookie
-                        let response = expect_context::<ResponseOptions>();
-                        let cookie_value = format!(
-                            "session={}; Path=/; HttpOnly; SameSite=Strict; Max-Age=604800{}",
-                            session_token,
-                            if cfg!(debug_assertions) {
-                                ""
-                            } else {
-                                "; Secure"
-                            }
-                        );
-
-                        response.insert_header(
-                            header::SET_COOKIE,
-                            header::HeaderValue::from_str(&cookie_value)
-                                .expect("Failed to create header value"),
-                        );
-
+                match start_session(&pool, user.id, SessionProvider::Password).await {
+                    Ok(_) => {
                         Ok(AuthResponse {
                             success: true,
                             message: "Registration successful".to_string(),
                             user: Some(user.to_session_user()), // Convert to SessionUser
+                            mfa: None,
                         })
                     }
                     Err(e) => {
@@ -304,6 +512,7 @@ pub async fn register(
                             success: false,
                             message: "Failed to create session".to_string(),
                             user: None,
+                            mfa: None,
                         })
                     }
                 }
@@ -314,6 +523,7 @@ pub async fn register(
                     success: false,
                     message: "Failed to create user".to_string(),
                     user: None,
+                    mfa: None,
                 })
             }
         }
@@ -336,6 +546,8 @@ pub async fn request_password_reset(email: String) -> Result<AuthResponse, Serve
         use leptos_actix::extract;
         use rand::{distributions::Alphanumeric, Rng};
 
+        authorize(Permission::Public).await?;
+
         log::info!("Password reset requested for email: {}", email);
 
         let pool = extract::<web::Data<PgPool>>()
@@ -365,6 +577,7 @@ pub async fn request_password_reset(email: String) -> Result<AuthResponse, Serve
                             success: true,
                             message: "Password reset instructions sent to your email".to_string(),
                             user: None,
+                            mfa: None,
                         })
                     }
                     Err(e) => {
@@ -373,6 +586,7 @@ pub async fn request_password_reset(email: String) -> Result<AuthResponse, Serve
                             success: false,
                             message: "Failed to initiate password reset".to_string(),
                             user: None,
+                            mfa: None,
                         })
                     }
                 }
@@ -385,6 +599,7 @@ pub async fn request_password_reset(email: String) -> Result<AuthResponse, Serve
                         "If this email is registered, password reset instructions have been sent"
                             .to_string(),
                     user: None,
+                    mfa: None,
                 })
             }
             Err(e) => {
@@ -393,6 +608,7 @@ pub async fn request_password_reset(email: String) -> Result<AuthResponse, Serve
                     success: false,
                     message: "An error occurred processing your request".to_string(),
                     user: None,
+                    mfa: None,
                 })
             }
         }
@@ -411,6 +627,8 @@ pub async fn validate_reset_token(token: String) -> Result<bool, ServerFnError>
         use actix_web::web;
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -440,11 +658,14 @@ pub async fn reset_password(
         use actix_web::web;
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         if new_password.len() < 8 {
             return Ok(AuthResponse {
                 success: false,
                 message: "Password must be at least 8 characters long".to_string(),
                 user: None,
+                mfa: None,
             });
         }
 
@@ -464,17 +685,27 @@ pub async fn reset_password(
                     )
                     .await
                     {
-                        Ok(_) => Ok(AuthResponse {
-                            success: true,
-                            message: "Password successfully reset".to_string(),
-                            user: None,
-                        }),
+                        Ok(_) => {
+                            // Sessions signed in with the old password end with it
+                            if let Err(e) =
+                                user_database::revoke_all_user_sessions(&pool, user.id, None).await
+                            {
+                                log::error!("Failed to revoke sessions after reset: {:?}", e);
+                            }
+                            Ok(AuthResponse {
+                                success: true,
+                                message: "Password successfully reset".to_string(),
+                                user: None,
+                                mfa: None,
+                            })
+                        }
                         Err(e) => {
                             log::error!("Failed to update password: {:?}", e);
                             Ok(AuthResponse {
                                 success: false,
                                 message: "Failed to reset password".to_string(),
                                 user: None,
+                                mfa: None,
                             })
                         }
                     }
@@ -483,6 +714,7 @@ pub async fn reset_password(
                     success: false,
                     message: "Invalid reset token".to_string(),
                     user: None,
+                    mfa: None,
                 }),
                 Err(e) => {
                     log::error!("Database error looking up user by reset token: {:?}", e);
@@ -490,6 +722,7 @@ pub async fn reset_password(
                         success: false,
                         message: "An error occurred processing your request".to_string(),
                         user: None,
+                        mfa: None,
                     })
                 }
             }
@@ -498,6 +731,7 @@ pub async fn reset_password(
                 success: false,
                 message: "Invalid or expired reset token".to_string(),
                 user: None,
+                mfa: None,
             })
         }
     }
@@ -1,6 +1,15 @@
+use crate::app::models::permission::Permission;
 use crate::app::models::user::{SessionUser, UserRole};
 use leptos::*;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use {
+    crate::app::db::user_database,
+    crate::app::errors::AuthError,
+    crate::app::models::permission::DataScope,
+    actix_web::{HttpMessage, HttpRequest},
+    leptos_actix::extract,
+};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AuthorizationCheck {
@@ -135,3 +144,117 @@ pub async fn check_page_authorization(
         Err(ServerFnError::ServerError("Not implemented".to_string()))
     }
 }
+
+// Server-side guards. Every #[server] fn calls one of these first so the role
+// check happens at the API boundary rather than only in the page router. The
+// user comes from the request extensions populated by the Authentication
+// middleware, so no extra session lookup is needed.
+#[cfg(feature = "ssr")]
+pub async fn current_session_user() -> Result<Option<SessionUser>, ServerFnError> {
+    let req = extract::<HttpRequest>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
+
+    let user = req.extensions().get::<SessionUser>().cloned();
+    Ok(user)
+}
+
+// Checks the declared permission and returns the caller, if any. Public
+// endpoints use this directly; everything else goes through require_permission.
+#[cfg(feature = "ssr")]
+pub async fn authorize(permission: Permission) -> Result<Option<SessionUser>, ServerFnError> {
+    let user = current_session_user().await?;
+
+    if let Err(e) = permission.check(user.as_ref()) {
+        log::warn!(
+            "Rejected call requiring {} from {}",
+            permission,
+            user.as_ref()
+                .map(|u| u.username.as_str())
+                .unwrap_or("anonymous")
+        );
+        return Err(ServerFnError::new(e.to_string()));
+    }
+
+    Ok(user)
+}
+
+#[cfg(feature = "ssr")]
+pub async fn require_permission(permission: Permission) -> Result<SessionUser, ServerFnError> {
+    authorize(permission)
+        .await?
+        .ok_or_else(|| ServerFnError::new(AuthError::NotAuthenticated.to_string()))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn require_role(roles: &[UserRole]) -> Result<SessionUser, ServerFnError> {
+    let user = current_session_user()
+        .await?
+        .ok_or_else(|| ServerFnError::new(AuthError::NotAuthenticated.to_string()))?;
+
+    // Role checks have no scope to compare against, so tokens never pass them
+    if user.token_scopes.is_some() {
+        return Err(ServerFnError::new("Forbidden: not available to API tokens"));
+    }
+
+    if !user.has_any_role(roles) {
+        log::warn!(
+            "Rejected call requiring one of {:?} from {}",
+            roles,
+            user.username
+        );
+        return Err(ServerFnError::new(format!(
+            "Forbidden: one of {:?} roles required",
+            roles
+        )));
+    }
+
+    Ok(user)
+}
+
+// For endpoints that act on a single account: the owner may always call them,
+// anyone else needs the given permission.
+#[cfg(feature = "ssr")]
+pub async fn require_self_or_permission(
+    user_id: i64,
+    permission: Permission,
+) -> Result<SessionUser, ServerFnError> {
+    let user = require_permission(Permission::Authenticated).await?;
+
+    if user.id != user_id && !permission.is_granted_to(&user) {
+        log::warn!(
+            "User {} attempted to act on account {} without {}",
+            user.username,
+            user_id,
+            permission
+        );
+        return Err(ServerFnError::new(
+            AuthError::Forbidden(permission).to_string(),
+        ));
+    }
+
+    Ok(user)
+}
+
+// Works out which students the caller may see. Read paths for students,
+// scores and enrollments pass the scope down to the db layer so the caseload
+// filter is applied in SQL.
+#[cfg(feature = "ssr")]
+pub async fn data_scope_for(
+    user: &SessionUser,
+    pool: &sqlx::PgPool,
+) -> Result<DataScope, ServerFnError> {
+    if user.is_admin() {
+        return Ok(DataScope::Unrestricted);
+    }
+
+    let employee_id = user_database::get_user_employee_id(user.id, pool).await?;
+    if employee_id.is_none() {
+        log::warn!(
+            "User {} is not linked to an employee record; caseload is empty",
+            user.username
+        );
+    }
+
+    Ok(DataScope::for_user(user, employee_id))
+}
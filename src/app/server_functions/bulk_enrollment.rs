@@ -1,30 +1,88 @@
-use crate::app::models::bulk_enrollment::EnrollmentCsvRow;
 use crate::app::models::enrollment::{AcademicYear, Enrollment, EnrollmentStatus};
+use crate::app::models::import_validation::{
+    missing_columns, parse_id, ImportValidation, ParsedRows, RowReader,
+};
+use crate::app::models::roster_sync::{ApplySyncRequest, EnrollmentSyncDiff, SyncReport};
 use crate::app::models::student::GradeEnum;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+    services::roster_sync::SyncEnrollment,
+};
 use chrono::{NaiveDate, Utc};
 use csv::ReaderBuilder;
 use leptos::*;
+use std::collections::HashMap;
 use std::str::FromStr;
 use validator::Validate;
 
 #[cfg(feature = "ssr")]
-use {crate::app::db::enrollment_database, sqlx::PgPool};
+use {
+    crate::app::db::{enrollment_database, roster_sync_database},
+    sqlx::PgPool,
+    std::collections::HashSet,
+};
+
+// Checks an enrollment CSV without importing anything, listing every
+// problem by row and column
+#[server(ValidateBulkEnrollment, "/api")]
+pub async fn validate_bulk_enrollment(
+    file_contents: String,
+) -> Result<ImportValidation, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        require_permission(Permission::ManageEnrollments).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let parsed = validate_enrollment_upload(&pool, &file_contents).await?;
+        parsed.report(&file_contents).map_err(ServerFnError::new)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side rendering is not enabled".to_string(),
+        ))
+    }
+}
 
+// Imports the enrollments in a CSV. Any problem fails the whole upload
+// unless `valid_rows_only` is set, in which case rows with problems are
+// skipped.
 #[server(UploadBulkEnrollment, "/api")]
-pub async fn upload_bulk_enrollment(file_contents: String) -> Result<usize, ServerFnError> {
+pub async fn upload_bulk_enrollment(
+    file_contents: String,
+    valid_rows_only: bool,
+) -> Result<usize, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Parse and validate all rows in the CSV file
-        let enrollments = parse_and_validate_enrollments(&file_contents)?;
+        let parsed = validate_enrollment_upload(&pool, &file_contents).await?;
+        let enrollments = if valid_rows_only {
+            parsed.valid()
+        } else {
+            parsed.into_all().map_err(ServerFnError::new)?
+        };
+        if enrollments.is_empty() {
+            return Err(ServerFnError::new("No rows can be imported"));
+        }
 
         // Bulk insert using optimized method
-        match enrollment_database::bulk_insert_enrollments(&pool, &enrollments).await {
+        match enrollment_database::bulk_insert_enrollments(&pool, &enrollments, &user).await {
             Ok(count) => {
                 log::info!("Successfully imported {} enrollments", count);
                 Ok(count)
@@ -43,71 +101,156 @@ pub async fn upload_bulk_enrollment(file_contents: String) -> Result<usize, Serv
     }
 }
 
-// Separate parsing logic for reusability and better error handling
-fn parse_and_validate_enrollments(file_contents: &str) -> Result<Vec<Enrollment>, ServerFnError> {
+// What syncing the enrollment CSV would change, for approval before
+// anything is written
+#[server(PreviewEnrollmentSync, "/api")]
+pub async fn preview_enrollment_sync(
+    file_contents: String,
+) -> Result<EnrollmentSyncDiff, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        require_permission(Permission::ManageEnrollments).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let feed = parse_and_validate_enrollments(&file_contents)?;
+        roster_sync_database::preview_enrollment_sync(&pool, &feed).await
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side rendering is not enabled".to_string(),
+        ))
+    }
+}
+
+// Applies an approved enrollment sync. The diff is worked out again in the
+// same transaction and must match the one previewed.
+#[server(ApplyEnrollmentSync, "/api")]
+pub async fn apply_enrollment_sync(
+    file_contents: String,
+    request: ApplySyncRequest,
+) -> Result<SyncReport, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageEnrollments).await?;
+        request.validate().map_err(ServerFnError::new)?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let feed = parse_and_validate_enrollments(&file_contents)?;
+        let (report, closed) = roster_sync_database::apply_enrollment_sync(
+            &pool,
+            &feed,
+            &request.fingerprint,
+            &request.withdrawal_status,
+            Utc::now().date_naive(),
+            &user,
+        )
+        .await?;
+
+        log::info!(
+            "User {} synced enrollments: {} ({} enrollments closed)",
+            user.username,
+            report.summary(),
+            closed
+        );
+        Ok(report)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side rendering is not enabled".to_string(),
+        ))
+    }
+}
+
+// Columns every enrollment CSV needs
+const ENROLLMENT_COLUMNS: [&str; 4] = ["student_id", "academic_year", "grade_level", "teacher_id"];
+
+// Parses every row of an enrollment CSV, keeping the problems with each row
+// rather than stopping at the first
+fn parse_enrollment_rows(file_contents: &str) -> Result<ParsedRows<Enrollment>, ServerFnError> {
     let mut rdr = ReaderBuilder::new()
         .has_headers(true)
         .flexible(true) // Allow varying number of fields
         .trim(csv::Trim::All) // Trim whitespace from all fields
         .from_reader(file_contents.as_bytes());
 
-    let mut enrollments = Vec::new();
-    let mut errors = Vec::new();
-
     // Get current date for defaults
     let current_date = Utc::now().date_naive();
 
     // Log headers for debugging
     let headers = rdr
         .headers()
-        .map_err(|e| ServerFnError::new(format!("Failed to read CSV headers: {}", e)))?;
+        .map_err(|e| ServerFnError::new(format!("Failed to read CSV headers: {}", e)))?
+        .clone();
     log::info!("Enrollment CSV Headers: {:?}", headers);
 
-    for (row_num, result) in rdr.records().enumerate() {
-        match result {
-            Ok(record) => {
-                match record.deserialize::<EnrollmentCsvRow>(None) {
-                    Ok(csv_row) => {
-                        match parse_enrollment_record(csv_row, row_num + 2, current_date) {
-                            // +2 for header and 1-based indexing
-                            Ok(enrollment) => enrollments.push(enrollment),
-                            Err(e) => errors.push(e),
-                        }
-                    }
-                    Err(e) => {
-                        errors.push(format!(
-                            "Row {}: Failed to deserialize CSV row: {}",
-                            row_num + 2,
-                            e
-                        ));
-                    }
-                }
-            }
+    let missing = missing_columns(&headers, &ENROLLMENT_COLUMNS);
+    if !missing.is_empty() {
+        return Err(ServerFnError::new(format!(
+            "The CSV is missing these columns: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let mut parsed = ParsedRows::default();
+    let mut first_rows: HashMap<(i32, AcademicYear), usize> = HashMap::new();
+    for (index, result) in rdr.records().enumerate() {
+        let row = index + 2; // +2 for header and 1-based indexing
+        parsed.total_rows += 1;
+        let record = match result {
+            Ok(record) => record,
             Err(e) => {
-                errors.push(format!("Row {}: CSV parsing error: {}", row_num + 2, e));
+                parsed.flag(row, "", "", format!("CSV parsing error: {}", e));
+                continue;
             }
-        }
-    }
+        };
 
-    if !errors.is_empty() {
-        let error_msg = format!("Validation errors:\n{}", errors.join("\n"));
-        log::error!("{}", error_msg);
-        return Err(ServerFnError::new(error_msg));
+        let mut reader = RowReader::new(&headers, &record, row);
+        let enrollment = parse_enrollment_row(&mut reader, current_date);
+        parsed.issues.extend(reader.finish());
+        if let Some(enrollment) = enrollment {
+            let key = (enrollment.student_id, enrollment.academic_year.clone());
+            match first_rows.get(&key) {
+                Some(first_row) => parsed.flag(
+                    row,
+                    "academic_year",
+                    &enrollment.academic_year,
+                    format!(
+                        "Student {} is also enrolled for this year on row {}",
+                        enrollment.student_id, first_row
+                    ),
+                ),
+                None => {
+                    first_rows.insert(key, row);
+                }
+            }
+            parsed.rows.push((row, enrollment));
+        }
     }
+    Ok(parsed)
+}
 
-    // Validate all enrollments
-    for (index, enrollment) in enrollments.iter().enumerate() {
-        if let Err(validation_errors) = enrollment.validate() {
-            let error_msg = format!(
-                "Validation failed for enrollment student_id {} (row {}): {:?}",
-                enrollment.student_id,
-                index + 2,
-                validation_errors
-            );
+// The all-or-nothing parse the sync preview works from
+fn parse_and_validate_enrollments(file_contents: &str) -> Result<Vec<Enrollment>, ServerFnError> {
+    let enrollments = parse_enrollment_rows(file_contents)?
+        .into_all()
+        .map_err(|error_msg| {
             log::error!("{}", error_msg);
-            return Err(ServerFnError::new(error_msg));
-        }
-    }
+            ServerFnError::new(error_msg)
+        })?;
 
     log::info!(
         "Successfully parsed and validated {} enrollments",
@@ -116,70 +259,113 @@ fn parse_and_validate_enrollments(file_contents: &str) -> Result<Vec<Enrollment>
     Ok(enrollments)
 }
 
-fn parse_enrollment_record(
-    record: EnrollmentCsvRow,
-    row_num: usize,
-    current_date: NaiveDate,
-) -> Result<Enrollment, String> {
-    // Parse and validate academic year
-    let academic_year = AcademicYear::from_str(&record.academic_year.trim()).map_err(|e| {
-        format!(
-            "Row {}: Invalid academic year '{}': {}. Expected format like '2024-2025'",
-            row_num, record.academic_year, e
-        )
-    })?;
+// Reads one enrollment, noting a problem for each cell that doesn't parse.
+// Returns None if any did.
+fn parse_enrollment_row(reader: &mut RowReader, current_date: NaiveDate) -> Option<Enrollment> {
+    let student_id = reader.parse("student_id", |v| parse_id(v, "Student ID", 0, 2000000000));
+    let academic_year = reader.parse("academic_year", |v| {
+        AcademicYear::from_str(v)
+            .map_err(|_| "Invalid academic year. Expected format like '2024-2025'".to_string())
+    });
+    let grade_level = reader.parse("grade_level", |v| {
+        GradeEnum::from_str(v).map_err(|_| {
+            "Invalid grade level. Valid values: Kindergarten, 1st Grade, 2nd Grade, etc."
+                .to_string()
+        })
+    });
+    // Enrollments use formal teacher IDs, not teacher names
+    let teacher_id = reader.parse("teacher_id", |v| parse_id(v, "Teacher ID", 1, i32::MAX));
 
-    // Parse and validate grade level
-    let grade_level = GradeEnum::from_str(&record.grade_level.trim()).map_err(|e| {
-        format!(
-            "Row {}: Invalid grade level '{}': {}. Valid values: Kindergarten, 1st Grade, 2nd Grade, etc.",
-            row_num, record.grade_level, e
-        )
-    })?;
-
-    // Validate student_id
-    if record.student_id < 0 || record.student_id > 2000000000 {
-        return Err(format!(
-            "Row {}: Student ID {} is out of valid range (0-2000000000)",
-            row_num, record.student_id
-        ));
-    }
+    // Handle notes field
+    let notes = Some(reader.text("notes"))
+        .filter(|notes| !notes.is_empty())
+        .map(str::to_string);
 
-    // Validate teacher_id - IMPORTANT: This uses teacher_id (integer) not teacher name
-    // This reflects the decoupled architecture where enrollments use formal teacher IDs
-    if record.teacher_id < 1 {
-        return Err(format!(
-            "Row {}: Teacher ID {} is invalid (must be positive integer)",
-            row_num, record.teacher_id
-        ));
+    // For bulk uploads, status is always "Active" and the enrollment and
+    // status change dates are today - the CSV's fields are ignored
+    let enrollment = Enrollment {
+        student_id: student_id?,
+        academic_year: academic_year?,
+        grade_level: grade_level?,
+        teacher_id: teacher_id?,
+        status: EnrollmentStatus::Active,
+        enrollment_date: current_date,
+        status_change_date: Some(current_date),
+        notes,
+    };
+    if let Err(validation_errors) = enrollment.validate() {
+        reader.flag("", validation_errors.to_string());
+        return None;
     }
+    Some(enrollment)
+}
 
-    // For bulk uploads, status is always "Active" - ignore CSV status field
-    let status = EnrollmentStatus::Active;
-
-    // For bulk uploads, enrollment_date is always current date - ignore CSV field
-    let enrollment_date = current_date;
-
-    // For bulk uploads, status_change_date defaults to current date - ignore CSV field
-    let status_change_date = Some(current_date);
+// Flags rows the database rules out: students and teachers that don't
+// exist, and students already actively enrolled for the year outside any
+// course
+#[cfg(feature = "ssr")]
+fn check_enrollments_against_roster(
+    parsed: &mut ParsedRows<Enrollment>,
+    student_ids: &HashSet<i32>,
+    teacher_ids: &HashSet<i32>,
+    current: &[SyncEnrollment],
+) {
+    let enrolled: HashSet<(i32, &AcademicYear)> = current
+        .iter()
+        .filter(|enrollment| {
+            enrollment.course_id.is_none() && enrollment.status == EnrollmentStatus::Active
+        })
+        .map(|enrollment| (enrollment.student_id, &enrollment.academic_year))
+        .collect();
 
-    // Handle notes field
-    let notes = if record.notes.trim().is_empty() {
-        None
-    } else {
-        Some(record.notes.trim().to_string())
-    };
+    let mut issues = Vec::new();
+    for (row, enrollment) in &parsed.rows {
+        if !student_ids.contains(&enrollment.student_id) {
+            issues.push((
+                *row,
+                "student_id",
+                enrollment.student_id.to_string(),
+                "No student has this ID".to_string(),
+            ));
+        } else if enrolled.contains(&(enrollment.student_id, &enrollment.academic_year)) {
+            issues.push((
+                *row,
+                "academic_year",
+                enrollment.academic_year.to_string(),
+                "The student already has an active enrollment for this year".to_string(),
+            ));
+        }
+        if !teacher_ids.contains(&enrollment.teacher_id) {
+            issues.push((
+                *row,
+                "teacher_id",
+                enrollment.teacher_id.to_string(),
+                "No teacher has this ID".to_string(),
+            ));
+        }
+    }
+    for (row, column, value, message) in issues {
+        parsed.flag(row, column, value, message);
+    }
+}
 
-    Ok(Enrollment {
-        student_id: record.student_id,
-        academic_year,
-        grade_level,
-        teacher_id: record.teacher_id, // Using teacher_id (integer) for formal enrollment
-        status,
-        enrollment_date,
-        status_change_date,
-        notes,
-    })
+// Parses an enrollment CSV and checks it against the students, teachers
+// and enrollments on file
+#[cfg(feature = "ssr")]
+async fn validate_enrollment_upload(
+    pool: &PgPool,
+    file_contents: &str,
+) -> Result<ParsedRows<Enrollment>, ServerFnError> {
+    let mut parsed = parse_enrollment_rows(file_contents)?;
+    let student_ids = roster_sync_database::list_student_ids(pool).await?;
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    let teacher_ids = roster_sync_database::list_teacher_ids(&mut conn).await?;
+    let current = roster_sync_database::list_sync_enrollments(&mut conn).await?;
+    check_enrollments_against_roster(&mut parsed, &student_ids, &teacher_ids, &current);
+    Ok(parsed)
 }
 
 // Helper function to validate that a teacher_id exists in the teachers table
@@ -211,6 +397,9 @@ pub async fn upload_bulk_enrollment_with_validation(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -249,7 +438,7 @@ pub async fn upload_bulk_enrollment_with_validation(
         }
 
         // Bulk insert using optimized method
-        match enrollment_database::bulk_insert_enrollments(&pool, &enrollments).await {
+        match enrollment_database::bulk_insert_enrollments(&pool, &enrollments, &user).await {
             Ok(count) => {
                 log::info!(
                     "Successfully imported {} enrollments with teacher validation",
@@ -270,3 +459,53 @@ pub async fn upload_bulk_enrollment_with_validation(
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicting_enrollments_are_reported_per_row() {
+        let csv = "student_id,academic_year,grade_level,teacher_id,notes\n\
+                   100,2025-2026,1st Grade,7,\n\
+                   100,2025-2026,2nd Grade,7,\n\
+                   101,2025,Sixth,0,\n\
+                   102,2025-2026,1st Grade,9,\n";
+        let mut parsed = parse_enrollment_rows(csv).unwrap();
+        let current = vec![SyncEnrollment {
+            id: 1,
+            student_id: 102,
+            academic_year: AcademicYear::Year2025_2026,
+            grade_level: GradeEnum::Kindergarten,
+            teacher_id: 7,
+            status: EnrollmentStatus::Active,
+            notes: None,
+            course_id: None,
+        }];
+        check_enrollments_against_roster(
+            &mut parsed,
+            &HashSet::from([100, 101, 102]),
+            &HashSet::from([7]),
+            &current,
+        );
+
+        let mut columns: Vec<(usize, &str)> = parsed
+            .issues
+            .iter()
+            .map(|issue| (issue.row, issue.column.as_str()))
+            .collect();
+        columns.sort();
+        assert_eq!(
+            columns,
+            vec![
+                (3, "academic_year"),
+                (4, "academic_year"),
+                (4, "grade_level"),
+                (4, "teacher_id"),
+                (5, "academic_year"),
+                (5, "teacher_id"),
+            ]
+        );
+        assert_eq!(parsed.valid().len(), 1);
+    }
+}
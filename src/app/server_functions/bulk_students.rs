@@ -1,32 +1,102 @@
-use crate::app::models::bulk_student::{StudentCsvRow, StudentCsvRowAlternative};
+use crate::app::models::import_validation::{
+    missing_columns, parse_bool, parse_id, ImportValidation, ParsedRows, RowReader,
+};
+use crate::app::models::roster_sync::{ApplySyncRequest, StudentSyncDiff, SyncReport};
 use crate::app::models::student::{
     AddStudentRequest, ESLEnum, GenderEnum, GradeEnum, InterventionEnum,
 };
+use crate::app::models::student_attribute::{AttributeDefinition, AttributeValues};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::employee::Employee, models::permission::Permission,
+    server_functions::authorization::require_permission,
+};
 use chrono::NaiveDate;
 use csv::ReaderBuilder;
 use leptos::*;
+use std::collections::HashMap;
 use std::str::FromStr;
 use validator::Validate;
 
 #[cfg(feature = "ssr")]
-use {crate::app::db::student_database, sqlx::PgPool};
+use {
+    crate::app::db::{
+        roster_sync_database, student_attribute_database, student_database, teacher_database,
+    },
+    sqlx::PgPool,
+    std::collections::HashSet,
+};
+
+// Checks a student CSV without importing anything, listing every problem by
+// row and column
+#[server(ValidateStudentsBulk, "/api")]
+pub async fn validate_students_bulk(
+    file_contents: String,
+) -> Result<ImportValidation, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        require_permission(Permission::ManageStudents).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let (parsed, _) = validate_student_upload(&pool, &file_contents).await?;
+        parsed.report(&file_contents).map_err(ServerFnError::new)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
 
+// Imports the students in a CSV. Any problem fails the whole upload unless
+// `valid_rows_only` is set, in which case rows with problems are skipped.
 #[server(UploadStudentsBulk, "/api")]
-pub async fn upload_students_bulk(file_contents: String) -> Result<usize, ServerFnError> {
+pub async fn upload_students_bulk(
+    file_contents: String,
+    valid_rows_only: bool,
+) -> Result<usize, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Parse and validate all students first
-        let students = parse_and_validate_students(&file_contents)?;
+        let (parsed, attribute_keys) = validate_student_upload(&pool, &file_contents).await?;
+        let students = if valid_rows_only {
+            parsed.valid()
+        } else {
+            parsed.into_all().map_err(ServerFnError::new)?
+        };
+        if students.is_empty() {
+            return Err(ServerFnError::new("No rows can be imported"));
+        }
 
         // Bulk insert using optimized method
-        match student_database::bulk_insert_students_optimized(students, &pool).await {
-            Ok(count) => Ok(count),
+        match student_database::bulk_insert_students_optimized(
+            students,
+            &attribute_keys,
+            &user,
+            &pool,
+        )
+        .await
+        {
+            Ok(count) => {
+                log::info!("User {} imported {} students", user.username, count);
+                Ok(count)
+            }
             Err(e) => {
                 log::error!("Bulk student import failed: {:?}", e);
                 Err(ServerFnError::ServerError(format!("Import failed: {}", e)))
@@ -41,212 +111,423 @@ pub async fn upload_students_bulk(file_contents: String) -> Result<usize, Server
     }
 }
 
-// Enhanced parsing logic with better error handling and flexibility
-fn parse_and_validate_students(
+#[cfg(feature = "ssr")]
+async fn parse_student_feed(
+    pool: &PgPool,
+    file_contents: &str,
+) -> Result<(Vec<AddStudentRequest>, Vec<String>), ServerFnError> {
+    let definitions = student_attribute_database::list_attribute_definitions(pool).await?;
+    parse_and_validate_students(file_contents, &definitions)
+}
+
+// What syncing the student CSV would change, for approval before anything
+// is written
+#[server(PreviewStudentSync, "/api")]
+pub async fn preview_student_sync(file_contents: String) -> Result<StudentSyncDiff, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        require_permission(Permission::ManageStudents).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let (feed, attribute_keys) = parse_student_feed(&pool, &file_contents).await?;
+        roster_sync_database::preview_student_sync(&pool, &feed, &attribute_keys).await
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
+
+// Applies an approved student sync: adds and updates students from the CSV
+// and closes the active enrollments of students it leaves out. The diff is
+// worked out again in the same transaction and must match the one previewed.
+#[server(ApplyStudentSync, "/api")]
+pub async fn apply_student_sync(
+    file_contents: String,
+    request: ApplySyncRequest,
+) -> Result<SyncReport, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageStudents).await?;
+        require_permission(Permission::ManageEnrollments).await?;
+        request.validate().map_err(ServerFnError::new)?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let (feed, attribute_keys) = parse_student_feed(&pool, &file_contents).await?;
+        let (report, closed) = roster_sync_database::apply_student_sync(
+            &pool,
+            &feed,
+            &attribute_keys,
+            &request.fingerprint,
+            &request.withdrawal_status,
+            chrono::Utc::now().date_naive(),
+            &user,
+        )
+        .await?;
+
+        log::info!(
+            "User {} synced students: {} ({} enrollments closed)",
+            user.username,
+            report.summary(),
+            closed
+        );
+        Ok(report)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
+
+// Columns every student CSV needs, besides current_grade_level or grade
+const STUDENT_COLUMNS: [&str; 17] = [
+    "firstname",
+    "lastname",
+    "preferred",
+    "gender",
+    "date_of_birth",
+    "student_id",
+    "esl",
+    "teacher",
+    "iep",
+    "bip",
+    "student_504",
+    "readplan",
+    "gt",
+    "intervention",
+    "eye_glasses",
+    "notes",
+    "pin",
+];
+
+// Parses every row of a student CSV, keeping the problems with each row
+// rather than stopping at the first. Columns headed with a custom
+// attribute's key are read into custom_attributes; the keys found are
+// returned alongside the rows.
+fn parse_student_rows(
     file_contents: &str,
-) -> Result<Vec<AddStudentRequest>, ServerFnError> {
+    definitions: &[AttributeDefinition],
+) -> Result<(ParsedRows<AddStudentRequest>, Vec<String>), ServerFnError> {
     let mut rdr = ReaderBuilder::new()
         .has_headers(true)
         .flexible(true) // Allow varying number of fields
         .trim(csv::Trim::All) // Trim whitespace from all fields
         .from_reader(file_contents.as_bytes());
 
-    let mut students = Vec::new();
-    let mut errors = Vec::new();
-
-    // First, try to get headers to determine the format
     let headers = rdr
         .headers()
-        .map_err(|e| ServerFnError::new(format!("Failed to read CSV headers: {}", e)))?;
+        .map_err(|e| ServerFnError::new(format!("Failed to read CSV headers: {}", e)))?
+        .clone();
 
     log::info!("CSV Headers: {:?}", headers);
 
-    // Check if we have 'grade' or 'current_grade_level' in headers
-    let has_grade_field = headers.iter().any(|h| h == "grade");
-    let has_current_grade_level_field = headers.iter().any(|h| h == "current_grade_level");
-
-    for (row_num, result) in rdr.records().enumerate() {
-        match result {
-            Ok(record) => {
-                // Try to parse based on detected format
-                let student_result = if has_grade_field && !has_current_grade_level_field {
-                    // Use alternative format (matching CSV template with 'grade' header)
-                    match record.deserialize::<StudentCsvRowAlternative>(None) {
-                        Ok(alt_record) => {
-                            let converted_record = StudentCsvRow::from(alt_record);
-                            parse_student_record(converted_record, row_num + 2)
-                        }
-                        Err(e) => Err(format!(
-                            "Row {}: Failed to parse CSV row: {}",
-                            row_num + 2,
-                            e
-                        )),
-                    }
-                } else {
-                    // Use standard format
-                    match record.deserialize::<StudentCsvRow>(None) {
-                        Ok(std_record) => parse_student_record(std_record, row_num + 2),
-                        Err(e) => Err(format!(
-                            "Row {}: Failed to parse CSV row: {}",
-                            row_num + 2,
-                            e
-                        )),
-                    }
-                };
-
-                match student_result {
-                    Ok(student) => students.push(student),
-                    Err(e) => errors.push(e),
-                }
-            }
+    // The template heads the grade column 'grade'; exports use 'current_grade_level'
+    let grade_column = if headers.iter().any(|h| h == "current_grade_level") {
+        "current_grade_level"
+    } else {
+        "grade"
+    };
+    let mut required = STUDENT_COLUMNS.to_vec();
+    required.push(grade_column);
+    let missing = missing_columns(&headers, &required);
+    if !missing.is_empty() {
+        return Err(ServerFnError::new(format!(
+            "The CSV is missing these columns: {}",
+            missing.join(", ")
+        )));
+    }
+
+    let attribute_columns: Vec<(String, &AttributeDefinition)> = headers
+        .iter()
+        .filter_map(|header| {
+            definitions
+                .iter()
+                .find(|definition| definition.key.eq_ignore_ascii_case(header))
+                .map(|definition| (header.to_string(), definition))
+        })
+        .collect();
+
+    let mut parsed = ParsedRows::default();
+    let mut first_rows: HashMap<i32, usize> = HashMap::new();
+    for (index, result) in rdr.records().enumerate() {
+        let row = index + 2; // +2 for header and 1-based indexing
+        parsed.total_rows += 1;
+        let record = match result {
+            Ok(record) => record,
             Err(e) => {
-                errors.push(format!("Row {}: CSV parsing error: {}", row_num + 2, e));
+                parsed.flag(row, "", "", format!("CSV parsing error: {}", e));
+                continue;
+            }
+        };
+
+        let mut reader = RowReader::new(&headers, &record, row);
+        let student = parse_student_row(&mut reader, grade_column, &attribute_columns);
+        parsed.issues.extend(reader.finish());
+        if let Some(student) = student {
+            match first_rows.get(&student.student_id) {
+                Some(first_row) => parsed.flag(
+                    row,
+                    "student_id",
+                    student.student_id,
+                    format!("Duplicate student ID; also on row {}", first_row),
+                ),
+                None => {
+                    first_rows.insert(student.student_id, row);
+                }
             }
+            parsed.rows.push((row, student));
         }
     }
 
-    if !errors.is_empty() {
-        let error_msg = format!("Validation errors:\n{}", errors.join("\n"));
+    let attribute_keys = attribute_columns
+        .iter()
+        .map(|(_, definition)| definition.key.clone())
+        .collect();
+    Ok((parsed, attribute_keys))
+}
+
+// The all-or-nothing parse the sync preview works from
+fn parse_and_validate_students(
+    file_contents: &str,
+    definitions: &[AttributeDefinition],
+) -> Result<(Vec<AddStudentRequest>, Vec<String>), ServerFnError> {
+    let (parsed, attribute_keys) = parse_student_rows(file_contents, definitions)?;
+    let students = parsed.into_all().map_err(|error_msg| {
         log::error!("{}", error_msg);
-        return Err(ServerFnError::new(error_msg));
-    }
-
-    // Validate all students
-    for (index, student) in students.iter().enumerate() {
-        if let Err(validation_errors) = student.validate() {
-            let error_msg = format!(
-                "Validation failed for student {} {} (row {}): {:?}",
-                student.firstname,
-                student.lastname,
-                index + 2,
-                validation_errors
-            );
-            log::error!("{}", error_msg);
-            return Err(ServerFnError::new(error_msg));
-        }
-    }
+        ServerFnError::new(error_msg)
+    })?;
 
     log::info!(
         "Successfully parsed and validated {} students",
         students.len()
     );
-    Ok(students)
+    Ok((students, attribute_keys))
 }
 
-fn parse_student_record(
-    record: StudentCsvRow,
-    row_num: usize,
-) -> Result<AddStudentRequest, String> {
-    // Parse and validate gender
-    let gender = GenderEnum::from_str(&record.gender.trim()).map_err(|e| {
-        format!(
-            "Row {}: Invalid gender '{}': {}. Valid values: Male, Female, Non-binary",
-            row_num, record.gender, e
-        )
-    })?;
+fn required_text(value: &str, label: &str) -> Result<String, String> {
+    if value.is_empty() {
+        return Err(format!("{} cannot be empty", label));
+    }
+    Ok(value.to_string())
+}
 
-    // Parse and validate date of birth
-    let date_of_birth = NaiveDate::parse_from_str(&record.date_of_birth.trim(), "%Y-%m-%d")
-        .map_err(|e| {
-            format!(
-                "Row {}: Invalid date of birth '{}': {}. Expected format: YYYY-MM-DD",
-                row_num, record.date_of_birth, e
-            )
-        })?;
-
-    // Parse and validate current grade level
-    let current_grade_level = GradeEnum::from_str(&record.current_grade_level.trim())
-        .map_err(|e| {
-            format!(
-                "Row {}: Invalid grade '{}': {}. Valid values: Kindergarten, 1st Grade, 2nd Grade, etc.",
-                row_num, record.current_grade_level, e
-            )
-        })?;
-
-    // Parse and validate intervention (optional field)
-    let intervention = if record.intervention.trim() == "None"
-        || record.intervention.trim().is_empty()
-    {
-        None
-    } else {
-        Some(
-            InterventionEnum::from_str(&record.intervention.trim()).map_err(|e| {
-                format!(
-                    "Row {}: Invalid intervention '{}': {}. Valid values: Literacy, Math, Literacy and Math, None",
-                    row_num, record.intervention, e
-                )
-            })?,
-        )
+// Reads one student, noting a problem for each cell that doesn't parse.
+// Returns None if any did.
+fn parse_student_row(
+    reader: &mut RowReader,
+    grade_column: &str,
+    attribute_columns: &[(String, &AttributeDefinition)],
+) -> Option<AddStudentRequest> {
+    let firstname = reader.parse("firstname", |v| required_text(v, "First name"));
+    let lastname = reader.parse("lastname", |v| required_text(v, "Last name"));
+    let gender = reader.parse("gender", |v| {
+        GenderEnum::from_str(v)
+            .map_err(|_| "Invalid gender. Valid values: Male, Female, Non-binary".to_string())
+    });
+    let date_of_birth = reader.parse("date_of_birth", |v| {
+        NaiveDate::parse_from_str(v, "%Y-%m-%d")
+            .map_err(|_| "Invalid date of birth. Expected format: YYYY-MM-DD".to_string())
+    });
+    let student_id = reader.parse("student_id", |v| parse_id(v, "Student ID", 0, 2000000000));
+    let esl = reader.parse("esl", |v| {
+        ESLEnum::from_str(v).map_err(|_| {
+            "Invalid ESL value. Valid values: Not Applicable, Spanish, Arabic, etc.".to_string()
+        })
+    });
+    let current_grade_level = reader.parse(grade_column, |v| {
+        GradeEnum::from_str(v).map_err(|_| {
+            "Invalid grade. Valid values: Kindergarten, 1st Grade, 2nd Grade, etc.".to_string()
+        })
+    });
+    // Teachers are decoupled from students, which store the teacher's name
+    let teacher = reader.parse("teacher", |v| match v.len() {
+        0 => Err("Teacher name cannot be empty".to_string()),
+        1 => Err("Teacher name is too short".to_string()),
+        101.. => Err("Teacher name is too long (max 100 characters)".to_string()),
+        _ => Ok(v.to_string()),
+    });
+    let iep = reader.parse("iep", parse_bool);
+    let bip = reader.parse("bip", parse_bool);
+    let student_504 = reader.parse("student_504", parse_bool);
+    let readplan = reader.parse("readplan", parse_bool);
+    let gt = reader.parse("gt", parse_bool);
+    let eye_glasses = reader.parse("eye_glasses", parse_bool);
+    let intervention = reader.parse("intervention", |v| {
+        if v.is_empty() || v == "None" {
+            return Ok(None);
+        }
+        InterventionEnum::from_str(v).map(Some).map_err(|_| {
+            "Invalid intervention. Valid values: Literacy, Math, Literacy and Math, None"
+                .to_string()
+        })
+    });
+    let pin = reader.parse("pin", |v| parse_id(v, "PIN", 0, 99999999));
+
+    let mut custom_attributes = AttributeValues::new();
+    for (column, definition) in attribute_columns {
+        if let Some(Some(value)) = reader.parse(column, |v| definition.normalize_value(v)) {
+            custom_attributes.insert(definition.key.clone(), value);
+        }
+    }
+
+    let student = AddStudentRequest {
+        firstname: firstname?,
+        lastname: lastname?,
+        preferred: reader.text("preferred").to_string(),
+        gender: gender?,
+        date_of_birth: date_of_birth?,
+        student_id: student_id?,
+        esl: esl?,
+        current_grade_level: current_grade_level?,
+        teacher: teacher?,
+        iep: iep?,
+        bip: bip?,
+        student_504: student_504?,
+        readplan: readplan?,
+        gt: gt?,
+        intervention: intervention?,
+        eye_glasses: eye_glasses?,
+        notes: reader.text("notes").to_string(),
+        pin: pin?,
+        custom_attributes,
     };
+    if let Err(validation_errors) = student.validate() {
+        reader.flag("", validation_errors.to_string());
+        return None;
+    }
+    Some(student)
+}
+
+// A teacher name as written in a CSV, lowercased and without a title, so
+// "Mr. Smith" finds Smith
+#[cfg(feature = "ssr")]
+fn teacher_lookup_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    [
+        "mr. ", "mrs. ", "ms. ", "miss ", "dr. ", "mr ", "mrs ", "ms ", "dr ",
+    ]
+    .iter()
+    .find_map(|title| name.strip_prefix(title))
+    .map(str::to_string)
+    .unwrap_or(name)
+}
+
+// Flags rows the database rules out: student IDs already taken, and
+// teachers no one on staff goes by. Students name their teacher by last
+// name, as the add student form does; a full name matches too.
+#[cfg(feature = "ssr")]
+fn check_students_against_roster(
+    parsed: &mut ParsedRows<AddStudentRequest>,
+    existing_ids: &HashSet<i32>,
+    teachers: &[Employee],
+) {
+    let teacher_names: HashSet<String> = teachers
+        .iter()
+        .flat_map(|teacher| {
+            [
+                teacher.lastname.to_lowercase(),
+                format!("{} {}", teacher.firstname, teacher.lastname).to_lowercase(),
+            ]
+        })
+        .collect();
+
+    let mut issues = Vec::new();
+    for (row, student) in &parsed.rows {
+        if existing_ids.contains(&student.student_id) {
+            issues.push((
+                *row,
+                "student_id",
+                student.student_id.to_string(),
+                "A student with this ID already exists".to_string(),
+            ));
+        }
+        if !teacher_names.contains(&teacher_lookup_name(&student.teacher)) {
+            issues.push((
+                *row,
+                "teacher",
+                student.teacher.clone(),
+                "No teacher goes by this name".to_string(),
+            ));
+        }
+    }
+    for (row, column, value, message) in issues {
+        parsed.flag(row, column, value, message);
+    }
+}
+
+// Parses a student CSV and checks it against the students and teachers on
+// file
+#[cfg(feature = "ssr")]
+async fn validate_student_upload(
+    pool: &PgPool,
+    file_contents: &str,
+) -> Result<(ParsedRows<AddStudentRequest>, Vec<String>), ServerFnError> {
+    let definitions = student_attribute_database::list_attribute_definitions(pool).await?;
+    let (mut parsed, attribute_keys) = parse_student_rows(file_contents, &definitions)?;
+    let existing_ids = roster_sync_database::list_student_ids(pool).await?;
+    let teachers = teacher_database::get_all_teachers(pool).await?;
+    check_students_against_roster(&mut parsed, &existing_ids, &teachers);
+    Ok((parsed, attribute_keys))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "firstname,lastname,preferred,gender,date_of_birth,student_id,esl,grade,teacher,iep,bip,student_504,readplan,gt,intervention,eye_glasses,notes,pin";
+
+    #[test]
+    fn every_problem_in_a_row_is_reported() {
+        let csv = format!(
+            "{}\n\
+             Ada,Lovelace,,Female,2015-02-30,100,Not Applicable,Sixth,Smith,true,false,false,false,false,None,yes,,1234\n\
+             Alan,Turing,,Male,2015-06-23,101,Not Applicable,1st Grade,Smith,false,false,false,false,false,None,false,,1235\n\
+             Grace,Hopper,,Female,2015-12-09,101,Not Applicable,1st Grade,Smith,false,false,false,false,false,None,false,,1236\n",
+            HEADER
+        );
+        let (parsed, _) = parse_student_rows(&csv, &[]).unwrap();
+        assert_eq!(parsed.total_rows, 3);
 
-    // Parse and validate ESL
-    let esl = ESLEnum::from_str(&record.esl.trim())
-        .map_err(|e| format!("Row {}: Invalid ESL value '{}': {}. Valid values: Not Applicable, Spanish, Arabic, etc.", 
-                            row_num, record.esl, e))?;
-
-    // Validate student ID range
-    if record.student_id < 0 || record.student_id > 2000000000 {
-        return Err(format!(
-            "Row {}: Student ID {} is out of valid range (0-2000000000)",
-            row_num, record.student_id
-        ));
-    }
-
-    // Validate PIN range (assuming similar constraints)
-    if record.pin < 0 || record.pin > 99999999 {
-        return Err(format!(
-            "Row {}: PIN {} is out of valid range",
-            row_num, record.pin
-        ));
-    }
-
-    // Validate required string fields are not empty
-    if record.firstname.trim().is_empty() {
-        return Err(format!("Row {}: First name cannot be empty", row_num));
-    }
-    if record.lastname.trim().is_empty() {
-        return Err(format!("Row {}: Last name cannot be empty", row_num));
-    }
-
-    // IMPORTANT: Teacher field validation for decoupled model
-    // Since teachers are decoupled, we now store teacher names as strings
-    if record.teacher.trim().is_empty() {
-        return Err(format!("Row {}: Teacher name cannot be empty", row_num));
-    }
-
-    // Validate teacher name is reasonable (basic validation)
-    let teacher_name = record.teacher.trim();
-    if teacher_name.len() < 2 {
-        return Err(format!(
-            "Row {}: Teacher name '{}' is too short",
-            row_num, teacher_name
-        ));
-    }
-    if teacher_name.len() > 100 {
-        return Err(format!(
-            "Row {}: Teacher name '{}' is too long (max 100 characters)",
-            row_num, teacher_name
-        ));
-    }
-
-    Ok(AddStudentRequest {
-        firstname: record.firstname.trim().to_string(),
-        lastname: record.lastname.trim().to_string(),
-        preferred: record.preferred.trim().to_string(),
-        gender,
-        date_of_birth,
-        student_id: record.student_id,
-        esl,
-        current_grade_level,
-        teacher: teacher_name.to_string(), // Store as string, not ID
-        iep: record.iep,
-        bip: record.bip,
-        student_504: record.student_504,
-        readplan: record.readplan,
-        gt: record.gt,
-        intervention,
-        eye_glasses: record.eye_glasses,
-        notes: record.notes.trim().to_string(),
-        pin: record.pin,
-    })
+        let columns: Vec<(usize, &str)> = parsed
+            .issues
+            .iter()
+            .map(|issue| (issue.row, issue.column.as_str()))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                (2, "date_of_birth"),
+                (2, "grade"),
+                (2, "eye_glasses"),
+                (4, "student_id"),
+            ]
+        );
+
+        let students = parsed.valid();
+        assert_eq!(students.len(), 1);
+        assert_eq!(students[0].firstname, "Alan");
+    }
+
+    #[test]
+    fn teachers_match_by_name_without_title() {
+        assert_eq!(teacher_lookup_name("Mr. Smith"), "smith");
+        assert_eq!(teacher_lookup_name(" Jane Doe "), "jane doe");
+        assert_eq!(teacher_lookup_name("Mrsmith"), "mrsmith");
+    }
 }
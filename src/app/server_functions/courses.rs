@@ -1,4 +1,8 @@
 use crate::app::models::course::{Course, CreateCourseRequest, UpdateCourseRequest};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+};
 use leptos::*;
 
 #[cfg(feature = "ssr")]
@@ -10,6 +14,9 @@ pub async fn get_courses() -> Result<Vec<Course>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewCourses).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract database pool: {}", e)))?;
@@ -37,6 +44,9 @@ pub async fn get_course(course_id: i32) -> Result<Course, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewCourses).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract database pool: {}", e)))?;
@@ -64,6 +74,9 @@ pub async fn get_course_by_code(course_code: String) -> Result<Course, ServerFnE
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewCourses).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract database pool: {}", e)))?;
@@ -91,6 +104,9 @@ pub async fn add_course(add_course_request: CreateCourseRequest) -> Result<Cours
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageCourses).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract database pool: {}", e)))?;
@@ -125,6 +141,9 @@ pub async fn update_course(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageCourses).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract database pool: {}", e)))?;
@@ -150,6 +169,9 @@ pub async fn delete_course(course_id: i32) -> Result<(), ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageCourses).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract database pool: {}", e)))?;
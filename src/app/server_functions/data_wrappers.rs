@@ -1,4 +1,8 @@
 use crate::app::components::data_processing::student_results_summary::StudentResultsSummary;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+};
 use leptos::*;
 use std::collections::HashMap;
 
@@ -10,6 +14,8 @@ pub async fn get_student_results_server(
     {
         use crate::app::components::data_processing::student_results_summary::get_student_results;
 
+        require_permission(Permission::ViewScores).await?;
+
         get_student_results(student_id)
             .await
             .map_err(|e| ServerFnError::new(format!("There was an issue compiling and processing data for the designated student. Error: {}", e)))
@@ -30,6 +36,8 @@ pub async fn get_student_results_batch(
     {
         use crate::app::components::data_processing::student_results_summary::get_student_results;
 
+        require_permission(Permission::ViewScores).await?;
+
         let mut results_map: HashMap<i32, StudentResultsSummary> =
             HashMap::with_capacity(student_ids.len());
 
@@ -81,6 +89,8 @@ pub async fn get_student_results_stream(
     {
         use crate::app::server_functions::students::get_students;
 
+        require_permission(Permission::ViewScores).await?;
+
         // Get all students first
         let all_students = get_students().await?;
 
@@ -191,6 +201,8 @@ pub async fn get_student_results_batch_cached(
         use crate::app::components::data_processing::student_results_summary::get_student_results;
         use simple_cache::{cache_student_result, get_cached_student_result};
 
+        require_permission(Permission::ViewScores).await?;
+
         let mut results_map: HashMap<i32, StudentResultsSummary> =
             HashMap::with_capacity(student_ids.len());
         let mut uncached_ids = Vec::new();
@@ -4,6 +4,10 @@ use crate::app::models::employee::Employee;
 use crate::app::models::employee::{AddNewEmployeeRequest, UpdateEmployeeRequest};
 use crate::app::models::DeleteTeacherRequest;
 use crate::app::models::EmployeeRole;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+};
 use leptos::*;
 use log::{error, info};
 
@@ -19,6 +23,9 @@ pub async fn get_employees() -> Result<Vec<Employee>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -47,6 +54,9 @@ pub async fn add_employee(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -106,6 +116,9 @@ pub async fn delete_employee(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -130,6 +143,8 @@ pub async fn edit_employee(
         use actix_web::web;
         use leptos_actix::extract;
 
+        require_permission(Permission::ManageStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -1,6 +1,15 @@
 use crate::app::models::enrollment::{
     AcademicYear, CreateEnrollmentRequest, Enrollment, EnrollmentStatus, UpdateEnrollmentRequest,
 };
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::{DataScope, Permission},
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{data_scope_for, require_permission},
+    },
+};
 use leptos::*;
 use uuid::Uuid;
 
@@ -13,13 +22,17 @@ pub async fn get_enrollments() -> Result<Vec<Enrollment>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!("Attempting to retrieve all enrollments from the database"); // Fixed log message
 
-        match enrollment_database::get_all_enrollments(&pool).await {
+        match enrollment_database::get_all_enrollments(&scope, &pool).await {
             Ok(enrollments) => {
                 log::info!("Successfully retrieved enrollments: {:?}", enrollments);
                 Ok(enrollments)
@@ -41,9 +54,13 @@ pub async fn get_enrollment(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!(
             "Attempting to retrieve enrollment for student_id: {} and academic_year: {:?}",
@@ -55,6 +72,7 @@ pub async fn get_enrollment(
             &pool,
             student_id,
             academic_year,
+            &scope,
         )
         .await
         {
@@ -76,16 +94,20 @@ pub async fn get_enrollments_by_student(student_id: i32) -> Result<Vec<Enrollmen
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!(
             "Attempting to retrieve enrollments for student_id: {}",
             student_id
         );
 
-        match enrollment_database::get_enrollments_by_student(&student_id, &pool).await {
+        match enrollment_database::get_enrollments_by_student(&student_id, &scope, &pool).await {
             Ok(enrollments) => {
                 log::info!(
                     "Successfully retrieved enrollments for student: {:?}",
@@ -109,16 +131,22 @@ pub async fn get_enrollments_by_year(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!(
             "Attempting to retrieve enrollments for academic_year: {:?}",
             academic_year
         );
 
-        match enrollment_database::get_enrollments_by_academic_year(&academic_year, &pool).await {
+        match enrollment_database::get_enrollments_by_academic_year(&academic_year, &scope, &pool)
+            .await
+        {
             Ok(enrollments) => {
                 log::info!(
                     "Successfully retrieved enrollments for year: {:?}",
@@ -140,16 +168,20 @@ pub async fn get_enrollments_by_teacher(teacher_id: i32) -> Result<Vec<Enrollmen
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!(
             "Attempting to retrieve enrollments for teacher_id: {}",
             teacher_id
         );
 
-        match enrollment_database::get_enrollments_by_teacher(teacher_id, &pool).await {
+        match enrollment_database::get_enrollments_by_teacher(teacher_id, &scope, &pool).await {
             Ok(enrollments) => {
                 log::info!(
                     "Successfully retrieved enrollments for teacher: {:?}",
@@ -173,6 +205,9 @@ pub async fn create_enrollment(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -198,6 +233,15 @@ pub async fn create_enrollment(
         match enrollment_database::add_enrollment(&enrollment, &pool).await {
             Ok(enrollment) => {
                 log::info!("Successfully created enrollment: {:?}", enrollment);
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Create,
+                    AuditEntity::Enrollment,
+                    enrollment.audit_id(),
+                    AuditChange::created(&enrollment),
+                )
+                .await?;
                 Ok(enrollment)
             }
             Err(e) => Err(ServerFnError::new(format!(
@@ -216,6 +260,9 @@ pub async fn modify_enrollment(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -226,6 +273,14 @@ pub async fn modify_enrollment(
             update_enrollment_request.academic_year
         );
 
+        let before = enrollment_database::get_enrollment_by_student_and_year(
+            &pool,
+            update_enrollment_request.student_id,
+            update_enrollment_request.academic_year.clone(),
+            &DataScope::Unrestricted,
+        )
+        .await?;
+
         match enrollment_database::update_enrollment(
             &update_enrollment_request.student_id,
             &update_enrollment_request.academic_year,
@@ -241,6 +296,15 @@ pub async fn modify_enrollment(
         {
             Ok(Some(enrollment)) => {
                 log::info!("Successfully modified enrollment: {:?}", enrollment);
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Enrollment,
+                    enrollment.audit_id(),
+                    AuditChange::updated(&before, &enrollment),
+                )
+                .await?;
                 Ok(enrollment)
             }
             Ok(None) => Err(ServerFnError::new("Enrollment not found".to_string())),
@@ -264,6 +328,8 @@ pub async fn update_enrollment_status(
         use chrono::Utc;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -271,6 +337,14 @@ pub async fn update_enrollment_status(
         log::info!("Attempting to update enrollment status for student_id: {}, academic_year: {:?}, status: {:?}", 
             student_id, academic_year, status);
 
+        let before = enrollment_database::get_enrollment_by_student_and_year(
+            &pool,
+            student_id,
+            academic_year.clone(),
+            &DataScope::Unrestricted,
+        )
+        .await?;
+
         let status_change_date = Utc::now().naive_utc().date();
 
         match enrollment_database::update_enrollment_status(
@@ -284,6 +358,15 @@ pub async fn update_enrollment_status(
         {
             Ok(Some(enrollment)) => {
                 log::info!("Successfully updated enrollment status: {:?}", enrollment);
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Enrollment,
+                    enrollment.audit_id(),
+                    AuditChange::updated(&before, &enrollment),
+                )
+                .await?;
                 Ok(enrollment)
             }
             Ok(None) => Err(ServerFnError::new("Enrollment not found".to_string())),
@@ -304,6 +387,9 @@ pub async fn delete_enrollment(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageEnrollments).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -317,6 +403,15 @@ pub async fn delete_enrollment(
         match enrollment_database::delete_enrollment(student_id, academic_year, &pool).await {
             Ok(Some(enrollment)) => {
                 log::info!("Successfully deleted enrollment: {:?}", enrollment);
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Delete,
+                    AuditEntity::Enrollment,
+                    enrollment.audit_id(),
+                    AuditChange::deleted(&enrollment),
+                )
+                .await?;
                 Ok(enrollment)
             }
             Ok(None) => Err(ServerFnError::new("Enrollment not found".to_string())),
@@ -0,0 +1,135 @@
+use crate::app::models::import_mapping::{
+    ImportKind, MappingProfile, MappingSuggestion, SaveMappingProfileRequest,
+};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::import_mapping_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    models::user::SessionUser,
+    server_functions::{audit::record_audit_event, authorization::require_permission},
+    services::import_mapping,
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Mapping a kind's columns takes the permission importing them does
+#[cfg(feature = "ssr")]
+async fn require_import_permission(kind: ImportKind) -> Result<SessionUser, ServerFnError> {
+    require_permission(match kind {
+        ImportKind::Students => Permission::ManageStudents,
+        ImportKind::Enrollments => Permission::ManageEnrollments,
+    })
+    .await
+}
+
+// How the file's columns should map onto the import template: a saved
+// profile that fits its headers, or a best guess from header names
+#[server(SuggestImportMapping, "/api")]
+pub async fn suggest_import_mapping(
+    kind: ImportKind,
+    file_contents: String,
+) -> Result<MappingSuggestion, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_import_permission(kind).await?;
+        let pool = extract_pool().await?;
+
+        let profiles = import_mapping_database::list_mapping_profiles(&pool, kind).await?;
+        import_mapping::suggest_mapping(kind, &file_contents, &profiles).map_err(ServerFnError::new)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
+
+#[server(ListImportMappingProfiles, "/api")]
+pub async fn list_import_mapping_profiles(
+    kind: ImportKind,
+) -> Result<Vec<MappingProfile>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_import_permission(kind).await?;
+        let pool = extract_pool().await?;
+        import_mapping_database::list_mapping_profiles(&pool, kind).await
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
+
+#[server(SaveImportMappingProfile, "/api")]
+pub async fn save_import_mapping_profile(
+    request: SaveMappingProfileRequest,
+) -> Result<MappingProfile, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_import_permission(request.kind).await?;
+        request.validate().map_err(ServerFnError::new)?;
+        let pool = extract_pool().await?;
+
+        let profile =
+            import_mapping_database::save_mapping_profile(&pool, &request, &user.username).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::Roster,
+            format!("mapping_profile:{}", profile.id),
+            AuditChange::created(&profile),
+        )
+        .await?;
+        Ok(profile)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
+
+#[server(DeleteImportMappingProfile, "/api")]
+pub async fn delete_import_mapping_profile(id: i32) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract_pool().await?;
+        let Some(profile) = import_mapping_database::get_mapping_profile(&pool, id).await? else {
+            return Err(ServerFnError::new(format!("No mapping profile {}", id)));
+        };
+        let user = require_import_permission(profile.kind).await?;
+
+        if let Some(deleted) = import_mapping_database::delete_mapping_profile(&pool, id).await? {
+            record_audit_event(
+                &pool,
+                &user,
+                AuditAction::Delete,
+                AuditEntity::Roster,
+                format!("mapping_profile:{}", id),
+                AuditChange::deleted(&deleted),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
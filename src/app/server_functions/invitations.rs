@@ -6,6 +6,10 @@ use crate::app::models::user::SessionUser;
 use leptos::*;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+};
 #[cfg(feature = "ssr")]
 use {actix_web::web, leptos_actix::extract, sqlx::PgPool};
 
@@ -22,36 +26,13 @@ pub async fn create_invitation(
 ) -> Result<InvitationResponse, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
-        use crate::app::middleware::authentication;
+        let user = require_permission(Permission::ManageUsers).await?;
+        let user_id = Some(user.id);
 
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
-        let req = extract::<actix_web::HttpRequest>()
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
-
-        // Check if user is admin
-        let current_user = authentication::get_current_user_from_request(&req);
-        let user_id = match current_user {
-            Some(user) if user.is_admin() => Some(user.id),
-            Some(_) => {
-                return Ok(InvitationResponse {
-                    success: false,
-                    message: "Admin access required".to_string(),
-                    invitation: None,
-                });
-            }
-            None => {
-                return Ok(InvitationResponse {
-                    success: false,
-                    message: "Authentication required".to_string(),
-                    invitation: None,
-                });
-            }
-        };
-
         // Validate request
         if request.school_name.trim().is_empty() {
             return Ok(InvitationResponse {
@@ -417,35 +398,23 @@ pub async fn get_invitations(
 ) -> Result<Vec<Invitation>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
-        use crate::app::middleware::authentication;
+        require_permission(Permission::ManageUsers).await?;
 
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
-        let req = extract::<actix_web::HttpRequest>()
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
-
-        // Check if user is admin
-        match authentication::get_current_user_from_request(&req) {
-            Some(user) if user.is_admin() => {
-                let limit = limit.unwrap_or(50);
-                let offset = offset.unwrap_or(0);
-
-                match invitation_database::get_all_invitations_for_admin(&pool, limit, offset).await
-                {
-                    Ok(invitations) => Ok(invitations),
-                    Err(e) => {
-                        log::error!("Failed to get invitations: {:?}", e);
-                        Err(ServerFnError::new(
-                            "Failed to fetch invitations".to_string(),
-                        ))
-                    }
-                }
+        let limit = limit.unwrap_or(50);
+        let offset = offset.unwrap_or(0);
+
+        match invitation_database::get_all_invitations_for_admin(&pool, limit, offset).await {
+            Ok(invitations) => Ok(invitations),
+            Err(e) => {
+                log::error!("Failed to get invitations: {:?}", e);
+                Err(ServerFnError::new(
+                    "Failed to fetch invitations".to_string(),
+                ))
             }
-            Some(_) => Err(ServerFnError::new("Admin access required".to_string())),
-            None => Err(ServerFnError::new("Authentication required".to_string())),
         }
     }
 
@@ -459,45 +428,28 @@ pub async fn get_invitations(
 pub async fn delete_invitation(invitation_id: i64) -> Result<VerificationResponse, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
-        use crate::app::middleware::authentication;
+        require_permission(Permission::ManageUsers).await?;
 
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
-        let req = extract::<actix_web::HttpRequest>()
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
-
-        // Check if user is admin
-        match authentication::get_current_user_from_request(&req) {
-            Some(user) if user.is_admin() => {
-                match invitation_database::delete_invitation(&pool, invitation_id).await {
-                    Ok(true) => Ok(VerificationResponse {
-                        success: true,
-                        message: "Invitation deleted successfully".to_string(),
-                    }),
-                    Ok(false) => Ok(VerificationResponse {
-                        success: false,
-                        message: "Invitation not found".to_string(),
-                    }),
-                    Err(e) => {
-                        log::error!("Failed to delete invitation: {:?}", e);
-                        Ok(VerificationResponse {
-                            success: false,
-                            message: "Failed to delete invitation".to_string(),
-                        })
-                    }
-                }
-            }
-            Some(_) => Ok(VerificationResponse {
-                success: false,
-                message: "Admin access required".to_string(),
+        match invitation_database::delete_invitation(&pool, invitation_id).await {
+            Ok(true) => Ok(VerificationResponse {
+                success: true,
+                message: "Invitation deleted successfully".to_string(),
             }),
-            None => Ok(VerificationResponse {
+            Ok(false) => Ok(VerificationResponse {
                 success: false,
-                message: "Authentication required".to_string(),
+                message: "Invitation not found".to_string(),
             }),
+            Err(e) => {
+                log::error!("Failed to delete invitation: {:?}", e);
+                Ok(VerificationResponse {
+                    success: false,
+                    message: "Failed to delete invitation".to_string(),
+                })
+            }
         }
     }
 
@@ -0,0 +1,372 @@
+#[cfg(feature = "ssr")]
+use crate::app::db::{global_database, login_throttle_database, mfa_database, user_database};
+use crate::app::models::mfa::{MfaEnrollment, MfaStatus};
+use crate::app::models::user::SessionUser;
+#[cfg(feature = "ssr")]
+use crate::app::models::{
+    auth::SessionProvider, global::SettingsCache, mfa::MfaChallenge, user::User,
+};
+#[cfg(feature = "ssr")]
+use crate::app::services::mfa as totp;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission,
+    server_functions::{
+        auth::{client_ip, rotate_current_session, start_session, too_many_attempts_message},
+        authorization::{authorize, require_permission},
+    },
+};
+use leptos::*;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MfaVerifyResponse {
+    pub success: bool,
+    pub message: String,
+    pub user: Option<SessionUser>,
+    // Only filled when this login completed a policy-required enrollment
+    pub recovery_codes: Vec<String>,
+}
+
+// Called by login once the password is verified. Returns a challenge when the
+// account has MFA, or when policy requires it and enrollment must happen now.
+#[cfg(feature = "ssr")]
+pub async fn begin_mfa_challenge(
+    pool: &PgPool,
+    user: &User,
+    settings: &SettingsCache,
+) -> Result<Option<MfaChallenge>, ServerFnError> {
+    let enabled = mfa_database::get_user_mfa(pool, user.id)
+        .await?
+        .map(|mfa| mfa.enabled)
+        .unwrap_or(false);
+
+    let enrollment = if enabled {
+        None
+    } else if settings.requires_mfa(&user.role) {
+        let secret = totp::generate_secret();
+        mfa_database::start_mfa_enrollment(pool, user.id, &secret).await?;
+        Some(totp::enrollment(&secret, &user.username).map_err(ServerFnError::new)?)
+    } else {
+        return Ok(None);
+    };
+
+    let token = mfa_database::create_mfa_challenge(pool, user.id).await?;
+    Ok(Some(MfaChallenge { token, enrollment }))
+}
+
+// Accepts a current TOTP code or an unused recovery code for an enabled factor
+#[cfg(feature = "ssr")]
+async fn check_second_factor(
+    pool: &PgPool,
+    user_id: i64,
+    mfa: &mfa_database::UserMfa,
+    code: &str,
+) -> Result<bool, ServerFnError> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    if let Some(step) = totp::verify_code(&mfa.totp_secret, code, mfa.last_used_step, now) {
+        return mfa_database::record_mfa_step(pool, user_id, step).await;
+    }
+    mfa_database::consume_recovery_code(pool, user_id, &totp::hash_recovery_code(code)).await
+}
+
+// Confirms a pending secret with its first code and returns the plaintext
+// recovery codes, which are never shown again
+#[cfg(feature = "ssr")]
+async fn complete_enrollment(
+    pool: &PgPool,
+    user_id: i64,
+    mfa: &mfa_database::UserMfa,
+    code: &str,
+) -> Result<Option<Vec<String>>, ServerFnError> {
+    let now = chrono::Utc::now().timestamp() as u64;
+    let Some(step) = totp::verify_code(&mfa.totp_secret, code, None, now) else {
+        return Ok(None);
+    };
+
+    let recovery_codes = totp::generate_recovery_codes();
+    let hashes: Vec<String> = recovery_codes
+        .iter()
+        .map(|code| totp::hash_recovery_code(code))
+        .collect();
+    mfa_database::enable_mfa(pool, user_id, step, &hashes).await?;
+
+    Ok(Some(recovery_codes))
+}
+
+#[cfg(feature = "ssr")]
+async fn enabled_mfa_for(
+    pool: &PgPool,
+    user_id: i64,
+) -> Result<mfa_database::UserMfa, ServerFnError> {
+    mfa_database::get_user_mfa(pool, user_id)
+        .await?
+        .filter(|mfa| mfa.enabled)
+        .ok_or_else(|| ServerFnError::new("Two-factor authentication is not enabled"))
+}
+
+// Second login step: trades a challenge token and code for a session
+#[server(VerifyMfaLogin, "/api")]
+pub async fn verify_mfa_login(
+    token: String,
+    code: String,
+) -> Result<MfaVerifyResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::{web, HttpRequest};
+        use leptos_actix::extract;
+
+        authorize(Permission::Public).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let failure = |message: &str| MfaVerifyResponse {
+            success: false,
+            message: message.to_string(),
+            user: None,
+            recovery_codes: Vec::new(),
+        };
+
+        let Some(user_id) = mfa_database::take_mfa_challenge_attempt(&pool, &token).await? else {
+            return Ok(failure(
+                "This sign-in has expired. Enter your password again.",
+            ));
+        };
+
+        let user = user_database::get_user(user_id, &pool).await?;
+        let Some(mfa) = mfa_database::get_user_mfa(&pool, user_id).await? else {
+            return Ok(failure("Two-factor authentication is not set up"));
+        };
+
+        // Wrong codes count against the same username throttle as wrong
+        // passwords, so fresh challenges don't reset the attempt budget
+        let req = extract::<HttpRequest>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract request: {}", e)))?;
+        let ip_address = client_ip(&req);
+        let throttle = global_database::get_all_global_settings(&pool)
+            .await
+            .unwrap_or_default()
+            .login_throttle;
+        if let Some(wait) = login_throttle_database::login_retry_after(
+            &pool,
+            &throttle,
+            &user.username,
+            ip_address.as_deref(),
+        )
+        .await?
+        {
+            log::warn!("Throttled second factor for user: {}", user.username);
+            return Ok(failure(&too_many_attempts_message(wait)));
+        }
+
+        let recovery_codes = if mfa.enabled {
+            check_second_factor(&pool, user_id, &mfa, &code)
+                .await?
+                .then(Vec::new)
+        } else {
+            complete_enrollment(&pool, user_id, &mfa, &code).await?
+        };
+        let Some(recovery_codes) = recovery_codes else {
+            log::warn!("Invalid second factor for user: {}", user.username);
+            login_throttle_database::record_login_failure(
+                &pool,
+                &throttle,
+                &user.username,
+                ip_address.as_deref(),
+            )
+            .await?;
+            return Ok(failure("Invalid authentication code"));
+        };
+        if !mfa.enabled {
+            log::info!("User {} enrolled in MFA during login", user.username);
+        }
+
+        mfa_database::delete_mfa_challenge(&pool, &token).await?;
+        login_throttle_database::clear_login_failures(&pool, &user.username).await?;
+
+        start_session(&pool, user.id, SessionProvider::Password).await?;
+        log::info!("Session created for user: {}", user.username);
+
+        Ok(MfaVerifyResponse {
+            success: true,
+            message: "Login successful".to_string(),
+            user: Some(user.to_session_user()),
+            recovery_codes,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(GetMfaStatus, "/api")]
+pub async fn get_mfa_status() -> Result<MfaStatus, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let settings = global_database::get_all_global_settings(&pool).await?;
+        let mfa = mfa_database::get_user_mfa(&pool, user.id)
+            .await?
+            .filter(|mfa| mfa.enabled);
+        let recovery_codes_remaining = match mfa {
+            Some(_) => mfa_database::count_recovery_codes(&pool, user.id).await?,
+            None => 0,
+        };
+
+        Ok(MfaStatus {
+            enabled: mfa.is_some(),
+            enrolled_at: mfa.and_then(|mfa| mfa.enrolled_at),
+            recovery_codes_remaining,
+            required: settings.requires_mfa(&user.role),
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Starts enrollment from My Account; the factor is off until confirmed
+#[server(BeginMfaEnrollment, "/api")]
+pub async fn begin_mfa_enrollment() -> Result<MfaEnrollment, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let secret = totp::generate_secret();
+        mfa_database::start_mfa_enrollment(&pool, user.id, &secret).await?;
+
+        totp::enrollment(&secret, &user.username).map_err(ServerFnError::new)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(ConfirmMfaEnrollment, "/api")]
+pub async fn confirm_mfa_enrollment(code: String) -> Result<Vec<String>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let mfa = mfa_database::get_user_mfa(&pool, user.id)
+            .await?
+            .filter(|mfa| !mfa.enabled)
+            .ok_or_else(|| ServerFnError::new("No pending two-factor enrollment"))?;
+
+        let codes = complete_enrollment(&pool, user.id, &mfa, &code)
+            .await?
+            .ok_or_else(|| ServerFnError::new("Invalid authentication code"))?;
+
+        log::info!("User {} enabled MFA", user.username);
+        rotate_current_session(&pool).await?;
+        Ok(codes)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(RegenerateRecoveryCodes, "/api")]
+pub async fn regenerate_recovery_codes(code: String) -> Result<Vec<String>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let mfa = enabled_mfa_for(&pool, user.id).await?;
+        if !check_second_factor(&pool, user.id, &mfa, &code).await? {
+            return Err(ServerFnError::new("Invalid authentication code"));
+        }
+
+        let recovery_codes = totp::generate_recovery_codes();
+        let hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| totp::hash_recovery_code(code))
+            .collect();
+        mfa_database::replace_recovery_codes(&pool, user.id, &hashes).await?;
+
+        log::info!("User {} regenerated MFA recovery codes", user.username);
+        Ok(recovery_codes)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(DisableMfa, "/api")]
+pub async fn disable_mfa(code: String) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let settings = global_database::get_all_global_settings(&pool).await?;
+        if settings.requires_mfa(&user.role) {
+            return Err(ServerFnError::new(
+                "Two-factor authentication is required for your role",
+            ));
+        }
+
+        let mfa = enabled_mfa_for(&pool, user.id).await?;
+        if !check_second_factor(&pool, user.id, &mfa, &code).await? {
+            return Err(ServerFnError::new("Invalid authentication code"));
+        }
+
+        log::info!("User {} disabled MFA", user.username);
+        mfa_database::reset_mfa(&pool, user.id).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
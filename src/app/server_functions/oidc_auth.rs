@@ -0,0 +1,357 @@
+use crate::app::models::auth::{OidcConfig, SamlMappingSettings};
+use crate::app::server_functions::saml_auth::SamlAuthResponse;
+use leptos::*;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::oidc_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::{auth::SsoAttributeMapping, permission::Permission},
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{authorize, require_permission},
+    },
+};
+#[cfg(feature = "ssr")]
+use {actix_web::web, leptos_actix::extract, sqlx::PgPool, uuid::Uuid};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OidcInstitution {
+    pub id: String,
+    pub name: String,
+}
+
+impl OidcInstitution {
+    pub fn login_url(&self) -> String {
+        format!("/oidc/login?institution={}", self.id)
+    }
+}
+
+#[cfg(feature = "ssr")]
+fn parse_config_id(config_id: &str) -> Result<Uuid, ServerFnError> {
+    Uuid::parse_str(config_id).map_err(|_| ServerFnError::new("Invalid config ID format"))
+}
+
+#[cfg(feature = "ssr")]
+fn validate_issuer(issuer: &str) -> Result<(), ServerFnError> {
+    let url = url::Url::parse(issuer)
+        .map_err(|e| ServerFnError::new(format!("Invalid issuer URL: {}", e)))?;
+    let local = matches!(url.host_str(), Some("localhost") | Some("127.0.0.1"));
+    if url.scheme() != "https" && !local {
+        return Err(ServerFnError::new("Issuer must use https"));
+    }
+    Ok(())
+}
+
+// Active OIDC institutions for the login page
+#[server(GetOidcInstitutions, "/api")]
+pub async fn get_oidc_institutions() -> Result<Vec<OidcInstitution>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        authorize(Permission::Public).await?;
+
+        let configs = oidc_database::get_oidc_configs(&pool, true).await?;
+        Ok(configs
+            .into_iter()
+            .map(|config| OidcInstitution {
+                id: config.id.to_string(),
+                name: config.institution_name,
+            })
+            .collect())
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// All OIDC configs for the admin panel. Client secrets are never serialized.
+#[server(GetOidcConfigs, "/api")]
+pub async fn get_oidc_configs() -> Result<Vec<OidcConfig>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        require_permission(Permission::ManageSso).await?;
+
+        oidc_database::get_oidc_configs(&pool, false).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(CreateOidcConfig, "/api")]
+pub async fn create_oidc_config(
+    institution_name: String,
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    scopes: String,
+    trust_unverified_email: bool,
+) -> Result<SamlAuthResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use chrono::Utc;
+        use std::collections::HashMap;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = require_permission(Permission::ManageSso).await?;
+
+        let issuer = issuer.trim().trim_end_matches('/').to_string();
+        validate_issuer(&issuer)?;
+        if institution_name.trim().is_empty() || client_id.trim().is_empty() {
+            return Err(ServerFnError::new(
+                "Institution name and client ID are required",
+            ));
+        }
+
+        let scopes = if scopes.trim().is_empty() {
+            "openid email profile".to_string()
+        } else {
+            scopes.trim().to_string()
+        };
+
+        let config = OidcConfig {
+            id: Uuid::new_v4(),
+            institution_name: institution_name.trim().to_string(),
+            issuer,
+            client_id: client_id.trim().to_string(),
+            client_secret,
+            scopes,
+            active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            attribute_mapping: HashMap::new(),
+            role_mapping: HashMap::new(),
+            auto_provision: true,
+            trust_unverified_email,
+        };
+
+        oidc_database::create_oidc_config(&pool, &config).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Create,
+            AuditEntity::OidcConfig,
+            config.id,
+            AuditChange::created(&config),
+        )
+        .await?;
+
+        Ok(SamlAuthResponse {
+            success: true,
+            message: format!("OIDC configuration created for {}", config.institution_name),
+            redirect_url: None,
+            user: None,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Leave client_secret empty to keep the stored secret
+#[server(UpdateOidcConfig, "/api")]
+pub async fn update_oidc_config(
+    config_id: String,
+    institution_name: String,
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    scopes: String,
+    active: bool,
+    trust_unverified_email: bool,
+) -> Result<SamlAuthResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = require_permission(Permission::ManageSso).await?;
+
+        let issuer = issuer.trim().trim_end_matches('/').to_string();
+        validate_issuer(&issuer)?;
+
+        let mut config = oidc_database::get_oidc_config_by_id(&pool, parse_config_id(&config_id)?)
+            .await?
+            .ok_or_else(|| ServerFnError::new("OIDC configuration not found"))?;
+        let before = config.clone();
+
+        config.institution_name = institution_name.trim().to_string();
+        config.issuer = issuer;
+        config.client_id = client_id.trim().to_string();
+        config.client_secret = client_secret;
+        config.scopes = scopes.trim().to_string();
+        config.active = active;
+        config.trust_unverified_email = trust_unverified_email;
+
+        oidc_database::update_oidc_config(&pool, &config).await?;
+        // The client secret is never serialized, so it stays out of the log
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::OidcConfig,
+            config.id,
+            AuditChange::updated(&before, &config),
+        )
+        .await?;
+
+        Ok(SamlAuthResponse {
+            success: true,
+            message: format!("OIDC configuration updated for {}", config.institution_name),
+            redirect_url: None,
+            user: None,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(DeleteOidcConfig, "/api")]
+pub async fn delete_oidc_config(config_id: String) -> Result<SamlAuthResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = require_permission(Permission::ManageSso).await?;
+
+        let config_uuid = parse_config_id(&config_id)?;
+        let before = oidc_database::get_oidc_config_by_id(&pool, config_uuid).await?;
+        let institution_name = oidc_database::delete_oidc_config(&pool, config_uuid).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Delete,
+            AuditEntity::OidcConfig,
+            config_uuid,
+            AuditChange::deleted(&before),
+        )
+        .await?;
+
+        Ok(SamlAuthResponse {
+            success: true,
+            message: format!("OIDC configuration deleted for {}", institution_name),
+            redirect_url: None,
+            user: None,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(ToggleOidcConfig, "/api")]
+pub async fn toggle_oidc_config(config_id: String) -> Result<SamlAuthResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = require_permission(Permission::ManageSso).await?;
+
+        let config_uuid = parse_config_id(&config_id)?;
+        let (institution_name, active) =
+            oidc_database::toggle_oidc_config(&pool, config_uuid).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::OidcConfig,
+            config_uuid,
+            AuditChange::updated(
+                &serde_json::json!({ "active": !active }),
+                &serde_json::json!({ "active": active }),
+            ),
+        )
+        .await?;
+
+        Ok(SamlAuthResponse {
+            success: true,
+            message: format!(
+                "OIDC configuration for {} is now {}",
+                institution_name,
+                if active { "active" } else { "inactive" }
+            ),
+            redirect_url: None,
+            user: None,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Claim and role mapping, using the same settings shape as SAML
+#[server(UpdateOidcMapping, "/api")]
+pub async fn update_oidc_mapping(
+    config_id: String,
+    settings: SamlMappingSettings,
+) -> Result<SamlAuthResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = require_permission(Permission::ManageSso).await?;
+
+        settings.validate().map_err(ServerFnError::new)?;
+
+        let mut config = oidc_database::get_oidc_config_by_id(&pool, parse_config_id(&config_id)?)
+            .await?
+            .ok_or_else(|| ServerFnError::new("OIDC configuration not found"))?;
+        let before = config.clone();
+        config.apply_mapping_settings(&settings);
+
+        oidc_database::update_oidc_mapping(&pool, &config).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::OidcConfig,
+            config.id,
+            AuditChange::updated(&before, &config),
+        )
+        .await?;
+
+        Ok(SamlAuthResponse {
+            success: true,
+            message: format!("Claim mapping saved for {}", config.institution_name),
+            redirect_url: None,
+            user: None,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
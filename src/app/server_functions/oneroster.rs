@@ -0,0 +1,100 @@
+use crate::app::models::oneroster::{RosterExportBundle, RosterImportSummary};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::oneroster_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{audit::record_audit_event, authorization::require_permission},
+    services::oneroster,
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Imports a OneRoster 1.2 CSV bundle from a student information system.
+// Students, teachers, classes and enrollments are created or updated by
+// sourcedId; nothing is deleted.
+#[server(ImportOneRosterBundle, "/api")]
+pub async fn import_oneroster_bundle(
+    archive_base64: String,
+) -> Result<RosterImportSummary, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let user = require_permission(Permission::ManageStudents).await?;
+        require_permission(Permission::ManageCourses).await?;
+        require_permission(Permission::ManageEnrollments).await?;
+        let pool = extract_pool().await?;
+
+        let archive = STANDARD
+            .decode(archive_base64.trim())
+            .map_err(|e| ServerFnError::new(format!("The upload is not a zip file: {}", e)))?;
+        let bundle = oneroster::read_bundle(&archive).map_err(ServerFnError::new)?;
+
+        let today = chrono::Local::now().date_naive();
+        let summary =
+            oneroster_database::import_roster_bundle(&pool, &bundle, today, &user).await?;
+
+        log::info!(
+            "User {} imported a OneRoster bundle: {} students and {} classes created",
+            user.username,
+            summary.students_created,
+            summary.classes_created
+        );
+        Ok(summary)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Exports every student, staff member, course and enrollment as a OneRoster
+// 1.2 CSV bundle
+#[server(ExportOneRosterBundle, "/api")]
+pub async fn export_oneroster_bundle() -> Result<RosterExportBundle, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+
+        let bundle = oneroster_database::load_roster_bundle(&pool).await?;
+        let export = oneroster::build_export_bundle(&bundle, chrono::Utc::now())
+            .map_err(ServerFnError::new)?;
+
+        log::info!("User {} exported a OneRoster bundle", user.username);
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Export,
+            AuditEntity::Roster,
+            "oneroster",
+            AuditChange {
+                before: None,
+                after: Some(serde_json::json!({
+                    "filename": export.filename,
+                    "users": bundle.users.len(),
+                    "classes": bundle.classes.len(),
+                    "enrollments": bundle.enrollments.len(),
+                })),
+            },
+        )
+        .await?;
+
+        Ok(export)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
@@ -4,6 +4,15 @@ use crate::app::models::{
     question::{Question, QuestionType},
     CreateNewQuestionRequest, DeleteQuestionRequest, UpdateQuestionRequest,
 };
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{authorize, require_permission},
+    },
+};
 use leptos::*;
 #[cfg(feature = "ssr")]
 use {
@@ -17,6 +26,9 @@ pub async fn get_questions(test_id: String) -> Result<Vec<Question>, ServerFnErr
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -41,6 +53,9 @@ pub async fn delete_questions(test_id: String) -> Result<Vec<Question>, ServerFn
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -49,6 +64,17 @@ pub async fn delete_questions(test_id: String) -> Result<Vec<Question>, ServerFn
         match question_database::delete_all_questions(test_id, &pool).await {
             Ok(questions) => {
                 log::info!("Successfully deleted all questions related to test from database");
+                for question in &questions {
+                    record_audit_event(
+                        &pool,
+                        &user,
+                        AuditAction::Delete,
+                        AuditEntity::Question,
+                        question.audit_id(),
+                        AuditChange::deleted(question),
+                    )
+                    .await?;
+                }
                 Ok(questions)
             }
             Err(e) => {
@@ -68,6 +94,9 @@ pub async fn add_question(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -107,6 +136,15 @@ pub async fn add_question(
                     "Created question weighted_options: {:?}",
                     created_question.weighted_options
                 );
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Create,
+                    AuditEntity::Question,
+                    created_question.audit_id(),
+                    AuditChange::created(&created_question),
+                )
+                .await?;
                 Ok(created_question)
             }
             Err(e) => {
@@ -128,6 +166,9 @@ pub async fn delete_question(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -141,7 +182,18 @@ pub async fn delete_question(
         )
         .await
         {
-            Ok(deleted) => Ok(deleted),
+            Ok(deleted) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Delete,
+                    AuditEntity::Question,
+                    deleted.audit_id(),
+                    AuditChange::deleted(&deleted),
+                )
+                .await?;
+                Ok(deleted)
+            }
             Err(_) => Err(ServerFnError::new(
                 "Failed to delete question from the database",
             )),
@@ -158,12 +210,22 @@ pub async fn edit_question(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Attempting to update question from the database");
 
+        let before = question_database::get_single_question(
+            edit_question_request.qnumber,
+            edit_question_request.testlinker.clone(),
+            &pool,
+        )
+        .await?;
+
         let buffer_question = Question::new(
             edit_question_request.word_problem,
             edit_question_request.point_value,
@@ -175,7 +237,18 @@ pub async fn edit_question(
         );
 
         match question_database::update_question(&buffer_question, &pool).await {
-            Ok(Some(updated_student)) => Ok(updated_student),
+            Ok(Some(updated_question)) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Question,
+                    updated_question.audit_id(),
+                    AuditChange::updated(&before, &updated_question),
+                )
+                .await?;
+                Ok(updated_question)
+            }
             Ok(None) => Err(ServerFnError::new(format!(
                 "Failed to correctly existing student in the database"
             ))),
@@ -196,6 +269,9 @@ pub async fn duplicate_and_randomize_questions(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -243,6 +319,15 @@ pub async fn duplicate_and_randomize_questions(
                 for question in randomized_questions {
                     match question_database::add_question(&question, &pool).await {
                         Ok(created_question) => {
+                            record_audit_event(
+                                &pool,
+                                &user,
+                                AuditAction::Create,
+                                AuditEntity::Question,
+                                created_question.audit_id(),
+                                AuditChange::created(&created_question),
+                            )
+                            .await?;
                             created_questions.push(created_question);
                         }
                         Err(e) => {
@@ -283,6 +368,8 @@ pub async fn generate_randomized_test(
         use crate::app::models::test::{CreateNewTestRequest, Test};
         use crate::app::server_functions::tests::{add_test, get_test};
 
+        require_permission(Permission::ManageTests).await?;
+
         log::info!("Attempting to generate randomized test");
 
         // Get the base test
@@ -335,6 +422,9 @@ pub async fn shuffle_question_options(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -361,7 +451,18 @@ pub async fn shuffle_question_options(
                     )
                     .await
                     {
-                        Ok(updated_question) => Ok(updated_question),
+                        Ok(updated_question) => {
+                            record_audit_event(
+                                &pool,
+                                &user,
+                                AuditAction::Update,
+                                AuditEntity::Question,
+                                updated_question.audit_id(),
+                                AuditChange::updated(&question, &updated_question),
+                            )
+                            .await?;
+                            Ok(updated_question)
+                        }
                         Err(e) => Err(ServerFnError::new(format!(
                             "Failed to update question options: {}",
                             e
@@ -387,6 +488,9 @@ pub async fn validate_test_for_randomization(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
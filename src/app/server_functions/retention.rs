@@ -0,0 +1,214 @@
+use crate::app::models::retention::{
+    LegalHold, PurgeReport, RetentionPolicy, RetentionPreview, SaveRetentionPolicyRequest,
+};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::retention_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{audit::record_audit_event, authorization::require_permission},
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+#[server(GetRetentionPolicies, "/api")]
+pub async fn get_retention_policies() -> Result<Vec<RetentionPolicy>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+
+        retention_database::list_retention_policies(&pool).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(SaveRetentionPolicy, "/api")]
+pub async fn save_retention_policy(
+    request: SaveRetentionPolicyRequest,
+) -> Result<RetentionPolicy, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+        request.validate().map_err(ServerFnError::new)?;
+
+        let updated_by: i32 = user
+            .id
+            .try_into()
+            .map_err(|e| ServerFnError::new(format!("User ID conversion error: {}", e)))?;
+        let before = match request.id {
+            Some(id) => retention_database::list_retention_policies(&pool)
+                .await?
+                .into_iter()
+                .find(|policy| policy.id == id),
+            None => None,
+        };
+        let policy = retention_database::save_retention_policy(&pool, &request, updated_by).await?;
+
+        record_audit_event(
+            &pool,
+            &user,
+            if before.is_some() {
+                AuditAction::Update
+            } else {
+                AuditAction::Create
+            },
+            AuditEntity::RetentionPolicy,
+            policy.id,
+            match &before {
+                Some(before) => AuditChange::updated(before, &policy),
+                None => AuditChange::created(&policy),
+            },
+        )
+        .await?;
+
+        Ok(policy)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(DeleteRetentionPolicy, "/api")]
+pub async fn delete_retention_policy(id: i32) -> Result<RetentionPolicy, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+
+        let deleted = retention_database::delete_retention_policy(&pool, id).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Delete,
+            AuditEntity::RetentionPolicy,
+            deleted.id,
+            AuditChange::deleted(&deleted),
+        )
+        .await?;
+
+        Ok(deleted)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Students a purge would remove right now, without changing anything
+#[server(PreviewRetention, "/api")]
+pub async fn preview_retention() -> Result<RetentionPreview, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+
+        retention_database::preview_retention(&pool).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(RunRetentionPurge, "/api")]
+pub async fn run_retention_purge() -> Result<PurgeReport, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+
+        let report =
+            retention_database::purge_expired_students(&pool, Some(user.id), &user.username)
+                .await?;
+        log::info!(
+            "User {} ran the retention purge: {}",
+            user.username,
+            report.summary()
+        );
+
+        Ok(report)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(GetStudentLegalHold, "/api")]
+pub async fn get_student_legal_hold(student_id: i32) -> Result<LegalHold, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+
+        retention_database::get_legal_hold(&pool, student_id).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(SetStudentLegalHold, "/api")]
+pub async fn set_student_legal_hold(
+    student_id: i32,
+    held: bool,
+    reason: Option<String>,
+) -> Result<LegalHold, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+
+        let reason = reason
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+        if held && reason.is_none() {
+            return Err(ServerFnError::new("Give a reason for the legal hold"));
+        }
+
+        let before = retention_database::get_legal_hold(&pool, student_id).await?;
+        let hold =
+            retention_database::set_legal_hold(&pool, student_id, held, reason, &user.username)
+                .await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::Student,
+            student_id,
+            AuditChange::updated(
+                &serde_json::json!({ "legal_hold": before.held, "legal_hold_reason": before.reason }),
+                &serde_json::json!({ "legal_hold": hold.held, "legal_hold_reason": hold.reason }),
+            ),
+        )
+        .await?;
+
+        Ok(hold)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
@@ -1,9 +1,21 @@
 use crate::app::db::saml_database;
-use crate::app::models::auth::{AuthProvider, SamlConfig, SamlResponse};
-use crate::app::models::user::{SessionUser, UserRole};
+use crate::app::models::auth::{
+    AuthProvider, SamlConfig, SamlMappingPreview, SamlMappingSettings, SamlResponse,
+    SsoAttributeMapping,
+};
+use crate::app::models::user::SessionUser;
 use leptos::*;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{authorize, require_permission},
+    },
+};
 #[cfg(feature = "ssr")]
 use {
     actix_web::{cookie::Cookie, http::header, HttpRequest, HttpResponse},
@@ -50,9 +62,9 @@ pub async fn get_saml_institutions() -> Result<Vec<SamlInstitution>, ServerFnErr
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Check if user is admin for full list, otherwise return only active configs
-        let current_user = crate::app::server_functions::auth::get_current_user().await?;
+        let current_user = authorize(Permission::Public).await?;
         let is_admin = current_user
-            .map(|u| matches!(u.role, UserRole::Admin | UserRole::SuperAdmin))
+            .map(|u| Permission::ManageSso.is_granted_to(&u))
             .unwrap_or(false);
 
         let configs = if is_admin {
@@ -96,6 +108,8 @@ pub async fn initiate_saml_login(
         use actix_web::web;
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -113,8 +127,10 @@ pub async fn initiate_saml_login(
             .map_err(|e| ServerFnError::new(format!("Failed to create SAML manager: {}", e)))?;
 
         // Generate auth request URL
+        let request_id = saml_database::SamlManager::new_request_id();
+        saml_database::record_saml_request(&pool, &request_id, &config.id.to_string()).await?;
         let auth_url = saml_manager
-            .generate_auth_request(&institution_id, relay_state.as_deref())
+            .generate_auth_request(&request_id, relay_state.as_deref())
             .map_err(|e| ServerFnError::new(format!("Failed to generate auth request: {}", e)))?;
 
         // Store the institution ID in session for later use
@@ -160,6 +176,8 @@ pub async fn handle_saml_response(
         use base64::{engine::general_purpose, Engine as _};
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -197,37 +215,32 @@ pub async fn handle_saml_response(
         let saml_manager = saml_database::SamlManager::new(&base_url)
             .map_err(|e| ServerFnError::new(format!("Failed to create SAML manager: {}", e)))?;
 
+        saml_database::verify_saml_response(&pool, &saml_manager, &config, &saml_xml).await?;
+
         let parsed_response = saml_manager
-            .parse_saml_response(&saml_xml, &institution_id)
+            .parse_saml_response(&saml_xml, &config)
             .map_err(|e| ServerFnError::new(format!("Failed to parse SAML response: {}", e)))?;
 
         // Provision or get existing user
-        let user =
-            saml_database::provision_saml_user(&pool, &parsed_response, &institution_id).await?;
-
-        // Create session
-        let session_token = crate::app::db::user_database::create_session(&pool, user.id)
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to create session: {}", e)))?;
-
-        // Set session cookie
-        let response = expect_context::<ResponseOptions>();
-        let session_cookie = format!(
-            "session={}; Path=/; HttpOnly; SameSite=Strict; Max-Age=604800{}",
-            session_token,
-            if cfg!(debug_assertions) {
-                ""
-            } else {
-                "; Secure"
-            }
-        );
+        let user = saml_database::provision_saml_user(
+            &pool,
+            &parsed_response,
+            &institution_id,
+            config.auto_provision,
+        )
+        .await?;
 
-        response.insert_header(
-            header::SET_COOKIE,
-            header::HeaderValue::from_str(&session_cookie).expect("Failed to create header value"),
-        );
+        // Create session and set its cookie
+        crate::app::server_functions::auth::start_session(
+            &pool,
+            user.id,
+            crate::app::models::auth::SessionProvider::Saml,
+        )
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to create session: {}", e)))?;
 
         // Clear institution cookie
+        let response = expect_context::<ResponseOptions>();
         let clear_institution_cookie = format!(
             "saml_institution=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0{}",
             if cfg!(debug_assertions) {
@@ -252,6 +265,7 @@ pub async fn handle_saml_response(
             display_name: user.display_name,
             first_name: user.first_name,
             last_name: user.last_name,
+            token_scopes: None,
         };
 
         Ok(SamlAuthResponse {
@@ -276,6 +290,8 @@ pub async fn initiate_saml_logout() -> Result<SamlAuthResponse, ServerFnError> {
         use actix_web::web;
         use leptos_actix::extract;
 
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -422,13 +438,7 @@ pub async fn create_saml_config(
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        let user = require_permission(Permission::ManageSso).await?;
 
         let config = SamlConfig {
             id: Uuid::new_v4(),
@@ -448,6 +458,15 @@ pub async fn create_saml_config(
         };
 
         saml_database::create_saml_config(&pool, &config).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Create,
+            AuditEntity::SamlConfig,
+            config.id,
+            AuditChange::created(&config),
+        )
+        .await?;
 
         Ok(SamlAuthResponse {
             success: true,
@@ -486,17 +505,15 @@ pub async fn update_saml_config(
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        let user = require_permission(Permission::ManageSso).await?;
 
         let config_uuid = Uuid::parse_str(&config_id)
             .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
 
+        let before = saml_database::get_saml_config_by_id(&pool, config_uuid)
+            .await?
+            .ok_or_else(|| ServerFnError::new("SAML configuration not found"))?;
+
         saml_database::update_saml_config(
             &pool,
             config_uuid,
@@ -510,6 +527,18 @@ pub async fn update_saml_config(
         )
         .await?;
 
+        if let Some(after) = saml_database::get_saml_config_by_id(&pool, config_uuid).await? {
+            record_audit_event(
+                &pool,
+                &user,
+                AuditAction::Update,
+                AuditEntity::SamlConfig,
+                config_uuid,
+                AuditChange::updated(&before, &after),
+            )
+            .await?;
+        }
+
         Ok(SamlAuthResponse {
             success: true,
             message: format!("SAML configuration updated for {}", institution_name),
@@ -538,18 +567,22 @@ pub async fn delete_saml_config(config_id: String) -> Result<SamlAuthResponse, S
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        let user = require_permission(Permission::ManageSso).await?;
 
         let config_uuid = Uuid::parse_str(&config_id)
             .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
 
+        let before = saml_database::get_saml_config_by_id(&pool, config_uuid).await?;
         let institution_name = saml_database::delete_saml_config(&pool, config_uuid).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Delete,
+            AuditEntity::SamlConfig,
+            config_uuid,
+            AuditChange::deleted(&before),
+        )
+        .await?;
 
         Ok(SamlAuthResponse {
             success: true,
@@ -579,19 +612,25 @@ pub async fn toggle_saml_config(config_id: String) -> Result<SamlAuthResponse, S
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        let user = require_permission(Permission::ManageSso).await?;
 
         let config_uuid = Uuid::parse_str(&config_id)
             .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
 
         let (institution_name, new_status) =
             saml_database::toggle_saml_config_status(&pool, config_uuid).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::SamlConfig,
+            config_uuid,
+            AuditChange::updated(
+                &serde_json::json!({ "active": !new_status }),
+                &serde_json::json!({ "active": new_status }),
+            ),
+        )
+        .await?;
 
         Ok(SamlAuthResponse {
             success: true,
@@ -625,13 +664,7 @@ pub async fn get_saml_config_details(config_id: String) -> Result<SamlConfig, Se
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        require_permission(Permission::ManageSso).await?;
 
         let config_uuid = Uuid::parse_str(&config_id)
             .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
@@ -649,6 +682,119 @@ pub async fn get_saml_config_details(config_id: String) -> Result<SamlConfig, Se
     }
 }
 
+// Save the attribute and role mapping for an institution
+#[server(UpdateSamlMapping, "/api")]
+pub async fn update_saml_mapping(
+    config_id: String,
+    settings: SamlMappingSettings,
+) -> Result<SamlAuthResponse, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use uuid::Uuid;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = require_permission(Permission::ManageSso).await?;
+
+        settings.validate().map_err(ServerFnError::new)?;
+
+        let config_uuid = Uuid::parse_str(&config_id)
+            .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
+
+        let mut config = saml_database::get_saml_config_by_id(&pool, config_uuid)
+            .await?
+            .ok_or_else(|| ServerFnError::new("SAML configuration not found"))?;
+        let before = config.clone();
+        config.apply_mapping_settings(&settings);
+
+        saml_database::update_saml_mapping(&pool, &config).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::SamlConfig,
+            config_uuid,
+            AuditChange::updated(&before, &config),
+        )
+        .await?;
+
+        Ok(SamlAuthResponse {
+            success: true,
+            message: format!("Attribute mapping saved for {}", config.institution_name),
+            redirect_url: None,
+            user: None,
+        })
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Run a pasted SAML response through unsaved mapping settings
+#[server(PreviewSamlMapping, "/api")]
+pub async fn preview_saml_mapping(
+    config_id: String,
+    settings: SamlMappingSettings,
+    saml_response: String,
+) -> Result<SamlMappingPreview, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use uuid::Uuid;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        require_permission(Permission::ManageSso).await?;
+
+        settings.validate().map_err(ServerFnError::new)?;
+
+        let config_uuid = Uuid::parse_str(&config_id)
+            .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
+
+        let mut config = saml_database::get_saml_config_by_id(&pool, config_uuid)
+            .await?
+            .ok_or_else(|| ServerFnError::new("SAML configuration not found"))?;
+        config.apply_mapping_settings(&settings);
+
+        use base64::{engine::general_purpose, Engine as _};
+
+        // Accept either the raw XML or the base64 SAMLResponse form value
+        let trimmed = saml_response.trim();
+        let saml_xml = if trimmed.starts_with('<') {
+            trimmed.to_string()
+        } else {
+            let decoded = general_purpose::STANDARD
+                .decode(trimmed.split_whitespace().collect::<String>())
+                .map_err(|e| {
+                    ServerFnError::new(format!("Failed to decode SAML response: {}", e))
+                })?;
+            String::from_utf8(decoded)
+                .map_err(|e| ServerFnError::new(format!("Invalid UTF-8 in SAML response: {}", e)))?
+        };
+
+        let base_url =
+            std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+        let saml_manager = saml_database::SamlManager::new(&base_url)
+            .map_err(|e| ServerFnError::new(format!("Failed to create SAML manager: {}", e)))?;
+
+        saml_database::preview_saml_mapping(&saml_manager, &config, &saml_xml)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
 // Test SAML configuration
 #[server(TestSamlConfig, "/api")]
 pub async fn test_saml_config(config_id: String) -> Result<SamlAuthResponse, ServerFnError> {
@@ -663,13 +809,7 @@ pub async fn test_saml_config(config_id: String) -> Result<SamlAuthResponse, Ser
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        require_permission(Permission::ManageSso).await?;
 
         let config_uuid = Uuid::parse_str(&config_id)
             .map_err(|_| ServerFnError::new("Invalid config ID format"))?;
@@ -734,13 +874,7 @@ pub async fn get_saml_stats() -> Result<SamlStats, ServerFnError> {
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        require_permission(Permission::ManageSso).await?;
 
         let stats = saml_database::get_saml_statistics(&pool).await?;
         Ok(stats)
@@ -776,13 +910,7 @@ pub async fn import_saml_metadata(
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        require_permission(Permission::ManageSso).await?;
 
         // Validate URL
         if !metadata_url.starts_with("http") {
@@ -831,13 +959,7 @@ pub async fn get_detailed_saml_institutions() -> Result<Vec<SamlInstitutionDetai
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        require_permission(Permission::ManageSso).await?;
 
         let configs = saml_database::get_all_saml_configs_detailed(&pool).await?;
 
@@ -893,13 +1015,7 @@ pub async fn bulk_toggle_saml_configs(
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        let user = require_permission(Permission::ManageSso).await?;
 
         let mut updated_count = 0;
         let mut errors = Vec::new();
@@ -908,7 +1024,21 @@ pub async fn bulk_toggle_saml_configs(
             match Uuid::parse_str(&config_id) {
                 Ok(uuid) => {
                     match saml_database::update_saml_config_status(&pool, uuid, enable).await {
-                        Ok(_) => updated_count += 1,
+                        Ok(_) => {
+                            record_audit_event(
+                                &pool,
+                                &user,
+                                AuditAction::Update,
+                                AuditEntity::SamlConfig,
+                                uuid,
+                                AuditChange::updated(
+                                    &serde_json::json!({ "active": !enable }),
+                                    &serde_json::json!({ "active": enable }),
+                                ),
+                            )
+                            .await?;
+                            updated_count += 1;
+                        }
                         Err(e) => errors.push(format!("Failed to update {}: {}", config_id, e)),
                     }
                 }
@@ -957,13 +1087,7 @@ pub async fn validate_saml_config(
     #[cfg(feature = "ssr")]
     {
         // Verify admin permissions
-        let current_user = crate::app::server_functions::auth::get_current_user()
-            .await?
-            .ok_or_else(|| ServerFnError::new("Authentication required"))?;
-
-        if !matches!(current_user.role, UserRole::Admin | UserRole::SuperAdmin) {
-            return Err(ServerFnError::new("Admin privileges required"));
-        }
+        require_permission(Permission::ManageSso).await?;
 
         let mut validation_errors = Vec::new();
         let mut warnings = Vec::new();
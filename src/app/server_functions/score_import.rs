@@ -0,0 +1,138 @@
+use crate::app::models::import_validation::ImportValidation;
+use crate::app::models::score_import::ScoreImportFile;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::{question_database, score_database, student_database, test_database},
+    models::import_validation::ParsedRows,
+    models::permission::Permission,
+    models::score_import::HistoricalScore,
+    models::user::SessionUser,
+    server_functions::authorization::{data_scope_for, require_permission},
+    services::score_import::{self, ScoreImportContext},
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use {
+    sqlx::PgPool,
+    std::collections::{BTreeSet, HashMap, HashSet},
+    uuid::Uuid,
+};
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Parses a score CSV or workbook and checks it against the tests it names, their
+// questions, the user's caseload and the scores already on file
+#[cfg(feature = "ssr")]
+async fn validate_score_upload(
+    pool: &PgPool,
+    user: &SessionUser,
+    file_contents: &str,
+) -> Result<ParsedRows<HistoricalScore>, ServerFnError> {
+    let parsed = score_import::parse_score_rows(file_contents).map_err(ServerFnError::new)?;
+    let tests = test_database::get_all_tests(pool).await?;
+    let test_ids: BTreeSet<String> = parsed
+        .rows
+        .iter()
+        .filter_map(|(_, row)| score_import::resolve_test(&row.test, row.variant, &tests).ok())
+        .map(|test| test.test_id.clone())
+        .collect();
+
+    let mut questions = HashMap::new();
+    for test_id in &test_ids {
+        let test_questions = question_database::get_all_questions(test_id.clone(), pool).await?;
+        questions.insert(test_id.clone(), test_questions);
+    }
+
+    let scope = data_scope_for(user, pool).await?;
+    let student_ids: HashSet<i32> = student_database::get_all_students(&scope, pool)
+        .await?
+        .iter()
+        .map(|student| student.student_id)
+        .collect();
+    let test_uuids: Vec<Uuid> = test_ids
+        .iter()
+        .filter_map(|test_id| Uuid::parse_str(test_id).ok())
+        .collect();
+    let existing = score_database::get_scores_by_test(test_uuids, &scope, pool).await?;
+
+    let default_evaluator = user.id.to_string();
+    let context = ScoreImportContext {
+        tests: &tests,
+        questions: &questions,
+        student_ids: &student_ids,
+        existing: &existing,
+        default_evaluator: &default_evaluator,
+        today: chrono::Utc::now().date_naive(),
+    };
+    Ok(score_import::check_scores(parsed, &context))
+}
+
+// Checks a score file without importing anything, listing every problem by
+// row and column
+#[server(ValidateScoreImport, "/api")]
+pub async fn validate_score_import(
+    file: ScoreImportFile,
+) -> Result<ImportValidation, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageScores).await?;
+        let pool = extract_pool().await?;
+        let file_contents = score_import::score_file_csv(file).map_err(ServerFnError::new)?;
+
+        let parsed = validate_score_upload(&pool, &user, &file_contents).await?;
+        parsed.report(&file_contents).map_err(ServerFnError::new)
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
+
+// Imports historical and paper-administered scores from a CSV or Excel
+// workbook, keeping each row's date and evaluator. Any problem fails the
+// whole upload unless `valid_rows_only` is set, in which case rows with
+// problems are skipped.
+#[server(UploadScoreImport, "/api")]
+pub async fn upload_score_import(
+    file: ScoreImportFile,
+    valid_rows_only: bool,
+) -> Result<usize, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageScores).await?;
+        let pool = extract_pool().await?;
+        let file_contents = score_import::score_file_csv(file).map_err(ServerFnError::new)?;
+
+        let parsed = validate_score_upload(&pool, &user, &file_contents).await?;
+        let mut scores = if valid_rows_only {
+            parsed.valid()
+        } else {
+            parsed.into_all().map_err(ServerFnError::new)?
+        };
+        if scores.is_empty() {
+            return Err(ServerFnError::new("No rows can be imported"));
+        }
+        scores.sort_by(|a, b| a.attempt_order().cmp(&b.attempt_order()));
+
+        let imported = score_database::import_scores(&scores, &user, &pool).await?;
+        log::info!(
+            "User {} imported {} historical scores",
+            user.username,
+            imported.len()
+        );
+        Ok(imported.len())
+    }
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError(
+            "Server-side functionality not available".to_string(),
+        ))
+    }
+}
@@ -1,11 +1,20 @@
 use crate::app::models::score::*;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{data_scope_for, require_permission},
+    },
+};
 use leptos::*;
 use uuid::Uuid;
 
 #[cfg(feature = "ssr")]
 use {
-    crate::app::db::database, crate::app::db::score_database, actix_web::web, sqlx::PgPool,
-    std::error::Error,
+    crate::app::db::database, crate::app::db::score_database, crate::app::db::student_database,
+    actix_web::web, sqlx::PgPool, std::error::Error,
 };
 
 #[server(GetScores, "/api")]
@@ -14,14 +23,18 @@ pub async fn get_scores() -> Result<Vec<Score>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewScores).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!("Attempting to retrieve all scores from database");
         use crate::app::db::score_database;
 
-        match score_database::get_all_scores(&pool).await {
+        match score_database::get_all_scores(&scope, &pool).await {
             Ok(scores) => {
                 log::info!(
                     "Successfully retrieved {} scores from database",
@@ -43,13 +56,17 @@ pub async fn get_scores_by_test(test_ids: Vec<Uuid>) -> Result<Vec<Score>, Serve
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewScores).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!("Attempting to get scores based upon test IDs");
 
-        match score_database::get_scores_by_test(test_ids, &pool).await {
+        match score_database::get_scores_by_test(test_ids, &scope, &pool).await {
             Ok(scores) => {
                 log::info!("Successfully retrieved scores from database");
                 Ok(scores)
@@ -73,13 +90,19 @@ pub async fn get_score(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewScores).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!("Attempting to retrieve all scores from database");
 
-        match score_database::get_score(student_id, test_id, test_variant, attempt, &pool).await {
+        match score_database::get_score(student_id, test_id, test_variant, attempt, &scope, &pool)
+            .await
+        {
             Ok(score) => {
                 log::info!("Successfully retrieved score from database");
                 Ok(score)
@@ -98,16 +121,20 @@ pub async fn get_student_scores(student_id: i32) -> Result<Vec<Score>, ServerFnE
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewScores).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!(
             "Attempting to retrieve all scores for student: {}",
             student_id
         );
 
-        match score_database::get_all_student_scores(student_id, &pool).await {
+        match score_database::get_all_student_scores(student_id, &scope, &pool).await {
             Ok(scores) => {
                 log::info!(
                     "Successfully retrieved scores from database for student: {}",
@@ -129,9 +156,22 @@ pub async fn add_score(add_score_request: CreateScoreRequest) -> Result<Score, S
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageScores).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        if !student_database::is_student_in_scope(add_score_request.student_id, &scope, &pool)
+            .await?
+        {
+            return Err(ServerFnError::new(format!(
+                "Student {} is not on your caseload",
+                add_score_request.student_id
+            )));
+        }
 
         log::info!("Attempting to add new score to the database");
 
@@ -141,6 +181,15 @@ pub async fn add_score(add_score_request: CreateScoreRequest) -> Result<Score, S
                     "Successfully created score for student {}",
                     created_score.student_id
                 );
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Create,
+                    AuditEntity::Score,
+                    created_score.audit_id(),
+                    AuditChange::created(&created_score),
+                )
+                .await?;
                 Ok(created_score)
             }
             Err(e) => {
@@ -161,9 +210,22 @@ pub async fn delete_score(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageScores).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        if !student_database::is_student_in_scope(delete_score_request.student_id, &scope, &pool)
+            .await?
+        {
+            return Err(ServerFnError::new(format!(
+                "Student {} is not on your caseload",
+                delete_score_request.student_id
+            )));
+        }
 
         log::info!("Attempting to delete score from the database");
 
@@ -176,7 +238,18 @@ pub async fn delete_score(
         )
         .await
         {
-            Ok(deleted) => Ok(deleted),
+            Ok(deleted) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Delete,
+                    AuditEntity::Score,
+                    deleted.audit_id(),
+                    AuditChange::deleted(&deleted),
+                )
+                .await?;
+                Ok(deleted)
+            }
             Err(_) => Err(ServerFnError::new(
                 "Failed to delete score from the database",
             )),
@@ -0,0 +1,91 @@
+use crate::app::models::auth::ActiveSession;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::user_database,
+    models::permission::Permission,
+    server_functions::{auth::current_session_token, authorization::require_permission},
+};
+use leptos::*;
+
+// The caller's live sessions, with the one making this request marked current
+#[server(GetMySessions, "/api")]
+pub async fn get_my_sessions() -> Result<Vec<ActiveSession>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let current_token = current_session_token().await?;
+        user_database::list_user_sessions(&pool, user.id, current_token.as_deref()).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(RevokeMySession, "/api")]
+pub async fn revoke_my_session(session_id: i64) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        if !user_database::revoke_user_session(&pool, user.id, session_id).await? {
+            return Err(ServerFnError::new("Session not found"));
+        }
+
+        log::info!("User {} revoked session {}", user.username, session_id);
+        Ok(())
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Signs the caller out on every other device, keeping this one
+#[server(RevokeMyOtherSessions, "/api")]
+pub async fn revoke_my_other_sessions() -> Result<u64, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let user = require_permission(Permission::Authenticated).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let current_token = current_session_token().await?;
+        let revoked =
+            user_database::revoke_all_user_sessions(&pool, user.id, current_token.as_deref())
+                .await?;
+
+        log::info!("User {} revoked {} other sessions", user.username, revoked);
+        Ok(revoked)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
@@ -0,0 +1,197 @@
+use crate::app::models::student_attribute::{
+    AttributeDefinition, AttributeValues, SaveAttributeDefinitionRequest,
+};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::student_attribute_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    models::student_attribute::normalize_attribute_values,
+    models::user::SessionUser,
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{data_scope_for, require_permission},
+    },
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Every custom attribute, inactive ones included, in display order
+#[server(GetAttributeDefinitions, "/api")]
+pub async fn get_attribute_definitions() -> Result<Vec<AttributeDefinition>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_permission(Permission::ViewStudents).await?;
+        let pool = extract_pool().await?;
+
+        student_attribute_database::list_attribute_definitions(&pool).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(SaveAttributeDefinition, "/api")]
+pub async fn save_attribute_definition(
+    request: SaveAttributeDefinitionRequest,
+) -> Result<AttributeDefinition, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+        request.validate().map_err(ServerFnError::new)?;
+
+        let before = match request.id {
+            Some(id) => student_attribute_database::list_attribute_definitions(&pool)
+                .await?
+                .into_iter()
+                .find(|definition| definition.id == id),
+            None => None,
+        };
+        let definition =
+            student_attribute_database::save_attribute_definition(&pool, &request).await?;
+
+        record_audit_event(
+            &pool,
+            &user,
+            if before.is_some() {
+                AuditAction::Update
+            } else {
+                AuditAction::Create
+            },
+            AuditEntity::StudentAttribute,
+            definition.id,
+            match &before {
+                Some(before) => AuditChange::updated(before, &definition),
+                None => AuditChange::created(&definition),
+            },
+        )
+        .await?;
+
+        Ok(definition)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Deletes the attribute along with every student's value for it
+#[server(DeleteAttributeDefinition, "/api")]
+pub async fn delete_attribute_definition(id: i32) -> Result<AttributeDefinition, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageSettings).await?;
+        let pool = extract_pool().await?;
+
+        let deleted = student_attribute_database::delete_attribute_definition(&pool, id).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Delete,
+            AuditEntity::StudentAttribute,
+            deleted.id,
+            AuditChange::deleted(&deleted),
+        )
+        .await?;
+
+        Ok(deleted)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Custom attribute values of the given students, keyed by student ID.
+// Students outside the caller's caseload are left out.
+#[server(GetStudentAttributeValues, "/api")]
+pub async fn get_student_attribute_values(
+    student_ids: Vec<i32>,
+) -> Result<HashMap<i32, AttributeValues>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ViewStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        student_attribute_database::list_student_attribute_values(&pool, &student_ids, &scope).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Checks values submitted with a student against the attribute definitions.
+// Returns the keys the submission covers (every active attribute, plus any
+// inactive one it names) and the normalized values.
+#[cfg(feature = "ssr")]
+pub async fn normalize_submitted_attributes(
+    pool: &PgPool,
+    submitted: &AttributeValues,
+) -> Result<(Vec<String>, AttributeValues), ServerFnError> {
+    let definitions = student_attribute_database::list_attribute_definitions(pool).await?;
+    let values = normalize_attribute_values(&definitions, submitted).map_err(ServerFnError::new)?;
+    let keys = definitions
+        .into_iter()
+        .filter(|definition| definition.active || submitted.contains_key(&definition.key))
+        .map(|definition| definition.key)
+        .collect();
+    Ok((keys, values))
+}
+
+// Stores one student's normalized values for `keys`, auditing the change
+// against the student when anything differs
+#[cfg(feature = "ssr")]
+pub async fn store_student_attributes(
+    pool: &PgPool,
+    user: &SessionUser,
+    student_id: i32,
+    keys: &[String],
+    values: AttributeValues,
+) -> Result<(), ServerFnError> {
+    use crate::app::models::permission::DataScope;
+
+    let before = student_attribute_database::list_student_attribute_values(
+        pool,
+        &[student_id],
+        &DataScope::Unrestricted,
+    )
+    .await?
+    .remove(&student_id)
+    .unwrap_or_default();
+    let mut after = before.clone();
+    after.retain(|key, _| !keys.contains(key));
+    after.extend(values.clone());
+    if after == before {
+        return Ok(());
+    }
+
+    student_attribute_database::set_student_attribute_values(pool, keys, &[(student_id, values)])
+        .await?;
+    record_audit_event(
+        pool,
+        user,
+        AuditAction::Update,
+        AuditEntity::Student,
+        student_id,
+        // Only which attributes changed; values can be as sensitive as PII
+        AuditChange::updated(&before, &after).redacted(&[]),
+    )
+    .await
+}
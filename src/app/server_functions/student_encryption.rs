@@ -0,0 +1,144 @@
+use crate::app::models::student_encryption::{KeyRotationReport, StudentEncryptionStatus};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::student_encryption_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{audit::record_audit_event, authorization::require_permission},
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+#[server(GetStudentEncryptionStatus, "/api")]
+pub async fn get_student_encryption_status() -> Result<StudentEncryptionStatus, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_permission(Permission::ManageStudentProtection).await?;
+        let pool = extract_pool().await?;
+
+        student_encryption_database::get_student_encryption_status(&pool).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Starts a new data key and re-encrypts every student under it. The old key
+// is deleted once nothing references it.
+#[server(RotateStudentDataKey, "/api")]
+pub async fn rotate_student_data_key() -> Result<KeyRotationReport, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudentProtection).await?;
+        let pool = extract_pool().await?;
+
+        let key_id = student_encryption_database::rotate_student_data_key(&pool).await?;
+        let report = student_encryption_database::reseal_all_students(&pool).await?;
+
+        log::info!(
+            "User {} rotated the student data key to {}: {}",
+            user.username,
+            key_id,
+            report.summary()
+        );
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Create,
+            AuditEntity::StudentDataKey,
+            key_id,
+            AuditChange::created(&report),
+        )
+        .await?;
+
+        Ok(report)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Re-wraps every data key under the provider's active master key, so a
+// retired master key can be removed. Student rows are untouched.
+#[server(RewrapStudentDataKeys, "/api")]
+pub async fn rewrap_student_data_keys() -> Result<KeyRotationReport, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudentProtection).await?;
+        let pool = extract_pool().await?;
+
+        let report = KeyRotationReport {
+            keys_rewrapped: student_encryption_database::rewrap_student_data_keys(&pool).await?,
+            ..Default::default()
+        };
+
+        log::info!(
+            "User {} re-wrapped student data keys: {}",
+            user.username,
+            report.summary()
+        );
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::StudentDataKey,
+            "all",
+            AuditChange::created(&report),
+        )
+        .await?;
+
+        Ok(report)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Encrypts students still stored in plaintext, e.g. rows written before a
+// key provider was configured, and finishes an interrupted rotation
+#[server(EncryptPendingStudents, "/api")]
+pub async fn encrypt_pending_students() -> Result<KeyRotationReport, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudentProtection).await?;
+        let pool = extract_pool().await?;
+
+        let report = student_encryption_database::reseal_all_students(&pool).await?;
+
+        log::info!(
+            "User {} encrypted pending students: {}",
+            user.username,
+            report.summary()
+        );
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Update,
+            AuditEntity::StudentDataKey,
+            "all",
+            AuditChange::created(&report),
+        )
+        .await?;
+
+        Ok(report)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
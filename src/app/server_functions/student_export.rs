@@ -0,0 +1,102 @@
+use crate::app::models::student_export::StudentExportBundle;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::{
+        audit_database, enrollment_database, question_database, score_database, student_database,
+        test_database,
+    },
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    models::student_export::{InterventionSummary, StudentRecordExport},
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{data_scope_for, require_permission},
+    },
+    services::student_export,
+};
+use leptos::*;
+
+// Gathers everything held on one student into a zip for a records request.
+// Each export is recorded in the audit log as a disclosure.
+#[server(ExportStudentRecords, "/api")]
+pub async fn export_student_records(student_id: i32) -> Result<StudentExportBundle, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+        use std::collections::HashMap;
+        use uuid::Uuid;
+
+        let user = require_permission(Permission::ManageStudents).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        let student = student_database::get_certain_student(student_id, &scope, &pool).await?;
+        let enrollments =
+            enrollment_database::get_enrollments_by_student(&student_id, &scope, &pool).await?;
+        let scores = score_database::get_all_student_scores(student_id, &scope, &pool).await?;
+
+        let mut test_ids: Vec<Uuid> = scores
+            .iter()
+            .filter_map(|score| Uuid::parse_str(&score.test_id).ok())
+            .collect();
+        test_ids.sort();
+        test_ids.dedup();
+        let names: HashMap<String, String> =
+            test_database::get_tests_batch(test_ids.clone(), &pool)
+                .await?
+                .into_iter()
+                .map(|test| (test.test_id, test.name))
+                .collect();
+        let mut tests = HashMap::new();
+        for test_id in test_ids.iter().map(Uuid::to_string) {
+            let questions = question_database::get_all_questions(test_id.clone(), &pool).await?;
+            tests.insert(test_id.clone(), (names.get(&test_id).cloned(), questions));
+        }
+
+        let record = StudentRecordExport {
+            generated_at: chrono::Utc::now(),
+            generated_by: user.username.clone(),
+            interventions: InterventionSummary::from_student(&student),
+            student,
+            enrollments,
+            scores: student_export::export_scores(scores, &tests),
+            audit_events: audit_database::list_student_audit_events(&pool, student_id).await?,
+        };
+        let bundle = student_export::build_bundle(&record).map_err(ServerFnError::new)?;
+
+        log::info!(
+            "User {} exported the records of student {}",
+            user.username,
+            student_id
+        );
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Export,
+            AuditEntity::Student,
+            student_id,
+            AuditChange {
+                before: None,
+                after: Some(serde_json::json!({
+                    "filename": bundle.filename,
+                    "enrollments": record.enrollments.len(),
+                    "scores": record.scores.len(),
+                    "audit_events": record.audit_events.len(),
+                })),
+            },
+        )
+        .await?;
+
+        Ok(bundle)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
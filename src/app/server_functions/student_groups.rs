@@ -0,0 +1,138 @@
+use crate::app::models::student_group::{GroupTestSummary, SaveStudentGroupRequest, StudentGroup};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::student_group_database,
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::{DataScope, Permission},
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{data_scope_for, require_permission},
+    },
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Groups the caller owns or that hold students in their caseload; admins
+// see every group
+#[server(GetStudentGroups, "/api")]
+pub async fn get_student_groups() -> Result<Vec<StudentGroup>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ViewStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        student_group_database::list_student_groups(&pool, &scope).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(SaveStudentGroup, "/api")]
+pub async fn save_student_group(
+    request: SaveStudentGroupRequest,
+) -> Result<StudentGroup, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+        request.validate().map_err(ServerFnError::new)?;
+
+        let before = match request.id {
+            Some(id) => {
+                student_group_database::get_student_group(&pool, id, &DataScope::Unrestricted)
+                    .await?
+            }
+            None => None,
+        };
+        let group = student_group_database::save_student_group(&pool, &request).await?;
+
+        record_audit_event(
+            &pool,
+            &user,
+            if before.is_some() {
+                AuditAction::Update
+            } else {
+                AuditAction::Create
+            },
+            AuditEntity::StudentGroup,
+            group.id,
+            match &before {
+                Some(before) => AuditChange::updated(before, &group),
+                None => AuditChange::created(&group),
+            },
+        )
+        .await?;
+
+        Ok(group)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(DeleteStudentGroup, "/api")]
+pub async fn delete_student_group(id: i32) -> Result<StudentGroup, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+
+        let deleted = student_group_database::delete_student_group(&pool, id).await?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Delete,
+            AuditEntity::StudentGroup,
+            deleted.id,
+            AuditChange::deleted(&deleted),
+        )
+        .await?;
+
+        Ok(deleted)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// Per-test results of a group the caller can see
+#[server(GetGroupTestSummaries, "/api")]
+pub async fn get_group_test_summaries(
+    group_id: i32,
+) -> Result<Vec<GroupTestSummary>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ViewScores).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        if student_group_database::get_student_group(&pool, group_id, &scope)
+            .await?
+            .is_none()
+        {
+            return Err(ServerFnError::new("Group not found"));
+        }
+        student_group_database::get_group_test_summaries(&pool, group_id, &scope).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
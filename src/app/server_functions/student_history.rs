@@ -0,0 +1,70 @@
+use crate::app::models::student::Student;
+use crate::app::models::student_history::StudentVersion;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::{student_database, student_history_database},
+    models::permission::Permission,
+    server_functions::authorization::{data_scope_for, require_permission},
+};
+use chrono::NaiveDate;
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Every recorded version of a student, oldest first
+#[server(GetStudentHistory, "/api")]
+pub async fn get_student_history(student_id: i32) -> Result<Vec<StudentVersion>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ViewStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+        if !student_database::is_student_in_scope(student_id, &scope, &pool).await? {
+            return Err(ServerFnError::new("Student not found"));
+        }
+
+        student_history_database::list_student_versions(&pool, student_id).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+// The student's flags and placement as they stood at the end of `as_of`,
+// with their current name and other PII. None before history began.
+#[server(GetStudentAsOf, "/api")]
+pub async fn get_student_as_of(
+    student_id: i32,
+    as_of: NaiveDate,
+) -> Result<Option<Student>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ViewStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        let student = student_database::get_certain_student(student_id, &scope, &pool).await?;
+        let end_of_day = as_of
+            .and_hms_opt(23, 59, 59)
+            .ok_or_else(|| ServerFnError::new("Invalid date"))?
+            .and_utc();
+        let version =
+            student_history_database::student_version_as_of(&pool, student_id, end_of_day).await?;
+
+        Ok(version.map(|version| version.apply_to(student)))
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
@@ -0,0 +1,127 @@
+use crate::app::models::student_merge::{DuplicateCandidate, MergeStudentsRequest, StudentMerge};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    db::{student_database, student_merge_database},
+    models::permission::Permission,
+    models::student_merge::DEFAULT_DUPLICATE_THRESHOLD,
+    server_functions::authorization::{data_scope_for, require_permission},
+    services::student_duplicates,
+};
+use leptos::*;
+#[cfg(feature = "ssr")]
+use sqlx::PgPool;
+
+#[cfg(feature = "ssr")]
+async fn extract_pool() -> Result<actix_web::web::Data<PgPool>, ServerFnError> {
+    leptos_actix::extract::<actix_web::web::Data<PgPool>>()
+        .await
+        .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))
+}
+
+// Likely duplicates among the students the caller can see, most alike first
+#[server(FindDuplicateStudents, "/api")]
+pub async fn find_duplicate_students(
+    threshold: Option<f64>,
+) -> Result<Vec<DuplicateCandidate>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        let students = student_database::get_all_students(&scope, &pool).await?;
+        let threshold = threshold
+            .unwrap_or(DEFAULT_DUPLICATE_THRESHOLD)
+            .clamp(0.5, 1.0);
+
+        Ok(student_duplicates::find_duplicates(&students, threshold))
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(GetStudentMerges, "/api")]
+pub async fn get_student_merges(
+    student_id: Option<i32>,
+) -> Result<Vec<StudentMerge>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+
+        student_merge_database::list_student_merges(&pool, student_id).await
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(MergeStudents, "/api")]
+pub async fn merge_students(request: MergeStudentsRequest) -> Result<StudentMerge, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+        request.validate().map_err(ServerFnError::new)?;
+
+        // Both must be visible to the caller, and the merged one is audited as deleted
+        student_database::get_certain_student(request.survivor_id, &scope, &pool).await?;
+        let merged =
+            student_database::get_certain_student(request.merged_id, &scope, &pool).await?;
+
+        let merge = student_merge_database::merge_students(&pool, &request, &merged, &user).await?;
+        log::info!(
+            "User {} merged student {} into {}",
+            user.username,
+            merge.merged_id,
+            merge.survivor_id
+        );
+
+        Ok(merge)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
+
+#[server(UnmergeStudents, "/api")]
+pub async fn unmerge_students(merge_id: i32) -> Result<StudentMerge, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        let user = require_permission(Permission::ManageStudents).await?;
+        let pool = extract_pool().await?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        let merge = student_merge_database::list_student_merges(&pool, None)
+            .await?
+            .into_iter()
+            .find(|merge| merge.id == merge_id)
+            .ok_or_else(|| ServerFnError::new("Merge not found"))?;
+        if !student_database::is_student_in_scope(merge.survivor_id, &scope, &pool).await? {
+            return Err(ServerFnError::new("Student not found"));
+        }
+
+        let merge = student_merge_database::unmerge_students(&pool, merge_id, &user).await?;
+        log::info!(
+            "User {} undid merge {}, restoring student {}",
+            user.username,
+            merge.id,
+            merge.merged_id
+        );
+
+        Ok(merge)
+    }
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        Err(ServerFnError::ServerError("Not implemented".to_string()))
+    }
+}
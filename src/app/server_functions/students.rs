@@ -1,13 +1,25 @@
 use crate::app::errors::ErrorMessage;
+use crate::app::models::student_search::{StudentSearchRequest, StudentSearchResults};
 use crate::app::models::{
     student::Student, AddStudentRequest, DeleteStudentRequest, UpdateStudentRequest,
 };
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::{DataScope, Permission},
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{data_scope_for, require_permission},
+        student_attributes::{normalize_submitted_attributes, store_student_attributes},
+    },
+};
 use leptos::*;
 
 #[cfg(feature = "ssr")]
 use {
-    crate::app::db::database, crate::app::db::student_database, crate::app::errors::StudentError,
-    actix_web::web, chrono::Local, sqlx::PgPool, std::error::Error, uuid::Uuid,
+    crate::app::db::database, crate::app::db::student_database,
+    crate::app::db::student_history_database, crate::app::errors::StudentError, actix_web::web,
+    chrono::Local, sqlx::PgPool, std::error::Error, uuid::Uuid,
 };
 
 #[server(GetStudents, "/api")]
@@ -16,13 +28,17 @@ pub async fn get_students() -> Result<Vec<Student>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!("Attempting to retrieve all students from database");
 
-        match student_database::get_all_students(&pool).await {
+        match student_database::get_all_students(&scope, &pool).await {
             Ok(students) => {
                 log::info!("Successfully retrieve_all_students from database");
                 Ok(students)
@@ -41,13 +57,17 @@ pub async fn get_student(student_id: i32) -> Result<Student, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
         log::info!("Attempting to retrieve certain student");
 
-        match student_database::get_certain_student(student_id, &pool).await {
+        match student_database::get_certain_student(student_id, &scope, &pool).await {
             Ok(student) => {
                 log::info!("Successfully got certain student");
                 Ok(student)
@@ -66,15 +86,19 @@ pub async fn get_students_smart(fragment: String) -> Result<Vec<Student>, Server
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
 
-        log::info!("Attempting to retrieve all students from database (smartly)");
+        log::info!("Searching students from database");
 
-        match student_database::get_all_students(&pool).await {
+        match student_database::search_students(&fragment, &scope, &pool).await {
             Ok(students) => {
-                log::info!("Successfully retrieve_all_students from database");
+                log::info!("Successfully searched students from database");
                 Ok(students)
             }
             Err(e) => {
@@ -85,17 +109,43 @@ pub async fn get_students_smart(fragment: String) -> Result<Vec<Student>, Server
     }
 }
 
+// Ranked, typo-tolerant search within the caller's caseload, one page at
+// a time
+#[server(SearchStudents, "/api")]
+pub async fn search_students(
+    request: StudentSearchRequest,
+) -> Result<StudentSearchResults, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use leptos_actix::extract;
+
+        let user = require_permission(Permission::ViewStudents).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+        let scope = data_scope_for(&user, &pool).await?;
+
+        student_database::rank_student_search(&request, &scope, &pool).await
+    }
+}
+
 #[server(AddStudent, "/api")]
 pub async fn add_student(add_student_request: AddStudentRequest) -> Result<Student, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Attempting to add new student to the database");
+        let (attribute_keys, attribute_values) =
+            normalize_submitted_attributes(&pool, &add_student_request.custom_attributes).await?;
         let bufferStudent = Student::new(
             Some(add_student_request.firstname),
             Some(add_student_request.lastname),
@@ -123,6 +173,29 @@ pub async fn add_student(add_student_request: AddStudentRequest) -> Result<Stude
                     "Successfully created student with ID: {}",
                     created_student.student_id
                 );
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Create,
+                    AuditEntity::Student,
+                    created_student.student_id,
+                    AuditChange::created(&created_student).redacted(&["student_id"]),
+                )
+                .await?;
+                store_student_attributes(
+                    &pool,
+                    &user,
+                    created_student.student_id,
+                    &attribute_keys,
+                    attribute_values,
+                )
+                .await?;
+                student_history_database::record_student_versions(
+                    &pool,
+                    &[created_student.student_id],
+                    &user.username,
+                )
+                .await?;
                 Ok(created_student)
             }
             Err(e) => {
@@ -143,6 +216,9 @@ pub async fn delete_student(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -157,7 +233,18 @@ pub async fn delete_student(
         )
         .await
         {
-            Ok(deleted) => Ok(deleted),
+            Ok(deleted) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Delete,
+                    AuditEntity::Student,
+                    deleted.student_id,
+                    AuditChange::deleted(&deleted).redacted(&["student_id"]),
+                )
+                .await?;
+                Ok(deleted)
+            }
             Err(_) => Err(ServerFnError::new(
                 "Failed to delete student from the database",
             )),
@@ -174,11 +261,23 @@ pub async fn edit_student(
         use actix_web::web;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageStudents).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Attempting to update student in the database");
+        let attributes = match &edit_student_request.custom_attributes {
+            Some(submitted) => Some(normalize_submitted_attributes(&pool, submitted).await?),
+            None => None,
+        };
+        let before = student_database::get_certain_student(
+            edit_student_request.student_id,
+            &DataScope::Unrestricted,
+            &pool,
+        )
+        .await?;
 
         match student_database::update_student(
             edit_student_request.firstname,
@@ -203,7 +302,34 @@ pub async fn edit_student(
         )
         .await
         {
-            Ok(Some(updated_student)) => Ok(updated_student),
+            Ok(Some(updated_student)) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Student,
+                    updated_student.student_id,
+                    AuditChange::updated(&before, &updated_student).redacted(&["student_id"]),
+                )
+                .await?;
+                if let Some((attribute_keys, attribute_values)) = attributes {
+                    store_student_attributes(
+                        &pool,
+                        &user,
+                        updated_student.student_id,
+                        &attribute_keys,
+                        attribute_values,
+                    )
+                    .await?;
+                }
+                student_history_database::record_student_versions(
+                    &pool,
+                    &[updated_student.student_id],
+                    &user.username,
+                )
+                .await?;
+                Ok(updated_student)
+            }
             Ok(None) => Err(ServerFnError::new(format!(
                 "An None Value was returned instead of an updated student"
             ))),
@@ -4,6 +4,10 @@ use crate::app::models::teacher::{
     AddNewTeacherRequest, DeleteTeacherRequest, UpdateTeacherRequest,
 };
 use crate::app::models::StatusEnum;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+};
 use leptos::*;
 
 #[cfg(feature = "ssr")]
@@ -18,6 +22,9 @@ pub async fn get_employees() -> Result<Vec<Employee>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -43,6 +50,9 @@ pub async fn get_teachers() -> Result<Vec<Employee>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -70,6 +80,9 @@ pub async fn add_teacher(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -111,6 +124,9 @@ pub async fn delete_teacher(
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ManageStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -135,6 +151,8 @@ pub async fn edit_teacher(
         use actix_web::web;
         use leptos_actix::extract;
 
+        require_permission(Permission::ManageStaff).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
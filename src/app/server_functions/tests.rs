@@ -1,6 +1,15 @@
 use crate::app::models::test::BenchmarkCategory;
 use crate::app::models::TestType;
 use crate::app::models::{test::Test, CreateNewTestRequest, DeleteTestRequest, UpdateTestRequest};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{
+        audit::record_audit_event,
+        authorization::{authorize, require_permission},
+    },
+};
 use leptos::*;
 use uuid::Uuid;
 #[cfg(feature = "ssr")]
@@ -18,6 +27,9 @@ pub async fn get_tests() -> Result<Vec<Test>, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        authorize(Permission::Public).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -44,6 +56,8 @@ pub async fn get_tests_batch(test_ids: Vec<Uuid>) -> Result<Vec<Test>, ServerFnE
         use actix_web::web;
         use leptos_actix::extract;
 
+        require_permission(Permission::ViewTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -69,6 +83,9 @@ pub async fn get_test(test_id: String) -> Result<Test, ServerFnError> {
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        require_permission(Permission::ViewTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -95,6 +112,8 @@ pub async fn add_test(add_test_request: CreateNewTestRequest) -> Result<Test, Se
         use actix_web::web;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -116,12 +135,22 @@ pub async fn add_test(add_test_request: CreateNewTestRequest) -> Result<Test, Se
             add_test_request.scope,
             add_test_request.course_id,
         );
-        test_database::add_test(&bufferTest, &pool)
+        let created_test = test_database::add_test(&bufferTest, &pool)
             .await
             .map_err(|e| {
                 log::error!("Database error while adding test: {}", e);
                 ServerFnError::new(format!("Database error: {}", e))
-            })
+            })?;
+        record_audit_event(
+            &pool,
+            &user,
+            AuditAction::Create,
+            AuditEntity::Test,
+            &created_test.test_id,
+            AuditChange::created(&created_test),
+        )
+        .await?;
+        Ok(created_test)
     }
 }
 
@@ -131,6 +160,9 @@ pub async fn delete_test(delete_test_request: DeleteTestRequest) -> Result<Test,
     {
         use actix_web::web;
         use leptos_actix::extract;
+
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -138,7 +170,18 @@ pub async fn delete_test(delete_test_request: DeleteTestRequest) -> Result<Test,
         log::info!("Attempting to delete test");
 
         match test_database::delete_test(delete_test_request.test_id, &pool).await {
-            Ok(deleted) => Ok(deleted),
+            Ok(deleted) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Delete,
+                    AuditEntity::Test,
+                    &deleted.test_id,
+                    AuditChange::deleted(&deleted),
+                )
+                .await?;
+                Ok(deleted)
+            }
             Err(_) => Err(ServerFnError::new("Error in deleting test")),
         }
     }
@@ -151,12 +194,16 @@ pub async fn update_test(update_test_request: UpdateTestRequest) -> Result<Test,
         use actix_web::web;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Attempting to update test");
 
+        let before = test_database::get_test(update_test_request.test_id.clone(), &pool).await?;
+
         let buffer_test = Test::new(
             update_test_request.name,
             update_test_request.score,
@@ -173,7 +220,18 @@ pub async fn update_test(update_test_request: UpdateTestRequest) -> Result<Test,
         );
 
         match test_database::update_test(&buffer_test, &pool).await {
-            Ok(Some(updated_test)) => Ok(updated_test),
+            Ok(Some(updated_test)) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Test,
+                    &updated_test.test_id,
+                    AuditChange::updated(&before, &updated_test),
+                )
+                .await?;
+                Ok(updated_test)
+            }
             Ok(None) => Err(ServerFnError::new(format!(
                 "A None value was returned instead of an updated test"
             ))),
@@ -189,14 +247,29 @@ pub async fn score_overrider(test_id: String, score: i32) -> Result<Test, Server
         use actix_web::web;
         use leptos_actix::extract;
 
+        let user = require_permission(Permission::ManageTests).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Attempting to modify score for a test");
 
+        let before = test_database::get_test(test_id.clone(), &pool).await?;
+
         match test_database::score_override(test_id, score, &pool).await {
-            Ok(updated_test) => Ok(updated_test),
+            Ok(updated_test) => {
+                record_audit_event(
+                    &pool,
+                    &user,
+                    AuditAction::Update,
+                    AuditEntity::Test,
+                    &updated_test.test_id,
+                    AuditChange::updated(&before, &updated_test),
+                )
+                .await?;
+                Ok(updated_test)
+            }
             Err(e) => Err(ServerFnError::new(format!(
                 "Failed to update student: {}",
                 e
@@ -1,6 +1,11 @@
 use crate::app::db::user_database;
 use crate::app::models::setting_data::{UserSettings, UserSettingsUpdate};
 use leptos::*;
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission,
+    server_functions::authorization::require_self_or_permission,
+};
 
 #[server(GetUserSettings, "/api")]
 pub async fn get_user_settings(user_id: i64) -> Result<UserSettings, ServerFnError> {
@@ -9,7 +14,9 @@ pub async fn get_user_settings(user_id: i64) -> Result<UserSettings, ServerFnErr
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
-        
+
+        require_self_or_permission(user_id, Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -39,7 +46,9 @@ pub async fn update_user_settings(
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
-        
+
+        require_self_or_permission(user_id, Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -66,7 +75,9 @@ pub async fn reset_user_settings(user_id: i64) -> Result<UserSettings, ServerFnE
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
-        
+
+        require_self_or_permission(user_id, Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -1,6 +1,32 @@
 use crate::app::db::user_database;
+#[cfg(feature = "ssr")]
+use crate::app::db::{login_throttle_database, mfa_database};
 use crate::app::models::user::{User, UserRole};
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::audit::{AuditAction, AuditChange, AuditEntity},
+    models::permission::Permission,
+    server_functions::{
+        audit::record_audit_event,
+        auth::rotate_current_session,
+        authorization::{require_permission, require_self_or_permission},
+    },
+};
+use chrono::{DateTime, Utc};
 use leptos::*;
+#[cfg(feature = "ssr")]
+use serde_json::json;
+use std::collections::HashMap;
+
+// Account fields as recorded in the audit log, without credential material
+#[cfg(feature = "ssr")]
+fn audit_snapshot(user: &User) -> serde_json::Value {
+    let mut snapshot = serde_json::to_value(user).unwrap_or_default();
+    if let Some(fields) = snapshot.as_object_mut() {
+        fields.remove("password_salt");
+    }
+    snapshot
+}
 
 #[server(GetUsers, "/api")]
 pub async fn get_users() -> Result<Vec<User>, ServerFnError> {
@@ -9,6 +35,9 @@ pub async fn get_users() -> Result<Vec<User>, ServerFnError> {
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
+
+        require_permission(Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -35,6 +64,9 @@ pub async fn get_user(id: i64) -> Result<User, ServerFnError> {
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
+
+        require_self_or_permission(id, Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -64,15 +96,36 @@ pub async fn update_user_permissions(user_id: i64, role: UserRole) -> Result<(),
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
+
+        let admin = require_permission(Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
 
         log::info!("Updating user permissions in the database");
 
+        let before = user_database::get_user(user_id, &pool).await?;
+
         match user_database::update_permissions(user_id, role, &pool).await {
             Ok(_) => {
                 log::info!("Successfully updated user permissions");
+                record_audit_event(
+                    &pool,
+                    &admin,
+                    AuditAction::Update,
+                    AuditEntity::User,
+                    user_id,
+                    AuditChange::updated(&json!({ "role": before.role }), &json!({ "role": role })),
+                )
+                .await?;
+                // Sessions issued under the old role don't survive the change:
+                // the caller's own token is rotated, anyone else signs in again
+                if user_id == admin.id {
+                    rotate_current_session(&pool).await?;
+                } else {
+                    user_database::revoke_all_user_sessions(&pool, user_id, None).await?;
+                }
                 Ok(())
             }
             Err(e) => {
@@ -90,6 +143,9 @@ pub async fn update_user(new_user_data: User) -> Result<User, ServerFnError> {
         use actix_web::web;
         use leptos_actix::extract;
         use sqlx::PgPool;
+
+        let admin = require_self_or_permission(new_user_data.id, Permission::ManageUsers).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool")))?;
@@ -100,9 +156,20 @@ pub async fn update_user(new_user_data: User) -> Result<User, ServerFnError> {
             new_user_data.last_name.unwrap_or("None".to_string())
         );*/
 
+        let before = user_database::get_user(new_user_data.id, &pool).await?;
+
         match user_database::update_user_data(new_user_data, &pool).await {
             Ok(user) => {
                 log::info!("Successfully updated user data");
+                record_audit_event(
+                    &pool,
+                    &admin,
+                    AuditAction::Update,
+                    AuditEntity::User,
+                    user.id,
+                    AuditChange::updated(&audit_snapshot(&before), &audit_snapshot(&user)),
+                )
+                .await?;
                 Ok(user)
             }
             Err(e) => {
@@ -112,3 +179,242 @@ pub async fn update_user(new_user_data: User) -> Result<User, ServerFnError> {
         }
     }
 }
+
+// Maps user ids to the employee record they teach as. Teachers only see
+// students on that employee's caseload.
+#[server(GetUserEmployeeLinks, "/api")]
+pub async fn get_user_employee_links() -> Result<HashMap<i64, i32>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        match user_database::get_all_user_employee_links(&pool).await {
+            Ok(links) => Ok(links.into_iter().collect()),
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                Err(ServerFnError::new(format!("Database error: {}", e)))
+            }
+        }
+    }
+}
+
+#[server(LinkUserToEmployee, "/api")]
+pub async fn link_user_to_employee(
+    user_id: i64,
+    employee_id: Option<i32>,
+) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let admin = require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        log::info!("Linking user {} to employee {:?}", user_id, employee_id);
+
+        let previous_employee_id = user_database::get_user_employee_id(user_id, &pool).await?;
+
+        match user_database::set_user_employee_id(user_id, employee_id, &pool).await {
+            Ok(_) => {
+                record_audit_event(
+                    &pool,
+                    &admin,
+                    AuditAction::Update,
+                    AuditEntity::User,
+                    user_id,
+                    AuditChange::updated(
+                        &json!({ "employee_id": previous_employee_id }),
+                        &json!({ "employee_id": employee_id }),
+                    ),
+                )
+                .await?;
+                Ok(())
+            }
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                Err(ServerFnError::new(format!("Database error: {}", e)))
+            }
+        }
+    }
+}
+
+// Users currently locked out of password login, with when the lock expires
+#[server(GetLockedUsers, "/api")]
+pub async fn get_locked_users() -> Result<HashMap<i64, DateTime<Utc>>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        match login_throttle_database::get_locked_users(&pool).await {
+            Ok(locked) => Ok(locked.into_iter().collect()),
+            Err(e) => {
+                log::error!("Database error: {}", e);
+                Err(ServerFnError::new(format!("Database error: {}", e)))
+            }
+        }
+    }
+}
+
+#[server(UnlockUserLogin, "/api")]
+pub async fn unlock_user_login(user_id: i64) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let admin = require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = user_database::get_user(user_id, &pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+        log::info!("User {} unlocking login for {}", admin.id, user.username);
+
+        login_throttle_database::unlock_login(&pool, &user.username, admin.id).await?;
+        record_audit_event(
+            &pool,
+            &admin,
+            AuditAction::Update,
+            AuditEntity::User,
+            user_id,
+            AuditChange::updated(
+                &json!({ "login_locked": true }),
+                &json!({ "login_locked": false }),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+#[server(GetMfaEnabledUsers, "/api")]
+pub async fn get_mfa_enabled_users() -> Result<Vec<i64>, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        mfa_database::get_mfa_enabled_users(&pool).await
+    }
+}
+
+// For staff who lost their authenticator and recovery codes. If policy
+// requires MFA they will enroll again at their next login.
+#[server(ResetUserMfa, "/api")]
+pub async fn reset_user_mfa(user_id: i64) -> Result<(), ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let admin = require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let user = user_database::get_user(user_id, &pool)
+            .await
+            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+
+        if user.is_super_admin() && !admin.is_super_admin() {
+            return Err(ServerFnError::new(
+                "Only a super administrator can reset another super administrator's MFA",
+            ));
+        }
+
+        log::info!("User {} resetting MFA for {}", admin.id, user.username);
+
+        let mfa_enabled = mfa_database::get_user_mfa(&pool, user_id)
+            .await?
+            .map(|mfa| mfa.enabled)
+            .unwrap_or(false);
+        mfa_database::reset_mfa(&pool, user_id).await?;
+        record_audit_event(
+            &pool,
+            &admin,
+            AuditAction::Update,
+            AuditEntity::User,
+            user_id,
+            AuditChange::updated(
+                &json!({ "mfa_enabled": mfa_enabled }),
+                &json!({ "mfa_enabled": false }),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+// Admin "sign out everywhere" for a lost device or a departing staff member
+#[server(SignOutUserEverywhere, "/api")]
+pub async fn sign_out_user_everywhere(user_id: i64) -> Result<u64, ServerFnError> {
+    #[cfg(feature = "ssr")]
+    {
+        use actix_web::web;
+        use leptos_actix::extract;
+        use sqlx::PgPool;
+
+        let admin = require_permission(Permission::ManageUsers).await?;
+
+        let pool = extract::<web::Data<PgPool>>()
+            .await
+            .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
+
+        let revoked = user_database::revoke_all_user_sessions(&pool, user_id, None).await?;
+        log::info!(
+            "User {} signed out user {} everywhere ({} sessions)",
+            admin.id,
+            user_id,
+            revoked
+        );
+        record_audit_event(
+            &pool,
+            &admin,
+            AuditAction::Update,
+            AuditEntity::User,
+            user_id,
+            AuditChange::updated(
+                &json!({ "active_sessions": revoked }),
+                &json!({ "active_sessions": 0 }),
+            ),
+        )
+        .await?;
+
+        Ok(revoked)
+    }
+}
@@ -1,6 +1,10 @@
 use crate::app::models::websocket_session::{
     CreateSessionRequest, Session, SessionStatus, SessionSummary, SessionType,
 };
+#[cfg(feature = "ssr")]
+use crate::app::{
+    models::permission::Permission, server_functions::authorization::require_permission,
+};
 use chrono::{DateTime, Utc};
 use leptos::*;
 use uuid::Uuid;
@@ -15,6 +19,8 @@ use {
 pub async fn list_active_sessions() -> Result<Vec<SessionSummary>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::Authenticated).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -40,6 +46,8 @@ pub async fn list_active_sessions() -> Result<Vec<SessionSummary>, ServerFnError
 pub async fn get_active_test_sessions() -> Result<Vec<SessionSummary>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -58,6 +66,8 @@ pub async fn get_active_test_sessions() -> Result<Vec<SessionSummary>, ServerFnE
 pub async fn get_session(session_id: String) -> Result<Option<SessionSummary>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::Authenticated).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -79,6 +89,8 @@ pub async fn get_test_sessions_by_test_id(
 ) -> Result<Vec<SessionSummary>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -99,6 +111,8 @@ pub async fn create_session(
 ) -> Result<SessionSummary, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -164,6 +178,8 @@ pub async fn start_test_session(
 ) -> Result<SessionSummary, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -206,6 +222,8 @@ pub async fn join_session(
 ) -> Result<bool, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::Authenticated).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -252,6 +270,8 @@ pub async fn join_session(
 pub async fn leave_session(session_id: String) -> Result<(), ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::Authenticated).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -271,6 +291,8 @@ pub async fn leave_session(session_id: String) -> Result<(), ServerFnError> {
 pub async fn end_test_session(session_id: String) -> Result<SessionSummary, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -311,6 +333,8 @@ pub async fn end_test_session(session_id: String) -> Result<SessionSummary, Serv
 pub async fn close_session(session_id: String) -> Result<(), ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -331,6 +355,8 @@ pub async fn close_session(session_id: String) -> Result<(), ServerFnError> {
 pub async fn check_teacher_access(test_id: String, teacher_id: i32) -> Result<bool, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -385,6 +411,8 @@ pub async fn assign_teacher_to_session(
 ) -> Result<SessionSummary, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -412,6 +440,8 @@ pub async fn get_teacher_active_session(
 ) -> Result<Option<SessionSummary>, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -434,6 +464,8 @@ pub async fn get_teacher_active_session(
 pub async fn release_teacher_from_session(session_id: String) -> Result<(), ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -458,6 +490,8 @@ pub async fn release_teacher_from_session(session_id: String) -> Result<(), Serv
 pub async fn cleanup_teacher_session_endpoint(teacher_id: i32) -> Result<(), ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -485,6 +519,8 @@ pub async fn create_or_join_session(
 ) -> Result<SessionSummary, ServerFnError> {
     #[cfg(feature = "ssr")]
     {
+        require_permission(Permission::ConductTestSessions).await?;
+
         let pool = extract::<web::Data<PgPool>>()
             .await
             .map_err(|e| ServerFnError::new(format!("Failed to extract pool: {}", e)))?;
@@ -1,2 +1,34 @@
 pub mod email_service;
 pub use email_service::*;
+
+pub mod saml_validation;
+
+pub mod oidc;
+
+pub mod mfa;
+
+pub mod api_tokens;
+
+pub mod audit;
+
+pub mod student_protection;
+
+pub mod student_encryption;
+
+pub mod student_export;
+
+pub mod zip;
+
+pub mod retention;
+
+pub mod student_duplicates;
+
+pub mod oneroster;
+
+pub mod roster_sync;
+
+pub mod import_mapping;
+
+pub mod score_import;
+
+pub mod xlsx;
@@ -0,0 +1,193 @@
+// Hash chain and CSV export for the audit log. Each event's hash covers the
+// previous event's hash and a canonical encoding of its own fields, so any
+// edit, deletion or reordering shows up when the chain is re-hashed.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::audit::AuditEvent;
+        use chrono::{DateTime, SecondsFormat, Utc};
+        use serde_json::Value;
+        use sha2::{Digest, Sha256};
+
+        // prev_hash of the first event
+        pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+        // JSON with object keys sorted at every level. Postgres reorders JSONB
+        // keys, so hashes must not depend on the order they were written in.
+        pub fn canonical_json(value: &Value) -> String {
+            let mut out = String::new();
+            write_canonical(value, &mut out);
+            out
+        }
+
+        fn write_canonical(value: &Value, out: &mut String) {
+            match value {
+                Value::Object(map) => {
+                    let mut entries: Vec<_> = map.iter().collect();
+                    entries.sort_by(|a, b| a.0.cmp(b.0));
+                    out.push('{');
+                    for (i, (key, value)) in entries.into_iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        out.push_str(&Value::String(key.clone()).to_string());
+                        out.push(':');
+                        write_canonical(value, out);
+                    }
+                    out.push('}');
+                }
+                Value::Array(items) => {
+                    out.push('[');
+                    for (i, item) in items.iter().enumerate() {
+                        if i > 0 {
+                            out.push(',');
+                        }
+                        write_canonical(item, out);
+                    }
+                    out.push(']');
+                }
+                other => out.push_str(&other.to_string()),
+            }
+        }
+
+        // Timestamps are hashed at the microsecond precision Postgres stores
+        pub fn hash_timestamp(at: &DateTime<Utc>) -> String {
+            at.to_rfc3339_opts(SecondsFormat::Micros, true)
+        }
+
+        // The hash an event should carry, given its prev_hash. The id is left
+        // out since it is assigned on insert; order is covered by prev_hash.
+        pub fn event_hash(event: &AuditEvent) -> String {
+            let fields = Value::Array(vec![
+                Value::from(event.prev_hash.as_str()),
+                Value::from(hash_timestamp(&event.occurred_at)),
+                event.actor_id.map(Value::from).unwrap_or(Value::Null),
+                Value::from(event.actor_username.as_str()),
+                Value::from(event.action.as_str()),
+                Value::from(event.entity_type.as_str()),
+                Value::from(event.entity_id.as_str()),
+                event.before.clone().unwrap_or(Value::Null),
+                event.after.clone().unwrap_or(Value::Null),
+            ]);
+            Sha256::digest(canonical_json(&fields).as_bytes())
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect()
+        }
+
+        // Checks events in id order against the hash of the event before them.
+        // Returns the id of the first event that does not fit the chain.
+        pub fn find_broken_link(previous_hash: &str, events: &[AuditEvent]) -> Option<i64> {
+            let mut expected_prev = previous_hash;
+            for event in events {
+                if event.prev_hash != expected_prev || event_hash(event) != event.hash {
+                    return Some(event.id);
+                }
+                expected_prev = &event.hash;
+            }
+            None
+        }
+
+        pub fn events_to_csv(events: &[AuditEvent]) -> Result<String, String> {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer
+                .write_record([
+                    "id", "occurred_at", "actor_id", "actor_username", "action",
+                    "entity_type", "entity_id", "before", "after", "prev_hash", "hash",
+                ])
+                .map_err(|e| format!("Failed to write CSV: {}", e))?;
+
+            for event in events {
+                writer
+                    .write_record([
+                        event.id.to_string(),
+                        hash_timestamp(&event.occurred_at),
+                        event.actor_id.map(|id| id.to_string()).unwrap_or_default(),
+                        event.actor_username.clone(),
+                        event.action.to_string(),
+                        event.entity_type.to_string(),
+                        event.entity_id.clone(),
+                        event.before.as_ref().map(canonical_json).unwrap_or_default(),
+                        event.after.as_ref().map(canonical_json).unwrap_or_default(),
+                        event.prev_hash.clone(),
+                        event.hash.clone(),
+                    ])
+                    .map_err(|e| format!("Failed to write CSV: {}", e))?;
+            }
+
+            let bytes = writer.into_inner().map_err(|e| format!("Failed to write CSV: {}", e))?;
+            String::from_utf8(bytes).map_err(|e| format!("Failed to write CSV: {}", e))
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::app::models::audit::{AuditAction, AuditEntity};
+            use serde_json::json;
+
+            fn chain(count: usize) -> Vec<AuditEvent> {
+                let mut prev_hash = GENESIS_HASH.to_string();
+                (0..count)
+                    .map(|i| {
+                        let mut event = AuditEvent {
+                            id: i as i64 + 1,
+                            occurred_at: DateTime::from_timestamp(1_700_000_000 + i as i64, 123_456_000).unwrap(),
+                            actor_id: Some(7),
+                            actor_username: "principal".to_string(),
+                            action: AuditAction::Update,
+                            entity_type: AuditEntity::Score,
+                            entity_id: format!("{}", i),
+                            before: Some(json!({"points": [1, 2], "comment": "ok"})),
+                            after: Some(json!({"points": [2, 2], "comment": "regraded, \"fair\""})),
+                            prev_hash: prev_hash.clone(),
+                            hash: String::new(),
+                        };
+                        event.hash = event_hash(&event);
+                        prev_hash = event.hash.clone();
+                        event
+                    })
+                    .collect()
+            }
+
+            #[test]
+            fn canonical_json_ignores_key_order() {
+                let written = json!({"b": 1, "a": {"d": [true, null], "c": "x"}});
+                let reread: Value = serde_json::from_str(r#"{"a": {"c": "x", "d": [true, null]}, "b": 1}"#).unwrap();
+                assert_eq!(canonical_json(&written), canonical_json(&reread));
+                assert_eq!(canonical_json(&written), r#"{"a":{"c":"x","d":[true,null]},"b":1}"#);
+            }
+
+            #[test]
+            fn intact_chain_verifies() {
+                let events = chain(4);
+                assert_eq!(find_broken_link(GENESIS_HASH, &events), None);
+                assert_eq!(find_broken_link(GENESIS_HASH, &events[2..]), Some(3));
+                assert_eq!(find_broken_link(&events[1].hash, &events[2..]), None);
+            }
+
+            #[test]
+            fn tampering_is_detected() {
+                let mut edited = chain(4);
+                edited[1].after = Some(json!({"points": [9, 9], "comment": "regraded"}));
+                assert_eq!(find_broken_link(GENESIS_HASH, &edited), Some(2));
+
+                let mut rehashed = chain(4);
+                rehashed[1].actor_username = "someone else".to_string();
+                rehashed[1].hash = event_hash(&rehashed[1]);
+                assert_eq!(find_broken_link(GENESIS_HASH, &rehashed), Some(3));
+
+                let mut removed = chain(4);
+                removed.remove(2);
+                assert_eq!(find_broken_link(GENESIS_HASH, &removed), Some(4));
+            }
+
+            #[test]
+            fn csv_quotes_json_columns() {
+                let csv = events_to_csv(&chain(1)).unwrap();
+                let mut reader = csv::Reader::from_reader(csv.as_bytes());
+                let record = reader.records().next().unwrap().unwrap();
+                assert_eq!(&record[1], "2023-11-14T22:13:20.123456Z");
+                assert_eq!(&record[8], r#"{"comment":"regraded, \"fair\"","points":[2,2]}"#);
+            }
+        }
+    }
+}
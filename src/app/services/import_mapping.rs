@@ -0,0 +1,131 @@
+// Column matching for CSV imports: each template column takes the file
+// column whose header reads most like its name or one of its aliases.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::import_mapping::{ColumnMapping, DateFormat, FieldType, ImportKind, MappingProfile, MappingSuggestion, ValueTransforms};
+        use csv::ReaderBuilder;
+        use std::collections::{BTreeMap, HashSet};
+        use strsim::jaro_winkler;
+
+        // Headers less alike than this are left for the user to map
+        const MIN_HEADER_SIMILARITY: f64 = 0.88;
+
+        // Date cells looked at to guess the date format
+        const DATE_SAMPLES: usize = 50;
+
+        fn normalize(header: &str) -> String {
+            header.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+        }
+
+        fn header_score(header: &str, names: &[&str]) -> f64 {
+            let header = normalize(header);
+            if header.is_empty() {
+                return 0.0;
+            }
+            names.iter().map(|name| jaro_winkler(&header, &normalize(name))).fold(0.0, f64::max)
+        }
+
+        // Template column to file header, best matches first, each header
+        // used at most once
+        pub fn match_columns(kind: ImportKind, headers: &[String]) -> BTreeMap<String, String> {
+            let mut candidates = Vec::new();
+            for field in kind.fields() {
+                let mut names = vec![field.column, field.label];
+                names.extend(field.aliases);
+                for (index, header) in headers.iter().enumerate() {
+                    let score = header_score(header, &names);
+                    if score >= MIN_HEADER_SIMILARITY {
+                        candidates.push((score, field.column, index));
+                    }
+                }
+            }
+            candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            let mut columns = BTreeMap::new();
+            let mut used = HashSet::new();
+            for (_, column, index) in candidates {
+                if !columns.contains_key(column) && used.insert(index) {
+                    columns.insert(column.to_string(), headers[index].clone());
+                }
+            }
+            columns
+        }
+
+        // The saved profile for the file's source, if one fits its headers;
+        // otherwise columns matched by header, with the date format guessed
+        // from the file
+        pub fn suggest_mapping(kind: ImportKind, file_contents: &str, profiles: &[MappingProfile]) -> Result<MappingSuggestion, String> {
+            let mut reader = ReaderBuilder::new()
+                .has_headers(true)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(file_contents.as_bytes());
+            let headers: Vec<String> = reader
+                .headers()
+                .map_err(|e| format!("Failed to read CSV headers: {}", e))?
+                .iter()
+                .map(str::to_string)
+                .collect();
+
+            let profile = profiles
+                .iter()
+                .filter(|profile| profile.kind == kind && profile.fits(&headers))
+                .max_by_key(|profile| profile.mapping.columns.len());
+            if let Some(profile) = profile {
+                return Ok(MappingSuggestion { headers, mapping: profile.mapping.clone(), profile_id: Some(profile.id) });
+            }
+
+            let columns = match_columns(kind, &headers);
+            let date_columns: Vec<usize> = kind
+                .fields()
+                .iter()
+                .filter(|field| field.field_type == FieldType::Date)
+                .filter_map(|field| columns.get(field.column))
+                .filter_map(|source| headers.iter().position(|header| header == source))
+                .collect();
+            let records: Vec<csv::StringRecord> = reader.records().take(DATE_SAMPLES).filter_map(Result::ok).collect();
+            let samples = records
+                .iter()
+                .flat_map(|record| date_columns.iter().filter_map(|index| record.get(*index)));
+            let date_format = DateFormat::detect(samples).unwrap_or_default();
+
+            Ok(MappingSuggestion {
+                headers,
+                mapping: ColumnMapping { columns, transforms: ValueTransforms { date_format, ..ValueTransforms::default() } },
+                profile_id: None,
+            })
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn sis_headers_are_matched_to_template_columns() {
+                let csv = "Student Number,First Name,Last Name,DOB,Grade Lvl,Homeroom Teacher,Bus Route\n1001,Ada,Lovelace,09/15/2015,K,Smith,12\n";
+                let suggestion = suggest_mapping(ImportKind::Students, csv, &[]).unwrap();
+                let columns = &suggestion.mapping.columns;
+                assert_eq!(columns.get("student_id").map(String::as_str), Some("Student Number"));
+                assert_eq!(columns.get("firstname").map(String::as_str), Some("First Name"));
+                assert_eq!(columns.get("lastname").map(String::as_str), Some("Last Name"));
+                assert_eq!(columns.get("date_of_birth").map(String::as_str), Some("DOB"));
+                assert_eq!(columns.get("current_grade_level").map(String::as_str), Some("Grade Lvl"));
+                assert_eq!(columns.get("teacher").map(String::as_str), Some("Homeroom Teacher"));
+                assert!(!columns.values().any(|source| source == "Bus Route"));
+                assert_eq!(suggestion.mapping.transforms.date_format, DateFormat::MonthDayYear);
+
+                let profile = MappingProfile {
+                    id: 7,
+                    name: "PowerSchool".to_string(),
+                    kind: ImportKind::Students,
+                    mapping: ColumnMapping {
+                        columns: BTreeMap::from([("student_id".to_string(), "Student Number".to_string())]),
+                        transforms: ValueTransforms::default(),
+                    },
+                };
+                let suggestion = suggest_mapping(ImportKind::Students, csv, &[profile]).unwrap();
+                assert_eq!(suggestion.profile_id, Some(7));
+            }
+        }
+    }
+}
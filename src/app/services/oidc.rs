@@ -0,0 +1,661 @@
+// OpenID Connect authorization-code flow with PKCE. Provider endpoints come
+// from the issuer's discovery document and ID tokens are verified against the
+// provider's published JWKS; nothing from an ID token may be trusted until it
+// has passed validate_id_token.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+        use chrono::{DateTime, Duration, TimeZone, Utc};
+        use once_cell::sync::Lazy;
+        use rand::RngCore;
+        use rsa::pkcs1v15::{Signature, VerifyingKey};
+        use rsa::signature::Verifier;
+        use rsa::{BigUint, RsaPublicKey};
+        use serde::Deserialize;
+        use sha2::{Digest, Sha256};
+        use std::collections::HashMap;
+        use thiserror::Error;
+        use tokio::sync::RwLock;
+
+        // Tolerated difference between our clock and the IdP's
+        pub const CLOCK_SKEW_SECONDS: i64 = 120;
+
+        // How long discovery documents and key sets are cached
+        const PROVIDER_CACHE_MINUTES: i64 = 60;
+
+        #[derive(Error, Debug, Clone, PartialEq, Eq)]
+        pub enum OidcError {
+            #[error("OIDC discovery failed: {0}")]
+            Discovery(String),
+            #[error("Failed to load signing keys: {0}")]
+            Jwks(String),
+            #[error("Token exchange failed: {0}")]
+            TokenExchange(String),
+            #[error("Malformed ID token: {0}")]
+            MalformedToken(String),
+            #[error("Unsupported ID token algorithm: {0}")]
+            UnsupportedAlgorithm(String),
+            #[error("No signing key matches kid {0:?}")]
+            UnknownKey(Option<String>),
+            #[error("ID token signature does not verify")]
+            BadSignature,
+            #[error("Unexpected issuer: {0}")]
+            IssuerMismatch(String),
+            #[error("ID token is not intended for this client")]
+            AudienceMismatch,
+            #[error("ID token expired at {0}")]
+            Expired(DateTime<Utc>),
+            #[error("ID token was issued in the future")]
+            IssuedInFuture,
+            #[error("ID token nonce does not match the login request")]
+            NonceMismatch,
+            #[error("ID token is missing the {0} claim")]
+            MissingClaim(&'static str),
+        }
+
+        #[derive(Debug, Clone, Deserialize)]
+        pub struct ProviderMetadata {
+            pub issuer: String,
+            pub authorization_endpoint: String,
+            pub token_endpoint: String,
+            pub jwks_uri: String,
+            pub userinfo_endpoint: Option<String>,
+        }
+
+        #[derive(Debug, Clone, Deserialize)]
+        pub struct JsonWebKeySet {
+            pub keys: Vec<JsonWebKey>,
+        }
+
+        #[derive(Debug, Clone, Deserialize)]
+        pub struct JsonWebKey {
+            pub kty: String,
+            pub kid: Option<String>,
+            #[serde(rename = "use")]
+            pub key_use: Option<String>,
+            pub alg: Option<String>,
+            pub n: Option<String>,
+            pub e: Option<String>,
+        }
+
+        #[derive(Debug, Clone, Deserialize)]
+        pub struct TokenResponse {
+            pub id_token: String,
+            pub access_token: Option<String>,
+            pub token_type: Option<String>,
+            pub expires_in: Option<i64>,
+        }
+
+        pub struct PkcePair {
+            pub verifier: String,
+            pub challenge: String,
+        }
+
+        // What a login request committed to; checked against the ID token
+        pub struct IdTokenContext<'a> {
+            pub issuer: &'a str,
+            pub client_id: &'a str,
+            pub nonce: &'a str,
+            pub now: DateTime<Utc>,
+        }
+
+        #[derive(Debug, Clone)]
+        pub struct IdTokenClaims {
+            pub subject: String,
+            pub email: Option<String>,
+            pub email_verified: bool,
+            pub expires_at: DateTime<Utc>,
+            // Every scalar or list-of-scalar claim, for attribute mapping
+            pub attributes: HashMap<String, Vec<String>>,
+        }
+
+        #[derive(Deserialize)]
+        struct JwtHeader {
+            alg: String,
+            kid: Option<String>,
+        }
+
+        #[derive(Clone)]
+        struct CachedProvider {
+            metadata: ProviderMetadata,
+            jwks: JsonWebKeySet,
+            fetched_at: DateTime<Utc>,
+        }
+
+        static PROVIDER_CACHE: Lazy<RwLock<HashMap<String, CachedProvider>>> =
+            Lazy::new(|| RwLock::new(HashMap::new()));
+
+        pub fn random_token() -> String {
+            let mut bytes = [0u8; 32];
+            rand::thread_rng().fill_bytes(&mut bytes);
+            URL_SAFE_NO_PAD.encode(bytes)
+        }
+
+        // RFC 7636 S256 code challenge
+        pub fn generate_pkce() -> PkcePair {
+            let verifier = random_token();
+            let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+            PkcePair { verifier, challenge }
+        }
+
+        fn http_client() -> Result<reqwest::Client, String> {
+            reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(15))
+                .build()
+                .map_err(|e| format!("Failed to create HTTP client: {}", e))
+        }
+
+        fn normalize_issuer(issuer: &str) -> &str {
+            issuer.trim().trim_end_matches('/')
+        }
+
+        pub async fn discover(issuer: &str) -> Result<ProviderMetadata, OidcError> {
+            let url = format!("{}/.well-known/openid-configuration", normalize_issuer(issuer));
+            let client = http_client().map_err(OidcError::Discovery)?;
+            let response = client
+                .get(&url)
+                .send()
+                .await
+                .map_err(|e| OidcError::Discovery(format!("{}: {}", url, e)))?;
+            if !response.status().is_success() {
+                return Err(OidcError::Discovery(format!("{} returned {}", url, response.status())));
+            }
+            let metadata: ProviderMetadata = response
+                .json()
+                .await
+                .map_err(|e| OidcError::Discovery(format!("Invalid discovery document: {}", e)))?;
+
+            // OIDC Discovery 4.3: the document must describe the issuer we asked for
+            if normalize_issuer(&metadata.issuer) != normalize_issuer(issuer) {
+                return Err(OidcError::IssuerMismatch(metadata.issuer));
+            }
+            Ok(metadata)
+        }
+
+        pub async fn fetch_jwks(jwks_uri: &str) -> Result<JsonWebKeySet, OidcError> {
+            let client = http_client().map_err(OidcError::Jwks)?;
+            let response = client
+                .get(jwks_uri)
+                .send()
+                .await
+                .map_err(|e| OidcError::Jwks(format!("{}: {}", jwks_uri, e)))?;
+            if !response.status().is_success() {
+                return Err(OidcError::Jwks(format!("{} returned {}", jwks_uri, response.status())));
+            }
+            response
+                .json()
+                .await
+                .map_err(|e| OidcError::Jwks(format!("Invalid key set: {}", e)))
+        }
+
+        // Discovery document and keys for an issuer, from cache when fresh.
+        // `refresh` forces a refetch, used when a token names an unknown key
+        // after the IdP rotates its signing keys.
+        pub async fn provider(issuer: &str, refresh: bool) -> Result<(ProviderMetadata, JsonWebKeySet), OidcError> {
+            let key = normalize_issuer(issuer).to_string();
+            if !refresh {
+                if let Some(cached) = PROVIDER_CACHE.read().await.get(&key) {
+                    if cached.fetched_at + Duration::minutes(PROVIDER_CACHE_MINUTES) > Utc::now() {
+                        return Ok((cached.metadata.clone(), cached.jwks.clone()));
+                    }
+                }
+            }
+
+            let metadata = discover(issuer).await?;
+            let jwks = fetch_jwks(&metadata.jwks_uri).await?;
+            PROVIDER_CACHE.write().await.insert(
+                key,
+                CachedProvider {
+                    metadata: metadata.clone(),
+                    jwks: jwks.clone(),
+                    fetched_at: Utc::now(),
+                },
+            );
+            Ok((metadata, jwks))
+        }
+
+        pub fn authorization_url(
+            metadata: &ProviderMetadata,
+            client_id: &str,
+            redirect_uri: &str,
+            scopes: &str,
+            state: &str,
+            nonce: &str,
+            code_challenge: &str,
+        ) -> Result<String, OidcError> {
+            let mut url = url::Url::parse(&metadata.authorization_endpoint)
+                .map_err(|e| OidcError::Discovery(format!("Invalid authorization endpoint: {}", e)))?;
+            url.query_pairs_mut()
+                .append_pair("response_type", "code")
+                .append_pair("client_id", client_id)
+                .append_pair("redirect_uri", redirect_uri)
+                .append_pair("scope", scopes)
+                .append_pair("state", state)
+                .append_pair("nonce", nonce)
+                .append_pair("code_challenge", code_challenge)
+                .append_pair("code_challenge_method", "S256");
+            Ok(url.to_string())
+        }
+
+        pub async fn exchange_code(
+            metadata: &ProviderMetadata,
+            client_id: &str,
+            client_secret: &str,
+            redirect_uri: &str,
+            code: &str,
+            code_verifier: &str,
+        ) -> Result<TokenResponse, OidcError> {
+            let client = http_client().map_err(OidcError::TokenExchange)?;
+            let mut form = vec![
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+                ("client_id", client_id),
+                ("code_verifier", code_verifier),
+            ];
+            // Public clients rely on PKCE alone
+            if !client_secret.is_empty() {
+                form.push(("client_secret", client_secret));
+            }
+
+            let response = client
+                .post(&metadata.token_endpoint)
+                .form(&form)
+                .send()
+                .await
+                .map_err(|e| OidcError::TokenExchange(e.to_string()))?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(OidcError::TokenExchange(format!("{}: {}", status, body)));
+            }
+            response
+                .json()
+                .await
+                .map_err(|e| OidcError::TokenExchange(format!("Invalid token response: {}", e)))
+        }
+
+        // Exchanges the code and validates the ID token, refetching the key
+        // set once if the token is signed with a key we have not seen.
+        pub async fn complete_login(
+            issuer: &str,
+            client_id: &str,
+            client_secret: &str,
+            redirect_uri: &str,
+            code: &str,
+            code_verifier: &str,
+            nonce: &str,
+        ) -> Result<IdTokenClaims, OidcError> {
+            let (metadata, jwks) = provider(issuer, false).await?;
+            let tokens = exchange_code(&metadata, client_id, client_secret, redirect_uri, code, code_verifier).await?;
+            let context = IdTokenContext {
+                issuer: &metadata.issuer,
+                client_id,
+                nonce,
+                now: Utc::now(),
+            };
+
+            match validate_id_token(&tokens.id_token, &jwks, &context) {
+                Err(OidcError::UnknownKey(_)) => {
+                    let (_, jwks) = provider(issuer, true).await?;
+                    validate_id_token(&tokens.id_token, &jwks, &context)
+                }
+                result => result,
+            }
+        }
+
+        pub fn validate_id_token(
+            token: &str,
+            jwks: &JsonWebKeySet,
+            context: &IdTokenContext,
+        ) -> Result<IdTokenClaims, OidcError> {
+            let parts: Vec<&str> = token.split('.').collect();
+            if parts.len() != 3 {
+                return Err(OidcError::MalformedToken("expected three segments".to_string()));
+            }
+
+            let header: JwtHeader = serde_json::from_slice(&decode_segment(parts[0])?)
+                .map_err(|e| OidcError::MalformedToken(format!("header: {}", e)))?;
+            // Only RS256 is accepted; never trust "none" or an HMAC alg
+            // keyed with a public key
+            if header.alg != "RS256" {
+                return Err(OidcError::UnsupportedAlgorithm(header.alg));
+            }
+
+            let key = find_signing_key(jwks, header.kid.as_deref())?;
+            let signature = Signature::try_from(decode_segment(parts[2])?.as_slice())
+                .map_err(|_| OidcError::BadSignature)?;
+            let signing_input = format!("{}.{}", parts[0], parts[1]);
+            VerifyingKey::<Sha256>::new(key)
+                .verify(signing_input.as_bytes(), &signature)
+                .map_err(|_| OidcError::BadSignature)?;
+
+            let claims: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&decode_segment(parts[1])?)
+                .map_err(|e| OidcError::MalformedToken(format!("claims: {}", e)))?;
+            check_claims(&claims, context)
+        }
+
+        fn decode_segment(segment: &str) -> Result<Vec<u8>, OidcError> {
+            URL_SAFE_NO_PAD
+                .decode(segment)
+                .map_err(|e| OidcError::MalformedToken(format!("base64: {}", e)))
+        }
+
+        fn find_signing_key(jwks: &JsonWebKeySet, kid: Option<&str>) -> Result<RsaPublicKey, OidcError> {
+            let candidates: Vec<&JsonWebKey> = jwks
+                .keys
+                .iter()
+                .filter(|key| key.kty == "RSA")
+                .filter(|key| key.key_use.as_deref().map_or(true, |u| u == "sig"))
+                .filter(|key| key.alg.as_deref().map_or(true, |alg| alg == "RS256"))
+                .filter(|key| kid.is_none() || key.kid.as_deref() == kid)
+                .collect();
+
+            // Without a kid the key is only unambiguous if there is exactly one
+            let key = match candidates.as_slice() {
+                [key] => *key,
+                _ => return Err(OidcError::UnknownKey(kid.map(str::to_string))),
+            };
+
+            let component = |value: &Option<String>| -> Result<BigUint, OidcError> {
+                let bytes = URL_SAFE_NO_PAD
+                    .decode(value.as_deref().unwrap_or_default())
+                    .map_err(|e| OidcError::Jwks(format!("Invalid key component: {}", e)))?;
+                Ok(BigUint::from_bytes_be(&bytes))
+            };
+            RsaPublicKey::new(component(&key.n)?, component(&key.e)?)
+                .map_err(|e| OidcError::Jwks(format!("Invalid RSA key: {}", e)))
+        }
+
+        fn check_claims(
+            claims: &serde_json::Map<String, serde_json::Value>,
+            context: &IdTokenContext,
+        ) -> Result<IdTokenClaims, OidcError> {
+            let string_claim = |name: &'static str| -> Result<&str, OidcError> {
+                claims.get(name).and_then(|v| v.as_str()).ok_or(OidcError::MissingClaim(name))
+            };
+            let time_claim = |name: &'static str| -> Result<DateTime<Utc>, OidcError> {
+                claims
+                    .get(name)
+                    .and_then(|v| v.as_i64())
+                    .and_then(|ts| Utc.timestamp_opt(ts, 0).single())
+                    .ok_or(OidcError::MissingClaim(name))
+            };
+
+            let issuer = string_claim("iss")?;
+            if normalize_issuer(issuer) != normalize_issuer(context.issuer) {
+                return Err(OidcError::IssuerMismatch(issuer.to_string()));
+            }
+
+            let audiences: Vec<&str> = match claims.get("aud") {
+                Some(serde_json::Value::String(aud)) => vec![aud.as_str()],
+                Some(serde_json::Value::Array(auds)) => auds.iter().filter_map(|a| a.as_str()).collect(),
+                _ => return Err(OidcError::MissingClaim("aud")),
+            };
+            if !audiences.contains(&context.client_id) {
+                return Err(OidcError::AudienceMismatch);
+            }
+            // With several audiences the authorized party must be us
+            if audiences.len() > 1 && claims.get("azp").and_then(|v| v.as_str()) != Some(context.client_id) {
+                return Err(OidcError::AudienceMismatch);
+            }
+
+            let skew = Duration::seconds(CLOCK_SKEW_SECONDS);
+            let expires_at = time_claim("exp")?;
+            if context.now >= expires_at + skew {
+                return Err(OidcError::Expired(expires_at));
+            }
+            if time_claim("iat")? > context.now + skew {
+                return Err(OidcError::IssuedInFuture);
+            }
+
+            if claims.get("nonce").and_then(|v| v.as_str()) != Some(context.nonce) {
+                return Err(OidcError::NonceMismatch);
+            }
+
+            let subject = string_claim("sub")?.to_string();
+            let email_verified = match claims.get("email_verified") {
+                Some(serde_json::Value::Bool(verified)) => *verified,
+                // Some IdPs send the string form
+                Some(serde_json::Value::String(verified)) => verified == "true",
+                _ => false,
+            };
+
+            Ok(IdTokenClaims {
+                subject,
+                email: claims.get("email").and_then(|v| v.as_str()).map(str::to_string),
+                email_verified,
+                expires_at,
+                attributes: claims_to_attributes(claims),
+            })
+        }
+
+        pub fn claims_to_attributes(claims: &serde_json::Map<String, serde_json::Value>) -> HashMap<String, Vec<String>> {
+            let scalar = |value: &serde_json::Value| match value {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                serde_json::Value::Bool(b) => Some(b.to_string()),
+                _ => None,
+            };
+
+            claims
+                .iter()
+                .filter_map(|(name, value)| {
+                    let values: Vec<String> = match value {
+                        serde_json::Value::Array(items) => items.iter().filter_map(scalar).collect(),
+                        other => scalar(other).into_iter().collect(),
+                    };
+                    (!values.is_empty()).then(|| (name.clone(), values))
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::pkcs8::DecodePrivateKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::traits::PublicKeyParts;
+    use rsa::RsaPrivateKey;
+    use serde_json::json;
+
+    const IDP_KEY: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/oidc/idp_key.pem"));
+    const ISSUER: &str = "https://idp.example.edu";
+    const CLIENT_ID: &str = "dahlia-test-client";
+    const NONCE: &str = "n-0S6_WzA2Mj";
+    // 2025-01-01T12:00:00Z
+    const ISSUED_AT: i64 = 1_735_732_800;
+
+    fn private_key() -> RsaPrivateKey {
+        RsaPrivateKey::from_pkcs8_pem(IDP_KEY).unwrap()
+    }
+
+    fn jwks() -> JsonWebKeySet {
+        let public_key = private_key().to_public_key();
+        JsonWebKeySet {
+            keys: vec![JsonWebKey {
+                kty: "RSA".to_string(),
+                kid: Some("test-key".to_string()),
+                key_use: Some("sig".to_string()),
+                alg: Some("RS256".to_string()),
+                n: Some(URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be())),
+                e: Some(URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be())),
+            }],
+        }
+    }
+
+    fn sign(header: serde_json::Value, claims: serde_json::Value) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(header.to_string()),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = SigningKey::<Sha256>::new(private_key()).sign(signing_input.as_bytes());
+        format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(signature.to_bytes()))
+    }
+
+    fn claims(issuer: &str) -> serde_json::Value {
+        json!({
+            "iss": issuer,
+            "sub": "248289761001",
+            "aud": CLIENT_ID,
+            "nonce": NONCE,
+            "iat": ISSUED_AT,
+            "exp": ISSUED_AT + 300,
+            "email": "jdoe@example.edu",
+            "email_verified": true,
+            "given_name": "Jane",
+            "family_name": "Doe",
+            "groups": ["staff", "teachers"],
+        })
+    }
+
+    fn token(claims: serde_json::Value) -> String {
+        sign(json!({ "alg": "RS256", "kid": "test-key", "typ": "JWT" }), claims)
+    }
+
+    fn context(now: i64) -> IdTokenContext<'static> {
+        IdTokenContext {
+            issuer: ISSUER,
+            client_id: CLIENT_ID,
+            nonce: NONCE,
+            now: Utc.timestamp_opt(now, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_id_token() {
+        let validated = validate_id_token(&token(claims(ISSUER)), &jwks(), &context(ISSUED_AT + 60)).unwrap();
+        assert_eq!(validated.subject, "248289761001");
+        assert_eq!(validated.email.as_deref(), Some("jdoe@example.edu"));
+        assert!(validated.email_verified);
+        assert_eq!(validated.attributes["groups"], vec!["staff", "teachers"]);
+    }
+
+    #[test]
+    fn rejects_tampered_and_unsigned_tokens() {
+        let valid = token(claims(ISSUER));
+        let parts: Vec<&str> = valid.split('.').collect();
+        let mut forged = claims(ISSUER);
+        forged["sub"] = json!("admin");
+        let tampered = format!("{}.{}.{}", parts[0], URL_SAFE_NO_PAD.encode(forged.to_string()), parts[2]);
+        let result = validate_id_token(&tampered, &jwks(), &context(ISSUED_AT + 60));
+        assert_eq!(result.unwrap_err(), OidcError::BadSignature);
+
+        let unsigned = format!("{}.{}.", URL_SAFE_NO_PAD.encode(json!({ "alg": "none" }).to_string()), parts[1]);
+        let result = validate_id_token(&unsigned, &jwks(), &context(ISSUED_AT + 60));
+        assert_eq!(result.unwrap_err(), OidcError::UnsupportedAlgorithm("none".to_string()));
+    }
+
+    #[test]
+    fn rejects_wrong_claims() {
+        let jwks = jwks();
+        let now = context(ISSUED_AT + 60);
+
+        let mut wrong_audience = claims(ISSUER);
+        wrong_audience["aud"] = json!("another-client");
+        assert_eq!(validate_id_token(&token(wrong_audience), &jwks, &now).unwrap_err(), OidcError::AudienceMismatch);
+
+        let mut wrong_nonce = claims(ISSUER);
+        wrong_nonce["nonce"] = json!("replayed-nonce");
+        assert_eq!(validate_id_token(&token(wrong_nonce), &jwks, &now).unwrap_err(), OidcError::NonceMismatch);
+
+        let wrong_issuer = claims("https://evil.example.com");
+        assert!(matches!(validate_id_token(&token(wrong_issuer), &jwks, &now), Err(OidcError::IssuerMismatch(_))));
+
+        let later = context(ISSUED_AT + 300 + CLOCK_SKEW_SECONDS + 1);
+        assert!(matches!(validate_id_token(&token(claims(ISSUER)), &jwks, &later), Err(OidcError::Expired(_))));
+    }
+
+    #[test]
+    fn rejects_unknown_key_id() {
+        let rotated = sign(json!({ "alg": "RS256", "kid": "rotated-key" }), claims(ISSUER));
+        let result = validate_id_token(&rotated, &jwks(), &context(ISSUED_AT + 60));
+        assert_eq!(result.unwrap_err(), OidcError::UnknownKey(Some("rotated-key".to_string())));
+    }
+
+    // Full discovery -> authorize -> token exchange -> validation round trip
+    // against a mock IdP on a local port
+    #[actix_web::test]
+    async fn completes_login_against_mock_idp() {
+        use actix_web::{web, App, HttpResponse, HttpServer};
+        use std::sync::{Arc, Mutex};
+
+        let pkce = generate_pkce();
+        let expected_challenge = pkce.challenge.clone();
+        let issued: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+
+        let server_issuer = issuer.clone();
+        let server_issued = issued.clone();
+        let server = HttpServer::new(move || {
+            let issuer = server_issuer.clone();
+            let issued = server_issued.clone();
+            let challenge = expected_challenge.clone();
+            App::new()
+                .route("/.well-known/openid-configuration", web::get().to({
+                    let issuer = issuer.clone();
+                    move || {
+                        let issuer = issuer.clone();
+                        async move {
+                            HttpResponse::Ok().json(json!({
+                                "issuer": issuer,
+                                "authorization_endpoint": format!("{}/authorize", issuer),
+                                "token_endpoint": format!("{}/token", issuer),
+                                "jwks_uri": format!("{}/jwks", issuer),
+                            }))
+                        }
+                    }
+                }))
+                .route("/jwks", web::get().to(|| async {
+                    let key = &jwks().keys[0];
+                    HttpResponse::Ok().json(json!({ "keys": [{
+                        "kty": key.kty, "kid": key.kid, "use": "sig", "alg": "RS256", "n": key.n, "e": key.e,
+                    }]}))
+                }))
+                .route("/token", web::post().to(move |form: web::Form<HashMap<String, String>>| {
+                    let issuer = issuer.clone();
+                    let issued = issued.clone();
+                    let challenge = challenge.clone();
+                    async move {
+                        let verifier = form.get("code_verifier").cloned().unwrap_or_default();
+                        let computed = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+                        if form.get("code").map(String::as_str) != Some("mock-code") || computed != challenge {
+                            return HttpResponse::BadRequest().json(json!({ "error": "invalid_grant" }));
+                        }
+                        let now = Utc::now().timestamp();
+                        let mut claims = claims(&issuer);
+                        claims["iat"] = json!(now);
+                        claims["exp"] = json!(now + 300);
+                        let id_token = token(claims);
+                        *issued.lock().unwrap() = Some(id_token.clone());
+                        HttpResponse::Ok().json(json!({ "id_token": id_token, "access_token": "at", "token_type": "Bearer" }))
+                    }
+                }))
+        })
+        .listen(listener)
+        .unwrap()
+        .run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let (metadata, _) = provider(&issuer, true).await.unwrap();
+        let url = authorization_url(&metadata, CLIENT_ID, "http://localhost/oidc/callback", "openid email", "state-1", NONCE, &pkce.challenge).unwrap();
+        assert!(url.contains("code_challenge_method=S256"));
+
+        let wrong_verifier = complete_login(&issuer, CLIENT_ID, "secret", "http://localhost/oidc/callback", "mock-code", "not-the-verifier", NONCE).await;
+        assert!(matches!(wrong_verifier, Err(OidcError::TokenExchange(_))));
+
+        let claims = complete_login(&issuer, CLIENT_ID, "secret", "http://localhost/oidc/callback", "mock-code", &pkce.verifier, NONCE)
+            .await
+            .unwrap();
+        assert_eq!(claims.subject, "248289761001");
+        assert!(issued.lock().unwrap().is_some());
+
+        handle.stop(true).await;
+    }
+}
@@ -0,0 +1,701 @@
+// OneRoster 1.2 CSV bundles: reading an uploaded zip into roster records
+// and writing records back out. Only bulk bundles are read; delta files
+// carry changes since an earlier export the app never saw.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::oneroster::{RosterAcademicSession, RosterBundle, RosterClass, RosterCourse, RosterEnrollment, RosterExportBundle, RosterOrg, RosterUser};
+        use crate::app::services::zip::{unzip_archive, zip_archive};
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use chrono::{DateTime, NaiveDate, Utc};
+        use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+        use std::collections::HashMap;
+
+        const ORGS_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "name", "type", "identifier", "parentSourcedId"];
+        const ACADEMIC_SESSIONS_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "title", "type", "startDate", "endDate", "parentSourcedId", "schoolYear"];
+        const COURSES_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "schoolYearSourcedId", "title", "courseCode", "grades", "orgSourcedId", "subjects", "subjectCodes"];
+        const CLASSES_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "title", "grades", "courseSourcedId", "classCode", "classType", "location", "schoolSourcedId", "termSourcedIds", "subjects", "subjectCodes", "periods"];
+        const USERS_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "enabledUser", "username", "userIds", "givenName", "familyName", "middleName", "identifier", "email", "sms", "phone", "agentSourcedIds", "grades", "password", "userMasterIdentifier", "resourceSourcedIds", "preferredGivenName", "preferredMiddleName", "preferredFamilyName", "primaryOrgSourcedId", "pronouns"];
+        const ROLES_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "userSourcedId", "roleType", "role", "beginDate", "endDate", "orgSourcedId", "userProfileSourcedId"];
+        const ENROLLMENTS_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "classSourcedId", "schoolSourcedId", "userSourcedId", "role", "primary", "beginDate", "endDate"];
+        const DEMOGRAPHICS_HEADERS: &[&str] = &["sourcedId", "status", "dateLastModified", "birthDate", "sex", "americanIndianOrAlaskaNative", "asian", "blackOrAfricanAmerican", "nativeHawaiianOrOtherPacificIslander", "white", "demographicRaceTwoOrMoreRaces", "hispanicOrLatinoEthnicity", "countryOfBirthCode", "stateOfBirthAbbreviation", "cityOfBirth", "publicSchoolResidenceStatus"];
+
+        // Every file a 1.2 manifest accounts for, in the spec's order
+        const MANIFEST_FILES: &[&str] = &[
+            "academicSessions", "categories", "classes", "classResources", "courses", "courseResources", "demographics", "enrollments",
+            "lineItemLearningObjectiveIds", "lineItems", "lineItemScoreScales", "orgs", "resources", "resultLearningObjectiveIds",
+            "results", "resultScoreScales", "roles", "scoreScales", "userProfiles", "userResources", "users",
+        ];
+        const EXPORTED_FILES: &[&str] = &["academicSessions", "classes", "courses", "demographics", "enrollments", "orgs", "roles", "users"];
+        const REQUIRED_FILES: &[&str] = &["users", "classes", "enrollments"];
+        const SOURCE_SYSTEM: &str = "Dahlia";
+        // Errors listed before the rest are only counted
+        const MAX_REPORTED_ERRORS: usize = 25;
+
+        struct CsvFile {
+            name: String,
+            columns: HashMap<String, usize>,
+            // With their line numbers, counting the header as line 1
+            rows: Vec<(usize, StringRecord)>,
+        }
+
+        impl CsvFile {
+            fn parse(name: &str, data: &[u8]) -> Result<Self, String> {
+                let data = data.strip_prefix(b"\xef\xbb\xbf".as_slice()).unwrap_or(data);
+                let mut reader = ReaderBuilder::new()
+                    .has_headers(true)
+                    .flexible(true)
+                    .trim(csv::Trim::All)
+                    .from_reader(data);
+                let columns: HashMap<String, usize> = reader
+                    .headers()
+                    .map_err(|e| format!("{}: {}", name, e))?
+                    .iter()
+                    .enumerate()
+                    .map(|(index, header)| (header.to_string(), index))
+                    .collect();
+                let status = columns.get("status").copied();
+                let mut rows = Vec::new();
+                for (index, record) in reader.records().enumerate() {
+                    let record = record.map_err(|e| format!("{} line {}: {}", name, index + 2, e))?;
+                    // Bulk files may still carry rows the SIS has retired
+                    let retired = status
+                        .and_then(|status| record.get(status))
+                        .is_some_and(|status| status.eq_ignore_ascii_case("tobedeleted"));
+                    if !retired {
+                        rows.push((index + 2, record));
+                    }
+                }
+                Ok(CsvFile { name: name.to_string(), columns, rows })
+            }
+
+            fn require(&self, columns: &[&str]) -> Result<(), String> {
+                let missing: Vec<&str> = columns.iter().copied().filter(|column| !self.columns.contains_key(*column)).collect();
+                if missing.is_empty() {
+                    Ok(())
+                } else {
+                    Err(format!("{} is missing the {} column(s)", self.name, missing.join(", ")))
+                }
+            }
+
+            fn get<'a>(&self, record: &'a StringRecord, column: &str) -> &'a str {
+                self.columns.get(column).and_then(|&index| record.get(index)).unwrap_or_default()
+            }
+
+            fn optional(&self, record: &StringRecord, column: &str) -> Option<String> {
+                Some(self.get(record, column)).filter(|value| !value.is_empty()).map(str::to_string)
+            }
+
+            fn list(&self, record: &StringRecord, column: &str) -> Vec<String> {
+                self.get(record, column)
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|value| !value.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }
+
+            fn required(&self, line: usize, record: &StringRecord, column: &str) -> Result<String, String> {
+                self.optional(record, column)
+                    .ok_or_else(|| format!("{} line {}: {} is required", self.name, line, column))
+            }
+
+            fn date(&self, line: usize, record: &StringRecord, column: &str) -> Result<Option<NaiveDate>, String> {
+                self.optional(record, column)
+                    .map(|value| {
+                        NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                            .map_err(|_| format!("{} line {}: {} '{}' is not a YYYY-MM-DD date", self.name, line, column, value))
+                    })
+                    .transpose()
+            }
+        }
+
+        fn collect_errors<T>(results: Vec<Result<T, String>>, errors: &mut Vec<String>) -> Vec<T> {
+            results
+                .into_iter()
+                .filter_map(|result| result.map_err(|e| errors.push(e)).ok())
+                .collect()
+        }
+
+        fn read_orgs(file: &CsvFile) -> Vec<Result<RosterOrg, String>> {
+            file.rows
+                .iter()
+                .map(|(line, record)| {
+                    Ok(RosterOrg {
+                        sourced_id: file.required(*line, record, "sourcedId")?,
+                        name: file.required(*line, record, "name")?,
+                        org_type: file.required(*line, record, "type")?,
+                        identifier: file.optional(record, "identifier"),
+                        parent_sourced_id: file.optional(record, "parentSourcedId"),
+                    })
+                })
+                .collect()
+        }
+
+        fn read_academic_sessions(file: &CsvFile) -> Vec<Result<RosterAcademicSession, String>> {
+            file.rows
+                .iter()
+                .map(|(line, record)| {
+                    let school_year = file.required(*line, record, "schoolYear")?;
+                    Ok(RosterAcademicSession {
+                        sourced_id: file.required(*line, record, "sourcedId")?,
+                        title: file.required(*line, record, "title")?,
+                        session_type: file.required(*line, record, "type")?,
+                        start_date: file.date(*line, record, "startDate")?
+                            .ok_or_else(|| format!("{} line {}: startDate is required", file.name, line))?,
+                        end_date: file.date(*line, record, "endDate")?
+                            .ok_or_else(|| format!("{} line {}: endDate is required", file.name, line))?,
+                        parent_sourced_id: file.optional(record, "parentSourcedId"),
+                        school_year: school_year
+                            .parse()
+                            .map_err(|_| format!("{} line {}: schoolYear '{}' is not a year", file.name, line, school_year))?,
+                    })
+                })
+                .collect()
+        }
+
+        fn read_courses(file: &CsvFile) -> Vec<Result<RosterCourse, String>> {
+            file.rows
+                .iter()
+                .map(|(line, record)| {
+                    Ok(RosterCourse {
+                        sourced_id: file.required(*line, record, "sourcedId")?,
+                        title: file.required(*line, record, "title")?,
+                        course_code: file.optional(record, "courseCode"),
+                        school_year_sourced_id: file.optional(record, "schoolYearSourcedId"),
+                        org_sourced_id: file.required(*line, record, "orgSourcedId")?,
+                        grades: file.list(record, "grades"),
+                        subjects: file.list(record, "subjects"),
+                    })
+                })
+                .collect()
+        }
+
+        fn read_classes(file: &CsvFile) -> Vec<Result<RosterClass, String>> {
+            file.rows
+                .iter()
+                .map(|(line, record)| {
+                    Ok(RosterClass {
+                        sourced_id: file.required(*line, record, "sourcedId")?,
+                        title: file.required(*line, record, "title")?,
+                        course_sourced_id: file.required(*line, record, "courseSourcedId")?,
+                        class_code: file.optional(record, "classCode"),
+                        class_type: file.optional(record, "classType").unwrap_or_else(|| "scheduled".to_string()),
+                        location: file.optional(record, "location"),
+                        school_sourced_id: file.required(*line, record, "schoolSourcedId")?,
+                        term_sourced_ids: file.list(record, "termSourcedIds"),
+                        grades: file.list(record, "grades"),
+                        subjects: file.list(record, "subjects"),
+                    })
+                })
+                .collect()
+        }
+
+        fn read_enrollments(file: &CsvFile) -> Vec<Result<RosterEnrollment, String>> {
+            file.rows
+                .iter()
+                .map(|(line, record)| {
+                    Ok(RosterEnrollment {
+                        sourced_id: file.required(*line, record, "sourcedId")?,
+                        class_sourced_id: file.required(*line, record, "classSourcedId")?,
+                        school_sourced_id: file.required(*line, record, "schoolSourcedId")?,
+                        user_sourced_id: file.required(*line, record, "userSourcedId")?,
+                        role: file.required(*line, record, "role")?,
+                        primary: file.get(record, "primary").eq_ignore_ascii_case("true"),
+                        begin_date: file.date(*line, record, "beginDate")?,
+                        end_date: file.date(*line, record, "endDate")?,
+                    })
+                })
+                .collect()
+        }
+
+        // Each user's role: the primary one in roles.csv, else a 1.1 style role
+        // column in users.csv, else the role they are enrolled in classes with
+        fn read_users(
+            file: &CsvFile,
+            roles: &HashMap<String, String>,
+            enrollments: &[RosterEnrollment],
+            demographics: &HashMap<String, (Option<NaiveDate>, Option<String>)>,
+        ) -> Vec<Result<RosterUser, String>> {
+            file.rows
+                .iter()
+                .map(|(line, record)| {
+                    let sourced_id = file.required(*line, record, "sourcedId")?;
+                    let role = roles
+                        .get(&sourced_id)
+                        .cloned()
+                        .or_else(|| file.optional(record, "role"))
+                        .or_else(|| {
+                            enrollments
+                                .iter()
+                                .find(|enrollment| enrollment.user_sourced_id == sourced_id)
+                                .map(|enrollment| enrollment.role.clone())
+                        })
+                        .unwrap_or_default();
+                    let (birth_date, sex) = demographics.get(&sourced_id).cloned().unwrap_or_default();
+                    Ok(RosterUser {
+                        enabled: !file.get(record, "enabledUser").eq_ignore_ascii_case("false"),
+                        username: file.get(record, "username").to_string(),
+                        identifier: file.optional(record, "identifier"),
+                        given_name: file.required(*line, record, "givenName")?,
+                        family_name: file.required(*line, record, "familyName")?,
+                        preferred_given_name: file.optional(record, "preferredGivenName"),
+                        email: file.optional(record, "email"),
+                        grades: file.list(record, "grades"),
+                        role: role.to_ascii_lowercase(),
+                        org_sourced_id: file
+                            .optional(record, "primaryOrgSourcedId")
+                            .or_else(|| file.list(record, "orgSourcedIds").into_iter().next()),
+                        birth_date,
+                        sex,
+                        sourced_id,
+                    })
+                })
+                .collect()
+        }
+
+        fn read_roles(file: &CsvFile) -> HashMap<String, String> {
+            let mut roles = HashMap::new();
+            for (_, record) in &file.rows {
+                let user = file.get(record, "userSourcedId").to_string();
+                let role = file.get(record, "role").to_string();
+                if file.get(record, "roleType").eq_ignore_ascii_case("primary") {
+                    roles.insert(user, role);
+                } else {
+                    roles.entry(user).or_insert(role);
+                }
+            }
+            roles
+        }
+
+        fn read_demographics(file: &CsvFile, errors: &mut Vec<String>) -> HashMap<String, (Option<NaiveDate>, Option<String>)> {
+            let mut demographics = HashMap::new();
+            for (line, record) in &file.rows {
+                match file.date(*line, record, "birthDate") {
+                    Ok(birth_date) => {
+                        demographics.insert(file.get(record, "sourcedId").to_string(), (birth_date, file.optional(record, "sex")));
+                    }
+                    Err(e) => errors.push(e),
+                }
+            }
+            demographics
+        }
+
+        fn report(errors: Vec<String>) -> String {
+            let mut message = errors.iter().take(MAX_REPORTED_ERRORS).cloned().collect::<Vec<_>>().join("\n");
+            if errors.len() > MAX_REPORTED_ERRORS {
+                message.push_str(&format!("\n...and {} more", errors.len() - MAX_REPORTED_ERRORS));
+            }
+            message
+        }
+
+        // Reads a bulk OneRoster bundle. Files may sit in a folder inside the
+        // zip. Every problem found is reported together.
+        pub fn read_bundle(archive: &[u8]) -> Result<RosterBundle, String> {
+            let files: HashMap<String, Vec<u8>> = unzip_archive(archive)?
+                .into_iter()
+                .filter_map(|(name, data)| {
+                    let file = name.rsplit('/').next().unwrap_or(&name);
+                    file.strip_suffix(".csv").map(|stem| (stem.to_string(), data))
+                })
+                .collect();
+
+            let mut manifest = HashMap::new();
+            if let Some(data) = files.get("manifest") {
+                let file = CsvFile::parse("manifest.csv", data)?;
+                for (_, record) in &file.rows {
+                    manifest.insert(file.get(record, "propertyName").to_string(), file.get(record, "value").to_string());
+                }
+            }
+            let mut errors = Vec::new();
+            let mut tables = HashMap::new();
+            for (stem, data) in &files {
+                if stem == "manifest" {
+                    continue;
+                }
+                match manifest.get(&format!("file.{}", stem)).map(|mode| mode.to_ascii_lowercase()).as_deref() {
+                    Some("absent") => continue,
+                    Some("delta") => errors.push(format!("{}.csv is a delta file; export a bulk bundle from the SIS instead", stem)),
+                    _ => match CsvFile::parse(&format!("{}.csv", stem), data) {
+                        Ok(file) => {
+                            tables.insert(stem.clone(), file);
+                        }
+                        Err(e) => errors.push(e),
+                    },
+                }
+            }
+            for stem in REQUIRED_FILES {
+                if !tables.contains_key(*stem) {
+                    errors.push(format!("The bundle has no {}.csv", stem));
+                }
+            }
+            for (stem, mode) in &manifest {
+                if let Some(file) = stem.strip_prefix("file.") {
+                    if mode.eq_ignore_ascii_case("bulk") && !files.contains_key(file) {
+                        errors.push(format!("The manifest lists {}.csv but the bundle has no such file", file));
+                    }
+                }
+            }
+            if !errors.is_empty() {
+                return Err(report(errors));
+            }
+
+            for (stem, columns) in [
+                ("orgs", &["sourcedId", "name", "type"][..]),
+                ("academicSessions", &["sourcedId", "title", "type", "startDate", "endDate", "schoolYear"][..]),
+                ("courses", &["sourcedId", "title", "orgSourcedId"][..]),
+                ("classes", &["sourcedId", "title", "courseSourcedId", "schoolSourcedId"][..]),
+                ("users", &["sourcedId", "givenName", "familyName"][..]),
+                ("enrollments", &["sourcedId", "classSourcedId", "schoolSourcedId", "userSourcedId", "role"][..]),
+                ("roles", &["userSourcedId", "role"][..]),
+                ("demographics", &["sourcedId", "birthDate"][..]),
+            ] {
+                if let Some(Err(e)) = tables.get(stem).map(|file| file.require(columns)) {
+                    errors.push(e);
+                }
+            }
+            if !errors.is_empty() {
+                return Err(report(errors));
+            }
+
+            let read = |stem: &str| tables.get(stem);
+            let orgs = read("orgs").map(read_orgs).unwrap_or_default();
+            let academic_sessions = read("academicSessions").map(read_academic_sessions).unwrap_or_default();
+            let courses = read("courses").map(read_courses).unwrap_or_default();
+            let classes = read("classes").map(read_classes).unwrap_or_default();
+            let enrollments = collect_errors(read("enrollments").map(read_enrollments).unwrap_or_default(), &mut errors);
+            let roles = read("roles").map(read_roles).unwrap_or_default();
+            let demographics = read("demographics").map(|file| read_demographics(file, &mut errors)).unwrap_or_default();
+            let users = read("users")
+                .map(|file| read_users(file, &roles, &enrollments, &demographics))
+                .unwrap_or_default();
+
+            let bundle = RosterBundle {
+                source_system: manifest.get("source.systemName").filter(|name| !name.is_empty()).cloned(),
+                orgs: collect_errors(orgs, &mut errors),
+                academic_sessions: collect_errors(academic_sessions, &mut errors),
+                courses: collect_errors(courses, &mut errors),
+                classes: collect_errors(classes, &mut errors),
+                users: collect_errors(users, &mut errors),
+                enrollments,
+            };
+            if errors.is_empty() {
+                Ok(bundle)
+            } else {
+                Err(report(errors))
+            }
+        }
+
+        fn write_csv(headers: &[&str], rows: Vec<Vec<String>>) -> Result<Vec<u8>, String> {
+            let mut writer = WriterBuilder::new().from_writer(Vec::new());
+            writer.write_record(headers).map_err(|e| e.to_string())?;
+            for row in rows {
+                writer.write_record(&row).map_err(|e| e.to_string())?;
+            }
+            writer.into_inner().map_err(|e| e.to_string())
+        }
+
+        // A row with `values` in the named columns and the rest blank
+        fn row(headers: &[&str], values: &[(&str, String)]) -> Vec<String> {
+            headers
+                .iter()
+                .map(|header| {
+                    values
+                        .iter()
+                        .find(|(column, _)| column == header)
+                        .map(|(_, value)| value.clone())
+                        .unwrap_or_default()
+                })
+                .collect()
+        }
+
+        fn date_text(date: Option<NaiveDate>) -> String {
+            date.map(|date| date.format("%Y-%m-%d").to_string()).unwrap_or_default()
+        }
+
+        // Writes a bulk bundle: users.csv plus the roles.csv and demographics.csv
+        // that carry each user's role, birth date and sex
+        pub fn write_bundle(bundle: &RosterBundle, generated_at: DateTime<Utc>) -> Result<Vec<u8>, String> {
+            let bool_text = |value: bool| value.to_string();
+            let orgs = bundle.orgs.iter().map(|org| row(ORGS_HEADERS, &[
+                ("sourcedId", org.sourced_id.clone()),
+                ("name", org.name.clone()),
+                ("type", org.org_type.clone()),
+                ("identifier", org.identifier.clone().unwrap_or_default()),
+                ("parentSourcedId", org.parent_sourced_id.clone().unwrap_or_default()),
+            ])).collect();
+            let academic_sessions = bundle.academic_sessions.iter().map(|session| row(ACADEMIC_SESSIONS_HEADERS, &[
+                ("sourcedId", session.sourced_id.clone()),
+                ("title", session.title.clone()),
+                ("type", session.session_type.clone()),
+                ("startDate", date_text(Some(session.start_date))),
+                ("endDate", date_text(Some(session.end_date))),
+                ("parentSourcedId", session.parent_sourced_id.clone().unwrap_or_default()),
+                ("schoolYear", session.school_year.to_string()),
+            ])).collect();
+            let courses = bundle.courses.iter().map(|course| row(COURSES_HEADERS, &[
+                ("sourcedId", course.sourced_id.clone()),
+                ("schoolYearSourcedId", course.school_year_sourced_id.clone().unwrap_or_default()),
+                ("title", course.title.clone()),
+                ("courseCode", course.course_code.clone().unwrap_or_default()),
+                ("grades", course.grades.join(",")),
+                ("orgSourcedId", course.org_sourced_id.clone()),
+                ("subjects", course.subjects.join(",")),
+            ])).collect();
+            let classes = bundle.classes.iter().map(|class| row(CLASSES_HEADERS, &[
+                ("sourcedId", class.sourced_id.clone()),
+                ("title", class.title.clone()),
+                ("grades", class.grades.join(",")),
+                ("courseSourcedId", class.course_sourced_id.clone()),
+                ("classCode", class.class_code.clone().unwrap_or_default()),
+                ("classType", class.class_type.clone()),
+                ("location", class.location.clone().unwrap_or_default()),
+                ("schoolSourcedId", class.school_sourced_id.clone()),
+                ("termSourcedIds", class.term_sourced_ids.join(",")),
+                ("subjects", class.subjects.join(",")),
+            ])).collect();
+            let users = bundle.users.iter().map(|user| row(USERS_HEADERS, &[
+                ("sourcedId", user.sourced_id.clone()),
+                ("enabledUser", bool_text(user.enabled)),
+                ("username", user.username.clone()),
+                ("givenName", user.given_name.clone()),
+                ("familyName", user.family_name.clone()),
+                ("identifier", user.identifier.clone().unwrap_or_default()),
+                ("email", user.email.clone().unwrap_or_default()),
+                ("grades", user.grades.join(",")),
+                ("preferredGivenName", user.preferred_given_name.clone().unwrap_or_default()),
+                ("primaryOrgSourcedId", user.org_sourced_id.clone().unwrap_or_default()),
+            ])).collect();
+            let roles = bundle.users.iter().filter(|user| user.org_sourced_id.is_some()).map(|user| row(ROLES_HEADERS, &[
+                ("sourcedId", format!("{}-role", user.sourced_id)),
+                ("userSourcedId", user.sourced_id.clone()),
+                ("roleType", "primary".to_string()),
+                ("role", user.role.clone()),
+                ("orgSourcedId", user.org_sourced_id.clone().unwrap_or_default()),
+            ])).collect();
+            let demographics = bundle.users.iter().filter(|user| user.birth_date.is_some() || user.sex.is_some()).map(|user| row(DEMOGRAPHICS_HEADERS, &[
+                ("sourcedId", user.sourced_id.clone()),
+                ("birthDate", date_text(user.birth_date)),
+                ("sex", user.sex.clone().unwrap_or_default()),
+            ])).collect();
+            let enrollments = bundle.enrollments.iter().map(|enrollment| row(ENROLLMENTS_HEADERS, &[
+                ("sourcedId", enrollment.sourced_id.clone()),
+                ("classSourcedId", enrollment.class_sourced_id.clone()),
+                ("schoolSourcedId", enrollment.school_sourced_id.clone()),
+                ("userSourcedId", enrollment.user_sourced_id.clone()),
+                ("role", enrollment.role.clone()),
+                ("primary", bool_text(enrollment.primary)),
+                ("beginDate", date_text(enrollment.begin_date)),
+                ("endDate", date_text(enrollment.end_date)),
+            ])).collect();
+
+            let mut manifest = vec![
+                vec!["manifest.version".to_string(), "1.0".to_string()],
+                vec!["oneroster.version".to_string(), "1.2".to_string()],
+            ];
+            for file in MANIFEST_FILES {
+                let mode = if EXPORTED_FILES.contains(file) { "bulk" } else { "absent" };
+                manifest.push(vec![format!("file.{}", file), mode.to_string()]);
+            }
+            manifest.push(vec!["source.systemName".to_string(), SOURCE_SYSTEM.to_string()]);
+            manifest.push(vec!["source.systemCode".to_string(), String::new()]);
+
+            let files = [
+                ("manifest.csv", write_csv(&["propertyName", "value"], manifest)?),
+                ("academicSessions.csv", write_csv(ACADEMIC_SESSIONS_HEADERS, academic_sessions)?),
+                ("classes.csv", write_csv(CLASSES_HEADERS, classes)?),
+                ("courses.csv", write_csv(COURSES_HEADERS, courses)?),
+                ("demographics.csv", write_csv(DEMOGRAPHICS_HEADERS, demographics)?),
+                ("enrollments.csv", write_csv(ENROLLMENTS_HEADERS, enrollments)?),
+                ("orgs.csv", write_csv(ORGS_HEADERS, orgs)?),
+                ("roles.csv", write_csv(ROLES_HEADERS, roles)?),
+                ("users.csv", write_csv(USERS_HEADERS, users)?),
+            ];
+            zip_archive(&files, generated_at)
+        }
+
+        pub fn build_export_bundle(bundle: &RosterBundle, generated_at: DateTime<Utc>) -> Result<RosterExportBundle, String> {
+            let archive = write_bundle(bundle, generated_at)?;
+            Ok(RosterExportBundle {
+                filename: format!("oneroster_{}.zip", generated_at.format("%Y%m%d")),
+                archive_base64: STANDARD.encode(archive),
+            })
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::app::models::oneroster::{ROLE_STUDENT, ROLE_TEACHER};
+            use crate::app::models::student::{GenderEnum, GradeEnum};
+
+            fn date(month: u32, day: u32) -> NaiveDate {
+                NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+            }
+
+            fn sample_bundle() -> RosterBundle {
+                let user = |sourced_id: &str, role: &str, given: &str, family: &str| RosterUser {
+                    sourced_id: sourced_id.to_string(),
+                    enabled: true,
+                    username: sourced_id.to_string(),
+                    identifier: None,
+                    given_name: given.to_string(),
+                    family_name: family.to_string(),
+                    preferred_given_name: None,
+                    email: None,
+                    grades: Vec::new(),
+                    role: role.to_string(),
+                    org_sourced_id: Some("org-1".to_string()),
+                    birth_date: None,
+                    sex: None,
+                };
+                RosterBundle {
+                    source_system: Some(SOURCE_SYSTEM.to_string()),
+                    orgs: vec![RosterOrg {
+                        sourced_id: "org-1".to_string(),
+                        name: "Lincoln Elementary".to_string(),
+                        org_type: "school".to_string(),
+                        identifier: None,
+                        parent_sourced_id: None,
+                    }],
+                    academic_sessions: vec![RosterAcademicSession {
+                        sourced_id: "sy-2025".to_string(),
+                        title: "2024-2025".to_string(),
+                        session_type: "schoolYear".to_string(),
+                        start_date: NaiveDate::from_ymd_opt(2024, 8, 1).unwrap(),
+                        end_date: date(6, 30),
+                        parent_sourced_id: None,
+                        school_year: 2025,
+                    }],
+                    courses: vec![RosterCourse {
+                        sourced_id: "course-1".to_string(),
+                        title: "Reading, Grade 1".to_string(),
+                        course_code: Some("RD1".to_string()),
+                        school_year_sourced_id: Some("sy-2025".to_string()),
+                        org_sourced_id: "org-1".to_string(),
+                        grades: vec!["01".to_string()],
+                        subjects: vec!["Reading".to_string()],
+                    }],
+                    classes: vec![RosterClass {
+                        sourced_id: "class-1".to_string(),
+                        title: "Reading 1A".to_string(),
+                        course_sourced_id: "course-1".to_string(),
+                        class_code: Some("RD1-A".to_string()),
+                        class_type: "scheduled".to_string(),
+                        location: Some("104".to_string()),
+                        school_sourced_id: "org-1".to_string(),
+                        term_sourced_ids: vec!["sy-2025".to_string()],
+                        grades: vec!["01".to_string(), "02".to_string()],
+                        subjects: vec!["Reading".to_string()],
+                    }],
+                    users: vec![
+                        RosterUser {
+                            identifier: Some("52884".to_string()),
+                            preferred_given_name: Some("Tee".to_string()),
+                            grades: vec!["01".to_string()],
+                            birth_date: Some(NaiveDate::from_ymd_opt(2018, 3, 9).unwrap()),
+                            sex: Some("male".to_string()),
+                            ..user("stu-1", ROLE_STUDENT, "Thi\u{ea}n", "L\u{ea}")
+                        },
+                        RosterUser {
+                            email: Some("ruiz@example.org".to_string()),
+                            ..user("tch-1", ROLE_TEACHER, "Ana", "Ruiz")
+                        },
+                    ],
+                    enrollments: vec![
+                        RosterEnrollment {
+                            sourced_id: "enr-1".to_string(),
+                            class_sourced_id: "class-1".to_string(),
+                            school_sourced_id: "org-1".to_string(),
+                            user_sourced_id: "stu-1".to_string(),
+                            role: ROLE_STUDENT.to_string(),
+                            primary: false,
+                            begin_date: Some(date(1, 6)),
+                            end_date: None,
+                        },
+                        RosterEnrollment {
+                            sourced_id: "enr-2".to_string(),
+                            class_sourced_id: "class-1".to_string(),
+                            school_sourced_id: "org-1".to_string(),
+                            user_sourced_id: "tch-1".to_string(),
+                            role: ROLE_TEACHER.to_string(),
+                            primary: true,
+                            begin_date: None,
+                            end_date: None,
+                        },
+                    ],
+                }
+            }
+
+            #[test]
+            fn bundles_round_trip() {
+                let bundle = sample_bundle();
+                let archive = write_bundle(&bundle, Utc::now()).unwrap();
+                let read = read_bundle(&archive).unwrap();
+                assert_eq!(read, bundle);
+
+                let student = &read.users[0];
+                assert_eq!(student.student_id(), Some(52884));
+                assert_eq!(student.grade(), Some(GradeEnum::First));
+                assert_eq!(student.gender(), Some(GenderEnum::Male));
+            }
+
+            #[test]
+            fn sis_exports_without_roles_or_manifest_still_read() {
+                let users = "\u{feff}sourcedId,status,dateLastModified,enabledUser,username,givenName,familyName,identifier,grades\n\
+                             stu-1,,,true,tle,Thien,Le,52884,\"01,02\"\n\
+                             stu-2,tobedeleted,,true,gone,Old,Record,1,01\n\
+                             tch-1,,,true,aruiz,Ana,Ruiz,,\n";
+                let enrollments = "sourcedId,classSourcedId,schoolSourcedId,userSourcedId,role,primary,beginDate,endDate\n\
+                                   e1,class-1,org-1,stu-1,student,false,2024-08-20,\n\
+                                   e2,class-1,org-1,tch-1,teacher,true,,\n";
+                let classes = "sourcedId,title,courseSourcedId,schoolSourcedId,termSourcedIds\nclass-1,Reading 1A,course-1,org-1,sy-2025\n";
+                let archive = zip_archive(
+                    &[
+                        ("export/users.csv", users.as_bytes().to_vec()),
+                        ("export/enrollments.csv", enrollments.as_bytes().to_vec()),
+                        ("export/classes.csv", classes.as_bytes().to_vec()),
+                    ],
+                    Utc::now(),
+                )
+                .unwrap();
+
+                let bundle = read_bundle(&archive).unwrap();
+                assert_eq!(bundle.users.len(), 2);
+                assert!(bundle.users[0].is_student());
+                assert_eq!(bundle.users[0].grades, vec!["01", "02"]);
+                assert!(bundle.users[1].is_teacher());
+                assert_eq!(bundle.enrollments[0].begin_date, NaiveDate::from_ymd_opt(2024, 8, 20));
+            }
+
+            #[test]
+            fn delta_and_incomplete_bundles_are_rejected() {
+                let manifest = "propertyName,value\nfile.users,delta\nfile.orgs,bulk\n";
+                let archive = zip_archive(
+                    &[
+                        ("manifest.csv", manifest.as_bytes().to_vec()),
+                        ("users.csv", b"sourcedId,givenName,familyName\n".to_vec()),
+                    ],
+                    Utc::now(),
+                )
+                .unwrap();
+                let error = read_bundle(&archive).unwrap_err();
+                assert!(error.contains("users.csv is a delta file"));
+                assert!(error.contains("manifest lists orgs.csv"));
+                assert!(error.contains("no classes.csv"));
+
+                let enrollments = "sourcedId,classSourcedId,schoolSourcedId,userSourcedId,role,beginDate\ne1,c1,o1,u1,student,08/20/2024\n";
+                let archive = zip_archive(
+                    &[
+                        ("users.csv", b"sourcedId,givenName,familyName\nu1,Ana,\n".to_vec()),
+                        ("classes.csv", b"sourcedId,title,courseSourcedId,schoolSourcedId\n".to_vec()),
+                        ("enrollments.csv", enrollments.as_bytes().to_vec()),
+                    ],
+                    Utc::now(),
+                )
+                .unwrap();
+                let error = read_bundle(&archive).unwrap_err();
+                assert!(error.contains("users.csv line 2: familyName is required"));
+                assert!(error.contains("enrollments.csv line 2: beginDate '08/20/2024'"));
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+// Daily purge of students past their retention period. It only runs while
+// the retention_purge_enabled global setting is on, so policies can be
+// previewed before anything is deleted.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::db::{global_database, retention_database};
+        use sqlx::PgPool;
+        use std::time::Duration;
+
+        const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+        // Let the server finish starting before the first run
+        const FIRST_RUN_DELAY: Duration = Duration::from_secs(10 * 60);
+
+        // Recorded as the actor of scheduled purges in the audit log
+        pub const SCHEDULED_PURGE_ACTOR: &str = "retention-schedule";
+
+        pub async fn run_retention_schedule(pool: PgPool) {
+            actix_web::rt::time::sleep(FIRST_RUN_DELAY).await;
+            loop {
+                match global_database::get_all_global_settings(&pool).await {
+                    Ok(settings) if settings.retention_purge_enabled => {
+                        match retention_database::purge_expired_students(&pool, None, SCHEDULED_PURGE_ACTOR).await {
+                            Ok(report) => log::info!("Scheduled retention purge: {}", report.summary()),
+                            Err(e) => log::error!("Scheduled retention purge failed: {}", e),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::error!("Failed to load settings for retention purge: {}", e),
+                }
+                actix_web::rt::time::sleep(PURGE_INTERVAL).await;
+            }
+        }
+    }
+}
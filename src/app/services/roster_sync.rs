@@ -0,0 +1,374 @@
+// Diffs a student or enrollment CSV feed against the database for a sync.
+// Nothing here touches the database; roster_sync_database applies the
+// result.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::enrollment::{AcademicYear, Enrollment, EnrollmentStatus};
+        use crate::app::models::roster_sync::{
+            EnrollmentSyncChange, EnrollmentSyncDiff, FieldChange, StudentSyncChange, StudentSyncDiff, SyncWithdrawal, ENROLLMENT_GRADE_FIELD,
+            ENROLLMENT_TEACHER_FIELD, STUDENT_GRADE_FIELD, STUDENT_TEACHER_FIELD,
+        };
+        use crate::app::models::student::{AddStudentRequest, GradeEnum, Student};
+        use crate::app::models::student_attribute::AttributeValues;
+        use serde::Serialize;
+        use sha2::{Digest, Sha256};
+        use std::collections::{BTreeMap, HashMap, HashSet};
+
+        // An enrollment as the sync sees it
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct SyncEnrollment {
+            pub id: i32,
+            pub student_id: i32,
+            pub academic_year: AcademicYear,
+            pub grade_level: GradeEnum,
+            pub teacher_id: i32,
+            pub status: EnrollmentStatus,
+            pub notes: Option<String>,
+            pub course_id: Option<i32>,
+        }
+
+        fn push_change(changes: &mut Vec<FieldChange>, field: &str, before: String, after: String) {
+            if before != after {
+                changes.push(FieldChange { field: field.to_string(), before, after });
+            }
+        }
+
+        fn student_changes(current: &Student, attributes: Option<&AttributeValues>, feed: &AddStudentRequest, attribute_keys: &[String]) -> Vec<FieldChange> {
+            let mut changes = Vec::new();
+            push_change(&mut changes, "firstname", current.firstname.clone().unwrap_or_default(), feed.firstname.clone());
+            push_change(&mut changes, "lastname", current.lastname.clone().unwrap_or_default(), feed.lastname.clone());
+            push_change(&mut changes, "preferred", current.preferred.clone(), feed.preferred.clone());
+            push_change(&mut changes, "gender", current.gender.to_string(), feed.gender.to_string());
+            push_change(&mut changes, "date_of_birth", current.date_of_birth.to_string(), feed.date_of_birth.to_string());
+            push_change(&mut changes, "esl", current.esl.to_string(), feed.esl.to_string());
+            push_change(&mut changes, STUDENT_GRADE_FIELD, current.current_grade_level.to_string(), feed.current_grade_level.to_string());
+            push_change(&mut changes, STUDENT_TEACHER_FIELD, current.teacher.clone(), feed.teacher.clone());
+            push_change(&mut changes, "iep", current.iep.to_string(), feed.iep.to_string());
+            push_change(&mut changes, "bip", current.bip.to_string(), feed.bip.to_string());
+            push_change(&mut changes, "student_504", current.student_504.to_string(), feed.student_504.to_string());
+            push_change(&mut changes, "readplan", current.readplan.to_string(), feed.readplan.to_string());
+            push_change(&mut changes, "gt", current.gt.to_string(), feed.gt.to_string());
+            let intervention = |value: &Option<_>| value.as_ref().map_or_else(|| "None".to_string(), ToString::to_string);
+            push_change(&mut changes, "intervention", intervention(&current.intervention), intervention(&feed.intervention));
+            push_change(&mut changes, "eye_glasses", current.eye_glasses.to_string(), feed.eye_glasses.to_string());
+            push_change(&mut changes, "notes", current.notes.clone(), feed.notes.clone());
+            push_change(&mut changes, "pin", current.pin.map(|pin| pin.to_string()).unwrap_or_default(), feed.pin.to_string());
+            for key in attribute_keys {
+                let before = attributes.and_then(|values| values.get(key)).cloned().unwrap_or_default();
+                let after = feed.custom_attributes.get(key).cloned().unwrap_or_default();
+                push_change(&mut changes, key, before, after);
+            }
+            changes
+        }
+
+        // The student row a sync writes for a feed row
+        pub fn student_from_request(request: &AddStudentRequest) -> Student {
+            Student::new(
+                Some(request.firstname.clone()),
+                Some(request.lastname.clone()),
+                request.preferred.clone(),
+                request.gender.clone(),
+                request.date_of_birth,
+                request.student_id,
+                request.esl.clone(),
+                request.current_grade_level.clone(),
+                request.teacher.clone(),
+                request.iep,
+                request.bip,
+                request.student_504,
+                request.readplan,
+                request.gt,
+                request.intervention.clone(),
+                request.eye_glasses,
+                request.notes.clone(),
+                Some(request.pin),
+            )
+        }
+
+        // Groups the enrollments to close by student, in student ID order
+        fn withdrawals<'a>(enrollments: impl Iterator<Item = &'a SyncEnrollment>, students: &HashMap<i32, &Student>) -> Vec<SyncWithdrawal> {
+            let mut by_student: BTreeMap<i32, SyncWithdrawal> = BTreeMap::new();
+            for enrollment in enrollments {
+                let student = students.get(&enrollment.student_id);
+                let withdrawal = by_student.entry(enrollment.student_id).or_insert_with(|| SyncWithdrawal {
+                    student_id: enrollment.student_id,
+                    firstname: student.and_then(|student| student.firstname.clone()),
+                    lastname: student.and_then(|student| student.lastname.clone()),
+                    enrollment_ids: Vec::new(),
+                    academic_years: Vec::new(),
+                });
+                withdrawal.enrollment_ids.push(enrollment.id);
+                if !withdrawal.academic_years.contains(&enrollment.academic_year) {
+                    withdrawal.academic_years.push(enrollment.academic_year.clone());
+                }
+            }
+            by_student.into_values().collect()
+        }
+
+        // Hash of a diff with its fingerprint blank
+        pub fn fingerprint<T: Serialize>(diff: &T) -> String {
+            let json = serde_json::to_vec(diff).unwrap_or_default();
+            Sha256::digest(&json).iter().map(|b| format!("{:02x}", b)).collect()
+        }
+
+        // New students, changed fields (custom attributes in `attribute_keys`
+        // included), and students with an active enrollment the feed leaves
+        // out. A student ID appearing twice in the feed is an error.
+        pub fn diff_students(
+            feed: &[AddStudentRequest],
+            attribute_keys: &[String],
+            students: &[Student],
+            attributes: &HashMap<i32, AttributeValues>,
+            enrollments: &[SyncEnrollment],
+        ) -> Result<StudentSyncDiff, String> {
+            let mut seen = HashSet::new();
+            let duplicates: Vec<String> = feed
+                .iter()
+                .filter(|student| !seen.insert(student.student_id))
+                .map(|student| student.student_id.to_string())
+                .collect();
+            if !duplicates.is_empty() {
+                return Err(format!("Student IDs appear more than once in the file: {}", duplicates.join(", ")));
+            }
+
+            let current: HashMap<i32, &Student> = students.iter().map(|student| (student.student_id, student)).collect();
+            let mut diff = StudentSyncDiff::default();
+            for student in feed {
+                match current.get(&student.student_id) {
+                    None => diff.new_students.push(student.clone()),
+                    Some(existing) => {
+                        let changes = student_changes(existing, attributes.get(&student.student_id), student, attribute_keys);
+                        if changes.is_empty() {
+                            diff.unchanged += 1;
+                        } else {
+                            diff.changed.push(StudentSyncChange {
+                                student_id: student.student_id,
+                                firstname: existing.firstname.clone(),
+                                lastname: existing.lastname.clone(),
+                                changes,
+                            });
+                        }
+                    }
+                }
+            }
+            diff.withdrawn = withdrawals(
+                enrollments
+                    .iter()
+                    .filter(|enrollment| enrollment.status == EnrollmentStatus::Active && !seen.contains(&enrollment.student_id)),
+                &current,
+            );
+            diff.fingerprint = fingerprint(&diff);
+            Ok(diff)
+        }
+
+        // New enrollments, changes to the matching enrollment outside any
+        // course (preferring an active one), and active enrollments in the
+        // feed's academic years that it leaves out. Unknown students and
+        // teachers and repeated student/year pairs are errors.
+        pub fn diff_enrollments(
+            feed: &[Enrollment],
+            students: &[Student],
+            teacher_ids: &HashSet<i32>,
+            enrollments: &[SyncEnrollment],
+        ) -> Result<EnrollmentSyncDiff, String> {
+            let current: HashMap<i32, &Student> = students.iter().map(|student| (student.student_id, student)).collect();
+            let mut errors = Vec::new();
+            let mut seen = HashSet::new();
+            for enrollment in feed {
+                let label = format!("Student {} ({})", enrollment.student_id, enrollment.academic_year);
+                if !seen.insert((enrollment.student_id, enrollment.academic_year.clone())) {
+                    errors.push(format!("{}: appears more than once in the file", label));
+                }
+                if !current.contains_key(&enrollment.student_id) {
+                    errors.push(format!("{}: no student has this ID", label));
+                }
+                if !teacher_ids.contains(&enrollment.teacher_id) {
+                    errors.push(format!("{}: teacher ID {} does not exist or is not a teacher", label, enrollment.teacher_id));
+                }
+            }
+            if !errors.is_empty() {
+                return Err(errors.join("\n"));
+            }
+
+            // The enrollment each student/year pair syncs onto
+            let mut matches: HashMap<(i32, AcademicYear), &SyncEnrollment> = HashMap::new();
+            for enrollment in enrollments.iter().filter(|enrollment| enrollment.course_id.is_none()) {
+                let key = (enrollment.student_id, enrollment.academic_year.clone());
+                let preferred = |e: &SyncEnrollment| (e.status == EnrollmentStatus::Active, e.id);
+                match matches.get(&key) {
+                    Some(existing) if preferred(existing) >= preferred(enrollment) => {}
+                    _ => {
+                        matches.insert(key, enrollment);
+                    }
+                }
+            }
+
+            let mut diff = EnrollmentSyncDiff::default();
+            for enrollment in feed {
+                match matches.get(&(enrollment.student_id, enrollment.academic_year.clone())) {
+                    None => diff.new_enrollments.push(enrollment.clone()),
+                    Some(existing) => {
+                        let mut changes = Vec::new();
+                        push_change(&mut changes, ENROLLMENT_GRADE_FIELD, existing.grade_level.to_string(), enrollment.grade_level.to_string());
+                        push_change(&mut changes, ENROLLMENT_TEACHER_FIELD, existing.teacher_id.to_string(), enrollment.teacher_id.to_string());
+                        push_change(&mut changes, "status", existing.status.to_string(), EnrollmentStatus::Active.to_string());
+                        if let Some(notes) = &enrollment.notes {
+                            push_change(&mut changes, "notes", existing.notes.clone().unwrap_or_default(), notes.clone());
+                        }
+                        if changes.is_empty() {
+                            diff.unchanged += 1;
+                        } else {
+                            diff.changed.push(EnrollmentSyncChange {
+                                enrollment_id: existing.id,
+                                student_id: enrollment.student_id,
+                                academic_year: enrollment.academic_year.clone(),
+                                changes,
+                            });
+                        }
+                    }
+                }
+            }
+            let years: HashSet<&AcademicYear> = feed.iter().map(|enrollment| &enrollment.academic_year).collect();
+            diff.withdrawn = withdrawals(
+                enrollments.iter().filter(|enrollment| {
+                    enrollment.course_id.is_none()
+                        && enrollment.status == EnrollmentStatus::Active
+                        && years.contains(&enrollment.academic_year)
+                        && !seen.contains(&(enrollment.student_id, enrollment.academic_year.clone()))
+                }),
+                &current,
+            );
+            diff.fingerprint = fingerprint(&diff);
+            Ok(diff)
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::app::models::student::{ESLEnum, GenderEnum};
+            use chrono::NaiveDate;
+
+            fn student(student_id: i32, grade: GradeEnum, teacher: &str) -> Student {
+                Student::new(
+                    Some("Ana".to_string()),
+                    Some("Diaz".to_string()),
+                    "Ana".to_string(),
+                    GenderEnum::Female,
+                    NaiveDate::from_ymd_opt(2015, 3, 4).unwrap(),
+                    student_id,
+                    ESLEnum::NotApplicable,
+                    grade,
+                    teacher.to_string(),
+                    false,
+                    false,
+                    false,
+                    false,
+                    false,
+                    None,
+                    false,
+                    String::new(),
+                    Some(1234),
+                )
+            }
+
+            fn request(student: &Student) -> AddStudentRequest {
+                AddStudentRequest::new(
+                    student.firstname.clone().unwrap_or_default(),
+                    student.lastname.clone().unwrap_or_default(),
+                    student.preferred.clone(),
+                    student.gender.clone(),
+                    student.date_of_birth,
+                    student.student_id,
+                    student.esl.clone(),
+                    student.current_grade_level.clone(),
+                    student.teacher.clone(),
+                    student.iep,
+                    student.bip,
+                    student.student_504,
+                    student.readplan,
+                    student.gt,
+                    student.intervention.clone(),
+                    student.eye_glasses,
+                    student.notes.clone(),
+                    student.pin.unwrap_or_default(),
+                )
+            }
+
+            fn enrollment(id: i32, student_id: i32, status: EnrollmentStatus, course_id: Option<i32>) -> SyncEnrollment {
+                SyncEnrollment {
+                    id,
+                    student_id,
+                    academic_year: AcademicYear::Year2024_2025,
+                    grade_level: GradeEnum::Third,
+                    teacher_id: 7,
+                    status,
+                    notes: None,
+                    course_id,
+                }
+            }
+
+            #[test]
+            fn student_feeds_diff_into_new_changed_and_withdrawn() {
+                let stored = vec![student(1, GradeEnum::Third, "Smith"), student(2, GradeEnum::Third, "Smith"), student(3, GradeEnum::Third, "Jones")];
+                let mut moved = request(&stored[0]);
+                moved.current_grade_level = GradeEnum::Fourth;
+                moved.teacher = "Lee".to_string();
+                let feed = vec![moved, request(&stored[1]), request(&student(4, GradeEnum::First, "Lee"))];
+                let enrollments = vec![
+                    enrollment(10, 3, EnrollmentStatus::Active, None),
+                    enrollment(11, 3, EnrollmentStatus::Active, Some(5)),
+                    enrollment(12, 2, EnrollmentStatus::Active, None),
+                    enrollment(13, 3, EnrollmentStatus::Graduated, None),
+                ];
+
+                let diff = diff_students(&feed, &[], &stored, &HashMap::new(), &enrollments).unwrap();
+                assert_eq!(diff.new_students.len(), 1);
+                assert_eq!(diff.new_students[0].student_id, 4);
+                assert_eq!(diff.changed.len(), 1);
+                assert_eq!(diff.changed[0].changes.len(), 2);
+                assert_eq!((diff.grade_changes(), diff.teacher_changes()), (1, 1));
+                assert_eq!(diff.unchanged, 1);
+                assert_eq!(diff.withdrawn.len(), 1);
+                assert_eq!(diff.withdrawn[0].student_id, 3);
+                assert_eq!(diff.withdrawn[0].enrollment_ids, vec![10, 11]);
+
+                // The same inputs give the same fingerprint; any change gives another
+                let again = diff_students(&feed, &[], &stored, &HashMap::new(), &enrollments).unwrap();
+                assert_eq!(diff.fingerprint, again.fingerprint);
+                let fewer = diff_students(&feed[..2], &[], &stored, &HashMap::new(), &enrollments).unwrap();
+                assert_ne!(diff.fingerprint, fewer.fingerprint);
+
+                let repeated = vec![request(&stored[0]), request(&stored[0])];
+                assert!(diff_students(&repeated, &[], &stored, &HashMap::new(), &enrollments).is_err());
+            }
+
+            #[test]
+            fn enrollment_feeds_sync_onto_enrollments_outside_courses() {
+                let stored = vec![student(1, GradeEnum::Third, "Smith"), student(2, GradeEnum::Third, "Smith"), student(3, GradeEnum::Third, "Smith")];
+                let teachers = HashSet::from([7, 8]);
+                let enrollments = vec![
+                    enrollment(10, 1, EnrollmentStatus::Dropped, None),
+                    enrollment(11, 1, EnrollmentStatus::Active, None),
+                    enrollment(12, 2, EnrollmentStatus::Active, None),
+                    enrollment(13, 2, EnrollmentStatus::Active, Some(5)),
+                ];
+                let today = NaiveDate::from_ymd_opt(2024, 9, 1).unwrap();
+                let row = |student_id: i32, teacher_id: i32| {
+                    Enrollment::new(student_id, AcademicYear::Year2024_2025, GradeEnum::Third, teacher_id, EnrollmentStatus::Active, today, Some(today), None)
+                };
+
+                let diff = diff_enrollments(&[row(1, 8), row(3, 7)], &stored, &teachers, &enrollments).unwrap();
+                assert_eq!(diff.new_enrollments.len(), 1);
+                assert_eq!(diff.changed.len(), 1);
+                assert_eq!(diff.changed[0].enrollment_id, 11);
+                assert_eq!(diff.teacher_changes(), 1);
+                assert_eq!(diff.withdrawn.len(), 1);
+                assert_eq!(diff.withdrawn[0].enrollment_ids, vec![12]);
+
+                let error = diff_enrollments(&[row(9, 7), row(1, 99)], &stored, &teachers, &enrollments).unwrap_err();
+                assert!(error.contains("Student 9 (2024-2025): no student has this ID"));
+                assert!(error.contains("teacher ID 99"));
+            }
+        }
+    }
+}
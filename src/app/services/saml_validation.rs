@@ -0,0 +1,732 @@
+// Validation of SAML responses posted to the ACS endpoint. Nothing from a
+// response may be trusted (NameID, attributes, roles) until it has passed
+// validate_saml_response: the XML signature must verify against the IdP
+// certificate stored in SamlConfig.x509_cert and the assertion conditions must
+// hold for this service provider at the current time.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use base64::{engine::general_purpose, Engine as _};
+        use chrono::{DateTime, Duration, Utc};
+        use roxmltree::{Document, Node, NodeId};
+        use rsa::pkcs1v15::{Signature, VerifyingKey};
+        use rsa::pkcs8::DecodePublicKey;
+        use rsa::signature::Verifier;
+        use rsa::RsaPublicKey;
+        use sha1::Sha1;
+        use sha2::{Digest, Sha256};
+        use std::collections::{BTreeSet, HashMap};
+        use thiserror::Error;
+        use x509_cert::der::{Decode, Encode};
+        use x509_cert::Certificate;
+
+        const SAMLP_NS: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+        const SAML_NS: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+        const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+        const EXC_C14N: &str = "http://www.w3.org/2001/10/xml-exc-c14n#";
+        const EXC_C14N_WITH_COMMENTS: &str = "http://www.w3.org/2001/10/xml-exc-c14n#WithComments";
+        const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+        const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+        const RSA_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#rsa-sha1";
+        const DIGEST_SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+        const DIGEST_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#sha1";
+        const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+        const BEARER: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+
+        // Tolerated difference between our clock and the IdP's
+        pub const CLOCK_SKEW_SECONDS: i64 = 180;
+
+        #[derive(Error, Debug, Clone, PartialEq, Eq)]
+        pub enum SamlValidationError {
+            #[error("Malformed SAML response: {0}")]
+            Malformed(String),
+            #[error("Invalid IdP certificate: {0}")]
+            InvalidCertificate(String),
+            #[error("SAML response is not signed")]
+            Unsigned,
+            #[error("Unsupported signature algorithm: {0}")]
+            UnsupportedAlgorithm(String),
+            #[error("Signature reference does not point at the signed element")]
+            ReferenceMismatch,
+            #[error("Digest of signed content does not match")]
+            DigestMismatch,
+            #[error("Signature does not verify against the IdP certificate")]
+            BadSignature,
+            #[error("SAML status was not Success: {0}")]
+            StatusNotSuccess(String),
+            #[error("Unexpected issuer: {0}")]
+            IssuerMismatch(String),
+            #[error("Unexpected destination: {0}")]
+            DestinationMismatch(String),
+            #[error("Assertion is not valid before {0}")]
+            NotYetValid(DateTime<Utc>),
+            #[error("Assertion expired at {0}")]
+            Expired(DateTime<Utc>),
+            #[error("Assertion is not intended for this service provider")]
+            AudienceMismatch,
+            #[error("Subject confirmation failed: {0}")]
+            SubjectConfirmation(String),
+            #[error("Response does not answer one of our requests")]
+            InResponseToMismatch,
+            #[error("Assertion {0} has already been used")]
+            Replayed(String),
+            #[error("Encrypted assertions are not supported")]
+            EncryptedAssertion,
+        }
+
+        // What the response must match for this service provider
+        pub struct SamlValidationContext<'a> {
+            pub idp_certificate: &'a str,
+            pub idp_entity_id: &'a str,
+            pub sp_entity_id: &'a str,
+            pub acs_url: &'a str,
+            pub now: DateTime<Utc>,
+        }
+
+        // Facts about a validated assertion the caller still has to act on:
+        // InResponseTo must be matched against an outstanding AuthnRequest and
+        // the assertion ID recorded in the replay cache until it expires.
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct ValidatedAssertion {
+            pub assertion_id: String,
+            pub in_response_to: Option<String>,
+            pub expires_at: DateTime<Utc>,
+        }
+
+        pub fn validate_saml_response(
+            saml_xml: &str,
+            context: &SamlValidationContext,
+        ) -> Result<ValidatedAssertion, SamlValidationError> {
+            let doc = Document::parse(saml_xml)
+                .map_err(|e| SamlValidationError::Malformed(e.to_string()))?;
+            let response = doc.root_element();
+
+            if !is_element(response, SAMLP_NS, "Response") {
+                return Err(SamlValidationError::Malformed("root element is not samlp:Response".to_string()));
+            }
+
+            if doc.descendants().any(|n| is_element(n, SAML_NS, "EncryptedAssertion")) {
+                return Err(SamlValidationError::EncryptedAssertion);
+            }
+
+            let assertion = signed_assertion(&doc)?;
+
+            ensure_unique_ids(&doc)?;
+
+            let key = parse_idp_public_key(context.idp_certificate)?;
+            let response_signed = verify_enveloped_signature(response, &key)?;
+            let assertion_signed = verify_enveloped_signature(assertion, &key)?;
+            if !response_signed && !assertion_signed {
+                return Err(SamlValidationError::Unsigned);
+            }
+
+            check_status(response)?;
+            check_issuer(response, context)?;
+            check_issuer(assertion, context)?;
+
+            if let Some(destination) = response.attribute("Destination") {
+                if destination != context.acs_url {
+                    return Err(SamlValidationError::DestinationMismatch(destination.to_string()));
+                }
+            }
+
+            let in_response_to = response.attribute("InResponseTo").map(|s| s.to_string());
+            let skew = Duration::seconds(CLOCK_SKEW_SECONDS);
+            let mut expires_at = check_conditions(assertion, context, skew)?;
+
+            if let Some(confirmation_expiry) =
+                check_subject_confirmation(assertion, context, in_response_to.as_deref(), skew)?
+            {
+                expires_at = Some(expires_at.map_or(confirmation_expiry, |e| e.max(confirmation_expiry)));
+            }
+
+            let assertion_id = assertion
+                .attribute("ID")
+                .ok_or_else(|| SamlValidationError::Malformed("Assertion has no ID".to_string()))?
+                .to_string();
+
+            Ok(ValidatedAssertion {
+                assertion_id,
+                in_response_to,
+                // Without explicit bounds keep the ID in the replay cache for a day
+                expires_at: expires_at.unwrap_or(context.now + Duration::days(1)),
+            })
+        }
+
+        // The one SAML Assertion directly under the Response: the element
+        // validate_saml_response checks, and the only one anything may be read
+        // from. Anything else is a signature wrapping attempt or something we
+        // do not support; lookalikes in other namespaces are never matched.
+        pub fn signed_assertion<'a, 'input>(doc: &'a Document<'input>) -> Result<Node<'a, 'input>, SamlValidationError> {
+            let response = doc.root_element();
+            if !is_element(response, SAMLP_NS, "Response") {
+                return Err(SamlValidationError::Malformed("root element is not samlp:Response".to_string()));
+            }
+
+            let assertions: Vec<Node> = doc
+                .descendants()
+                .filter(|n| is_element(*n, SAML_NS, "Assertion"))
+                .collect();
+            match assertions.as_slice() {
+                [assertion] if assertion.parent_element() == Some(response) => Ok(*assertion),
+                [] => Err(SamlValidationError::Malformed("no Assertion found".to_string())),
+                _ => Err(SamlValidationError::Malformed("unexpected Assertion placement".to_string())),
+            }
+        }
+
+        // Subject/NameID of an assertion from signed_assertion
+        pub fn assertion_name_id(assertion: Node) -> Option<String> {
+            child(assertion, SAML_NS, "Subject")
+                .and_then(|subject| child(subject, SAML_NS, "NameID"))
+                .and_then(|name_id| name_id.text())
+                .map(str::to_string)
+        }
+
+        // AttributeStatement values of an assertion from signed_assertion, by
+        // attribute Name
+        pub fn assertion_attributes(assertion: Node) -> HashMap<String, Vec<String>> {
+            let mut attributes = HashMap::new();
+            for statement in assertion.children().filter(|n| is_element(*n, SAML_NS, "AttributeStatement")) {
+                for attribute in statement.children().filter(|n| is_element(*n, SAML_NS, "Attribute")) {
+                    let Some(name) = attribute.attribute("Name") else { continue };
+                    let values: Vec<String> = attribute
+                        .children()
+                        .filter(|n| is_element(*n, SAML_NS, "AttributeValue"))
+                        .filter_map(|n| n.text())
+                        .map(str::to_string)
+                        .collect();
+                    if !values.is_empty() {
+                        attributes.insert(name.to_string(), values);
+                    }
+                }
+            }
+            attributes
+        }
+
+        pub fn assertion_session_index(assertion: Node) -> Option<String> {
+            child(assertion, SAML_NS, "AuthnStatement")
+                .and_then(|statement| statement.attribute("SessionIndex"))
+                .map(str::to_string)
+        }
+
+        // Accepts a PEM certificate or the bare base64 body as pasted from IdP metadata
+        pub fn parse_idp_public_key(certificate: &str) -> Result<RsaPublicKey, SamlValidationError> {
+            let body: String = certificate
+                .lines()
+                .filter(|line| !line.contains("-----"))
+                .flat_map(|line| line.chars())
+                .filter(|c| !c.is_whitespace())
+                .collect();
+
+            let der = general_purpose::STANDARD
+                .decode(body)
+                .map_err(|e| SamlValidationError::InvalidCertificate(e.to_string()))?;
+            let cert = Certificate::from_der(&der)
+                .map_err(|e| SamlValidationError::InvalidCertificate(e.to_string()))?;
+            let spki = cert
+                .tbs_certificate
+                .subject_public_key_info
+                .to_der()
+                .map_err(|e| SamlValidationError::InvalidCertificate(e.to_string()))?;
+
+            RsaPublicKey::from_public_key_der(&spki)
+                .map_err(|e| SamlValidationError::InvalidCertificate(e.to_string()))
+        }
+
+        fn is_element(node: Node, namespace: &str, name: &str) -> bool {
+            node.is_element() && node.tag_name().namespace() == Some(namespace) && node.tag_name().name() == name
+        }
+
+        fn child<'a, 'input>(node: Node<'a, 'input>, namespace: &str, name: &str) -> Option<Node<'a, 'input>> {
+            node.children().find(|n| is_element(*n, namespace, name))
+        }
+
+        fn ensure_unique_ids(doc: &Document) -> Result<(), SamlValidationError> {
+            let mut seen = BTreeSet::new();
+            for id in doc.descendants().filter_map(|n| n.attribute("ID")) {
+                if !seen.insert(id) {
+                    return Err(SamlValidationError::Malformed(format!("duplicate ID {}", id)));
+                }
+            }
+            Ok(())
+        }
+
+        // Returns Ok(false) when the element carries no signature at all, and an
+        // error when it carries one that does not verify.
+        fn verify_enveloped_signature(element: Node, key: &RsaPublicKey) -> Result<bool, SamlValidationError> {
+            let signature = match child(element, DSIG_NS, "Signature") {
+                Some(signature) => signature,
+                None => return Ok(false),
+            };
+            let missing = |what: &str| SamlValidationError::Malformed(format!("Signature is missing {}", what));
+
+            let signed_info = child(signature, DSIG_NS, "SignedInfo").ok_or_else(|| missing("SignedInfo"))?;
+            let c14n_method = child(signed_info, DSIG_NS, "CanonicalizationMethod")
+                .ok_or_else(|| missing("CanonicalizationMethod"))?;
+            let c14n_algorithm = c14n_method.attribute("Algorithm").unwrap_or_default();
+            if c14n_algorithm != EXC_C14N && c14n_algorithm != EXC_C14N_WITH_COMMENTS {
+                return Err(SamlValidationError::UnsupportedAlgorithm(c14n_algorithm.to_string()));
+            }
+            let signature_algorithm = child(signed_info, DSIG_NS, "SignatureMethod")
+                .and_then(|n| n.attribute("Algorithm"))
+                .ok_or_else(|| missing("SignatureMethod"))?;
+
+            // One reference, and it must be the element the signature sits in
+            let references: Vec<Node> = signed_info
+                .children()
+                .filter(|n| is_element(*n, DSIG_NS, "Reference"))
+                .collect();
+            let reference = match references.as_slice() {
+                [reference] => *reference,
+                _ => return Err(SamlValidationError::ReferenceMismatch),
+            };
+            let element_id = element.attribute("ID").ok_or(SamlValidationError::ReferenceMismatch)?;
+            if reference.attribute("URI") != Some(format!("#{}", element_id).as_str()) {
+                return Err(SamlValidationError::ReferenceMismatch);
+            }
+
+            let mut reference_prefixes = Vec::new();
+            if let Some(transforms) = child(reference, DSIG_NS, "Transforms") {
+                for transform in transforms.children().filter(|n| is_element(*n, DSIG_NS, "Transform")) {
+                    match transform.attribute("Algorithm").unwrap_or_default() {
+                        ENVELOPED_SIGNATURE => {}
+                        EXC_C14N | EXC_C14N_WITH_COMMENTS => {
+                            reference_prefixes = inclusive_namespace_prefixes(transform);
+                        }
+                        other => return Err(SamlValidationError::UnsupportedAlgorithm(other.to_string())),
+                    }
+                }
+            }
+
+            let digest_algorithm = child(reference, DSIG_NS, "DigestMethod")
+                .and_then(|n| n.attribute("Algorithm"))
+                .ok_or_else(|| missing("DigestMethod"))?;
+            let expected_digest = decode_base64_text(child(reference, DSIG_NS, "DigestValue"))
+                .ok_or_else(|| missing("DigestValue"))?;
+
+            let canonical_element = canonicalize(element, Some(signature.id()), &reference_prefixes);
+            let actual_digest = match digest_algorithm {
+                DIGEST_SHA256 => Sha256::digest(canonical_element.as_bytes()).to_vec(),
+                DIGEST_SHA1 => Sha1::digest(canonical_element.as_bytes()).to_vec(),
+                other => return Err(SamlValidationError::UnsupportedAlgorithm(other.to_string())),
+            };
+            if actual_digest != expected_digest {
+                return Err(SamlValidationError::DigestMismatch);
+            }
+
+            let signature_value = decode_base64_text(child(signature, DSIG_NS, "SignatureValue"))
+                .ok_or_else(|| missing("SignatureValue"))?;
+            let signature_value = Signature::try_from(signature_value.as_slice())
+                .map_err(|_| SamlValidationError::BadSignature)?;
+            let canonical_signed_info =
+                canonicalize(signed_info, None, &inclusive_namespace_prefixes(c14n_method));
+
+            let verified = match signature_algorithm {
+                RSA_SHA256 => VerifyingKey::<Sha256>::new(key.clone())
+                    .verify(canonical_signed_info.as_bytes(), &signature_value),
+                RSA_SHA1 => VerifyingKey::<Sha1>::new(key.clone())
+                    .verify(canonical_signed_info.as_bytes(), &signature_value),
+                other => return Err(SamlValidationError::UnsupportedAlgorithm(other.to_string())),
+            };
+            verified.map_err(|_| SamlValidationError::BadSignature)?;
+
+            Ok(true)
+        }
+
+        fn decode_base64_text(node: Option<Node>) -> Option<Vec<u8>> {
+            let text: String = node?
+                .text()?
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect();
+            general_purpose::STANDARD.decode(text).ok()
+        }
+
+        fn inclusive_namespace_prefixes(method: Node) -> Vec<String> {
+            method
+                .children()
+                .find(|n| n.is_element() && n.tag_name().name() == "InclusiveNamespaces")
+                .and_then(|n| n.attribute("PrefixList"))
+                .map(|list| list.split_whitespace().map(|p| p.to_string()).collect())
+                .unwrap_or_default()
+        }
+
+        fn check_status(response: Node) -> Result<(), SamlValidationError> {
+            let status_code = child(response, SAMLP_NS, "Status")
+                .and_then(|status| child(status, SAMLP_NS, "StatusCode"))
+                .and_then(|code| code.attribute("Value"))
+                .unwrap_or_default();
+
+            if status_code != STATUS_SUCCESS {
+                return Err(SamlValidationError::StatusNotSuccess(status_code.to_string()));
+            }
+            Ok(())
+        }
+
+        fn check_issuer(element: Node, context: &SamlValidationContext) -> Result<(), SamlValidationError> {
+            if context.idp_entity_id.is_empty() {
+                return Ok(());
+            }
+            if let Some(issuer) = child(element, SAML_NS, "Issuer").and_then(|n| n.text()) {
+                if issuer.trim() != context.idp_entity_id {
+                    return Err(SamlValidationError::IssuerMismatch(issuer.trim().to_string()));
+                }
+            }
+            Ok(())
+        }
+
+        fn parse_instant(value: &str) -> Result<DateTime<Utc>, SamlValidationError> {
+            DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|e| SamlValidationError::Malformed(format!("invalid timestamp {}: {}", value, e)))
+        }
+
+        // NotBefore / NotOnOrAfter and AudienceRestriction. Returns the expiry, if any.
+        fn check_conditions(
+            assertion: Node,
+            context: &SamlValidationContext,
+            skew: Duration,
+        ) -> Result<Option<DateTime<Utc>>, SamlValidationError> {
+            let conditions = match child(assertion, SAML_NS, "Conditions") {
+                Some(conditions) => conditions,
+                None => return Err(SamlValidationError::Malformed("Assertion has no Conditions".to_string())),
+            };
+
+            if let Some(not_before) = conditions.attribute("NotBefore") {
+                let not_before = parse_instant(not_before)?;
+                if context.now + skew < not_before {
+                    return Err(SamlValidationError::NotYetValid(not_before));
+                }
+            }
+
+            let mut expires_at = None;
+            if let Some(not_on_or_after) = conditions.attribute("NotOnOrAfter") {
+                let not_on_or_after = parse_instant(not_on_or_after)?;
+                if context.now - skew >= not_on_or_after {
+                    return Err(SamlValidationError::Expired(not_on_or_after));
+                }
+                expires_at = Some(not_on_or_after);
+            }
+
+            // Every AudienceRestriction present must name us
+            let restrictions: Vec<Node> = conditions
+                .children()
+                .filter(|n| is_element(*n, SAML_NS, "AudienceRestriction"))
+                .collect();
+            if restrictions.is_empty() {
+                return Err(SamlValidationError::AudienceMismatch);
+            }
+            for restriction in restrictions {
+                let allowed = restriction
+                    .children()
+                    .filter(|n| is_element(*n, SAML_NS, "Audience"))
+                    .filter_map(|n| n.text())
+                    .any(|audience| audience.trim() == context.sp_entity_id);
+                if !allowed {
+                    return Err(SamlValidationError::AudienceMismatch);
+                }
+            }
+
+            Ok(expires_at)
+        }
+
+        // At least one bearer SubjectConfirmation must be addressed to our ACS
+        // URL, unexpired, and tied to the same request as the Response.
+        fn check_subject_confirmation(
+            assertion: Node,
+            context: &SamlValidationContext,
+            in_response_to: Option<&str>,
+            skew: Duration,
+        ) -> Result<Option<DateTime<Utc>>, SamlValidationError> {
+            let subject = child(assertion, SAML_NS, "Subject")
+                .ok_or_else(|| SamlValidationError::SubjectConfirmation("Assertion has no Subject".to_string()))?;
+
+            let mut last_error = "no bearer SubjectConfirmation".to_string();
+            for confirmation in subject.children().filter(|n| is_element(*n, SAML_NS, "SubjectConfirmation")) {
+                if confirmation.attribute("Method") != Some(BEARER) {
+                    continue;
+                }
+                let data = match child(confirmation, SAML_NS, "SubjectConfirmationData") {
+                    Some(data) => data,
+                    None => {
+                        last_error = "missing SubjectConfirmationData".to_string();
+                        continue;
+                    }
+                };
+
+                if let Some(recipient) = data.attribute("Recipient") {
+                    if recipient != context.acs_url {
+                        last_error = format!("recipient {} is not our ACS URL", recipient);
+                        continue;
+                    }
+                }
+                if data.attribute("InResponseTo") != in_response_to {
+                    last_error = "InResponseTo differs from the Response".to_string();
+                    continue;
+                }
+                let not_on_or_after = match data.attribute("NotOnOrAfter") {
+                    Some(value) => parse_instant(value)?,
+                    None => {
+                        last_error = "bearer confirmation has no NotOnOrAfter".to_string();
+                        continue;
+                    }
+                };
+                if context.now - skew >= not_on_or_after {
+                    return Err(SamlValidationError::Expired(not_on_or_after));
+                }
+
+                return Ok(Some(not_on_or_after));
+            }
+
+            Err(SamlValidationError::SubjectConfirmation(last_error))
+        }
+
+        // Exclusive XML Canonicalization 1.0 (without comments) of the subtree at
+        // `node`, skipping the `excluded` subtree (the enveloped signature).
+        // Prefixes named in an InclusiveNamespaces PrefixList are rendered as if
+        // they were visibly used.
+        pub fn canonicalize(node: Node, excluded: Option<NodeId>, inclusive_prefixes: &[String]) -> String {
+            let mut out = String::new();
+            write_canonical_element(node, excluded, inclusive_prefixes, &HashMap::new(), &mut out);
+            out
+        }
+
+        fn write_canonical_element(
+            node: Node,
+            excluded: Option<NodeId>,
+            inclusive_prefixes: &[String],
+            rendered: &HashMap<String, String>,
+            out: &mut String,
+        ) {
+            let input = node.document().input_text();
+            let qname = element_qname(node, input);
+
+            // Namespaces visibly utilized by the element and its attributes
+            let mut utilized = BTreeSet::new();
+            utilized.insert(qname.split_once(':').map(|(prefix, _)| prefix).unwrap_or("").to_string());
+            for attribute in node.attributes() {
+                if let Some((prefix, _)) = input[attribute.range_qname()].split_once(':') {
+                    if prefix != "xml" {
+                        utilized.insert(prefix.to_string());
+                    }
+                }
+            }
+            for prefix in inclusive_prefixes {
+                let prefix = if prefix == "#default" { "" } else { prefix.as_str() };
+                let in_scope = if prefix.is_empty() {
+                    node.lookup_namespace_uri(None).is_some()
+                } else {
+                    node.lookup_namespace_uri(Some(prefix)).is_some()
+                };
+                if in_scope {
+                    utilized.insert(prefix.to_string());
+                }
+            }
+
+            let mut rendered_here = rendered.clone();
+            let mut declarations = Vec::new();
+            for prefix in utilized {
+                let uri = if prefix.is_empty() {
+                    node.lookup_namespace_uri(None)
+                } else {
+                    node.lookup_namespace_uri(Some(prefix.as_str()))
+                }
+                .unwrap_or("")
+                .to_string();
+
+                let already_rendered = match rendered.get(&prefix) {
+                    Some(existing) => *existing == uri,
+                    // An empty default namespace only needs declaring to undo an outer one
+                    None => prefix.is_empty() && uri.is_empty(),
+                };
+                if !already_rendered {
+                    declarations.push((prefix.clone(), uri.clone()));
+                    rendered_here.insert(prefix, uri);
+                }
+            }
+
+            out.push('<');
+            out.push_str(qname);
+            for (prefix, uri) in &declarations {
+                if prefix.is_empty() {
+                    out.push_str(" xmlns=\"");
+                } else {
+                    out.push_str(" xmlns:");
+                    out.push_str(prefix);
+                    out.push_str("=\"");
+                }
+                escape_attribute_value(uri, out);
+                out.push('"');
+            }
+
+            let mut attributes: Vec<(&str, &str, &str, &str)> = node
+                .attributes()
+                .map(|a| (a.namespace().unwrap_or(""), a.name(), &input[a.range_qname()], a.value()))
+                .collect();
+            attributes.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+            for (_, _, attribute_qname, value) in attributes {
+                out.push(' ');
+                out.push_str(attribute_qname);
+                out.push_str("=\"");
+                escape_attribute_value(value, out);
+                out.push('"');
+            }
+            out.push('>');
+
+            for child in node.children() {
+                if Some(child.id()) == excluded {
+                    continue;
+                }
+                if child.is_element() {
+                    write_canonical_element(child, excluded, inclusive_prefixes, &rendered_here, out);
+                } else if child.is_text() {
+                    escape_text(child.text().unwrap_or_default(), out);
+                } else if let Some(pi) = child.pi() {
+                    out.push_str("<?");
+                    out.push_str(pi.target);
+                    if let Some(value) = pi.value {
+                        out.push(' ');
+                        out.push_str(value);
+                    }
+                    out.push_str("?>");
+                }
+            }
+
+            out.push_str("</");
+            out.push_str(qname);
+            out.push('>');
+        }
+
+        // The element name exactly as written, prefix included
+        fn element_qname<'input>(node: Node, input: &'input str) -> &'input str {
+            let start = node.range().start + 1;
+            let rest = &input[start..];
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .unwrap_or(rest.len());
+            &rest[..end]
+        }
+
+        fn escape_text(text: &str, out: &mut String) {
+            for c in text.chars() {
+                match c {
+                    '&' => out.push_str("&amp;"),
+                    '<' => out.push_str("&lt;"),
+                    '>' => out.push_str("&gt;"),
+                    '\r' => out.push_str("&#xD;"),
+                    _ => out.push(c),
+                }
+            }
+        }
+
+        fn escape_attribute_value(value: &str, out: &mut String) {
+            for c in value.chars() {
+                match c {
+                    '&' => out.push_str("&amp;"),
+                    '<' => out.push_str("&lt;"),
+                    '"' => out.push_str("&quot;"),
+                    '\t' => out.push_str("&#x9;"),
+                    '\n' => out.push_str("&#xA;"),
+                    '\r' => out.push_str("&#xD;"),
+                    _ => out.push(c),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    const IDP_CERT: &str = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/saml/idp_cert.pem"));
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/tests/fixtures/saml/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn context(now: &str) -> SamlValidationContext<'static> {
+        SamlValidationContext {
+            idp_certificate: IDP_CERT,
+            idp_entity_id: "https://idp.example.edu/metadata",
+            sp_entity_id: "https://sp.example.edu/saml/metadata",
+            acs_url: "https://sp.example.edu/saml/acs",
+            now: DateTime::parse_from_rfc3339(now).unwrap().with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn accepts_valid_signed_response() {
+        let validated = validate_saml_response(&fixture("valid_response.xml"), &context("2025-01-01T12:01:00Z")).unwrap();
+        assert_eq!(validated.assertion_id, "_assertion-0001");
+        assert_eq!(validated.in_response_to.as_deref(), Some("_request-0001"));
+    }
+
+    #[test]
+    fn rejects_tampered_assertion() {
+        let result = validate_saml_response(&fixture("tampered_response.xml"), &context("2025-01-01T12:01:00Z"));
+        assert!(matches!(result, Err(SamlValidationError::DigestMismatch)), "{:?}", result);
+    }
+
+    #[test]
+    fn rejects_expired_and_early_responses() {
+        let expired = validate_saml_response(&fixture("expired_response.xml"), &context("2025-01-01T12:01:00Z"));
+        assert!(matches!(expired, Err(SamlValidationError::Expired(_))), "{:?}", expired);
+
+        let early = validate_saml_response(&fixture("valid_response.xml"), &context("2025-01-01T11:50:00Z"));
+        assert!(matches!(early, Err(SamlValidationError::NotYetValid(_))), "{:?}", early);
+    }
+
+    #[test]
+    fn rejects_wrong_audience_and_destination() {
+        let audience = validate_saml_response(&fixture("wrong_audience_response.xml"), &context("2025-01-01T12:01:00Z"));
+        assert!(matches!(audience, Err(SamlValidationError::AudienceMismatch)), "{:?}", audience);
+
+        let mut ctx = context("2025-01-01T12:01:00Z");
+        ctx.acs_url = "https://attacker.example.com/saml/acs";
+        let destination = validate_saml_response(&fixture("valid_response.xml"), &ctx);
+        assert!(matches!(destination, Err(SamlValidationError::DestinationMismatch(_))), "{:?}", destination);
+    }
+
+    #[test]
+    fn rejects_stripped_signature() {
+        let xml = fixture("valid_response.xml");
+        let start = xml.find("<ds:Signature").unwrap();
+        let end = xml.find("</ds:Signature>").unwrap() + "</ds:Signature>".len();
+        let unsigned = format!("{}{}", &xml[..start], &xml[end..]);
+        let result = validate_saml_response(&unsigned, &context("2025-01-01T12:01:00Z"));
+        assert!(matches!(result, Err(SamlValidationError::Unsigned)), "{:?}", result);
+    }
+
+    #[test]
+    fn wrapped_assertions_are_never_read() {
+        let xml = fixture("valid_response.xml");
+        let status = xml.find("<samlp:Status>").unwrap();
+        let forged = r#"<evil:Assertion xmlns:evil="urn:example:evil" ID="_forged"><saml:Subject><saml:NameID>admin@example.edu</saml:NameID></saml:Subject><saml:AttributeStatement><saml:Attribute Name="eduPersonAffiliation"><saml:AttributeValue>admin</saml:AttributeValue></saml:Attribute></saml:AttributeStatement></evil:Assertion>"#;
+
+        // A lookalike in another namespace passes validation, but only the
+        // signed assertion is read
+        let wrapped = format!("{}{}{}", &xml[..status], forged, &xml[status..]);
+        validate_saml_response(&wrapped, &context("2025-01-01T12:01:00Z")).unwrap();
+        let doc = Document::parse(&wrapped).unwrap();
+        let assertion = signed_assertion(&doc).unwrap();
+        assert_eq!(assertion.attribute("ID"), Some("_assertion-0001"));
+        assert_eq!(assertion_name_id(assertion).as_deref(), Some("jdoe@example.edu"));
+        assert_eq!(
+            assertion_attributes(assertion).get("eduPersonAffiliation"),
+            Some(&vec!["faculty".to_string()])
+        );
+        assert_eq!(assertion_session_index(assertion).as_deref(), Some("_session-0001"));
+
+        // A second SAML assertion anywhere is rejected outright
+        let nested = format!(
+            "{}{}{}",
+            &xml[..status],
+            forged.replace("evil:Assertion", "saml:Assertion").replace(r#" xmlns:evil="urn:example:evil""#, ""),
+            &xml[status..]
+        );
+        let result = validate_saml_response(&nested, &context("2025-01-01T12:01:00Z"));
+        assert!(matches!(result, Err(SamlValidationError::Malformed(_))), "{:?}", result);
+        assert!(signed_assertion(&Document::parse(&nested).unwrap()).is_err());
+    }
+}
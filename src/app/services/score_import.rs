@@ -0,0 +1,383 @@
+// Historical score imports: reading each row of a score CSV or workbook and
+// checking it against the test it names, that test's questions and the
+// scores on file before anything is written.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::import_validation::{missing_columns, parse_id, ParsedRows, RowReader};
+        use crate::app::models::score_import::{HistoricalScore, ScoreImportFile, ScoreImportRow, ScorePoints};
+        use crate::app::models::{Question, Score, Test};
+        use crate::app::services::xlsx::first_sheet_as_csv;
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+        use csv::ReaderBuilder;
+        use std::collections::{BTreeMap, HashMap, HashSet};
+        use uuid::Uuid;
+
+        // Columns every score CSV needs. Points come from a total column, a
+        // q<number> column per question, or both.
+        const SCORE_COLUMNS: [&str; 3] = ["student_id", "test", "date_administered"];
+        const VARIANT_COLUMN: &str = "variant";
+        const EVALUATOR_COLUMN: &str = "evaluator";
+        const TOTAL_COLUMN: &str = "total";
+
+        // Everything the rows of a score CSV are checked against
+        pub struct ScoreImportContext<'a> {
+            pub tests: &'a [Test],
+            // Each named test's questions, by test ID
+            pub questions: &'a HashMap<String, Vec<Question>>,
+            // Students on the importer's caseload
+            pub student_ids: &'a HashSet<i32>,
+            pub existing: &'a [Score],
+            // Who scored rows that leave the evaluator blank
+            pub default_evaluator: &'a str,
+            pub today: NaiveDate,
+        }
+
+        // The qnumber a header such as q3 or Q3 stands for
+        fn question_number(header: &str) -> Option<i32> {
+            header.strip_prefix(['q', 'Q'])?.parse().ok().filter(|qnumber| *qnumber > 0)
+        }
+
+        fn parse_date(value: &str) -> Result<NaiveDate, String> {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .or_else(|_| NaiveDate::parse_from_str(value, "%m/%d/%Y"))
+                .map_err(|_| "Invalid date. Expected format: YYYY-MM-DD or MM/DD/YYYY".to_string())
+        }
+
+        fn parse_points(value: &str) -> Result<Option<i32>, String> {
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value.parse::<i32>().map(Some).map_err(|_| "Points must be a whole number".to_string())
+        }
+
+        // Imported dates have no time of day. Noon keeps them on the same
+        // date wherever they are shown.
+        fn administered_at(date: NaiveDate) -> DateTime<Utc> {
+            let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap_or_default();
+            DateTime::<Utc>::from_naive_utc_and_offset(date.and_time(noon), Utc)
+        }
+
+        // Reads one score, noting a problem for each cell that doesn't parse.
+        // Returns None if any did.
+        fn parse_score_row(reader: &mut RowReader, question_columns: &[(String, i32)]) -> Option<ScoreImportRow> {
+            let student_id = reader.parse("student_id", |v| parse_id(v, "Student ID", 0, 2000000000));
+            let test = reader.parse("test", |v| {
+                if v.is_empty() {
+                    return Err("Test cannot be empty".to_string());
+                }
+                Ok(v.to_string())
+            });
+            let variant = reader.parse(VARIANT_COLUMN, |v| {
+                if v.is_empty() {
+                    return Ok(None);
+                }
+                parse_id(v, "Variant", 0, 9999).map(Some)
+            });
+            let date_administered = reader.parse("date_administered", parse_date);
+            let evaluator = Some(reader.text(EVALUATOR_COLUMN)).filter(|v| !v.is_empty()).map(str::to_string);
+
+            let mut questions = BTreeMap::new();
+            let mut points_read = true;
+            for (column, qnumber) in question_columns {
+                match reader.parse(column, parse_points) {
+                    Some(Some(points)) => {
+                        questions.insert(*qnumber, points);
+                    }
+                    Some(None) => {}
+                    None => points_read = false,
+                }
+            }
+            let total = reader.parse(TOTAL_COLUMN, parse_points);
+            let points = match (points_read, total) {
+                (true, Some(total)) => match (questions.is_empty(), total) {
+                    (true, None) => {
+                        reader.flag("", "No points given; fill in the total or the question columns".to_string());
+                        None
+                    }
+                    (true, Some(total)) => Some(ScorePoints::Total(total)),
+                    (false, Some(total)) if questions.values().sum::<i32>() != total => {
+                        reader.flag(TOTAL_COLUMN, format!("The question points add up to {}", questions.values().sum::<i32>()));
+                        None
+                    }
+                    (false, _) => Some(ScorePoints::Questions(questions)),
+                },
+                _ => None,
+            };
+
+            Some(ScoreImportRow {
+                student_id: student_id?,
+                test: test?,
+                variant: variant?,
+                date_administered: date_administered?,
+                evaluator,
+                points: points?,
+            })
+        }
+
+        // The uploaded file as CSV text; workbooks are read from their first sheet
+        pub fn score_file_csv(file: ScoreImportFile) -> Result<String, String> {
+            match file {
+                ScoreImportFile::Csv(contents) => Ok(contents),
+                ScoreImportFile::Xlsx(workbook_base64) => {
+                    let workbook = STANDARD
+                        .decode(workbook_base64.trim())
+                        .map_err(|e| format!("The upload is not an Excel workbook: {}", e))?;
+                    first_sheet_as_csv(&workbook)
+                }
+            }
+        }
+
+        // Parses every row of a score CSV, keeping the problems with each row
+        // rather than stopping at the first
+        pub fn parse_score_rows(file_contents: &str) -> Result<ParsedRows<ScoreImportRow>, String> {
+            let mut rdr = ReaderBuilder::new()
+                .has_headers(true)
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(file_contents.as_bytes());
+
+            let headers = rdr
+                .headers()
+                .map_err(|e| format!("Failed to read CSV headers: {}", e))?
+                .clone();
+
+            let missing = missing_columns(&headers, &SCORE_COLUMNS);
+            if !missing.is_empty() {
+                return Err(format!("The CSV is missing these columns: {}", missing.join(", ")));
+            }
+            let question_columns: Vec<(String, i32)> = headers
+                .iter()
+                .filter_map(|header| question_number(header).map(|qnumber| (header.to_string(), qnumber)))
+                .collect();
+            if question_columns.is_empty() && !headers.iter().any(|header| header == TOTAL_COLUMN) {
+                return Err("The CSV needs a total column or a column per question, headed q1, q2 and so on".to_string());
+            }
+
+            let mut parsed = ParsedRows::default();
+            for (index, result) in rdr.records().enumerate() {
+                let row = index + 2; // +2 for header and 1-based indexing
+                parsed.total_rows += 1;
+                let record = match result {
+                    Ok(record) => record,
+                    Err(e) => {
+                        parsed.flag(row, "", "", format!("CSV parsing error: {}", e));
+                        continue;
+                    }
+                };
+
+                let mut reader = RowReader::new(&headers, &record, row);
+                let score = parse_score_row(&mut reader, &question_columns);
+                parsed.issues.extend(reader.finish());
+                if let Some(score) = score {
+                    parsed.rows.push((row, score));
+                }
+            }
+            Ok(parsed)
+        }
+
+        // The test a row names by ID or by name. Variations of a test are
+        // tests of their own, so a name several share needs the variant too.
+        pub fn resolve_test<'a>(test: &str, variant: Option<i32>, tests: &'a [Test]) -> Result<&'a Test, String> {
+            let test_id = Uuid::parse_str(test).ok().map(|id| id.to_string());
+            let named: Vec<&Test> = tests
+                .iter()
+                .filter(|candidate| match &test_id {
+                    Some(test_id) => candidate.test_id == *test_id,
+                    None => candidate.name.trim().eq_ignore_ascii_case(test),
+                })
+                .collect();
+            if named.is_empty() {
+                return Err("No test has this name or ID".to_string());
+            }
+
+            let matching: Vec<&Test> = named
+                .into_iter()
+                .filter(|candidate| variant.is_none_or(|variant| candidate.test_variant == variant))
+                .collect();
+            match matching.as_slice() {
+                [test] => Ok(test),
+                [] => Err(format!("The test has no variant {}", variant.unwrap_or_default())),
+                _ => Err("Several tests go by this name; give the variant or the test ID".to_string()),
+            }
+        }
+
+        // The points as the scores table keeps them: one entry per question in
+        // qnumber order, or the total alone. Problems come back as (column,
+        // value, message).
+        fn test_scores(points: &ScorePoints, test: &Test, questions: &[Question]) -> Result<Vec<i32>, Vec<(String, String, String)>> {
+            match points {
+                ScorePoints::Total(total) => {
+                    if *total < 0 || *total > test.score {
+                        return Err(vec![(TOTAL_COLUMN.to_string(), total.to_string(), format!("Must be between 0 and the test's {} points", test.score))]);
+                    }
+                    Ok(vec![*total])
+                }
+                ScorePoints::Questions(points) => {
+                    if questions.is_empty() {
+                        return Err(vec![(String::new(), String::new(), "The test has no questions on file; give a total instead".to_string())]);
+                    }
+
+                    let mut problems = Vec::new();
+                    for (qnumber, value) in points {
+                        let column = format!("q{}", qnumber);
+                        match questions.iter().find(|question| question.qnumber == *qnumber) {
+                            None => problems.push((column, value.to_string(), format!("The test has no question {}", qnumber))),
+                            Some(question) if question.point_value >= 0 && !(0..=question.point_value).contains(value) => {
+                                problems.push((column, value.to_string(), format!("Must be between 0 and the question's {} points", question.point_value)));
+                            }
+                            Some(_) => {}
+                        }
+                    }
+                    let unanswered: Vec<String> = questions
+                        .iter()
+                        .filter(|question| !points.contains_key(&question.qnumber))
+                        .map(|question| question.qnumber.to_string())
+                        .collect();
+                    if !unanswered.is_empty() {
+                        problems.push((String::new(), String::new(), format!("The test has {} questions; no points for question {}", questions.len(), unanswered.join(", "))));
+                    }
+                    if !problems.is_empty() {
+                        return Err(problems);
+                    }
+
+                    Ok(questions.iter().map(|question| points[&question.qnumber]).collect())
+                }
+            }
+        }
+
+        // Checks parsed rows against the tests, questions, caseload and scores
+        // on file, turning the rows that pass into scores ready to write.
+        // The same student, test, variant and date twice counts as a
+        // duplicate, so importing a file again adds nothing.
+        pub fn check_scores(parsed: ParsedRows<ScoreImportRow>, context: &ScoreImportContext) -> ParsedRows<HistoricalScore> {
+            let mut checked = ParsedRows { rows: Vec::new(), issues: parsed.issues, total_rows: parsed.total_rows };
+            let on_file: HashSet<(i32, &str, i32, NaiveDate)> = context
+                .existing
+                .iter()
+                .map(|score| (score.student_id, score.test_id.as_str(), score.test_variant, score.date_administered.date_naive()))
+                .collect();
+            let mut first_rows: HashMap<(i32, String, i32, NaiveDate), usize> = HashMap::new();
+
+            for (row, score) in parsed.rows {
+                let mut problems = Vec::new();
+                if !context.student_ids.contains(&score.student_id) {
+                    problems.push(("student_id".to_string(), score.student_id.to_string(), "No student with this ID is on your caseload".to_string()));
+                }
+                if score.date_administered > context.today {
+                    problems.push(("date_administered".to_string(), score.date_administered.to_string(), "The date is in the future".to_string()));
+                }
+
+                let test = match resolve_test(&score.test, score.variant, context.tests) {
+                    Ok(test) => Some(test),
+                    Err(message) => {
+                        problems.push(("test".to_string(), score.test.clone(), message));
+                        None
+                    }
+                };
+                let points = test.map(|test| {
+                    let questions = context.questions.get(&test.test_id).map(Vec::as_slice).unwrap_or_default();
+                    test_scores(&score.points, test, questions)
+                });
+
+                if let Some(test) = test {
+                    let key = (score.student_id, test.test_id.clone(), test.test_variant, score.date_administered);
+                    if on_file.contains(&(key.0, key.1.as_str(), key.2, key.3)) {
+                        problems.push((String::new(), String::new(), "A score for this student and test on this date is already on file".to_string()));
+                    } else if let Some(first_row) = first_rows.get(&key) {
+                        problems.push((String::new(), String::new(), format!("Duplicate score; also on row {}", first_row)));
+                    } else {
+                        first_rows.insert(key, row);
+                    }
+                }
+
+                match (test, points) {
+                    (Some(test), Some(Ok(test_scores))) if problems.is_empty() => {
+                        checked.rows.push((
+                            row,
+                            HistoricalScore {
+                                student_id: score.student_id,
+                                test_id: test.test_id.clone(),
+                                test_variant: test.test_variant,
+                                date_administered: administered_at(score.date_administered),
+                                comments: vec![String::new(); test_scores.len()],
+                                test_scores,
+                                evaluator: score.evaluator.unwrap_or_else(|| context.default_evaluator.to_string()),
+                            },
+                        ));
+                    }
+                    (_, Some(Err(point_problems))) => problems.extend(point_problems),
+                    _ => {}
+                }
+                for (column, value, message) in problems {
+                    checked.flag(row, &column, value, message);
+                }
+            }
+            checked
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::app::models::{QuestionType, TestType};
+
+            fn test(test_id: &str, name: &str, variant: i32, score: i32) -> Test {
+                Test::new(name.to_string(), score, None, String::new(), TestType::Reading, None, None, variant, None, test_id.to_string(), None, None)
+            }
+
+            fn question(test_id: &str, qnumber: i32, point_value: i32) -> Question {
+                Question::new(format!("Question {}", qnumber), point_value, QuestionType::Written, vec![String::new()], String::new(), qnumber, test_id.to_string())
+            }
+
+            #[test]
+            fn rows_are_checked_against_tests_and_questions() {
+                let spelling = "6f1c2b8e-2a8e-4a4c-9d0e-2f5d3c1b7a10";
+                let spelling_b = "0b6c4f0a-54a1-4b3e-8f47-1d3a5d8e9c21";
+                let reading = "9d2e7a41-8c3b-4f6e-a1d2-5b7c9e0f3a42";
+                let csv = format!(
+                    "student_id,test,variant,date_administered,evaluator,q1,q2,total\n\
+                     100,Spelling,1,2021-09-14,Ms. Paper,2,3,\n\
+                     100,Spelling,,2021-09-15,,2,3,\n\
+                     101,{},,03/02/2022,,,,15\n\
+                     101,{},,03/02/2022,,,,15\n\
+                     102,Spelling,2,2021-09-14,,2,9,\n\
+                     100,Spelling,1,2021-09-20,,2,,\n\
+                     100,Spelling,1,2021-09-21,,2,3,4\n\
+                     100,{},,2021-09-22,,1,1,\n",
+                    reading, reading, reading
+                );
+                let tests = vec![test(spelling, "Spelling", 1, 5), test(spelling_b, "Spelling", 2, 5), test(reading, "Reading", 1, 20)];
+                let questions = HashMap::from([
+                    (spelling.to_string(), vec![question(spelling, 1, 2), question(spelling, 2, 3)]),
+                    (spelling_b.to_string(), vec![question(spelling_b, 1, 2), question(spelling_b, 2, 3)]),
+                ]);
+                let student_ids = HashSet::from([100, 101]);
+                let context = ScoreImportContext {
+                    tests: &tests,
+                    questions: &questions,
+                    student_ids: &student_ids,
+                    existing: &[],
+                    default_evaluator: "7",
+                    today: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                };
+
+                let parsed = parse_score_rows(&csv).unwrap();
+                let mut checked = check_scores(parsed, &context);
+                checked.issues.sort_by_key(|issue| issue.row);
+                let flagged: Vec<(usize, &str)> = checked.issues.iter().map(|issue| (issue.row, issue.column.as_str())).collect();
+                assert_eq!(
+                    flagged,
+                    vec![(3, "test"), (5, ""), (6, "student_id"), (6, "q2"), (7, ""), (8, "total"), (9, "")]
+                );
+
+                let scores: Vec<&HistoricalScore> = checked.rows.iter().map(|(_, score)| score).collect();
+                assert_eq!(scores.len(), 2);
+                assert_eq!(scores[0].test_scores, vec![2, 3]);
+                assert_eq!(scores[0].evaluator, "Ms. Paper");
+                assert_eq!(scores[0].date_administered.date_naive(), NaiveDate::from_ymd_opt(2021, 9, 14).unwrap());
+                assert_eq!(scores[1].test_scores, vec![15]);
+                assert_eq!(scores[1].evaluator, "7");
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// Fuzzy duplicate detection. Names are sealed at rest, so matching runs
+// here over decrypted students instead of in SQL.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::student::Student;
+        use crate::app::models::student_merge::DuplicateCandidate;
+        use chrono::Datelike;
+        use std::collections::{HashMap, HashSet};
+        use strsim::jaro_winkler;
+
+        const NAME_WEIGHT: f64 = 0.6;
+        const BIRTH_DATE_WEIGHT: f64 = 0.3;
+        const PIN_WEIGHT: f64 = 0.1;
+
+        // Names this alike are called out in the reasons
+        const SIMILAR_NAME: f64 = 0.9;
+
+        fn normalize(name: Option<&str>) -> String {
+            name.unwrap_or_default()
+                .chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        }
+
+        fn similarity(a: &str, b: &str) -> f64 {
+            if a.is_empty() || b.is_empty() {
+                0.0
+            } else {
+                jaro_winkler(a, b)
+            }
+        }
+
+        // Best of the names as entered and with first and last swapped
+        fn name_score(a: &Student, b: &Student) -> (f64, bool) {
+            let (a_first, a_last) = (normalize(a.firstname.as_deref()), normalize(a.lastname.as_deref()));
+            let (b_first, b_last) = (normalize(b.firstname.as_deref()), normalize(b.lastname.as_deref()));
+            let straight = (similarity(&a_first, &b_first) + similarity(&a_last, &b_last)) / 2.0;
+            let swapped = (similarity(&a_first, &b_last) + similarity(&a_last, &b_first)) / 2.0;
+            if swapped > straight {
+                (swapped, true)
+            } else {
+                (straight, false)
+            }
+        }
+
+        // Exact match, or day and month transposed on entry
+        fn birth_date_score(a: &Student, b: &Student) -> (f64, Option<&'static str>) {
+            let (a, b) = (a.date_of_birth, b.date_of_birth);
+            if a == b {
+                (1.0, Some("Same date of birth"))
+            } else if a.year() == b.year() && a.month() == b.day() && a.day() == b.month() {
+                (0.5, Some("Date of birth with day and month swapped"))
+            } else {
+                (0.0, None)
+            }
+        }
+
+        fn usable_pin(student: &Student) -> Option<i32> {
+            student.pin.filter(|pin| *pin > 0)
+        }
+
+        pub fn score_pair(a: &Student, b: &Student) -> (f64, Vec<String>) {
+            let mut reasons = Vec::new();
+
+            let (names, swapped) = name_score(a, b);
+            if names >= SIMILAR_NAME {
+                reasons.push(if swapped { "First and last names swapped" } else { "Similar names" }.to_string());
+            }
+            let (birth_date, birth_reason) = birth_date_score(a, b);
+            if let Some(reason) = birth_reason {
+                reasons.push(reason.to_string());
+            }
+            let pin = match (usable_pin(a), usable_pin(b)) {
+                (Some(a), Some(b)) if a == b => {
+                    reasons.push("Same PIN".to_string());
+                    1.0
+                }
+                _ => 0.0,
+            };
+
+            (names * NAME_WEIGHT + birth_date * BIRTH_DATE_WEIGHT + pin * PIN_WEIGHT, reasons)
+        }
+
+        // Only students sharing a birth date, a PIN or the start of a name are
+        // compared, so large schools are not scored pair by pair
+        fn blocking_keys(student: &Student) -> Vec<String> {
+            let mut keys = vec![format!("dob:{}", student.date_of_birth)];
+            if let Some(pin) = usable_pin(student) {
+                keys.push(format!("pin:{}", pin));
+            }
+            for name in [student.firstname.as_deref(), student.lastname.as_deref()] {
+                let prefix: String = normalize(name).chars().take(2).collect();
+                if !prefix.is_empty() {
+                    keys.push(format!("name:{}", prefix));
+                }
+            }
+            keys
+        }
+
+        pub fn find_duplicates(students: &[Student], threshold: f64) -> Vec<DuplicateCandidate> {
+            let mut blocks: HashMap<String, Vec<usize>> = HashMap::new();
+            for (index, student) in students.iter().enumerate() {
+                for key in blocking_keys(student) {
+                    blocks.entry(key).or_default().push(index);
+                }
+            }
+
+            let mut compared = HashSet::new();
+            let mut candidates = Vec::new();
+            for members in blocks.values() {
+                for (i, &a) in members.iter().enumerate() {
+                    for &b in &members[i + 1..] {
+                        if !compared.insert((a.min(b), a.max(b))) {
+                            continue;
+                        }
+                        let (score, reasons) = score_pair(&students[a], &students[b]);
+                        if score >= threshold {
+                            // Lower ID first, so a pair always reads the same way
+                            let (first, second) = if students[a].student_id <= students[b].student_id { (a, b) } else { (b, a) };
+                            candidates.push(DuplicateCandidate {
+                                first: students[first].clone(),
+                                second: students[second].clone(),
+                                score,
+                                reasons,
+                            });
+                        }
+                    }
+                }
+            }
+
+            candidates.sort_by(|a, b| {
+                b.score
+                    .total_cmp(&a.score)
+                    .then(a.first.student_id.cmp(&b.first.student_id))
+                    .then(a.second.student_id.cmp(&b.second.student_id))
+            });
+            candidates
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::app::models::student::{ESLEnum, GenderEnum, GradeEnum};
+            use chrono::NaiveDate;
+
+            fn student(student_id: i32, firstname: &str, lastname: &str, date_of_birth: (i32, u32, u32), pin: i32) -> Student {
+                Student {
+                    firstname: Some(firstname.to_string()),
+                    lastname: Some(lastname.to_string()),
+                    preferred: String::new(),
+                    gender: GenderEnum::Female,
+                    date_of_birth: NaiveDate::from_ymd_opt(date_of_birth.0, date_of_birth.1, date_of_birth.2).unwrap(),
+                    student_id,
+                    esl: ESLEnum::NotApplicable,
+                    current_grade_level: GradeEnum::Second,
+                    teacher: String::new(),
+                    iep: false,
+                    bip: false,
+                    student_504: false,
+                    readplan: false,
+                    gt: false,
+                    intervention: None,
+                    eye_glasses: false,
+                    notes: String::new(),
+                    pin: Some(pin),
+                }
+            }
+
+            #[test]
+            fn finds_the_same_child_under_two_ids() {
+                let students = vec![
+                    student(2002, "Maria", "Gonzalez", (2016, 4, 9), 1234),
+                    student(1001, "María", "Gonzales", (2016, 4, 9), 0),
+                    student(3003, "Gonzalez", "Maria", (2016, 9, 4), 1234),
+                    student(4004, "Maria", "Nguyen", (2015, 1, 20), 5678),
+                ];
+
+                let duplicates = find_duplicates(&students, 0.8);
+                let pairs: Vec<(i32, i32)> = duplicates
+                    .iter()
+                    .map(|candidate| (candidate.first.student_id, candidate.second.student_id))
+                    .collect();
+
+                assert_eq!(pairs, vec![(1001, 2002), (2002, 3003)]);
+                assert!(duplicates[0].reasons.contains(&"Same date of birth".to_string()));
+            }
+        }
+    }
+}
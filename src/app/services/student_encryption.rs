@@ -0,0 +1,493 @@
+// Field-level encryption for student PII. firstname, lastname, pin,
+// date_of_birth and notes are stored as AES-256-GCM ciphertext under a data
+// key; data keys are stored wrapped by a master key from the configured key
+// provider, which never reaches the database.
+//
+// PII_KEY_PROVIDER picks the provider:
+//   keyfile  master keys read from PII_KEYFILE (default keys/student_pii.keys), for development
+//   env      master keys read from PII_MASTER_KEYS, for secret managers that inject them
+// Left unset, students are written in plaintext. Both take "id:base64-key"
+// entries, one per line or comma separated. The first entry wraps new data
+// keys and the rest only unwrap, so a master key is rotated by adding a new
+// first entry and re-wrapping.
+//
+// Names also get blind indexes, keyed HMACs of the normalized value and of
+// each word's prefixes, so lookups and name search work without decrypting.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use aes_gcm::aead::{Aead, KeyInit, Payload};
+        use aes_gcm::{Aes256Gcm, Nonce};
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use chrono::NaiveDate;
+        use hmac::{Hmac, Mac};
+        use once_cell::sync::Lazy;
+        use rand::RngCore;
+        use sha2::Sha256;
+        use std::collections::HashMap;
+        use std::sync::{Arc, RwLock};
+        use std::time::{Duration, Instant};
+
+        pub const KEY_LENGTH: usize = 32;
+        const NONCE_LENGTH: usize = 12;
+        const INDEX_LENGTH: usize = 16;
+        const DEFAULT_KEYFILE: &str = "keys/student_pii.keys";
+        // Longest name prefix indexed for search
+        const MAX_PREFIX_CHARS: usize = 16;
+        // How soon other servers pick up a rotated data key
+        const KEYRING_TTL: Duration = Duration::from_secs(60);
+
+        pub trait KeyProvider: Send + Sync {
+            fn name(&self) -> &'static str;
+            // Master key that wraps new data keys
+            fn active_key_id(&self) -> &str;
+            fn wrap(&self, key: &[u8]) -> Result<Vec<u8>, String>;
+            fn unwrap(&self, master_key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, String>;
+        }
+
+        // Master keys held in memory, read from a keyfile or the environment
+        pub struct LocalKeyProvider {
+            source: &'static str,
+            keys: Vec<(String, [u8; KEY_LENGTH])>,
+        }
+
+        impl LocalKeyProvider {
+            pub fn parse(source: &'static str, entries: &str) -> Result<Self, String> {
+                let mut keys: Vec<(String, [u8; KEY_LENGTH])> = Vec::new();
+                for entry in entries.split(['\n', ',']) {
+                    let entry = entry.trim();
+                    if entry.is_empty() || entry.starts_with('#') {
+                        continue;
+                    }
+                    let (id, key) = entry
+                        .split_once(':')
+                        .ok_or_else(|| "Master key entries must be id:base64-key".to_string())?;
+                    let id = id.trim();
+                    let key: [u8; KEY_LENGTH] = STANDARD
+                        .decode(key.trim())
+                        .ok()
+                        .and_then(|key| key.try_into().ok())
+                        .ok_or_else(|| format!("Master key '{}' must be {} bytes of base64", id, KEY_LENGTH))?;
+                    if keys.iter().any(|(existing, _)| existing == id) {
+                        return Err(format!("Master key '{}' is listed twice", id));
+                    }
+                    keys.push((id.to_string(), key));
+                }
+
+                if keys.is_empty() {
+                    return Err(format!("No master keys found in {}", source));
+                }
+                Ok(LocalKeyProvider { source, keys })
+            }
+        }
+
+        impl KeyProvider for LocalKeyProvider {
+            fn name(&self) -> &'static str {
+                self.source
+            }
+
+            fn active_key_id(&self) -> &str {
+                &self.keys[0].0
+            }
+
+            fn wrap(&self, key: &[u8]) -> Result<Vec<u8>, String> {
+                let (id, master) = &self.keys[0];
+                seal(master, id.as_bytes(), key)
+            }
+
+            fn unwrap(&self, master_key_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, String> {
+                let (id, master) = self
+                    .keys
+                    .iter()
+                    .find(|(id, _)| id == master_key_id)
+                    .ok_or_else(|| format!("Master key '{}' is not configured", master_key_id))?;
+                open(master, id.as_bytes(), wrapped)
+            }
+        }
+
+        static PROVIDER: Lazy<Result<Option<Arc<dyn KeyProvider>>, String>> = Lazy::new(load_provider);
+
+        fn load_provider() -> Result<Option<Arc<dyn KeyProvider>>, String> {
+            let provider = std::env::var("PII_KEY_PROVIDER").unwrap_or_default();
+            match provider.trim() {
+                "" | "none" => Ok(None),
+                "keyfile" => {
+                    let path = std::env::var("PII_KEYFILE").unwrap_or_else(|_| DEFAULT_KEYFILE.to_string());
+                    let entries = std::fs::read_to_string(&path)
+                        .map_err(|e| format!("Cannot read PII keyfile {}: {}", path, e))?;
+                    Ok(Some(Arc::new(LocalKeyProvider::parse("keyfile", &entries)?)))
+                }
+                "env" => {
+                    let entries = std::env::var("PII_MASTER_KEYS")
+                        .map_err(|_| "PII_MASTER_KEYS is not set".to_string())?;
+                    Ok(Some(Arc::new(LocalKeyProvider::parse("env", &entries)?)))
+                }
+                other => Err(format!("Unknown PII_KEY_PROVIDER '{}'", other)),
+            }
+        }
+
+        // None when PII encryption is not configured
+        pub fn key_provider() -> Result<Option<Arc<dyn KeyProvider>>, String> {
+            PROVIDER.clone()
+        }
+
+        pub fn generate_key() -> [u8; KEY_LENGTH] {
+            let mut key = [0u8; KEY_LENGTH];
+            rand::thread_rng().fill_bytes(&mut key);
+            key
+        }
+
+        // nonce || ciphertext, bound to `aad`
+        fn seal(key: &[u8; KEY_LENGTH], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+            let mut nonce = [0u8; NONCE_LENGTH];
+            rand::thread_rng().fill_bytes(&mut nonce);
+            let ciphertext = Aes256Gcm::new(key.into())
+                .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+                .map_err(|_| "Encryption failed".to_string())?;
+            Ok([nonce.as_slice(), &ciphertext].concat())
+        }
+
+        fn open(key: &[u8; KEY_LENGTH], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, String> {
+            if sealed.len() < NONCE_LENGTH {
+                return Err("Ciphertext is truncated".to_string());
+            }
+            let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+            Aes256Gcm::new(key.into())
+                .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+                .map_err(|_| "Decryption failed: wrong key or corrupted data".to_string())
+        }
+
+        fn normalize(value: &str) -> String {
+            value.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+        }
+
+        // Lowercased words of a name or search fragment
+        pub fn search_words(value: &str) -> Vec<String> {
+            value
+                .split(|c: char| c.is_whitespace() || c == '-')
+                .filter(|word| !word.is_empty())
+                .map(str::to_lowercase)
+                .collect()
+        }
+
+        fn prefix(word: &str, chars: usize) -> String {
+            word.chars().take(chars).collect()
+        }
+
+        pub struct DataKey {
+            pub id: i32,
+            key: [u8; KEY_LENGTH],
+            index_key: [u8; KEY_LENGTH],
+        }
+
+        impl DataKey {
+            pub fn unwrap(
+                provider: &dyn KeyProvider,
+                id: i32,
+                master_key_id: &str,
+                wrapped_key: &[u8],
+                wrapped_index_key: &[u8],
+            ) -> Result<Self, String> {
+                let unwrap = |wrapped: &[u8]| -> Result<[u8; KEY_LENGTH], String> {
+                    provider
+                        .unwrap(master_key_id, wrapped)?
+                        .try_into()
+                        .map_err(|_| format!("Student data key {} has the wrong length", id))
+                };
+                Ok(DataKey {
+                    id,
+                    key: unwrap(wrapped_key)?,
+                    index_key: unwrap(wrapped_index_key)?,
+                })
+            }
+
+            fn blind_index(&self, domain: &str, value: &str) -> Vec<u8> {
+                let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.index_key)
+                    .expect("HMAC accepts keys of any length");
+                mac.update(domain.as_bytes());
+                mac.update(&[0]);
+                mac.update(value.as_bytes());
+                mac.finalize().into_bytes()[..INDEX_LENGTH].to_vec()
+            }
+
+            fn name_tokens(&self, names: &[&str]) -> Vec<Vec<u8>> {
+                let mut tokens: Vec<Vec<u8>> = names
+                    .iter()
+                    .flat_map(|name| search_words(name))
+                    .flat_map(|word| {
+                        let length = word.chars().count().min(MAX_PREFIX_CHARS);
+                        (1..=length)
+                            .map(|chars| self.blind_index("name_prefix", &prefix(&word, chars)))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                tokens.sort();
+                tokens.dedup();
+                tokens
+            }
+        }
+
+        #[derive(Debug, Clone, Default, PartialEq)]
+        pub struct StudentPii {
+            pub firstname: Option<String>,
+            pub lastname: Option<String>,
+            pub pin: Option<i32>,
+            pub date_of_birth: Option<NaiveDate>,
+            pub notes: Option<String>,
+        }
+
+        // The PII columns of a students row as stored. Encrypted rows carry a
+        // key id and leave the plaintext columns NULL.
+        #[derive(Debug, Clone, Default)]
+        pub struct StoredPii {
+            pub key_id: Option<i32>,
+            pub plaintext: StudentPii,
+            pub firstname_enc: Option<Vec<u8>>,
+            pub lastname_enc: Option<Vec<u8>>,
+            pub pin_enc: Option<Vec<u8>>,
+            pub date_of_birth_enc: Option<Vec<u8>>,
+            pub notes_enc: Option<Vec<u8>>,
+            pub firstname_bidx: Option<Vec<u8>>,
+            pub lastname_bidx: Option<Vec<u8>>,
+            pub date_of_birth_bidx: Option<Vec<u8>>,
+            pub name_tokens: Option<Vec<Vec<u8>>>,
+        }
+
+        pub struct PiiKeyring {
+            active: i32,
+            keys: HashMap<i32, DataKey>,
+        }
+
+        impl PiiKeyring {
+            pub fn new(keys: Vec<DataKey>, active: i32) -> Result<Self, String> {
+                let keys: HashMap<i32, DataKey> = keys.into_iter().map(|key| (key.id, key)).collect();
+                if !keys.contains_key(&active) {
+                    return Err(format!("Active student data key {} could not be loaded", active));
+                }
+                Ok(PiiKeyring { active, keys })
+            }
+
+            pub fn active_key_id(&self) -> i32 {
+                self.active
+            }
+
+            fn key(&self, id: i32) -> Result<&DataKey, String> {
+                self.keys
+                    .get(&id)
+                    .ok_or_else(|| format!("Student data key {} is not available", id))
+            }
+
+            fn decrypt(&self, key_id: i32, column: &str, sealed: &[u8]) -> Result<String, String> {
+                let plaintext = open(&self.key(key_id)?.key, column.as_bytes(), sealed)
+                    .map_err(|e| format!("Cannot decrypt {}: {}", column, e))?;
+                String::from_utf8(plaintext).map_err(|_| format!("Decrypted {} is not text", column))
+            }
+
+            // Exact-match index of `value` under every loaded key, so lookups
+            // still find rows not yet re-encrypted after a rotation
+            pub fn blind_indexes(&self, column: &str, value: &str) -> Vec<(i32, Vec<u8>)> {
+                self.keys
+                    .values()
+                    .map(|key| (key.id, key.blind_index(column, &normalize(value))))
+                    .collect()
+            }
+
+            // Per key, the name tokens a row must hold to match every word
+            pub fn search_tokens(&self, words: &[String]) -> Vec<(i32, Vec<Vec<u8>>)> {
+                self.keys
+                    .values()
+                    .map(|key| {
+                        let tokens = words
+                            .iter()
+                            .map(|word| key.blind_index("name_prefix", &prefix(word, MAX_PREFIX_CHARS)))
+                            .collect();
+                        (key.id, tokens)
+                    })
+                    .collect()
+            }
+
+            // Per key, a token for every prefix of every word, tagged with the
+            // word's index and how much of the word the prefix covers. Rows
+            // holding only a shorter prefix still match, which tolerates typos
+            // past the first letters of an encrypted name.
+            pub fn prefix_search_tokens(&self, words: &[String]) -> Vec<(i32, i32, f64, Vec<u8>)> {
+                let mut tokens = Vec::new();
+                for key in self.keys.values() {
+                    for (index, word) in words.iter().enumerate() {
+                        let length = word.chars().count().min(MAX_PREFIX_CHARS);
+                        for chars in 1..=length {
+                            tokens.push((key.id, index as i32, chars as f64 / length as f64, key.blind_index("name_prefix", &prefix(word, chars))));
+                        }
+                    }
+                }
+                tokens
+            }
+
+            pub fn seal(&self, pii: &StudentPii) -> Result<StoredPii, String> {
+                let key = self.key(self.active)?;
+                let encrypt = |column: &str, value: Option<String>| {
+                    value
+                        .map(|value| seal(&key.key, column.as_bytes(), value.as_bytes()))
+                        .transpose()
+                };
+                let names: Vec<&str> = [&pii.firstname, &pii.lastname]
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .collect();
+
+                Ok(StoredPii {
+                    key_id: Some(self.active),
+                    plaintext: StudentPii::default(),
+                    firstname_enc: encrypt("firstname", pii.firstname.clone())?,
+                    lastname_enc: encrypt("lastname", pii.lastname.clone())?,
+                    pin_enc: encrypt("pin", pii.pin.map(|pin| pin.to_string()))?,
+                    date_of_birth_enc: encrypt("date_of_birth", pii.date_of_birth.map(|date| date.to_string()))?,
+                    notes_enc: encrypt("notes", pii.notes.clone())?,
+                    firstname_bidx: pii.firstname.as_deref().map(|name| key.blind_index("firstname", &normalize(name))),
+                    lastname_bidx: pii.lastname.as_deref().map(|name| key.blind_index("lastname", &normalize(name))),
+                    date_of_birth_bidx: pii.date_of_birth.map(|date| key.blind_index("date_of_birth", &date.to_string())),
+                    name_tokens: Some(key.name_tokens(&names)),
+                })
+            }
+        }
+
+        // Encrypted under the active data key when encryption is configured
+        pub fn seal_pii(keyring: Option<&PiiKeyring>, pii: StudentPii) -> Result<StoredPii, String> {
+            match keyring {
+                Some(keyring) => keyring.seal(&pii),
+                None => Ok(StoredPii { plaintext: pii, ..Default::default() }),
+            }
+        }
+
+        // Plaintext columns win over ciphertext: they hold values written since
+        // the row was last sealed (a protection restore), pending re-encryption
+        pub fn open_pii(keyring: Option<&PiiKeyring>, stored: &StoredPii) -> Result<StudentPii, String> {
+            let Some(key_id) = stored.key_id else {
+                return Ok(stored.plaintext.clone());
+            };
+            let keyring = keyring
+                .ok_or_else(|| "Student data is encrypted but no PII key provider is configured".to_string())?;
+            let field = |plaintext: Option<String>, sealed: &Option<Vec<u8>>, column: &str| match (plaintext, sealed) {
+                (Some(value), _) => Ok(Some(value)),
+                (None, Some(sealed)) => keyring.decrypt(key_id, column, sealed).map(Some),
+                (None, None) => Ok(None),
+            };
+            let parse_error = |column: &str| format!("Decrypted {} is malformed", column);
+
+            let plaintext = &stored.plaintext;
+            Ok(StudentPii {
+                firstname: field(plaintext.firstname.clone(), &stored.firstname_enc, "firstname")?,
+                lastname: field(plaintext.lastname.clone(), &stored.lastname_enc, "lastname")?,
+                pin: field(plaintext.pin.map(|pin| pin.to_string()), &stored.pin_enc, "pin")?
+                    .map(|pin| pin.parse().map_err(|_| parse_error("pin")))
+                    .transpose()?,
+                date_of_birth: field(plaintext.date_of_birth.map(|date| date.to_string()), &stored.date_of_birth_enc, "date_of_birth")?
+                    .map(|date| date.parse().map_err(|_| parse_error("date_of_birth")))
+                    .transpose()?,
+                notes: field(plaintext.notes.clone(), &stored.notes_enc, "notes")?,
+            })
+        }
+
+        // When the keyring was loaded, and the keyring
+        type CachedKeyring = (Instant, Arc<PiiKeyring>);
+
+        static KEYRING: Lazy<RwLock<Option<CachedKeyring>>> = Lazy::new(|| RwLock::new(None));
+
+        pub fn cached_keyring() -> Option<Arc<PiiKeyring>> {
+            let cached = KEYRING.read().ok()?;
+            cached
+                .as_ref()
+                .filter(|(loaded, _)| loaded.elapsed() < KEYRING_TTL)
+                .map(|(_, keyring)| keyring.clone())
+        }
+
+        pub fn cache_keyring(keyring: Option<Arc<PiiKeyring>>) {
+            if let Ok(mut cached) = KEYRING.write() {
+                *cached = keyring.map(|keyring| (Instant::now(), keyring));
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            fn data_key(id: i32) -> DataKey {
+                DataKey { id, key: generate_key(), index_key: generate_key() }
+            }
+
+            fn pii() -> StudentPii {
+                StudentPii {
+                    firstname: Some("Mary Ann".to_string()),
+                    lastname: Some("Ruiz".to_string()),
+                    pin: Some(1234),
+                    date_of_birth: NaiveDate::from_ymd_opt(2015, 3, 9),
+                    notes: Some("Allergic to peanuts".to_string()),
+                }
+            }
+
+            #[test]
+            fn master_keys_wrap_and_rotate() {
+                let old = STANDARD.encode(generate_key());
+                let new = STANDARD.encode(generate_key());
+                let before = LocalKeyProvider::parse("env", &format!("2024:{}", old)).unwrap();
+                let after = LocalKeyProvider::parse("keyfile", &format!("# rotated\n2025:{}\n2024:{}\n", new, old)).unwrap();
+
+                let wrapped = before.wrap(b"data key").unwrap();
+                assert_eq!(after.unwrap("2024", &wrapped).unwrap(), b"data key");
+                assert_eq!(after.active_key_id(), "2025");
+                assert!(after.unwrap("2025", &wrapped).is_err());
+
+                assert!(LocalKeyProvider::parse("env", "2024:c2hvcnQ=").is_err());
+                assert!(LocalKeyProvider::parse("env", &format!("a:{},a:{}", old, new)).is_err());
+            }
+
+            #[test]
+            fn sealed_pii_opens_and_hides_plaintext() {
+                let keyring = PiiKeyring::new(vec![data_key(1)], 1).unwrap();
+                let stored = seal_pii(Some(&keyring), pii()).unwrap();
+                assert_eq!(stored.plaintext, StudentPii::default());
+                assert_eq!(open_pii(Some(&keyring), &stored).unwrap(), pii());
+                assert!(open_pii(None, &stored).is_err());
+
+                let other = PiiKeyring::new(vec![data_key(1)], 1).unwrap();
+                assert!(open_pii(Some(&other), &stored).is_err());
+
+                let plaintext = seal_pii(None, pii()).unwrap();
+                assert_eq!(open_pii(None, &plaintext).unwrap(), pii());
+            }
+
+            #[test]
+            fn blind_indexes_find_names_under_every_key() {
+                let keyring = PiiKeyring::new(vec![data_key(1), data_key(2)], 2).unwrap();
+                let stored = keyring.seal(&pii()).unwrap();
+                let tokens = stored.name_tokens.unwrap();
+
+                let matches = |fragment: &str| {
+                    keyring
+                        .search_tokens(&search_words(fragment))
+                        .into_iter()
+                        .any(|(id, wanted)| id == 2 && wanted.iter().all(|token| tokens.contains(token)))
+                };
+                assert!(matches("ann ru"));
+                assert!(matches("MARY"));
+                assert!(!matches("ann smith"));
+
+                let best_coverage = |word: &str| {
+                    keyring
+                        .prefix_search_tokens(&search_words(word))
+                        .into_iter()
+                        .filter(|(id, _, _, token)| *id == 2 && tokens.contains(token))
+                        .map(|(_, _, coverage, _)| coverage)
+                        .fold(0.0, f64::max)
+                };
+                assert_eq!(best_coverage("ruiz"), 1.0);
+                assert_eq!(best_coverage("ruis"), 0.75);
+                assert_eq!(best_coverage("zzz"), 0.0);
+
+                let exact = keyring.blind_indexes("lastname", "  RUIZ ");
+                assert_eq!(exact.len(), 2);
+                assert!(exact.contains(&(2, stored.lastname_bidx.unwrap())));
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// Records request export: a zip holding the student's full record as JSON
+// plus a readable HTML summary that prints cleanly to PDF from a browser.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::question::Question;
+        use crate::app::models::score::Score;
+        use crate::app::models::student_export::{ExportedAnswer, ExportedScore, StudentExportBundle, StudentRecordExport};
+        use crate::app::services::zip::zip_archive;
+        use crate::app::utils::html::escape_html;
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        use std::collections::HashMap;
+        use std::fmt::Write as _;
+
+        // Pairs each score with its test's questions. Points and comments are
+        // stored in question order, so entry i belongs to the i-th question.
+        pub fn export_scores(scores: Vec<Score>, tests: &HashMap<String, (Option<String>, Vec<Question>)>) -> Vec<ExportedScore> {
+            let mut exported: Vec<ExportedScore> = scores
+                .into_iter()
+                .map(|score| {
+                    let (test_name, questions) = tests
+                        .get(&score.test_id)
+                        .map(|(name, questions)| (name.clone(), questions.as_slice()))
+                        .unwrap_or((None, &[]));
+                    let answers = questions
+                        .iter()
+                        .enumerate()
+                        .map(|(index, question)| ExportedAnswer {
+                            qnumber: question.qnumber,
+                            question: question.word_problem.clone(),
+                            point_value: question.point_value,
+                            points_earned: score.test_scores.get(index).copied(),
+                            comment: score.comments.get(index).filter(|comment| !comment.is_empty()).cloned(),
+                        })
+                        .collect();
+                    ExportedScore {
+                        total: score.get_total(),
+                        test_id: score.test_id,
+                        test_name,
+                        date_administered: score.date_administered,
+                        test_variant: score.test_variant,
+                        attempt: score.attempt,
+                        evaluator: score.evaluator,
+                        answers,
+                    }
+                })
+                .collect();
+            exported.sort_by_key(|score| score.date_administered);
+            exported
+        }
+
+        fn yes_no(value: bool) -> &'static str {
+            if value { "Yes" } else { "No" }
+        }
+
+        fn table_row(html: &mut String, cells: &[String]) {
+            html.push_str("<tr>");
+            for cell in cells {
+                let _ = write!(html, "<td>{}</td>", escape_html(cell));
+            }
+            html.push_str("</tr>\n");
+        }
+
+        fn table_header(html: &mut String, headings: &[&str]) {
+            html.push_str("<table>\n<tr>");
+            for heading in headings {
+                let _ = write!(html, "<th>{}</th>", heading);
+            }
+            html.push_str("</tr>\n");
+        }
+
+        pub fn render_summary_html(record: &StudentRecordExport) -> String {
+            let student = &record.student;
+            let name = format!(
+                "{} {}",
+                student.firstname.as_deref().unwrap_or(""),
+                student.lastname.as_deref().unwrap_or("")
+            );
+            let mut html = String::new();
+            let _ = write!(
+                html,
+                "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Student record: {}</title>\n\
+                 <style>\nbody {{ font-family: sans-serif; color: #2E3A59; margin: 2em; }}\n\
+                 table {{ border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }}\n\
+                 th, td {{ border: 1px solid #DADADA; padding: 4px 8px; text-align: left; vertical-align: top; }}\n\
+                 th {{ background: #F9F9F8; }}\n\
+                 @media print {{ h2 {{ page-break-after: avoid; }} table {{ page-break-inside: auto; }} }}\n\
+                 </style>\n</head>\n<body>\n<h1>Student record: {}</h1>\n<p>Generated {} by {}.</p>\n",
+                escape_html(name.trim()),
+                escape_html(name.trim()),
+                record.generated_at.format("%Y-%m-%d %H:%M UTC"),
+                escape_html(&record.generated_by)
+            );
+
+            html.push_str("<h2>Profile</h2>\n");
+            table_header(&mut html, &["Field", "Value"]);
+            for (field, value) in [
+                ("Student ID", student.student_id.to_string()),
+                ("First name", student.firstname.clone().unwrap_or_default()),
+                ("Last name", student.lastname.clone().unwrap_or_default()),
+                ("Preferred name", student.preferred.clone()),
+                ("Gender", student.gender.to_string()),
+                ("Date of birth", student.date_of_birth.to_string()),
+                ("Grade", student.current_grade_level.to_string()),
+                ("Teacher", student.teacher.clone()),
+                ("PIN", student.pin.map(|pin| pin.to_string()).unwrap_or_default()),
+                ("Notes", student.notes.clone()),
+            ] {
+                table_row(&mut html, &[field.to_string(), value]);
+            }
+            html.push_str("</table>\n");
+
+            let interventions = &record.interventions;
+            html.push_str("<h2>Programs and interventions</h2>\n");
+            table_header(&mut html, &["Program", "Value"]);
+            for (program, value) in [
+                ("Intervention", interventions.intervention.clone().unwrap_or_else(|| "None".to_string())),
+                ("ESL", interventions.esl.clone()),
+                ("IEP", yes_no(interventions.iep).to_string()),
+                ("BIP", yes_no(interventions.bip).to_string()),
+                ("504 plan", yes_no(interventions.student_504).to_string()),
+                ("Read plan", yes_no(interventions.readplan).to_string()),
+                ("Gifted and talented", yes_no(interventions.gt).to_string()),
+                ("Eye glasses", yes_no(interventions.eye_glasses).to_string()),
+            ] {
+                table_row(&mut html, &[program.to_string(), value]);
+            }
+            html.push_str("</table>\n");
+
+            html.push_str("<h2>Enrollments</h2>\n");
+            table_header(&mut html, &["Academic year", "Grade", "Teacher ID", "Status", "Enrolled", "Status changed", "Notes"]);
+            for enrollment in &record.enrollments {
+                table_row(&mut html, &[
+                    enrollment.academic_year.to_string(),
+                    enrollment.grade_level.to_string(),
+                    enrollment.teacher_id.to_string(),
+                    enrollment.status.to_string(),
+                    enrollment.enrollment_date.to_string(),
+                    enrollment.status_change_date.map(|date| date.to_string()).unwrap_or_default(),
+                    enrollment.notes.clone().unwrap_or_default(),
+                ]);
+            }
+            html.push_str("</table>\n");
+
+            html.push_str("<h2>Assessment scores</h2>\n");
+            if record.scores.is_empty() {
+                html.push_str("<p>No scores recorded.</p>\n");
+            }
+            for score in &record.scores {
+                let _ = write!(
+                    html,
+                    "<h3>{}</h3>\n<p>{}, variant {}, attempt {}, evaluated by {}. Total: {}</p>\n",
+                    escape_html(score.test_name.as_deref().unwrap_or(&score.test_id)),
+                    score.date_administered.format("%Y-%m-%d"),
+                    score.test_variant,
+                    score.attempt,
+                    escape_html(&score.evaluator),
+                    score.total
+                );
+                table_header(&mut html, &["#", "Question", "Points", "Earned", "Comment"]);
+                for answer in &score.answers {
+                    table_row(&mut html, &[
+                        answer.qnumber.to_string(),
+                        answer.question.clone(),
+                        answer.point_value.to_string(),
+                        answer.points_earned.map(|points| points.to_string()).unwrap_or_default(),
+                        answer.comment.clone().unwrap_or_default(),
+                    ]);
+                }
+                html.push_str("</table>\n");
+            }
+
+            html.push_str("<h2>Change and disclosure history</h2>\n");
+            table_header(&mut html, &["When", "Who", "Action", "Record"]);
+            for event in &record.audit_events {
+                table_row(&mut html, &[
+                    event.occurred_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    event.actor_username.clone(),
+                    event.action.to_string(),
+                    format!("{} {}", event.entity_type.label(), event.entity_id),
+                ]);
+            }
+            html.push_str("</table>\n</body>\n</html>\n");
+            html
+        }
+
+        pub fn build_bundle(record: &StudentRecordExport) -> Result<StudentExportBundle, String> {
+            let json = serde_json::to_vec_pretty(record).map_err(|e| format!("Failed to serialize record: {}", e))?;
+            let html = render_summary_html(record).into_bytes();
+            let archive = zip_archive(&[("record.json", json), ("summary.html", html)], record.generated_at)?;
+
+            Ok(StudentExportBundle {
+                filename: format!(
+                    "student_{}_records_{}.zip",
+                    record.student.student_id,
+                    record.generated_at.format("%Y%m%d")
+                ),
+                archive_base64: STANDARD.encode(archive),
+            })
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use chrono::Utc;
+
+            #[test]
+            fn scores_pair_points_with_questions() {
+                use crate::app::models::question::QuestionType;
+
+                let question = |qnumber: i32, text: &str| Question {
+                    word_problem: text.to_string(),
+                    point_value: 2,
+                    question_type: QuestionType::MultipleChoice,
+                    options: vec![],
+                    correct_answer: String::new(),
+                    qnumber,
+                    testlinker: "t1".to_string(),
+                    weighted_options: None,
+                };
+                let tests = HashMap::from([(
+                    "t1".to_string(),
+                    (Some("Fall <Reading>".to_string()), vec![question(1, "2 + 2"), question(2, "3 + 3")]),
+                )]);
+                let score = Score::new(1001, Utc::now(), "t1".to_string(), vec![2, 0], vec![String::new(), "guessed".to_string()], 1, "Ms. Ruiz".to_string(), 1);
+
+                let exported = export_scores(vec![score], &tests);
+                assert_eq!(exported[0].total, 2);
+                assert_eq!(exported[0].answers[1].points_earned, Some(0));
+                assert_eq!(exported[0].answers[1].comment.as_deref(), Some("guessed"));
+                assert_eq!(exported[0].answers[0].comment, None);
+
+                let mut html = String::new();
+                table_row(&mut html, &[exported[0].test_name.clone().unwrap()]);
+                assert!(html.contains("Fall &lt;Reading&gt;"));
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+// Student protection replaces every student ID with an anonymized app ID and
+// clears names, PINs and SIS sourcedIds. The mapping needed to undo that is
+// sealed with AES-256-GCM under a key derived from admin passphrases; the
+// server keeps only the ciphertext and never stores the passphrases or the key.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::models::student_protection::{KdfParams, ProtectionProgress, StudentIdMapping};
+        use crate::app::utils::mapping_crypto::{derive_key, encrypt_mapping, validate_passphrases, NONCE_LENGTH};
+        use once_cell::sync::Lazy;
+        use rand::RngCore;
+        use std::sync::RwLock;
+
+        // App IDs start here, or well above any ID already in this range
+        pub const FIRST_APP_ID: i32 = 100_000;
+        const APP_ID_GAP: i32 = 1000;
+
+        const SALT_LENGTH: usize = 16;
+
+        pub struct SealedParts {
+            pub salt: Vec<u8>,
+            pub nonce: Vec<u8>,
+            pub ciphertext: Vec<u8>,
+        }
+
+        static PROGRESS: Lazy<RwLock<Option<ProtectionProgress>>> = Lazy::new(|| RwLock::new(None));
+
+        pub fn set_progress(progress: ProtectionProgress) {
+            if let Ok(mut current) = PROGRESS.write() {
+                *current = Some(progress);
+            }
+        }
+
+        pub fn current_progress() -> Option<ProtectionProgress> {
+            PROGRESS.read().ok().and_then(|current| current.clone())
+        }
+
+        // Pairs each student, in ID order, with a fresh app ID
+        pub fn assign_app_ids(students: Vec<(i32, String, String, i32)>) -> Vec<StudentIdMapping> {
+            let max_existing = students.iter().map(|(id, ..)| *id).max().unwrap_or(0);
+            let first = if max_existing >= FIRST_APP_ID {
+                max_existing + APP_ID_GAP
+            } else {
+                FIRST_APP_ID
+            };
+
+            let mut students = students;
+            students.sort_by_key(|(id, ..)| *id);
+            students
+                .into_iter()
+                .zip(first..)
+                .map(|((student_id, firstname, lastname, pin), app_id)| StudentIdMapping {
+                    app_id,
+                    student_id,
+                    firstname,
+                    lastname,
+                    pin,
+                    sourced_id: None,
+                })
+                .collect()
+        }
+
+        pub fn seal_mapping(
+            mapping: &[StudentIdMapping],
+            passphrases: &[String],
+            params: &KdfParams,
+        ) -> Result<SealedParts, String> {
+            validate_passphrases(passphrases)?;
+
+            let mut salt = [0u8; SALT_LENGTH];
+            let mut nonce = [0u8; NONCE_LENGTH];
+            rand::thread_rng().fill_bytes(&mut salt);
+            rand::thread_rng().fill_bytes(&mut nonce);
+
+            let key = derive_key(passphrases, &salt, params)?;
+            let ciphertext = encrypt_mapping(&key, &nonce, mapping)?;
+            Ok(SealedParts { salt: salt.to_vec(), nonce: nonce.to_vec(), ciphertext })
+        }
+
+        // Mapping files exported by the old psql scripts:
+        // app_id, original student id, firstname, lastname, pin[, created_at]
+        pub fn parse_mapping_csv(content: &str) -> Result<Vec<StudentIdMapping>, String> {
+            let mut reader = csv::ReaderBuilder::new()
+                .flexible(true)
+                .trim(csv::Trim::All)
+                .from_reader(content.as_bytes());
+
+            let mut mapping = Vec::new();
+            for (index, record) in reader.records().enumerate() {
+                let line = index + 2;
+                let record = record.map_err(|e| format!("Invalid CSV at line {}: {}", line, e))?;
+                if record.iter().all(|field| field.is_empty()) {
+                    continue;
+                }
+                if record.len() < 5 {
+                    return Err(format!("Line {}: expected at least 5 columns, found {}", line, record.len()));
+                }
+
+                let number = |column: usize, name: &str| {
+                    record[column]
+                        .parse::<i32>()
+                        .map_err(|_| format!("Line {}: invalid {} '{}'", line, name, &record[column]))
+                };
+                mapping.push(StudentIdMapping {
+                    app_id: number(0, "app_id")?,
+                    student_id: number(1, "student_id")?,
+                    firstname: record[2].to_string(),
+                    lastname: record[3].to_string(),
+                    pin: if record[4].is_empty() { 0 } else { number(4, "pin")? },
+                    sourced_id: None,
+                });
+            }
+
+            if mapping.is_empty() {
+                return Err("Mapping file contains no data rows".to_string());
+            }
+            Ok(mapping)
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            fn students() -> Vec<(i32, String, String, i32)> {
+                vec![
+                    (52884, "Thien".to_string(), "Le".to_string(), 1234),
+                    (1001, "Ana".to_string(), "Ruiz, Jr".to_string(), 0),
+                ]
+            }
+
+            #[test]
+            fn app_ids_follow_student_order() {
+                let mapping = assign_app_ids(students());
+                assert_eq!(
+                    mapping.iter().map(|m| (m.student_id, m.app_id)).collect::<Vec<_>>(),
+                    vec![(1001, 100_000), (52884, 100_001)]
+                );
+
+                let mut high = students();
+                high.push((100_500, "Kim".to_string(), "Park".to_string(), 7));
+                assert_eq!(assign_app_ids(high)[0].app_id, 101_500);
+            }
+
+            #[test]
+            fn sealed_mapping_needs_every_share() {
+                use crate::app::models::student_protection::SealedMapping;
+                use crate::app::utils::mapping_crypto::open_mapping;
+                use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+                let mut mapping = assign_app_ids(students());
+                mapping[0].sourced_id = Some("sis-1001".to_string());
+                let shares = vec!["first admin's phrase".to_string(), "second admin's phrase".to_string()];
+                // Cheap settings; the real cost is irrelevant to correctness
+                let params = KdfParams { memory_kib: 64, iterations: 1, parallelism: 1 };
+                let parts = seal_mapping(&mapping, &shares, &params).unwrap();
+                let sealed = SealedMapping {
+                    kdf_salt: Some(STANDARD.encode(&parts.salt)),
+                    kdf: params,
+                    key_shares: 2,
+                    nonce: STANDARD.encode(&parts.nonce),
+                    ciphertext: STANDARD.encode(&parts.ciphertext),
+                    student_count: mapping.len() as i32,
+                    created_at: chrono::Utc::now(),
+                };
+
+                let reversed: Vec<String> = shares.iter().rev().cloned().collect();
+                assert_eq!(open_mapping(&sealed, &reversed).unwrap(), mapping);
+                assert!(open_mapping(&sealed, &shares[..1]).is_err());
+                assert!(open_mapping(&sealed, &[shares[0].clone(), "a wrong passphrase".to_string()]).is_err());
+                assert!(seal_mapping(&mapping, &["too short".to_string()], &params).is_err());
+            }
+
+            #[test]
+            fn legacy_mapping_file_parses() {
+                let legacy = "app_id,original_student_id,firstname,lastname,pin,created_at\n100000,1001,Ana,\"Ruiz, Jr\",1234,2025-06-09\n100001,52884,Thien,Le,,\n";
+                let mapping = parse_mapping_csv(legacy).unwrap();
+                assert_eq!(mapping[0].lastname, "Ruiz, Jr");
+                assert_eq!(mapping[1].pin, 0);
+                assert!(parse_mapping_csv("app_id,student_id\n1,2\n").is_err());
+            }
+
+            #[test]
+            fn mapping_sealed_without_sourced_ids_opens() {
+                let sealed_earlier = r#"[{"app_id":100000,"student_id":1001,"firstname":"Ana","lastname":"Ruiz","pin":0}]"#;
+                let mapping: Vec<StudentIdMapping> = serde_json::from_str(sealed_earlier).unwrap();
+                assert_eq!(mapping[0].sourced_id, None);
+            }
+        }
+    }
+}
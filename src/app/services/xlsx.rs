@@ -0,0 +1,242 @@
+// Excel workbooks (.xlsx) read as CSV, so spreadsheet uploads go through the
+// same parsing as CSV files. Only the first sheet is read, with each cell as
+// Excel shows it: strings, numbers, booleans and dates as YYYY-MM-DD.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use crate::app::services::zip::unzip_archive;
+        use chrono::{Duration, NaiveDate};
+        use csv::WriterBuilder;
+        use roxmltree::{Document, Node};
+        use std::collections::HashMap;
+
+        const RELATIONSHIPS_NAMESPACE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+        // Built-in number formats that show a date
+        const DATE_FORMAT_IDS: [u32; 12] = [14, 15, 16, 17, 18, 19, 20, 21, 22, 45, 46, 47];
+        // Excel's own limit; cell references past it are refused rather than padded out
+        const MAX_COLUMNS: usize = 16_384;
+
+        type Parts = HashMap<String, Vec<u8>>;
+
+        fn parse_part<'a>(parts: &'a Parts, name: &str) -> Result<Option<Document<'a>>, String> {
+            let Some(data) = parts.get(name) else {
+                return Ok(None);
+            };
+            let text = std::str::from_utf8(data).map_err(|_| format!("{} is not UTF-8", name))?;
+            Document::parse(text.trim_start_matches('\u{feff}'))
+                .map(Some)
+                .map_err(|e| format!("{} is not valid XML: {}", name, e))
+        }
+
+        // The text of a string item, leaving out phonetic guides
+        fn item_text(item: Node) -> String {
+            item.descendants()
+                .filter(|node| node.has_tag_name("t") && !node.ancestors().any(|ancestor| ancestor.has_tag_name("rPh")))
+                .filter_map(|node| node.text())
+                .collect()
+        }
+
+        fn shared_strings(parts: &Parts) -> Result<Vec<String>, String> {
+            Ok(match parse_part(parts, "xl/sharedStrings.xml")? {
+                Some(doc) => doc.root_element().children().filter(|node| node.has_tag_name("si")).map(item_text).collect(),
+                None => Vec::new(),
+            })
+        }
+
+        // A custom number format shows a date if it has a day or year code
+        // outside quoted text and [colour] or [condition] brackets. Month codes
+        // are left out as "m" also means minutes.
+        fn is_date_format(code: &str) -> bool {
+            let (mut quoted, mut bracketed, mut escaped) = (false, false, false);
+            for ch in code.chars() {
+                if escaped {
+                    escaped = false;
+                    continue;
+                }
+                match ch {
+                    '"' => quoted = !quoted,
+                    _ if quoted => {}
+                    '\\' => escaped = true,
+                    '[' => bracketed = true,
+                    ']' => bracketed = false,
+                    'd' | 'D' | 'y' | 'Y' if !bracketed => return true,
+                    _ => {}
+                }
+            }
+            false
+        }
+
+        // Whether each cell style, by index, shows a date
+        fn date_styles(parts: &Parts) -> Result<Vec<bool>, String> {
+            let Some(doc) = parse_part(parts, "xl/styles.xml")? else {
+                return Ok(Vec::new());
+            };
+            let custom: HashMap<u32, bool> = doc
+                .descendants()
+                .filter(|node| node.has_tag_name("numFmt"))
+                .filter_map(|format| Some((format.attribute("numFmtId")?.parse().ok()?, is_date_format(format.attribute("formatCode")?))))
+                .collect();
+            Ok(doc
+                .descendants()
+                .find(|node| node.has_tag_name("cellXfs"))
+                .map(|styles| {
+                    styles
+                        .children()
+                        .filter(|node| node.has_tag_name("xf"))
+                        .map(|style| {
+                            let id: u32 = style.attribute("numFmtId").and_then(|id| id.parse().ok()).unwrap_or(0);
+                            custom.get(&id).copied().unwrap_or(DATE_FORMAT_IDS.contains(&id))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default())
+        }
+
+        // The part holding the workbook's first sheet, and whether the
+        // workbook counts dates from 1904
+        fn first_sheet(parts: &Parts) -> Result<(String, bool), String> {
+            let workbook = parse_part(parts, "xl/workbook.xml")?.ok_or_else(|| "The file is not an Excel workbook".to_string())?;
+            let date1904 = workbook
+                .descendants()
+                .find(|node| node.has_tag_name("workbookPr"))
+                .and_then(|properties| properties.attribute("date1904"))
+                .is_some_and(|value| value == "1" || value == "true");
+            let relationship = workbook
+                .descendants()
+                .find(|node| node.has_tag_name("sheet"))
+                .and_then(|sheet| sheet.attribute((RELATIONSHIPS_NAMESPACE, "id")))
+                .ok_or_else(|| "The workbook has no sheets".to_string())?;
+
+            let relationships = parse_part(parts, "xl/_rels/workbook.xml.rels")?.ok_or_else(|| "The workbook is missing its sheet list".to_string())?;
+            let target = relationships
+                .descendants()
+                .find(|node| node.has_tag_name("Relationship") && node.attribute("Id") == Some(relationship))
+                .and_then(|node| node.attribute("Target"))
+                .ok_or_else(|| "The workbook's first sheet is missing".to_string())?;
+            // Targets are relative to xl/ unless they start from the root
+            let path = match target.strip_prefix('/') {
+                Some(path) => path.to_string(),
+                None => format!("xl/{}", target),
+            };
+            Ok((path, date1904))
+        }
+
+        // Zero-based column of a cell reference such as "C7"
+        fn column_index(reference: &str) -> Option<usize> {
+            let letters: Vec<u8> = reference.bytes().take_while(u8::is_ascii_alphabetic).collect();
+            if letters.is_empty() {
+                return None;
+            }
+            let number = letters
+                .iter()
+                .fold(0usize, |number, letter| number.saturating_mul(26).saturating_add((letter.to_ascii_uppercase() - b'A') as usize + 1));
+            Some(number - 1)
+        }
+
+        // Excel counts days from 1899-12-30, which keeps its phantom
+        // 29 February 1900 in line, or in 1904 workbooks from 1904-01-01
+        fn excel_date(serial: f64, date1904: bool) -> Option<NaiveDate> {
+            let epoch = if date1904 { NaiveDate::from_ymd_opt(1904, 1, 1)? } else { NaiveDate::from_ymd_opt(1899, 12, 30)? };
+            if !(0.0..=2_958_465.0).contains(&serial) {
+                return None;
+            }
+            epoch.checked_add_signed(Duration::days(serial.floor() as i64))
+        }
+
+        fn cell_text(cell: Node, strings: &[String], dates: &[bool], date1904: bool) -> Result<String, String> {
+            let value = cell.children().find(|node| node.has_tag_name("v")).and_then(|node| node.text()).unwrap_or_default();
+            Ok(match cell.attribute("t").unwrap_or("n") {
+                "s" => value
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| strings.get(index))
+                    .cloned()
+                    .ok_or_else(|| format!("Cell {} refers to a missing string", cell.attribute("r").unwrap_or_default()))?,
+                "inlineStr" => cell.children().find(|node| node.has_tag_name("is")).map(item_text).unwrap_or_default(),
+                "b" => if value == "1" { "TRUE" } else { "FALSE" }.to_string(),
+                "n" => {
+                    let style: usize = cell.attribute("s").and_then(|style| style.parse().ok()).unwrap_or(0);
+                    match value.parse::<f64>().ok().filter(|_| dates.get(style) == Some(&true)).and_then(|serial| excel_date(serial, date1904)) {
+                        Some(date) => date.format("%Y-%m-%d").to_string(),
+                        None => value.to_string(),
+                    }
+                }
+                // Formula strings, errors and ISO dates are shown as stored
+                _ => value.to_string(),
+            })
+        }
+
+        // The workbook's first sheet as CSV text. Empty rows are left out.
+        pub fn first_sheet_as_csv(workbook: &[u8]) -> Result<String, String> {
+            let parts: Parts = unzip_archive(workbook)
+                .map_err(|e| format!("The file is not an Excel workbook: {}", e))?
+                .into_iter()
+                .collect();
+            let (path, date1904) = first_sheet(&parts)?;
+            let strings = shared_strings(&parts)?;
+            let dates = date_styles(&parts)?;
+            let sheet = parse_part(&parts, &path)?.ok_or_else(|| "The workbook's first sheet is missing".to_string())?;
+
+            let mut writer = WriterBuilder::new().flexible(true).from_writer(Vec::new());
+            for row in sheet.descendants().filter(|node| node.has_tag_name("row")) {
+                let mut cells: Vec<String> = Vec::new();
+                for cell in row.children().filter(|node| node.has_tag_name("c")) {
+                    let column = cell.attribute("r").and_then(column_index).unwrap_or(cells.len());
+                    if column >= MAX_COLUMNS {
+                        return Err(format!("Cell {} is past the last column", cell.attribute("r").unwrap_or_default()));
+                    }
+                    if cells.len() <= column {
+                        cells.resize(column + 1, String::new());
+                    }
+                    cells[column] = cell_text(cell, &strings, &dates, date1904)?;
+                }
+                if cells.iter().all(String::is_empty) {
+                    continue;
+                }
+                writer.write_record(&cells).map_err(|e| format!("Failed to read the sheet: {}", e))?;
+            }
+
+            let csv = writer.into_inner().map_err(|e| format!("Failed to read the sheet: {}", e))?;
+            String::from_utf8(csv).map_err(|_| "The sheet is not valid text".to_string())
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+            use crate::app::services::zip::zip_archive;
+            use chrono::Utc;
+
+            #[test]
+            fn first_sheet_reads_strings_numbers_and_dates() {
+                let workbook = r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Scores" sheetId="1" r:id="rId3"/><sheet name="Notes" sheetId="2" r:id="rId1"/></sheets></workbook>"#;
+                let relationships = r#"<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Target="worksheets/sheet2.xml"/><Relationship Id="rId3" Target="/xl/worksheets/sheet1.xml"/></Relationships>"#;
+                let strings = r#"<sst><si><t>student_id</t></si><si><r><t>Reading </t></r><r><t>Check, Fall</t></r></si></sst>"#;
+                let styles = r#"<styleSheet><numFmts><numFmt numFmtId="164" formatCode="[Red]0.00"/><numFmt numFmtId="165" formatCode="dd&quot;/&quot;mm&quot;/&quot;yyyy"/></numFmts><cellXfs><xf numFmtId="0"/><xf numFmtId="14"/><xf numFmtId="164"/><xf numFmtId="165"/></cellXfs></styleSheet>"#;
+                let sheet = r#"<worksheet><sheetData>
+                    <row r="1"><c r="A1" t="s"><v>0</v></c><c r="B1" t="inlineStr"><is><t>test</t></is></c><c r="C1" t="inlineStr"><is><t>date_administered</t></is></c><c r="E1" t="inlineStr"><is><t>total</t></is></c></row>
+                    <row r="2"/>
+                    <row r="3"><c r="A3"><v>52884</v></c><c r="B3" t="s"><v>1</v></c><c r="C3" s="1"><v>45910</v></c><c r="E3" s="2"><v>18</v></c></row>
+                    <row r="4"><c r="A4"><v>1001</v></c><c r="B4" t="s"><v>1</v></c><c r="C4" s="3"><v>45911.5</v></c><c r="D4" t="b"><v>1</v></c></row>
+                </sheetData></worksheet>"#;
+                let archive = zip_archive(
+                    &[
+                        ("xl/workbook.xml", workbook.as_bytes().to_vec()),
+                        ("xl/_rels/workbook.xml.rels", relationships.as_bytes().to_vec()),
+                        ("xl/sharedStrings.xml", strings.as_bytes().to_vec()),
+                        ("xl/styles.xml", styles.as_bytes().to_vec()),
+                        ("xl/worksheets/sheet1.xml", sheet.as_bytes().to_vec()),
+                        ("xl/worksheets/sheet2.xml", b"<worksheet><sheetData/></worksheet>".to_vec()),
+                    ],
+                    Utc::now(),
+                )
+                .unwrap();
+
+                assert_eq!(
+                    first_sheet_as_csv(&archive).unwrap(),
+                    "student_id,test,date_administered,,total\n52884,\"Reading Check, Fall\",2025-09-10,,18\n1001,\"Reading Check, Fall\",2025-09-11,TRUE\n"
+                );
+                assert!(first_sheet_as_csv(b"student_id,test\n52884,Reading").is_err());
+            }
+        }
+    }
+}
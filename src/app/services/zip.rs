@@ -0,0 +1,250 @@
+// Minimal zip archives for the records export and roster bundles: deflated
+// or stored entries, no zip64, no encryption.
+cfg_if::cfg_if! {
+    if #[cfg(feature = "ssr")] {
+        use chrono::{DateTime, Datelike, Timelike, Utc};
+        use flate2::read::DeflateDecoder;
+        use flate2::write::DeflateEncoder;
+        use flate2::{Compression, Crc};
+        use std::io::{Read as _, Write as _};
+
+        const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+        const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+        const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+        const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+        const CENTRAL_HEADER_SIZE: usize = 46;
+        const LOCAL_HEADER_SIZE: usize = 30;
+        const ZIP_VERSION: u16 = 20;
+        // File names are UTF-8
+        const ZIP_UTF8_FLAG: u16 = 0x0800;
+        const ZIP_STORED: u16 = 0;
+        const ZIP_DEFLATE: u16 = 8;
+        // Uploaded archives inflating past this are refused rather than read
+        pub const MAX_UNZIPPED_SIZE: usize = 256 * 1024 * 1024;
+
+        fn dos_date_time(time: DateTime<Utc>) -> (u16, u16) {
+            // DOS dates start in 1980 and count seconds in twos
+            let year = time.year().clamp(1980, 2107) as u16;
+            let date = ((year - 1980) << 9) | ((time.month() as u16) << 5) | time.day() as u16;
+            let clock = ((time.hour() as u16) << 11) | ((time.minute() as u16) << 5) | (time.second() as u16 / 2);
+            (clock, date)
+        }
+
+        pub fn zip_archive(files: &[(&str, Vec<u8>)], modified: DateTime<Utc>) -> Result<Vec<u8>, String> {
+            let (clock, date) = dos_date_time(modified);
+            let mut archive = Vec::new();
+            let mut central = Vec::new();
+
+            for (name, data) in files {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data).map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+                let compressed = encoder.finish().map_err(|e| format!("Failed to compress {}: {}", name, e))?;
+                let mut crc = Crc::new();
+                crc.update(data);
+
+                let too_large = |_| format!("{} is too large for the archive", name);
+                let offset = u32::try_from(archive.len()).map_err(too_large)?;
+                let compressed_size = u32::try_from(compressed.len()).map_err(too_large)?;
+                let size = u32::try_from(data.len()).map_err(too_large)?;
+                let name_length = u16::try_from(name.len()).map_err(|_| format!("File name {} is too long", name))?;
+
+                let mut fields = Vec::new();
+                for value in [ZIP_VERSION, ZIP_UTF8_FLAG, ZIP_DEFLATE, clock, date] {
+                    fields.extend_from_slice(&value.to_le_bytes());
+                }
+                for value in [crc.sum(), compressed_size, size] {
+                    fields.extend_from_slice(&value.to_le_bytes());
+                }
+                fields.extend_from_slice(&name_length.to_le_bytes());
+                // Extra field length
+                fields.extend_from_slice(&0u16.to_le_bytes());
+
+                archive.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+                archive.extend_from_slice(&fields);
+                archive.extend_from_slice(name.as_bytes());
+                archive.extend_from_slice(&compressed);
+
+                central.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+                // Version made by, then the same fields as the local header
+                central.extend_from_slice(&ZIP_VERSION.to_le_bytes());
+                central.extend_from_slice(&fields);
+                // Comment length, disk number, internal and external attributes
+                central.extend_from_slice(&[0u8; 10]);
+                central.extend_from_slice(&offset.to_le_bytes());
+                central.extend_from_slice(name.as_bytes());
+            }
+
+            let entries = u16::try_from(files.len()).map_err(|_| "Too many files for the archive".to_string())?;
+            let central_offset = u32::try_from(archive.len()).map_err(|_| "Archive is too large".to_string())?;
+            let central_size = u32::try_from(central.len()).map_err(|_| "Archive is too large".to_string())?;
+            archive.extend_from_slice(&central);
+            archive.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            // This disk and the disk holding the central directory
+            archive.extend_from_slice(&[0u8; 4]);
+            archive.extend_from_slice(&entries.to_le_bytes());
+            archive.extend_from_slice(&entries.to_le_bytes());
+            archive.extend_from_slice(&central_size.to_le_bytes());
+            archive.extend_from_slice(&central_offset.to_le_bytes());
+            // Comment length
+            archive.extend_from_slice(&0u16.to_le_bytes());
+            Ok(archive)
+        }
+
+        fn u16_at(bytes: &[u8], at: usize) -> Result<u16, String> {
+            bytes
+                .get(at..at + 2)
+                .map(|b| u16::from_le_bytes([b[0], b[1]]))
+                .ok_or_else(|| "Archive is truncated".to_string())
+        }
+
+        fn u32_at(bytes: &[u8], at: usize) -> Result<u32, String> {
+            bytes
+                .get(at..at + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| "Archive is truncated".to_string())
+        }
+
+        // The end of central directory record sits last, followed only by an
+        // archive comment of up to 64 KiB
+        fn find_end_of_central_directory(archive: &[u8]) -> Result<usize, String> {
+            let last = archive
+                .len()
+                .checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)
+                .ok_or_else(|| "Not a zip archive".to_string())?;
+            let first = last.saturating_sub(u16::MAX as usize);
+            (first..=last)
+                .rev()
+                .find(|&at| u32_at(archive, at) == Ok(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+                .ok_or_else(|| "Not a zip archive".to_string())
+        }
+
+        // Reads every file in an archive written by most zip tools. Folders are
+        // skipped; names keep any folder prefix.
+        pub fn unzip_archive(archive: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+            let end = find_end_of_central_directory(archive)?;
+            let entries = u16_at(archive, end + 10)? as usize;
+            let mut central = u32_at(archive, end + 16)? as usize;
+            let mut files = Vec::with_capacity(entries);
+            let mut total_size = 0usize;
+
+            for _ in 0..entries {
+                if u32_at(archive, central)? != CENTRAL_HEADER_SIGNATURE {
+                    return Err("Archive directory is corrupt".to_string());
+                }
+                let flags = u16_at(archive, central + 8)?;
+                let method = u16_at(archive, central + 10)?;
+                let crc_sum = u32_at(archive, central + 16)?;
+                let compressed_size = u32_at(archive, central + 20)? as usize;
+                let size = u32_at(archive, central + 24)? as usize;
+                let name_length = u16_at(archive, central + 28)? as usize;
+                let extra_length = u16_at(archive, central + 30)? as usize;
+                let comment_length = u16_at(archive, central + 32)? as usize;
+                let local = u32_at(archive, central + 42)? as usize;
+                let name_start = central + CENTRAL_HEADER_SIZE;
+                let name = archive
+                    .get(name_start..name_start + name_length)
+                    .map(|name| String::from_utf8_lossy(name).into_owned())
+                    .ok_or_else(|| "Archive is truncated".to_string())?;
+                central = name_start + name_length + extra_length + comment_length;
+
+                if name.ends_with('/') {
+                    continue;
+                }
+                if flags & 0x0001 != 0 {
+                    return Err(format!("{} is encrypted", name));
+                }
+                total_size += size;
+                if total_size > MAX_UNZIPPED_SIZE {
+                    return Err("Archive is too large to import".to_string());
+                }
+
+                if u32_at(archive, local)? != LOCAL_HEADER_SIGNATURE {
+                    return Err(format!("{} is corrupt", name));
+                }
+                let data_start = local + LOCAL_HEADER_SIZE + u16_at(archive, local + 26)? as usize + u16_at(archive, local + 28)? as usize;
+                let compressed = archive
+                    .get(data_start..data_start + compressed_size)
+                    .ok_or_else(|| format!("{} is truncated", name))?;
+                let data = match method {
+                    ZIP_STORED => compressed.to_vec(),
+                    ZIP_DEFLATE => {
+                        let mut data = Vec::with_capacity(size);
+                        DeflateDecoder::new(compressed)
+                            .take(size as u64 + 1)
+                            .read_to_end(&mut data)
+                            .map_err(|e| format!("Failed to inflate {}: {}", name, e))?;
+                        data
+                    }
+                    other => return Err(format!("{} uses unsupported compression method {}", name, other)),
+                };
+
+                let mut crc = Crc::new();
+                crc.update(&data);
+                if data.len() != size || crc.sum() != crc_sum {
+                    return Err(format!("{} failed its checksum", name));
+                }
+                files.push((name, data));
+            }
+            Ok(files)
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            #[test]
+            fn zip_entries_inflate_to_their_contents() {
+                let files = vec![
+                    ("record.json", b"{\"student_id\": 52884}".repeat(20)),
+                    ("summary.html", "<p>Thi\u{ea}n L\u{ea}</p>".as_bytes().to_vec()),
+                ];
+                let archive = zip_archive(&files, Utc::now()).unwrap();
+
+                let end = archive.len() - END_OF_CENTRAL_DIRECTORY_SIZE;
+                assert_eq!(u32_at(&archive, end), Ok(END_OF_CENTRAL_DIRECTORY_SIGNATURE));
+                assert_eq!(u16_at(&archive, end + 10), Ok(2));
+
+                let mut central = u32_at(&archive, end + 16).unwrap() as usize;
+                for (name, data) in &files {
+                    assert_eq!(u32_at(&archive, central), Ok(CENTRAL_HEADER_SIGNATURE));
+                    let name_length = u16_at(&archive, central + 28).unwrap() as usize;
+                    assert_eq!(&archive[central + 46..central + 46 + name_length], name.as_bytes());
+
+                    let local = u32_at(&archive, central + 42).unwrap() as usize;
+                    assert_eq!(u32_at(&archive, local), Ok(LOCAL_HEADER_SIGNATURE));
+                    let compressed_size = u32_at(&archive, local + 18).unwrap() as usize;
+                    let start = local + 30 + u16_at(&archive, local + 26).unwrap() as usize;
+                    let mut inflated = Vec::new();
+                    DeflateDecoder::new(&archive[start..start + compressed_size])
+                        .read_to_end(&mut inflated)
+                        .unwrap();
+                    assert_eq!(&inflated, data);
+
+                    let mut crc = Crc::new();
+                    crc.update(&inflated);
+                    assert_eq!(u32_at(&archive, local + 14), Ok(crc.sum()));
+                    central += 46 + name_length;
+                }
+            }
+
+            #[test]
+            fn archives_read_back_and_reject_corruption() {
+                let files = vec![
+                    ("oneroster/users.csv", b"sourcedId,givenName\nu1,Thi\xc3\xaan\n".to_vec()),
+                    ("manifest.csv", Vec::new()),
+                ];
+                let mut archive = zip_archive(&files, Utc::now()).unwrap();
+                let read = unzip_archive(&archive).unwrap();
+                assert_eq!(read.len(), 2);
+                assert_eq!(read[0].0, "oneroster/users.csv");
+                assert_eq!(read[0].1, files[0].1);
+                assert!(read[1].1.is_empty());
+
+                // Flip a byte of the first entry's compressed data
+                archive[LOCAL_HEADER_SIZE + files[0].0.len()] ^= 0xff;
+                assert!(unzip_archive(&archive).is_err());
+                assert!(unzip_archive(b"sourcedId,givenName").is_err());
+            }
+        }
+    }
+}
@@ -1,5 +1,9 @@
 pub mod benchmark_utils;
 pub use benchmark_utils::*;
 
+pub mod html;
+
+pub mod mapping_crypto;
+
 //Re-exporting the benchmark_utils module to make its contents available at the top level of the `utils` module.
 pub use benchmark_utils::{BenchmarkStats, BenchmarkUtils};
@@ -0,0 +1,116 @@
+use crate::app::models::student_protection::{KdfParams, SealedMapping, StudentIdMapping};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine as _;
+
+//This file holds the encryption for the student ID mapping. It is compiled for both the
+//server, which seals the mapping when protection is enabled, and the browser, where the
+//dashboard de-anonymizer opens it so the plaintext never travels over the network.
+
+pub const MIN_PASSPHRASE_LENGTH: usize = 12;
+/// A key can be split so that two admins must each supply a passphrase
+pub const MAX_KEY_SHARES: usize = 2;
+pub const KEY_LENGTH: usize = 32;
+pub const NONCE_LENGTH: usize = 12;
+
+pub fn validate_passphrases(passphrases: &[String]) -> Result<(), String> {
+    if passphrases.is_empty() || passphrases.len() > MAX_KEY_SHARES {
+        return Err(format!(
+            "Provide between 1 and {} passphrases",
+            MAX_KEY_SHARES
+        ));
+    }
+    if passphrases
+        .iter()
+        .any(|passphrase| passphrase.chars().count() < MIN_PASSPHRASE_LENGTH)
+    {
+        return Err(format!(
+            "Each passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LENGTH
+        ));
+    }
+    if passphrases.len() == 2 && passphrases[0] == passphrases[1] {
+        return Err("The two passphrases must be different".to_string());
+    }
+    Ok(())
+}
+
+/// Argon2id over every share. Shares are sorted and length-prefixed first, so
+/// the admins can enter them in either order and no two splits collide.
+pub fn derive_key(
+    passphrases: &[String],
+    salt: &[u8],
+    params: &KdfParams,
+) -> Result<[u8; KEY_LENGTH], String> {
+    let mut shares: Vec<&str> = passphrases.iter().map(String::as_str).collect();
+    shares.sort_unstable();
+    let mut input = Vec::new();
+    for share in shares {
+        input.extend_from_slice(&(share.len() as u32).to_be_bytes());
+        input.extend_from_slice(share.as_bytes());
+    }
+
+    let params = Params::new(
+        params.memory_kib,
+        params.iterations,
+        params.parallelism,
+        Some(KEY_LENGTH),
+    )
+    .map_err(|e| format!("Invalid key derivation settings: {}", e))?;
+    let mut key = [0u8; KEY_LENGTH];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(&input, salt, &mut key)
+        .map_err(|e| format!("Failed to derive mapping key: {}", e))?;
+    Ok(key)
+}
+
+pub fn encrypt_mapping(
+    key: &[u8; KEY_LENGTH],
+    nonce: &[u8; NONCE_LENGTH],
+    mapping: &[StudentIdMapping],
+) -> Result<Vec<u8>, String> {
+    let plaintext =
+        serde_json::to_vec(mapping).map_err(|e| format!("Failed to serialize mapping: {}", e))?;
+    Aes256Gcm::new(key.into())
+        .encrypt(Nonce::from_slice(nonce), plaintext.as_slice())
+        .map_err(|_| "Failed to encrypt mapping".to_string())
+}
+
+pub fn open_mapping(
+    sealed: &SealedMapping,
+    passphrases: &[String],
+) -> Result<Vec<StudentIdMapping>, String> {
+    let corrupt = |_| "Stored mapping is corrupt".to_string();
+    let nonce = STANDARD.decode(&sealed.nonce).map_err(corrupt)?;
+    let ciphertext = STANDARD.decode(&sealed.ciphertext).map_err(corrupt)?;
+    if nonce.len() != NONCE_LENGTH {
+        return Err("Stored mapping is corrupt".to_string());
+    }
+    if passphrases.len() != sealed.key_shares as usize {
+        return Err(format!(
+            "This mapping needs {} passphrase(s)",
+            sealed.key_shares
+        ));
+    }
+
+    let key: [u8; KEY_LENGTH] = match &sealed.kdf_salt {
+        Some(salt) => derive_key(
+            passphrases,
+            &STANDARD.decode(salt).map_err(corrupt)?,
+            &sealed.kdf,
+        )?,
+        // Mappings sealed before passphrases: the "passphrase" is the issued key
+        None => URL_SAFE_NO_PAD
+            .decode(passphrases[0].trim())
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or_else(|| "Mapping key is not valid".to_string())?,
+    };
+
+    let plaintext = Aes256Gcm::new(&key.into())
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| "Passphrase does not unlock the stored mapping".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Stored mapping is corrupt: {}", e))
+}
@@ -7,6 +7,7 @@ async fn main() -> std::io::Result<()> {
     use actix_web::{web, App, HttpServer};
     use dahlia::app::db::database;
     use dahlia::app::middleware::authentication::Authentication;
+    use dahlia::app::routes::oidc_routes::configure_oidc_routes;
     use dahlia::app::routes::saml_routes::configure_saml_routes; // Add this import
     use dahlia::app::websockets::lobby::Lobby;
     use dahlia::app::websockets::start_connection::start_connection;
@@ -30,6 +31,11 @@ async fn main() -> std::io::Result<()> {
     println!("Database connection pool created successfully");
     let pool = web::Data::new(pool_one.clone());
 
+    // Purge students past their retention period, when enabled
+    actix_web::rt::spawn(dahlia::app::services::retention::run_retention_schedule(
+        pool_one.clone(),
+    ));
+
     //Initialize the Chat server
     let chat_server = web::Data::new(Lobby::new(pool_one.clone()).start());
 
@@ -58,6 +64,7 @@ async fn main() -> std::io::Result<()> {
             .wrap(Authentication::new())
             // Configure SAML routes BEFORE other routes
             .configure(configure_saml_routes)
+            .configure(configure_oidc_routes)
             .wrap(
                 DefaultHeaders::new()
                     .header("X-Frame-Options", "DENY")
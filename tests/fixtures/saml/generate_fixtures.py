@@ -0,0 +1,123 @@
+#!/usr/bin/env python3
+"""Regenerates the signed SAML response fixtures used by the
+saml_validation tests.
+
+The documents are written in canonical form (sorted attributes, explicit end
+tags) so the exclusive c14n output can be built by hand here without an XML
+security library. idp_key.pem / idp_cert.pem are a throwaway test key pair.
+
+Requires the `cryptography` package:  python3 generate_fixtures.py
+"""
+import base64
+import hashlib
+import os
+
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import padding
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+SAMLP_NS = "urn:oasis:names:tc:SAML:2.0:protocol"
+SAML_NS = "urn:oasis:names:tc:SAML:2.0:assertion"
+DS_NS = "http://www.w3.org/2000/09/xmldsig#"
+
+IDP_ENTITY_ID = "https://idp.example.edu/metadata"
+SP_ENTITY_ID = "https://sp.example.edu/saml/metadata"
+ACS_URL = "https://sp.example.edu/saml/acs"
+REQUEST_ID = "_request-0001"
+
+
+def assertion_body(assertion_id, audience, not_before, not_on_or_after, name_id):
+    # Everything inside <saml:Assertion>, minus the enveloped signature
+    issuer = f"<saml:Issuer>{IDP_ENTITY_ID}</saml:Issuer>"
+    subject = (
+        "<saml:Subject>"
+        f'<saml:NameID Format="urn:oasis:names:tc:SAML:1.1:nameid-format:emailAddress">{name_id}</saml:NameID>'
+        '<saml:SubjectConfirmation Method="urn:oasis:names:tc:SAML:2.0:cm:bearer">'
+        f'<saml:SubjectConfirmationData InResponseTo="{REQUEST_ID}" NotOnOrAfter="{not_on_or_after}" Recipient="{ACS_URL}"></saml:SubjectConfirmationData>'
+        "</saml:SubjectConfirmation>"
+        "</saml:Subject>"
+    )
+    conditions = (
+        f'<saml:Conditions NotBefore="{not_before}" NotOnOrAfter="{not_on_or_after}">'
+        f"<saml:AudienceRestriction><saml:Audience>{audience}</saml:Audience></saml:AudienceRestriction>"
+        "</saml:Conditions>"
+    )
+    authn = (
+        f'<saml:AuthnStatement AuthnInstant="{not_before}" SessionIndex="_session-0001">'
+        "<saml:AuthnContext><saml:AuthnContextClassRef>urn:oasis:names:tc:SAML:2.0:ac:classes:PasswordProtectedTransport</saml:AuthnContextClassRef></saml:AuthnContext>"
+        "</saml:AuthnStatement>"
+    )
+    attributes = (
+        "<saml:AttributeStatement>"
+        '<saml:Attribute Name="mail"><saml:AttributeValue>jdoe@example.edu</saml:AttributeValue></saml:Attribute>'
+        '<saml:Attribute Name="givenName"><saml:AttributeValue>Jane</saml:AttributeValue></saml:Attribute>'
+        '<saml:Attribute Name="sn"><saml:AttributeValue>Doe</saml:AttributeValue></saml:Attribute>'
+        '<saml:Attribute Name="eduPersonAffiliation"><saml:AttributeValue>faculty</saml:AttributeValue></saml:Attribute>'
+        "</saml:AttributeStatement>"
+    )
+    return issuer, subject + conditions + authn + attributes
+
+
+def signed_response(audience, not_before, not_on_or_after, name_id="jdoe@example.edu"):
+    with open(os.path.join(HERE, "idp_key.pem"), "rb") as f:
+        key = serialization.load_pem_private_key(f.read(), password=None)
+
+    assertion_id = "_assertion-0001"
+    assertion_attrs = f'ID="{assertion_id}" IssueInstant="{not_before}" Version="2.0"'
+    issuer, rest = assertion_body(assertion_id, audience, not_before, not_on_or_after, name_id)
+
+    # Exclusive c14n of the assertion: the saml prefix declared on the Response
+    # is visibly used, so it is rendered on the apex element
+    canonical_assertion = f'<saml:Assertion xmlns:saml="{SAML_NS}" {assertion_attrs}>{issuer}{rest}</saml:Assertion>'
+    digest = base64.b64encode(hashlib.sha256(canonical_assertion.encode()).digest()).decode()
+
+    signed_info_inner = (
+        '<ds:CanonicalizationMethod Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"></ds:CanonicalizationMethod>'
+        '<ds:SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#rsa-sha256"></ds:SignatureMethod>'
+        f'<ds:Reference URI="#{assertion_id}">'
+        "<ds:Transforms>"
+        '<ds:Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"></ds:Transform>'
+        '<ds:Transform Algorithm="http://www.w3.org/2001/10/xml-exc-c14n#"></ds:Transform>'
+        "</ds:Transforms>"
+        '<ds:DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"></ds:DigestMethod>'
+        f"<ds:DigestValue>{digest}</ds:DigestValue>"
+        "</ds:Reference>"
+    )
+    canonical_signed_info = f'<ds:SignedInfo xmlns:ds="{DS_NS}">{signed_info_inner}</ds:SignedInfo>'
+    signature_value = base64.b64encode(
+        key.sign(canonical_signed_info.encode(), padding.PKCS1v15(), hashes.SHA256())
+    ).decode()
+
+    signature = (
+        f'<ds:Signature xmlns:ds="{DS_NS}">'
+        f"<ds:SignedInfo>{signed_info_inner}</ds:SignedInfo>"
+        f"<ds:SignatureValue>{signature_value}</ds:SignatureValue>"
+        "</ds:Signature>"
+    )
+    assertion = f"<saml:Assertion {assertion_attrs}>{issuer}{signature}{rest}</saml:Assertion>"
+
+    return (
+        '<?xml version="1.0" encoding="UTF-8"?>\n'
+        f'<samlp:Response xmlns:samlp="{SAMLP_NS}" xmlns:saml="{SAML_NS}" '
+        f'Destination="{ACS_URL}" ID="_response-0001" InResponseTo="{REQUEST_ID}" '
+        f'IssueInstant="{not_before}" Version="2.0">'
+        f"<saml:Issuer>{IDP_ENTITY_ID}</saml:Issuer>"
+        '<samlp:Status><samlp:StatusCode Value="urn:oasis:names:tc:SAML:2.0:status:Success"></samlp:StatusCode></samlp:Status>'
+        f"{assertion}"
+        "</samlp:Response>\n"
+    )
+
+
+def write(name, contents):
+    with open(os.path.join(HERE, name), "w") as f:
+        f.write(contents)
+
+
+if __name__ == "__main__":
+    valid = signed_response(SP_ENTITY_ID, "2025-01-01T12:00:00Z", "2025-01-01T12:05:00Z")
+    write("valid_response.xml", valid)
+    # Signed as jdoe, then edited to claim another identity
+    write("tampered_response.xml", valid.replace(">jdoe@example.edu</saml:NameID>", ">admin@example.edu</saml:NameID>"))
+    write("expired_response.xml", signed_response(SP_ENTITY_ID, "2024-06-01T08:00:00Z", "2024-06-01T08:05:00Z"))
+    write("wrong_audience_response.xml", signed_response("https://other-sp.example.com/metadata", "2025-01-01T12:00:00Z", "2025-01-01T12:05:00Z"))